@@ -18,7 +18,7 @@ macro_rules! token_lit {
 //   This seems to generate better assembly.  Also generate `StaticEncodeSize`
 //   for structs that only contain `StaticEncodeSize` types.
 
-#[proc_macro_derive(Decode, attributes(WithParam, DecodeDebug))]
+#[proc_macro_derive(Decode, attributes(WithParam, DecodeDebug, AllowZeroOffset, Anchor))]
 pub fn parse_decode(input: TokenStream) -> TokenStream {
     let source = input.to_string();
     let ast = syn::parse_derive_input(&source).expect("failed to parse rust syntax");
@@ -26,14 +26,7 @@ pub fn parse_decode(input: TokenStream) -> TokenStream {
     let ret: TokenStream = gen.parse().expect("failed to serialize to rust syntax");
 
     // Check for DecodeDebug attribute
-    let debug = ast.attrs.iter()
-        .filter(|attr|
-            match attr.value {
-                syn::MetaItem::Word(ref ident) => ident == "DecodeDebug",
-                _ => false,
-            })
-        .next()
-        .is_some();
+    let debug = has_word_attr(&ast.attrs, "DecodeDebug");
 
     if debug {
         panic!("{:?}", ret.to_string())
@@ -93,7 +86,7 @@ fn is_param_field(field: &syn::Field) -> bool {
 }
 
 fn decode_params(fields: &[syn::Field]) -> Option<(quote::Tokens, quote::Tokens)> {
-    let mut it = fields
+    let it = fields
         .iter()
         .filter(|field| is_param_field(field))
         .map(|field| {
@@ -160,8 +153,20 @@ fn decode_field(field: &syn::Field) -> quote::Tokens {
         }
     }
 
-    // Gather `WithParam`s, if they exist.
-    let params = field_params(&field.attrs);
+    // Gather `WithParam`s, if they exist.  Offsets without them are
+    // resolved from their anchor.
+    let params = field_params(&field.attrs).or_else(|| offset_anchor(field));
+
+    // Decode an `Option<OffsetN<T>>` marked `#[AllowZeroOffset]` as an
+    // offset which is always present.
+    if has_word_attr(&field.attrs, "AllowZeroOffset") {
+        let offset = option_type(ty).expect("`AllowZeroOffset` requires an `Option` of an offset");
+        let params = params.expect("`AllowZeroOffset` requires an offset");
+        return quote! {
+            let #ident = <#offset>::decode(#params).map(Some)?;
+            let buf = buf.split_at(#ident .encode_size()).1;
+        }
+    }
 
     match params {
         Some(params) => {
@@ -214,6 +219,66 @@ fn discarded_type(ty: &syn::Ty) -> Option<quote::Tokens> {
     }
 }
 
+fn is_offset_type(ty: &syn::Ty) -> bool {
+    let ty = option_type(ty).unwrap_or(ty);
+    match *ty {
+        syn::Ty::Path(None, ref path) => {
+            let ident = &path.segments.last().unwrap().ident;
+            ident == "Offset16" || ident == "Offset32"
+        },
+        _ => false,
+    }
+}
+
+/// The parameters of an `OffsetN<T>` or `Option<OffsetN<T>>` field, which
+/// is relative to the slice named by its `#[Anchor = "..."]` attribute:
+///
+///  * By default, the start of the struct, as for most subtables.
+///  * `"self"`, the position of the offset itself.
+///  * Any other expression, such as a `__table` parameter holding the
+///    start of the enclosing table.
+fn offset_anchor(field: &syn::Field) -> Option<quote::Tokens> {
+    if !is_offset_type(&field.ty) {
+        return None
+    }
+
+    let anchor = field.attrs.iter()
+        .filter_map(|attr| match attr.value {
+            syn::MetaItem::NameValue(ref id, syn::Lit::Str(ref anchor, _)) if id == "Anchor" => Some(anchor.as_str()),
+            _ => None,
+        })
+        .next()
+        .unwrap_or("buffer");
+
+    let mut t = quote::Tokens::new();
+    t.append("buf, ");
+    t.append(if anchor == "self" { "buf" } else { anchor });
+    Some(t)
+}
+
+fn has_word_attr(attrs: &[syn::Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| match attr.value {
+        syn::MetaItem::Word(ref ident) => ident == name,
+        _ => false,
+    })
+}
+
+/// The `T` in `Option<T>`.
+fn option_type(ty: &syn::Ty) -> Option<&syn::Ty> {
+    if let syn::Ty::Path(None, ref path) = *ty {
+        let seg = path.segments.last().unwrap();
+        if seg.ident != "Option" {
+            return None
+        }
+
+        if let syn::PathParameters::AngleBracketed(ref data) = seg.parameters {
+            return data.types.first()
+        }
+    }
+
+    None
+}
+
 fn field_params(attrs: &[syn::Attribute]) -> Option<quote::Tokens> {
     let mut params = attrs
         .iter()
@@ -275,4 +340,46 @@ fn impl_static_size(ast: &syn::DeriveInput) -> quote::Tokens {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Derive `EncodeSize` for an enum whose variants each wrap one value,
+/// such as the versions of a table, whose size is that of the variant it
+/// holds.  Such an enum can then be read from a stream with `DecodeRead`.
+#[proc_macro_derive(EncodeSize)]
+pub fn parse_encode_size(input: TokenStream) -> TokenStream {
+    let source = input.to_string();
+    let ast = syn::parse_derive_input(&source).expect("failed to parse rust syntax");
+    let gen = impl_encode_size(&ast);
+    gen.parse().expect("failed to serialize to rust syntax")
+}
+
+fn impl_encode_size(ast: &syn::DeriveInput) -> quote::Tokens {
+    use syn::{Body, VariantData};
+
+    let variants = match ast.body {
+        Body::Enum(ref variants) => variants,
+        _ => panic!("#[derive(EncodeSize)] is only defined for enums"),
+    };
+
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let ident = &ast.ident;
+    let arms = variants.iter().map(|variant| {
+        match variant.data {
+            VariantData::Tuple(ref fields) if fields.len() == 1 => {},
+            _ => panic!("#[derive(EncodeSize)] requires each variant to wrap one value"),
+        }
+        let name = &variant.ident;
+        quote! { #ident::#name(ref inner) => inner.encode_size() }
+    });
+
+    quote! {
+        impl #impl_generics EncodeSize for #ident #ty_generics #where_clause {
+            #[inline]
+            fn encode_size(&self) -> usize {
+                match *self {
+                    #(#arms),*
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! This module reports which color glyph formats a font contains, so that
+//! text renderers can choose a rendering path without parsing the tables.
+
+use font::Font;
+use primitives::{GlyphId, Tag};
+use table::TaggedTable;
+use table::colr::Colr;
+use table::svg::Svg;
+use table::sbix::Sbix;
+use table::cblc::{Cblc, Eblc, StrikeInfo};
+use decode::Decode1;
+use error::Result;
+
+/// The color glyph technologies present in a font.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ColorFormats {
+    /// Layered glyphs from a version 0 `COLR` table.
+    pub colr_v0: bool,
+    /// Paint graphs from a version 1 `COLR` table.
+    pub colr_v1: bool,
+    pub svg: bool,
+    pub sbix: bool,
+    /// Color bitmaps from `CBDT` and `CBLC`.
+    pub cbdt: bool,
+}
+
+impl ColorFormats {
+    pub fn is_empty(&self) -> bool {
+        *self == ColorFormats::default()
+    }
+}
+
+impl<'fnt> Font<'fnt> {
+    /// Which color glyph formats the font contains.  Only the table
+    /// directory and table headers are read.
+    pub fn color_format(&self) -> Result<ColorFormats> {
+        let mut formats = ColorFormats::default();
+        if let Some(colr) = self.optional_table::<Colr>()? {
+            formats.colr_v0 = !colr.base_glyphs().is_empty();
+            formats.colr_v1 = !colr.base_glyph_paints().is_empty();
+        }
+
+        formats.svg = self.table_record(Svg::tag()).is_some();
+        formats.sbix = self.table_record(Sbix::tag()).is_some();
+        formats.cbdt = self.table_record(Cblc::tag()).is_some()
+            && self.table_record(Tag::new(*b"CBDT")).is_some();
+
+        Ok(formats)
+    }
+
+    /// The bitmap strikes of `CBLC` and then `EBLC`, each with the tag of
+    /// its table, so that a consumer can choose a strike before decoding
+    /// any bitmap data.
+    pub fn bitmap_strikes(&self) -> Result<Vec<(Tag, StrikeInfo)>> {
+        let mut strikes = Vec::new();
+        if let Some(cblc) = self.optional_table::<Cblc>()? {
+            strikes.extend(cblc.strikes()?.into_iter().map(|strike| (Cblc::tag(), strike)));
+        }
+        if let Some(eblc) = self.optional_table::<Eblc>()? {
+            strikes.extend(eblc.strikes()?.into_iter().map(|strike| (Eblc::tag(), strike)));
+        }
+        Ok(strikes)
+    }
+
+    /// Whether `glyph` has a color representation in any of the font's
+    /// color glyph formats.
+    pub fn has_color_glyph(&self, glyph: GlyphId) -> Result<bool> {
+        if let Some(colr) = self.optional_table::<Colr>()? {
+            if colr.base_glyph(glyph).is_some() || colr.base_glyph_paint(glyph).is_some() {
+                return Ok(true)
+            }
+        }
+
+        if let Some(svg) = self.optional_table::<Svg>()? {
+            if svg.document(glyph)?.is_some() {
+                return Ok(true)
+            }
+        }
+
+        if self.table_record(Sbix::tag()).is_some() {
+            let num_glyphs = self.num_glyphs()? as usize;
+            let sbix = Sbix::decode(self.table_data(Sbix::tag())?, num_glyphs)?;
+            for strike in sbix.strikes() {
+                if strike?.has_glyph(glyph)? {
+                    return Ok(true)
+                }
+            }
+        }
+
+        if let Some(cblc) = self.optional_table::<Cblc>()? {
+            if cblc.has_glyph(glyph)? {
+                return Ok(true)
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+    use test_fonts;
+
+    #[test]
+    fn emoji_fonts() {
+        let data = open_file!("data/ss-emoji/ss-emoji-microsoft.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let formats = font.color_format().unwrap();
+        assert_eq!(formats, ColorFormats { colr_v0: true, ..ColorFormats::default() });
+
+        let colr = font.table::<Colr>().unwrap();
+        let base = colr.base_glyphs().get(0).unwrap();
+        assert_eq!(font.has_color_glyph(base.glyph_id), Ok(true));
+        assert_eq!(font.has_color_glyph(GlyphId(1)), Ok(false));
+
+        let data = open_file!("data/ss-emoji/ss-emoji-apple.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        assert_eq!(font.color_format().unwrap(), ColorFormats { sbix: true, ..ColorFormats::default() });
+
+        let num_glyphs = font.num_glyphs().unwrap();
+        let colored = (0..num_glyphs).filter(|&gid| font.has_color_glyph(GlyphId(gid)).unwrap()).count();
+        assert!(colored > 0 && colored < num_glyphs as usize);
+
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        assert!(font.color_format().unwrap().is_empty());
+        assert_eq!(font.bitmap_strikes(), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn bitmap_strikes() {
+        let data = test_fonts::minimal_font(false)
+            .table(b"CBLC", test_fonts::bitmap_location(3, &[(109, 32, &[(1, 2)])]))
+            .table(b"EBLC", test_fonts::bitmap_location(2, &[(12, 1, &[(1, 1)]), (16, 8, &[(1, 2)])]))
+            .build();
+        let font = Font::decode(&data).unwrap();
+        let strikes = font.bitmap_strikes().unwrap().into_iter()
+            .map(|(tag, strike)| (tag, strike.ppem_y, strike.bit_depth, strike.glyph_count()))
+            .collect::<Vec<_>>();
+        assert_eq!(strikes, vec![
+            (Cblc::tag(), 109, 32, 2),
+            (Eblc::tag(), 12, 1, 1),
+            (Eblc::tag(), 16, 8, 2),
+        ]);
+    }
+}
@@ -0,0 +1,627 @@
+//! This module renders `COLR` color glyphs to RGBA bitmaps in the colors
+//! of a `CPAL` palette.
+//!
+//! Each glyph a color glyph is built from is rendered by the outline
+//! rasterizer and used as a mask.  Version 0 glyphs are layers of solid
+//! colors; version 1 paint graphs add gradients, transforms and blending,
+//! which are evaluated at the centre of each pixel.  Colors are in sRGB
+//! and are composited without gamma correction, with premultiplied alpha.
+
+use std::cell::Cell;
+use std::ops::Range;
+
+use font::Font;
+use outline::{BoundsSink, Transform, TransformSink};
+use primitives::GlyphId;
+use raster;
+use table::colr::{BaseGlyphRecord, ColorLine, Colr, CompositeMode, Extend, Paint};
+use table::cpal::{Color, Cpal};
+use table::head::Head;
+use error::{Error, Result};
+
+/// The deepest a paint graph is followed.  Graphs which refer back to
+/// themselves through `ColrGlyph` or `Layers` are deeper than any other.
+const MAX_DEPTH: usize = 32;
+
+/// The most paints drawn for one glyph, counting a paint each time it is
+/// reached.  Paints may share children, so a shallow graph can still
+/// reach a child an exponential number of times.
+const MAX_PAINTS: usize = 2048;
+
+/// The most pixels a color bitmap may have.  Each pixel takes sixteen
+/// bytes while it is painted, and a graph holds a few canvases at each
+/// level, so this is smaller than the limit of coverage bitmaps.
+pub const MAX_COLOR_PIXELS: usize = 1 << 20;
+
+/// The palette index of the text color, which is drawn black.
+const FOREGROUND: u16 = 0xFFFF;
+
+/// A color bitmap, in rows from top to bottom, of pixels in RGBA order
+/// with premultiplied alpha.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RgbaBitmap {
+    pub width: usize,
+    pub height: usize,
+    /// The offset of the left edge from the pen position, in pixels.
+    pub left: i32,
+    /// The offset of the top edge above the baseline, in pixels.
+    pub top: i32,
+    pub data: Vec<u8>,
+}
+
+impl RgbaBitmap {
+    /// The pixel in `column` of `row`, counted from the top left.
+    pub fn get(&self, column: usize, row: usize) -> Option<[u8; 4]> {
+        if column >= self.width {
+            return None
+        }
+        let pixel = self.data.get((row * self.width + column) * 4..)?.get(..4)?;
+        Some([pixel[0], pixel[1], pixel[2], pixel[3]])
+    }
+}
+
+/// A premultiplied color, with components from 0 to 1.
+type Pixel = [f32; 4];
+
+const CLEAR: Pixel = [0.0; 4];
+
+impl<'fnt> Font<'fnt> {
+    /// Render the color glyph `glyph` at `ppem` pixels per em in the
+    /// colors of `palette`, or return `None` if the font has no `COLR`
+    /// glyph for it.  A version 1 paint graph is preferred to version 0
+    /// layers.
+    pub fn render_color_glyph(&self, glyph: GlyphId, palette: u16, ppem: f32) -> Result<Option<RgbaBitmap>> {
+        let colr = match self.optional_table::<Colr>()? {
+            Some(colr) => colr,
+            None => return Ok(None),
+        };
+        let root = match (colr.paint(glyph)?, colr.base_glyph(glyph)) {
+            (Some(paint), _) => Root::Paint(paint),
+            (None, Some(record)) => Root::Layers(record),
+            (None, None) => return Ok(None),
+        };
+
+        let units_per_em = self.table::<Head>()?.units_per_em;
+        if units_per_em == 0 {
+            return Err(Error::InvalidData)
+        }
+        let scale = ppem / f32::from(units_per_em);
+
+        let mut painter = Painter {
+            font: self,
+            colr,
+            cpal: self.optional_table::<Cpal>()?,
+            palette,
+            width: 0,
+            height: 0,
+            budget: Cell::new(MAX_PAINTS),
+        };
+
+        let mut bounds = BoundsSink::new();
+        match root {
+            Root::Paint(ref paint) => painter.bounds(paint, Transform::IDENTITY, 0, &mut bounds)?,
+            Root::Layers(record) => for layer in painter.layers(record) {
+                self.outline_glyph(layer?.glyph_id, &mut bounds)?;
+            },
+        }
+        let bounds = match bounds.bounds() {
+            Some(bounds) => bounds,
+            None => return Ok(Some(RgbaBitmap::default())),
+        };
+
+        let left = (bounds.x_min * scale).floor() as i32;
+        let right = (bounds.x_max * scale).ceil() as i32;
+        let top = (bounds.y_max * scale).ceil() as i32;
+        let bottom = (bounds.y_min * scale).floor() as i32;
+        let (width, height) = raster::bitmap_size(left, right, bottom, top)?;
+        if width * height > MAX_COLOR_PIXELS {
+            return Err(Error::InvalidData)
+        }
+        painter.width = width;
+        painter.height = height;
+        let device = Transform::scale(scale, -scale).then(&Transform::translate(-left as f32, top as f32));
+
+        let pixels = match root {
+            Root::Paint(ref paint) => painter.paint(paint, device, 0)?,
+            Root::Layers(record) => painter.paint_layers(record, device)?,
+        };
+        let data = pixels.iter()
+            .flat_map(|pixel| pixel.iter().map(|&component| (component.clamp(0.0, 1.0) * 255.0).round() as u8))
+            .collect();
+
+        Ok(Some(RgbaBitmap { width: painter.width, height: painter.height, left, top, data }))
+    }
+}
+
+enum Root<'fnt> {
+    Paint(Paint<'fnt>),
+    Layers(BaseGlyphRecord),
+}
+
+/// A layer of a version 0 color glyph.
+struct Layer {
+    glyph_id: GlyphId,
+    palette_index: u16,
+}
+
+/// Paints the glyphs of a color glyph on a grid of pixels.
+struct Painter<'a, 'fnt: 'a> {
+    font: &'a Font<'fnt>,
+    colr: Colr<'fnt>,
+    cpal: Option<Cpal<'fnt>>,
+    palette: u16,
+    width: usize,
+    height: usize,
+    /// The paints which may still be drawn, of `MAX_PAINTS`.
+    budget: Cell<usize>,
+}
+
+impl<'a, 'fnt> Painter<'a, 'fnt> {
+    /// Count a paint at `depth` against the limits, failing with
+    /// `Error::InvalidData` past either.
+    fn visit(&self, depth: usize) -> Result<()> {
+        let budget = self.budget.get();
+        if depth > MAX_DEPTH || budget == 0 {
+            return Err(Error::InvalidData)
+        }
+        self.budget.set(budget - 1);
+        Ok(())
+    }
+
+    fn layers(&self, record: BaseGlyphRecord) -> impl Iterator<Item = Result<Layer>> + 'fnt {
+        let layers = self.colr.layers();
+        let first = record.first_layer_index as usize;
+        (first..first + record.num_layers as usize).map(move |index| {
+            let layer = layers.get(index).ok_or(Error::InvalidData)?;
+            Ok(Layer { glyph_id: layer.glyph_id, palette_index: layer.palette_index })
+        })
+    }
+
+    /// Add the outlines `paint` is clipped to, mapped by `transform`, to
+    /// `bounds`.  Fills are unbounded, so the clips are the extent of the
+    /// glyph.
+    fn bounds(&self, paint: &Paint<'fnt>, transform: Transform, depth: usize, bounds: &mut BoundsSink) -> Result<()> {
+        self.visit(depth)?;
+
+        match *paint {
+            Paint::Layers { first_layer, num_layers } => {
+                for index in layer_indices(first_layer, num_layers)? {
+                    self.bounds(&self.colr.layer_paint(index)?, transform, depth + 1, bounds)?;
+                }
+            },
+            Paint::Glyph { glyph, .. } => {
+                self.font.outline_glyph(glyph, &mut TransformSink::new(bounds, transform))?;
+            },
+            Paint::ColrGlyph { glyph } => {
+                if let Some(paint) = self.colr.paint(glyph)? {
+                    self.bounds(&paint, transform, depth + 1, bounds)?;
+                }
+            },
+            Paint::Transform { transform: inner, paint } => {
+                self.bounds(&paint.get()?, inner.then(&transform), depth + 1, bounds)?;
+            },
+            Paint::Composite { source, backdrop, .. } => {
+                self.bounds(&source.get()?, transform, depth + 1, bounds)?;
+                self.bounds(&backdrop.get()?, transform, depth + 1, bounds)?;
+            },
+            Paint::Solid { .. } | Paint::LinearGradient { .. } | Paint::RadialGradient { .. }
+                | Paint::SweepGradient { .. } => {},
+        }
+        Ok(())
+    }
+
+    fn paint_layers(&self, record: BaseGlyphRecord, device: Transform) -> Result<Vec<Pixel>> {
+        let mut canvas = vec![CLEAR; self.width * self.height];
+        for layer in self.layers(record) {
+            let layer = layer?;
+            let color = self.color(layer.palette_index, 1.0)?;
+            let mask = raster::rasterize_transformed(self.font, layer.glyph_id, device, self.width, self.height)?;
+            for (pixel, &coverage) in canvas.iter_mut().zip(&mask) {
+                *pixel = composite(CompositeMode::SourceOver, scaled(color, f32::from(coverage) / 255.0), *pixel);
+            }
+        }
+        Ok(canvas)
+    }
+
+    /// The pixels of `paint`, whose coordinates `transform` maps to the
+    /// pixel grid.
+    fn paint(&self, paint: &Paint<'fnt>, transform: Transform, depth: usize) -> Result<Vec<Pixel>> {
+        self.visit(depth)?;
+
+        let size = self.width * self.height;
+        match *paint {
+            Paint::Layers { first_layer, num_layers } => {
+                let mut canvas = vec![CLEAR; size];
+                for index in layer_indices(first_layer, num_layers)? {
+                    let layer = self.paint(&self.colr.layer_paint(index)?, transform, depth + 1)?;
+                    for (pixel, &source) in canvas.iter_mut().zip(&layer) {
+                        *pixel = composite(CompositeMode::SourceOver, source, *pixel);
+                    }
+                }
+                Ok(canvas)
+            },
+            Paint::Solid { palette_index, alpha } => Ok(vec![self.color(palette_index, alpha)?; size]),
+            Paint::LinearGradient { ref color_line, p0, p1, p2 } => {
+                // Color lines run perpendicular to `p0` to `p2`, so the
+                // gradient runs from `p0` along `p1` projected onto the
+                // normal of that line.
+                let normal = (p0.1 - p2.1, p2.0 - p0.0);
+                let length = dot(normal, normal);
+                let end = if length == 0.0 {
+                    p1
+                } else {
+                    let along = dot((p1.0 - p0.0, p1.1 - p0.1), normal) / length;
+                    (p0.0 + normal.0 * along, p0.1 + normal.1 * along)
+                };
+                let direction = (end.0 - p0.0, end.1 - p0.1);
+                let length = dot(direction, direction);
+                self.gradient(color_line, transform, |point| {
+                    if length == 0.0 {
+                        return None
+                    }
+                    Some(dot((point.0 - p0.0, point.1 - p0.1), direction) / length)
+                })
+            },
+            Paint::RadialGradient { ref color_line, c0, r0, c1, r1 } => {
+                self.gradient(color_line, transform, |point| radial_position(point, c0, r0, c1, r1))
+            },
+            Paint::SweepGradient { ref color_line, center, start_angle, end_angle } => {
+                self.gradient(color_line, transform, |point| {
+                    if start_angle == end_angle {
+                        return None
+                    }
+                    let angle = (point.1 - center.1).atan2(point.0 - center.0).to_degrees();
+                    let angle = if angle < 0.0 { angle + 360.0 } else { angle };
+                    Some((angle - start_angle) / (end_angle - start_angle))
+                })
+            },
+            Paint::Glyph { glyph, paint } => {
+                let mask = raster::rasterize_transformed(self.font, glyph, transform, self.width, self.height)?;
+                let mut pixels = self.paint(&paint.get()?, transform, depth + 1)?;
+                for (pixel, &coverage) in pixels.iter_mut().zip(&mask) {
+                    *pixel = scaled(*pixel, f32::from(coverage) / 255.0);
+                }
+                Ok(pixels)
+            },
+            Paint::ColrGlyph { glyph } => match self.colr.paint(glyph)? {
+                Some(paint) => self.paint(&paint, transform, depth + 1),
+                None => Ok(vec![CLEAR; size]),
+            },
+            Paint::Transform { transform: inner, paint } => self.paint(&paint.get()?, inner.then(&transform), depth + 1),
+            Paint::Composite { source, mode, backdrop } => {
+                let mut pixels = self.paint(&backdrop.get()?, transform, depth + 1)?;
+                let source = self.paint(&source.get()?, transform, depth + 1)?;
+                for (pixel, &source) in pixels.iter_mut().zip(&source) {
+                    *pixel = composite(mode, source, *pixel);
+                }
+                Ok(pixels)
+            },
+        }
+    }
+
+    /// The pixels of a gradient, where `position` gives the position along
+    /// `line` of a point in paint coordinates, or `None` for a point the
+    /// gradient does not cover.
+    fn gradient<F>(&self, line: &ColorLine, transform: Transform, position: F) -> Result<Vec<Pixel>>
+    where
+        F: Fn((f32, f32)) -> Option<f32>,
+    {
+        let mut stops = line.stops.iter()
+            .map(|stop| Ok((stop.offset, self.color(stop.palette_index, stop.alpha)?)))
+            .collect::<Result<Vec<_>>>()?;
+        stops.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(::std::cmp::Ordering::Equal));
+
+        let inverse = match transform.invert() {
+            Some(inverse) if !stops.is_empty() => inverse,
+            _ => return Ok(vec![CLEAR; self.width * self.height]),
+        };
+        let mut pixels = Vec::with_capacity(self.width * self.height);
+        for row in 0..self.height {
+            for column in 0..self.width {
+                let point = inverse.apply(column as f32 + 0.5, row as f32 + 0.5);
+                pixels.push(position(point).map_or(CLEAR, |offset| color_at(&stops, line.extend, offset)));
+            }
+        }
+        Ok(pixels)
+    }
+
+    /// The premultiplied color of `palette_index` in the palette, with its
+    /// alpha scaled by `alpha`.
+    fn color(&self, palette_index: u16, alpha: f32) -> Result<Pixel> {
+        let color = if palette_index == FOREGROUND {
+            Color::BLACK
+        } else {
+            self.cpal.ok_or(Error::MissingTable)?.color(self.palette, palette_index).ok_or(Error::InvalidData)?
+        };
+        let alpha = f32::from(color.alpha) / 255.0 * alpha.clamp(0.0, 1.0);
+        let component = |value: u8| f32::from(value) / 255.0 * alpha;
+        Ok([component(color.red), component(color.green), component(color.blue), alpha])
+    }
+}
+
+/// The indices in the layer list of `num_layers` layers from `first_layer`.
+fn layer_indices(first_layer: u32, num_layers: u8) -> Result<Range<u32>> {
+    let end = first_layer.checked_add(u32::from(num_layers)).ok_or(Error::InvalidData)?;
+    Ok(first_layer..end)
+}
+
+fn dot(a: (f32, f32), b: (f32, f32)) -> f32 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+fn scaled(pixel: Pixel, factor: f32) -> Pixel {
+    [pixel[0] * factor, pixel[1] * factor, pixel[2] * factor, pixel[3] * factor]
+}
+
+/// The position along a two point conical gradient of `point`: the
+/// greatest `t` for which the circle interpolated between the two
+/// circles passes through the point with a radius which is not negative.
+fn radial_position(point: (f32, f32), c0: (f32, f32), r0: f32, c1: (f32, f32), r1: f32) -> Option<f32> {
+    let (cd, pd, dr) = ((c1.0 - c0.0, c1.1 - c0.1), (point.0 - c0.0, point.1 - c0.1), r1 - r0);
+    let a = dot(cd, cd) - dr * dr;
+    let b = dot(pd, cd) + r0 * dr;
+    let c = dot(pd, pd) - r0 * r0;
+    let radius = |t: f32| r0 + t * dr >= 0.0;
+
+    if a.abs() < 1e-6 {
+        let t = c / (2.0 * b);
+        return if b != 0.0 && radius(t) { Some(t) } else { None }
+    }
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None
+    }
+    let (t1, t2) = ((b + discriminant.sqrt()) / a, (b - discriminant.sqrt()) / a);
+    let (high, low) = if t1 > t2 { (t1, t2) } else { (t2, t1) };
+    if radius(high) {
+        Some(high)
+    } else if radius(low) {
+        Some(low)
+    } else {
+        None
+    }
+}
+
+/// The color at `offset` along sorted `stops`.
+fn color_at(stops: &[(f32, Pixel)], extend: Extend, offset: f32) -> Pixel {
+    let (first, last) = (stops[0], stops[stops.len() - 1]);
+    let span = last.0 - first.0;
+    let offset = match extend {
+        Extend::Repeat if span > 0.0 => {
+            let t = (offset - first.0) / span;
+            first.0 + (t - t.floor()) * span
+        },
+        Extend::Reflect if span > 0.0 => {
+            let t = (offset - first.0) / span;
+            let t = t - 2.0 * (t / 2.0).floor();
+            first.0 + if t > 1.0 { 2.0 - t } else { t } * span
+        },
+        _ => offset,
+    };
+
+    if offset <= first.0 {
+        return first.1
+    }
+    for pair in stops.windows(2) {
+        let ((start, from), (end, to)) = (pair[0], pair[1]);
+        if offset < end {
+            let t = (offset - start) / (end - start);
+            return [0, 1, 2, 3].map(|index| from[index] + (to[index] - from[index]) * t)
+        }
+    }
+    last.1
+}
+
+/// `source` composited onto `backdrop` in `mode`.
+fn composite(mode: CompositeMode, source: Pixel, backdrop: Pixel) -> Pixel {
+    use table::colr::CompositeMode::*;
+
+    let (source_alpha, backdrop_alpha) = (source[3], backdrop[3]);
+    // The Porter-Duff operators weigh the source and the backdrop.
+    let weights = match mode {
+        Clear => Some((0.0, 0.0)),
+        Source => Some((1.0, 0.0)),
+        Destination => Some((0.0, 1.0)),
+        SourceOver => Some((1.0, 1.0 - source_alpha)),
+        DestinationOver => Some((1.0 - backdrop_alpha, 1.0)),
+        SourceIn => Some((backdrop_alpha, 0.0)),
+        DestinationIn => Some((0.0, source_alpha)),
+        SourceOut => Some((1.0 - backdrop_alpha, 0.0)),
+        DestinationOut => Some((0.0, 1.0 - source_alpha)),
+        SourceAtop => Some((backdrop_alpha, 1.0 - source_alpha)),
+        DestinationAtop => Some((1.0 - backdrop_alpha, source_alpha)),
+        Xor => Some((1.0 - backdrop_alpha, 1.0 - source_alpha)),
+        Plus => Some((1.0, 1.0)),
+        _ => None,
+    };
+    if let Some((source_weight, backdrop_weight)) = weights {
+        return [0, 1, 2, 3].map(|index| (source[index] * source_weight + backdrop[index] * backdrop_weight).min(1.0))
+    }
+
+    // The blend modes mix the colors where both are drawn, and draw each
+    // where the other is not.
+    let unpremultiply = |pixel: Pixel| {
+        if pixel[3] == 0.0 { [0.0; 3] } else { [pixel[0] / pixel[3], pixel[1] / pixel[3], pixel[2] / pixel[3]] }
+    };
+    let blended = blend(mode, unpremultiply(source), unpremultiply(backdrop));
+    let both = source_alpha * backdrop_alpha;
+    let color = [0, 1, 2].map(|index| {
+        source[index] * (1.0 - backdrop_alpha) + backdrop[index] * (1.0 - source_alpha) + both * blended[index]
+    });
+    [color[0], color[1], color[2], source_alpha + backdrop_alpha - both]
+}
+
+/// The color of the blend `mode` of `source` over `backdrop`, which are
+/// not premultiplied.
+fn blend(mode: CompositeMode, source: [f32; 3], backdrop: [f32; 3]) -> [f32; 3] {
+    use table::colr::CompositeMode::*;
+
+    fn hard_light(source: f32, backdrop: f32) -> f32 {
+        if source <= 0.5 {
+            backdrop * 2.0 * source
+        } else {
+            let source = 2.0 * source - 1.0;
+            backdrop + source - backdrop * source
+        }
+    }
+
+    let separable = |function: fn(f32, f32) -> f32| [0, 1, 2].map(|index| function(source[index], backdrop[index]));
+    match mode {
+        Screen => separable(|source, backdrop| backdrop + source - backdrop * source),
+        Overlay => separable(|source, backdrop| hard_light(backdrop, source)),
+        Darken => separable(f32::min),
+        Lighten => separable(f32::max),
+        ColorDodge => separable(|source, backdrop| {
+            if backdrop == 0.0 { 0.0 } else if source >= 1.0 { 1.0 } else { (backdrop / (1.0 - source)).min(1.0) }
+        }),
+        ColorBurn => separable(|source, backdrop| {
+            if backdrop >= 1.0 { 1.0 } else if source <= 0.0 { 0.0 } else { 1.0 - ((1.0 - backdrop) / source).min(1.0) }
+        }),
+        HardLight => separable(hard_light),
+        SoftLight => separable(|source, backdrop| {
+            if source <= 0.5 {
+                backdrop - (1.0 - 2.0 * source) * backdrop * (1.0 - backdrop)
+            } else {
+                let d = if backdrop <= 0.25 { ((16.0 * backdrop - 12.0) * backdrop + 4.0) * backdrop } else { backdrop.sqrt() };
+                backdrop + (2.0 * source - 1.0) * (d - backdrop)
+            }
+        }),
+        Difference => separable(|source, backdrop| (backdrop - source).abs()),
+        Exclusion => separable(|source, backdrop| backdrop + source - 2.0 * backdrop * source),
+        Hue => set_luminosity(set_saturation(source, saturation(backdrop)), luminosity(backdrop)),
+        Saturation => set_luminosity(set_saturation(backdrop, saturation(source)), luminosity(backdrop)),
+        Color => set_luminosity(source, luminosity(backdrop)),
+        Luminosity => set_luminosity(backdrop, luminosity(source)),
+        // The Porter-Duff operators are handled by `composite`.
+        _ => separable(|source, backdrop| backdrop * source),
+    }
+}
+
+fn luminosity(color: [f32; 3]) -> f32 {
+    0.3 * color[0] + 0.59 * color[1] + 0.11 * color[2]
+}
+
+fn saturation(color: [f32; 3]) -> f32 {
+    color[0].max(color[1]).max(color[2]) - color[0].min(color[1]).min(color[2])
+}
+
+fn set_luminosity(color: [f32; 3], target: f32) -> [f32; 3] {
+    let difference = target - luminosity(color);
+    let color = color.map(|component| component + difference);
+
+    // Bring the components back within range, keeping the luminosity.
+    let (lum, min, max) = (luminosity(color), color[0].min(color[1]).min(color[2]), color[0].max(color[1]).max(color[2]));
+    color.map(|component| {
+        let mut component = component;
+        if min < 0.0 {
+            component = lum + (component - lum) * lum / (lum - min);
+        }
+        if max > 1.0 {
+            component = lum + (component - lum) * (1.0 - lum) / (max - lum);
+        }
+        component
+    })
+}
+
+fn set_saturation(color: [f32; 3], target: f32) -> [f32; 3] {
+    let (min, max) = (color[0].min(color[1]).min(color[2]), color[0].max(color[1]).max(color[2]));
+    if max <= min {
+        return [0.0; 3]
+    }
+    color.map(|component| (component - min) * target / (max - min))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+    use test_fonts;
+
+    #[test]
+    fn render_layers() {
+        let data = test_fonts::minimal_font(false)
+            .table(b"COLR", test_fonts::colr_v0(&[(2, &[(1, 0), (2, 1)])]))
+            .table(b"CPAL", test_fonts::cpal(&[&[[255, 0, 0, 255], [0, 0, 255, 128]], &[[0, 255, 0, 255], [0, 0, 0, 255]]]))
+            .build();
+        let font = Font::decode(&data).unwrap();
+        assert_eq!(font.render_color_glyph(GlyphId(1), 0, 10.0), Ok(None));
+
+        // The square is red, with the triangle half blue over it; the
+        // tip of the triangle rises above the square.
+        let bitmap = font.render_color_glyph(GlyphId(2), 0, 10.0).unwrap().unwrap();
+        assert_eq!((bitmap.width, bitmap.height, bitmap.left, bitmap.top), (6, 7, 0, 7));
+        assert_eq!(bitmap.get(1, 6), Some([127, 0, 128, 255]));
+        assert_eq!(bitmap.get(5, 2), Some([255, 0, 0, 255]));
+        assert_eq!(bitmap.get(0, 0), Some([0, 0, 0, 0]));
+        assert_eq!(bitmap.get(3, 1).map(|pixel| (pixel[0], pixel[2] > 0)), Some((0, true)));
+
+        let bitmap = font.render_color_glyph(GlyphId(2), 1, 10.0).unwrap().unwrap();
+        assert_eq!(bitmap.get(5, 2), Some([0, 255, 0, 255]));
+        assert_eq!(bitmap.get(3, 6), Some([0, 0, 0, 255]));
+        assert_eq!(font.render_color_glyph(GlyphId(2), 2, 10.0), Err(Error::InvalidData));
+
+        let data = test_fonts::minimal_font(false).table(b"COLR", test_fonts::colr_v0(&[(2, &[(1, 0)])])).build();
+        assert_eq!(Font::decode(&data).unwrap().render_color_glyph(GlyphId(2), 0, 10.0), Err(Error::MissingTable));
+    }
+
+    #[test]
+    fn invalid_paint_graphs() {
+        // Layers which run past the last index of the layer list.
+        let data = test_fonts::minimal_font(false)
+            .table(b"COLR", test_fonts::colr_v1(&[(1, test_fonts::paint_layers(0xFFFF_FFFF, 2))], &[]))
+            .build();
+        let font = Font::decode(&data).unwrap();
+        assert_eq!(font.render_color_glyph(GlyphId(1), 0, 10.0), Err(Error::InvalidData));
+
+        // Each layer paints the two after it, so the last layers are
+        // reached more often than the budget allows, though the graph is
+        // shallow.
+        let mut layers = (0..24).map(|index| test_fonts::paint_layers(index + 1, 2)).collect::<Vec<_>>();
+        layers.push(test_fonts::paint_glyph(1, test_fonts::paint_solid(0xFFFF, 1.0)));
+        layers.push(test_fonts::paint_glyph(2, test_fonts::paint_solid(0xFFFF, 1.0)));
+        let data = test_fonts::minimal_font(false)
+            .table(b"COLR", test_fonts::colr_v1(&[(1, test_fonts::paint_layers(0, 1)), (2, test_fonts::paint_layers(23, 1))], &layers))
+            .build();
+        let font = Font::decode(&data).unwrap();
+        assert_eq!(font.render_color_glyph(GlyphId(1), 0, 10.0), Err(Error::InvalidData));
+        assert!(font.render_color_glyph(GlyphId(2), 0, 10.0).is_ok());
+
+        // Canvases too large to allocate are rejected.
+        assert_eq!(font.render_color_glyph(GlyphId(2), 0, 1.0e5), Err(Error::InvalidData));
+    }
+
+    #[test]
+    fn render_paint_graph() {
+        // A gradient from red on the left of the square to blue on its
+        // right, under the text color clipped to the triangle.
+        let gradient = test_fonts::paint_linear_gradient([(100, 0), (600, 0), (100, 100)], &[(0.0, 0), (1.0, 1)]);
+        let data = test_fonts::minimal_font(false)
+            .table(b"COLR", test_fonts::colr_v1(
+                &[(1, test_fonts::paint_layers(0, 2)), (2, test_fonts::paint_layers(2, 1))],
+                &[
+                    test_fonts::paint_glyph(1, gradient),
+                    test_fonts::paint_glyph(2, test_fonts::paint_solid(0xFFFF, 0.5)),
+                    test_fonts::paint_glyph(2, test_fonts::paint_layers(0, 1)),
+                ],
+            ))
+            .table(b"CPAL", test_fonts::cpal(&[&[[255, 0, 0, 255], [0, 0, 255, 255]]]))
+            .build();
+        let font = Font::decode(&data).unwrap();
+
+        let bitmap = font.render_color_glyph(GlyphId(1), 0, 10.0).unwrap().unwrap();
+        assert_eq!((bitmap.width, bitmap.height, bitmap.left, bitmap.top), (6, 7, 0, 7));
+        // Pixel centres in the last column lie nine tenths of the way
+        // across the square, and in the middle column half way, where the
+        // triangle darkens them.
+        assert_eq!(bitmap.get(5, 2), Some([26, 0, 230, 255]));
+        assert_eq!(bitmap.get(3, 4), Some([64, 0, 64, 255]));
+        assert_eq!(bitmap.get(0, 0), Some([0, 0, 0, 0]));
+
+        // Only the part of the gradient inside both the square and the
+        // triangle is drawn.
+        let bitmap = font.render_color_glyph(GlyphId(2), 0, 10.0).unwrap().unwrap();
+        assert_eq!((bitmap.width, bitmap.height), (6, 7));
+        assert_eq!(bitmap.get(3, 4), Some([128, 0, 128, 255]));
+        assert_eq!(bitmap.get(3, 1), Some([0, 0, 0, 0]));
+    }
+}
@@ -2,8 +2,17 @@
 //! This includes `primitive` types and `table` types.
 //! This module also provides a wrapper around the `byteorder` create,
 //! since every datatype found in fonts are `BigEndian`.
+//!
+//! Only calling `decode` on the types of this crate is part of its stable
+//! interface.  The size and reader traits are the machinery of the
+//! derived decoders, and are hidden from the documentation; the readers
+//! are sealed, so that they can change without breaking other crates.
 
-use error::Result;
+#![forbid(unsafe_code)]
+
+use std::convert::TryInto;
+
+use error::{Error, Result};
 
 /// Types whose sizes are statically known should implement this trait.
 /// It's important to note that `size` refers to the encoding size in
@@ -11,12 +20,13 @@ use error::Result;
 /// For instance, a type `U24(u32)` to represent a 24-bit unsized integer
 /// has a stack size of 4-bytes, but should implement a
 /// `StaticSize::size() == 4`.
-
+#[doc(hidden)]
 pub trait EncodeSize {
     /// The size in bytes of the _decoded_ type.
     fn encode_size(&self) -> usize;
 }
 
+#[doc(hidden)]
 pub trait StaticEncodeSize {
     fn size() -> usize;
 }
@@ -29,19 +39,28 @@ impl<T: StaticEncodeSize> EncodeSize for T {
 
 /// The `Decode` trait provides the logic for taking a slice of bytes
 /// in a font file and decoding them into a rust type.
-
 pub trait Decode<'fnt>: Sized {
-    fn decode(&'fnt [u8]) -> Result<Self>;
+    fn decode(buffer: &'fnt [u8]) -> Result<Self>;
 }
 
 /// The `DecodeRead` trait provides a `Read`-like interface
 /// to decoding a type.  This trait is automatically implemented
 /// for types that implement `Decode` and `StaticSize` automatically.
-
-pub trait DecodeRead<'fnt>: Sized {
+#[doc(hidden)]
+pub trait DecodeRead<'fnt>: Sized + sealed::Sealed {
     fn decode_read<T: Decode<'fnt> + EncodeSize>(&mut self) -> Result<T>;
 }
 
+pub(crate) mod sealed {
+    /// Implemented only by the types of this crate, so that no other crate
+    /// can implement the traits which require it.
+    pub trait Sealed {}
+
+    impl Sealed for &[u8] {}
+    impl Sealed for u16 {}
+    impl Sealed for u32 {}
+}
+
 impl<'b: 'fnt, 'fnt> DecodeRead<'fnt> for &'b [u8] {
     #[inline]
     fn decode_read<T: Decode<'fnt> + EncodeSize>(&mut self) -> Result<T> {
@@ -55,18 +74,22 @@ impl<'b: 'fnt, 'fnt> DecodeRead<'fnt> for &'b [u8] {
 /// For these situations, the `DecodeWith<Param>` trait provides the
 /// same interface as `Decode` except that it provides a parameter
 /// which can be used in the implementation.
-
 // TODO: Investigate if P should be an associated type if the ATC RFC merges.
-
 pub trait Decode1<'fnt, P>: Sized {
-    fn decode(&'fnt [u8], param: P) -> Result<Self>;
+    fn decode(buffer: &'fnt [u8], param: P) -> Result<Self>;
+}
+
+/// The same as `Decode1`, but for tables which require two parameters
+/// to decode, such as `hmtx` which depends on both `hhea` and `maxp`.
+pub trait Decode2<'fnt, P1, P2>: Sized {
+    fn decode(buffer: &'fnt [u8], param1: P1, param2: P2) -> Result<Self>;
 }
 
 /// The `DecodeWithRead` trait provides a `Read`-like interface
 /// to decoding a type.  This trait is automatically implemented
 /// for types that implement `DecodeWith` and `StaticSize` automatically.
-
-pub trait DecodeRead1<'fnt, P>: Sized {
+#[doc(hidden)]
+pub trait DecodeRead1<'fnt, P>: Sized + sealed::Sealed {
     fn decode_read1<T>(&mut self, param: P) -> Result<T>
         where T: Decode1<'fnt, P> + EncodeSize;
 }
@@ -80,4 +103,73 @@ impl<'b: 'fnt, 'fnt, P> DecodeRead1<'fnt, P> for &'b [u8] {
         *self = &self[ret.encode_size()..];
         Ok(ret)
     }
-}
\ No newline at end of file
+}
+/// Arrays of types with a static size, such as the 10 PANOSE digits, are
+/// decoded element by element.
+impl<T: StaticEncodeSize, const N: usize> StaticEncodeSize for [T; N] {
+    fn size() -> usize {
+        T::size() * N
+    }
+}
+
+impl<'fnt, T, const N: usize> Decode<'fnt> for [T; N]
+    where T: Decode<'fnt> + StaticEncodeSize
+{
+    fn decode(mut buffer: &'fnt [u8]) -> Result<[T; N]> {
+        required_len!(buffer, Self::size());
+        let items = (0..N).map(|_| buffer.decode_read()).collect::<Result<Vec<T>>>()?;
+        Ok(items.try_into().unwrap_or_else(|_| unreachable!("an array of {} items", N)))
+    }
+}
+
+/// Tuples decode their fields in order, for paired records which have no
+/// name of their own.
+macro_rules! impl_tuple {
+    ($($name:ident),+) => (
+        impl<$($name: StaticEncodeSize),+> StaticEncodeSize for ($($name,)+) {
+            fn size() -> usize {
+                0 $(+ $name::size())+
+            }
+        }
+
+        impl<'fnt, $($name),+> Decode<'fnt> for ($($name,)+)
+            where $($name: Decode<'fnt> + StaticEncodeSize),+
+        {
+            fn decode(mut buffer: &'fnt [u8]) -> Result<($($name,)+)> {
+                required_len!(buffer, Self::size());
+                Ok(($(buffer.decode_read::<$name>()?,)+))
+            }
+        }
+    )
+}
+
+impl_tuple!(A, B);
+impl_tuple!(A, B, C);
+impl_tuple!(A, B, C, D);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use primitives::{FWord, GlyphId};
+
+    #[derive(Decode, StaticEncodeSize, Debug, PartialEq)]
+    struct Record {
+        ranges: [u16; 3],
+        pair: (GlyphId, FWord),
+        flag: u8,
+    }
+
+    #[test]
+    fn arrays_and_tuples() {
+        let data = [0, 1, 0, 2, 0, 3, 0, 7, 0xFF, 0xFE, 9];
+        assert_eq!(<[u16; 3]>::decode(&data), Ok([1, 2, 3]));
+        assert_eq!(<[u8; 0]>::decode(&[]), Ok([]));
+        assert_eq!(<(u8, u16)>::decode(&data), Ok((0, 0x0100)));
+        assert_eq!(<(u8, u8, u8, i8)>::size(), 4);
+        assert_eq!(<[u16; 6]>::decode(&data), Err(Error::UnexpectedEof));
+        assert_eq!(<(u32, u32, u32)>::decode(&data), Err(Error::UnexpectedEof));
+
+        assert_eq!(Record::size(), 11);
+        assert_eq!(Record::decode(&data), Ok(Record { ranges: [1, 2, 3], pair: (GlyphId(7), FWord::from(-2)), flag: 9 }));
+    }
+}
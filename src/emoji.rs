@@ -0,0 +1,92 @@
+//! This module resolves emoji sequences to glyphs, so that an application
+//! can tell whether a font shows a sequence as one emoji before choosing
+//! it, or whether to fall back to another font.
+//!
+//! ZWJ sequences, skin tone modifiers and flags are ligatures in emoji
+//! fonts, and a font without the ligature shows the components side by
+//! side.  Emoji and text presentation selectors are resolved through the
+//! variation sequences of `cmap`.
+
+use font::Font;
+use primitives::{GlyphId, Tag};
+use shape;
+use tags::{features, scripts};
+use error::Result;
+
+/// The features emoji fonts form sequences with.
+pub const EMOJI_FEATURES: [Tag; 2] = [features::CCMP, features::LIGA];
+
+/// How a font shows an emoji sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmojiSupport {
+    /// The sequence resolves to one glyph.
+    Single(GlyphId),
+    /// Every character is mapped, but the font has no glyph for the whole
+    /// sequence and shows these glyphs instead.
+    Components(Vec<GlyphId>),
+    /// Some character of the sequence is not mapped, or the sequence is
+    /// empty.
+    Missing,
+}
+
+impl EmojiSupport {
+    pub fn is_single(&self) -> bool {
+        matches!(*self, EmojiSupport::Single(_))
+    }
+}
+
+/// Resolve `sequence`, which is one emoji such as `"👩\u{200D}💻"`, through
+/// `cmap` and the `ccmp` and `liga` lookups of `GSUB`.  Joiners and
+/// variation selectors the font does not map are ignored, as shaping
+/// ignores them.
+pub fn resolve_emoji(font: &Font, sequence: &str) -> Result<EmojiSupport> {
+    let mut items = shape::map_text(font, sequence)?;
+    if items.is_empty() || items.iter().any(|item| item.glyph.0 == 0) {
+        return Ok(EmojiSupport::Missing)
+    }
+
+    shape::substitute_features(font, &mut items, scripts::DEFAULT, &EMOJI_FEATURES)?;
+    Ok(match items[..] {
+        [item] => EmojiSupport::Single(item.glyph),
+        _ => EmojiSupport::Components(items.iter().map(|item| item.glyph).collect()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+    use test_fonts;
+
+    #[test]
+    fn resolve_sequences() {
+        let cmap = test_fonts::cmap_with_sequences(&[
+            ('\u{1F469}', 1), ('\u{200D}', 2), ('\u{1F4BB}', 3),
+            ('\u{1F44D}', 5), ('\u{1F3FD}', 6), ('\u{1F3FF}', 11),
+            ('\u{1F1EB}', 8), ('\u{1F1F7}', 9),
+            ('\u{263A}', 13),
+        ], &[('\u{263A}', '\u{FE0E}', None), ('\u{263A}', '\u{FE0F}', Some(12))]);
+        let gsub = test_fonts::layout_table(b"DFLT", &[(b"liga", &[0, 1, 2])], &[
+            (4, test_fonts::ligature_substitution(&[1, 2, 3], 4)),
+            (4, test_fonts::ligature_substitution(&[5, 6], 7)),
+            (4, test_fonts::ligature_substitution(&[8, 9], 10)),
+        ]);
+        let data = test_fonts::minimal_font(false).table(b"cmap", cmap).table(b"GSUB", gsub).build();
+        let font = Font::decode(&data).unwrap();
+        let resolve = |sequence: &str| resolve_emoji(&font, sequence).unwrap();
+
+        assert_eq!(resolve("\u{1F469}\u{200D}\u{1F4BB}"), EmojiSupport::Single(GlyphId(4)));
+        assert_eq!(resolve("\u{1F44D}\u{1F3FD}"), EmojiSupport::Single(GlyphId(7)));
+        assert_eq!(resolve("\u{1F1EB}\u{1F1F7}"), EmojiSupport::Single(GlyphId(10)));
+        assert_eq!(resolve("\u{263A}\u{FE0F}"), EmojiSupport::Single(GlyphId(12)));
+        assert_eq!(resolve("\u{263A}\u{FE0E}"), EmojiSupport::Single(GlyphId(13)));
+        assert!(resolve("\u{1F44D}\u{FE0F}").is_single());
+
+        // Without a ligature for the darkest skin tone, the modifier is
+        // shown after the base.
+        assert_eq!(resolve("\u{1F44D}\u{1F3FF}"), EmojiSupport::Components(vec![GlyphId(5), GlyphId(11)]));
+        assert_eq!(resolve("\u{1F469}\u{200D}\u{1F680}"), EmojiSupport::Missing);
+        assert_eq!(resolve("\u{1F3F4}\u{E0067}\u{E007F}"), EmojiSupport::Missing);
+        assert_eq!(resolve(""), EmojiSupport::Missing);
+    }
+}
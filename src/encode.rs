@@ -0,0 +1,269 @@
+//! This module contains the counterpart of `decode`: the `Encode` trait,
+//! which writes a primitive in its big-endian font file representation,
+//! and `EncodeBuf`, the buffer table writers build their output in.
+//!
+//! Tables commonly refer forward to data which has not been written yet,
+//! so `EncodeBuf` can reserve space for an offset or value and fill it in
+//! once it is known.
+
+#![forbid(unsafe_code)]
+
+use primitives::{Fixed, FWord, UFWord, F2Dot14, Uint24, FixedVersion, GlyphId, LongDateTime, Tag};
+use error::{Error, Result};
+
+/// Types which can be written to a font file.  The encoded size agrees
+/// with `EncodeSize` for types which implement both.
+pub trait Encode {
+    fn encode(&self, buf: &mut EncodeBuf);
+}
+
+macro_rules! impl_encode {
+    ($($type:ty => |$value:ident| $bytes:expr),* $(,)*) => (
+        $(
+            impl Encode for $type {
+                fn encode(&self, buf: &mut EncodeBuf) {
+                    let $value = *self;
+                    buf.put_bytes(&$bytes);
+                }
+            }
+        )*
+    );
+}
+
+impl_encode!(
+    u8 => |value| [value],
+    i8 => |value| [value as u8],
+    u16 => |value| value.to_be_bytes(),
+    i16 => |value| value.to_be_bytes(),
+    u32 => |value| value.to_be_bytes(),
+    i32 => |value| value.to_be_bytes(),
+    u64 => |value| value.to_be_bytes(),
+    i64 => |value| value.to_be_bytes(),
+    Fixed => |value| i32::from(value).to_be_bytes(),
+    FWord => |value| i16::from(value).to_be_bytes(),
+    UFWord => |value| u16::from(value).to_be_bytes(),
+    F2Dot14 => |value| i16::from(value).to_be_bytes(),
+    LongDateTime => |value| u64::from(value).to_be_bytes(),
+    GlyphId => |value| value.0.to_be_bytes(),
+    Tag => |value| value.bytes(),
+);
+
+impl Encode for Uint24 {
+    fn encode(&self, buf: &mut EncodeBuf) {
+        buf.put_bytes(&u32::from(*self).to_be_bytes()[1..]);
+    }
+}
+
+impl Encode for FixedVersion {
+    fn encode(&self, buf: &mut EncodeBuf) {
+        buf.put_u16(self.major);
+        buf.put_u16(self.minor);
+    }
+}
+
+/// Space reserved in an `EncodeBuf` for a value of `len` bytes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct Reservation {
+    pos: usize,
+    len: usize,
+}
+
+/// Space reserved for an offset which is measured from `base`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct OffsetPatch {
+    reservation: Reservation,
+    base: usize,
+}
+
+/// A growable buffer of big-endian font data.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EncodeBuf {
+    data: Vec<u8>,
+}
+
+impl EncodeBuf {
+    pub fn new() -> EncodeBuf {
+        EncodeBuf::default()
+    }
+
+    /// The number of bytes written, which is the position of the next.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.data
+    }
+
+    pub fn put<T: Encode>(&mut self, value: T) {
+        value.encode(self);
+    }
+
+    pub fn put_bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    pub fn put_u8(&mut self, value: u8) {
+        self.put(value);
+    }
+
+    pub fn put_i8(&mut self, value: i8) {
+        self.put(value);
+    }
+
+    pub fn put_u16(&mut self, value: u16) {
+        self.put(value);
+    }
+
+    pub fn put_i16(&mut self, value: i16) {
+        self.put(value);
+    }
+
+    pub fn put_u24(&mut self, value: Uint24) {
+        self.put(value);
+    }
+
+    pub fn put_u32(&mut self, value: u32) {
+        self.put(value);
+    }
+
+    pub fn put_i32(&mut self, value: i32) {
+        self.put(value);
+    }
+
+    pub fn put_fixed(&mut self, value: Fixed) {
+        self.put(value);
+    }
+
+    pub fn put_f2dot14(&mut self, value: F2Dot14) {
+        self.put(value);
+    }
+
+    pub fn put_tag(&mut self, tag: Tag) {
+        self.put(tag);
+    }
+
+    pub fn put_glyph_id(&mut self, glyph: GlyphId) {
+        self.put(glyph);
+    }
+
+    /// Pad with zeros to a multiple of `alignment` bytes.  An alignment of
+    /// zero, like one, adds no padding.
+    pub fn align(&mut self, alignment: usize) {
+        let len = self.data.len().next_multiple_of(alignment.max(1));
+        self.data.resize(len, 0);
+    }
+
+    /// Write `len` zero bytes to be filled in later.
+    pub fn reserve(&mut self, len: usize) -> Reservation {
+        let pos = self.data.len();
+        self.data.resize(pos + len, 0);
+        Reservation { pos, len }
+    }
+
+    /// Fill `reservation` with `value`, whose encoding must be exactly the
+    /// reserved length.
+    pub fn fill<T: Encode>(&mut self, reservation: Reservation, value: T) -> Result<()> {
+        let mut encoded = EncodeBuf::new();
+        encoded.put(value);
+        if encoded.len() != reservation.len {
+            return Err(Error::InvalidData)
+        }
+
+        let end = reservation.pos + reservation.len;
+        let target = self.data.get_mut(reservation.pos..end).ok_or(Error::UnexpectedEof)?;
+        target.copy_from_slice(&encoded.data);
+        Ok(())
+    }
+
+    /// Reserve a 16-bit offset measured from `base`, usually the start of
+    /// the table or subtable which contains it.
+    pub fn reserve_offset16(&mut self, base: usize) -> OffsetPatch {
+        OffsetPatch { reservation: self.reserve(2), base }
+    }
+
+    /// Reserve a 32-bit offset measured from `base`.
+    pub fn reserve_offset32(&mut self, base: usize) -> OffsetPatch {
+        OffsetPatch { reservation: self.reserve(4), base }
+    }
+
+    /// Point `patch` at the current position.  An offset which does not
+    /// fit its reserved size, or which would point before its base, is
+    /// `InvalidData`.
+    pub fn patch_offset(&mut self, patch: OffsetPatch) -> Result<()> {
+        let offset = self.data.len().checked_sub(patch.base).ok_or(Error::InvalidData)?;
+        match patch.reservation.len {
+            2 if offset <= 0xFFFF => self.fill(patch.reservation, offset as u16),
+            4 if offset <= 0xFFFF_FFFF => self.fill(patch.reservation, offset as u32),
+            _ => Err(Error::InvalidData),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+
+    #[test]
+    fn primitives_round_trip() {
+        let mut buf = EncodeBuf::new();
+        buf.put_u8(0xAB);
+        buf.put_i16(-2);
+        buf.put_u24(Uint24::from(0x0A0B0C));
+        buf.put_fixed(Fixed::from(0x0001_8000));
+        buf.put_f2dot14(F2Dot14::from(-0x2000));
+        buf.put_tag(Tag::new(*b"glyf"));
+        buf.put_glyph_id(GlyphId(300));
+        buf.put(FixedVersion { major: 1, minor: 0 });
+        assert_eq!(buf.as_slice(), &[
+            0xAB, 0xFF, 0xFE, 0x0A, 0x0B, 0x0C, 0x00, 0x01, 0x80, 0x00, 0xE0, 0x00,
+            b'g', b'l', b'y', b'f', 0x01, 0x2C, 0x00, 0x01, 0x00, 0x00,
+        ][..]);
+
+        let data = buf.as_slice();
+        assert_eq!(Uint24::decode(&data[3..]), Ok(Uint24::from(0x0A0B0C)));
+        assert_eq!(f32::from(Fixed::decode(&data[6..]).unwrap()), 1.5);
+        assert_eq!(f32::from(F2Dot14::decode(&data[10..]).unwrap()), -0.5);
+    }
+
+    #[test]
+    fn backpatching() {
+        let mut buf = EncodeBuf::new();
+        buf.put_u16(1);
+        let count = buf.reserve(2);
+        let offset = buf.reserve_offset16(0);
+        let long_offset = buf.reserve_offset32(2);
+        buf.put_bytes(&[0xAA; 3]);
+        buf.patch_offset(offset).unwrap();
+        buf.put_u8(0xBB);
+        buf.patch_offset(long_offset).unwrap();
+        buf.fill(count, 7u16).unwrap();
+        assert_eq!(buf.fill(count, 7u32), Err(Error::InvalidData));
+
+        assert_eq!(buf.into_vec(), vec![
+            0, 1, 0, 7, 0, 13, 0, 0, 0, 12, 0xAA, 0xAA, 0xAA, 0xBB,
+        ]);
+
+        // An offset past 0xFFFF cannot be stored in 16 bits.
+        let mut buf = EncodeBuf::new();
+        let offset = buf.reserve_offset16(0);
+        buf.put_bytes(&[0; 0x10000]);
+        assert_eq!(buf.patch_offset(offset), Err(Error::InvalidData));
+        buf.align(4);
+        assert_eq!(buf.len(), 0x10004);
+        buf.put_u8(0);
+        buf.align(0);
+        assert_eq!(buf.len(), 0x10005);
+    }
+}
@@ -7,6 +7,9 @@ pub enum Error {
     UnexpectedEof,
     InvalidData,
     UnsupportedCmapFormat,
-    UnsupportedVersion,
+    /// A table or subtable version which is not supported, widened to `u32`.
+    UnsupportedVersion(u32),
     TtcfUnsupported,
+    MissingTable,
+    InvalidGlyphId,
 }
\ No newline at end of file
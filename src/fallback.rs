@@ -0,0 +1,167 @@
+//! This module chooses, for each character of a text, the first font of a
+//! list which can show it, and splits the text into runs which can each be
+//! shaped with a single font.
+
+use std::ops::Range;
+
+use font::Font;
+use primitives::GlyphId;
+use table::TaggedTable;
+use table::cmap::{Cmap, CmapSubtable};
+use unicode::{extends_grapheme, is_default_ignorable};
+use error::Result;
+
+/// An ordered list of fonts, from most to least preferred.
+#[derive(Debug, Clone)]
+pub struct FallbackChain<'fnt> {
+    fonts: Vec<Font<'fnt>>,
+    /// The Unicode subtable of each font, which is decoded once rather
+    /// than for every character.
+    subtables: Vec<Option<CmapSubtable<'fnt>>>,
+}
+
+/// A run of text which `font` covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontRun {
+    /// The index of the font in the chain.
+    pub font: usize,
+    /// The byte range of the run in the text.
+    pub range: Range<usize>,
+}
+
+impl<'fnt> FallbackChain<'fnt> {
+    pub fn new(fonts: Vec<Font<'fnt>>) -> Result<FallbackChain<'fnt>> {
+        let subtables = fonts.iter()
+            .map(|font| match font.optional_table::<Cmap>()? {
+                Some(cmap) => cmap.unicode_subtable(),
+                None => Ok(None),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(FallbackChain { fonts, subtables })
+    }
+
+    pub fn fonts(&self) -> &[Font<'fnt>] {
+        &self.fonts
+    }
+
+    fn font_glyph(&self, index: usize, character: char) -> Result<Option<GlyphId>> {
+        match self.subtables[index] {
+            Some(ref subtable) => Ok(subtable.glyph_id(character as u32)),
+            // Symbol fonts and fonts without a `cmap`.
+            None if self.fonts[index].table_record(Cmap::tag()).is_some() => self.fonts[index].glyph_index(character),
+            None => Ok(None),
+        }
+    }
+
+    /// The first font which maps `character`, and its glyph.
+    pub fn glyph_for_char(&self, character: char) -> Result<Option<(usize, GlyphId)>> {
+        for index in 0..self.fonts.len() {
+            if let Some(glyph) = self.font_glyph(index, character)? {
+                return Ok(Some((index, glyph)))
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Whether font `index` maps every character of `cluster` which must
+    /// be visible.
+    fn covers(&self, index: usize, cluster: &str) -> Result<bool> {
+        for character in cluster.chars() {
+            if !is_default_ignorable(character) && self.font_glyph(index, character)?.is_none() {
+                return Ok(false)
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Split `text` into runs of the font each part should be shown with.
+    ///
+    /// A character and the marks, joiners and selectors which extend it
+    /// are kept in one font, the first which covers them all.  Spaces and
+    /// punctuation stay in the run before them when its font covers them,
+    /// so that they do not split a run of another font.  Text which no
+    /// font covers is given to the first font, which shows `.notdef`.
+    pub fn itemize(&self, text: &str) -> Result<Vec<FontRun>> {
+        let mut runs: Vec<FontRun> = Vec::new();
+        let mut clusters = cluster_starts(text).peekable();
+        while let Some(start) = clusters.next() {
+            let end = clusters.peek().cloned().unwrap_or(text.len());
+            let cluster = &text[start..end];
+
+            let current = runs.last().map(|run| run.font);
+            let base = cluster.chars().next().unwrap_or(' ');
+            let sticky = match current {
+                Some(current) if !base.is_alphanumeric() => self.covers(current, cluster)?,
+                _ => false,
+            };
+
+            let font = if sticky {
+                current
+            } else {
+                let mut font = None;
+                for index in 0..self.fonts.len() {
+                    if self.covers(index, cluster)? {
+                        font = Some(index);
+                        break
+                    }
+                }
+                font
+            };
+
+            // A font for the base character is better than none.
+            let font = match font {
+                Some(font) => font,
+                None => self.glyph_for_char(base)?.map_or(0, |(font, _)| font),
+            };
+
+            match runs.last_mut() {
+                Some(run) if run.font == font => run.range.end = end,
+                _ => runs.push(FontRun { font, range: start..end }),
+            }
+        }
+
+        Ok(runs)
+    }
+}
+
+/// The byte offsets at which each cluster of `text` starts.
+fn cluster_starts(text: &str) -> impl Iterator<Item = usize> + '_ {
+    text.char_indices()
+        .filter(|&(index, character)| index == 0 || !extends_grapheme(character))
+        .map(|(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+    use test_fonts;
+
+    #[test]
+    fn fallback_runs() {
+        let latin = test_fonts::minimal_font(false)
+            .table(b"cmap", test_fonts::cmap(&[('a', 1), ('b', 2), (' ', 1), ('.', 2)]))
+            .build();
+        let greek = test_fonts::minimal_font(false)
+            .table(b"cmap", test_fonts::cmap(&[('α', 1), ('β', 2), (' ', 1), ('a', 2), ('\u{301}', 1)]))
+            .build();
+        let chain = FallbackChain::new(vec![Font::decode(&latin).unwrap(), Font::decode(&greek).unwrap()]).unwrap();
+
+        assert_eq!(chain.glyph_for_char('b'), Ok(Some((0, GlyphId(2)))));
+        assert_eq!(chain.glyph_for_char('β'), Ok(Some((1, GlyphId(2)))));
+        assert_eq!(chain.glyph_for_char('ж'), Ok(None));
+
+        fn runs<'t>(chain: &FallbackChain, text: &'t str) -> Vec<(usize, &'t str)> {
+            chain.itemize(text).unwrap().into_iter().map(|run| (run.font, &text[run.range])).collect()
+        }
+
+        // The space after `α` stays with the Greek font, and the accented
+        // `a` moves to it since the Latin font has no combining acute.
+        assert_eq!(runs(&chain, "ab αβ ba."), vec![(0, "ab "), (1, "αβ "), (0, "ba.")]);
+        assert_eq!(runs(&chain, "ba\u{301}b"), vec![(0, "b"), (1, "a\u{301}"), (0, "b")]);
+        assert_eq!(runs(&chain, "a\u{200D}ж"), vec![(0, "a\u{200D}ж")]);
+        assert_eq!(runs(&chain, ""), Vec::new());
+    }
+}
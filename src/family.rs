@@ -0,0 +1,342 @@
+//! This module groups faces into the families a font menu shows, with one
+//! entry per family and its styles listed underneath.
+//!
+//! Faces are grouped by their WWS family name, falling back to the
+//! typographic family name and then the legacy family name, so that a
+//! family with more styles than the four RIBBI styles the legacy names
+//! allow is still shown as one family.  Each named instance of a variable
+//! font is a member of its own, as if it were a separate face.
+
+use font::Font;
+use primitives::Tag;
+use table::head::Head;
+use table::name::{Name, NameId};
+use table::os2::Os2;
+#[cfg(feature = "variations")]
+use table::fvar::{Fvar, InstanceRecord};
+#[cfg(feature = "variations")]
+use table::stat::Stat;
+use error::Result;
+
+/// A family and its styles, ordered by width, weight and slope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Family {
+    pub name: String,
+    pub members: Vec<FamilyMember>,
+}
+
+/// A style of a family: a face, or a named instance of a variable face.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FamilyMember {
+    /// The index of the face in the slice given to `group_families`.
+    pub face: usize,
+    pub style: String,
+    /// The axis coordinates of a named instance, which are empty for a
+    /// face which is not variable.
+    pub coordinates: Vec<(Tag, f32)>,
+    /// The weight, as in `usWeightClass`.
+    pub weight: u16,
+    /// The width, as in `usWidthClass`.
+    pub width_class: u16,
+    pub italic: bool,
+}
+
+/// Group `faces` into families, sorted by name.  A face without a family
+/// name is grouped under the empty name.
+pub fn group_families(faces: &[Font]) -> Result<Vec<Family>> {
+    let mut families: Vec<Family> = Vec::new();
+    for (index, face) in faces.iter().enumerate() {
+        let name = face.table::<Name>()?;
+        let family_name = find_first(&name, &[NameId::WwsFamilyName, NameId::TypographicFamilyName, NameId::FamilyName])
+            .unwrap_or_default();
+        let members = face_members(index, face, &name)?;
+
+        match families.iter_mut().find(|family| family.name == family_name) {
+            Some(family) => family.members.extend(members),
+            None => families.push(Family { name: family_name, members }),
+        }
+    }
+
+    for family in &mut families {
+        family.members.sort_by_key(|member| (member.width_class, member.weight, member.italic, member.face));
+    }
+    families.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(families)
+}
+
+fn find_first(name: &Name, ids: &[NameId]) -> Option<String> {
+    ids.iter().filter_map(|&id| name.find(id)).next()
+}
+
+/// The members of one face: its named instances if it is variable and
+/// has any, and otherwise the face itself.
+fn face_members(index: usize, face: &Font, name: &Name) -> Result<Vec<FamilyMember>> {
+    let style = find_first(name, &[NameId::WwsSubfamilyName, NameId::TypographicSubfamilyName, NameId::SubfamilyName])
+        .unwrap_or_default();
+    let member = match face.optional_table::<Os2>()? {
+        Some(os2) => FamilyMember {
+            face: index,
+            style,
+            coordinates: Vec::new(),
+            weight: os2.us_weight_class,
+            width_class: os2.us_width_class,
+            italic: os2.is_italic(),
+        },
+        None => {
+            let head = face.table::<Head>()?;
+            FamilyMember {
+                face: index,
+                style,
+                coordinates: Vec::new(),
+                weight: if head.is_bold() { 700 } else { 400 },
+                width_class: 5,
+                italic: head.is_italic(),
+            }
+        },
+    };
+
+    #[cfg(feature = "variations")]
+    {
+        if let Some(fvar) = face.optional_table::<Fvar>()? {
+            let instances = instance_members(&fvar, name, &member);
+            if !instances.is_empty() {
+                return Ok(instances)
+            }
+        }
+    }
+
+    Ok(vec![member])
+}
+
+/// A member for each named instance with a style name.  Axes which the
+/// instance does not describe are taken from the face.
+#[cfg(feature = "variations")]
+fn instance_members(fvar: &Fvar, name: &Name, face: &FamilyMember) -> Vec<FamilyMember> {
+    let axes = fvar.axes().into_iter().map(|axis| axis.axis_tag).collect::<Vec<_>>();
+    fvar.instances()
+        .filter_map(|instance| {
+            let style = name.find(NameId::from(instance.subfamily_name_id))?;
+            let coordinates = axes.iter().cloned()
+                .zip(instance.coordinates.into_iter().map(f32::from))
+                .collect::<Vec<_>>();
+            let value = |tag: &[u8; 4]| {
+                coordinates.iter().find(|&&(axis, _)| axis == Tag::new(*tag)).map(|&(_, value)| value)
+            };
+
+            let weight = value(b"wght").map_or(face.weight, |weight| weight.round().clamp(1.0, 1000.0) as u16);
+            let width_class = value(b"wdth").map_or(face.width_class, width_class);
+            let italic = match (value(b"ital"), value(b"slnt")) {
+                (None, None) => face.italic,
+                (ital, slnt) => ital.is_some_and(|ital| ital >= 0.5) || slnt.is_some_and(|slnt| slnt != 0.0),
+            };
+            Some(FamilyMember { face: face.face, style, coordinates, weight, width_class, italic })
+        })
+        .collect()
+}
+
+/// The names of a named instance of a variable font.
+#[cfg(feature = "variations")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstanceName {
+    /// The typographic family name, or the legacy family name.
+    pub family: String,
+    pub style: String,
+    /// The family and style names, as a font menu shows the instance.
+    pub full_name: String,
+    /// The PostScript name from `fvar`, or else the one Adobe Technical
+    /// Note #5902 derives from the family prefix and the style.
+    pub postscript_name: String,
+}
+
+/// The names of each named instance of `font`, in `fvar` order.
+///
+/// The style is built from the `STAT` axis value names at the instance's
+/// coordinates, so that elidable names such as `Regular` are left out of
+/// compound styles: `Condensed` rather than `Condensed Regular`.  Without
+/// `STAT`, or if it does not name every axis value, the subfamily name of
+/// the `fvar` instance is used.  Fonts without `fvar` have no instances.
+#[cfg(feature = "variations")]
+pub fn instance_names(font: &Font) -> Result<Vec<InstanceName>> {
+    let fvar = match font.optional_table::<Fvar>()? {
+        Some(fvar) => fvar,
+        None => return Ok(Vec::new()),
+    };
+    let name = font.table::<Name>()?;
+    let stat = font.optional_table::<Stat>()?;
+    let family = find_first(&name, &[NameId::TypographicFamilyName, NameId::FamilyName]).unwrap_or_default();
+    let prefix = name.find(NameId::VariationsPostScriptNamePrefix).unwrap_or_else(|| family.clone());
+    let axes = fvar.axes().into_iter().map(|axis| axis.axis_tag).collect::<Vec<_>>();
+
+    Ok(fvar.instances()
+        .map(|instance| {
+            let style = stat.as_ref()
+                .and_then(|stat| stat_style(stat, &name, &axes, &instance))
+                .or_else(|| name.find(NameId::from(instance.subfamily_name_id)))
+                .unwrap_or_default();
+            let full_name = match (family.is_empty(), style.is_empty()) {
+                (_, true) => family.clone(),
+                (true, false) => style.clone(),
+                (false, false) => format!("{} {}", family, style),
+            };
+            let postscript_name = instance.post_script_name_id
+                .filter(|&id| id != 0xFFFF)
+                .and_then(|id| name.find(NameId::from(id)))
+                .unwrap_or_else(|| format!("{}-{}", postscript_chars(&prefix), postscript_chars(&style)));
+            InstanceName { family: family.clone(), style, full_name, postscript_name }
+        })
+        .collect())
+}
+
+/// The style name `STAT` gives the coordinates of `instance`.
+#[cfg(feature = "variations")]
+fn stat_style(stat: &Stat, name: &Name, axes: &[Tag], instance: &InstanceRecord) -> Option<String> {
+    let location = axes.iter().cloned()
+        .zip(instance.coordinates.into_iter().map(f32::from))
+        .collect::<Vec<_>>();
+    let words = stat.style_name_ids(&location)?
+        .into_iter()
+        .map(|id| name.find(NameId::from(id)))
+        .collect::<Option<Vec<_>>>()?;
+    Some(words.join(" "))
+}
+
+/// `text` with only the characters a PostScript name allows.
+#[cfg(feature = "variations")]
+fn postscript_chars(text: &str) -> String {
+    text.chars()
+        .filter(|&c| c.is_ascii_graphic() && !"[](){}<>/%".contains(c))
+        .collect()
+}
+
+/// The `usWidthClass` nearest to a `wdth` axis value, which is a
+/// percentage of the normal width.
+#[cfg(feature = "variations")]
+pub(crate) fn width_class(percent: f32) -> u16 {
+    const WIDTHS: [f32; 9] = [50.0, 62.5, 75.0, 87.5, 100.0, 112.5, 125.0, 150.0, 200.0];
+    let mut nearest = 0;
+    for (index, &width) in WIDTHS.iter().enumerate() {
+        if (width - percent).abs() < (WIDTHS[nearest] - percent).abs() {
+            nearest = index;
+        }
+    }
+    nearest as u16 + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+
+    #[test]
+    fn group_static_faces() {
+        let data = [
+            open_file!("data/DroidSerif-BoldItalic.ttf"),
+            open_file!("data/DroidSerif.ttf"),
+            open_file!("data/DroidSerif-Italic.ttf"),
+            open_file!("data/DroidSerif-Bold.ttf"),
+        ];
+        let faces = data.iter().map(|data| Font::decode(data).expect("failed to read font")).collect::<Vec<_>>();
+
+        let families = group_families(&faces).unwrap();
+        assert_eq!(families.len(), 1);
+        assert_eq!(families[0].name, "Droid Serif");
+        let members = families[0].members.iter()
+            .map(|member| (member.face, member.style.as_str(), member.weight, member.italic))
+            .collect::<Vec<_>>();
+        assert_eq!(members, vec![
+            (1, "Regular", 400, false),
+            (2, "Italic", 400, true),
+            (3, "Bold", 700, false),
+            (0, "Bold Italic", 700, true),
+        ]);
+    }
+
+    #[cfg(feature = "variations")]
+    #[test]
+    fn group_named_instances() {
+        use test_fonts;
+
+        let fvar = test_fonts::fvar_with_instances(&[(b"wght", 100.0, 400.0, 900.0), (b"wdth", 75.0, 100.0, 100.0)], &[
+            (258, &[700.0, 75.0]),
+            (257, &[400.0, 100.0]),
+            (300, &[900.0, 100.0]),
+        ]);
+        let name = test_fonts::name(&[
+            (1, "Sample Variable"),
+            (2, "Regular"),
+            (16, "Sample"),
+            (257, "Regular"),
+            (258, "Condensed Bold"),
+        ]);
+        let variable = test_fonts::minimal_font(false).table(b"fvar", fvar).table(b"name", name).build();
+        let name = test_fonts::name(&[(1, "Sample Light"), (2, "Regular"), (16, "Sample"), (17, "Light")]);
+        let light = test_fonts::minimal_font(false).table(b"name", name).build();
+        let name = test_fonts::name(&[(1, "Other")]);
+        let other = test_fonts::minimal_font(false).table(b"name", name).build();
+        let faces = [&variable, &light, &other].iter().map(|data| Font::decode(data).unwrap()).collect::<Vec<_>>();
+
+        let families = group_families(&faces).unwrap();
+        let names = families.iter().map(|family| family.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Other", "Sample"]);
+        assert_eq!(families[0].members[0].style, "");
+
+        // The instance without a style name is left out, and the light face
+        // has no `OS/2`, so its weight falls back to regular and it sorts
+        // after the variable face.
+        let members = families[1].members.iter()
+            .map(|member| (member.face, member.style.as_str(), member.weight, member.width_class))
+            .collect::<Vec<_>>();
+        assert_eq!(members, vec![(0, "Condensed Bold", 700, 3), (0, "Regular", 400, 5), (1, "Light", 400, 5)]);
+        assert_eq!(families[1].members[0].coordinates, vec![(Tag::new(*b"wght"), 700.0), (Tag::new(*b"wdth"), 75.0)]);
+
+        assert_eq!(width_class(100.0), 5);
+        assert_eq!(width_class(80.0), 3);
+        assert_eq!(width_class(500.0), 9);
+    }
+
+    #[cfg(feature = "variations")]
+    #[test]
+    fn stat_instance_names() {
+        use test_fonts::{self, AxisValueRecord};
+
+        let fvar = test_fonts::fvar_with_instances(&[(b"wght", 100.0, 400.0, 900.0), (b"wdth", 75.0, 100.0, 100.0)], &[
+            (257, &[400.0, 100.0]),
+            (258, &[400.0, 75.0]),
+            (259, &[700.0, 75.0]),
+            (260, &[550.0, 100.0]),
+        ]);
+        let stat = test_fonts::stat(&[(b"wght", 1), (b"wdth", 0)], &[
+            AxisValueRecord::Single(0, 400.0, 0x0002, 261),
+            AxisValueRecord::Single(0, 700.0, 0, 262),
+            AxisValueRecord::Single(1, 100.0, 0x0002, 261),
+            AxisValueRecord::Single(1, 75.0, 0, 263),
+        ], 261);
+        let name = test_fonts::name(&[
+            (1, "Sample Variable"),
+            (16, "Sample"),
+            (257, "Regular"),
+            (258, "Condensed Regular"),
+            (259, "Condensed Bold"),
+            (260, "Medium"),
+            (261, "Regular"),
+            (262, "Bold"),
+            (263, "Condensed"),
+        ]);
+        let data = test_fonts::minimal_font(false)
+            .table(b"fvar", fvar)
+            .table(b"STAT", stat)
+            .table(b"name", name)
+            .build();
+        let font = Font::decode(&data).unwrap();
+
+        // The medium instance has no `STAT` name, so `fvar` names it.
+        let names = instance_names(&font).unwrap();
+        let full_names = names.iter().map(|name| name.full_name.as_str()).collect::<Vec<_>>();
+        assert_eq!(full_names, vec!["Sample Regular", "Sample Condensed", "Sample Condensed Bold", "Sample Medium"]);
+        assert_eq!(names[2].postscript_name, "Sample-CondensedBold");
+        assert_eq!(names[0].family, "Sample");
+
+        let data = test_fonts::minimal_font(false).build();
+        assert_eq!(instance_names(&Font::decode(&data).unwrap()), Ok(Vec::new()));
+    }
+}
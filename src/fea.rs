@@ -0,0 +1,360 @@
+//! This module renders the `GSUB` and `GPOS` tables of a font as text in
+//! the syntax of Adobe feature files, so that what a compiled font
+//! actually contains can be reviewed by eye.
+//!
+//! The output is approximate and is not meant to be compiled again:
+//! single, multiple, alternate and ligature substitutions and single and
+//! pair adjustments are written out rule by rule, while the subtables of
+//! other lookup types are noted in comments.  Glyphs are written by the
+//! names `Font::glyph_name` resolves, and glyphs past the end of the font
+//! as `glyph00042`, after their glyph id.
+
+use std::fmt::Write;
+
+use decode::{Decode, Decode1};
+use font::{Font, GlyphNames};
+use primitives::{GlyphId, Tag};
+use table::layout::{ClassDef, Coverage, Gpos, Gsub, LayoutTable, Lookup, LookupDescription, ValueRecord};
+use error::{Error, Result};
+
+impl<'fnt> Font<'fnt> {
+    /// The lookups and features of `GSUB` and `GPOS` in feature file
+    /// syntax.  Lookups are named after their table and index, as
+    /// `GSUB_3`, and each feature block lists the lookups it uses under
+    /// every script and language which enables it.
+    ///
+    /// A subtable which cannot be read is noted in a comment rather than
+    /// failing the whole export.
+    pub fn to_fea(&self) -> Result<String> {
+        let names = self.glyph_names()?;
+        let gsub = self.optional_table::<Gsub>()?;
+        let gpos = self.optional_table::<Gpos>()?;
+        let mut out = String::new();
+
+        let mut systems = Vec::new();
+        for table in gsub.iter().map(|gsub| **gsub).chain(gpos.iter().map(|gpos| **gpos)) {
+            for system in table.language_systems()? {
+                if !systems.contains(&(system.script, system.language)) {
+                    systems.push((system.script, system.language));
+                }
+            }
+        }
+        for (script, language) in systems {
+            let _ = writeln!(out, "languagesystem {} {};", tag(script), tag(language));
+        }
+
+        if let Some(gsub) = gsub {
+            let mut writer = FeaWriter { names: &names, out: &mut out, table: "GSUB" };
+            for index in 0..gsub.lookup_count()? {
+                writer.lookup(index, gsub.describe(index), gsub.lookup(index)?, FeaWriter::substitution);
+            }
+            writer.features(&gsub)?;
+        }
+        if let Some(gpos) = gpos {
+            let mut writer = FeaWriter { names: &names, out: &mut out, table: "GPOS" };
+            for index in 0..gpos.lookup_count()? {
+                writer.lookup(index, gpos.describe(index), gpos.lookup(index)?, FeaWriter::positioning);
+            }
+            writer.features(&gpos)?;
+        }
+
+        Ok(out)
+    }
+}
+
+/// A tag as it is written in a feature file, without trailing spaces.
+fn tag(tag: Tag) -> String {
+    String::from_utf8_lossy(&tag.0).trim_end().to_string()
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Result<u16> {
+    u16::decode(data.get(pos..).ok_or(Error::UnexpectedEof)?)
+}
+
+fn coverage(subtable: &[u8]) -> Result<Vec<GlyphId>> {
+    let offset = read_u16(subtable, 2)? as usize;
+    Ok(Coverage::decode(subtable.get(offset..).ok_or(Error::UnexpectedEof)?)?.glyphs())
+}
+
+/// The glyph ids of the array of `count` ids at `pos`.
+fn glyph_array(data: &[u8], pos: usize, count: usize) -> Result<Vec<GlyphId>> {
+    (0..count).map(|index| read_u16(data, pos + index * 2).map(GlyphId)).collect()
+}
+
+/// A value record in feature file syntax: the advance alone when only the
+/// horizontal advance is adjusted, and all four values otherwise.
+fn value(value: ValueRecord) -> String {
+    if value.x_placement == 0 && value.y_placement == 0 && value.y_advance == 0 {
+        value.x_advance.to_string()
+    } else {
+        format!("<{} {} {} {}>", value.x_placement, value.y_placement, value.x_advance, value.y_advance)
+    }
+}
+
+struct FeaWriter<'a> {
+    names: &'a GlyphNames,
+    out: &'a mut String,
+    /// The tag of the table being written, which prefixes lookup names.
+    table: &'static str,
+}
+
+type SubtableWriter<'a> = fn(&mut FeaWriter<'a>, u16, &[u8]) -> Result<()>;
+
+impl<'a> FeaWriter<'a> {
+    fn glyph(&self, glyph: GlyphId) -> String {
+        match self.names.name(glyph) {
+            Some(name) => name.to_string(),
+            None => format!("glyph{:05}", glyph.0),
+        }
+    }
+
+    fn glyphs(&self, glyphs: &[GlyphId]) -> String {
+        let names = glyphs.iter().map(|&glyph| self.glyph(glyph)).collect::<Vec<_>>();
+        match names.len() {
+            1 => names[0].clone(),
+            _ => format!("[{}]", names.join(" ")),
+        }
+    }
+
+    fn rule(&mut self, rule: &str) {
+        let _ = writeln!(self.out, "    {};", rule);
+    }
+
+    fn lookup(&mut self, index: u16, description: Result<LookupDescription>, lookup: Lookup, subtable: SubtableWriter<'a>) {
+        let _ = writeln!(self.out, "\nlookup {}_{} {{", self.table, index);
+        if let Ok(description) = description {
+            let _ = writeln!(self.out, "    # {}", description);
+        }
+        if lookup.flag != 0 {
+            self.rule(&format!("lookupflag {}", lookup.flag));
+        }
+        for (number, data) in lookup.subtables().iter().enumerate() {
+            if number > 0 {
+                self.rule("subtable");
+            }
+            if let Err(error) = subtable(self, lookup.lookup_type, data) {
+                let _ = writeln!(self.out, "    # Subtable {} could not be read: {:?}", number, error);
+            }
+        }
+        let _ = writeln!(self.out, "}} {}_{};", self.table, index);
+    }
+
+    /// Write the rules of a `GSUB` subtable.
+    fn substitution(&mut self, lookup_type: u16, subtable: &[u8]) -> Result<()> {
+        let covered = coverage(subtable)?;
+        match (lookup_type, read_u16(subtable, 0)?) {
+            (1, 1) => {
+                let delta = read_u16(subtable, 4)?;
+                for glyph in covered {
+                    let rule = format!("sub {} by {}", self.glyph(glyph), self.glyph(GlyphId(glyph.0.wrapping_add(delta))));
+                    self.rule(&rule);
+                }
+            },
+            (1, 2) => {
+                for (index, glyph) in covered.into_iter().enumerate() {
+                    let rule = format!("sub {} by {}", self.glyph(glyph), self.glyph(GlyphId(read_u16(subtable, 6 + index * 2)?)));
+                    self.rule(&rule);
+                }
+            },
+            (2, 1) | (3, 1) => {
+                for (index, glyph) in covered.into_iter().enumerate() {
+                    let offset = read_u16(subtable, 6 + index * 2)? as usize;
+                    let glyphs = glyph_array(subtable, offset + 2, read_u16(subtable, offset)? as usize)?;
+                    let rule = match lookup_type {
+                        2 => format!("sub {} by {}", self.glyph(glyph), glyphs.iter().map(|&glyph| self.glyph(glyph)).collect::<Vec<_>>().join(" ")),
+                        _ => format!("sub {} from [{}]", self.glyph(glyph), glyphs.iter().map(|&glyph| self.glyph(glyph)).collect::<Vec<_>>().join(" ")),
+                    };
+                    self.rule(&rule);
+                }
+            },
+            (4, 1) => {
+                for (index, first) in covered.into_iter().enumerate() {
+                    let set = read_u16(subtable, 6 + index * 2)? as usize;
+                    for ligature in 0..read_u16(subtable, set)? as usize {
+                        let ligature = set + read_u16(subtable, set + 2 + ligature * 2)? as usize;
+                        let count = (read_u16(subtable, ligature + 2)? as usize).saturating_sub(1);
+                        let mut components = vec![first];
+                        components.extend(glyph_array(subtable, ligature + 4, count)?);
+                        let components = components.iter().map(|&glyph| self.glyph(glyph)).collect::<Vec<_>>();
+                        let rule = format!("sub {} by {}", components.join(" "), self.glyph(GlyphId(read_u16(subtable, ligature)?)));
+                        self.rule(&rule);
+                    }
+                }
+            },
+            (lookup_type, format) => {
+                let _ = writeln!(self.out, "    # Subtable of type {} format {} not exported", lookup_type, format);
+            },
+        }
+        Ok(())
+    }
+
+    /// Write the rules of a `GPOS` subtable.
+    fn positioning(&mut self, lookup_type: u16, subtable: &[u8]) -> Result<()> {
+        let value_record = |pos: usize, format: u16| {
+            ValueRecord::decode(subtable.get(pos..).ok_or(Error::UnexpectedEof)?, format)
+        };
+        let covered = coverage(subtable)?;
+        match (lookup_type, read_u16(subtable, 0)?) {
+            (1, 1) => {
+                let record = value_record(6, read_u16(subtable, 4)?)?;
+                let rule = format!("pos {} {}", self.glyphs(&covered), value(record));
+                self.rule(&rule);
+            },
+            (1, 2) => {
+                let format = read_u16(subtable, 4)?;
+                for (index, glyph) in covered.into_iter().enumerate() {
+                    let record = value_record(8 + index * ValueRecord::encoded_size(format), format)?;
+                    let rule = format!("pos {} {}", self.glyph(glyph), value(record));
+                    self.rule(&rule);
+                }
+            },
+            (2, 1) => {
+                let (format1, format2) = (read_u16(subtable, 4)?, read_u16(subtable, 6)?);
+                let size1 = ValueRecord::encoded_size(format1);
+                let record_size = 2 + size1 + ValueRecord::encoded_size(format2);
+                for (index, first) in covered.into_iter().enumerate() {
+                    let set = read_u16(subtable, 10 + index * 2)? as usize;
+                    for pair in 0..read_u16(subtable, set)? as usize {
+                        let record = set + 2 + pair * record_size;
+                        let second = GlyphId(read_u16(subtable, record)?);
+                        let (value1, value2) = (value_record(record + 2, format1)?, value_record(record + 2 + size1, format2)?);
+                        self.pair(&[first], value1, &[second], value2);
+                    }
+                }
+            },
+            (2, 2) => {
+                let (format1, format2) = (read_u16(subtable, 4)?, read_u16(subtable, 6)?);
+                let (size1, size2) = (ValueRecord::encoded_size(format1), ValueRecord::encoded_size(format2));
+                let class_def = |pos: usize| -> Result<ClassDef> {
+                    let offset = read_u16(subtable, pos)? as usize;
+                    ClassDef::decode(subtable.get(offset..).ok_or(Error::UnexpectedEof)?)
+                };
+                let (class_def1, class_def2) = (class_def(8)?, class_def(10)?);
+                let (class1_count, class2_count) = (read_u16(subtable, 12)? as usize, read_u16(subtable, 14)? as usize);
+                for class1 in 0..class1_count {
+                    let first = covered.iter().cloned().filter(|&glyph| class_def1.class(glyph) as usize == class1).collect::<Vec<_>>();
+                    // Class 0 of the second glyph is every glyph not listed,
+                    // which a feature file cannot name.
+                    for class2 in (1..class2_count).filter(|_| !first.is_empty()) {
+                        let record = 16 + (class1 * class2_count + class2) * (size1 + size2);
+                        let (value1, value2) = (value_record(record, format1)?, value_record(record + size1, format2)?);
+                        if value1 != ValueRecord::default() || value2 != ValueRecord::default() {
+                            self.pair(&first, value1, &class_def2.glyphs(class2 as u16), value2);
+                        }
+                    }
+                }
+            },
+            (lookup_type, format) => {
+                let _ = writeln!(self.out, "    # Subtable of type {} format {} not exported", lookup_type, format);
+            },
+        }
+        Ok(())
+    }
+
+    fn pair(&mut self, first: &[GlyphId], value1: ValueRecord, second: &[GlyphId], value2: ValueRecord) {
+        let rule = if value2 == ValueRecord::default() {
+            format!("pos {} {} {}", self.glyphs(first), self.glyphs(second), value(value1))
+        } else {
+            format!("pos {} {} {} {}", self.glyphs(first), value(value1), self.glyphs(second), value(value2))
+        };
+        self.rule(&rule);
+    }
+
+    /// Write a block for each feature tag, in the order of the feature
+    /// list, listing its lookups under each language system.
+    fn features(&mut self, table: &LayoutTable) -> Result<()> {
+        let features = table.features()?.records();
+        let systems = table.language_systems()?;
+        let mut tags = Vec::new();
+        for record in features {
+            if !tags.contains(&record.tag) {
+                tags.push(record.tag);
+            }
+        }
+
+        for feature_tag in tags {
+            let _ = writeln!(self.out, "\nfeature {} {{", tag(feature_tag));
+            for system in &systems {
+                let indices = system.feature_indices.iter().chain(&system.required_feature)
+                    .filter(|&&index| features.get(index as usize).map(|record| record.tag) == Some(feature_tag));
+                let mut lookups = Vec::new();
+                for &index in indices {
+                    lookups.extend(table.feature_lookups(index as usize)?);
+                }
+                if lookups.is_empty() {
+                    continue
+                }
+
+                let _ = writeln!(self.out, "    script {};", tag(system.script));
+                let _ = writeln!(self.out, "    language {};", tag(system.language));
+                for lookup in lookups {
+                    let _ = writeln!(self.out, "    lookup {}_{};", self.table, lookup);
+                }
+            }
+            let _ = writeln!(self.out, "}} {};", tag(feature_tag));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_fonts;
+
+    #[test]
+    fn export_fea() {
+        let gsub = test_fonts::layout_table(b"latn", &[(b"liga", &[1]), (b"smcp", &[0])], &[
+            (1, test_fonts::single_substitution(&[(1, 2)])),
+            (4, test_fonts::ligature_substitution(&[1, 2], 3)),
+        ]);
+        let gpos = test_fonts::layout_table(b"latn", &[(b"kern", &[0])], &[
+            (2, test_fonts::class_pair_adjustment(&[4], &[1, 3], -50)),
+        ]);
+        let data = test_fonts::minimal_font(false)
+            .table(b"maxp", test_fonts::maxp(5))
+            .table(b"hmtx", test_fonts::hmtx(&[(500, 0), (400, 0), (300, 0), (650, 0), (700, 0)], &[]))
+            .table(b"post", test_fonts::post(&[".notdef", "f", "i"]))
+            .table(b"GSUB", gsub)
+            .table(b"GPOS", gpos)
+            .build();
+        // The font has three outlines, so the ligature and glyph 4 are
+        // unnamed.
+        let fea = Font::decode(&data).unwrap().to_fea().unwrap();
+        assert_eq!(fea, "languagesystem latn dflt;
+
+lookup GSUB_0 {
+    # Lookup 0: Single sub, coverage 1 glyphs
+    sub f by i;
+} GSUB_0;
+
+lookup GSUB_1 {
+    # Lookup 1: Ligature sub, 1 ligatures, coverage 1 glyphs
+    sub f i by glyph00003;
+} GSUB_1;
+
+feature liga {
+    script latn;
+    language dflt;
+    lookup GSUB_1;
+} liga;
+
+feature smcp {
+    script latn;
+    language dflt;
+    lookup GSUB_0;
+} smcp;
+
+lookup GPOS_0 {
+    # Lookup 0: Pair pos, 4 class pairs, coverage 1 glyphs
+    pos glyph00004 [f glyph00003] -50;
+} GPOS_0;
+
+feature kern {
+    script latn;
+    language dflt;
+    lookup GPOS_0;
+} kern;
+");
+    }
+}
@@ -0,0 +1,235 @@
+//! This module contains fixed point arithmetic on the 26.6 numbers of
+//! pixel coordinates and the 16.16 `Fixed` numbers of font tables, in
+//! integers alone, so that results are the same on every platform.
+//!
+//! Products and quotients are computed in 64 bits and rounded to the
+//! nearest representable value, halves away from zero, as FreeType's
+//! `FT_MulDiv` rounds.  Results which do not fit saturate.
+
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+use primitives::Fixed;
+
+/// `a * b / c`, rounded to the nearest integer with halves away from
+/// zero.  A result out of range, including division by zero, saturates
+/// towards the sign of `a * b`.
+pub fn mul_div(a: i32, b: i32, c: i32) -> i32 {
+    let product = i64::from(a) * i64::from(b);
+    if c == 0 {
+        return match product {
+            0 => 0,
+            product if product < 0 => i32::MIN,
+            _ => i32::MAX,
+        }
+    }
+
+    let (magnitude, divisor) = (product.abs(), i64::from(c).abs());
+    let quotient = (magnitude + divisor / 2) / divisor;
+    let negative = (product < 0) != (c < 0);
+    let quotient = if negative { -quotient } else { quotient };
+    quotient.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32
+}
+
+/// A 32-bit signed fixed point number with 6 fractional bits, as pixel
+/// coordinates are stored in 64ths of a pixel.
+#[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct F26Dot6(i32);
+
+impl F26Dot6 {
+    pub const ZERO: F26Dot6 = F26Dot6(0);
+    pub const ONE: F26Dot6 = F26Dot6(64);
+
+    /// The number whose 64ths are `bits`.
+    pub const fn from_bits(bits: i32) -> F26Dot6 {
+        F26Dot6(bits)
+    }
+
+    pub const fn to_bits(self) -> i32 {
+        self.0
+    }
+
+    pub fn from_int(value: i32) -> F26Dot6 {
+        F26Dot6(value.saturating_mul(64))
+    }
+
+    /// The nearest number to `value`, which is how a float enters fixed
+    /// point arithmetic; it is exact for values already in 64ths.
+    pub fn from_f32(value: f32) -> F26Dot6 {
+        F26Dot6((value * 64.0).round() as i32)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        self.0 as f32 / 64.0
+    }
+
+    /// `units` font units at `ppem` pixels per em, for a font with
+    /// `units_per_em` units per em.
+    pub fn from_font_units(units: i32, ppem: F26Dot6, units_per_em: u16) -> F26Dot6 {
+        F26Dot6(mul_div(units, ppem.0, i32::from(units_per_em)))
+    }
+
+    /// The largest whole number no greater than `self`.
+    pub fn floor(self) -> F26Dot6 {
+        F26Dot6(self.0 & !63)
+    }
+
+    /// The smallest whole number no less than `self`.
+    pub fn ceil(self) -> F26Dot6 {
+        F26Dot6(self.0.saturating_add(63) & !63)
+    }
+
+    /// The nearest whole number, with halves rounded up, as pixel
+    /// coordinates are rounded to the grid.
+    pub fn round(self) -> F26Dot6 {
+        F26Dot6(self.0.saturating_add(32) & !63)
+    }
+
+    /// `self` rounded to a whole number, as an integer.
+    pub fn to_int(self) -> i32 {
+        self.round().0 >> 6
+    }
+
+    pub fn abs(self) -> F26Dot6 {
+        F26Dot6(self.0.saturating_abs())
+    }
+}
+
+impl fmt::Debug for F26Dot6 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "F26Dot6({})", self.to_f32())
+    }
+}
+
+impl From<Fixed> for F26Dot6 {
+    fn from(value: Fixed) -> F26Dot6 {
+        F26Dot6(mul_div(value.to_bits(), 1, 1 << 10))
+    }
+}
+
+impl From<F26Dot6> for Fixed {
+    fn from(value: F26Dot6) -> Fixed {
+        Fixed::from_bits(value.0.saturating_mul(1 << 10))
+    }
+}
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed::from_bits(0);
+    pub const ONE: Fixed = Fixed::from_bits(1 << 16);
+
+    pub fn from_int(value: i32) -> Fixed {
+        Fixed::from_bits(value.saturating_mul(Fixed::ONE.to_bits()))
+    }
+
+    /// The largest whole number no greater than `self`.
+    pub fn floor(self) -> Fixed {
+        Fixed::from_bits(self.to_bits() & !0xFFFF)
+    }
+
+    /// The smallest whole number no less than `self`.
+    pub fn ceil(self) -> Fixed {
+        Fixed::from_bits(self.to_bits().saturating_add(0xFFFF) & !0xFFFF)
+    }
+
+    /// The nearest whole number, with halves rounded up.
+    pub fn round(self) -> Fixed {
+        Fixed::from_bits(self.to_bits().saturating_add(0x8000) & !0xFFFF)
+    }
+
+    /// `self` rounded to a whole number, as an integer.
+    pub fn to_int(self) -> i32 {
+        self.round().to_bits() >> 16
+    }
+}
+
+/// Implement the arithmetic operators for a fixed point type, through its
+/// `from_bits`, `to_bits` and `ONE`.
+macro_rules! impl_fixed_ops {
+    ($type:ident) => (
+        impl Add for $type {
+            type Output = $type;
+
+            fn add(self, other: $type) -> $type {
+                $type::from_bits(self.to_bits().saturating_add(other.to_bits()))
+            }
+        }
+
+        impl Sub for $type {
+            type Output = $type;
+
+            fn sub(self, other: $type) -> $type {
+                $type::from_bits(self.to_bits().saturating_sub(other.to_bits()))
+            }
+        }
+
+        impl Neg for $type {
+            type Output = $type;
+
+            fn neg(self) -> $type {
+                $type::from_bits(self.to_bits().saturating_neg())
+            }
+        }
+
+        impl Mul for $type {
+            type Output = $type;
+
+            fn mul(self, other: $type) -> $type {
+                $type::from_bits(mul_div(self.to_bits(), other.to_bits(), $type::ONE.to_bits()))
+            }
+        }
+
+        impl Div for $type {
+            type Output = $type;
+
+            fn div(self, other: $type) -> $type {
+                $type::from_bits(mul_div(self.to_bits(), $type::ONE.to_bits(), other.to_bits()))
+            }
+        }
+
+        impl AddAssign for $type {
+            fn add_assign(&mut self, other: $type) {
+                *self = *self + other;
+            }
+        }
+
+        impl SubAssign for $type {
+            fn sub_assign(&mut self, other: $type) {
+                *self = *self - other;
+            }
+        }
+    )
+}
+
+impl_fixed_ops!(F26Dot6);
+impl_fixed_ops!(Fixed);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_point_arithmetic() {
+        assert_eq!(mul_div(10, 3, 4), 8);
+        assert_eq!(mul_div(-10, 3, 4), -8);
+        assert_eq!(mul_div(5, 1, 2), 3);
+        assert_eq!(mul_div(-5, 1, 2), -3);
+        assert_eq!(mul_div(i32::MAX, 4, 2), i32::MAX);
+        assert_eq!((mul_div(-1, 1, 0), mul_div(0, 1, 0)), (i32::MIN, 0));
+
+        let (half, one_and_half) = (F26Dot6::from_bits(32), F26Dot6::from_f32(1.5));
+        assert_eq!(one_and_half * one_and_half, F26Dot6::from_f32(2.25));
+        assert_eq!(F26Dot6::ONE / F26Dot6::from_int(3), F26Dot6::from_bits(21));
+        assert_eq!(F26Dot6::from_int(2) / F26Dot6::from_int(3), F26Dot6::from_bits(43));
+        assert_eq!((one_and_half.floor(), one_and_half.ceil(), one_and_half.round()), (F26Dot6::ONE, F26Dot6::from_int(2), F26Dot6::from_int(2)));
+        assert_eq!((-half).round(), F26Dot6::ZERO);
+        assert_eq!((-one_and_half).floor().to_int(), -2);
+        assert_eq!(F26Dot6::from_font_units(333, F26Dot6::from_int(12), 1000), F26Dot6::from_bits(256));
+
+        let fixed = Fixed::from_bits(0x0001_8000);
+        assert_eq!(fixed * fixed, Fixed::from_bits(0x0002_4000));
+        assert_eq!(Fixed::ONE / Fixed::from_int(3), Fixed::from_bits(0x5555));
+        assert_eq!((fixed.floor(), fixed.ceil(), fixed.round().to_int()), (Fixed::from_int(1), Fixed::from_int(2), 2));
+        assert_eq!(F26Dot6::from(fixed), one_and_half);
+        assert_eq!(Fixed::from(one_and_half) - fixed, Fixed::ZERO);
+    }
+}
@@ -1,12 +1,80 @@
-use primitives::{Tag, Ignored, Array, Discarded};
-use decode::{StaticEncodeSize, EncodeSize, Decode, Decode1, DecodeRead, DecodeRead1};
+#![forbid(unsafe_code)]
+
+use primitives::{Tag, Array, Discarded, GlyphId};
+use decode::{StaticEncodeSize, EncodeSize, Decode, Decode1, Decode2};
 use error::{Error, Result};
+use table::TaggedTable;
+use table::maxp::{Maxp, Profile};
+use table::hhea::Hhea;
+use table::hmtx::{Hmtx, GlyphMetrics};
+use table::head::Head;
+use table::loca::Loca;
+use table::glyf::Glyf;
+#[cfg(feature = "layout")]
+use table::layout::{FeatureParams, Gsub, Gpos};
+#[cfg(feature = "layout")]
+use table::name::{Name, NameId};
+#[cfg(feature = "aat")]
+use table::feat::Feat;
+use table::post::{agl_character, Post};
+#[cfg(feature = "cff")]
+use table::cff::Cff;
+use table::os2::Os2;
+use table::cmap::{CharacterMap, Cmap, VariantGlyph};
+use glyph_key::{fnv1a, FNV_OFFSET_BASIS};
+use std::collections::{HashMap, HashSet};
 use std::marker::PhantomData;
+use std::sync::{Arc, OnceLock};
+use std::vec;
+
+#[cfg(test)]
+use test_fonts;
+
+/// The glyph count to use when `maxp` holds `maxp` and `loca` has entries
+/// for `loca` glyphs.  `maxp` is trusted unless it is unreadable, zero, or
+/// more than `loca` holds.
+pub(crate) fn corrected_num_glyphs(maxp: Result<u16>, loca: Option<u16>) -> Result<u16> {
+    match (maxp, loca) {
+        (Ok(count), Some(loca)) if count == 0 || count > loca => Ok(loca),
+        (Err(_), Some(loca)) => Ok(loca),
+        (maxp, _) => maxp,
+    }
+}
+
+/// The last of the first `num_glyphs + 1` offsets of `loca` in the long or
+/// short format, or `None` unless they ascend and stay within a `glyf`
+/// table of `glyf_len` bytes.
+fn loca_end(data: &[u8], long: bool, num_glyphs: usize, glyf_len: usize) -> Option<usize> {
+    let offset = |index: usize| if long {
+        u32::decode(data.get(index * 4..).unwrap_or(&[])).map(|offset| offset as usize)
+    } else {
+        u16::decode(data.get(index * 2..).unwrap_or(&[])).map(|offset| offset as usize * 2)
+    };
+
+    let mut last = 0;
+    for index in 0..=num_glyphs {
+        match offset(index) {
+            Ok(offset) if last <= offset && offset <= glyf_len => last = offset,
+            _ => return None,
+        }
+    }
+    Some(last)
+}
 
+/// The sfnt version at the start of a font file, which names the kind of
+/// glyph data the font carries.  Fonts do not always carry what their
+/// version says, so `Font::outline_format` checks the tables instead.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Version {
+    /// `OTTO`, for CFF or CFF2 outlines.
     OpenType,
+    /// `0x00010000`, or `true` in fonts for Apple platforms, for TrueType
+    /// outlines.
     TrueType,
+    /// `typ1`, for a PostScript Type 1 font wrapped in an sfnt by Apple.
+    Type1,
+    /// `0x00020000`, which some legacy sfnts with only bitmap glyphs use.
+    Bitmap,
 }
 
 static_size!(Version = 4);
@@ -14,30 +82,147 @@ static_size!(Version = 4);
 impl<'fnt> Decode<'fnt> for Version {
     fn decode(buffer: &[u8]) -> Result<Version> {
         const VERSION1: [u8; 4] = [0x00, 0x01, 0x00, 0x00];
+        const VERSION2: [u8; 4] = [0x00, 0x02, 0x00, 0x00];
         let tag = Tag::decode(buffer)?;
         match &tag.0 {
             b"OTTO" => Ok(Version::OpenType),
-            &VERSION1 | b"true" | b"typ1" => Ok(Version::TrueType),
+            &VERSION1 | b"true" => Ok(Version::TrueType),
+            b"typ1" => Ok(Version::Type1),
+            &VERSION2 => Ok(Version::Bitmap),
             b"ttcf" => Err(Error::TtcfUnsupported),
             _ => Err(Error::InvalidData),
         }
     }
 }
 
-#[derive(Decode, Debug)]
+impl Version {
+    /// The tag written for this version.  Fonts read with `true` are
+    /// written with `0x00010000`, which every platform accepts.
+    pub fn tag(&self) -> Tag {
+        match *self {
+            Version::OpenType => Tag(*b"OTTO"),
+            Version::TrueType => Tag([0x00, 0x01, 0x00, 0x00]),
+            Version::Type1 => Tag(*b"typ1"),
+            Version::Bitmap => Tag([0x00, 0x02, 0x00, 0x00]),
+        }
+    }
+}
+
+/// The kind of glyph data a font carries, judged from its tables.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutlineFormat {
+    /// Quadratic outlines in `glyf`.
+    TrueType,
+    /// Cubic outlines in `CFF `.
+    Cff,
+    /// Cubic outlines in `CFF2`.
+    Cff2,
+    /// A PostScript Type 1 font in `TYP1`, which this crate cannot outline.
+    Type1,
+    /// Bitmap or image glyphs only, in `EBDT`, `CBDT`, `bdat` or `sbix`.
+    Bitmap,
+    /// No glyph data which this crate knows of.
+    None,
+}
+
+#[derive(Decode, Debug, Copy, Clone)]
+#[allow(dead_code)]
 // #[DecodeDebug]
-pub struct OffsetTable<'fnt> {
+pub struct TableDirectory<'fnt> {
     __font: Discarded<&'fnt [u8]>,
     sfnt_version: Version,
     num_tables: Discarded<u16>,
-    search_range: Ignored<u16>,
-    entry_selector: Ignored<u16>,
-    range_shift: Ignored<u16>,
+    search_range: u16,
+    entry_selector: u16,
+    range_shift: u16,
     #[WithParam = "num_tables as usize"]
     tables: Array<'fnt, TableRecord>,
 }
 
-#[derive(Decode, StaticEncodeSize, Debug, PartialEq)]
+/// The fields of a table directory which help to binary search it.  They
+/// depend only on the number of tables.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SearchParams {
+    pub search_range: u16,
+    pub entry_selector: u16,
+    pub range_shift: u16,
+}
+
+impl SearchParams {
+    /// The parameters for a directory of `num_tables` records: the largest
+    /// power of two not above the count, its logarithm, and the remainder,
+    /// with both ranges in bytes.
+    pub fn for_tables(num_tables: u16) -> SearchParams {
+        let entry_selector = (16 - num_tables.max(1).leading_zeros() - 1) as u16;
+        let search_range = (1u16 << entry_selector).wrapping_mul(16);
+        SearchParams {
+            search_range,
+            entry_selector,
+            range_shift: num_tables.wrapping_mul(16).saturating_sub(search_range),
+        }
+    }
+}
+
+impl<'fnt> TableDirectory<'fnt> {
+    pub fn sfnt_version(&self) -> Version {
+        self.sfnt_version
+    }
+
+    /// The table records in the order they are stored in the directory,
+    /// which should be sorted by tag.
+    pub fn tables(&self) -> Array<'fnt, TableRecord> {
+        self.tables
+    }
+
+    /// The table records in the order their tables are stored in the file.
+    pub fn tables_by_offset(&self) -> Vec<TableRecord> {
+        let mut tables = self.tables.into_iter().collect::<Vec<_>>();
+        tables.sort_by_key(|record| (record.offset, record.length));
+        tables
+    }
+
+    /// The tables in the order they are stored in the file, each with the
+    /// gap or overlap before it, for tools which copy a font while keeping
+    /// its layout or compacting it.
+    pub fn spans(&self) -> TableSpans {
+        TableSpans {
+            records: self.tables_by_offset().into_iter(),
+            end: 12 + 16 * self.tables.len() as u64,
+        }
+    }
+
+    /// The search parameters stored in the directory, which may not match
+    /// `SearchParams::for_tables`.
+    pub fn search_params(&self) -> SearchParams {
+        SearchParams {
+            search_range: self.search_range,
+            entry_selector: self.entry_selector,
+            range_shift: self.range_shift,
+        }
+    }
+
+    /// Find the record of `tag` by binary search, as the directory is
+    /// sorted by tag.  A directory which is not sorted is scanned instead
+    /// when the search fails.
+    pub fn find(&self, tag: Tag) -> Option<TableRecord> {
+        let (mut low, mut high) = (0, self.tables.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            let record = self.tables.get(mid)?;
+            if record.tag.0 < tag.0 {
+                low = mid + 1;
+            } else if record.tag.0 > tag.0 {
+                high = mid;
+            } else {
+                return Some(record)
+            }
+        }
+
+        self.tables.into_iter().find(|record| record.tag == tag)
+    }
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
 pub struct TableRecord {
     pub tag: Tag,
     pub check_sum: u32,
@@ -45,15 +230,1125 @@ pub struct TableRecord {
     pub length: u32,
 }
 
+/// A table in file order, and how it sits against the tables before it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TableSpan {
+    pub record: TableRecord,
+    /// The bytes between the start of the table and the end of the tables
+    /// before it, or of the table directory, padded to four bytes.  It is
+    /// zero in a packed font.
+    pub gap: u32,
+    /// The bytes at the start of the table which tables before it cover
+    /// too, as when two tables share their data.
+    pub overlap: u32,
+}
+
+/// An iterator over the tables of a font in file order, from
+/// `TableDirectory::spans`.
+#[derive(Debug, Clone)]
+pub struct TableSpans {
+    records: vec::IntoIter<TableRecord>,
+    /// The furthest end of the tables so far, or the end of the directory.
+    end: u64,
+}
+
+impl Iterator for TableSpans {
+    type Item = TableSpan;
+
+    fn next(&mut self) -> Option<TableSpan> {
+        let record = self.records.next()?;
+        let (start, length) = (u64::from(record.offset), u64::from(record.length));
+        let padded_end = (self.end + 3) & !3;
+        let span = TableSpan {
+            record,
+            gap: start.saturating_sub(padded_end) as u32,
+            overlap: self.end.saturating_sub(start).min(length) as u32,
+        };
+        self.end = self.end.max(start + length);
+        Some(span)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.records.size_hint()
+    }
+}
+
+impl ExactSizeIterator for TableSpans {}
+
+/// The `head` table field which balances the checksum of the whole font.
+const CHECKSUM_ADJUSTMENT_OFFSET: usize = 8;
+
+/// The sum of `data` as big-endian 32-bit words, where `data` starts at
+/// `offset` within the font.  Bytes past the end are zero, as if padded.
+pub(crate) fn checksum_at(data: &[u8], offset: usize) -> u32 {
+    data.iter().enumerate().fold(0u32, |sum, (index, &byte)| {
+        let shift = 8 * (3 - (offset + index) % 4);
+        sum.wrapping_add((byte as u32) << shift)
+    })
+}
+
+impl TableRecord {
+    /// The checksum of `data`, the contents of this table.  For `head`,
+    /// the `checkSumAdjustment` field is treated as zero, as the record's
+    /// checksum requires.
+    pub fn compute_checksum(&self, data: &[u8]) -> u32 {
+        let sum = checksum_at(data, 0);
+        if self.tag != Head::tag() {
+            return sum
+        }
+
+        let end = data.len().min(CHECKSUM_ADJUSTMENT_OFFSET + 4);
+        let adjustment = data.get(CHECKSUM_ADJUSTMENT_OFFSET..end).unwrap_or(&[]);
+        sum.wrapping_sub(checksum_at(adjustment, CHECKSUM_ADJUSTMENT_OFFSET))
+    }
+}
+
+/// How strictly a font is checked against the specification.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Reject fonts which violate the specification, as a validator would.
+    Strict,
+    /// Tolerate violations which rasterizers accept, repairing them where
+    /// possible.  Unsorted `cmap` subtables are searched linearly,
+    /// overlapping tables are read as they are, and a glyph count in
+    /// `maxp` which `loca` or `hmtx` cannot hold, or a `loca` format
+    /// which `head` declares wrongly, is corrected.
+    #[default]
+    Permissive,
+}
+
+/// `head.magicNumber`.
+const HEAD_MAGIC_NUMBER: u32 = 0x5F0F_3CF5;
+/// Bit 15 of `head.flags` is reserved.
+pub(crate) const HEAD_RESERVED_FLAGS: u16 = 0x8000;
+/// Bits 10 to 15 of OS/2 `fsSelection` are reserved.
+pub(crate) const FS_SELECTION_RESERVED: u16 = 0xFC00;
+
+/// A single font face backed by the bytes of an sfnt file.  Tables are
+/// located through the table directory and decoded lazily when requested.
+///
+/// A font and the tables decoded from it only borrow the font data, so
+/// they are `Send` and `Sync`, and can be shared between threads as they
+/// are.  Tables which hold nothing but borrowed data are also `Copy`.
+#[derive(Debug, Clone)]
+pub struct Font<'fnt> {
+    buffer: &'fnt [u8],
+    directory: TableDirectory<'fnt>,
+    mode: ParseMode,
+    /// The glyph name index, built the first time it is needed.
+    glyph_names: OnceLock<Result<Arc<GlyphNames>>>,
+}
+
+/// The names of the glyphs of a font, indexed both by glyph and by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlyphNames {
+    /// The name of each glyph, in glyph order.  Glyphs without a name have
+    /// an empty one.
+    names: Vec<String>,
+    /// The named glyphs, sorted by name and then by glyph.
+    sorted: Vec<GlyphId>,
+    /// The characters which Adobe Glyph List names stand for, sorted, each
+    /// with the first glyph so named.
+    characters: Vec<(char, GlyphId)>,
+}
+
+impl GlyphNames {
+    /// Index `names`, the name of each glyph in glyph order.
+    pub fn new(names: Vec<String>) -> GlyphNames {
+        let mut sorted = (0..names.len())
+            .filter(|&index| !names[index].is_empty())
+            .map(|index| GlyphId(index as u16))
+            .collect::<Vec<_>>();
+        sorted.sort_by(|a, b| names[a.0 as usize].cmp(&names[b.0 as usize]));
+        let mut characters = names.iter()
+            .enumerate()
+            .filter_map(|(index, name)| agl_character(name).map(|character| (character, GlyphId(index as u16))))
+            .collect::<Vec<_>>();
+        characters.sort();
+        characters.dedup_by_key(|&mut (character, _)| character);
+        GlyphNames { names, sorted, characters }
+    }
+
+    /// The name of `glyph`, or `None` if it has none.
+    pub fn name(&self, glyph: GlyphId) -> Option<&str> {
+        self.names.get(glyph.0 as usize).map(String::as_str).filter(|name| !name.is_empty())
+    }
+
+    /// The glyph named `name`.  When several glyphs share a name, the
+    /// first of them is returned.
+    pub fn glyph(&self, name: &str) -> Option<GlyphId> {
+        let index = self.sorted.partition_point(|glyph| self.names[glyph.0 as usize].as_str() < name);
+        self.sorted.get(index).cloned().filter(|glyph| self.names[glyph.0 as usize] == name)
+    }
+
+    /// The first glyph whose name stands for `character` in the Adobe
+    /// Glyph List conventions, such as `Aacute` or `uni00C1`.
+    pub fn character_glyph(&self, character: char) -> Option<GlyphId> {
+        let index = self.characters.binary_search_by_key(&character, |&(character, _)| character).ok()?;
+        Some(self.characters[index].1)
+    }
+
+    /// The number of glyphs, named or not.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+impl<'fnt> Decode<'fnt> for Font<'fnt> {
+    /// Decode a font in `ParseMode::Permissive`.
+    fn decode(buffer: &'fnt [u8]) -> Result<Font<'fnt>> {
+        Font::parse(buffer, ParseMode::Permissive)
+    }
+}
+
+impl<'fnt> Font<'fnt> {
+    /// Decode the font in `buffer`.  In strict mode, the table directory
+    /// must be sorted with every table in bounds and no two overlapping,
+    /// reserved bits of `head` and OS/2 must be clear, and the Unicode
+    /// `cmap` subtable must be sorted; any violation is `InvalidData`.
+    pub fn parse(buffer: &'fnt [u8], mode: ParseMode) -> Result<Font<'fnt>> {
+        let font = Font {
+            buffer,
+            directory: TableDirectory::decode(buffer, buffer)?,
+            mode,
+            glyph_names: OnceLock::new(),
+        };
+
+        if mode == ParseMode::Strict {
+            font.check_strict()?;
+        }
+
+        Ok(font)
+    }
+
+    /// The sfnt version of the font file.
+    pub fn sfnt_version(&self) -> Version {
+        self.directory.sfnt_version()
+    }
+
+    /// The kind of glyph data the font carries.  This is judged from the
+    /// tables present rather than the sfnt version, which is often wrong:
+    /// fonts with `CFF ` outlines under the TrueType version are common.
+    /// A font with both outlines and bitmaps has an outline format.
+    pub fn outline_format(&self) -> OutlineFormat {
+        let has = |tag: &[u8; 4]| self.table_record(Tag(*tag)).is_some();
+        if has(b"glyf") {
+            OutlineFormat::TrueType
+        } else if has(b"CFF ") {
+            OutlineFormat::Cff
+        } else if has(b"CFF2") {
+            OutlineFormat::Cff2
+        } else if has(b"TYP1") {
+            OutlineFormat::Type1
+        } else if has(b"EBDT") || has(b"CBDT") || has(b"bdat") || has(b"sbix") {
+            OutlineFormat::Bitmap
+        } else {
+            OutlineFormat::None
+        }
+    }
+
+    pub fn mode(&self) -> ParseMode {
+        self.mode
+    }
+
+    /// The bytes of the whole font file.
+    pub fn data(&self) -> &'fnt [u8] {
+        self.buffer
+    }
+
+    fn check_strict(&self) -> Result<()> {
+        let mut ranges = Vec::new();
+        let mut previous: Option<Tag> = None;
+        for record in self.directory.tables {
+            if previous.is_some_and(|previous| previous.0 >= record.tag.0) {
+                return Err(Error::InvalidData)
+            }
+            previous = Some(record.tag);
+
+            let start = record.offset as usize;
+            let end = start + record.length as usize;
+            if end > self.buffer.len() {
+                return Err(Error::InvalidData)
+            }
+            ranges.push((start, end));
+        }
+
+        ranges.sort();
+        if ranges.windows(2).any(|pair| pair[0].1 > pair[1].0) {
+            return Err(Error::InvalidData)
+        }
+
+        let head = self.table::<Head>()?;
+        if head.magic_number != HEAD_MAGIC_NUMBER || head.flags & HEAD_RESERVED_FLAGS != 0 {
+            return Err(Error::InvalidData)
+        }
+
+        if let Some(os2) = self.optional_table::<Os2>()? {
+            if os2.fs_selection & FS_SELECTION_RESERVED != 0 {
+                return Err(Error::InvalidData)
+            }
+        }
+
+        if let Some(cmap) = self.optional_table::<Cmap>()? {
+            if cmap.unicode_subtable()?.is_some_and(|subtable| !subtable.is_sorted()) {
+                return Err(Error::InvalidData)
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn table_directory(&self) -> &TableDirectory<'fnt> {
+        &self.directory
+    }
+
+    /// Find the table record for `tag`, if the font contains that table.
+    pub fn table_record(&self, tag: Tag) -> Option<TableRecord> {
+        self.directory.find(tag)
+    }
+
+    /// The raw bytes of the table identified by `tag`.
+    pub fn table_data(&self, tag: Tag) -> Result<&'fnt [u8]> {
+        let record = self.table_record(tag).ok_or(Error::MissingTable)?;
+        let start = record.offset as usize;
+        let end = start.checked_add(record.length as usize).ok_or(Error::InvalidData)?;
+        self.buffer.get(start..end).ok_or(Error::UnexpectedEof)
+    }
+
+    /// The value `head.checkSumAdjustment` should hold for the font as it
+    /// is now, so that the checksum of the whole file is `0xB1B0AFBA`.
+    /// This lets a table be patched in place without rewriting the font.
+    pub fn compute_checksum_adjustment(&self) -> Result<u32> {
+        let head = self.table_record(Head::tag()).ok_or(Error::MissingTable)?;
+        let start = head.offset as usize + CHECKSUM_ADJUSTMENT_OFFSET;
+        let adjustment = self.buffer.get(start..start + 4).ok_or(Error::UnexpectedEof)?;
+
+        let sum = checksum_at(self.buffer, 0).wrapping_sub(checksum_at(adjustment, start));
+        Ok(0xB1B0_AFBAu32.wrapping_sub(sum))
+    }
+
+    /// A digest of the tables of the font which is stable between runs,
+    /// builds and platforms, so that caches and font services can tell the
+    /// same face apart from others under any file name.
+    ///
+    /// It covers the tag, length and checksum of each table, in tag order.
+    /// Checksums are computed from the table data rather than read from
+    /// the directory, so the order and padding of the tables, a stale
+    /// directory checksum and `head.checkSumAdjustment` do not change it.
+    pub fn fingerprint(&self) -> Result<u64> {
+        let mut records = self.directory.tables().into_iter().collect::<Vec<_>>();
+        records.sort_by_key(|record| record.tag.0);
+
+        let mut hash = FNV_OFFSET_BASIS;
+        for record in records {
+            let check_sum = record.compute_checksum(self.table_data(record.tag)?);
+            hash = fnv1a(hash, &record.tag.0);
+            hash = fnv1a(hash, &record.length.to_be_bytes());
+            hash = fnv1a(hash, &check_sum.to_be_bytes());
+        }
+        Ok(hash)
+    }
+
+    /// Decode a table which requires no additional parameters.
+    pub fn table<T>(&self) -> Result<T>
+        where T: TaggedTable<'fnt> + Decode<'fnt>
+    {
+        T::decode(self.table_data(T::tag())?)
+    }
+
+    /// Decode a table which the font is not required to have.
+    pub fn optional_table<T>(&self) -> Result<Option<T>>
+        where T: TaggedTable<'fnt> + Decode<'fnt>
+    {
+        match self.table_record(T::tag()) {
+            Some(_) => self.table().map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The capacity hints of `maxp`, or `None` if the font has no version
+    /// 1 table, as is usual for fonts with CFF outlines.
+    pub fn profile(&self) -> Result<Option<Profile>> {
+        Ok(self.table::<Maxp>()?.profile())
+    }
+
+    /// The number of glyphs in the font, from `maxp`.
+    ///
+    /// Some broken fonts have a `maxp` which is empty, claims no glyphs or
+    /// claims more glyphs than `loca` has entries for.  In permissive mode
+    /// the count `loca` implies is used instead, as rasterizers do.
+    pub fn num_glyphs(&self) -> Result<u16> {
+        let maxp = self.table::<Maxp>().map(|maxp| maxp.get_num_glyphs());
+        match self.mode {
+            ParseMode::Strict => maxp,
+            ParseMode::Permissive => corrected_num_glyphs(maxp, self.loca_num_glyphs()),
+        }
+    }
+
+    /// The number of glyphs the length of `loca` implies, or `None` if the
+    /// font has no `loca` or its format cannot be read.
+    pub(crate) fn loca_num_glyphs(&self) -> Option<u16> {
+        let long = self.loca_is_long().ok()?;
+        let entries = self.table_data(Loca::tag()).ok()?.len() / if long { 4 } else { 2 };
+        Some(entries.saturating_sub(1).min(0xFFFF) as u16)
+    }
+
+    /// Decode the `hmtx` table, which depends on both `hhea` and the glyph
+    /// count.  In permissive mode, glyphs past the end of a short table
+    /// are left out rather than failing the whole table.
+    pub fn hmtx(&self) -> Result<Hmtx<'fnt>> {
+        let hhea = self.table::<Hhea>()?;
+        let data = self.table_data(Hmtx::tag())?;
+        let num_h_metrics = hhea.number_of_h_metrics as usize;
+        let mut num_glyphs = self.num_glyphs()? as usize;
+        if self.mode == ParseMode::Permissive {
+            num_glyphs = num_glyphs.min(num_h_metrics + data.len().saturating_sub(num_h_metrics * 4) / 2);
+        }
+        Hmtx::decode(data, num_h_metrics, num_glyphs)
+    }
+
+    /// Decode the `loca` table, which depends on both `head` and the glyph
+    /// count.
+    pub fn loca(&self) -> Result<Loca<'fnt>> {
+        Loca::decode(self.table_data(Loca::tag())?, self.loca_is_long()?, self.num_glyphs()? as usize)
+    }
+
+    /// Whether `loca` stores long offsets.
+    ///
+    /// Some generated fonts declare the wrong format in
+    /// `head.indexToLocFormat`.  In permissive mode, a table whose length
+    /// suits the other format is read in the other format if its offsets
+    /// ascend within `glyf` in it, and either do not in the declared
+    /// format or only end at the end of `glyf` in the other.
+    pub(crate) fn loca_is_long(&self) -> Result<bool> {
+        let declared = self.table::<Head>()?.index_to_loc_format != 0;
+        if self.mode == ParseMode::Strict {
+            return Ok(declared)
+        }
+
+        let (data, glyf, num_glyphs) = match (self.table_data(Loca::tag()), self.table_data(Glyf::tag()), self.table::<Maxp>()) {
+            (Ok(data), Ok(glyf), Ok(maxp)) => (data, glyf.len(), maxp.get_num_glyphs() as usize),
+            _ => return Ok(declared),
+        };
+        // Long offsets take twice the room of short ones, so only a table
+        // too short for long offsets, or long enough for them, is suspect.
+        if (data.len() < (num_glyphs + 1) * 4) != declared {
+            return Ok(declared)
+        }
+        match (loca_end(data, declared, num_glyphs, glyf), loca_end(data, !declared, num_glyphs, glyf)) {
+            (None, Some(_)) => Ok(!declared),
+            (Some(end), Some(other)) if end != glyf && other == glyf => Ok(!declared),
+            _ => Ok(declared),
+        }
+    }
+
+    /// The glyph which `character` maps to through the preferred Unicode
+    /// subtable of `cmap`, or `None` if the font does not map it.  Symbol
+    /// fonts without a Unicode subtable map characters up to `U+00FF` as
+    /// single byte codes, and others by their code point.
+    ///
+    /// In permissive mode, a font whose `cmap` is missing, cannot be read
+    /// or has neither subtable maps characters through the Adobe Glyph
+    /// List names of its glyphs, from `glyph_names`, as old broken fonts
+    /// are still displayed by other engines.
+    pub fn glyph_index(&self, character: char) -> Result<Option<GlyphId>> {
+        match self.cmap_glyph_index(character) {
+            Ok(Some(glyph)) => Ok(glyph),
+            unusable => match self.named_glyph_index(character) {
+                Some(glyph) => Ok(glyph),
+                None => unusable.map(|_| None),
+            },
+        }
+    }
+
+    /// The glyph of `character` in `cmap`, or `None` if the table has
+    /// neither a Unicode nor a symbol subtable.
+    fn cmap_glyph_index(&self, character: char) -> Result<Option<Option<GlyphId>>> {
+        Ok(self.character_map()?.map(|map| map.glyph_id(character)))
+    }
+
+    /// The subtable of `cmap` which `glyph_index` maps characters through,
+    /// or `None` if the table has neither a Unicode nor a symbol subtable.
+    pub(crate) fn character_map(&self) -> Result<Option<CharacterMap<'fnt>>> {
+        let cmap = self.table::<Cmap>()?;
+        if let Some(subtable) = cmap.unicode_subtable()? {
+            return Ok(Some(CharacterMap::Unicode(subtable)))
+        }
+
+        Ok(cmap.symbol_subtable()?.map(CharacterMap::Symbol))
+    }
+
+    /// The first glyph whose name, as `glyph_names` resolves it, stands
+    /// for `character`, or `None` in strict mode or if no glyph name
+    /// stands for a character.
+    pub(crate) fn named_glyph_index(&self, character: char) -> Option<Option<GlyphId>> {
+        if self.mode == ParseMode::Strict {
+            return None
+        }
+        let names = self.glyph_names().ok()?;
+        if names.characters.is_empty() {
+            return None
+        }
+
+        Some(names.character_glyph(character))
+    }
+
+    /// The glyph of `character` followed by the variation selector
+    /// `selector`, from the format 14 subtable of `cmap`, or `None` if the
+    /// font does not support the sequence.
+    pub fn glyph_variant(&self, character: char, selector: char) -> Result<Option<GlyphId>> {
+        let sequences = match self.table::<Cmap>()?.variation_sequences()? {
+            Some(sequences) => sequences,
+            None => return Ok(None),
+        };
+
+        match sequences.variant(character as u32, selector as u32)? {
+            Some(VariantGlyph::Default) => self.glyph_index(character),
+            Some(VariantGlyph::Variant(glyph)) => Ok(Some(glyph)),
+            None => Ok(None),
+        }
+    }
+
+    /// The name of `glyph`, or `None` if the font has no such glyph.
+    ///
+    /// Names are resolved for each glyph in this order:
+    ///
+    /// 1. `post`, for versions 1, 2 and 2.5, which name glyphs;
+    /// 2. the charset of a name-keyed `CFF ` table, as fonts with version
+    ///    3 of `post` need;
+    /// 3. a synthesized name: `.notdef` for glyph 0, `uniXXXX` or
+    ///    `uXXXXX` after the lowest character `cmap` maps to the glyph,
+    ///    and `glyphNNNNN` after the glyph id otherwise.
+    ///
+    /// A synthesized name which another glyph already has is followed by
+    /// `#1`, `#2` and so on, so that every name is unique.  The names are
+    /// resolved once for the whole font, by `glyph_names`.
+    pub fn glyph_name(&self, glyph: GlyphId) -> Result<Option<String>> {
+        Ok(self.glyph_names()?.name(glyph).map(str::to_string))
+    }
+
+    /// The names of every glyph, resolved as `glyph_name` describes.  The
+    /// index is built on the first call and kept, so later lookups, by
+    /// glyph or by name, are cheap.
+    pub fn glyph_names(&self) -> Result<Arc<GlyphNames>> {
+        self.glyph_names.get_or_init(|| self.build_glyph_names().map(Arc::new)).clone()
+    }
+
+    /// The glyph named `name`, such as `uni0410`.
+    pub fn gid_by_name(&self, name: &str) -> Result<Option<GlyphId>> {
+        Ok(self.glyph_names()?.glyph(name))
+    }
+
+    fn build_glyph_names(&self) -> Result<GlyphNames> {
+        let num_glyphs = self.num_glyphs()? as usize;
+        let mut names = match self.optional_table::<Post>()? {
+            Some(post) => post.glyph_order()?,
+            None => Vec::new(),
+        };
+        names.resize(num_glyphs, String::new());
+
+        #[cfg(feature = "cff")]
+        {
+            if let Some(cff) = self.optional_table::<Cff>()? {
+                for (glyph, name) in names.iter_mut().enumerate().filter(|(_, name)| name.is_empty()) {
+                    *name = cff.glyph_name(GlyphId(glyph as u16)).unwrap_or_default().to_string();
+                }
+            }
+        }
+
+        if names.iter().any(String::is_empty) {
+            // Synthesis is a last resort, so a `cmap` which cannot be read
+            // only costs the names it would give.
+            let mut characters = HashMap::new();
+            let cmap = self.optional_table::<Cmap>().ok().flatten();
+            if let Some(subtable) = cmap.and_then(|cmap| cmap.unicode_subtable().ok().flatten()) {
+                for (code, glyph) in subtable.mappings() {
+                    let lowest = characters.entry(glyph).or_insert(code);
+                    *lowest = code.min(*lowest);
+                }
+            }
+
+            let mut taken = names.iter().filter(|name| !name.is_empty()).cloned().collect::<HashSet<_>>();
+            for (glyph, name) in names.iter_mut().enumerate().filter(|(_, name)| name.is_empty()) {
+                let synthesized = match characters.get(&GlyphId(glyph as u16)) {
+                    _ if glyph == 0 => ".notdef".to_string(),
+                    Some(&code) if code <= 0xFFFF => format!("uni{:04X}", code),
+                    Some(&code) => format!("u{:X}", code),
+                    None => format!("glyph{:05}", glyph),
+                };
+                let unique = (0..)
+                    .map(|suffix| if suffix == 0 { synthesized.clone() } else { format!("{}#{}", synthesized, suffix) })
+                    .find(|candidate| !taken.contains(candidate))
+                    .unwrap_or(synthesized);
+                taken.insert(unique.clone());
+                *name = unique;
+            }
+        }
+
+        Ok(GlyphNames::new(names))
+    }
+
+    /// Whether the font is monospaced.  This is true when either `post` or
+    /// the OS/2 PANOSE classification declare it, or when every glyph with
+    /// an advance uses the nominal advance or twice it, as dual-width CJK
+    /// fonts do.
+    pub fn is_monospaced(&self) -> Result<bool> {
+        if self.table::<Post>()?.is_fixed_pitch() {
+            return Ok(true)
+        }
+
+        if let Some(os2) = self.optional_table::<Os2>()? {
+            if os2.is_panose_monospaced() {
+                return Ok(true)
+            }
+        }
+
+        let nominal = match self.nominal_advance()? {
+            Some(nominal) => nominal,
+            None => return Ok(false),
+        };
+
+        let hmtx = self.hmtx()?;
+        let monospaced = hmtx.advances()
+            .all(|advance| advance == 0 || advance == nominal || u32::from(advance) == u32::from(nominal) * 2);
+        Ok(monospaced)
+    }
+
+    /// The width of a single cell for terminal-style layout: the most common
+    /// non-zero advance, or half of it when the font also has glyphs of half
+    /// that width.  This is `None` when no glyph has an advance.
+    pub fn nominal_advance(&self) -> Result<Option<u16>> {
+        let hmtx = self.hmtx()?;
+        let mut counts = HashMap::new();
+        for advance in hmtx.advances().filter(|&advance| advance != 0) {
+            *counts.entry(advance).or_insert(0) += 1;
+        }
+
+        // Prefer the smaller advance when counts are tied, for determinism.
+        let most_common = counts.iter()
+            .max_by_key(|&(&advance, &count)| (count, ::std::cmp::Reverse(advance)))
+            .map(|(&advance, _)| advance);
+
+        Ok(most_common.map(|advance| {
+            if advance % 2 == 0 && counts.contains_key(&(advance / 2)) {
+                advance / 2
+            } else {
+                advance
+            }
+        }))
+    }
+
+    /// The layout features supported by the font, from `GSUB`, `GPOS` and
+    /// the AAT `feat` table.  The tags are sorted and deduplicated.
+    pub fn features(&self) -> Result<Vec<Tag>> {
+        let mut features = Vec::new();
+        #[cfg(feature = "layout")]
+        {
+            if let Some(gsub) = self.optional_table::<Gsub>()? {
+                features.extend(gsub.features()?.tags());
+            }
+            if let Some(gpos) = self.optional_table::<Gpos>()? {
+                features.extend(gpos.features()?.tags());
+            }
+        }
+        #[cfg(feature = "aat")]
+        {
+            if let Some(feat) = self.optional_table::<Feat>()? {
+                features.extend(feat.opentype_features()?);
+            }
+        }
+
+        features.sort();
+        features.dedup();
+        Ok(features)
+    }
+
+    /// The parameters of the first feature `tag` of `GSUB` or `GPOS` which
+    /// has any, such as the names of a stylistic set.
+    #[cfg(feature = "layout")]
+    pub fn feature_params(&self, tag: Tag) -> Result<Option<FeatureParams>> {
+        let tables = [self.optional_table::<Gsub>()?.map(|gsub| *gsub), self.optional_table::<Gpos>()?.map(|gpos| *gpos)];
+        for table in tables.iter().flatten() {
+            for (index, record) in table.features()?.records().into_iter().enumerate() {
+                if record.tag != tag {
+                    continue
+                }
+                if let Some(params) = table.feature_params(index)? {
+                    return Ok(Some(params))
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// The name a user interface shows for the feature `tag`, which the
+    /// font gives for stylistic sets and character variants, or `None`.
+    #[cfg(feature = "layout")]
+    pub fn feature_ui_name(&self, tag: Tag) -> Result<Option<String>> {
+        let name_id = match self.feature_params(tag)? {
+            Some(FeatureParams::StylisticSet { ui_name_id }) => ui_name_id,
+            Some(FeatureParams::CharacterVariant { label_name_id, .. }) => label_name_id,
+            _ => return Ok(None),
+        };
+        match (name_id, self.optional_table::<Name>()?) {
+            (0, _) | (_, None) => Ok(None),
+            (_, Some(name)) => Ok(name.find(NameId::from(name_id))),
+        }
+    }
+
+    /// The scripts supported by the font's `GSUB` and `GPOS` tables.  The
+    /// tags are sorted and deduplicated.
+    pub fn scripts(&self) -> Result<Vec<Tag>> {
+        let mut scripts = Vec::new();
+        #[cfg(feature = "layout")]
+        {
+            if let Some(gsub) = self.optional_table::<Gsub>()? {
+                scripts.extend(gsub.scripts()?.tags());
+            }
+            if let Some(gpos) = self.optional_table::<Gpos>()? {
+                scripts.extend(gpos.scripts()?.tags());
+            }
+        }
+
+        scripts.sort();
+        scripts.dedup();
+        Ok(scripts)
+    }
+
+    /// The horizontal metrics for each of `glyphs`.  The tables are only
+    /// decoded once, which makes this cheaper than repeated single lookups
+    /// when the metrics of many glyphs are needed, such as a PDF widths array.
+    pub fn metrics_for_glyphs(&self, glyphs: &[GlyphId]) -> Result<Vec<GlyphMetrics>> {
+        let hmtx = self.hmtx()?;
+        glyphs.iter()
+            .map(|&glyph| hmtx.metrics(glyph).ok_or(Error::InvalidGlyphId))
+            .collect()
+    }
+}
+
 #[test]
 fn try() {
     let data = open_file!("data/DroidSerif.ttf");
-    let font = OffsetTable::decode(&data, &data).expect("failed to read offset table");
-    println!("{:?}", font);
+    let font = TableDirectory::decode(&data, &data).expect("failed to read table directory");
+
+    assert_eq!(font.sfnt_version(), Version::TrueType);
+    assert_eq!(font.tables().len(), 18);
+    for tbl in font.tables() {
+        assert!((tbl.offset + tbl.length) as usize <= data.len());
+    }
+}
+
+#[test]
+fn sfnt_versions() {
+    let data = open_file!("data/DroidSerif.ttf");
+    let font = Font::decode(&data).expect("failed to read font");
+    assert_eq!(font.sfnt_version(), Version::TrueType);
+    assert_eq!(font.outline_format(), OutlineFormat::TrueType);
+
+    let data = open_file!("data/SourceSansPro/SourceSansPro-Regular.otf");
+    let font = Font::decode(&data).expect("failed to read font");
+    assert_eq!(font.sfnt_version(), Version::OpenType);
+    assert_eq!(font.outline_format(), OutlineFormat::Cff);
+
+    for &(tag, version) in &[(*b"true", Version::TrueType), (*b"typ1", Version::Type1), ([0, 2, 0, 0], Version::Bitmap)] {
+        assert_eq!(Version::decode(&tag), Ok(version));
+    }
+    assert_eq!(Version::decode(b"ttcf"), Err(Error::TtcfUnsupported));
+    assert_eq!(Version::decode(&[0, 3, 0, 0]), Err(Error::InvalidData));
+    assert_eq!(Version::decode(&Version::Bitmap.tag().0), Ok(Version::Bitmap));
+
+    // A bitmap font under version 2, and a wrapped Type 1 font under the
+    // TrueType version.
+    let mut data = test_fonts::minimal_font(false).without(b"glyf").without(b"loca").table(b"EBDT", vec![0, 2, 0, 0]).build();
+    data[..4].copy_from_slice(&[0, 2, 0, 0]);
+    let font = Font::decode(&data).expect("failed to read font");
+    assert_eq!((font.sfnt_version(), font.outline_format()), (Version::Bitmap, OutlineFormat::Bitmap));
+
+    let data = test_fonts::minimal_font(false).without(b"glyf").without(b"loca").table(b"TYP1", vec![0; 4]).build();
+    let font = Font::decode(&data).expect("failed to read font");
+    assert_eq!((font.sfnt_version(), font.outline_format()), (Version::TrueType, OutlineFormat::Type1));
+}
+
+#[test]
+fn profile() {
+    let data = open_file!("data/DroidSerif.ttf");
+    let font = Font::decode(&data).expect("failed to read font");
+    let maxp = match font.table::<Maxp>() {
+        Ok(Maxp::Version1(maxp)) => maxp,
+        maxp => panic!("unexpected maxp {:?}", maxp),
+    };
+    let profile = font.profile().unwrap().expect("missing profile");
+    assert_eq!(profile.max_points, maxp.max_points.max(maxp.max_composite_points));
+    assert_eq!(profile.max_component_depth, maxp.max_component_depth);
+    assert_eq!(profile.max_stack_elements, maxp.max_stack_elements);
+
+    let data = open_file!("data/SourceSansPro/SourceSansPro-Regular.otf");
+    assert_eq!(Font::decode(&data).unwrap().profile(), Ok(None));
+}
+
+#[test]
+fn table_directory() {
+    let data = open_file!("data/DroidSerif.ttf");
+    let directory = TableDirectory::decode(&data, &data).expect("failed to read table directory");
+    assert_eq!(directory.search_params(), SearchParams::for_tables(18));
+    assert_eq!(SearchParams::for_tables(18), SearchParams { search_range: 256, entry_selector: 4, range_shift: 32 });
+    assert_eq!(SearchParams::for_tables(0), SearchParams { search_range: 16, entry_selector: 0, range_shift: 0 });
+
+    for record in directory.tables() {
+        assert_eq!(directory.find(record.tag), Some(record));
+    }
+    assert_eq!(directory.find(Tag::new(*b"CFF ")), None);
+    let offsets = directory.tables_by_offset().iter().map(|record| record.offset).collect::<Vec<_>>();
+    assert!(offsets.windows(2).all(|pair| pair[0] < pair[1]));
+
+    // Tables are still found when the directory is out of order.
+    let mut data = test_fonts::minimal_font(false).build();
+    let records = data[12..12 + 16 * 6].to_vec();
+    data[12..12 + 16 * 6].copy_from_slice(&[&records[16 * 3..], &records[..16 * 3]].concat());
+    let font = Font::decode(&data).unwrap();
+    for tag in &[b"glyf", b"head", b"hhea", b"hmtx", b"loca", b"maxp"] {
+        assert_eq!(font.table_record(Tag::new(**tag)).map(|record| record.tag), Some(Tag::new(**tag)));
+    }
+}
+
+#[test]
+fn glyph_metrics() {
+    let data = open_file!("data/DroidSerif.ttf");
+    let font = Font::decode(&data).expect("failed to read font");
+
+    let hmtx = font.hmtx().expect("failed to read hmtx");
+    let advances = hmtx.advances().collect::<Vec<_>>();
+    assert_eq!(advances.len(), hmtx.num_glyphs());
+
+    let glyphs = (0..advances.len() as u16).map(GlyphId).collect::<Vec<_>>();
+    let metrics = font.metrics_for_glyphs(&glyphs).expect("failed to read metrics");
+    for (metric, &advance) in metrics.iter().zip(&advances) {
+        assert_eq!(metric.advance_width, advance);
+    }
+
+    let out_of_range = GlyphId(advances.len() as u16);
+    assert_eq!(font.metrics_for_glyphs(&[out_of_range]), Err(Error::InvalidGlyphId));
+}
+
+#[test]
+fn malformed_tables() {
+
+    let data = test_fonts::minimal_font(false).without(b"hhea").build();
+    let font = Font::decode(&data).expect("failed to read font");
+    assert_eq!(font.hmtx().err(), Some(Error::MissingTable));
+    assert_eq!(font.optional_table::<Hhea>(), Ok(None));
+
+    // A table record which extends past the end of the file.  The 6 byte
+    // `maxp` table sorts last and is followed by two bytes of padding.
+    let mut data = test_fonts::minimal_font(false).build();
+    let end = data.len() - 4;
+    data.truncate(end);
+    let font = Font::decode(&data).expect("failed to read font");
+    assert_eq!(font.table_data(Tag(*b"maxp")).err(), Some(Error::UnexpectedEof));
+
+    // A directory which claims more tables than are present.
+    assert_eq!(Font::decode(&data[..40]).err(), Some(Error::UnexpectedEof));
+}
+
+#[test]
+fn parse_modes() {
+    let data = open_file!("data/DroidSerif.ttf");
+    let font = Font::parse(&data, ParseMode::Strict).expect("failed to read font strictly");
+    assert_eq!(font.mode(), ParseMode::Strict);
+    assert_eq!(Font::decode(&data).unwrap().mode(), ParseMode::Permissive);
+
+    // Groups of a format 12 subtable out of order, which a permissive
+    // font still searches.
+    let mut cmap = test_fonts::cmap(&[('A', 1), ('B', 2)]);
+    let second = cmap[40..52].to_vec();
+    cmap.copy_within(28..40, 40);
+    cmap[28..40].copy_from_slice(&second);
+    let data = test_fonts::minimal_font(false).table(b"cmap", cmap).build();
+    assert_eq!(Font::parse(&data, ParseMode::Strict).err(), Some(Error::InvalidData));
+    let font = Font::parse(&data, ParseMode::Permissive).expect("failed to read font");
+    assert_eq!(font.glyph_index('A'), Ok(Some(GlyphId(1))));
+    assert_eq!(font.glyph_index('B'), Ok(Some(GlyphId(2))));
+
+    // A reserved `head` flag.
+    let mut data = test_fonts::minimal_font(false).build();
+    let head = Font::decode(&data).unwrap().table_record(Head::tag()).unwrap().offset as usize;
+    data[head + 16] |= 0x80;
+    assert_eq!(Font::parse(&data, ParseMode::Strict).err(), Some(Error::InvalidData));
+    assert!(Font::parse(&data, ParseMode::Permissive).is_ok());
+
+    // Two records pointing into the same bytes.
+    let mut data = test_fonts::minimal_font(false).build();
+    let hhea = Font::decode(&data).unwrap().table_record(Hhea::tag()).unwrap().offset;
+    data[12 + 8..12 + 12].copy_from_slice(&hhea.to_be_bytes());
+    assert_eq!(Font::parse(&data, ParseMode::Strict).err(), Some(Error::InvalidData));
+    assert!(Font::parse(&data, ParseMode::Permissive).is_ok());
+}
+
+#[test]
+fn glyph_names_without_cmap() {
+    let post = test_fonts::post(&[".notdef", "uni0041.alt", "eacute"]);
+    let data = test_fonts::minimal_font(false).table(b"post", post).build();
+    let font = Font::parse(&data, ParseMode::Permissive).unwrap();
+    assert_eq!(font.glyph_index('A'), Ok(Some(GlyphId(1))));
+    assert_eq!(font.glyph_index('\u{E9}'), Ok(Some(GlyphId(2))));
+    assert_eq!(font.glyph_index('B'), Ok(None));
+    // The names are resolved once and kept for every later lookup.
+    let names = font.glyph_names().unwrap();
+    assert_eq!(font.glyph_index('A'), Ok(Some(GlyphId(1))));
+    assert!(Arc::ptr_eq(&names, &font.glyph_names().unwrap()));
+    assert_eq!((names.character_glyph('\u{E9}'), names.character_glyph('B')), (Some(GlyphId(2)), None));
+    let font = Font::parse(&data, ParseMode::Strict).unwrap();
+    assert_eq!(font.glyph_index('A'), Err(Error::MissingTable));
+
+    // A `cmap` with neither a Unicode nor a symbol subtable.
+    let cmap = vec![0, 0, 0, 1, 0, 1, 0, 0, 0, 0, 0, 12, 0, 0, 0, 6, 0, 0];
+    let data = test_fonts::minimal_font(false).table(b"cmap", cmap).table(b"post", test_fonts::post(&[".notdef", "B", "A"])).build();
+    assert_eq!(Font::decode(&data).unwrap().glyph_index('A'), Ok(Some(GlyphId(2))));
+
+    assert_eq!(agl_character("Lslash"), Some('\u{141}'));
+    assert_eq!(agl_character("u1F600"), Some('\u{1F600}'));
+    assert_eq!((agl_character("uni00e9"), agl_character("f_i"), agl_character("uniD800")), (None, None, None));
+}
+
+#[test]
+fn glyph_name_index() {
+    let post = test_fonts::post(&[".notdef", "uni0410", "A"]);
+    let data = test_fonts::minimal_font(false).table(b"post", post).build();
+    let font = Font::decode(&data).unwrap();
+    assert_eq!(font.gid_by_name("uni0410"), Ok(Some(GlyphId(1))));
+    assert_eq!(font.gid_by_name("A"), Ok(Some(GlyphId(2))));
+    assert_eq!(font.gid_by_name("B"), Ok(None));
+    let names = font.glyph_names().unwrap();
+    assert!(Arc::ptr_eq(&names, &font.glyph_names().unwrap()));
+    assert_eq!((names.len(), names.name(GlyphId(0)), names.name(GlyphId(3))), (3, Some(".notdef"), None));
+
+    // Without `post` names, glyphs are named after their characters, or
+    // their ids, and a name already in use gets a suffix.
+    let cmap = test_fonts::cmap(&[('\u{1F600}', 1), ('b', 1), ('a', 1)]);
+    let data = test_fonts::minimal_font(false).table(b"cmap", cmap).without(b"post").build();
+    let font = Font::decode(&data).unwrap();
+    let names = (0..4).map(|glyph| font.glyph_name(GlyphId(glyph)).unwrap()).collect::<Vec<_>>();
+    assert_eq!(names, vec![Some(".notdef".to_string()), Some("uni0061".to_string()), Some("glyph00002".to_string()), None]);
+    let cmap = test_fonts::cmap(&[('\u{1F600}', 1)]);
+    let post = test_fonts::post(&[".notdef", "", "u1F600"]);
+    let data = test_fonts::minimal_font(false).table(b"cmap", cmap).table(b"post", post).build();
+    let font = Font::decode(&data).unwrap();
+    assert_eq!(font.glyph_name(GlyphId(1)), Ok(Some("u1F600#1".to_string())));
+    assert_eq!(font.gid_by_name("u1F600"), Ok(Some(GlyphId(2))));
+
+    // A repeated name finds the first glyph, and unnamed glyphs are not
+    // indexed.
+    let names = GlyphNames::new(vec!["a".to_string(), String::new(), "b".to_string(), "a".to_string()]);
+    assert_eq!((names.glyph("a"), names.glyph("b"), names.glyph("")), (Some(GlyphId(0)), Some(GlyphId(2)), None));
+    assert_eq!(names.name(GlyphId(1)), None);
+
+    let data = open_file!("data/SourceSansPro/SourceSansPro-Regular.otf");
+    let font = Font::decode(&data).unwrap();
+    let names = font.glyph_names().unwrap();
+    assert_eq!(names.len(), font.num_glyphs().unwrap() as usize);
+    for glyph in (0..names.len() as u16).step_by(97).map(GlyphId) {
+        let name = font.glyph_name(glyph).unwrap().unwrap();
+        assert_eq!(font.gid_by_name(&name), Ok(Some(glyph)));
+    }
+}
+
+#[test]
+fn bogus_glyph_count() {
+    // `maxp` claims no glyphs, then more than `loca` and `hmtx` hold.
+    for &count in &[0, 40] {
+        let data = test_fonts::minimal_font(false).table(b"maxp", test_fonts::maxp(count)).build();
+        let font = Font::parse(&data, ParseMode::Permissive).unwrap();
+        assert_eq!(font.num_glyphs(), Ok(3));
+        assert_eq!(font.loca().map(|loca| loca.num_glyphs()), Ok(3));
+        assert_eq!(font.hmtx().map(|hmtx| hmtx.num_glyphs()), Ok(3));
+
+        let font = Font::parse(&data, ParseMode::Strict).unwrap();
+        assert_eq!(font.num_glyphs(), Ok(count));
+    }
+    let data = test_fonts::minimal_font(false).table(b"maxp", test_fonts::maxp(40)).build();
+    assert!(Font::parse(&data, ParseMode::Strict).unwrap().loca().is_err());
+
+    // An empty `maxp`, and a `maxp` which claims fewer glyphs than `loca`.
+    let data = test_fonts::minimal_font(false).table(b"maxp", Vec::new()).build();
+    assert_eq!(Font::decode(&data).unwrap().num_glyphs(), Ok(3));
+    let data = test_fonts::minimal_font(false).table(b"maxp", test_fonts::maxp(2)).build();
+    assert_eq!(Font::decode(&data).unwrap().num_glyphs(), Ok(2));
+
+    let data = open_file!("data/SourceSansPro/SourceSansPro-Regular.otf");
+    let font = Font::decode(&data).unwrap();
+    assert_eq!(font.num_glyphs(), font.table::<Maxp>().map(|maxp| maxp.get_num_glyphs()));
+}
+
+#[test]
+fn misaligned_buffer() {
+    let data = open_file!("data/DroidSerif.ttf");
+    let aligned = Font::decode(&data).expect("failed to read font");
 
-    for tbl in font.tables {
-        println!("{:?}", tbl);
+    // Start the font at an odd address, so every table is misaligned.
+    let mut shifted = vec![0];
+    shifted.extend_from_slice(&data);
+    let misaligned = Font::decode(&shifted[1..]).expect("failed to read misaligned font");
+    assert_eq!(misaligned.table::<Head>(), aligned.table::<Head>());
+    assert_eq!(misaligned.table::<Os2>(), aligned.table::<Os2>());
+    assert_eq!(misaligned.hmtx().unwrap().advances().collect::<Vec<_>>(),
+               aligned.hmtx().unwrap().advances().collect::<Vec<_>>());
+    assert_eq!(misaligned.glyph_index('x'), aligned.glyph_index('x'));
+
+    // A table with an odd length is not padded by the decoder, so a value
+    // which would extend into the padding is an error.
+    let mut hhea = test_fonts::hhea(800, -200, 2);
+    let odd = hhea.len() - 1;
+    hhea.truncate(odd);
+    let data = test_fonts::minimal_font(false).table(b"hhea", hhea).build();
+    let font = Font::decode(&data).expect("failed to read font");
+    assert_eq!(font.table_record(Tag(*b"hhea")).map(|record| record.length), Some(35));
+    assert_eq!(font.table::<Hhea>().err(), Some(Error::UnexpectedEof));
+}
+
+#[test]
+fn checksums() {
+    let data = open_file!("data/DroidSerif.ttf");
+    let font = Font::decode(&data).expect("failed to read font");
+    for record in font.table_directory().tables() {
+        let table = font.table_data(record.tag).unwrap();
+        assert_eq!(record.compute_checksum(table), record.check_sum);
     }
 
-    panic!();
-}
\ No newline at end of file
+    let head = font.table::<Head>().unwrap();
+    assert_eq!(font.compute_checksum_adjustment(), Ok(head.check_sum_adjustment));
+
+    // Patch a table in place, then fix up the record and the adjustment.
+    let mut data = test_fonts::minimal_font(false).build();
+    let font = Font::decode(&data).unwrap();
+    let expected = font.table::<Head>().unwrap().check_sum_adjustment;
+    assert_eq!(font.compute_checksum_adjustment(), Ok(expected));
+
+    let record = font.table_record(Tag(*b"hhea")).unwrap();
+    let head = font.table_record(Tag(*b"head")).unwrap().offset as usize;
+    let ascender = record.offset as usize + 4;
+    data[ascender..ascender + 2].copy_from_slice(&900i16.to_be_bytes());
+
+    let font = Font::decode(&data).unwrap();
+    let check_sum = record.compute_checksum(font.table_data(record.tag).unwrap());
+    assert_ne!(check_sum, record.check_sum);
+    let record_pos = 12 + font.table_directory().tables().into_iter().position(|r| r.tag == record.tag).unwrap() * 16;
+    data[record_pos + 4..record_pos + 8].copy_from_slice(&check_sum.to_be_bytes());
+
+    let font = Font::decode(&data).unwrap();
+    let adjustment = font.compute_checksum_adjustment().unwrap();
+    data[head + 8..head + 12].copy_from_slice(&adjustment.to_be_bytes());
+    let sum = checksum_at(&data, 0);
+    assert_eq!(sum, 0xB1B0_AFBA);
+}
+
+#[test]
+fn table_spans() {
+    let data = open_file!("data/DroidSerif.ttf");
+    let font = Font::decode(&data).unwrap();
+    let spans = font.table_directory().spans().collect::<Vec<_>>();
+    assert_eq!(spans.len(), font.table_directory().tables().len());
+    assert!(spans.windows(2).all(|pair| pair[0].record.offset <= pair[1].record.offset));
+    assert!(spans.iter().all(|span| span.gap == 0 && span.overlap == 0));
+
+    // Move `hhea` onto the middle of `head`, and `maxp` past a gap.
+    let mut data = test_fonts::minimal_font(false).build();
+    let font = Font::decode(&data).unwrap();
+    let record_pos = |tag: &[u8; 4]| 12 + font.table_directory().tables().into_iter().position(|r| r.tag == Tag(*tag)).unwrap() * 16;
+    let (head, hhea, maxp) = (record_pos(b"head"), record_pos(b"hhea"), record_pos(b"maxp"));
+    let head_offset = u32::decode(&data[head + 8..]).unwrap();
+    let maxp_offset = u32::decode(&data[maxp + 8..]).unwrap();
+    data[hhea + 8..hhea + 12].copy_from_slice(&(head_offset + 50).to_be_bytes());
+    data[maxp + 8..maxp + 12].copy_from_slice(&(maxp_offset + 8).to_be_bytes());
+    let font = Font::decode(&data).unwrap();
+    let span = |tag: &[u8; 4]| font.table_directory().spans().find(|span| span.record.tag == Tag(*tag)).unwrap();
+    assert_eq!((span(b"hhea").gap, span(b"hhea").overlap), (0, 4));
+    assert_eq!((span(b"maxp").gap, span(b"maxp").overlap), (8, 0));
+}
+
+#[test]
+fn fingerprint() {
+    let mut data = test_fonts::minimal_font(false).build();
+    let fingerprint = Font::decode(&data).unwrap().fingerprint().unwrap();
+    assert_eq!(Font::decode(&test_fonts::minimal_font(false).build()).unwrap().fingerprint(), Ok(fingerprint));
+
+    // A stale checksum in the directory and a new adjustment in `head` do
+    // not change the fingerprint.
+    let font = Font::decode(&data).unwrap();
+    let head = font.table_record(Tag(*b"head")).unwrap().offset as usize;
+    data[head + 8..head + 12].copy_from_slice(&0x1234_5678u32.to_be_bytes());
+    data[12 + 4..12 + 8].copy_from_slice(&0u32.to_be_bytes());
+    assert_eq!(Font::decode(&data).unwrap().fingerprint(), Ok(fingerprint));
+
+    let other = test_fonts::minimal_font(false).table(b"hhea", test_fonts::hhea(900, -200, 3)).build();
+    assert_ne!(Font::decode(&other).unwrap().fingerprint(), Ok(fingerprint));
+    let other = test_fonts::minimal_font(true).build();
+    assert_ne!(Font::decode(&other).unwrap().fingerprint(), Ok(fingerprint));
+}
+
+#[test]
+#[cfg(all(feature = "layout", feature = "aat"))]
+fn features_and_scripts() {
+    let data = open_file!("data/NotoSans-Regular.ttf");
+    let font = Font::decode(&data).expect("failed to read font");
+
+    let tags = |names: &[&[u8; 4]]| names.iter().map(|&&name| Tag(name)).collect::<Vec<_>>();
+    assert_eq!(font.scripts(), Ok(tags(&[b"cyrl", b"dev2", b"deva", b"grek", b"latn"])));
+    assert_eq!(font.features(), Ok(tags(&[
+        b"abvm", b"abvs", b"akhn", b"blwf", b"blwm", b"blws", b"ccmp", b"cjct", b"dist", b"half",
+        b"haln", b"kern", b"locl", b"mark", b"mkmk", b"nukt", b"pres", b"psts", b"rkrf", b"rphf",
+        b"vatu",
+    ])));
+
+    // An AAT font with ligature settings, and no OpenType layout tables.
+    let feat = vec![
+        0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0,
+        0, 1, 0, 2, 0, 0, 0, 24, 0, 0, 1, 0,
+        0, 2, 1, 1, 0, 4, 1, 2,
+    ];
+    let data = test_fonts::minimal_font(false).table(b"feat", feat).build();
+    let font = Font::decode(&data).expect("failed to read font");
+    assert_eq!(font.scripts(), Ok(Vec::new()));
+    assert_eq!(font.features(), Ok(tags(&[b"dlig", b"liga"])));
+}
+
+#[test]
+fn monospaced() {
+    let data = open_file!("data/DroidSerif.ttf");
+    let font = Font::decode(&data).expect("failed to read font");
+    assert_eq!(font.is_monospaced(), Ok(false));
+
+    let post = vec![0; 32];
+    let font_with = |metrics: &[(u16, i16)], lsbs: &[i16]| {
+        test_fonts::minimal_font(false)
+            .table(b"post", post.clone())
+            .table(b"hhea", test_fonts::hhea(800, -200, metrics.len() as u16))
+            .table(b"maxp", test_fonts::maxp((metrics.len() + lsbs.len()) as u16))
+            .table(b"hmtx", test_fonts::hmtx(metrics, lsbs))
+            .build()
+    };
+
+    let data = font_with(&[(600, 0), (0, 0)], &[0, 0]);
+    let font = Font::decode(&data).unwrap();
+    assert_eq!(font.is_monospaced(), Ok(true));
+    assert_eq!(font.nominal_advance(), Ok(Some(600)));
+
+    // A dual-width font, where most glyphs are full width.
+    let data = font_with(&[(500, 0), (1000, 0), (0, 0), (1000, 0)], &[]);
+    let font = Font::decode(&data).unwrap();
+    assert_eq!(font.is_monospaced(), Ok(true));
+    assert_eq!(font.nominal_advance(), Ok(Some(500)));
+
+    let data = font_with(&[(500, 0), (700, 0)], &[]);
+    let font = Font::decode(&data).unwrap();
+    assert_eq!(font.is_monospaced(), Ok(false));
+    assert_eq!(font.nominal_advance(), Ok(Some(500)));
+
+    // Twice an advance of 40000 does not fit in 16 bits.
+    let data = font_with(&[(40000, 0), (40000, 0), (500, 0)], &[]);
+    let font = Font::decode(&data).unwrap();
+    assert_eq!(font.is_monospaced(), Ok(false));
+}
@@ -0,0 +1,170 @@
+//! This module contains the key a glyph cache stores rasterized glyphs
+//! under.
+//!
+//! Sizes, pen positions and variation coordinates are floating point, so
+//! they are quantized before they become part of a key.  Values which
+//! render identically must give equal keys: `-0.0` and `0.0`, coordinates
+//! which differ by less than the `F2Dot14` precision the variation tables
+//! are evaluated at, and coordinate lists which differ only by trailing
+//! axes at their default.
+
+use primitives::GlyphId;
+
+/// The number of fractions of a pixel a size is quantized to, as in the
+/// 26.6 fixed point format of FreeType.
+pub const PPEM_SCALE: f32 = 64.0;
+
+/// Identifies one rasterization of a glyph.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GlyphKey {
+    pub glyph_id: GlyphId,
+    /// The horizontal size in 1/64 pixels per em.
+    pub ppem_x: u32,
+    /// The vertical size in 1/64 pixels per em.
+    pub ppem_y: u32,
+    /// The fractional pen position, from `subpixel_bucket`.
+    pub subpixel_bucket: u8,
+    /// The hash of the normalized variation coordinates, from
+    /// `hash_coords`, which is zero at the default instance.
+    pub coords_hash: u64,
+    /// The width synthetic bold adds to stems, in 1/64 pixels, or zero.
+    pub embolden: u32,
+    /// A hash of the gamma and filter coverage is blended with, which is
+    /// zero for the default blending.
+    pub blending_hash: u64,
+}
+
+impl GlyphKey {
+    /// The key of `glyph_id` at a size in pixels per em, in a subpixel
+    /// bucket and at normalized variation coordinates.
+    pub fn new(glyph_id: GlyphId, ppem_x: f32, ppem_y: f32, subpixel_bucket: u8, coords: &[f32]) -> GlyphKey {
+        GlyphKey {
+            glyph_id,
+            ppem_x: quantize_ppem(ppem_x),
+            ppem_y: quantize_ppem(ppem_y),
+            subpixel_bucket,
+            coords_hash: hash_coords(coords),
+            embolden: 0,
+            blending_hash: 0,
+        }
+    }
+
+    /// The key of the same rendering made bolder by `pixels`.
+    pub fn with_embolden(self, pixels: f32) -> GlyphKey {
+        GlyphKey { embolden: quantize_ppem(pixels), ..self }
+    }
+
+    /// The key of the same rendering blended as `blending_hash` describes.
+    pub fn with_blending_hash(self, blending_hash: u64) -> GlyphKey {
+        GlyphKey { blending_hash, ..self }
+    }
+}
+
+/// `ppem` in 1/64 pixels.  Negative and NaN sizes are zero.
+pub fn quantize_ppem(ppem: f32) -> u32 {
+    if ppem.is_nan() || ppem <= 0.0 {
+        return 0
+    }
+    (ppem * PPEM_SCALE).round().min(u32::MAX as f32) as u32
+}
+
+/// Split a pen position into whole pixels and one of `buckets` fractions
+/// of a pixel.  A fraction which rounds up to a whole pixel moves to
+/// bucket zero of the next pixel, so the bucket is always less than
+/// `buckets`.  With a single bucket, or none, positions round to whole
+/// pixels.
+pub fn subpixel_bucket(position: f32, buckets: u8) -> (i32, u8) {
+    let buckets = buckets.max(1);
+    let pixel = position.floor();
+    let bucket = ((position - pixel) * f32::from(buckets)).round() as u32;
+    if bucket >= u32::from(buckets) {
+        (pixel as i32 + 1, 0)
+    } else {
+        (pixel as i32, bucket as u8)
+    }
+}
+
+/// The offset of the left edge of `bucket`, in pixels, which is added to
+/// the outline before rasterizing.
+pub fn subpixel_offset(bucket: u8, buckets: u8) -> f32 {
+    f32::from(bucket) / f32::from(buckets.max(1))
+}
+
+/// A normalized coordinate at the `F2Dot14` precision of the variation
+/// tables.  Coordinates are clamped to `-1.0..=1.0`, and NaN is the
+/// default.
+pub fn quantize_coord(coord: f32) -> i16 {
+    if coord.is_nan() {
+        return 0
+    }
+    (coord.clamp(-1.0, 1.0) * 16384.0).round() as i16
+}
+
+/// The initial state of a 64-bit FNV-1a hash.
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Continue the 64-bit FNV-1a hash `hash` over `bytes`.  The hash does
+/// not depend on the platform or the build, unlike `std`'s hashers.
+pub(crate) fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// A hash of normalized coordinates which is stable between runs and
+/// builds, so that it can key a persistent cache.  Trailing default
+/// coordinates are ignored, and the default instance hashes to zero, as
+/// does a font without variations.
+pub fn hash_coords(coords: &[f32]) -> u64 {
+    let quantized = coords.iter().map(|&coord| quantize_coord(coord)).collect::<Vec<_>>();
+    let len = quantized.iter().rposition(|&coord| coord != 0).map_or(0, |last| last + 1);
+    if len == 0 {
+        return 0
+    }
+
+    let hash = quantized[..len].iter().fold(FNV_OFFSET_BASIS, |hash, coord| fnv1a(hash, &coord.to_be_bytes()));
+
+    // Keep zero for the default instance.
+    hash.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantization() {
+        assert_eq!(quantize_ppem(12.0), 768);
+        assert_eq!(quantize_ppem(12.004), 768);
+        assert_eq!(quantize_ppem(-1.0), 0);
+        assert_eq!(quantize_ppem(f32::NAN), 0);
+
+        assert_eq!(subpixel_bucket(10.3, 4), (10, 1));
+        assert_eq!(subpixel_bucket(10.9, 4), (11, 0));
+        assert_eq!(subpixel_bucket(-0.3, 4), (-1, 3));
+        assert_eq!(subpixel_bucket(-0.1, 4), (0, 0));
+        assert_eq!(subpixel_bucket(2.6, 0), (3, 0));
+        assert_eq!(subpixel_offset(3, 4), 0.75);
+
+        assert_eq!(quantize_coord(0.5), 8192);
+        assert_eq!(quantize_coord(-0.0), 0);
+        assert_eq!(quantize_coord(1.5), 16384);
+        assert_eq!(quantize_coord(f32::NAN), 0);
+    }
+
+    #[test]
+    fn coordinate_hashes() {
+        assert_eq!(hash_coords(&[]), 0);
+        assert_eq!(hash_coords(&[0.0, -0.0]), 0);
+        assert_ne!(hash_coords(&[0.5]), 0);
+        assert_eq!(hash_coords(&[0.5]), hash_coords(&[0.5, 0.0]));
+        assert_eq!(hash_coords(&[0.5]), hash_coords(&[0.50001]));
+        assert_ne!(hash_coords(&[0.5]), hash_coords(&[0.0, 0.5]));
+        assert_ne!(hash_coords(&[0.5]), hash_coords(&[-0.5]));
+        // The hash is part of persistent cache keys, so it must not change.
+        assert_eq!(hash_coords(&[1.0]), 0x090c_0807_b5a4_3a2d);
+
+        let key = GlyphKey::new(GlyphId(3), 16.0, 16.0, 2, &[-0.0]);
+        assert_eq!(key, GlyphKey { glyph_id: GlyphId(3), ppem_x: 1024, ppem_y: 1024, subpixel_bucket: 2, coords_hash: 0, embolden: 0, blending_hash: 0 });
+        assert_ne!(key, GlyphKey::new(GlyphId(3), 16.0, 16.0, 2, &[0.25]));
+        assert_eq!(key.with_embolden(0.5).embolden, 32);
+    }
+}
@@ -0,0 +1,204 @@
+//! This module renumbers glyphs, as subsetting or merging fonts requires,
+//! and rewrites the glyph ids stored in tables to match.
+
+use std::collections::HashMap;
+
+use primitives::GlyphId;
+use table::hmtx::{Hmtx, GlyphMetrics};
+use table::glyf;
+#[cfg(feature = "layout")]
+use table::layout::Coverage;
+use error::{Error, Result};
+
+/// A mapping from the glyph ids of a source font to the glyph ids of a
+/// new font.  New ids are assigned consecutively in insertion order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GlyphMap {
+    first_id: u16,
+    new_ids: HashMap<GlyphId, GlyphId>,
+    old_ids: Vec<GlyphId>,
+}
+
+impl GlyphMap {
+    pub fn new() -> GlyphMap {
+        GlyphMap::default()
+    }
+
+    /// A mapping whose new ids start at `first` rather than zero, such as
+    /// for glyphs appended after those of another font.
+    pub fn with_first_id(first: GlyphId) -> GlyphMap {
+        GlyphMap { first_id: first.0, ..GlyphMap::default() }
+    }
+
+    /// A mapping where new glyph `i` is `old_glyphs[i]`.  Repeated glyphs
+    /// keep their first position.  More distinct glyphs than a font can
+    /// number are an error.
+    pub fn from_order(old_glyphs: &[GlyphId]) -> Result<GlyphMap> {
+        let mut map = GlyphMap::new();
+        for &glyph in old_glyphs {
+            map.insert(glyph)?;
+        }
+        Ok(map)
+    }
+
+    /// Add `old` to the mapping if it is not present, and return its new id.
+    /// It is an error if every glyph id is already assigned.
+    pub fn insert(&mut self, old: GlyphId) -> Result<GlyphId> {
+        if let Some(&new) = self.new_ids.get(&old) {
+            return Ok(new)
+        }
+
+        let next = u32::from(self.first_id) + self.old_ids.len() as u32;
+        if next > 0xFFFF {
+            return Err(Error::InvalidData)
+        }
+        self.old_ids.push(old);
+        self.new_ids.insert(old, GlyphId(next as u16));
+        Ok(GlyphId(next as u16))
+    }
+
+    /// The new id of `old`, or `None` if it is not mapped.
+    pub fn get(&self, old: GlyphId) -> Option<GlyphId> {
+        self.new_ids.get(&old).cloned()
+    }
+
+    /// The source glyph of `new`.
+    pub fn old_id(&self, new: GlyphId) -> Option<GlyphId> {
+        let index = new.0.checked_sub(self.first_id)?;
+        self.old_ids.get(index as usize).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.old_ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.old_ids.is_empty()
+    }
+
+    /// The `(old, new)` pairs, in new glyph order.
+    pub fn iter(&self) -> impl Iterator<Item = (GlyphId, GlyphId)> + '_ {
+        let first_id = self.first_id;
+        self.old_ids.iter().enumerate().map(move |(new, &old)| (old, GlyphId(first_id + new as u16)))
+    }
+
+    /// Rewrite `(code point, glyph)` mappings, dropping those whose glyph
+    /// is not mapped.
+    pub fn remap_cmap(&self, mappings: &[(u32, GlyphId)]) -> Vec<(u32, GlyphId)> {
+        mappings.iter()
+            .filter_map(|&(code, glyph)| self.get(glyph).map(|glyph| (code, glyph)))
+            .collect()
+    }
+
+    /// The horizontal metrics of each new glyph, in new glyph order.
+    pub fn remap_hmtx(&self, hmtx: &Hmtx) -> Result<Vec<GlyphMetrics>> {
+        self.old_ids.iter()
+            .map(|&old| hmtx.metrics(old).ok_or(Error::InvalidGlyphId))
+            .collect()
+    }
+
+    /// A copy of the `glyf` data of a glyph with the ids of its components
+    /// rewritten.  Simple and empty glyphs are returned unchanged, and a
+    /// component which is not mapped is an error.
+    pub fn remap_glyph(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut out = data.to_vec();
+        match glyf::glyph_header(data)? {
+            Some(ref header) if header.number_of_contours < 0 => (),
+            _ => return Ok(out),
+        }
+
+        for offset in glyf::component_glyph_offsets(data)? {
+            let old = GlyphId(u16::from(data[offset]) << 8 | u16::from(data[offset + 1]));
+            let new = self.get(old).ok_or(Error::InvalidGlyphId)?;
+            out[offset] = (new.0 >> 8) as u8;
+            out[offset + 1] = new.0 as u8;
+        }
+
+        Ok(out)
+    }
+
+    /// Serialize `coverage` with its glyphs renumbered, dropping glyphs
+    /// which are not mapped.  Coverage indices change with the glyph
+    /// order, so the arrays of the owning subtable must be reordered to
+    /// match.
+    #[cfg(feature = "layout")]
+    pub fn remap_coverage(&self, coverage: &Coverage) -> Vec<u8> {
+        let glyphs = coverage.glyphs()
+            .into_iter()
+            .filter_map(|glyph| self.get(glyph))
+            .collect::<Vec<_>>();
+        Coverage::encode(&glyphs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+    use font::Font;
+    use table::glyf::Glyf;
+    use test_fonts;
+
+    #[test]
+    fn remapping() {
+        let mut map = GlyphMap::from_order(&[GlyphId(0), GlyphId(2), GlyphId(2)]).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.insert(GlyphId(1)), Ok(GlyphId(2)));
+        assert_eq!(map.get(GlyphId(2)), Some(GlyphId(1)));
+        assert_eq!(map.old_id(GlyphId(2)), Some(GlyphId(1)));
+        assert_eq!(map.get(GlyphId(5)), None);
+
+        let mut shifted = GlyphMap::with_first_id(GlyphId(10));
+        assert_eq!(shifted.insert(GlyphId(3)), Ok(GlyphId(10)));
+        assert_eq!(shifted.old_id(GlyphId(10)), Some(GlyphId(3)));
+        assert_eq!(shifted.old_id(GlyphId(0)), None);
+        assert_eq!(shifted.iter().collect::<Vec<_>>(), vec![(GlyphId(3), GlyphId(10))]);
+
+        // Ids past 0xFFFF cannot be assigned, though mapped glyphs can be
+        // looked up again.
+        let mut full = GlyphMap::with_first_id(GlyphId(0xFFFF));
+        assert_eq!(full.insert(GlyphId(1)), Ok(GlyphId(0xFFFF)));
+        assert_eq!(full.insert(GlyphId(2)), Err(Error::InvalidData));
+        assert_eq!(full.insert(GlyphId(1)), Ok(GlyphId(0xFFFF)));
+
+        let cmap = [(0x41, GlyphId(1)), (0x42, GlyphId(2)), (0x43, GlyphId(5))];
+        assert_eq!(map.remap_cmap(&cmap), vec![(0x41, GlyphId(2)), (0x42, GlyphId(1))]);
+
+        let data = test_fonts::minimal_font(false).build();
+        let font = Font::decode(&data).unwrap();
+        let metrics = map.remap_hmtx(&font.hmtx().unwrap()).unwrap();
+        let advances = metrics.iter().map(|metric| metric.advance_width).collect::<Vec<_>>();
+        assert_eq!(advances, vec![500, 700, 700]);
+    }
+
+    #[test]
+    #[cfg(feature = "layout")]
+    fn remapped_coverage() {
+        let map = GlyphMap::from_order(&[GlyphId(0), GlyphId(2), GlyphId(1)]).unwrap();
+        let coverage = Coverage::encode(&[GlyphId(1), GlyphId(2), GlyphId(5)]);
+        let coverage = Coverage::decode(&coverage).unwrap();
+        let remapped = map.remap_coverage(&coverage);
+        assert_eq!(Coverage::decode(&remapped).unwrap().glyphs(), vec![GlyphId(1), GlyphId(2)]);
+    }
+
+    #[test]
+    fn composite_components() {
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let loca = font.loca().unwrap();
+        let glyf = font.table::<Glyf>().unwrap();
+        let aacute = glyf.glyph_data(&loca, GlyphId(131)).unwrap();
+        let components = glyf::components(aacute).unwrap();
+
+        let mut map = GlyphMap::from_order(&[GlyphId(0), GlyphId(131)]).unwrap();
+        assert_eq!(map.remap_glyph(aacute), Err(Error::InvalidGlyphId));
+        for component in &components {
+            map.insert(component.glyph).unwrap();
+        }
+
+        let remapped = map.remap_glyph(aacute).unwrap();
+        let glyphs = glyf::components(&remapped).unwrap().iter().map(|c| c.glyph).collect::<Vec<_>>();
+        assert_eq!(glyphs, vec![GlyphId(2), GlyphId(3)]);
+        assert_eq!(remapped.len(), aacute.len());
+    }
+}
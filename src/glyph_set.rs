@@ -0,0 +1,280 @@
+//! This module holds sets of glyph ids, as closure computation, subsetting
+//! and coverage analysis build them.  A font has at most 65536 glyphs, so
+//! a set is a bitmap of at most 8 KiB, which makes membership, union and
+//! intersection a matter of word operations.
+
+use std::fmt;
+use std::iter::FromIterator;
+
+use primitives::GlyphId;
+
+const WORD_BITS: usize = 64;
+
+/// A set of glyph ids, iterated in increasing order.
+#[derive(Clone, Default)]
+pub struct GlyphSet {
+    words: Vec<u64>,
+}
+
+impl GlyphSet {
+    pub fn new() -> GlyphSet {
+        GlyphSet::default()
+    }
+
+    /// An empty set with room for the glyphs of a font with `num_glyphs`
+    /// glyphs, such as `maxp.numGlyphs`.  Larger ids may still be added.
+    pub fn with_num_glyphs(num_glyphs: u16) -> GlyphSet {
+        GlyphSet { words: vec![0; (num_glyphs as usize).div_ceil(WORD_BITS)] }
+    }
+
+    fn position(glyph: GlyphId) -> (usize, u64) {
+        let index = glyph.0 as usize;
+        (index / WORD_BITS, 1 << (index % WORD_BITS))
+    }
+
+    /// Add `glyph`, and return whether it was not already present.
+    pub fn insert(&mut self, glyph: GlyphId) -> bool {
+        let (word, bit) = GlyphSet::position(glyph);
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        let added = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        added
+    }
+
+    /// Add the glyphs from `first` to `last` inclusive, a word at a time.
+    pub fn insert_range(&mut self, first: GlyphId, last: GlyphId) {
+        if first > last {
+            return
+        }
+
+        let (first, last) = (first.0 as usize, last.0 as usize);
+        if last / WORD_BITS >= self.words.len() {
+            self.words.resize(last / WORD_BITS + 1, 0);
+        }
+        for word in first / WORD_BITS..=last / WORD_BITS {
+            let low = if word == first / WORD_BITS { first % WORD_BITS } else { 0 };
+            let high = if word == last / WORD_BITS { last % WORD_BITS } else { WORD_BITS - 1 };
+            let mask = (!0u64 >> (WORD_BITS - 1 - high)) & (!0u64 << low);
+            self.words[word] |= mask;
+        }
+    }
+
+    /// Remove `glyph`, and return whether it was present.
+    pub fn remove(&mut self, glyph: GlyphId) -> bool {
+        let (word, bit) = GlyphSet::position(glyph);
+        match self.words.get_mut(word) {
+            Some(word) if *word & bit != 0 => {
+                *word &= !bit;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    pub fn contains(&self, glyph: GlyphId) -> bool {
+        let (word, bit) = GlyphSet::position(glyph);
+        self.words.get(word).is_some_and(|word| word & bit != 0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    pub fn clear(&mut self) {
+        for word in &mut self.words {
+            *word = 0;
+        }
+    }
+
+    /// Add every glyph of `other`.
+    pub fn union_with(&mut self, other: &GlyphSet) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word |= other;
+        }
+    }
+
+    /// Keep only the glyphs which are also in `other`.
+    pub fn intersect_with(&mut self, other: &GlyphSet) {
+        for (index, word) in self.words.iter_mut().enumerate() {
+            *word &= other.words.get(index).cloned().unwrap_or(0);
+        }
+    }
+
+    /// Remove every glyph of `other`.
+    pub fn subtract(&mut self, other: &GlyphSet) {
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word &= !other;
+        }
+    }
+
+    /// Whether the sets have any glyph in common.
+    pub fn intersects(&self, other: &GlyphSet) -> bool {
+        self.words.iter().zip(&other.words).any(|(word, other)| word & other != 0)
+    }
+
+    pub fn is_subset(&self, other: &GlyphSet) -> bool {
+        self.words.iter().enumerate()
+            .all(|(index, word)| word & !other.words.get(index).cloned().unwrap_or(0) == 0)
+    }
+
+    /// The smallest glyph of the set.
+    pub fn first(&self) -> Option<GlyphId> {
+        self.iter().next()
+    }
+
+    /// The glyphs of the set, in increasing order.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { words: &self.words, index: 0, word: self.words.first().cloned().unwrap_or(0) }
+    }
+
+    /// The runs of consecutive glyphs of the set as `(first, last)` pairs,
+    /// in increasing order, as a format 2 coverage table stores them.
+    pub fn ranges(&self) -> Ranges<'_> {
+        Ranges { glyphs: self.iter(), next: None }
+    }
+}
+
+impl PartialEq for GlyphSet {
+    /// Sets are equal when they hold the same glyphs, however much room
+    /// either has.
+    fn eq(&self, other: &GlyphSet) -> bool {
+        let len = self.words.len().max(other.words.len());
+        (0..len).all(|index| self.words.get(index).unwrap_or(&0) == other.words.get(index).unwrap_or(&0))
+    }
+}
+
+impl Eq for GlyphSet {}
+
+impl fmt::Debug for GlyphSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl FromIterator<GlyphId> for GlyphSet {
+    fn from_iter<I: IntoIterator<Item = GlyphId>>(glyphs: I) -> GlyphSet {
+        let mut set = GlyphSet::new();
+        set.extend(glyphs);
+        set
+    }
+}
+
+impl Extend<GlyphId> for GlyphSet {
+    fn extend<I: IntoIterator<Item = GlyphId>>(&mut self, glyphs: I) {
+        for glyph in glyphs {
+            self.insert(glyph);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a GlyphSet {
+    type Item = GlyphId;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// The glyphs of a `GlyphSet`, in increasing order.
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+    words: &'a [u64],
+    index: usize,
+    /// The bits of the word at `index` which are not yet returned.
+    word: u64,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = GlyphId;
+
+    fn next(&mut self) -> Option<GlyphId> {
+        while self.word == 0 {
+            self.index += 1;
+            self.word = *self.words.get(self.index)?;
+        }
+
+        let bit = self.word.trailing_zeros() as usize;
+        self.word &= self.word - 1;
+        Some(GlyphId((self.index * WORD_BITS + bit) as u16))
+    }
+}
+
+/// The runs of consecutive glyphs of a `GlyphSet`.
+#[derive(Debug, Clone)]
+pub struct Ranges<'a> {
+    glyphs: Iter<'a>,
+    next: Option<GlyphId>,
+}
+
+impl<'a> Iterator for Ranges<'a> {
+    type Item = (GlyphId, GlyphId);
+
+    fn next(&mut self) -> Option<(GlyphId, GlyphId)> {
+        let first = self.next.take().or_else(|| self.glyphs.next())?;
+        let mut last = first;
+        for glyph in &mut self.glyphs {
+            if glyph.0 != last.0 + 1 {
+                self.next = Some(glyph);
+                break
+            }
+            last = glyph;
+        }
+        Some((first, last))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glyphs(ids: &[u16]) -> GlyphSet {
+        ids.iter().cloned().map(GlyphId).collect()
+    }
+
+    #[test]
+    fn set_operations() {
+        let mut set = GlyphSet::with_num_glyphs(100);
+        assert!(set.is_empty());
+        assert!(set.insert(GlyphId(3)));
+        assert!(!set.insert(GlyphId(3)));
+        assert!(set.insert(GlyphId(65535)));
+        set.insert_range(GlyphId(60), GlyphId(130));
+        assert_eq!(set.len(), 73);
+        assert!(set.contains(GlyphId(64)) && set.contains(GlyphId(130)) && !set.contains(GlyphId(131)));
+        assert_eq!(set.ranges().collect::<Vec<_>>(), vec![
+            (GlyphId(3), GlyphId(3)),
+            (GlyphId(60), GlyphId(130)),
+            (GlyphId(65535), GlyphId(65535)),
+        ]);
+        assert!(set.remove(GlyphId(65535)));
+        assert!(!set.remove(GlyphId(65535)));
+        assert_eq!(set.first(), Some(GlyphId(3)));
+
+        let mut other = glyphs(&[1, 3, 128, 200]);
+        assert!(set.intersects(&other));
+        other.intersect_with(&set);
+        assert_eq!(other, glyphs(&[3, 128]));
+        assert!(other.is_subset(&set) && !set.is_subset(&other));
+        assert_eq!(other.iter().collect::<Vec<_>>(), vec![GlyphId(3), GlyphId(128)]);
+
+        other.union_with(&glyphs(&[0, 500]));
+        assert_eq!(format!("{:?}", other), "{GlyphId(0), GlyphId(3), GlyphId(128), GlyphId(500)}");
+        other.subtract(&set);
+        assert_eq!(other, glyphs(&[0, 500]));
+
+        // Trailing room does not affect equality.
+        other.clear();
+        assert_eq!(other, GlyphSet::new());
+        assert_eq!(GlyphSet::new().iter().next(), None);
+    }
+}
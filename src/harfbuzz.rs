@@ -0,0 +1,286 @@
+//! This module answers the per-glyph queries of HarfBuzz's font functions
+//! (`hb_font_funcs_t`) from this crate's tables, so that an application
+//! can shape with HarfBuzz while reading font data only through this
+//! crate.
+//!
+//! `FaceProvider` implements the `FontFuncs` trait of `harfbuzz_rs`, so
+//! it is installed with `harfbuzz_rs::Font::set_font_funcs`, and answers
+//! each callback at the scale of the HarfBuzz font.  Its methods may also
+//! be called directly, and their results are in HarfBuzz's conventions:
+//! positions are in the font scale, and the y-axis of glyph extents
+//! points up with a negative height.
+
+use harfbuzz_rs::{self, FontFuncs, Position};
+
+use font::Font;
+use outline::{OutlineSink, Rect};
+use primitives::GlyphId;
+use table::cmap::CharacterMap;
+use table::head::Head;
+use table::hhea::Hhea;
+use table::hmtx::Hmtx;
+use error::Result;
+
+/// The ink box of a glyph, as `hb_glyph_extents_t`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct GlyphExtents {
+    pub x_bearing: i32,
+    pub y_bearing: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// The horizontal line metrics, as `hb_font_extents_t`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct FontExtents {
+    pub ascender: i32,
+    pub descender: i32,
+    pub line_gap: i32,
+}
+
+/// The font functions of one font at one scale.
+#[derive(Debug, Copy, Clone)]
+pub struct FaceProvider<'a, 'fnt: 'a> {
+    font: &'a Font<'fnt>,
+    hmtx: Hmtx<'fnt>,
+    /// The `cmap` subtable, decoded once, or `None` if the font has no
+    /// usable one.
+    character_map: Option<CharacterMap<'fnt>>,
+    units_per_em: f32,
+    x_scale: i32,
+    y_scale: i32,
+}
+
+impl<'a, 'fnt> FaceProvider<'a, 'fnt> {
+    /// A provider for `font`, whose scale is initially its `unitsPerEm`,
+    /// so that positions are in font units.
+    pub fn new(font: &'a Font<'fnt>) -> Result<FaceProvider<'a, 'fnt>> {
+        let units_per_em = font.table::<Head>()?.units_per_em;
+        Ok(FaceProvider {
+            font,
+            hmtx: font.hmtx()?,
+            character_map: font.character_map().ok().and_then(|map| map),
+            units_per_em: f32::from(units_per_em),
+            x_scale: i32::from(units_per_em),
+            y_scale: i32::from(units_per_em),
+        })
+    }
+
+    /// Match the scale of the HarfBuzz font, as set by `hb_font_set_scale`.
+    pub fn set_scale(&mut self, x_scale: i32, y_scale: i32) {
+        self.x_scale = x_scale;
+        self.y_scale = y_scale;
+    }
+
+    fn scale_x(&self, value: f32) -> i32 {
+        (value * self.x_scale as f32 / self.units_per_em).round() as i32
+    }
+
+    fn scale_y(&self, value: f32) -> i32 {
+        (value * self.y_scale as f32 / self.units_per_em).round() as i32
+    }
+
+    /// The provider at the scale of the HarfBuzz font `font`.
+    fn at_scale(&self, font: &harfbuzz_rs::Font) -> FaceProvider<'a, 'fnt> {
+        let (x_scale, y_scale) = font.scale();
+        FaceProvider { x_scale, y_scale, ..*self }
+    }
+
+    /// The glyph for `unicode`, as `Font::glyph_index` maps it.
+    pub fn nominal_glyph(&self, unicode: u32) -> Option<u32> {
+        let character = ::std::char::from_u32(unicode)?;
+        let glyph = match self.character_map {
+            Some(ref map) => map.glyph_id(character),
+            None => self.font.named_glyph_index(character)?,
+        };
+        Some(u32::from(glyph?.0))
+    }
+
+    /// The glyph for `unicode` followed by the variation selector
+    /// `selector`, as `Font::glyph_variant` maps it.
+    pub fn variation_glyph(&self, unicode: u32, selector: u32) -> Option<u32> {
+        let character = ::std::char::from_u32(unicode)?;
+        let selector = ::std::char::from_u32(selector)?;
+        self.font.glyph_variant(character, selector).ok()?.map(|glyph| u32::from(glyph.0))
+    }
+
+    /// The advance of `glyph`, or zero if it does not exist.
+    pub fn glyph_h_advance(&self, glyph: u32) -> i32 {
+        let advance = to_glyph_id(glyph).and_then(|glyph| self.hmtx.advance(glyph)).unwrap_or(0);
+        self.scale_x(f32::from(advance))
+    }
+
+    /// The ink box of `glyph`, which is empty for a glyph without an
+    /// outline, or `None` if the glyph cannot be read.
+    pub fn glyph_extents(&self, glyph: u32) -> Option<GlyphExtents> {
+        let bounds = self.font.glyph_bounds(to_glyph_id(glyph)?).ok()?;
+        Some(bounds.map_or_else(GlyphExtents::default, |bounds: Rect| {
+            let x_bearing = self.scale_x(bounds.x_min);
+            let y_bearing = self.scale_y(bounds.y_max);
+            GlyphExtents {
+                x_bearing,
+                y_bearing,
+                width: self.scale_x(bounds.x_max) - x_bearing,
+                height: self.scale_y(bounds.y_min) - y_bearing,
+            }
+        }))
+    }
+
+    /// The ascender, descender and line gap of `hhea`.
+    pub fn font_h_extents(&self) -> Option<FontExtents> {
+        let hhea = self.font.table::<Hhea>().ok()?;
+        Some(FontExtents {
+            ascender: self.scale_y(f32::from(i16::from(hhea.ascender))),
+            descender: self.scale_y(f32::from(i16::from(hhea.descender))),
+            line_gap: self.scale_y(f32::from(i16::from(hhea.line_gap))),
+        })
+    }
+
+    /// Send the outline of `glyph` to `sink` in the font scale, as the
+    /// draw functions of `hb_font_draw_glyph` receive it.  Returns whether
+    /// the outline could be read.
+    pub fn draw_glyph<S: OutlineSink>(&self, glyph: u32, sink: &mut S) -> bool {
+        let glyph = match to_glyph_id(glyph) {
+            Some(glyph) => glyph,
+            None => return false,
+        };
+
+        let mut scaled = ScaledSink {
+            sink,
+            x_scale: self.x_scale as f32 / self.units_per_em,
+            y_scale: self.y_scale as f32 / self.units_per_em,
+        };
+        self.font.outline_glyph(glyph, &mut scaled).is_ok()
+    }
+}
+
+impl<'a, 'fnt> FontFuncs for FaceProvider<'a, 'fnt> {
+    fn get_font_h_extents(&self, font: &harfbuzz_rs::Font) -> Option<harfbuzz_rs::FontExtents> {
+        let extents = self.at_scale(font).font_h_extents()?;
+        Some(harfbuzz_rs::FontExtents::new(extents.ascender, extents.descender, extents.line_gap))
+    }
+
+    fn get_nominal_glyph(&self, _: &harfbuzz_rs::Font, unicode: char) -> Option<harfbuzz_rs::Glyph> {
+        self.nominal_glyph(unicode as u32)
+    }
+
+    fn get_variation_glyph(&self, _: &harfbuzz_rs::Font, unicode: char, selector: char) -> Option<harfbuzz_rs::Glyph> {
+        self.variation_glyph(unicode as u32, selector as u32)
+    }
+
+    fn get_glyph_h_advance(&self, font: &harfbuzz_rs::Font, glyph: harfbuzz_rs::Glyph) -> Position {
+        self.at_scale(font).glyph_h_advance(glyph)
+    }
+
+    fn get_glyph_h_origin(&self, _: &harfbuzz_rs::Font, _: harfbuzz_rs::Glyph) -> Option<(Position, Position)> {
+        Some((0, 0))
+    }
+
+    fn get_glyph_extents(&self, font: &harfbuzz_rs::Font, glyph: harfbuzz_rs::Glyph) -> Option<harfbuzz_rs::GlyphExtents> {
+        let extents = self.at_scale(font).glyph_extents(glyph)?;
+        Some(harfbuzz_rs::GlyphExtents {
+            x_bearing: extents.x_bearing,
+            y_bearing: extents.y_bearing,
+            width: extents.width,
+            height: extents.height,
+        })
+    }
+}
+
+/// HarfBuzz glyph indices are 32 bits wide.
+fn to_glyph_id(glyph: u32) -> Option<GlyphId> {
+    if glyph > 0xFFFF {
+        return None
+    }
+    Some(GlyphId(glyph as u16))
+}
+
+struct ScaledSink<'s, S: 's> {
+    sink: &'s mut S,
+    x_scale: f32,
+    y_scale: f32,
+}
+
+impl<'s, S: OutlineSink> OutlineSink for ScaledSink<'s, S> {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.sink.move_to(x * self.x_scale, y * self.y_scale);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.sink.line_to(x * self.x_scale, y * self.y_scale);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.sink.quad_to(x1 * self.x_scale, y1 * self.y_scale, x * self.x_scale, y * self.y_scale);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.sink.curve_to(x1 * self.x_scale, y1 * self.y_scale,
+                           x2 * self.x_scale, y2 * self.y_scale,
+                           x * self.x_scale, y * self.y_scale);
+    }
+
+    fn close(&mut self) {
+        self.sink.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+    use outline::BoundsSink;
+    use test_fonts;
+
+    #[test]
+    fn face_provider() {
+        let data = test_fonts::minimal_font(false)
+            .table(b"cmap", test_fonts::cmap(&[('A', 1)]))
+            .build();
+        let font = Font::decode(&data).unwrap();
+        let mut provider = FaceProvider::new(&font).unwrap();
+
+        assert_eq!(provider.nominal_glyph('A' as u32), Some(1));
+        assert_eq!(provider.nominal_glyph('B' as u32), None);
+        assert_eq!(provider.nominal_glyph(0xD800), None);
+        assert_eq!(provider.glyph_h_advance(1), 700);
+        assert_eq!(provider.glyph_h_advance(70_000), 0);
+        assert_eq!(provider.glyph_extents(1), Some(GlyphExtents {
+            x_bearing: 100,
+            y_bearing: 500,
+            width: 500,
+            height: -500,
+        }));
+        assert_eq!(provider.glyph_extents(0), Some(GlyphExtents::default()));
+        assert_eq!(provider.glyph_extents(3), None);
+
+        provider.set_scale(2000, 500);
+        assert_eq!(provider.glyph_h_advance(1), 1400);
+        assert_eq!(provider.font_h_extents(), Some(FontExtents { ascender: 400, descender: -100, line_gap: 0 }));
+
+        let mut sink = BoundsSink::new();
+        assert!(provider.draw_glyph(2, &mut sink));
+        assert_eq!(sink.bounds(), Some(Rect { x_min: 100.0, y_min: 0.0, x_max: 1100.0, y_max: 350.0 }));
+        assert!(!provider.draw_glyph(3, &mut sink));
+    }
+
+    #[test]
+    fn shape_with_harfbuzz() {
+        let data = test_fonts::minimal_font(false)
+            .table(b"cmap", test_fonts::cmap(&[('A', 1), ('B', 2)]))
+            .build();
+        let font = Font::decode(&data).unwrap();
+        let face = harfbuzz_rs::Face::from_bytes(&data, 0);
+        let mut hb_font = harfbuzz_rs::Font::new(face);
+        hb_font.set_scale(2000, 2000);
+        hb_font.set_font_funcs(FaceProvider::new(&font).unwrap());
+
+        let buffer = harfbuzz_rs::UnicodeBuffer::new().add_str("ABC");
+        let output = harfbuzz_rs::shape(&hb_font, buffer, &[]);
+        let glyphs = output.get_glyph_infos().iter().map(|info| info.codepoint).collect::<Vec<_>>();
+        let advances = output.get_glyph_positions().iter().map(|position| position.x_advance).collect::<Vec<_>>();
+        assert_eq!(glyphs, [1, 2, 0]);
+        assert_eq!(advances, [1400, 1400, 1000]);
+        assert_eq!(hb_font.get_glyph_extents(1).map(|extents| (extents.x_bearing, extents.height)), Some((200, -1000)));
+    }
+}
@@ -0,0 +1,471 @@
+//! This module reads the metadata a font index stores for each face, such
+//! as its names, weight and style, from `name`, `OS/2` and `head`.  With
+//! the `rayon` feature, `parse_many` reads a whole font directory in
+//! parallel.
+//!
+//! `FaceInfo` adds the characters and variation axes of a face, and can be
+//! written to a small binary form, so that an index can be cached between
+//! runs rather than parsing every font again at startup.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use decode::{Decode, StaticEncodeSize};
+use encode::EncodeBuf;
+use font::Font;
+use primitives::Tag;
+use table::cmap::Cmap;
+#[cfg(feature = "variations")]
+use table::fvar::Fvar;
+use table::head::Head;
+use table::name::{Name, NameId};
+use table::os2::Os2;
+use error::Error;
+
+/// The metadata of one face.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaceMetadata {
+    /// The typographic family name, or the legacy family name.
+    pub family: Option<String>,
+    /// The typographic subfamily name, or the legacy subfamily name.
+    pub style: Option<String>,
+    pub full_name: Option<String>,
+    pub postscript_name: Option<String>,
+    /// `usWeightClass`, or 700 or 400 from `head.macStyle` without OS/2.
+    pub weight: u16,
+    /// `usWidthClass`, or 5 without OS/2.
+    pub width_class: u16,
+    pub italic: bool,
+    pub units_per_em: u16,
+    pub font_revision: f32,
+    pub num_glyphs: u16,
+}
+
+impl FaceMetadata {
+    pub fn read(font: &Font) -> Result<FaceMetadata, Error> {
+        let head = font.table::<Head>()?;
+        let name = font.optional_table::<Name>()?;
+        let find = |ids: &[NameId]| name.as_ref().and_then(|name| ids.iter().filter_map(|&id| name.find(id)).next());
+        let (weight, width_class, italic) = match font.optional_table::<Os2>()? {
+            Some(os2) => (os2.us_weight_class, os2.us_width_class, os2.is_italic()),
+            None => (if head.is_bold() { 700 } else { 400 }, 5, head.is_italic()),
+        };
+
+        Ok(FaceMetadata {
+            family: find(&[NameId::TypographicFamilyName, NameId::FamilyName]),
+            style: find(&[NameId::TypographicSubfamilyName, NameId::SubfamilyName]),
+            full_name: find(&[NameId::FullName]),
+            postscript_name: find(&[NameId::PostScriptName]),
+            weight,
+            width_class,
+            italic,
+            units_per_em: head.units_per_em,
+            font_revision: f32::from(head.font_revision),
+            num_glyphs: font.num_glyphs()?,
+        })
+    }
+}
+
+/// The characters a face maps to glyphs, as a bitmap of the 256
+/// character pages which have any.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CharCoverage {
+    /// Each page with its bits, sorted by page.
+    pages: Vec<(u32, [u64; 4])>,
+}
+
+impl CharCoverage {
+    pub fn new() -> CharCoverage {
+        CharCoverage::default()
+    }
+
+    /// The characters the Unicode or symbol subtable of `cmap` maps to a
+    /// glyph other than `.notdef`.
+    pub fn read(cmap: &Cmap) -> Result<CharCoverage, Error> {
+        let mappings = match cmap.unicode_subtable()? {
+            Some(subtable) => subtable.mappings(),
+            None => match cmap.symbol_subtable()? {
+                Some(symbol) => symbol.mappings(),
+                None => Vec::new(),
+            },
+        };
+
+        let mut coverage = CharCoverage::new();
+        for (code, glyph) in mappings {
+            if glyph.0 != 0 {
+                coverage.insert(code);
+            }
+        }
+        Ok(coverage)
+    }
+
+    pub fn insert(&mut self, code: u32) {
+        let (page, word, bit) = (code >> 8, (code as usize & 0xFF) / 64, 1 << (code % 64));
+        let index = match self.pages.binary_search_by_key(&page, |&(page, _)| page) {
+            Ok(index) => index,
+            Err(index) => {
+                self.pages.insert(index, (page, [0; 4]));
+                index
+            },
+        };
+        self.pages[index].1[word] |= bit;
+    }
+
+    pub fn contains(&self, character: char) -> bool {
+        let code = character as u32;
+        match self.pages.binary_search_by_key(&(code >> 8), |&(page, _)| page) {
+            Ok(index) => self.pages[index].1[(code as usize & 0xFF) / 64] & (1 << (code % 64)) != 0,
+            Err(_) => false,
+        }
+    }
+
+    /// The covered characters as ranges of consecutive codes, from the
+    /// first to the last of each, in order.
+    pub fn ranges(&self) -> Vec<(u32, u32)> {
+        let mut ranges: Vec<(u32, u32)> = Vec::new();
+        for &(page, ref words) in &self.pages {
+            for (index, &word) in words.iter().enumerate() {
+                let mut bits = word;
+                while bits != 0 {
+                    let code = page << 8 | (index as u32 * 64 + bits.trailing_zeros());
+                    bits &= bits - 1;
+                    match ranges.last_mut() {
+                        Some(range) if range.1 + 1 == code => range.1 = code,
+                        _ => ranges.push((code, code)),
+                    }
+                }
+            }
+        }
+        ranges
+    }
+
+    pub fn len(&self) -> usize {
+        self.pages.iter()
+            .map(|(_, words)| words.iter().map(|word| word.count_ones() as usize).sum::<usize>())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+}
+
+/// A variation axis of a face, in user coordinates.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AxisInfo {
+    pub tag: Tag,
+    pub min: f32,
+    pub default: f32,
+    pub max: f32,
+}
+
+/// The version of the binary form `FaceInfo::to_bytes` writes.
+pub const FACE_INFO_VERSION: u16 = 1;
+
+const FACE_INFO_MAGIC: [u8; 4] = *b"FKFI";
+
+/// Everything a font index stores about a face: its metadata, the
+/// characters it covers and its variation axes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaceInfo {
+    pub metadata: FaceMetadata,
+    pub coverage: CharCoverage,
+    /// The axes of `fvar`, which are empty for a static face or without
+    /// the `variations` feature.
+    pub axes: Vec<AxisInfo>,
+}
+
+impl FaceInfo {
+    pub fn read(font: &Font) -> Result<FaceInfo, Error> {
+        let coverage = match font.optional_table::<Cmap>()? {
+            Some(cmap) => CharCoverage::read(&cmap)?,
+            None => CharCoverage::new(),
+        };
+        Ok(FaceInfo { metadata: FaceMetadata::read(font)?, coverage, axes: read_axes(font)? })
+    }
+
+    /// Write the face in a binary form for `from_bytes`, which begins with
+    /// the magic `FKFI` and `FACE_INFO_VERSION`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = EncodeBuf::new();
+        buf.put_bytes(&FACE_INFO_MAGIC);
+        buf.put_u16(FACE_INFO_VERSION);
+
+        let metadata = &self.metadata;
+        for name in &[&metadata.family, &metadata.style, &metadata.full_name, &metadata.postscript_name] {
+            match **name {
+                // Names are at most 0xFFFE bytes, so 0xFFFF marks a name the
+                // face does not have.
+                Some(ref name) => {
+                    let len = name.len().min(0xFFFE);
+                    let len = (0..=len).rev().find(|&len| name.is_char_boundary(len)).unwrap_or(0);
+                    buf.put_u16(len as u16);
+                    buf.put_bytes(&name.as_bytes()[..len]);
+                },
+                None => buf.put_u16(0xFFFF),
+            }
+        }
+        buf.put_u16(metadata.weight);
+        buf.put_u16(metadata.width_class);
+        buf.put_u8(metadata.italic as u8);
+        buf.put_u16(metadata.units_per_em);
+        buf.put_u32(metadata.font_revision.to_bits());
+        buf.put_u16(metadata.num_glyphs);
+
+        buf.put_u32(self.coverage.pages.len() as u32);
+        for &(page, ref words) in &self.coverage.pages {
+            buf.put_u32(page);
+            for &word in words {
+                buf.put(word);
+            }
+        }
+
+        buf.put_u16(self.axes.len() as u16);
+        for axis in &self.axes {
+            buf.put_tag(axis.tag);
+            for value in &[axis.min, axis.default, axis.max] {
+                buf.put_u32(value.to_bits());
+            }
+        }
+        buf.into_vec()
+    }
+
+    /// Read a face written by `to_bytes`.  Data of another version fails
+    /// with `UnsupportedVersion`, so a cache can tell it must be rebuilt.
+    pub fn from_bytes(data: &[u8]) -> Result<FaceInfo, Error> {
+        let mut reader = Reader { data, pos: 0 };
+        if reader.bytes(4)? != FACE_INFO_MAGIC {
+            return Err(Error::InvalidData)
+        }
+        let version = reader.read::<u16>()?;
+        if version != FACE_INFO_VERSION {
+            return Err(Error::UnsupportedVersion(u32::from(version)))
+        }
+
+        let mut names = Vec::with_capacity(4);
+        for _ in 0..4 {
+            names.push(match reader.read::<u16>()? {
+                0xFFFF => None,
+                len => Some(String::from_utf8(reader.bytes(len as usize)?.to_vec()).map_err(|_| Error::InvalidData)?),
+            });
+        }
+        let mut names = names.into_iter();
+        let metadata = FaceMetadata {
+            family: names.next().and_then(|name| name),
+            style: names.next().and_then(|name| name),
+            full_name: names.next().and_then(|name| name),
+            postscript_name: names.next().and_then(|name| name),
+            weight: reader.read()?,
+            width_class: reader.read()?,
+            italic: reader.read::<u8>()? != 0,
+            units_per_em: reader.read()?,
+            font_revision: f32::from_bits(reader.read()?),
+            num_glyphs: reader.read()?,
+        };
+
+        let page_count = reader.read::<u32>()? as usize;
+        let mut pages = Vec::with_capacity(page_count.min(data.len() / 36));
+        for _ in 0..page_count {
+            let page = reader.read::<u32>()?;
+            let mut words = [0; 4];
+            for word in &mut words {
+                *word = u64::from(reader.read::<u32>()?) << 32 | u64::from(reader.read::<u32>()?);
+            }
+            // Pages must be sorted, and are never empty.
+            if pages.last().is_some_and(|&(last, _)| last >= page) || words == [0; 4] {
+                return Err(Error::InvalidData)
+            }
+            pages.push((page, words));
+        }
+
+        let axis_count = reader.read::<u16>()?;
+        let axes = (0..axis_count)
+            .map(|_| Ok(AxisInfo {
+                tag: reader.read()?,
+                min: f32::from_bits(reader.read()?),
+                default: f32::from_bits(reader.read()?),
+                max: f32::from_bits(reader.read()?),
+            }))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        if reader.pos != data.len() {
+            return Err(Error::InvalidData)
+        }
+        Ok(FaceInfo { metadata, coverage: CharCoverage { pages }, axes })
+    }
+}
+
+#[cfg(feature = "variations")]
+fn read_axes(font: &Font) -> Result<Vec<AxisInfo>, Error> {
+    Ok(match font.optional_table::<Fvar>()? {
+        Some(fvar) => fvar.axes().into_iter()
+            .map(|axis| AxisInfo {
+                tag: axis.axis_tag,
+                min: f32::from(axis.min_value),
+                default: f32::from(axis.default_value),
+                max: f32::from(axis.max_value),
+            })
+            .collect(),
+        None => Vec::new(),
+    })
+}
+
+#[cfg(not(feature = "variations"))]
+fn read_axes(_font: &Font) -> Result<Vec<AxisInfo>, Error> {
+    Ok(Vec::new())
+}
+
+/// Reads the fields of a serialized `FaceInfo` in turn.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let bytes = self.data.get(self.pos..self.pos + len).ok_or(Error::UnexpectedEof)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn read<T: Decode<'a> + StaticEncodeSize>(&mut self) -> Result<T, Error> {
+        T::decode(self.bytes(T::size())?)
+    }
+}
+
+/// A font file to read, by path or already in memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FontSource<'a> {
+    Path(PathBuf),
+    Buffer(&'a [u8]),
+}
+
+impl<'a> From<&'a Path> for FontSource<'a> {
+    fn from(path: &'a Path) -> FontSource<'a> {
+        FontSource::Path(path.to_path_buf())
+    }
+}
+
+impl<'a> From<&'a [u8]> for FontSource<'a> {
+    fn from(buffer: &'a [u8]) -> FontSource<'a> {
+        FontSource::Buffer(buffer)
+    }
+}
+
+/// Why a font could not be ingested.
+#[derive(Debug)]
+pub enum IngestError {
+    Io(io::Error),
+    Font(Error),
+}
+
+impl fmt::Display for IngestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IngestError::Io(ref error) => write!(f, "{}", error),
+            IngestError::Font(ref error) => write!(f, "invalid font: {:?}", error),
+        }
+    }
+}
+
+impl ::std::error::Error for IngestError {}
+
+impl From<io::Error> for IngestError {
+    fn from(error: io::Error) -> IngestError {
+        IngestError::Io(error)
+    }
+}
+
+impl From<Error> for IngestError {
+    fn from(error: Error) -> IngestError {
+        IngestError::Font(error)
+    }
+}
+
+/// Read the metadata of the face in `source`, parsed permissively.
+pub fn parse_one(source: &FontSource) -> Result<FaceMetadata, IngestError> {
+    match *source {
+        FontSource::Path(ref path) => {
+            let data = fs::read(path)?;
+            Ok(FaceMetadata::read(&Font::decode(&data)?)?)
+        },
+        FontSource::Buffer(data) => Ok(FaceMetadata::read(&Font::decode(data)?)?),
+    }
+}
+
+/// Read the metadata of every face in `sources` in parallel, with the
+/// result for each source at its index.  A font which cannot be read does
+/// not stop the others.
+#[cfg(feature = "rayon")]
+pub fn parse_many(sources: &[FontSource]) -> Vec<Result<FaceMetadata, IngestError>> {
+    use rayon::prelude::*;
+
+    sources.par_iter().map(parse_one).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn face_metadata() {
+        let data = open_file!("data/DroidSerif-BoldItalic.ttf");
+        let metadata = parse_one(&FontSource::Buffer(&data)).unwrap();
+        assert_eq!(metadata.family.as_deref(), Some("Droid Serif"));
+        assert_eq!(metadata.style.as_deref(), Some("Bold Italic"));
+        assert_eq!((metadata.weight, metadata.italic, metadata.units_per_em), (700, true, 2048));
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/DroidSerif.ttf");
+        let regular = parse_one(&FontSource::from(path.as_path())).unwrap();
+        assert_eq!(regular.postscript_name.as_deref(), Some("DroidSerif"));
+        assert!(!regular.italic);
+
+        match parse_one(&FontSource::from(Path::new("data/missing.ttf"))) {
+            Err(IngestError::Io(error)) => assert_eq!(error.kind(), io::ErrorKind::NotFound),
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert!(matches!(parse_one(&FontSource::Buffer(&[0; 8])), Err(IngestError::Font(_))));
+    }
+
+    #[test]
+    fn face_info_round_trip() {
+        let data = open_file!("data/DroidSerif-BoldItalic.ttf");
+        let info = FaceInfo::read(&Font::decode(&data).unwrap()).unwrap();
+        assert!(info.coverage.contains('A') && info.coverage.contains('\u{E9}'));
+        assert!(!info.coverage.contains('\u{4E2D}'));
+        assert!(info.coverage.len() > 200);
+        assert!(info.axes.is_empty());
+
+        let bytes = info.to_bytes();
+        assert_eq!(&bytes[..6], b"FKFI\x00\x01");
+        assert_eq!(FaceInfo::from_bytes(&bytes), Ok(info.clone()));
+
+        let mut other = info.clone();
+        other.metadata.full_name = None;
+        other.axes.push(AxisInfo { tag: Tag::new(*b"wght"), min: 100.0, default: 400.0, max: 900.0 });
+        assert_eq!(FaceInfo::from_bytes(&other.to_bytes()), Ok(other));
+
+        assert_eq!(FaceInfo::from_bytes(&bytes[..bytes.len() - 1]).map(|_| ()), Err(Error::UnexpectedEof));
+        let mut future = bytes.clone();
+        future[5] = 2;
+        assert_eq!(FaceInfo::from_bytes(&future).map(|_| ()), Err(Error::UnsupportedVersion(2)));
+        assert_eq!(FaceInfo::from_bytes(b"TTCF\x00\x01").map(|_| ()), Err(Error::InvalidData));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parse_many_in_parallel() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("data");
+        let paths = ["DroidSerif.ttf", "DroidSerif-Bold.ttf", "missing.ttf", "OpenSans-Regular.ttf"]
+            .iter()
+            .map(|file| FontSource::Path(root.join(file)))
+            .collect::<Vec<_>>();
+        let results = parse_many(&paths);
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[1].as_ref().map(|metadata| metadata.weight).ok(), Some(700));
+        assert!(results[2].is_err());
+        assert_eq!(results[3].as_ref().ok().and_then(|metadata| metadata.family.clone()), Some("Open Sans".to_string()));
+    }
+}
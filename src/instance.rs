@@ -0,0 +1,308 @@
+//! This module provides access to a variable font at a particular
+//! position in its design space.
+
+use decode::Decode;
+use font::Font;
+use primitives::{GlyphId, Tag};
+use table::fvar::Fvar;
+use table::avar::Avar;
+use table::gvar::Gvar;
+use table::cvar::Cvar;
+use table::hvar::Hvar;
+use table::variation::{Location, NormalizedCoord};
+use table::glyf::{self, Glyf, SimpleGlyph};
+use table::TaggedTable;
+use error::{Error, Result};
+
+/// A variable font instanced at a set of normalized axis coordinates.
+#[derive(Clone)]
+pub struct InstancedFont<'a, 'fnt: 'a> {
+    font: &'a Font<'fnt>,
+    coords: Vec<NormalizedCoord>,
+}
+
+/// The horizontal metrics of a glyph at an instance, in font units, and
+/// the changes to its vertical metrics.  The crate does not read `vmtx`,
+/// so only the changes the `gvar` phantom points make to the vertical
+/// metrics are given.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct InstanceGlyphMetrics {
+    pub advance_width: f32,
+    pub left_side_bearing: f32,
+    pub right_side_bearing: f32,
+    pub top_side_bearing_delta: f32,
+    pub advance_height_delta: f32,
+}
+
+/// The `(x_min, x_max, y_max)` of the points of an outline, which are zero
+/// for an empty outline.
+type Bounds = (f32, f32, f32);
+
+/// The bounds of an outline at the default instance and at another, with
+/// the deltas of its phantom points there.
+struct VariedBounds {
+    default: Bounds,
+    varied: Bounds,
+    phantoms: [(f32, f32); 4],
+}
+
+impl<'a, 'fnt> InstancedFont<'a, 'fnt> {
+    /// Instance `font` at the given user-space axis values.  Axes which are
+    /// not given use their default value.
+    pub fn new(font: &'a Font<'fnt>, values: &[(Tag, f32)]) -> Result<InstancedFont<'a, 'fnt>> {
+        InstancedFont::at(font, &Location::from(values))
+    }
+
+    /// Instance `font` at a location in its design space.
+    pub fn at(font: &'a Font<'fnt>, location: &Location) -> Result<InstancedFont<'a, 'fnt>> {
+        let fvar = font.table::<Fvar>()?;
+        let avar = font.optional_table::<Avar>()?;
+        Ok(InstancedFont { font, coords: location.normalize(&fvar, avar.as_ref()) })
+    }
+
+    /// Instance `font` at coordinates which have already been normalized.
+    pub fn from_normalized(font: &'a Font<'fnt>, coords: Vec<NormalizedCoord>) -> InstancedFont<'a, 'fnt> {
+        InstancedFont { font, coords }
+    }
+
+    pub fn font(&self) -> &'a Font<'fnt> {
+        self.font
+    }
+
+    /// The normalized coordinates of this instance, in `fvar` axis order.
+    pub fn coords(&self) -> &[NormalizedCoord] {
+        &self.coords
+    }
+
+    /// The advance width of `glyph` at this instance.  The delta is taken
+    /// from `HVAR` when present, and otherwise from the `gvar` phantom points.
+    pub fn advance(&self, glyph: GlyphId) -> Result<f32> {
+        let hmtx = self.font.hmtx()?;
+        let advance = hmtx.advance(glyph).ok_or(Error::InvalidGlyphId)? as f32;
+        if self.coords.iter().all(|&coord| coord == NormalizedCoord::default()) {
+            return Ok(advance)
+        }
+
+        if let Some(hvar) = self.font.optional_table::<Hvar>()? {
+            return Ok(advance + hvar.advance_delta(glyph, &self.coords)?)
+        }
+
+        if let Some(gvar) = self.font.optional_table::<Gvar>()? {
+            let deltas = self.phantom_deltas(&gvar, glyph)?;
+            return Ok(advance + deltas[1].0 - deltas[0].0)
+        }
+
+        Ok(advance)
+    }
+
+    /// The metrics of `glyph` at this instance.
+    ///
+    /// The side bearings are measured from the phantom points of `gvar` to
+    /// the bounds of the varied outline points, as the advance is measured
+    /// between the phantom points.  The mappings of `HVAR` take precedence
+    /// for the advance and for each side bearing they cover.  Composite
+    /// and CFF outlines are not varied, so their bounds are those of the
+    /// default instance.
+    pub fn glyph_metrics(&self, glyph: GlyphId) -> Result<InstanceGlyphMetrics> {
+        let metrics = self.font.hmtx()?.metrics(glyph).ok_or(Error::InvalidGlyphId)?;
+        let (advance, lsb) = (f32::from(metrics.advance_width), f32::from(metrics.left_side_bearing));
+        let varied = self.coords.iter().any(|&coord| coord != NormalizedCoord::default());
+        let gvar = if varied { self.font.optional_table::<Gvar>()? } else { None };
+        let hvar = if varied { self.font.optional_table::<Hvar>()? } else { None };
+
+        let VariedBounds { default, varied, phantoms } = self.varied_bounds(glyph, gvar.as_ref())?;
+        let ((x_min, x_max, y_max), (varied_x_min, varied_x_max, varied_y_max)) = (default, varied);
+
+        // The first two phantom points are at the origin and the advance.
+        let origin = x_min - lsb + phantoms[0].0;
+        let end = x_min - lsb + advance + phantoms[1].0;
+        let mut metrics = InstanceGlyphMetrics {
+            advance_width: end - origin,
+            left_side_bearing: varied_x_min - origin,
+            right_side_bearing: end - varied_x_max,
+            top_side_bearing_delta: phantoms[2].1 - (varied_y_max - y_max),
+            advance_height_delta: phantoms[2].1 - phantoms[3].1,
+        };
+
+        if let Some(hvar) = hvar {
+            metrics.advance_width = advance + hvar.advance_delta(glyph, &self.coords)?;
+            if let Some(delta) = hvar.lsb_delta(glyph, &self.coords)? {
+                metrics.left_side_bearing = lsb + delta;
+            }
+            metrics.right_side_bearing = match hvar.rsb_delta(glyph, &self.coords)? {
+                Some(delta) => advance - lsb - (x_max - x_min) + delta,
+                None => metrics.advance_width - metrics.left_side_bearing - (varied_x_max - varied_x_min),
+            };
+        }
+
+        Ok(metrics)
+    }
+
+    /// The bounds of the outline points of `glyph` at the default instance
+    /// and at this one.
+    fn varied_bounds(&self, glyph: GlyphId, gvar: Option<&Gvar>) -> Result<VariedBounds> {
+        if self.font.table_record(Glyf::tag()).is_none() {
+            let bounds = self.font.glyph_bounds(glyph)?.map(|rect| (rect.x_min, rect.x_max, rect.y_max)).unwrap_or_default();
+            return Ok(VariedBounds { default: bounds, varied: bounds, phantoms: [(0.0, 0.0); 4] })
+        }
+
+        let loca = self.font.loca()?;
+        let data = self.font.table::<Glyf>()?.glyph_data(&loca, glyph)?;
+        let header = glyf::glyph_header(data)?;
+        let bounds = header.map(|header| (f32::from(header.x_min), f32::from(header.x_max), f32::from(header.y_max))).unwrap_or_default();
+        let gvar = match gvar {
+            Some(gvar) => gvar,
+            None => return Ok(VariedBounds { default: bounds, varied: bounds, phantoms: [(0.0, 0.0); 4] }),
+        };
+        if header.is_none_or(|header| header.number_of_contours < 0) {
+            let phantoms = gvar.phantom_deltas(glyph, glyf::num_points(data)?, &self.coords)?;
+            return Ok(VariedBounds { default: bounds, varied: bounds, phantoms })
+        }
+
+        let outline = SimpleGlyph::decode(data)?;
+        let deltas = gvar.glyph_deltas(glyph, &outline, &self.coords)?;
+        let varied = outline.points.iter()
+            .zip(&deltas)
+            .map(|(point, delta)| (point.x + delta.0, point.y + delta.1))
+            .fold(None, |bounds: Option<Bounds>, (x, y)| Some(match bounds {
+                Some((x_min, x_max, y_max)) => (x_min.min(x), x_max.max(x), y_max.max(y)),
+                None => (x, x, y),
+            }))
+            .unwrap_or_default();
+        let phantoms = &deltas[outline.points.len()..];
+        Ok(VariedBounds { default: bounds, varied, phantoms: [phantoms[0], phantoms[1], phantoms[2], phantoms[3]] })
+    }
+
+    /// The control values of the `cvt ` table at this instance, with the
+    /// `cvar` deltas applied.  Fonts without a `cvt ` table have none.
+    pub fn control_values(&self) -> Result<Vec<f32>> {
+        let data = match self.font.table_data(Tag(*b"cvt ")) {
+            Ok(data) => data,
+            Err(Error::MissingTable) => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+
+        let cvt = data.chunks(2)
+            .map(i16::decode)
+            .collect::<Result<Vec<_>>>()?;
+        match self.font.optional_table::<Cvar>()? {
+            Some(cvar) => cvar.apply(&cvt, &self.coords),
+            None => Ok(cvt.into_iter().map(|value| value as f32).collect()),
+        }
+    }
+
+    fn phantom_deltas(&self, gvar: &Gvar, glyph: GlyphId) -> Result<[(f32, f32); 4]> {
+        let loca = self.font.loca()?;
+        let data = self.font.table::<Glyf>()?.glyph_data(&loca, glyph)?;
+        gvar.phantom_deltas(glyph, glyf::num_points(data)?, &self.coords)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_fonts;
+
+    #[test]
+    fn phantom_point_advances_match_hvar() {
+        let data = open_file!("data/fonttest/TestHVARTwo.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let gvar = font.table::<Gvar>().expect("failed to read gvar");
+        let hvar = font.table::<Hvar>().expect("failed to read HVAR");
+        let hmtx = font.hmtx().unwrap();
+
+        for &coord in &[-1.0, -0.5, 0.25, 1.0] {
+            let coord = NormalizedCoord::new(coord);
+            let instance = InstancedFont::from_normalized(&font, vec![coord]);
+            for gid in 0..hmtx.num_glyphs() as u16 {
+                let glyph = GlyphId(gid);
+                let deltas = instance.phantom_deltas(&gvar, glyph).unwrap();
+                let expected = hvar.advance_delta(glyph, &[coord]).unwrap();
+                assert!((deltas[1].0 - deltas[0].0 - expected).abs() < 0.5);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_phantom_point_advances() {
+        let data = test_fonts::variable_font().build();
+        let font = Font::decode(&data).expect("failed to read font");
+        let weight = Tag(*b"wght");
+
+        let expected = [(400.0, 700.0), (650.0, 750.0), (900.0, 800.0), (100.0, 700.0)];
+        for &(value, advance) in &expected {
+            let instance = InstancedFont::new(&font, &[(weight, value)]).unwrap();
+            assert_eq!(instance.advance(GlyphId(1)), Ok(advance));
+            assert_eq!(instance.advance(GlyphId(2)), Ok(700.0));
+        }
+    }
+
+    #[test]
+    fn advance_without_hvar() {
+        let data = open_file!("data/Mada/Mada-VF.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        assert!(font.optional_table::<Hvar>().unwrap().is_none());
+
+        let fvar = font.table::<Fvar>().unwrap();
+        let weight = fvar.axes().get(0).unwrap();
+        let default = InstancedFont::new(&font, &[]).unwrap();
+        let heavy = InstancedFont::new(&font, &[(weight.axis_tag, f32::from(weight.max_value))]).unwrap();
+        assert_eq!(heavy.coords()[0].to_f32(), 1.0);
+
+        let changed = (0..100).map(GlyphId)
+            .filter(|&glyph| default.advance(glyph).unwrap() != heavy.advance(glyph).unwrap())
+            .count();
+        assert!(changed > 0);
+    }
+
+    #[test]
+    fn varied_glyph_metrics() {
+        let data = test_fonts::variable_font().build();
+        let font = Font::decode(&data).expect("failed to read font");
+        let metrics = |value, glyph| {
+            let instance = InstancedFont::new(&font, &[(Tag(*b"wght"), value)]).unwrap();
+            let metrics = instance.glyph_metrics(GlyphId(glyph)).unwrap();
+            (metrics.advance_width, metrics.left_side_bearing, metrics.right_side_bearing)
+        };
+
+        // The square spans 100 to 600 of an advance of 700, and at the
+        // heaviest weight its right edge moves by 200 and its advance by
+        // 100.
+        assert_eq!(metrics(400.0, 1), (700.0, 100.0, 100.0));
+        assert_eq!(metrics(900.0, 1), (800.0, 100.0, 0.0));
+        assert_eq!(metrics(650.0, 1), (750.0, 100.0, 50.0));
+        assert_eq!(metrics(900.0, 2), (700.0, 50.0, 150.0));
+
+        let instance = InstancedFont::new(&font, &[(Tag(*b"wght"), 900.0)]).unwrap();
+        assert_eq!(instance.glyph_metrics(GlyphId(1)).unwrap().advance_width, instance.advance(GlyphId(1)).unwrap());
+        assert_eq!(instance.glyph_metrics(GlyphId(3)), Err(Error::InvalidGlyphId));
+    }
+
+    #[test]
+    fn control_values() {
+        // One tuple at the maximum weight, with deltas for values 0 and 2.
+        let cvar = vec![
+            0, 1, 0, 0, 0, 1, 0, 14,
+            0, 7, 0xA0, 0, 0x40, 0,
+            2, 1, 0, 2,
+            1, 10, 0xEC,
+        ];
+        let data = test_fonts::variable_font()
+            .table(b"cvt ", vec![0, 100, 0, 200, 1, 44])
+            .table(b"cvar", cvar)
+            .build();
+        let font = Font::decode(&data).expect("failed to read font");
+        let weight = Tag(*b"wght");
+
+        let default = InstancedFont::new(&font, &[]).unwrap();
+        assert_eq!(default.control_values(), Ok(vec![100.0, 200.0, 300.0]));
+        let heavy = InstancedFont::new(&font, &[(weight, 900.0)]).unwrap();
+        assert_eq!(heavy.control_values(), Ok(vec![110.0, 200.0, 280.0]));
+        let medium = InstancedFont::new(&font, &[(weight, 650.0)]).unwrap();
+        assert_eq!(medium.control_values(), Ok(vec![105.0, 200.0, 290.0]));
+
+        let data = test_fonts::variable_font().build();
+        let font = Font::decode(&data).unwrap();
+        assert_eq!(InstancedFont::new(&font, &[]).unwrap().control_values(), Ok(Vec::new()));
+    }
+}
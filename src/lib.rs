@@ -1,13 +1,118 @@
+// Every read goes through `byteorder` on bounds-checked slices, so fonts
+// may be decoded from buffers of any alignment or length.  The decoding
+// modules forbid `unsafe` outright; an opt-in fast path would need to
+// allow it explicitly in its own module.
+#![deny(unsafe_code)]
+
 #[macro_use]
 extern crate derive_more;
 extern crate byteorder;
 #[macro_use]
 extern crate decode_derive;
+#[cfg(feature = "kurbo")]
+extern crate kurbo;
+#[cfg(feature = "lyon")]
+extern crate lyon_path;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "harfbuzz")]
+extern crate harfbuzz_rs;
+#[cfg(test)]
+extern crate proptest;
 
 #[macro_use]
 mod macros;
+pub mod prelude;
 pub mod table;
 pub mod primitives;
+pub mod fixed;
+pub mod tags;
 pub mod error;
 pub mod decode;
-pub mod font;
\ No newline at end of file
+pub mod encode;
+pub mod font;
+pub mod pdf;
+pub mod outline;
+pub mod svg_path;
+pub mod unicode;
+#[cfg(feature = "layout")]
+pub mod shape;
+#[cfg(feature = "layout")]
+pub mod emoji;
+#[cfg(feature = "layout")]
+pub mod measure;
+#[cfg(feature = "layout")]
+pub mod fea;
+#[cfg(feature = "harfbuzz")]
+pub mod harfbuzz;
+#[cfg(feature = "variations")]
+pub mod instance;
+#[cfg(feature = "color")]
+pub mod color;
+pub mod metrics;
+pub mod glyph_map;
+pub mod glyph_set;
+pub mod glyph_key;
+#[cfg(feature = "raster")]
+pub mod raster;
+#[cfg(all(feature = "raster", feature = "color"))]
+pub mod color_raster;
+pub mod writer;
+pub mod merge;
+pub mod fallback;
+pub mod validate;
+pub mod warning;
+pub mod provenance;
+pub mod family;
+pub mod ingest;
+pub mod web;
+pub mod stats;
+
+#[cfg(test)]
+mod test_fonts;
+
+#[cfg(test)]
+mod tests {
+    use font::{Font, TableDirectory};
+    use primitives::{Array, GlyphId};
+    use table::{cmap, glyf, head, hhea, hmtx, kern, loca, maxp, name, os2, post};
+    use fallback::FallbackChain;
+
+    fn assert_shared<T: Clone + Send + Sync>() {}
+    fn assert_copied<T: Copy + Send + Sync>() {}
+
+    #[test]
+    fn thread_safe_types() {
+        assert_shared::<Font>();
+        assert_shared::<FallbackChain>();
+        assert_shared::<kern::Kern>();
+        assert_copied::<TableDirectory>();
+        assert_copied::<Array<GlyphId>>();
+        assert_copied::<(head::Head, hhea::Hhea, maxp::Maxp, os2::Os2, post::Post)>();
+        assert_copied::<(hmtx::Hmtx, loca::Loca, glyf::Glyf, cmap::Cmap, cmap::CmapSubtable, name::Name)>();
+        #[cfg(feature = "cff")]
+        assert_shared::<::table::cff::Cff>();
+        #[cfg(feature = "layout")]
+        {
+            use table::{base, layout, math};
+            use shape::ShapeCache;
+            assert_shared::<ShapeCache>();
+            assert_copied::<(layout::Gsub, layout::Gpos, layout::Coverage, base::Base, math::Math)>();
+        }
+        #[cfg(feature = "variations")]
+        {
+            use table::{avar, cvar, fvar, gvar, hvar, mvar, stat};
+            assert_shared::<(avar::Avar, stat::Stat)>();
+            assert_copied::<(fvar::Fvar, gvar::Gvar, cvar::Cvar, hvar::Hvar, mvar::Mvar)>();
+        }
+        #[cfg(feature = "color")]
+        {
+            use table::{cblc, colr, cpal, ebsc, sbix, svg};
+            assert_copied::<(colr::Colr, cpal::Cpal, svg::Svg, sbix::Sbix, cblc::Cblc, cblc::Eblc, ebsc::Ebsc)>();
+        }
+        #[cfg(feature = "ift")]
+        assert_copied::<(::table::ift::Ift, ::table::ift::Iftx)>();
+        #[cfg(feature = "aat")]
+        assert_copied::<::table::feat::Feat>();
+    }
+}
@@ -10,9 +10,12 @@ macro_rules! static_size {
     )
 }
 
+/// Return `Error::UnexpectedEof` if `buffer` is shorter than `len`,
+/// noting where the read failed for `provenance`.
 macro_rules! required_len {
     ($buffer:expr, $len:expr) => (
         if $buffer.len() < $len {
+            ::provenance::record_failure(&$buffer[..]);
             return Err(Error::UnexpectedEof)
         }
     )
@@ -35,35 +38,38 @@ macro_rules! open_file {
     })
 }
 
-// macro_rules! versioned_table {
-//     (@match $var:expr, $($dst:ty = $tag:expr;)*) => {
-//         match $var {
-//             $(
-//                 $tag => { panic!(stringify!($dst)) },
-//             )*
-//             _ => panic!("No match"),
-//         }
-//     };
-//
-//     (@match $($tt:tt)*) => {
-//         panic!(stringify!($($tt)*));
-//     };
-//
-//     ($name:ty, $ty:ty => $($tt:tt)*) => {
-//         impl<'fnt> Decode<'fnt> for $name {
-//             fn decode(buffer: &'fnt [u8]) -> Result<Self> {
-//                 let tag = <$ty as Decode> :: decode(buffer)?;
-//
-//                 //panic!(stringify!(@match $bl));
-//                 versioned_table!(@match tag, $($tt)*);
-//                 unimplemented!()
-//             }
-//         }
-//     };
-// }
-//
-// versioned_table! {Maxp,
-//     u32 =>
-//         Version05 = 0x00005000;
-//         Version1  = 0x00010000;
-// }
\ No newline at end of file
+/// Implement `Decode` for an enum of table versions, dispatching on a
+/// leading version field of type `$version`.  Each variant is decoded from
+/// the start of the table, including the version field.
+///
+/// ```text
+/// versioned_table!(Maxp, u32 =>
+///     0x00005000 => Version05(Version05),
+///     0x00010000 => Version1(Version1),
+/// );
+/// ```
+macro_rules! versioned_table {
+    ($name:ident<$lt:lifetime>, $version:ty => $($value:expr => $variant:ident($inner:ty)),* $(,)*) => (
+        impl<$lt> Decode<$lt> for $name<$lt> {
+            fn decode(buffer: &$lt [u8]) -> Result<$name<$lt>> {
+                let version = <$version as Decode>::decode(buffer)?;
+                match version {
+                    $( $value => Ok($name::$variant(<$inner as Decode>::decode(buffer)?)), )*
+                    _ => Err(Error::UnsupportedVersion(version.into())),
+                }
+            }
+        }
+    );
+
+    ($name:ident, $version:ty => $($value:expr => $variant:ident($inner:ty)),* $(,)*) => (
+        impl<'fnt> Decode<'fnt> for $name {
+            fn decode(buffer: &'fnt [u8]) -> Result<$name> {
+                let version = <$version as Decode>::decode(buffer)?;
+                match version {
+                    $( $value => Ok($name::$variant(<$inner as Decode>::decode(buffer)?)), )*
+                    _ => Err(Error::UnsupportedVersion(version.into())),
+                }
+            }
+        }
+    );
+}
@@ -0,0 +1,344 @@
+//! This module measures runs of text, which is most of what an
+//! application asks of a font before drawing: how wide a label is, where
+//! the caret goes after each character and how far the ink extends.
+//!
+//! Text is shaped with `shape_simple`, so the same limits apply: scripts
+//! which need reordering or contextual forms are not measured correctly.
+//!
+//! `Font::measure_fixed` measures in integer 26.6 fixed point instead of
+//! floats, so that a document paginates identically on every platform.
+
+use std::ops::Range;
+
+use fixed::F26Dot6;
+use font::Font;
+use metrics::{LineMetricsSource, LineMetricsStrategy};
+use outline::Rect;
+use primitives::Tag;
+use shape::{self, PositionedGlyph};
+use tags::features::{self, KERN};
+use table::head::Head;
+use table::kern::Kern;
+use table::layout::Gpos;
+use error::{Error, Result};
+
+/// The features text is measured with by default, besides kerning.
+pub const MEASURE_FEATURES: [Tag; 2] = [features::CCMP, features::LIGA];
+
+/// How `Font::measure` lays out text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeasureOptions<'a> {
+    /// Apply the `kern` feature of `GPOS`, or the `kern` table when
+    /// `GPOS` has no kerning.
+    pub kerning: bool,
+    /// Space added after every cluster but the last, in pixels.  It may
+    /// be negative to tighten text.
+    pub letter_spacing: f32,
+    /// The `GSUB` and `GPOS` features to apply, besides `kern`.
+    pub features: &'a [Tag],
+}
+
+impl<'a> Default for MeasureOptions<'a> {
+    fn default() -> MeasureOptions<'a> {
+        MeasureOptions { kerning: true, letter_spacing: 0.0, features: &MEASURE_FEATURES }
+    }
+}
+
+/// The extent of a run of text, in pixels with the y-axis pointing up and
+/// the origin at the start of the run on the baseline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    /// The distance from the start of the run to the pen position after
+    /// its last glyph.
+    pub advance: f32,
+    /// The bounds of the ink, or `None` if no glyph has an outline.
+    pub bounds: Option<Rect>,
+    /// The clusters of the text, in order.
+    pub clusters: Vec<ClusterPosition>,
+}
+
+/// The place of one cluster: a grapheme, or several graphemes which were
+/// shaped into a ligature.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterPosition {
+    /// The byte range of the cluster in the text.
+    pub range: Range<usize>,
+    /// The pen position at the start of the cluster.
+    pub x: f32,
+    /// The advance of the cluster, including letter spacing after it.
+    pub advance: f32,
+}
+
+/// The extent of a run of text as `Font::measure_fixed` gives it, in
+/// pixels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedMeasurement {
+    pub advance: F26Dot6,
+    pub clusters: Vec<FixedClusterPosition>,
+}
+
+/// The place of one cluster, in pixels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedClusterPosition {
+    pub range: Range<usize>,
+    pub x: F26Dot6,
+    pub advance: F26Dot6,
+}
+
+/// The line metrics as `Font::line_metrics_fixed` gives them, in pixels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FixedLineMetrics {
+    pub ascender: F26Dot6,
+    pub descender: F26Dot6,
+    pub line_gap: F26Dot6,
+    pub source: LineMetricsSource,
+}
+
+impl FixedLineMetrics {
+    /// The distance between the baselines of consecutive lines.
+    pub fn line_height(&self) -> F26Dot6 {
+        self.ascender - self.descender + self.line_gap
+    }
+}
+
+impl<'fnt> Font<'fnt> {
+    /// Measure `text` at `size` pixels per em.
+    pub fn measure(&self, text: &str, size: f32, options: &MeasureOptions) -> Result<Measurement> {
+        let glyphs = self.measured_glyphs(text, options)?;
+        let units_per_em = self.table::<Head>()?.units_per_em;
+        if units_per_em == 0 {
+            return Err(Error::InvalidData)
+        }
+        let scale = size / f32::from(units_per_em);
+
+        let mut clusters: Vec<ClusterPosition> = Vec::new();
+        let mut bounds: Option<Rect> = None;
+        let mut pen = 0;
+        // The letter spacing added before the current cluster.
+        let mut spacing = 0.0;
+        for (index, glyph) in glyphs.iter().enumerate() {
+            if clusters.last().is_none_or(|cluster| cluster.range.start != glyph.cluster) {
+                if let Some(last) = clusters.last_mut() {
+                    last.advance += options.letter_spacing;
+                    spacing += options.letter_spacing;
+                }
+
+                // A ligature's cluster extends to the next glyph's.
+                let end = glyphs[index..].iter()
+                    .map(|next| next.cluster)
+                    .find(|&cluster| cluster != glyph.cluster)
+                    .unwrap_or(text.len());
+                clusters.push(ClusterPosition { range: glyph.cluster..end, x: pen as f32 * scale + spacing, advance: 0.0 });
+            }
+
+            if let Some(ink) = self.placed_bounds(glyph, pen as f32 * scale + spacing, scale)? {
+                bounds = Some(match bounds {
+                    Some(bounds) => Rect {
+                        x_min: bounds.x_min.min(ink.x_min),
+                        y_min: bounds.y_min.min(ink.y_min),
+                        x_max: bounds.x_max.max(ink.x_max),
+                        y_max: bounds.y_max.max(ink.y_max),
+                    },
+                    None => ink,
+                });
+            }
+
+            if let Some(cluster) = clusters.last_mut() {
+                cluster.advance += glyph.x_advance as f32 * scale;
+            }
+            pen += glyph.x_advance;
+        }
+
+        Ok(Measurement { advance: pen as f32 * scale + spacing, bounds, clusters })
+    }
+
+    /// Measure `text` at `ppem` pixels per em, in 26.6 fixed point.
+    ///
+    /// The result depends only on the font, the text and the options, not
+    /// on hinting or float rounding.  Each glyph advance is scaled and
+    /// rounded to the nearest 64th of a pixel on its own, in integer
+    /// arithmetic, and positions are sums of rounded advances, so a
+    /// cluster measures the same wherever it is in a line.  The letter
+    /// spacing is rounded to a 64th of a pixel once.  Ink bounds depend
+    /// on evaluating curves and are left out.
+    pub fn measure_fixed(&self, text: &str, ppem: F26Dot6, options: &MeasureOptions) -> Result<FixedMeasurement> {
+        let glyphs = self.measured_glyphs(text, options)?;
+        let units_per_em = self.table::<Head>()?.units_per_em;
+        if units_per_em == 0 {
+            return Err(Error::InvalidData)
+        }
+        let spacing = F26Dot6::from_f32(options.letter_spacing);
+
+        let mut clusters: Vec<FixedClusterPosition> = Vec::new();
+        let mut pen = F26Dot6::ZERO;
+        for (index, glyph) in glyphs.iter().enumerate() {
+            if clusters.last().is_none_or(|cluster| cluster.range.start != glyph.cluster) {
+                if let Some(last) = clusters.last_mut() {
+                    last.advance += spacing;
+                    pen += spacing;
+                }
+
+                let end = glyphs[index..].iter()
+                    .map(|next| next.cluster)
+                    .find(|&cluster| cluster != glyph.cluster)
+                    .unwrap_or(text.len());
+                clusters.push(FixedClusterPosition { range: glyph.cluster..end, x: pen, advance: F26Dot6::ZERO });
+            }
+
+            let advance = F26Dot6::from_font_units(glyph.x_advance, ppem, units_per_em);
+            if let Some(cluster) = clusters.last_mut() {
+                cluster.advance += advance;
+            }
+            pen += advance;
+        }
+
+        Ok(FixedMeasurement { advance: pen, clusters })
+    }
+
+    /// The line metrics chosen by `strategy` at `ppem` pixels per em, each
+    /// rounded as `measure_fixed` rounds advances, so that line heights
+    /// are exact sums.
+    pub fn line_metrics_fixed(&self, strategy: LineMetricsStrategy, ppem: F26Dot6) -> Result<FixedLineMetrics> {
+        let metrics = self.line_metrics(strategy)?;
+        let units_per_em = self.table::<Head>()?.units_per_em;
+        if units_per_em == 0 {
+            return Err(Error::InvalidData)
+        }
+        Ok(FixedLineMetrics {
+            ascender: F26Dot6::from_font_units(metrics.ascender, ppem, units_per_em),
+            descender: F26Dot6::from_font_units(metrics.descender, ppem, units_per_em),
+            line_gap: F26Dot6::from_font_units(metrics.line_gap, ppem, units_per_em),
+            source: metrics.source,
+        })
+    }
+
+    /// The glyphs of `text` shaped and kerned as `options` asks.
+    fn measured_glyphs(&self, text: &str, options: &MeasureOptions) -> Result<Vec<PositionedGlyph>> {
+        let mut features = options.features.iter().cloned().filter(|&feature| feature != KERN).collect::<Vec<_>>();
+        if options.kerning {
+            features.push(KERN);
+        }
+
+        let mut glyphs = shape::shape_simple(self, text, &features)?;
+        if options.kerning && !self.has_gpos_kerning()? {
+            if let Some(kern) = self.optional_table::<Kern>()? {
+                for index in 1..glyphs.len() {
+                    glyphs[index - 1].x_advance += kern.kerning(glyphs[index - 1].glyph, glyphs[index].glyph)?;
+                }
+            }
+        }
+        Ok(glyphs)
+    }
+
+    fn has_gpos_kerning(&self) -> Result<bool> {
+        match self.optional_table::<Gpos>()? {
+            Some(gpos) => Ok(gpos.features()?.tags().any(|tag| tag == KERN)),
+            None => Ok(false),
+        }
+    }
+
+    /// The bounds of `glyph` in pixels, with its origin at `origin`.
+    /// Fonts without outlines have no bounds.
+    fn placed_bounds(&self, glyph: &PositionedGlyph, origin: f32, scale: f32) -> Result<Option<Rect>> {
+        let bounds = match self.glyph_bounds(glyph.glyph) {
+            Ok(bounds) => bounds,
+            Err(Error::MissingTable) => None,
+            Err(err) => return Err(err),
+        };
+
+        let x = origin + glyph.x_offset as f32 * scale;
+        let y = glyph.y_offset as f32 * scale;
+        Ok(bounds.map(|bounds| Rect {
+            x_min: x + bounds.x_min * scale,
+            y_min: y + bounds.y_min * scale,
+            x_max: x + bounds.x_max * scale,
+            y_max: y + bounds.y_max * scale,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+    use test_fonts;
+
+    #[test]
+    fn measure_text() {
+        // Glyph 3 is the ligature of `f` and `i`, and `A` kerns against it
+        // in `GPOS`.  The legacy table kerns `A` and `f` as well, but is
+        // not used when `GPOS` kerns.
+        let gsub = test_fonts::layout_table(b"latn", &[(b"liga", &[0])], &[
+            (4, test_fonts::ligature_substitution(&[1, 2], 3)),
+        ]);
+        let gpos = test_fonts::layout_table(b"latn", &[(b"kern", &[0])], &[
+            (2, test_fonts::class_pair_adjustment(&[4], &[3], -50)),
+        ]);
+        let glyphs = [
+            Vec::new(),
+            test_fonts::simple_glyph(&[&[(0, 0), (0, 500), (400, 500), (400, 0)]]),
+            test_fonts::simple_glyph(&[&[(0, -100), (0, 700), (300, 700), (300, -100)]]),
+            test_fonts::simple_glyph(&[&[(0, 0), (0, 700), (650, 700), (650, 0)]]),
+            test_fonts::simple_glyph(&[&[(50, 0), (350, 700), (650, 0)]]),
+        ];
+        let (glyf, loca) = test_fonts::glyf_and_loca(&glyphs, false);
+        let builder = test_fonts::minimal_font(false)
+            .table(b"hhea", test_fonts::hhea(800, -200, 5))
+            .table(b"maxp", test_fonts::maxp(5))
+            .table(b"hmtx", test_fonts::hmtx(&[(500, 0), (400, 0), (300, 0), (650, 0), (700, 0)], &[]))
+            .table(b"glyf", glyf)
+            .table(b"loca", loca)
+            .table(b"cmap", test_fonts::cmap(&[('f', 1), ('i', 2), ('A', 4)]))
+            .table(b"kern", test_fonts::kern(&[(4, 1, -100)]))
+            .table(b"GSUB", gsub);
+        let data = builder.clone().table(b"GPOS", gpos).build();
+        let font = Font::decode(&data).unwrap();
+
+        let measured = font.measure("Afi", 10.0, &MeasureOptions::default()).unwrap();
+        assert_eq!(measured.advance, 6.5 + 6.5);
+        assert_eq!(measured.clusters, vec![
+            ClusterPosition { range: 0..1, x: 0.0, advance: 6.5 },
+            ClusterPosition { range: 1..3, x: 6.5, advance: 6.5 },
+        ]);
+        assert_eq!(measured.bounds, Some(Rect { x_min: 0.5, y_min: 0.0, x_max: 13.0, y_max: 7.0 }));
+
+        let options = MeasureOptions { kerning: false, letter_spacing: 1.0, features: &[] };
+        let measured = font.measure("Afi", 10.0, &options).unwrap();
+        assert_eq!(measured.advance, 7.0 + 4.0 + 3.0 + 2.0);
+        let clusters = measured.clusters.iter().map(|cluster| (cluster.range.clone(), cluster.x, cluster.advance)).collect::<Vec<_>>();
+        assert_eq!(clusters, vec![(0..1, 0.0, 8.0), (1..2, 8.0, 5.0), (2..3, 13.0, 3.0)]);
+        assert_eq!(measured.bounds.map(|bounds| bounds.y_min), Some(-1.0));
+
+        // Without `GPOS`, `A` and `f` kern through the `kern` table.
+        let data = builder.build();
+        let font = Font::decode(&data).unwrap();
+        let options = MeasureOptions { features: &[], ..MeasureOptions::default() };
+        assert_eq!(font.measure("Af", 10.0, &options).map(|measured| measured.advance), Ok(6.0 + 4.0));
+        assert_eq!(font.measure("", 10.0, &options), Ok(Measurement { advance: 0.0, bounds: None, clusters: Vec::new() }));
+    }
+
+    #[test]
+    fn measure_fixed_point() {
+        let data = test_fonts::minimal_font(false)
+            .table(b"hhea", test_fonts::hhea(800, -200, 3))
+            .table(b"maxp", test_fonts::maxp(3))
+            .table(b"hmtx", test_fonts::hmtx(&[(500, 0), (333, 0), (667, 0)], &[]))
+            .table(b"cmap", test_fonts::cmap(&[('i', 1), ('m', 2)]))
+            .build();
+        let font = Font::decode(&data).unwrap();
+        let options = MeasureOptions { letter_spacing: 0.5, ..MeasureOptions::default() };
+        let ppem = F26Dot6::from_int(12);
+        let measured = font.measure_fixed("imi", ppem, &options).unwrap();
+        // In 64ths of a pixel, each `i` is 255.744 rounded to 256 and the
+        // `m` 512.256 rounded to 512.
+        let clusters = measured.clusters.iter().map(|cluster| (cluster.x.to_bits(), cluster.advance.to_bits())).collect::<Vec<_>>();
+        assert_eq!(clusters, vec![(0, 256 + 32), (288, 512 + 32), (832, 256)]);
+        assert_eq!(measured.advance, F26Dot6::from_bits(1088));
+        assert_eq!(font.measure_fixed("", ppem, &options).map(|measured| measured.advance), Ok(F26Dot6::ZERO));
+
+        let line = font.line_metrics_fixed(LineMetricsStrategy::HheaOnly, ppem).unwrap();
+        let metrics = (line.ascender.to_bits(), line.descender.to_bits(), line.line_gap.to_bits());
+        assert_eq!(metrics, (614, -154, 0));
+        assert_eq!(line.line_height(), F26Dot6::from_bits(768));
+    }
+}
@@ -0,0 +1,297 @@
+//! This module combines two TrueType fonts into one, such as a Latin font
+//! and an extension covering another script.  The glyphs of the extension
+//! are appended after those of the base font, and every other table is
+//! taken from the base font.  That includes the hinting programs `fpgm`,
+//! `prep` and `cvt `, so the glyphs of the extension lose their
+//! instructions, which were written for the extension's programs.
+
+use std::collections::BTreeMap;
+
+use font::Font;
+use glyph_map::GlyphMap;
+use primitives::{GlyphId, Tag};
+use table::TaggedTable;
+use table::cmap::{self, Cmap};
+use table::glyf::{self, Glyf};
+use table::head::Head;
+use table::hhea::Hhea;
+use table::hmtx::Hmtx;
+use table::maxp::Maxp;
+use table::os2::Os2;
+use table::post::Post;
+use encode::EncodeBuf;
+use writer::FontWriter;
+use error::{Error, Result};
+
+/// Which font's glyph is kept when both map the same code point.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    PreferBase,
+    PreferExtension,
+}
+
+/// Tables which refer to glyphs but are not merged.  Keeping the base
+/// font's copy would leave them inconsistent with the merged glyph set.
+/// `DSIG` is left out by the writer.
+const DROPPED_TABLES: [&[u8; 4]; 20] = [
+    b"GSUB", b"GPOS", b"GDEF", b"BASE", b"JSTF", b"MATH", b"kern", b"hdmx", b"LTSH", b"VDMX",
+    b"vhea", b"vmtx", b"gvar", b"HVAR", b"VVAR", b"COLR", b"SVG ", b"sbix", b"CBDT", b"CBLC",
+];
+
+/// The offsets in a version 1 `maxp` of `maxPoints`, `maxContours`,
+/// `maxCompositePoints`, `maxCompositeContours`, `maxComponentElements`
+/// and `maxComponentDepth`.
+const MAXP_OUTLINE_LIMITS: [usize; 6] = [6, 8, 10, 12, 28, 30];
+
+/// Merge `extension` into `base`, returning the new font file.
+///
+/// The `.notdef` glyph of the extension is dropped, and its other glyphs
+/// are numbered after those of the base font.  The `glyf`, `loca`, `hmtx`
+/// and `cmap` tables are rebuilt, the counts and bounds in `head`, `hhea`
+/// and `maxp` are updated, and `post` is written without glyph names.
+/// The glyphs of the extension are unhinted in the merged font.
+/// Tables which index glyphs but are not merged, such as `GSUB`, are left
+/// out.
+pub fn merge(base: &Font, extension: &Font, policy: ConflictPolicy) -> Result<Vec<u8>> {
+    let base_glyphs = base.table::<Maxp>()?.get_num_glyphs();
+    let extension_glyphs = extension.table::<Maxp>()?.get_num_glyphs();
+    if base_glyphs as usize + extension_glyphs.saturating_sub(1) as usize > 0xFFFF {
+        return Err(Error::InvalidData)
+    }
+
+    let base_order = (0..base_glyphs).map(GlyphId).collect::<Vec<_>>();
+    let base_map = GlyphMap::from_order(&base_order)?;
+    let mut extension_map = GlyphMap::with_first_id(GlyphId(base_glyphs));
+    for glyph in 1..extension_glyphs {
+        extension_map.insert(GlyphId(glyph))?;
+    }
+    let sources = [(base, &base_map), (extension, &extension_map)];
+
+    let mut writer = FontWriter::new();
+    for record in base.table_directory().tables() {
+        if !DROPPED_TABLES.contains(&&record.tag.bytes()) {
+            writer.table(record.tag, base.table_data(record.tag)?.to_vec());
+        }
+    }
+
+    // Glyph outlines, with the components of composites renumbered.
+    let mut glyf = Vec::new();
+    let mut offsets = Vec::new();
+    for (index, &(font, map)) in sources.iter().enumerate() {
+        let loca = font.loca()?;
+        let glyph_data = font.table::<Glyf>()?;
+        for (old, _) in map.iter() {
+            offsets.push(glyf.len());
+            let data = map.remap_glyph(glyph_data.glyph_data(&loca, old)?)?;
+            if index == 0 {
+                glyf.extend(data);
+            } else {
+                glyf.extend(glyf::strip_instructions(&data)?);
+            }
+            while glyf.len() % 4 != 0 {
+                glyf.push(0);
+            }
+        }
+    }
+    offsets.push(glyf.len());
+
+    let long_loca = glyf.len() > 0x1FFFE;
+    let mut loca = EncodeBuf::new();
+    for offset in offsets {
+        if long_loca {
+            loca.put_u32(offset as u32);
+        } else {
+            loca.put_u16((offset / 2) as u16);
+        }
+    }
+
+    let mut metrics = Vec::new();
+    for &(font, map) in &sources {
+        metrics.extend(map.remap_hmtx(&font.hmtx()?)?);
+    }
+
+    let (hmtx, num_long_metrics) = Hmtx::encode(&metrics);
+
+    // Code points are assigned in order, so the preferred font goes last.
+    let mut mappings = BTreeMap::new();
+    let ordered = match policy {
+        ConflictPolicy::PreferBase => [sources[1], sources[0]],
+        ConflictPolicy::PreferExtension => sources,
+    };
+    for &(font, map) in &ordered {
+        if let Some(subtable) = font.table::<Cmap>()?.unicode_subtable()? {
+            mappings.extend(map.remap_cmap(&subtable.mappings()));
+        }
+    }
+    let mappings = mappings.into_iter().collect::<Vec<_>>();
+
+    let num_glyphs = metrics.len() as u16;
+    let base_head = base.table::<Head>()?;
+    let extension_head = extension.table::<Head>()?;
+    let mut head = base.table_data(Head::tag())?.to_vec();
+    set_u16(&mut head, 36, base_head.x_min.min(extension_head.x_min) as u16)?;
+    set_u16(&mut head, 38, base_head.y_min.min(extension_head.y_min) as u16)?;
+    set_u16(&mut head, 40, base_head.x_max.max(extension_head.x_max) as u16)?;
+    set_u16(&mut head, 42, base_head.y_max.max(extension_head.y_max) as u16)?;
+    set_u16(&mut head, 50, long_loca as u16)?;
+
+    let mut hhea = base.table_data(Hhea::tag())?.to_vec();
+    let advance_width_max = metrics.iter().map(|metric| metric.advance_width).max().unwrap_or(0);
+    set_u16(&mut hhea, 10, advance_width_max)?;
+    set_u16(&mut hhea, 34, num_long_metrics)?;
+
+    // Version 1 `maxp` outline limits apply to every glyph, so take the
+    // larger.  The hinting limits are those of the base font's programs,
+    // since only its glyphs keep their instructions.
+    let mut maxp = base.table_data(Maxp::tag())?.to_vec();
+    let extension_maxp = extension.table_data(Maxp::tag())?;
+    set_u16(&mut maxp, 4, num_glyphs)?;
+    if maxp.len() >= 32 && extension_maxp.len() >= 32 {
+        for &pos in &MAXP_OUTLINE_LIMITS {
+            let value = get_u16(&maxp, pos)?.max(get_u16(extension_maxp, pos)?);
+            set_u16(&mut maxp, pos, value)?;
+        }
+    }
+
+    let mut post = base.table_data(Post::tag())?.get(..32).ok_or(Error::UnexpectedEof)?.to_vec();
+    post[..4].copy_from_slice(&[0, 3, 0, 0]);
+
+    if writer.contains(Os2::tag()) {
+        let mut os2 = base.table_data(Os2::tag())?.to_vec();
+        let first = mappings.first().map_or(0, |mapping| mapping.0.min(0xFFFF));
+        let last = mappings.last().map_or(0, |mapping| mapping.0.min(0xFFFF));
+        set_u16(&mut os2, 64, first as u16)?;
+        set_u16(&mut os2, 66, last as u16)?;
+        writer.table(Os2::tag(), os2);
+    }
+
+    writer
+        .table(Glyf::tag(), glyf)
+        .table(Tag::new(*b"loca"), loca.into_vec())
+        .table(Tag::new(*b"hmtx"), hmtx)
+        .table(Cmap::tag(), cmap::encode_unicode(&mappings)?)
+        .table(Head::tag(), head)
+        .table(Hhea::tag(), hhea)
+        .table(Maxp::tag(), maxp)
+        .table(Post::tag(), post);
+
+    Ok(writer.build())
+}
+
+fn get_u16(data: &[u8], pos: usize) -> Result<u16> {
+    let bytes = data.get(pos..pos + 2).ok_or(Error::UnexpectedEof)?;
+    Ok(u16::from(bytes[0]) << 8 | u16::from(bytes[1]))
+}
+
+fn set_u16(data: &mut [u8], pos: usize, value: u16) -> Result<()> {
+    let bytes = data.get_mut(pos..pos + 2).ok_or(Error::UnexpectedEof)?;
+    bytes.copy_from_slice(&value.to_be_bytes());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+    use test_fonts;
+
+    #[test]
+    fn merge_fonts() {
+        let base = test_fonts::minimal_font(false)
+            .table(b"cmap", test_fonts::cmap(&[('A', 1), ('C', 2)]))
+            .table(b"post", vec![0; 32])
+            .build();
+        let extension = test_fonts::minimal_font(true)
+            .table(b"cmap", test_fonts::cmap(&[('A', 2), ('\u{4E00}', 1)]))
+            .table(b"post", vec![0; 32])
+            .build();
+        let base = Font::decode(&base).unwrap();
+        let extension = Font::decode(&extension).unwrap();
+
+        let data = merge(&base, &extension, ConflictPolicy::PreferBase).expect("failed to merge");
+        let merged = Font::decode(&data).expect("failed to read merged font");
+        assert_eq!(merged.table::<Maxp>().unwrap().get_num_glyphs(), 5);
+        assert_eq!(merged.compute_checksum_adjustment(), Ok(merged.table::<Head>().unwrap().check_sum_adjustment));
+
+        assert_eq!(merged.glyph_index('A'), Ok(Some(GlyphId(1))));
+        assert_eq!(merged.glyph_index('C'), Ok(Some(GlyphId(2))));
+        assert_eq!(merged.glyph_index('\u{4E00}'), Ok(Some(GlyphId(3))));
+        assert_eq!(merged.hmtx().unwrap().advances().collect::<Vec<_>>(), vec![500, 700, 700, 700, 700]);
+        assert_eq!(merged.table::<Hhea>().unwrap().number_of_h_metrics, 2);
+        assert_eq!(merged.glyph_bounds(GlyphId(3)), base.glyph_bounds(GlyphId(1)));
+        assert_eq!(merged.glyph_bounds(GlyphId(4)), base.glyph_bounds(GlyphId(2)));
+
+        let data = merge(&base, &extension, ConflictPolicy::PreferExtension).unwrap();
+        let merged = Font::decode(&data).unwrap();
+        assert_eq!(merged.glyph_index('A'), Ok(Some(GlyphId(4))));
+    }
+
+    #[test]
+    fn merge_hinted_fonts() {
+        // A version 1 `maxp` with every limit at `limit`.
+        let maxp = |limit: u16| {
+            let mut out = vec![0, 1, 0, 0, 0, 3];
+            for _ in 0..13 {
+                out.extend_from_slice(&limit.to_be_bytes());
+            }
+            out
+        };
+        let square: &[(i16, i16)] = &[(100, 0), (100, 500), (600, 500), (600, 0)];
+        let (glyf, loca) = test_fonts::glyf_and_loca(&[
+            Vec::new(),
+            test_fonts::hinted_glyph(&[square], &[0xB0, 0x01]),
+            test_fonts::simple_glyph(&[&[(50, 0), (300, 700), (550, 0)]]),
+        ], false);
+        let base = test_fonts::minimal_font(false)
+            .table(b"cmap", test_fonts::cmap(&[('A', 1)]))
+            .table(b"post", vec![0; 32])
+            .table(b"maxp", maxp(10))
+            .table(b"glyf", glyf.clone())
+            .table(b"loca", loca.clone())
+            .table(b"fpgm", vec![0xB0, 0x00])
+            .build();
+        let extension = test_fonts::minimal_font(false)
+            .table(b"cmap", test_fonts::cmap(&[('B', 1)]))
+            .table(b"post", vec![0; 32])
+            .table(b"maxp", maxp(20))
+            .table(b"glyf", glyf)
+            .table(b"loca", loca)
+            .build();
+        let base = Font::decode(&base).unwrap();
+        let extension = Font::decode(&extension).unwrap();
+
+        let data = merge(&base, &extension, ConflictPolicy::PreferBase).unwrap();
+        let merged = Font::decode(&data).unwrap();
+        let loca = merged.loca().unwrap();
+        let glyph_data = merged.table::<Glyf>().unwrap();
+        let instructions = |glyph| glyf::instructions(glyph_data.glyph_data(&loca, GlyphId(glyph)).unwrap()).unwrap();
+        assert_eq!(instructions(1), &[0xB0, 0x01]);
+        assert_eq!(instructions(3), &[]);
+        assert_eq!(merged.glyph_bounds(GlyphId(3)), base.glyph_bounds(GlyphId(1)));
+        assert_eq!(merged.table_data(Tag::new(*b"fpgm")), Ok(&[0xB0, 0x00][..]));
+
+        let profile = merged.table::<Maxp>().unwrap().profile().unwrap();
+        assert_eq!((profile.max_points, profile.max_component_elements), (20, 20));
+        assert_eq!((profile.max_function_defs, profile.max_size_of_instructions), (10, 10));
+    }
+
+    #[test]
+    fn merge_composites() {
+        let data = open_file!("data/DroidSerif.ttf");
+        let extension = Font::decode(&data).expect("failed to read font");
+        let base = test_fonts::minimal_font(false)
+            .table(b"cmap", test_fonts::cmap(&[]))
+            .table(b"post", vec![0; 32])
+            .build();
+        let base = Font::decode(&base).unwrap();
+
+        let data = merge(&base, &extension, ConflictPolicy::PreferBase).expect("failed to merge");
+        let merged = Font::decode(&data).expect("failed to read merged font");
+        assert!(merged.table_record(Tag::new(*b"GSUB")).is_none());
+
+        // Aacute is a composite, whose components were renumbered too.
+        let aacute = GlyphId(131 + 2);
+        assert_eq!(merged.glyph_index('\u{C1}'), Ok(Some(aacute)));
+        assert_eq!(merged.glyph_bounds(aacute), extension.glyph_bounds(GlyphId(131)));
+    }
+}
@@ -0,0 +1,466 @@
+//! This module assembles font-wide metrics from the tables which declare
+//! them, measuring the glyph outlines when an older font leaves them out.
+
+use font::Font;
+#[cfg(feature = "variations")]
+use instance::InstancedFont;
+use primitives::Tag;
+#[cfg(feature = "layout")]
+use table::base::Base;
+use table::head::Head;
+use table::hhea::Hhea;
+use table::os2::Os2;
+use table::post::Post;
+#[cfg(feature = "variations")]
+use table::mvar::{self, Mvar};
+use error::Result;
+
+/// Whether a metric was read from the font or measured from its glyphs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Provenance {
+    Declared,
+    Measured,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Metric {
+    pub value: i16,
+    pub provenance: Provenance,
+}
+
+/// Vertical metrics in font units.  The ascender, descender and line gap
+/// are those of `hhea`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FontMetrics {
+    pub units_per_em: u16,
+    pub ascender: i16,
+    pub descender: i16,
+    pub line_gap: i16,
+    /// The height of lowercase letters, or `None` if it is neither
+    /// declared nor measurable.
+    pub x_height: Option<Metric>,
+    /// The height of uppercase letters, or `None` if it is neither
+    /// declared nor measurable.
+    pub cap_height: Option<Metric>,
+}
+
+/// A line drawn parallel to the baseline, in font units.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Decoration {
+    /// The top of the stroke, which is positive above the baseline.
+    pub position: f32,
+    pub thickness: f32,
+}
+
+/// The underline from `post` and the strikeout from OS/2, which both
+/// measure the position of the top of the stroke.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DecorationMetrics {
+    pub underline: Decoration,
+    /// `None` when the font has no OS/2 table.
+    pub strikeout: Option<Decoration>,
+}
+
+/// The conventions by which platforms choose the vertical metrics lines
+/// are spaced with, among `hhea`, the typographic metrics of OS/2 and the
+/// Windows metrics of OS/2.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LineMetricsStrategy {
+    /// The typographic metrics, as CSS and most cross-platform layout
+    /// engines prefer, falling back to `hhea` when the font has no OS/2
+    /// or leaves them zero.
+    Typo,
+    /// The convention of Windows: the typographic metrics when the font
+    /// sets `USE_TYPO_METRICS`, and otherwise the Windows ascent and
+    /// descent with the part of the `hhea` line gap they do not already
+    /// cover.  Fonts without OS/2 use `hhea`.
+    Win,
+    /// The `hhea` metrics alone, as macOS uses.
+    HheaOnly,
+}
+
+/// The table a set of line metrics was taken from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LineMetricsSource {
+    Hhea,
+    Typo,
+    Win,
+}
+
+/// The vertical extent of a line in font units.  The descender is
+/// negative below the baseline.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LineMetrics {
+    pub ascender: i32,
+    pub descender: i32,
+    pub line_gap: i32,
+    pub source: LineMetricsSource,
+}
+
+impl LineMetrics {
+    /// The distance between the baselines of consecutive lines.
+    pub fn line_height(&self) -> i32 {
+        self.ascender - self.descender + self.line_gap
+    }
+}
+
+/// A baseline which text of some script aligns to, as CSS
+/// `dominant-baseline` chooses among them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BaselineTag {
+    /// The alphabetic baseline of Latin, Greek and Cyrillic.
+    Roman,
+    /// The hanging baseline of Devanagari, Bengali and Tibetan.
+    Hanging,
+    /// The bottom of the ideographic em box.
+    IdeographicBottom,
+    /// The top of the ideographic em box.
+    IdeographicTop,
+    /// The bottom of the ideographic character face, within the em box.
+    IcfBottom,
+    /// The top of the ideographic character face, within the em box.
+    IcfTop,
+    /// The baseline math operators are centered on.
+    Math,
+}
+
+impl BaselineTag {
+    /// The tag of the baseline in `BASE`.
+    pub fn tag(self) -> Tag {
+        Tag::new(match self {
+            BaselineTag::Roman => *b"romn",
+            BaselineTag::Hanging => *b"hang",
+            BaselineTag::IdeographicBottom => *b"ideo",
+            BaselineTag::IdeographicTop => *b"idtp",
+            BaselineTag::IcfBottom => *b"icfb",
+            BaselineTag::IcfTop => *b"icft",
+            BaselineTag::Math => *b"math",
+        })
+    }
+}
+
+impl<'fnt> Font<'fnt> {
+    /// The position of `baseline` for text in `script` in font units,
+    /// from the horizontal axis of `BASE`.
+    ///
+    /// When `BASE` does not give it, the baseline is synthesized as web
+    /// engines do: the roman baseline is at zero, the hanging baseline at
+    /// 80% of the ascender and the ideographic em box extends an em up
+    /// from the descender, with the character face inset by 5% of the em
+    /// on either side.  The math baseline is at half the x-height.  The
+    /// ascender and descender are the typographic ones of
+    /// `LineMetricsStrategy::Typo`.
+    pub fn baseline(&self, baseline: BaselineTag, script: Tag) -> Result<i16> {
+        if let Some(value) = self.declared_baseline(baseline, script)? {
+            return Ok(value)
+        }
+
+        let em = || self.table::<Head>().map(|head| i32::from(head.units_per_em));
+        let value = match baseline {
+            BaselineTag::Roman => 0,
+            BaselineTag::Hanging => {
+                let ascender = self.line_metrics(LineMetricsStrategy::Typo)?.ascender;
+                (ascender as f32 * 0.8).round() as i32
+            },
+            BaselineTag::IdeographicBottom => self.line_metrics(LineMetricsStrategy::Typo)?.descender,
+            // The em box and character face follow a declared ideographic
+            // baseline, if there is one.
+            BaselineTag::IdeographicTop => i32::from(self.baseline(BaselineTag::IdeographicBottom, script)?) + em()?,
+            BaselineTag::IcfBottom => i32::from(self.baseline(BaselineTag::IdeographicBottom, script)?) + em()? / 20,
+            BaselineTag::IcfTop => i32::from(self.baseline(BaselineTag::IdeographicTop, script)?) - em()? / 20,
+            BaselineTag::Math => match self.x_height()? {
+                Some(x_height) => i32::from(x_height.value) / 2,
+                None => em()? / 4,
+            },
+        };
+        Ok(value.max(i32::from(i16::MIN)).min(i32::from(i16::MAX)) as i16)
+    }
+
+    #[cfg(feature = "layout")]
+    fn declared_baseline(&self, baseline: BaselineTag, script: Tag) -> Result<Option<i16>> {
+        match self.optional_table::<Base>()?.and_then(|base| base.horizontal) {
+            Some(axis) => axis.coordinate(script, baseline.tag()),
+            None => Ok(None),
+        }
+    }
+
+    #[cfg(not(feature = "layout"))]
+    fn declared_baseline(&self, _baseline: BaselineTag, _script: Tag) -> Result<Option<i16>> {
+        Ok(None)
+    }
+
+    /// The line metrics chosen by `strategy`.
+    pub fn line_metrics(&self, strategy: LineMetricsStrategy) -> Result<LineMetrics> {
+        let hhea = self.table::<Hhea>()?;
+        let hhea = LineMetrics {
+            ascender: i16::from(hhea.ascender).into(),
+            descender: i16::from(hhea.descender).into(),
+            line_gap: i16::from(hhea.line_gap).into(),
+            source: LineMetricsSource::Hhea,
+        };
+        let os2 = match strategy {
+            LineMetricsStrategy::HheaOnly => return Ok(hhea),
+            _ => match self.optional_table::<Os2>()? {
+                Some(os2) => os2,
+                None => return Ok(hhea),
+            },
+        };
+
+        let typo = LineMetrics {
+            ascender: os2.s_typo_ascender.into(),
+            descender: os2.s_typo_descender.into(),
+            line_gap: os2.s_typo_line_gap.into(),
+            source: LineMetricsSource::Typo,
+        };
+        let typo_declared = typo.ascender != 0 || typo.descender != 0;
+
+        Ok(match strategy {
+            LineMetricsStrategy::Typo if typo_declared => typo,
+            LineMetricsStrategy::Win if os2.use_typo_metrics() && typo_declared => typo,
+            LineMetricsStrategy::Win if os2.us_win_ascent != 0 || os2.us_win_descent != 0 => {
+                // GDI's external leading: the line gap less the space the
+                // Windows metrics add over the `hhea` ascender and descender.
+                let (ascender, descender) = (i32::from(os2.us_win_ascent), -i32::from(os2.us_win_descent));
+                let extra = (ascender - descender) - (hhea.ascender - hhea.descender);
+                LineMetrics { ascender, descender, line_gap: (hhea.line_gap - extra).max(0), source: LineMetricsSource::Win }
+            },
+            _ => hhea,
+        })
+    }
+
+    pub fn decoration_metrics(&self) -> Result<DecorationMetrics> {
+        let post = self.table::<Post>()?;
+        let underline = Decoration {
+            position: i16::from(post.underline_position) as f32,
+            thickness: i16::from(post.underline_thickness) as f32,
+        };
+
+        let strikeout = self.optional_table::<Os2>()?.map(|os2| Decoration {
+            position: os2.y_strikeout_position as f32,
+            thickness: os2.y_strikeout_size as f32,
+        });
+
+        Ok(DecorationMetrics { underline, strikeout })
+    }
+
+    pub fn metrics(&self) -> Result<FontMetrics> {
+        let head = self.table::<Head>()?;
+        let hhea = self.table::<Hhea>()?;
+
+        Ok(FontMetrics {
+            units_per_em: head.units_per_em,
+            ascender: hhea.ascender.into(),
+            descender: hhea.descender.into(),
+            line_gap: hhea.line_gap.into(),
+            x_height: self.x_height()?,
+            cap_height: self.cap_height()?,
+        })
+    }
+
+    /// The `sxHeight` of OS/2, or the height of the outline of 'x' when
+    /// the table is older than version 2.
+    pub fn x_height(&self) -> Result<Option<Metric>> {
+        let declared = self.optional_table::<Os2>()?.and_then(|os2| os2.sx_height);
+        self.declared_or_measured(declared, 'x')
+    }
+
+    /// The `sCapHeight` of OS/2, or the height of the outline of 'H' when
+    /// the table is older than version 2.
+    pub fn cap_height(&self) -> Result<Option<Metric>> {
+        let declared = self.optional_table::<Os2>()?.and_then(|os2| os2.s_cap_height);
+        self.declared_or_measured(declared, 'H')
+    }
+
+    /// Fonts commonly write zero when they do not know a value, so only
+    /// positive declarations are trusted.
+    fn declared_or_measured(&self, declared: Option<i16>, character: char) -> Result<Option<Metric>> {
+        if let Some(value) = declared.filter(|&value| value > 0) {
+            return Ok(Some(Metric { value, provenance: Provenance::Declared }))
+        }
+
+        let glyph = match self.glyph_index(character)? {
+            Some(glyph) => glyph,
+            None => return Ok(None),
+        };
+
+        Ok(self.glyph_bounds(glyph)?.map(|bounds| Metric {
+            value: bounds.y_max.round() as i16,
+            provenance: Provenance::Measured,
+        }))
+    }
+}
+
+#[cfg(feature = "variations")]
+impl<'a, 'fnt> InstancedFont<'a, 'fnt> {
+    /// The decoration metrics of the font, with the `MVAR` deltas of this
+    /// instance applied.
+    pub fn decoration_metrics(&self) -> Result<DecorationMetrics> {
+        let mut metrics = self.font().decoration_metrics()?;
+        let mvar = match self.font().optional_table::<Mvar>()? {
+            Some(mvar) => mvar,
+            None => return Ok(metrics),
+        };
+
+        let coords = self.coords();
+        metrics.underline.position += mvar.delta(mvar::tags::UNDERLINE_OFFSET, coords)?;
+        metrics.underline.thickness += mvar.delta(mvar::tags::UNDERLINE_SIZE, coords)?;
+        if let Some(ref mut strikeout) = metrics.strikeout {
+            strikeout.position += mvar.delta(mvar::tags::STRIKEOUT_OFFSET, coords)?;
+            strikeout.thickness += mvar.delta(mvar::tags::STRIKEOUT_SIZE, coords)?;
+        }
+
+        Ok(metrics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+    use test_fonts;
+
+    #[test]
+    fn x_and_cap_height() {
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let metrics = font.metrics().expect("failed to read metrics");
+        assert_eq!(metrics.x_height, Some(Metric { value: 1098, provenance: Provenance::Declared }));
+        assert_eq!(metrics.cap_height, Some(Metric { value: 1462, provenance: Provenance::Declared }));
+
+        // The declared values agree with the outlines.
+        let x = font.glyph_index('x').unwrap().unwrap();
+        let h = font.glyph_index('H').unwrap().unwrap();
+        assert_eq!(font.glyph_bounds(x).unwrap().map(|bounds| bounds.y_max), Some(1098.0));
+        assert_eq!(font.glyph_bounds(h).unwrap().map(|bounds| bounds.y_max), Some(1462.0));
+
+        // Without OS/2 the heights of the square and triangle are measured.
+        let data = test_fonts::minimal_font(false)
+            .table(b"cmap", test_fonts::cmap(&[('x', 1), ('H', 2)]))
+            .build();
+        let font = Font::decode(&data).expect("failed to read font");
+        let metrics = font.metrics().expect("failed to read metrics");
+        assert_eq!(metrics.units_per_em, 1000);
+        assert_eq!((metrics.ascender, metrics.descender), (800, -200));
+        assert_eq!(metrics.x_height, Some(Metric { value: 500, provenance: Provenance::Measured }));
+        assert_eq!(metrics.cap_height, Some(Metric { value: 700, provenance: Provenance::Measured }));
+
+        let data = test_fonts::minimal_font(false).table(b"cmap", test_fonts::cmap(&[])).build();
+        let font = Font::decode(&data).expect("failed to read font");
+        assert_eq!(font.x_height(), Ok(None));
+    }
+
+    #[test]
+    fn synthesized_baselines() {
+        // The typographic ascender and descender are 750 and -250, and
+        // the square glyph for 'x' is 500 units high.
+        let data = test_fonts::minimal_font(false)
+            .table(b"OS/2", test_fonts::os2((750, -250, 100), (950, 300), 0))
+            .table(b"cmap", test_fonts::cmap(&[('x', 1)]))
+            .build();
+        let font = Font::decode(&data).unwrap();
+        let latin = Tag::new(*b"latn");
+        let baselines = [
+            BaselineTag::Roman,
+            BaselineTag::Hanging,
+            BaselineTag::IdeographicBottom,
+            BaselineTag::IdeographicTop,
+            BaselineTag::IcfBottom,
+            BaselineTag::IcfTop,
+            BaselineTag::Math,
+        ];
+        let values = baselines.iter().map(|&baseline| font.baseline(baseline, latin).unwrap()).collect::<Vec<_>>();
+        assert_eq!(values, vec![0, 600, -250, 750, -200, 700, 250]);
+        assert_eq!(BaselineTag::IcfTop.tag(), Tag::new(*b"icft"));
+    }
+
+    #[cfg(feature = "layout")]
+    #[test]
+    fn declared_baselines() {
+        let base = test_fonts::base(&[b"hang", b"ideo"], &[(b"deva", 0, &[640, -120])]);
+        let data = test_fonts::minimal_font(false).table(b"BASE", base).build();
+        let font = Font::decode(&data).unwrap();
+        let devanagari = Tag::new(*b"deva");
+        assert_eq!(font.baseline(BaselineTag::Hanging, devanagari), Ok(640));
+        // The em box extends from the declared ideographic baseline.
+        assert_eq!(font.baseline(BaselineTag::IdeographicTop, devanagari), Ok(880));
+        // Other scripts fall back to `hhea`, whose ascender is 800.
+        assert_eq!(font.baseline(BaselineTag::Hanging, Tag::new(*b"latn")), Ok(640));
+        assert_eq!(font.baseline(BaselineTag::IdeographicBottom, Tag::new(*b"latn")), Ok(-200));
+    }
+
+    #[test]
+    fn line_metrics() {
+        let metrics = |os2: Option<Vec<u8>>, strategy| {
+            let builder = test_fonts::minimal_font(false).table(b"hhea", test_fonts::hhea(800, -200, 2));
+            let builder = match os2 {
+                Some(os2) => builder.table(b"OS/2", os2),
+                None => builder,
+            };
+            let data = builder.build();
+            let font = Font::decode(&data).unwrap();
+            let metrics = font.line_metrics(strategy).unwrap();
+            (metrics.ascender, metrics.descender, metrics.line_gap, metrics.source)
+        };
+
+        use self::LineMetricsSource::*;
+        use self::LineMetricsStrategy::HheaOnly;
+        let os2 = test_fonts::os2((750, -250, 100), (950, 300), 0);
+        assert_eq!(metrics(Some(os2.clone()), LineMetricsStrategy::Typo), (750, -250, 100, Typo));
+        assert_eq!(metrics(Some(os2.clone()), LineMetricsStrategy::Win), (950, -300, 0, Win));
+        assert_eq!(metrics(Some(os2), HheaOnly), (800, -200, 0, Hhea));
+
+        let os2 = test_fonts::os2((750, -250, 100), (950, 300), 0x0080);
+        assert_eq!(metrics(Some(os2), LineMetricsStrategy::Win), (750, -250, 100, Typo));
+        let os2 = test_fonts::os2((0, 0, 0), (0, 0), 0x0080);
+        assert_eq!(metrics(Some(os2.clone()), LineMetricsStrategy::Typo), (800, -200, 0, Hhea));
+        assert_eq!(metrics(Some(os2), LineMetricsStrategy::Win), (800, -200, 0, Hhea));
+        assert_eq!(metrics(None, LineMetricsStrategy::Win), (800, -200, 0, Hhea));
+
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let hhea = font.line_metrics(HheaOnly).unwrap();
+        let win = font.line_metrics(LineMetricsStrategy::Win).unwrap();
+        assert_eq!(win.line_height(), hhea.line_height().max(win.ascender - win.descender));
+    }
+
+    #[test]
+    fn decoration_metrics() {
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let post = font.table::<Post>().unwrap();
+        let metrics = font.decoration_metrics().expect("failed to read decorations");
+        assert_eq!(metrics.underline.position, i16::from(post.underline_position) as f32);
+        assert!(metrics.underline.position < 0.0);
+        assert!(metrics.strikeout.unwrap().position > 0.0);
+    }
+
+    #[test]
+    #[cfg(feature = "variations")]
+    fn instance_decoration_metrics() {
+        // The instance at the default location is unchanged.
+        let data = open_file!("data/Mada/Mada-VF.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let default = InstancedFont::new(&font, &[]).unwrap();
+        assert_eq!(default.decoration_metrics(), font.decoration_metrics());
+        let heavy = InstancedFont::new(&font, &[(Tag::new(*b"wght"), 900.0)]).unwrap();
+        assert!(heavy.decoration_metrics().is_ok());
+
+        let mut post = vec![0; 32];
+        post[8..12].copy_from_slice(&[0xff, 0x9c, 0, 50]);
+        let data = test_fonts::variable_font()
+            .table(b"post", post)
+            .table(b"MVAR", test_fonts::mvar(1, &[(b"undo", -20), (b"unds", 10)]))
+            .build();
+        let font = Font::decode(&data).expect("failed to read font");
+        let metrics = font.decoration_metrics().unwrap();
+        assert_eq!(metrics.underline, Decoration { position: -100.0, thickness: 50.0 });
+        assert_eq!(metrics.strikeout, None);
+
+        let heavy = InstancedFont::new(&font, &[(Tag::new(*b"wght"), 900.0)]).unwrap();
+        let varied = heavy.decoration_metrics().unwrap();
+        assert_eq!(varied.underline, Decoration { position: -120.0, thickness: 60.0 });
+
+        let medium = InstancedFont::new(&font, &[(Tag::new(*b"wght"), 650.0)]).unwrap();
+        assert_eq!(medium.decoration_metrics().unwrap().underline.thickness, 55.0);
+    }
+}
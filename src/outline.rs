@@ -0,0 +1,1084 @@
+//! This module contains the interface used to receive glyph outlines,
+//! independently of whether they come from TrueType or CFF data.
+
+use std::cmp::Ordering;
+
+use font::Font;
+use primitives::GlyphId;
+use table::TaggedTable;
+use table::glyf::{Glyf, Glyph};
+#[cfg(feature = "cff")]
+use table::cff::Cff;
+#[cfg(not(feature = "cff"))]
+use error::Error;
+use error::Result;
+#[cfg(feature = "kurbo")]
+use kurbo::BezPath;
+#[cfg(feature = "lyon")]
+use lyon_path::{Path, math::{point, Point as LyonPoint}};
+
+/// A receiver of glyph outline segments.  Coordinates are in font units
+/// with the y-axis pointing up.
+pub trait OutlineSink {
+    fn move_to(&mut self, x: f32, y: f32);
+    fn line_to(&mut self, x: f32, y: f32);
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32);
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32);
+    fn close(&mut self);
+}
+
+/// An axis-aligned rectangle.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rect {
+    pub x_min: f32,
+    pub y_min: f32,
+    pub x_max: f32,
+    pub y_max: f32,
+}
+
+impl Rect {
+    fn point(x: f32, y: f32) -> Rect {
+        Rect { x_min: x, y_min: y, x_max: x, y_max: y }
+    }
+
+    fn extend(&mut self, x: f32, y: f32) {
+        self.x_min = self.x_min.min(x);
+        self.y_min = self.y_min.min(y);
+        self.x_max = self.x_max.max(x);
+        self.y_max = self.y_max.max(y);
+    }
+}
+
+/// An `OutlineSink` which computes the exact bounding box of an outline,
+/// including the extrema of curves rather than their control points.
+#[derive(Debug, Default, Clone)]
+pub struct BoundsSink {
+    bounds: Option<Rect>,
+    x: f32,
+    y: f32,
+}
+
+impl BoundsSink {
+    pub fn new() -> BoundsSink {
+        BoundsSink::default()
+    }
+
+    /// The bounding box of every segment received, or `None` for an
+    /// empty outline.
+    pub fn bounds(&self) -> Option<Rect> {
+        self.bounds
+    }
+
+    fn add(&mut self, x: f32, y: f32) {
+        match self.bounds {
+            Some(ref mut bounds) => bounds.extend(x, y),
+            None => self.bounds = Some(Rect::point(x, y)),
+        }
+    }
+}
+
+impl OutlineSink for BoundsSink {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.x = x;
+        self.y = y;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let (x0, y0) = (self.x, self.y);
+        self.add(x0, y0);
+        self.add(x, y);
+        self.move_to(x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (x0, y0) = (self.x, self.y);
+        self.add(x0, y0);
+        self.add(x, y);
+
+        // Points where either coordinate is at an extremum lie on the curve,
+        // so adding them to the bounds is exact.
+        for &(p0, p1, p2) in &[(x0, x1, x), (y0, y1, y)] {
+            let denom = p0 - 2.0 * p1 + p2;
+            if denom != 0.0 {
+                let t = (p0 - p1) / denom;
+                if t > 0.0 && t < 1.0 {
+                    let mt = 1.0 - t;
+                    self.add(mt * mt * x0 + 2.0 * mt * t * x1 + t * t * x,
+                             mt * mt * y0 + 2.0 * mt * t * y1 + t * t * y);
+                }
+            }
+        }
+
+        self.move_to(x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (x0, y0) = (self.x, self.y);
+        self.add(x0, y0);
+        self.add(x, y);
+
+        let eval = |t: f32, p0: f32, p1: f32, p2: f32, p3: f32| {
+            let mt = 1.0 - t;
+            mt * mt * mt * p0 + 3.0 * mt * mt * t * p1 + 3.0 * mt * t * t * p2 + t * t * t * p3
+        };
+
+        for &(p0, p1, p2, p3) in &[(x0, x1, x2, x), (y0, y1, y2, y)] {
+            for &t in cubic_extrema(p0, p1, p2, p3).iter().flatten() {
+                self.add(eval(t, x0, x1, x2, x), eval(t, y0, y1, y2, y));
+            }
+        }
+
+        self.move_to(x, y);
+    }
+
+    fn close(&mut self) {}
+}
+
+/// A 2x3 affine transform, which maps `(x, y)` to
+/// `(xx * x + xy * y + dx, yx * x + yy * y + dy)`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    pub xx: f32,
+    pub yx: f32,
+    pub xy: f32,
+    pub yy: f32,
+    pub dx: f32,
+    pub dy: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform { xx: 1.0, yx: 0.0, xy: 0.0, yy: 1.0, dx: 0.0, dy: 0.0 };
+
+    pub fn scale(x: f32, y: f32) -> Transform {
+        Transform { xx: x, yy: y, ..Transform::IDENTITY }
+    }
+
+    pub fn translate(dx: f32, dy: f32) -> Transform {
+        Transform { dx, dy, ..Transform::IDENTITY }
+    }
+
+    /// A rotation counterclockwise by `angle` radians about the origin.
+    pub fn rotate(angle: f32) -> Transform {
+        let (sin, cos) = angle.sin_cos();
+        Transform { xx: cos, yx: sin, xy: -sin, yy: cos, dx: 0.0, dy: 0.0 }
+    }
+
+    /// A shear moving each point `x` times its height to the right and
+    /// `y` times its x coordinate up.  A synthetic oblique is a shear of
+    /// `(angle.tan(), 0.0)`, with about 12 degrees typical.
+    pub fn shear(x: f32, y: f32) -> Transform {
+        Transform { yx: y, xy: x, ..Transform::IDENTITY }
+    }
+
+    /// The transform applying `self` and then `other`.
+    pub fn then(&self, other: &Transform) -> Transform {
+        let (dx, dy) = other.apply(self.dx, self.dy);
+        Transform {
+            xx: other.xx * self.xx + other.xy * self.yx,
+            yx: other.yx * self.xx + other.yy * self.yx,
+            xy: other.xx * self.xy + other.xy * self.yy,
+            yy: other.yx * self.xy + other.yy * self.yy,
+            dx,
+            dy,
+        }
+    }
+
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        (self.xx * x + self.xy * y + self.dx, self.yx * x + self.yy * y + self.dy)
+    }
+
+    /// The transform undoing `self`, or `None` if `self` collapses the
+    /// plane onto a line or a point.
+    pub fn invert(&self) -> Option<Transform> {
+        let determinant = self.xx * self.yy - self.xy * self.yx;
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None
+        }
+
+        let (xx, yx, xy, yy) = (self.yy / determinant, -self.yx / determinant, -self.xy / determinant, self.xx / determinant);
+        Some(Transform { xx, yx, xy, yy, dx: -(xx * self.dx + xy * self.dy), dy: -(yx * self.dx + yy * self.dy) })
+    }
+}
+
+/// An `OutlineSink` which transforms each point before passing it on to
+/// another sink.  Affine transforms map lines to lines and curves to
+/// curves of the same degree, so segments are passed on as they are.
+#[derive(Debug)]
+pub struct TransformSink<'a, S: 'a> {
+    sink: &'a mut S,
+    transform: Transform,
+}
+
+impl<'a, S: OutlineSink> TransformSink<'a, S> {
+    pub fn new(sink: &'a mut S, transform: Transform) -> TransformSink<'a, S> {
+        TransformSink { sink, transform }
+    }
+}
+
+impl<'a, S: OutlineSink> OutlineSink for TransformSink<'a, S> {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let (x, y) = self.transform.apply(x, y);
+        self.sink.move_to(x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let (x, y) = self.transform.apply(x, y);
+        self.sink.line_to(x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let ((x1, y1), (x, y)) = (self.transform.apply(x1, y1), self.transform.apply(x, y));
+        self.sink.quad_to(x1, y1, x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (x1, y1) = self.transform.apply(x1, y1);
+        let (x2, y2) = self.transform.apply(x2, y2);
+        let (x, y) = self.transform.apply(x, y);
+        self.sink.curve_to(x1, y1, x2, y2, x, y);
+    }
+
+    fn close(&mut self) {
+        self.sink.close();
+    }
+}
+
+/// Which points an outline with overlapping or nested contours covers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FillRule {
+    /// Points the contours wind around a nonzero number of times, which
+    /// is how TrueType and CFF outlines are filled.
+    NonZero,
+    /// Points inside an odd number of contours.
+    EvenOdd,
+}
+
+/// An `OutlineSink` which computes the winding number of an outline
+/// around a point, by counting the segments which cross the ray from the
+/// point towards positive x.  Curves are split where they turn in y, and
+/// each monotonic piece is crossed at most once.
+#[derive(Debug, Clone)]
+pub struct WindingSink {
+    x: f32,
+    y: f32,
+    start: (f32, f32),
+    current: (f32, f32),
+    winding: i32,
+}
+
+impl WindingSink {
+    /// A sink which tests the point `(x, y)`, in font units.
+    pub fn new(x: f32, y: f32) -> WindingSink {
+        WindingSink { x, y, start: (0.0, 0.0), current: (0.0, 0.0), winding: 0 }
+    }
+
+    /// The number of times the outline winds around the point, positive
+    /// for counterclockwise contours.
+    pub fn winding(&self) -> i32 {
+        self.winding
+    }
+
+    pub fn contains(&self, rule: FillRule) -> bool {
+        match rule {
+            FillRule::NonZero => self.winding != 0,
+            FillRule::EvenOdd => self.winding % 2 != 0,
+        }
+    }
+
+    /// The direction a segment from `y0` to `y1` crosses the ray in, or
+    /// zero if it does not reach it.  The lower end is included and the
+    /// upper end is not, so a ray through a vertex is crossed once.
+    fn direction(&self, y0: f32, y1: f32) -> i32 {
+        if y0 <= self.y && self.y < y1 {
+            1
+        } else if y1 <= self.y && self.y < y0 {
+            -1
+        } else {
+            0
+        }
+    }
+
+    /// Count the piece of `curve` between `t0` and `t1`, which must be
+    /// monotonic in y.
+    fn monotonic<F: Fn(f32) -> (f32, f32)>(&mut self, curve: F, t0: f32, t1: f32) {
+        let (y0, y1) = (curve(t0).1, curve(t1).1);
+        let direction = self.direction(y0, y1);
+        if direction == 0 {
+            return
+        }
+
+        // Bisect for the parameter where the piece meets the ray.
+        let (mut low, mut high) = (t0, t1);
+        for _ in 0..24 {
+            let mid = 0.5 * (low + high);
+            if (curve(mid).1 < self.y) == (y0 < y1) {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        if curve(0.5 * (low + high)).0 > self.x {
+            self.winding += direction;
+        }
+    }
+
+    /// Count `curve`, split at the parameters in `turns` where it turns in
+    /// y, unless every control point is above or below the ray.
+    fn curve<F: Fn(f32) -> (f32, f32)>(&mut self, curve: F, ys: &[f32], turns: &mut [f32]) {
+        if ys.iter().all(|&y| y > self.y) || ys.iter().all(|&y| y < self.y) {
+            return
+        }
+
+        turns.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        let mut t0 = 0.0;
+        for &t in turns.iter().chain(Some(&1.0)) {
+            self.monotonic(&curve, t0, t);
+            t0 = t;
+        }
+    }
+}
+
+impl OutlineSink for WindingSink {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.close();
+        self.start = (x, y);
+        self.current = (x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let (x0, y0) = self.current;
+        let direction = self.direction(y0, y);
+        if direction != 0 && x0 + (self.y - y0) * (x - x0) / (y - y0) > self.x {
+            self.winding += direction;
+        }
+        self.current = (x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (x0, y0) = self.current;
+        let quad = |t: f32| {
+            let mt = 1.0 - t;
+            (mt * mt * x0 + 2.0 * mt * t * x1 + t * t * x, mt * mt * y0 + 2.0 * mt * t * y1 + t * t * y)
+        };
+
+        let denom = y0 - 2.0 * y1 + y;
+        let turn = if denom != 0.0 { (y0 - y1) / denom } else { 0.0 };
+        let mut turns = if turn > 0.0 && turn < 1.0 { vec![turn] } else { Vec::new() };
+        self.curve(quad, &[y0, y1, y], &mut turns);
+        self.current = (x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (x0, y0) = self.current;
+        let cubic = |t: f32| {
+            let mt = 1.0 - t;
+            let (a, b, c, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+            (a * x0 + b * x1 + c * x2 + d * x, a * y0 + b * y1 + c * y2 + d * y)
+        };
+
+        let mut turns = cubic_extrema(y0, y1, y2, y).iter().flatten().cloned().collect::<Vec<_>>();
+        self.curve(cubic, &[y0, y1, y2, y], &mut turns);
+        self.current = (x, y);
+    }
+
+    /// Contours are closed by a line back to their start, whether or not
+    /// the outline closes them explicitly.
+    fn close(&mut self) {
+        let (x, y) = self.start;
+        self.line_to(x, y);
+    }
+}
+
+/// A segment of a contour, given by its control points and end point.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Segment {
+    Line((f32, f32)),
+    Quad((f32, f32), (f32, f32)),
+    Cubic((f32, f32), (f32, f32), (f32, f32)),
+}
+
+impl Segment {
+    fn points(&self) -> Vec<(f32, f32)> {
+        match *self {
+            Segment::Line(p) => vec![p],
+            Segment::Quad(p1, p) => vec![p1, p],
+            Segment::Cubic(p1, p2, p) => vec![p1, p2, p],
+        }
+    }
+}
+
+/// A closed contour: a start point and the segments which follow it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contour {
+    pub start: (f32, f32),
+    pub segments: Vec<Segment>,
+}
+
+impl Contour {
+    /// The start point and then the points of each segment, in order.
+    fn points(&self) -> Vec<(f32, f32)> {
+        let mut points = vec![self.start];
+        points.extend(self.segments.iter().flat_map(Segment::points));
+        points
+    }
+
+    /// The contour with its points, in the order of `points`, replaced.
+    fn with_points(&self, points: &[(f32, f32)]) -> Contour {
+        let mut points = points.iter().cloned();
+        let start = points.next().unwrap_or(self.start);
+        let segments = self.segments.iter()
+            .map(|segment| match *segment {
+                Segment::Line(p) => Segment::Line(points.next().unwrap_or(p)),
+                Segment::Quad(p1, p) => Segment::Quad(points.next().unwrap_or(p1), points.next().unwrap_or(p)),
+                Segment::Cubic(p1, p2, p) => {
+                    Segment::Cubic(points.next().unwrap_or(p1), points.next().unwrap_or(p2), points.next().unwrap_or(p))
+                },
+            })
+            .collect();
+        Contour { start, segments }
+    }
+
+    /// The signed area of the control polygon, positive when the contour
+    /// runs counterclockwise.
+    fn area(&self) -> f32 {
+        let points = self.points();
+        let next = points.iter().cycle().skip(1);
+        points.iter().zip(next).map(|(p, q)| p.0 * q.1 - q.0 * p.1).sum::<f32>() / 2.0
+    }
+
+    /// The segments as point lists from their start to their end, with
+    /// lines of no length left out and the closing line made explicit.
+    fn cycle(&self) -> Vec<Vec<(f32, f32)>> {
+        let mut current = self.start;
+        let mut cycle = Vec::with_capacity(self.segments.len() + 1);
+        for segment in &self.segments {
+            let mut points = segment.points();
+            let end = points[points.len() - 1];
+            if *segment != Segment::Line(current) {
+                points.insert(0, current);
+                cycle.push(points);
+            }
+            current = end;
+        }
+        if current != self.start {
+            cycle.push(vec![current, self.start]);
+        }
+        cycle
+    }
+}
+
+/// An `OutlineSink` which records an outline as its contours, to compare
+/// or transform outlines after they are read.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Outline {
+    pub contours: Vec<Contour>,
+    open: bool,
+}
+
+impl Outline {
+    pub fn new() -> Outline {
+        Outline::default()
+    }
+
+    fn push(&mut self, segment: Segment) {
+        // Segments outside a contour have nowhere to start from.
+        if !self.open {
+            return
+        }
+        if let Some(contour) = self.contours.last_mut() {
+            contour.segments.push(segment);
+        }
+    }
+
+    /// Send the recorded contours to `sink`.
+    pub fn emit<S: OutlineSink>(&self, sink: &mut S) {
+        for contour in &self.contours {
+            sink.move_to(contour.start.0, contour.start.1);
+            for segment in &contour.segments {
+                match *segment {
+                    Segment::Line((x, y)) => sink.line_to(x, y),
+                    Segment::Quad((x1, y1), (x, y)) => sink.quad_to(x1, y1, x, y),
+                    Segment::Cubic((x1, y1), (x2, y2), (x, y)) => sink.curve_to(x1, y1, x2, y2, x, y),
+                }
+            }
+            sink.close();
+        }
+    }
+
+    /// The outline with its stems made `strength` font units wider, for a
+    /// synthetic bold.
+    ///
+    /// Each point, on or off the curve, moves out along the bisector of
+    /// the normals of its neighbouring edges by half the strength, so the
+    /// outline grows by that much on every side; sharp corners are limited
+    /// to about three times that.  The result is then moved up and right
+    /// by half the strength, which keeps the left side bearing and the
+    /// baseline, so the advance should grow by the strength.  A contour
+    /// which shrinks past nothing, such as a counter narrower than the
+    /// strength, is dropped rather than filled.
+    pub fn embolden(&self, strength: f32) -> Outline {
+        let radius = strength / 2.0;
+        // Outer contours run counterclockwise in CFF outlines and
+        // clockwise in TrueType ones, which decides which side is out.
+        let orientation = if self.contours.iter().map(Contour::area).sum::<f32>() < 0.0 { -1.0 } else { 1.0 };
+        let contours = self.contours.iter()
+            .filter_map(|contour| {
+                let before = contour.points();
+                let after = offset_polygon(&before, radius * orientation).into_iter()
+                    .map(|(x, y)| (x + radius, y + radius))
+                    .collect::<Vec<_>>();
+                // The edges of a contour which has shrunk past nothing
+                // point backwards.
+                let edge = |points: &[(f32, f32)], index: usize| {
+                    let (from, to) = (points[index], points[(index + 1) % points.len()]);
+                    (to.0 - from.0, to.1 - from.1)
+                };
+                let agreement = (0..before.len())
+                    .map(|index| {
+                        let (old, new) = (edge(&before, index), edge(&after, index));
+                        old.0 * new.0 + old.1 * new.1
+                    })
+                    .sum::<f32>();
+                if agreement < 0.0 {
+                    return None
+                }
+                Some(contour.with_points(&after))
+            })
+            .collect();
+        Outline { contours, open: false }
+    }
+}
+
+/// Move each point of the closed polygon `points` `distance` to the right
+/// of the direction of travel, along the bisector of its two edges.
+/// Repeated points move with their neighbours.
+fn offset_polygon(points: &[(f32, f32)], distance: f32) -> Vec<(f32, f32)> {
+    let count = points.len();
+    let direction = |from: (f32, f32), to: (f32, f32)| {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        (dx / length, dy / length)
+    };
+
+    (0..count)
+        .map(|index| {
+            let point = points[index];
+            let distinct = |step: usize| (1..count).map(|offset| points[(index + step * offset) % count]).find(|&other| other != point);
+            let (previous, next) = match (distinct(count - 1), distinct(1)) {
+                (Some(previous), Some(next)) => (previous, next),
+                _ => return point,
+            };
+            let (incoming, outgoing) = (direction(previous, point), direction(point, next));
+            let (normal_in, normal_out) = ((incoming.1, -incoming.0), (outgoing.1, -outgoing.0));
+            // The miter of edges meeting at angle `a` is `1 / cos(a / 2)`
+            // long, which `1 + dot` gives squared and halved; capping it
+            // keeps spikes from sharp corners in check.
+            let dot = normal_in.0 * normal_out.0 + normal_in.1 * normal_out.1;
+            let scale = distance / (1.0 + dot).max(0.25);
+            (point.0 + (normal_in.0 + normal_out.0) * scale, point.1 + (normal_in.1 + normal_out.1) * scale)
+        })
+        .collect()
+}
+
+impl OutlineSink for Outline {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.contours.push(Contour { start: (x, y), segments: Vec::new() });
+        self.open = true;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.push(Segment::Line((x, y)));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.push(Segment::Quad((x1, y1), (x, y)));
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.push(Segment::Cubic((x1, y1), (x2, y2), (x, y)));
+    }
+
+    fn close(&mut self) {
+        self.open = false;
+    }
+}
+
+/// Whether two outlines are the same within `tolerance` font units in x
+/// and y at every point, as builds of a glyph by different tools are.
+///
+/// Each contour of `a` must match a different contour of `b`, in any
+/// order.  Contours match when their segments are of the same kinds with
+/// points within `tolerance`, starting at any on-curve point and running
+/// in either direction.  Lines of no length and the line closing a
+/// contour, which tools add or leave out freely, are ignored.  Outlines
+/// are not converted, so a quadratic outline never matches a cubic one.
+pub fn outline_eq(a: &Outline, b: &Outline, tolerance: f32) -> bool {
+    if a.contours.len() != b.contours.len() {
+        return false
+    }
+
+    let mut unmatched = b.contours.iter().map(Contour::cycle).collect::<Vec<_>>();
+    for contour in &a.contours {
+        let cycle = contour.cycle();
+        match unmatched.iter().position(|other| cycles_eq(&cycle, other, tolerance)) {
+            Some(index) => {
+                unmatched.swap_remove(index);
+            },
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Whether `b`, rotated and possibly reversed, matches `a`.
+fn cycles_eq(a: &[Vec<(f32, f32)>], b: &[Vec<(f32, f32)>], tolerance: f32) -> bool {
+    if a.len() != b.len() {
+        return false
+    }
+
+    let near = |p: &(f32, f32), q: &(f32, f32)| (p.0 - q.0).abs() <= tolerance && (p.1 - q.1).abs() <= tolerance;
+    let segments_eq = |s: &Vec<(f32, f32)>, t: &Vec<(f32, f32)>| s.len() == t.len() && s.iter().zip(t).all(|(p, q)| near(p, q));
+    let reversed = b.iter().rev()
+        .map(|segment| segment.iter().rev().cloned().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    [b, &reversed[..]].iter().any(|b| {
+        (0..b.len().max(1)).any(|rotation| {
+            a.iter().enumerate().all(|(index, segment)| segments_eq(segment, &b[(index + rotation) % b.len()]))
+        })
+    })
+}
+
+/// Build a `kurbo::BezPath` from an outline.
+#[cfg(feature = "kurbo")]
+impl OutlineSink for BezPath {
+    fn move_to(&mut self, x: f32, y: f32) {
+        BezPath::move_to(self, (f64::from(x), f64::from(y)));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        BezPath::line_to(self, (f64::from(x), f64::from(y)));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        BezPath::quad_to(self, (f64::from(x1), f64::from(y1)), (f64::from(x), f64::from(y)));
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        BezPath::curve_to(self,
+                          (f64::from(x1), f64::from(y1)),
+                          (f64::from(x2), f64::from(y2)),
+                          (f64::from(x), f64::from(y)));
+    }
+
+    fn close(&mut self) {
+        self.close_path();
+    }
+}
+
+/// An `OutlineSink` which builds a `lyon::path::Path`.  Lyon requires
+/// every sub-path to be ended explicitly, so one left open by the outline
+/// is ended when the next begins or the path is built.  Lyon also requires
+/// every sub-path to be begun, so a segment without a `move_to` before it
+/// begins one at the current point, as SVG path data does.
+#[cfg(feature = "lyon")]
+pub struct LyonSink {
+    builder: lyon_path::path::Builder,
+    open: bool,
+    /// The first point of the last sub-path.
+    start: LyonPoint,
+    /// The end of the last segment, where a new sub-path begins.
+    current: LyonPoint,
+}
+
+#[cfg(feature = "lyon")]
+impl LyonSink {
+    pub fn new() -> LyonSink {
+        LyonSink { builder: Path::builder(), open: false, start: point(0.0, 0.0), current: point(0.0, 0.0) }
+    }
+
+    pub fn build(mut self) -> Path {
+        if self.open {
+            self.builder.end(false);
+        }
+        self.builder.build()
+    }
+
+    /// Begin a sub-path at the current point unless one is open.
+    fn ensure_open(&mut self) {
+        if !self.open {
+            self.builder.begin(self.current);
+            self.start = self.current;
+            self.open = true;
+        }
+    }
+}
+
+#[cfg(feature = "lyon")]
+impl Default for LyonSink {
+    fn default() -> LyonSink {
+        LyonSink::new()
+    }
+}
+
+#[cfg(feature = "lyon")]
+impl OutlineSink for LyonSink {
+    fn move_to(&mut self, x: f32, y: f32) {
+        if self.open {
+            self.builder.end(false);
+        }
+        self.current = point(x, y);
+        self.open = false;
+        self.ensure_open();
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.ensure_open();
+        self.current = point(x, y);
+        self.builder.line_to(self.current);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.ensure_open();
+        self.current = point(x, y);
+        self.builder.quadratic_bezier_to(point(x1, y1), self.current);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.ensure_open();
+        self.current = point(x, y);
+        self.builder.cubic_bezier_to(point(x1, y1), point(x2, y2), self.current);
+    }
+
+    fn close(&mut self) {
+        if self.open {
+            self.builder.end(true);
+            self.open = false;
+            self.current = self.start;
+        }
+    }
+}
+
+impl<'fnt> Font<'fnt> {
+    /// The TrueType glyph `id`, which must be in a `glyf` table.
+    pub fn glyph(&self, id: GlyphId) -> Result<Glyph<'fnt>> {
+        self.table::<Glyf>()?.glyph(&self.loca()?, id)
+    }
+
+    /// Send the outline of `glyph` to `sink`, from `glyf` for TrueType
+    /// fonts or `CFF ` otherwise.
+    pub fn outline_glyph<S: OutlineSink>(&self, glyph: GlyphId, sink: &mut S) -> Result<()> {
+        if self.table_record(Glyf::tag()).is_some() {
+            let loca = self.loca()?;
+            return self.table::<Glyf>()?.outline(&loca, glyph, sink)
+        }
+
+        #[cfg(feature = "cff")]
+        return self.table::<Cff>()?.outline(glyph, sink).map(|_| ());
+        #[cfg(not(feature = "cff"))]
+        Err(Error::MissingTable)
+    }
+
+    /// Send the outline of `glyph` to `sink` with `transform` applied to
+    /// each point as it is emitted, such as a shear for a synthetic
+    /// oblique or a scale for a display with non-square pixels.
+    pub fn outline_glyph_transformed<S: OutlineSink>(&self, glyph: GlyphId, transform: Transform, sink: &mut S) -> Result<()> {
+        self.outline_glyph(glyph, &mut TransformSink::new(sink, transform))
+    }
+
+    /// The exact bounding box of the outline of `glyph`, or `None` if the
+    /// glyph is empty.
+    pub fn glyph_bounds(&self, glyph: GlyphId) -> Result<Option<Rect>> {
+        let mut sink = BoundsSink::new();
+        self.outline_glyph(glyph, &mut sink)?;
+        Ok(sink.bounds())
+    }
+
+    /// Whether the outline of `glyph` covers the point `(x, y)`, in font
+    /// units, under `rule`.  Points exactly on the outline may fall on
+    /// either side.
+    pub fn glyph_contains_point(&self, glyph: GlyphId, x: f32, y: f32, rule: FillRule) -> Result<bool> {
+        let mut sink = WindingSink::new(x, y);
+        self.outline_glyph(glyph, &mut sink)?;
+        sink.close();
+        Ok(sink.contains(rule))
+    }
+
+    /// The recorded outline of `glyph`, in font units.
+    pub fn glyph_outline(&self, glyph: GlyphId) -> Result<Outline> {
+        let mut outline = Outline::new();
+        self.outline_glyph(glyph, &mut outline)?;
+        Ok(outline)
+    }
+
+    /// The outline of `glyph` as a `kurbo::BezPath`, in font units.
+    #[cfg(feature = "kurbo")]
+    pub fn glyph_bez_path(&self, glyph: GlyphId) -> Result<BezPath> {
+        let mut path = BezPath::new();
+        self.outline_glyph(glyph, &mut path)?;
+        Ok(path)
+    }
+
+    /// The outline of `glyph` as a `lyon::path::Path`, in font units.
+    #[cfg(feature = "lyon")]
+    pub fn glyph_lyon_path(&self, glyph: GlyphId) -> Result<Path> {
+        let mut sink = LyonSink::new();
+        self.outline_glyph(glyph, &mut sink)?;
+        Ok(sink.build())
+    }
+}
+
+/// The parameters `t` in `(0, 1)` where the derivative of a one-dimensional
+/// cubic bezier is zero.
+fn cubic_extrema(p0: f32, p1: f32, p2: f32, p3: f32) -> [Option<f32>; 2] {
+    let a = -p0 + 3.0 * p1 - 3.0 * p2 + p3;
+    let b = 2.0 * (p0 - 2.0 * p1 + p2);
+    let c = p1 - p0;
+    let valid = |t: f32| if t > 0.0 && t < 1.0 { Some(t) } else { None };
+
+    if a.abs() < 1e-6 {
+        if b.abs() < 1e-6 {
+            return [None, None]
+        }
+        return [valid(-c / b), None]
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return [None, None]
+    }
+
+    let root = discriminant.sqrt();
+    [valid((-b + root) / (2.0 * a)), valid((-b - root) / (2.0 * a))]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+    use test_fonts;
+
+    #[test]
+    fn contains_point() {
+        let data = test_fonts::minimal_font(false).build();
+        let font = Font::decode(&data).unwrap();
+        let contains = |glyph: u16, x: f32, y: f32| font.glyph_contains_point(GlyphId(glyph), x, y, FillRule::NonZero).unwrap();
+        assert!(contains(1, 300.0, 250.0));
+        assert!(contains(1, 100.0, 0.0));
+        assert!(!contains(1, 50.0, 250.0));
+        assert!(!contains(1, 300.0, 600.0));
+        assert!(contains(2, 300.0, 300.0));
+        assert!(!contains(2, 100.0, 600.0));
+        assert!(!contains(0, 0.0, 0.0));
+        assert_eq!(font.glyph(GlyphId(2)).unwrap().contains_point(&font, 300.0, 650.0, FillRule::EvenOdd), Ok(true));
+
+        // Two overlapping squares wound the same way.
+        let mut sink = WindingSink::new(150.0, 150.0);
+        for &(x0, y0) in &[(0.0, 0.0), (100.0, 100.0)] {
+            sink.move_to(x0, y0);
+            sink.line_to(x0, y0 + 200.0);
+            sink.line_to(x0 + 200.0, y0 + 200.0);
+            sink.line_to(x0 + 200.0, y0);
+        }
+        sink.close();
+        assert_eq!(sink.winding(), -2);
+        assert!(sink.contains(FillRule::NonZero));
+        assert!(!sink.contains(FillRule::EvenOdd));
+
+        // A quadratic arch peaks at 50 and a cubic one at 75.
+        let quad = |x: f32, y: f32| {
+            let mut sink = WindingSink::new(x, y);
+            sink.move_to(0.0, 0.0);
+            sink.quad_to(50.0, 100.0, 100.0, 0.0);
+            sink.close();
+            sink.contains(FillRule::NonZero)
+        };
+        assert!(quad(50.0, 45.0));
+        assert!(!quad(50.0, 55.0));
+        assert!(quad(10.0, 10.0));
+        assert!(!quad(5.0, 15.0));
+
+        let cubic = |x: f32, y: f32| {
+            let mut sink = WindingSink::new(x, y);
+            sink.move_to(0.0, 0.0);
+            sink.curve_to(0.0, 100.0, 100.0, 100.0, 100.0, 0.0);
+            sink.close();
+            sink.contains(FillRule::EvenOdd)
+        };
+        assert!(cubic(50.0, 70.0));
+        assert!(!cubic(50.0, 80.0));
+        assert!(cubic(5.0, 30.0));
+        assert!(!cubic(-5.0, 30.0));
+    }
+
+    #[test]
+    fn compare_outlines() {
+        let square = |points: &[(f32, f32)], close: bool| {
+            let mut outline = Outline::new();
+            outline.move_to(points[0].0, points[0].1);
+            for &(x, y) in &points[1..] {
+                outline.line_to(x, y);
+            }
+            if close {
+                outline.line_to(points[0].0, points[0].1);
+            }
+            outline.close();
+            outline
+        };
+        let a = square(&[(0.0, 0.0), (0.0, 100.0), (100.0, 100.0), (100.0, 0.0)], false);
+        // Another start point, the other direction and an explicit close.
+        let b = square(&[(100.0, 100.0), (0.0, 100.0), (0.0, 0.0), (100.0, 0.5)], true);
+        assert!(outline_eq(&a, &b, 1.0));
+        assert!(!outline_eq(&a, &b, 0.25));
+        let c = square(&[(0.0, 0.0), (0.0, 100.0), (100.0, 100.0), (100.0, 0.0), (50.0, -10.0)], false);
+        assert!(!outline_eq(&a, &c, 1.0));
+
+        let data = test_fonts::minimal_font(false).build();
+        let font = Font::decode(&data).unwrap();
+        let (one, two) = (font.glyph_outline(GlyphId(1)).unwrap(), font.glyph_outline(GlyphId(2)).unwrap());
+        assert!(outline_eq(&one, &one, 0.0));
+        assert!(!outline_eq(&one, &two, 0.0));
+        assert!(outline_eq(&Outline::new(), &font.glyph_outline(GlyphId(0)).unwrap(), 0.0));
+    }
+
+    #[test]
+    fn embolden() {
+        let data = test_fonts::minimal_font(false).build();
+        let font = Font::decode(&data).unwrap();
+        let bounds = |outline: &Outline| {
+            let mut sink = BoundsSink::new();
+            outline.emit(&mut sink);
+            let bounds = sink.bounds().unwrap();
+            [bounds.x_min, bounds.y_min, bounds.x_max, bounds.y_max].iter().map(|value| value.round()).collect::<Vec<_>>()
+        };
+        let square = font.glyph_outline(GlyphId(1)).unwrap();
+        assert_eq!(bounds(&square.embolden(40.0)), vec![100.0, 0.0, 640.0, 540.0]);
+        assert_eq!(square.embolden(0.0), square);
+
+        // A ring whose hole is 20 units across keeps its hole at a strength
+        // of 10 and loses it at 30, whichever way its contours run.
+        let ring = |clockwise: bool| {
+            let mut outline = Outline::new();
+            for &(low, high, outer) in &[(0.0, 100.0, true), (40.0, 60.0, false)] {
+                let mut corners = [(low, low), (high, low), (high, high), (low, high)];
+                if outer == clockwise {
+                    corners.reverse();
+                }
+                outline.move_to(corners[0].0, corners[0].1);
+                for &(x, y) in &corners[1..] {
+                    outline.line_to(x, y);
+                }
+                outline.close();
+            }
+            outline
+        };
+        for &clockwise in &[false, true] {
+            let bold = ring(clockwise).embolden(10.0);
+            assert_eq!(bold.contours.len(), 2);
+            assert_eq!(bounds(&bold), vec![0.0, 0.0, 110.0, 110.0]);
+            assert_eq!(bold.contours[1].points().iter().map(|p| p.0).fold(f32::MAX, f32::min), 50.0);
+            assert_eq!(ring(clockwise).embolden(30.0).contours.len(), 1);
+        }
+    }
+
+    #[test]
+    fn transformed_outlines() {
+        let data = test_fonts::minimal_font(false).build();
+        let font = Font::decode(&data).unwrap();
+        let bounds = |transform: Transform| {
+            let mut sink = BoundsSink::new();
+            font.outline_glyph_transformed(GlyphId(1), transform, &mut sink).unwrap();
+            let bounds = sink.bounds().unwrap();
+            [bounds.x_min, bounds.y_min, bounds.x_max, bounds.y_max].iter().map(|value| value.round()).collect::<Vec<_>>()
+        };
+
+        // The square spans 100 to 600 across and 0 to 500 up.
+        assert_eq!(bounds(Transform::IDENTITY), vec![100.0, 0.0, 600.0, 500.0]);
+        assert_eq!(bounds(Transform::shear(0.2, 0.0)), vec![100.0, 0.0, 700.0, 500.0]);
+        assert_eq!(bounds(Transform::scale(2.0, 0.5)), vec![200.0, 0.0, 1200.0, 250.0]);
+        assert_eq!(bounds(Transform::rotate(::std::f32::consts::FRAC_PI_2)), vec![-500.0, 100.0, 0.0, 600.0]);
+        let moved = Transform::scale(2.0, 2.0).then(&Transform::translate(10.0, -20.0));
+        assert_eq!(bounds(moved), vec![210.0, -20.0, 1210.0, 980.0]);
+        assert_eq!(moved.apply(1.0, 1.0), (12.0, -18.0));
+        assert_eq!(moved.invert().map(|inverse| inverse.apply(12.0, -18.0)), Some((1.0, 1.0)));
+        assert_eq!(Transform::scale(1.0, 0.0).invert(), None);
+
+        let mut outline = Outline::new();
+        font.outline_glyph_transformed(GlyphId(2), Transform::IDENTITY, &mut outline).unwrap();
+        assert_eq!(outline, font.glyph_outline(GlyphId(2)).unwrap());
+    }
+
+    #[cfg(feature = "cff")]
+    #[test]
+    fn contains_point_cff() {
+        let data = open_file!("data/SourceSansPro/SourceSansPro-Regular.otf");
+        let font = Font::decode(&data).unwrap();
+        let glyph = font.glyph_index('O').unwrap().unwrap();
+        let bounds = font.glyph_bounds(glyph).unwrap().unwrap();
+        let (center_x, center_y) = ((bounds.x_min + bounds.x_max) / 2.0, (bounds.y_min + bounds.y_max) / 2.0);
+
+        // The counter of the `O` is not covered, but its stroke is.
+        assert!(!font.glyph_contains_point(glyph, center_x, center_y, FillRule::NonZero).unwrap());
+        assert!(font.glyph_contains_point(glyph, bounds.x_min + 20.0, center_y, FillRule::NonZero).unwrap());
+        assert!(!font.glyph_contains_point(glyph, bounds.x_min - 1.0, center_y, FillRule::NonZero).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "kurbo")]
+    fn kurbo_path() {
+        let data = test_fonts::minimal_font(false).build();
+        let font = Font::decode(&data).unwrap();
+        let path = font.glyph_bez_path(GlyphId(2)).unwrap();
+        assert_eq!(path.to_svg(), "M50,0 L300,700 L550,0 L50,0 Z");
+        assert!(font.glyph_bez_path(GlyphId(0)).unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "lyon")]
+    fn lyon_path() {
+        use lyon_path::PathEvent;
+
+        let data = test_fonts::minimal_font(false).build();
+        let font = Font::decode(&data).unwrap();
+        let path = font.glyph_lyon_path(GlyphId(2)).unwrap();
+        let events = path.iter().collect::<Vec<_>>();
+        assert_eq!(events.first(), Some(&PathEvent::Begin { at: point(50.0, 0.0) }));
+        assert_eq!(events.last(), Some(&PathEvent::End {
+            last: point(50.0, 0.0),
+            first: point(50.0, 0.0),
+            close: true,
+        }));
+        assert_eq!(events.len(), 5);
+
+        // A contour which is never closed is still ended.
+        let mut sink = LyonSink::new();
+        sink.move_to(0.0, 0.0);
+        sink.quad_to(1.0, 1.0, 2.0, 0.0);
+        sink.move_to(5.0, 5.0);
+        sink.curve_to(6.0, 6.0, 7.0, 6.0, 8.0, 5.0);
+        assert_eq!(sink.build().iter().filter(|event| match *event {
+            PathEvent::End { close, .. } => !close,
+            _ => false,
+        }).count(), 2);
+
+        // Segments without a `move_to` begin at the current point, which
+        // is the origin at first and the start of a closed sub-path after.
+        let mut sink = LyonSink::new();
+        sink.line_to(1.0, 0.0);
+        sink.line_to(1.0, 1.0);
+        sink.close();
+        sink.quad_to(2.0, 2.0, 3.0, 0.0);
+        let begins = sink.build().iter().filter_map(|event| match event {
+            PathEvent::Begin { at } => Some(at),
+            _ => None,
+        }).collect::<Vec<_>>();
+        assert_eq!(begins, vec![point(0.0, 0.0), point(0.0, 0.0)]);
+    }
+}
@@ -0,0 +1,199 @@
+//! This module computes the values needed to embed a font in a PDF
+//! document.  PDF glyph space uses 1000 units per em, so every metric
+//! returned here has already been scaled from the font's `unitsPerEm`.
+
+use font::Font;
+use primitives::GlyphId;
+use table::head::Head;
+use table::hhea::Hhea;
+use table::post::Post;
+use table::os2::{Os2, FamilyKind};
+use error::{Error, Result};
+
+/// The flags of a PDF font descriptor, as described in section 9.8.2
+/// of the PDF specification.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Flags(pub u32);
+
+impl Flags {
+    pub const FIXED_PITCH: u32 = 1 << 0;
+    pub const SERIF: u32 = 1 << 1;
+    pub const SYMBOLIC: u32 = 1 << 2;
+    pub const SCRIPT: u32 = 1 << 3;
+    pub const NONSYMBOLIC: u32 = 1 << 5;
+    pub const ITALIC: u32 = 1 << 6;
+    pub const ALL_CAP: u32 = 1 << 16;
+    pub const SMALL_CAP: u32 = 1 << 17;
+    pub const FORCE_BOLD: u32 = 1 << 18;
+
+    pub fn contains(&self, flag: u32) -> bool {
+        self.0 & flag == flag
+    }
+}
+
+/// The values of a PDF `/FontDescriptor` dictionary.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FontDescriptor {
+    /// `[xMin yMin xMax yMax]` from the `head` table.
+    pub font_bbox: [f32; 4],
+    pub italic_angle: f32,
+    pub ascent: f32,
+    pub descent: f32,
+    pub cap_height: f32,
+    /// PDF requires a dominant vertical stem width, which fonts do not
+    /// record.  This is estimated from `usWeightClass`.
+    pub stem_v: f32,
+    pub flags: Flags,
+}
+
+impl FontDescriptor {
+    pub fn new(font: &Font) -> Result<FontDescriptor> {
+        let head = font.table::<Head>()?;
+        if head.units_per_em == 0 {
+            return Err(Error::InvalidData)
+        }
+        let hhea = font.table::<Hhea>()?;
+        let post = font.table::<Post>()?;
+        let os2 = font.table::<Os2>().ok();
+        let scale = |value: i16| to_pdf_units(value as f32, head.units_per_em);
+
+        let italic_angle = f32::from(post.italic_angle);
+        let ascent = scale(hhea.ascender.into());
+        // Measuring the cap height needs `cmap` and outlines, which an
+        // embedded subset may not have.
+        let cap_height = match font.cap_height().ok().and_then(|cap_height| cap_height) {
+            Some(cap_height) => scale(cap_height.value),
+            None => ascent,
+        };
+
+        // The same approximation used by several PDF producers, which
+        // gives 50 at the lightest weights, about 88 for regular and
+        // about 166 for bold.
+        let weight = os2.as_ref().map(|os2| os2.us_weight_class).unwrap_or(400) as f32;
+        let stem_v = 50.0 + (weight / 65.0).powi(2);
+
+        let mut flags = 0;
+        if post.is_fixed_pitch() {
+            flags |= Flags::FIXED_PITCH;
+        }
+
+        if italic_angle != 0.0
+            || head.is_italic()
+            || os2.as_ref().is_some_and(|os2| os2.is_italic()) {
+            flags |= Flags::ITALIC;
+        }
+
+        // The IBM font classes 1-5 and 7 are serif classes, 10 is scripts
+        // and 12 is symbolic.  Pictorial PANOSE family kinds are symbolic too.
+        let class = os2.as_ref().map(|os2| os2.family_class()).unwrap_or(0);
+        let pictorial = os2.as_ref().is_some_and(|os2| os2.panose.family_kind() == FamilyKind::LatinSymbol);
+        match class {
+            1..=5 | 7 => flags |= Flags::SERIF,
+            10 => flags |= Flags::SCRIPT,
+            _ => (),
+        }
+
+        if class == 12 || pictorial {
+            flags |= Flags::SYMBOLIC;
+        } else {
+            flags |= Flags::NONSYMBOLIC;
+        }
+
+        Ok(FontDescriptor {
+            font_bbox: [
+                scale(head.x_min),
+                scale(head.y_min),
+                scale(head.x_max),
+                scale(head.y_max),
+            ],
+            italic_angle,
+            ascent,
+            descent: scale(hhea.descender.into()),
+            cap_height,
+            stem_v,
+            flags: Flags(flags),
+        })
+    }
+}
+
+/// The advance widths of `glyphs` in PDF glyph space, suitable for
+/// a `/Widths` or `/W` array.
+pub fn widths(font: &Font, glyphs: &[GlyphId]) -> Result<Vec<f32>> {
+    let head = font.table::<Head>()?;
+    if head.units_per_em == 0 {
+        return Err(Error::InvalidData)
+    }
+    let metrics = font.metrics_for_glyphs(glyphs)?;
+    Ok(metrics.iter()
+        .map(|metric| to_pdf_units(metric.advance_width as f32, head.units_per_em))
+        .collect())
+}
+
+/// Build a `/CIDToGIDMap` stream, where `glyphs[cid]` is the glyph used
+/// for each CID.  Each entry is encoded as a big-endian 16-bit glyph id.
+pub fn cid_to_gid_map(glyphs: &[GlyphId]) -> Vec<u8> {
+    let mut map = Vec::with_capacity(glyphs.len() * 2);
+    for glyph in glyphs {
+        map.push((glyph.0 >> 8) as u8);
+        map.push(glyph.0 as u8);
+    }
+
+    map
+}
+
+fn to_pdf_units(value: f32, units_per_em: u16) -> f32 {
+    value * 1000.0 / units_per_em as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+    use test_fonts;
+
+    #[test]
+    fn font_descriptor() {
+        let data = open_file!("data/DroidSerif-Italic.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let descriptor = FontDescriptor::new(&font).expect("failed to build descriptor");
+
+        assert!(descriptor.flags.contains(Flags::ITALIC));
+        assert!(descriptor.flags.contains(Flags::NONSYMBOLIC));
+        assert!(!descriptor.flags.contains(Flags::FIXED_PITCH));
+        assert!(descriptor.italic_angle < 0.0);
+        assert!(descriptor.ascent > 0.0 && descriptor.descent < 0.0);
+
+        let glyphs = [GlyphId(0), GlyphId(3)];
+        let widths = widths(&font, &glyphs).expect("failed to read widths");
+        assert_eq!(widths.len(), 2);
+        assert_eq!(cid_to_gid_map(&glyphs), vec![0, 0, 0, 3]);
+    }
+
+    #[test]
+    fn stem_v() {
+        let stem_v = |weight: u16| {
+            let mut os2 = test_fonts::os2((800, -200, 0), (800, 200), 0);
+            os2[4..6].copy_from_slice(&weight.to_be_bytes());
+            let data = test_fonts::minimal_font(false)
+                .table(b"OS/2", os2)
+                .table(b"post", vec![0; 32])
+                .build();
+            let font = Font::decode(&data).unwrap();
+            FontDescriptor::new(&font).unwrap().stem_v
+        };
+        assert!((stem_v(1) - 50.0).abs() < 0.01);
+        assert!((stem_v(400) - 87.87).abs() < 0.01);
+        assert!((stem_v(700) - 165.98).abs() < 0.01);
+    }
+
+    #[test]
+    fn zero_units_per_em() {
+        let data = test_fonts::minimal_font(false)
+            .table(b"head", test_fonts::head(0, false))
+            .table(b"post", vec![0; 32])
+            .build();
+        let font = Font::decode(&data).unwrap();
+        assert_eq!(FontDescriptor::new(&font), Err(Error::InvalidData));
+        assert_eq!(widths(&font, &[GlyphId(1)]), Err(Error::InvalidData));
+    }
+}
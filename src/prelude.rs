@@ -0,0 +1,46 @@
+//! This module re-exports the types most code which reads fonts needs:
+//! `Font` and the trait which decodes it, the primitives glyphs and tables
+//! are named by, and the tables of every font, so that
+//! `use fontkit::prelude::*;` is enough to read one.
+//!
+//! These are the names the crate keeps stable.  The decoding machinery
+//! they are built on may change between versions.
+
+pub use decode::Decode;
+pub use error::Error;
+pub use font::Font;
+pub use primitives::{GlyphId, Tag};
+pub use table::cmap::Cmap;
+pub use table::glyf::Glyf;
+pub use table::head::Head;
+pub use table::hhea::Hhea;
+pub use table::hmtx::Hmtx;
+pub use table::kern::Kern;
+pub use table::loca::Loca;
+pub use table::maxp::Maxp;
+pub use table::name::Name;
+pub use table::os2::Os2;
+pub use table::post::Post;
+#[cfg(feature = "cff")]
+pub use table::cff::Cff;
+#[cfg(feature = "layout")]
+pub use table::gdef::Gdef;
+#[cfg(feature = "layout")]
+pub use table::layout::{Gpos, Gsub};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_with_prelude() {
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).unwrap();
+        let head = font.table::<Head>().unwrap();
+        let maxp = font.table::<Maxp>().unwrap();
+        assert_eq!(font.num_glyphs(), Ok(maxp.get_num_glyphs()));
+        assert!(head.units_per_em > 0);
+        assert!(font.glyph_index('A').unwrap().is_some_and(|glyph| glyph != GlyphId(0)));
+        assert_eq!(font.optional_table::<Kern>().map(|kern| kern.is_some()), Ok(font.table_record(Tag::new(*b"kern")).is_some()));
+    }
+}
@@ -1,11 +1,13 @@
 //! This module contains a list of font primitives.  Font primitives are data
 //! types that are built into the OpenType/TrueType font specification.
 
+#![forbid(unsafe_code)]
+
 use std::marker::PhantomData;
 use std::fmt;
 
 use error::{Error, Result};
-use decode::{Decode, DecodeRead, Decode1, EncodeSize, StaticEncodeSize};
+use decode::{sealed, Decode, DecodeRead, Decode1, Decode2, EncodeSize, StaticEncodeSize};
 use byteorder::{BigEndian, ByteOrder};
 
 /// A 32-bit signed fixed-point number: 16.16.
@@ -40,8 +42,15 @@ impl<'fnt> Decode<'fnt> for Uint24 {
 }
 
 /// A 16.16 version containing a major (16-bit) and minor (16-bit).
-//  TODO: This should consist of two 16-bit unsigned integers.
-pub struct FixedVersion(Fixed);
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FixedVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+
+/// A 16-bit glyph index into the font's glyph data.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, From)]
+pub struct GlyphId(pub u16);
 
 /// An unsigned 64-bit date and time represented in the number of seconds
 /// since midnight, January 1, 1904.
@@ -84,6 +93,7 @@ impl_decode!(
     BigEndian::read_i16 => F2Dot14,
     BigEndian::read_i32 => Fixed,
     BigEndian::read_u64 => LongDateTime,
+    BigEndian::read_u16 => GlyphId,
 
     read_u8             => u8,
     read_i8             => i8,
@@ -94,6 +104,29 @@ impl_decode!(
     BigEndian::read_i64 => i64,
 );
 
+impl From<FWord> for i16 {
+    fn from(word: FWord) -> i16 {
+        word.0
+    }
+}
+
+impl From<Uint24> for u32 {
+    fn from(value: Uint24) -> u32 {
+        value.0
+    }
+}
+
+impl From<UFWord> for u16 {
+    fn from(word: UFWord) -> u16 {
+        word.0
+    }
+}
+
+impl From<Fixed> for i32 {
+    fn from(fixed: Fixed) -> i32 {
+        fixed.0
+    }
+}
 
 impl From<Fixed> for f64 {
     fn from(fixed: Fixed) -> f64 {
@@ -107,6 +140,29 @@ impl From<Fixed> for f32 {
     }
 }
 
+impl Fixed {
+    /// The number whose 65536ths are `bits`.
+    pub const fn from_bits(bits: i32) -> Fixed {
+        Fixed(bits)
+    }
+
+    pub const fn to_bits(self) -> i32 {
+        self.0
+    }
+}
+
+impl From<F2Dot14> for i16 {
+    fn from(fdot: F2Dot14) -> i16 {
+        fdot.0
+    }
+}
+
+impl From<LongDateTime> for u64 {
+    fn from(time: LongDateTime) -> u64 {
+        time.0
+    }
+}
+
 impl From<F2Dot14> for f64 {
     fn from(fdot: F2Dot14) -> f64 {
         (fdot.0 as f64) / ((1i32 << 14) as f64)
@@ -122,13 +178,24 @@ impl From<F2Dot14> for f32 {
 /// An array of 4 bytes used to identify scripts, language systems, features,
 /// baselines, and table names.  The bytes are either in Latin-1 or
 /// treated as a 32-bit native endian indentifying integer.
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Tag(pub(crate) [u8; 4]);
 
+impl Tag {
+    pub const fn new(tag: [u8; 4]) -> Tag {
+        Tag(tag)
+    }
+
+    pub fn bytes(&self) -> [u8; 4] {
+        self.0
+    }
+}
+
 static_size!(Tag = 4);
 
 impl<'fnt> Decode<'fnt> for Tag {
     fn decode(buffer: &'fnt [u8]) -> Result<Tag> {
+        required_len!(buffer, Tag::size());
         let tag = [
             buffer[0],
             buffer[1],
@@ -145,34 +212,40 @@ impl fmt::Debug for Tag {
         use ::std::str;
         // Print the ASCII name if the name contains only
         // visible ASCII characters.  Otherwise Hex.
-        if self.0.iter().all(|&c| c >= 32 && c <= 128) {
-            let s = str::from_utf8(&self.0[..]).unwrap();
-            f.debug_tuple("Tag")
-                .field(&s)
-                .finish()
-        } else {
-            let n = (self.0[0] as u32) << 24
-                | (self.0[1] as u32) << 16
-                | (self.0[2] as u32) << 8
-                | (self.0[3] as u32);
-
-            write!(f, "Tag(0x{:08X})", n)
+        match str::from_utf8(&self.0[..]) {
+            Ok(s) if self.0.iter().all(|&c| (32..127).contains(&c)) => {
+                f.debug_tuple("Tag")
+                    .field(&s)
+                    .finish()
+            },
+            _ => {
+                let n = (self.0[0] as u32) << 24
+                    | (self.0[1] as u32) << 16
+                    | (self.0[2] as u32) << 8
+                    | (self.0[3] as u32);
+
+                write!(f, "Tag(0x{:08X})", n)
+            },
         }
     }
 }
 
 /// A 16-bit unsigned integer, representing an offset to a table `T`.
 /// This type can only be used when the table is known at compile time.
+#[doc(hidden)]
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub struct Offset16<'fnt, T: 'fnt> {
+    pub(crate) offset: usize,
     pub(crate) buffer: &'fnt [u8],
     pub(crate) table: PhantomData<T>,
 }
 
 /// A 32-bit unsigned integer, representing an offset to a table `T`.
 /// This type can only be used when the table is known at compile time.
+#[doc(hidden)]
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub struct Offset32<'fnt, T: 'fnt> {
+    pub(crate) offset: usize,
     pub(crate) buffer: &'fnt [u8],
     pub(crate) table: PhantomData<T>,
 }
@@ -193,6 +266,7 @@ impl<'fnt, T> Decode1<'fnt, &'fnt [u8]> for Offset16<'fnt, T> {
         let buf = &parent[offset..];
 
         Ok(Offset16 {
+            offset,
             buffer: buf,
             table: PhantomData
         })
@@ -202,35 +276,115 @@ impl<'fnt, T> Decode1<'fnt, &'fnt [u8]> for Offset16<'fnt, T> {
 impl<'fnt, T> Decode1<'fnt, &'fnt [u8]> for Offset32<'fnt, T> {
     fn decode(buffer: &[u8], parent: &'fnt [u8]) -> Result<Offset32<'fnt, T>> {
         required_len!(buffer, Self::size());
-        let offset = u16::decode(buffer)? as usize;
+        let offset = u32::decode(buffer)? as usize;
         required_len!(parent, offset);
         let buf = &parent[offset..];
 
         Ok(Offset32 {
+            offset,
             buffer: buf,
             table: PhantomData
         })
     }
 }
 
-impl<'fnt, T> fmt::Debug for Offset16<'fnt, T> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Offset16")
+/// A zero offset is `NULL` in most tables, so an optional offset decodes
+/// zero as `None` rather than as an offset to the start of the parent.
+/// Tables in which zero is a legal offset use `Offset16` itself, or mark
+/// a derived field `#[AllowZeroOffset]`.
+impl<'fnt, T> StaticEncodeSize for Option<Offset16<'fnt, T>> {
+    fn size() -> usize { 2 }
+}
+
+impl<'fnt, T> StaticEncodeSize for Option<Offset32<'fnt, T>> {
+    fn size() -> usize { 4 }
+}
+
+impl<'fnt, T> Decode1<'fnt, &'fnt [u8]> for Option<Offset16<'fnt, T>> {
+    fn decode(buffer: &'fnt [u8], parent: &'fnt [u8]) -> Result<Option<Offset16<'fnt, T>>> {
+        match u16::decode(buffer)? {
+            0 => Ok(None),
+            _ => Offset16::decode(buffer, parent).map(Some),
+        }
     }
 }
 
-impl<'fnt, T> fmt::Debug for Offset32<'fnt, T> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Offset32")
+impl<'fnt, T> Decode1<'fnt, &'fnt [u8]> for Option<Offset32<'fnt, T>> {
+    fn decode(buffer: &'fnt [u8], parent: &'fnt [u8]) -> Result<Option<Offset32<'fnt, T>>> {
+        match u32::decode(buffer)? {
+            0 => Ok(None),
+            _ => Offset32::decode(buffer, parent).map(Some),
+        }
     }
 }
 
-// TODO: implement a reasonable Debug for `Offset*`.  This shoud look
-//       something like `Offset<Type>`.
+macro_rules! impl_offset {
+    ($($name:ident),*) => (
+        $(
+        impl<'fnt, T> $name<'fnt, T> {
+            /// The offset from the start of the parent table.
+            pub fn offset(&self) -> usize {
+                self.offset
+            }
+        }
+
+        impl<'fnt, T> $name<'fnt, T> where T: Decode<'fnt> {
+            /// Decode the table at this offset.
+            pub fn table(&self) -> Result<T> {
+                T::decode(self.buffer)
+            }
+        }
+
+        /// Prints the target type and offset, such as `Offset32<Cmap>(0x0001A4)`.
+        /// The alternate form `{:#?}` also decodes and prints the target.
+        impl<'fnt, T> fmt::Debug for $name<'fnt, T> where T: Decode<'fnt> + fmt::Debug {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}<{}>(0x{:06X})", stringify!($name), type_name::<T>(), self.offset)?;
+                if f.alternate() {
+                    write!(f, " ")?;
+                    match self.table() {
+                        Ok(table) => fmt::Debug::fmt(&table, f)?,
+                        Err(err) => write!(f, "Err({:?})", err)?,
+                    }
+                }
+
+                Ok(())
+            }
+        }
+        )*
+    )
+}
+
+impl_offset!(Offset16, Offset32);
+
+/// The name of `T` without module paths, including within generic
+/// parameters, such as `Array<TableRecord>`.
+fn type_name<T>() -> String {
+    let full = ::std::any::type_name::<T>();
+    let mut name = String::with_capacity(full.len());
+    let mut path_start = 0;
+    let mut rest = full;
+
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("::") {
+            name.truncate(path_start);
+            rest = &rest[2..];
+            continue
+        }
+
+        name.push(c);
+        if !(c.is_alphanumeric() || c == '_') {
+            path_start = name.len();
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+
+    name
+}
 
 /// The `Ignored` type indicates that a type will not
 /// be decoded, and instead skipped over.
-
+#[doc(hidden)]
 #[derive(Copy, Clone, Hash, PartialEq, Eq)]
 pub struct Ignored<T>(PhantomData<T>);
 
@@ -252,8 +406,17 @@ impl<T> fmt::Debug for Ignored<T> {
 
 /// A `Discarded<T>` type will parse the type `T` but will neglect
 /// to save this type in the decoded struct.
+#[doc(hidden)]
 pub struct Discarded<T>(pub PhantomData<T>);
 
+impl<T> Copy for Discarded<T> {}
+
+impl<T> Clone for Discarded<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
 impl<T> fmt::Debug for Discarded<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Discarded")
@@ -266,23 +429,40 @@ impl<T> fmt::Debug for Discarded<T> {
 /// `Array` implements `EncodeSize` but not `StaticEncodeSize`.  An `Array` also
 /// requires `T` to have implement `StaticEncodeSize` to properly implement random
 /// access.
-
-#[derive(Copy, Clone)]
 pub struct Array<'fnt, T> {
     buffer: &'fnt [u8],
     len: usize,
-    _phantom: PhantomData<T>,
+    _phantom: PhantomData<fn() -> T>,
 }
 
-impl<'fnt, T> fmt::Debug for Array<'fnt, T> {
+// An `Array` only borrows the font, so it is always `Copy`, `Send` and
+// `Sync` regardless of `T`, which it decodes rather than holds.
+impl<'fnt, T> Copy for Array<'fnt, T> {}
+
+impl<'fnt, T> Clone for Array<'fnt, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+/// Prints the element type and length, such as `Array<TableRecord>[len=17]`.
+/// The alternate form `{:#?}` also decodes and prints every element.
+impl<'fnt, T> fmt::Debug for Array<'fnt, T> where T: Decode<'fnt> + StaticEncodeSize + fmt::Debug {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Array")
+        write!(f, "Array<{}>[len={}]", type_name::<T>(), self.len)?;
+        if f.alternate() {
+            write!(f, " ")?;
+            f.debug_list().entries(*self).finish()?;
+        }
+
+        Ok(())
     }
 }
 
 
-impl<'fnt, T> Decode1<'fnt, usize> for Array<'fnt, T> {
+impl<'fnt, T> Decode1<'fnt, usize> for Array<'fnt, T> where T: StaticEncodeSize {
     fn decode(buffer: &'fnt [u8], param: usize) -> Result<Array<'fnt, T>> {
+        required_len!(buffer, T::size() * param);
         Ok(Array {
             buffer,
             len: param,
@@ -297,13 +477,163 @@ impl<'fnt, T> EncodeSize for Array<'fnt, T> where T: StaticEncodeSize {
     }
 }
 
-/// An iterator of type `T` constructed from an `Array<T>`.
+impl<'fnt, T> Array<'fnt, T> {
+    /// The number of elements in the array.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<'fnt, T> Array<'fnt, T>
+where
+    T: Decode<'fnt> + StaticEncodeSize
+{
+    /// Decode the element at `index`, or `None` if the index is out of bounds.
+    pub fn get(&self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None
+        }
+
+        T::decode(&self.buffer[index * T::size()..]).ok()
+    }
+
+    /// Decode the last element of the array, if one exists.
+    pub fn last(&self) -> Option<T> {
+        self.len.checked_sub(1).and_then(|index| self.get(index))
+    }
+}
+
+/// The integer types used to encode the offsets of an `OffsetArray`.
+#[doc(hidden)]
+pub trait OffsetType: StaticEncodeSize + sealed::Sealed {
+    fn read_offset(buffer: &[u8]) -> Result<usize>;
+}
+
+impl OffsetType for u16 {
+    fn read_offset(buffer: &[u8]) -> Result<usize> {
+        u16::decode(buffer).map(|offset| offset as usize)
+    }
+}
+
+impl OffsetType for u32 {
+    fn read_offset(buffer: &[u8]) -> Result<usize> {
+        u32::decode(buffer).map(|offset| offset as usize)
+    }
+}
+
+/// An array of `O` offsets to variable sized `T` records, such as the
+/// lookups of a lookup list.  Offsets are relative to a parent slice, and
+/// elements are only decoded when accessed.
+#[doc(hidden)]
+pub struct OffsetArray<'fnt, T, O> {
+    offsets: &'fnt [u8],
+    parent: &'fnt [u8],
+    len: usize,
+    _phantom: PhantomData<fn() -> (T, O)>,
+}
+
+impl<'fnt, T, O> Copy for OffsetArray<'fnt, T, O> {}
+
+impl<'fnt, T, O> Clone for OffsetArray<'fnt, T, O> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+/// Prints like `Array`, with the alternate form `{:#?}` decoding each element.
+impl<'fnt, T, O> fmt::Debug for OffsetArray<'fnt, T, O>
+where
+    T: Decode<'fnt> + fmt::Debug + 'fnt,
+    O: OffsetType + 'fnt,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "OffsetArray<{}, {}>[len={}]", type_name::<T>(), type_name::<O>(), self.len)?;
+        if f.alternate() {
+            write!(f, " ")?;
+            f.debug_list().entries(self.iter()).finish()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'fnt, T, O> Decode2<'fnt, usize, &'fnt [u8]> for OffsetArray<'fnt, T, O> where O: OffsetType {
+    fn decode(buffer: &'fnt [u8], len: usize, parent: &'fnt [u8]) -> Result<OffsetArray<'fnt, T, O>> {
+        required_len!(buffer, O::size() * len);
+        Ok(OffsetArray {
+            offsets: &buffer[..O::size() * len],
+            parent,
+            len,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl<'fnt, T, O> EncodeSize for OffsetArray<'fnt, T, O> where O: OffsetType {
+    fn encode_size(&self) -> usize {
+        O::size() * self.len
+    }
+}
+
+impl<'fnt, T, O> OffsetArray<'fnt, T, O> where O: OffsetType {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
 
+    /// The offset of element `index` from the start of the parent.
+    pub fn offset(&self, index: usize) -> Option<usize> {
+        if index >= self.len {
+            return None
+        }
+
+        O::read_offset(&self.offsets[index * O::size()..]).ok()
+    }
+}
+
+impl<'fnt, T, O> OffsetArray<'fnt, T, O>
+where
+    T: Decode<'fnt>,
+    O: OffsetType,
+{
+    /// Decode the element at `index`, or `None` if the index is out of
+    /// bounds.  An offset beyond the end of the parent is an error.
+    pub fn get(&self, index: usize) -> Result<Option<T>> {
+        let offset = match self.offset(index) {
+            Some(offset) => offset,
+            None => return Ok(None),
+        };
+
+        let data = self.parent.get(offset..).ok_or(Error::UnexpectedEof)?;
+        T::decode(data).map(Some)
+    }
+
+    /// Iterate over the decoded elements.
+    pub fn iter(&self) -> impl Iterator<Item = Result<T>> + 'fnt where T: 'fnt, O: 'fnt {
+        let array = *self;
+        (0..self.len).map(move |index| array.get(index).map(Option::unwrap))
+    }
+}
+
+/// An iterator of type `T` constructed from an `Array<T>`.
 pub struct ArrayIter<'fnt, T> {
     buffer: &'fnt [u8],
     len: usize,
     pos: usize,
-    _phantom: PhantomData<T>,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<'fnt, T> Clone for ArrayIter<'fnt, T> {
+    fn clone(&self) -> Self {
+        ArrayIter { _phantom: PhantomData, ..*self }
+    }
 }
 
 impl<'fnt, T> Iterator for ArrayIter<'fnt, T>
@@ -344,7 +674,112 @@ mod tests {
     use super::*;
 
     #[test]
-    #[allow(overflowing_literals)]
+    fn misaligned_and_truncated() {
+        // Multi-byte values decode identically at every alignment.
+        let bytes = [0, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0];
+        assert_eq!(u16::decode(&bytes[1..]), Ok(0x1234));
+        assert_eq!(u32::decode(&bytes[1..]), Ok(0x12345678));
+        assert_eq!(u32::decode(&bytes[2..]), Ok(0x3456789A));
+        assert_eq!(i64::decode(&bytes[1..]), Ok(0x123456789ABCDEF0));
+        assert_eq!(Tag::decode(&bytes[3..]), Ok(Tag([0x56, 0x78, 0x9A, 0xBC])));
+
+        // Odd lengths which end partway through a value are errors.
+        assert_eq!(u16::decode(&bytes[8..]), Err(Error::UnexpectedEof));
+        assert_eq!(u32::decode(&bytes[6..]), Err(Error::UnexpectedEof));
+        assert_eq!(Uint24::decode(&bytes[7..]).err(), Some(Error::UnexpectedEof));
+        assert_eq!(Array::<u16>::decode(&bytes[1..], 4).map(|array| array.last()), Ok(Some(0xDEF0)));
+        assert_eq!(Array::<u16>::decode(&bytes[2..], 4).err(), Some(Error::UnexpectedEof));
+    }
+
+    #[derive(Decode, Debug)]
+    #[allow(dead_code)]
+    struct TagList<'fnt> {
+        count: Discarded<u16>,
+        #[WithParam = "count as usize"]
+        #[WithParam = "buffer"]
+        tags: OffsetArray<'fnt, Tag, u16>,
+    }
+
+    #[test]
+    fn offset_array() {
+        let data = [0, 3, 0, 8, 0, 12, 0, 9, b'a', b'b', b'c', b'd', b'e', b'f', b'g', b'h'];
+        let list = TagList::decode(&data).expect("failed to read list");
+        let tags = list.tags.iter().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(tags, vec![Tag(*b"abcd"), Tag(*b"efgh"), Tag(*b"bcde")]);
+        assert_eq!(list.tags.get(3), Ok(None));
+
+        let truncated = OffsetArray::<Tag, u16>::decode(&data[2..], 3, &data[..12]).unwrap();
+        assert_eq!(truncated.get(0), Ok(Some(Tag(*b"abcd"))));
+        assert!(truncated.get(1).is_err());
+        assert!(OffsetArray::<Tag, u32>::decode(&data[2..], 4, &data).is_err());
+    }
+
+    #[derive(Decode, Debug)]
+    struct OptionalTags<'fnt> {
+        first: Option<Offset16<'fnt, Tag>>,
+        second: Option<Offset32<'fnt, Tag>>,
+        #[AllowZeroOffset]
+        header: Option<Offset16<'fnt, Tag>>,
+        last: Offset16<'fnt, Tag>,
+    }
+
+    #[test]
+    fn nullable_offsets() {
+        let data = [0, 10, 0, 0, 0, 0, 0, 0, 0, 0, b'a', b'b', b'c', b'd'];
+        let tags = OptionalTags::decode(&data).expect("failed to read tags");
+        assert_eq!(tags.first.map(|offset| offset.table()), Some(Ok(Tag(*b"abcd"))));
+        assert!(tags.second.is_none());
+        assert_eq!(tags.header.map(|offset| offset.offset()), Some(0));
+        assert_eq!(tags.last.offset(), 0);
+
+        assert_eq!(Option::<Offset32<Tag>>::decode(&data[6..], &data).map(|offset| offset.is_none()), Ok(true));
+        assert_eq!(Option::<Offset16<Tag>>::decode(&data[..1], &data).err(), Some(Error::UnexpectedEof));
+        assert_eq!(Option::<Offset16<Tag>>::decode(&data, &data[..4]).err(), Some(Error::UnexpectedEof));
+    }
+
+    #[derive(Decode, Debug)]
+    struct AnchoredTags<'fnt> {
+        __table: &'fnt [u8],
+        subtable: Offset16<'fnt, Tag>,
+        #[Anchor = "self"]
+        relative: Offset16<'fnt, Tag>,
+        #[Anchor = "__table"]
+        table: Option<Offset32<'fnt, Tag>>,
+    }
+
+    #[test]
+    fn offset_anchors() {
+        let data = [b'a', b'b', b'c', b'd', 0, 8, 0, 4, 0, 0, 0, 1, b'e', b'f', b'g', b'h'];
+        let tags = AnchoredTags::decode(&data[4..], &data).expect("failed to read tags");
+        assert_eq!(tags.subtable.table(), Ok(Tag(*b"efgh")));
+        assert_eq!(tags.relative.table(), Ok(Tag([0, 1, b'e', b'f'])));
+        assert_eq!(tags.table.map(|offset| offset.table()), Some(Ok(Tag(*b"bcd\0"))));
+    }
+
+    #[test]
+    fn debug_output() {
+        use font::TableRecord;
+
+        let data = [0, 4, b'h', b'e', b'a', b'd', 0, 0, 0, 1, 0, 0, 0, 8, 0, 0, 0, 2];
+        let records = Array::<TableRecord>::decode(&data[2..], 1).unwrap();
+        assert_eq!(format!("{:?}", records), "Array<TableRecord>[len=1]");
+        assert!(format!("{:#?}", records).contains("Tag(\n"));
+
+        let offset = Offset32::<Tag>::decode(&data[10..], &data).unwrap();
+        assert_eq!(format!("{:?}", offset), "Offset32<Tag>(0x000008)");
+        assert_eq!(format!("{:#?}", offset), "Offset32<Tag>(0x000008) Tag(0x00010000)");
+
+        let tags = OffsetArray::<Tag, u16>::decode(&data, 1, &data).unwrap();
+        assert_eq!(format!("{:?}", tags), "OffsetArray<Tag, u16>[len=1]");
+
+        // Only printable ASCII tags are printed as text.
+        assert_eq!(format!("{:?}", Tag(*b"OS/2")), "Tag(\"OS/2\")");
+        assert_eq!(format!("{:?}", Tag([b'a', b'b', b'c', 0x80])), "Tag(0x61626380)");
+        assert_eq!(format!("{:?}", Tag([b'a', b'b', b'c', 0x7F])), "Tag(0x6162637F)");
+    }
+
+    #[test]
+    #[allow(overflowing_literals, clippy::excessive_precision)]
     fn f2dot14_to_float() {
         assert_eq!(1.99993896484375,  f64::from(F2Dot14(0x7fff)));
         assert_eq!(1.75,              f64::from(F2Dot14(0x7000)));
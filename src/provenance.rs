@@ -0,0 +1,242 @@
+//! This module maps the slices which tables are decoded from back to
+//! their place in the font file, so that an error can say where it
+//! happened: "UnexpectedEof at font offset 0x2A31C, table GPOS+0x11C".
+//!
+//! `Error` stays a plain value, since it is compared and copied
+//! throughout.  Instead, a read past the end of a buffer records the
+//! buffer it failed on, and `TableSlice::in_context` turns the record
+//! into the offset of the failing read within the table.  Any other
+//! error, such as `InvalidData`, is located at the start of the table,
+//! since a record may be left by a read whose failure was ignored, and a
+//! decoder which knows the slice it failed on can wrap the error with
+//! `TableSlice::error_at` itself.
+
+#![forbid(unsafe_code)]
+
+use std::cell::Cell;
+use std::error;
+use std::fmt;
+
+use decode::Decode;
+use font::Font;
+use primitives::Tag;
+use table::TaggedTable;
+use error::{Error, Result};
+
+/// A place in a font file, within a table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TableLocation {
+    pub tag: Tag,
+    /// The offset from the start of the table.
+    pub table_offset: usize,
+    /// The offset from the start of the font file.
+    pub font_offset: usize,
+}
+
+impl fmt::Display for TableLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let tag = self.tag.0.iter()
+            .map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '?' })
+            .collect::<String>();
+        write!(f, "font offset 0x{:X}, table {}+0x{:X}", self.font_offset, tag.trim_end(), self.table_offset)
+    }
+}
+
+/// An error with the place in the font where it happened.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ContextError {
+    pub error: Error,
+    pub location: TableLocation,
+}
+
+impl fmt::Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} at {}", self.error, self.location)
+    }
+}
+
+impl error::Error for ContextError {}
+
+impl From<ContextError> for Error {
+    fn from(error: ContextError) -> Error {
+        error.error
+    }
+}
+
+thread_local! {
+    /// The address and length of the buffer of the last read which failed
+    /// for lack of bytes.
+    static FAILURE: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+}
+
+/// Note that a read from `buffer` failed, for `TableSlice::in_context` to
+/// locate.  Called by `required_len!`.
+pub(crate) fn record_failure(buffer: &[u8]) {
+    FAILURE.with(|failure| failure.set(Some((buffer.as_ptr() as usize, buffer.len()))));
+}
+
+/// The bytes of a table, with the offset of the table in the font file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TableSlice<'fnt> {
+    tag: Tag,
+    base: usize,
+    data: &'fnt [u8],
+}
+
+impl<'fnt> TableSlice<'fnt> {
+    pub fn new(tag: Tag, base: usize, data: &'fnt [u8]) -> TableSlice<'fnt> {
+        TableSlice { tag, base, data }
+    }
+
+    pub fn tag(&self) -> Tag {
+        self.tag
+    }
+
+    /// The offset of the table in the font file.
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    pub fn data(&self) -> &'fnt [u8] {
+        self.data
+    }
+
+    /// The location `table_offset` bytes into the table.
+    pub fn location(&self, table_offset: usize) -> TableLocation {
+        TableLocation { tag: self.tag, table_offset, font_offset: self.base + table_offset }
+    }
+
+    /// The location of the start of `part`, or `None` if `part` is not a
+    /// slice of this table.  Only the addresses are compared, so an empty
+    /// slice at the end of the table is located too.
+    pub fn locate(&self, part: &[u8]) -> Option<TableLocation> {
+        self.locate_address(part.as_ptr() as usize, part.len())
+    }
+
+    fn locate_address(&self, address: usize, len: usize) -> Option<TableLocation> {
+        let start = self.data.as_ptr() as usize;
+        if address < start || address + len > start + self.data.len() {
+            return None
+        }
+
+        Some(self.location(address - start))
+    }
+
+    /// Wrap `error`, which happened while decoding `part`, with its
+    /// location.  A `part` outside the table is located at its start.
+    pub fn error_at(&self, part: &[u8], error: Error) -> ContextError {
+        let location = self.locate(part).unwrap_or_else(|| self.location(0));
+        ContextError { error, location }
+    }
+
+    /// Run `decode`, which reads from this table, and locate an
+    /// `UnexpectedEof` it returns at the read which failed, and any other
+    /// error, or one no read within the table recorded, at the start of
+    /// the table.  Subtables which are decoded on demand
+    /// are located the same way:
+    /// `slice.in_context(|| gpos.lookup(index))`.
+    pub fn in_context<T, F>(&self, decode: F) -> ::std::result::Result<T, ContextError>
+        where F: FnOnce() -> Result<T>
+    {
+        FAILURE.with(|failure| failure.set(None));
+        decode().map_err(|error| {
+            let failure = FAILURE.with(|failure| failure.take()).filter(|_| error == Error::UnexpectedEof);
+            let location = failure.and_then(|(address, len)| self.locate_address(address, len))
+                .unwrap_or_else(|| self.location(0));
+            ContextError { error, location }
+        })
+    }
+
+    /// Decode the table, locating any error as `in_context` does.
+    pub fn decode<T: Decode<'fnt>>(&self) -> ::std::result::Result<T, ContextError> {
+        self.in_context(|| T::decode(self.data))
+    }
+}
+
+impl<'fnt> Font<'fnt> {
+    /// The bytes of the table identified by `tag`, with its offset.
+    pub fn table_slice(&self, tag: Tag) -> Result<TableSlice<'fnt>> {
+        let record = self.table_record(tag).ok_or(Error::MissingTable)?;
+        Ok(TableSlice::new(tag, record.offset as usize, self.table_data(tag)?))
+    }
+
+    /// Decode a table as `Font::table` does, with the location of the
+    /// table in any error.
+    pub fn table_in_context<T>(&self) -> ::std::result::Result<T, ContextError>
+        where T: TaggedTable<'fnt> + Decode<'fnt>
+    {
+        let slice = self.table_slice(T::tag()).map_err(|error| ContextError {
+            error,
+            location: TableLocation { tag: T::tag(), table_offset: 0, font_offset: 0 },
+        })?;
+        slice.decode()
+    }
+
+    /// The location of `part`, a slice of some table of the font, such as
+    /// the slice a subtable was decoded from.
+    pub fn locate(&self, part: &[u8]) -> Option<TableLocation> {
+        self.table_directory().tables().into_iter()
+            .filter_map(|record| self.table_slice(record.tag).ok())
+            .filter_map(|slice| slice.locate(part))
+            .next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use table::cmap::Cmap;
+    use table::head::Head;
+    use table::hhea::Hhea;
+    use test_fonts;
+
+    #[test]
+    fn locate_errors() {
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let head = font.table_slice(Head::tag()).unwrap();
+        let record = font.table_record(Head::tag()).unwrap();
+        assert_eq!(head.base(), record.offset as usize);
+
+        let location = font.locate(&head.data()[0x10..0x12]).unwrap();
+        assert_eq!(location, TableLocation { tag: Head::tag(), table_offset: 0x10, font_offset: record.offset as usize + 0x10 });
+        assert_eq!(font.locate(&[0; 4]), None);
+
+        let error = head.error_at(&head.data()[0x12..], Error::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            format!("InvalidData at font offset 0x{:X}, table head+0x12", record.offset + 0x12),
+        );
+
+        // A truncated `hhea` is reported at the first field which is cut
+        // off.
+        let data = test_fonts::minimal_font(false).table(b"hhea", vec![0; 9]).build();
+        let font = Font::decode(&data).unwrap();
+        let error = font.table_in_context::<Hhea>().unwrap_err();
+        assert_eq!((error.error, error.location.table_offset), (Error::UnexpectedEof, 8));
+        assert_eq!(error.location.font_offset, font.table_record(Hhea::tag()).unwrap().offset as usize + 8);
+        assert_eq!(Error::from(error), Error::UnexpectedEof);
+
+        // A subtable decoded on demand is located within its table, here
+        // at the groups of the format 12 subtable which starts at 12.
+        let cmap = test_fonts::cmap(&[('a', 1)]);
+        let data = test_fonts::minimal_font(false).table(b"cmap", cmap[..cmap.len() - 4].to_vec()).build();
+        let font = Font::decode(&data).unwrap();
+        let slice = font.table_slice(Cmap::tag()).unwrap();
+        let table = slice.decode::<Cmap>().unwrap();
+        let error = slice.in_context(|| table.unicode_subtable()).unwrap_err();
+        assert_eq!((error.error, error.location.table_offset), (Error::UnexpectedEof, 28));
+
+        // An error which no read recorded is located at the start.
+        let error = slice.in_context(|| -> Result<()> { Err(Error::InvalidData) }).unwrap_err();
+        assert_eq!(error.location, slice.location(0));
+
+        // A failed read whose error was ignored does not locate a later
+        // error of another kind.
+        let error = slice.in_context(|| -> Result<()> {
+            assert!(Hhea::decode(&slice.data()[4..9]).is_err());
+            Err(Error::InvalidData)
+        }).unwrap_err();
+        assert_eq!(error.location, slice.location(0));
+    }
+}
@@ -0,0 +1,699 @@
+//! This module renders glyph outlines to anti-aliased coverage bitmaps at
+//! a size in pixels per em.
+//!
+//! Coverage is computed exactly from the signed area each outline edge
+//! sweeps through a pixel, accumulated along each row.  Curves are
+//! flattened to lines first.  Outlines are not hinted, so the fractional
+//! pen offset of subpixel positioning is applied directly to the outline.
+//!
+//! A `SyntheticStyle` simulates a bold face for families which lack one,
+//! by emboldening outlines before they are rendered.
+//!
+//! A `Blending` describes how coverage is handed to the toolkit which
+//! blends it: the gamma it is encoded with, and the filter which spreads
+//! the subpixels of LCD rendering to soften color fringes.
+
+use font::Font;
+use glyph_key::{self, GlyphKey, FNV_OFFSET_BASIS};
+use outline::{BoundsSink, Outline, OutlineSink};
+#[cfg(feature = "color")]
+use outline::Transform;
+use primitives::GlyphId;
+use table::head::Head;
+use error::{Error, Result};
+
+/// An 8-bit coverage bitmap, in rows from top to bottom.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bitmap {
+    pub width: usize,
+    pub height: usize,
+    /// The offset of the left edge from the pen position, in pixels.
+    pub left: i32,
+    /// The offset of the top edge above the baseline, in pixels.
+    pub top: i32,
+    pub data: Vec<u8>,
+}
+
+impl Bitmap {
+    /// The coverage of the pixel in `column` of `row`, counted from the
+    /// top left.
+    pub fn get(&self, column: usize, row: usize) -> Option<u8> {
+        if column >= self.width {
+            return None
+        }
+        self.data.get(row * self.width + column).cloned()
+    }
+}
+
+/// A bitmap of the coverage of each color subpixel of an LCD, in rows
+/// from top to bottom, with the red, green and blue coverage of each
+/// pixel in turn.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LcdBitmap {
+    pub width: usize,
+    pub height: usize,
+    /// The offset of the left edge from the pen position, in pixels.
+    pub left: i32,
+    /// The offset of the top edge above the baseline, in pixels.
+    pub top: i32,
+    pub data: Vec<u8>,
+}
+
+impl LcdBitmap {
+    /// The red, green and blue coverage of the pixel in `column` of `row`,
+    /// counted from the top left.
+    pub fn get(&self, column: usize, row: usize) -> Option<[u8; 3]> {
+        if column >= self.width {
+            return None
+        }
+        let pixel = self.data.get((row * self.width + column) * 3..)?.get(..3)?;
+        Some([pixel[0], pixel[1], pixel[2]])
+    }
+}
+
+/// How coverage is encoded for the toolkit which blends it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Gamma {
+    /// Coverage as it is, for toolkits which blend in linear light.
+    Linear,
+    /// Coverage encoded with the sRGB transfer function, for toolkits
+    /// which blend sRGB colors without decoding them, so that text keeps
+    /// the weight it has when blended in linear light.
+    Srgb,
+    /// Coverage raised to the power of `1 / gamma`.  Values above 1
+    /// darken and thicken edges, and 1.8 to 2.2 are typical.
+    Perceptual(f32),
+}
+
+impl Gamma {
+    /// The encoded value of each coverage value.
+    fn table(&self) -> [u8; 256] {
+        let mut table = [0; 256];
+        for (coverage, entry) in table.iter_mut().enumerate() {
+            let linear = coverage as f32 / 255.0;
+            let encoded = match *self {
+                Gamma::Linear => linear,
+                Gamma::Srgb if linear <= 0.003_130_8 => linear * 12.92,
+                Gamma::Srgb => 1.055 * linear.powf(1.0 / 2.4) - 0.055,
+                Gamma::Perceptual(gamma) if gamma > 0.0 => linear.powf(1.0 / gamma),
+                Gamma::Perceptual(_) => linear,
+            };
+            *entry = (encoded.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+        table
+    }
+}
+
+/// The order of the color subpixels of an LCD, from left to right.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SubpixelOrder {
+    Rgb,
+    Bgr,
+}
+
+/// A five tap filter which spreads the coverage of each LCD subpixel over
+/// its neighbours, trading sharpness for fewer color fringes.  Weights are
+/// in 1/256ths, and should sum to 256 to keep solid areas solid.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LcdFilter {
+    pub weights: [u8; 5],
+    pub order: SubpixelOrder,
+}
+
+impl LcdFilter {
+    /// The default filter of FreeType.
+    pub const DEFAULT: LcdFilter = LcdFilter { weights: [8, 77, 86, 77, 8], order: SubpixelOrder::Rgb };
+    /// The light filter of FreeType, which is sharper but fringes more.
+    pub const LIGHT: LcdFilter = LcdFilter { weights: [0, 85, 86, 85, 0], order: SubpixelOrder::Rgb };
+
+    pub fn new(weights: [u8; 5]) -> LcdFilter {
+        LcdFilter { weights, order: SubpixelOrder::Rgb }
+    }
+
+    /// The filter for a display whose subpixels are in `order`.
+    pub fn with_order(self, order: SubpixelOrder) -> LcdFilter {
+        LcdFilter { order, ..self }
+    }
+
+    /// The filtered coverage of each subpixel of `samples`.
+    fn apply(&self, samples: &[u8]) -> Vec<u8> {
+        (0..samples.len())
+            .map(|index| {
+                let sum = self.weights.iter().enumerate()
+                    .filter_map(|(tap, &weight)| {
+                        let sample = (index + tap).checked_sub(2).and_then(|sample| samples.get(sample))?;
+                        Some(u32::from(*sample) * u32::from(weight))
+                    })
+                    .sum::<u32>();
+                ((sum + 128) / 256).min(255) as u8
+            })
+            .collect()
+    }
+}
+
+impl Default for LcdFilter {
+    fn default() -> LcdFilter {
+        LcdFilter::DEFAULT
+    }
+}
+
+/// How a `ScaledFont` prepares coverage for blending.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Blending {
+    pub gamma: Gamma,
+    /// The filter of `ScaledFont::rasterize_lcd`.
+    pub lcd_filter: LcdFilter,
+}
+
+impl Default for Blending {
+    fn default() -> Blending {
+        Blending { gamma: Gamma::Linear, lcd_filter: LcdFilter::default() }
+    }
+}
+
+impl Blending {
+    /// A hash of the blending which is stable between runs and builds, and
+    /// zero for the default.
+    pub fn hash(&self) -> u64 {
+        if *self == Blending::default() {
+            return 0
+        }
+
+        let (kind, gamma) = match self.gamma {
+            Gamma::Linear => (0, 1.0),
+            Gamma::Srgb => (1, 0.0),
+            Gamma::Perceptual(gamma) => (2, gamma),
+        };
+        let hash = glyph_key::fnv1a(FNV_OFFSET_BASIS, &[kind, self.lcd_filter.order as u8]);
+        let hash = glyph_key::fnv1a(hash, &gamma.to_bits().to_be_bytes());
+        glyph_key::fnv1a(hash, &self.lcd_filter.weights).max(1)
+    }
+}
+
+/// The styles a `ScaledFont` simulates for a family without the face.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct SyntheticStyle {
+    /// The width added to stems and to advances, as a fraction of the em.
+    pub embolden: f32,
+}
+
+impl SyntheticStyle {
+    /// A bold which adds `strength` of the em to each stem.  FreeType
+    /// emboldens by 1/24 em.
+    pub fn bold(strength: f32) -> SyntheticStyle {
+        SyntheticStyle { embolden: strength }
+    }
+}
+
+/// A font at a size in pixels per em.
+#[derive(Debug, Copy, Clone)]
+pub struct ScaledFont<'a, 'fnt: 'a> {
+    font: &'a Font<'fnt>,
+    ppem: f32,
+    scale: f32,
+    style: SyntheticStyle,
+    blending: Blending,
+}
+
+impl<'a, 'fnt> ScaledFont<'a, 'fnt> {
+    pub fn new(font: &'a Font<'fnt>, ppem: f32) -> Result<ScaledFont<'a, 'fnt>> {
+        let units_per_em = font.table::<Head>()?.units_per_em;
+        if units_per_em == 0 {
+            return Err(Error::InvalidData)
+        }
+        Ok(ScaledFont {
+            font,
+            ppem,
+            scale: ppem / f32::from(units_per_em),
+            style: SyntheticStyle::default(),
+            blending: Blending::default(),
+        })
+    }
+
+    /// The font rendered in `style`.
+    pub fn with_style(self, style: SyntheticStyle) -> ScaledFont<'a, 'fnt> {
+        ScaledFont { style, ..self }
+    }
+
+    pub fn style(&self) -> SyntheticStyle {
+        self.style
+    }
+
+    /// The font with its coverage prepared for `blending`.
+    pub fn with_blending(self, blending: Blending) -> ScaledFont<'a, 'fnt> {
+        ScaledFont { blending, ..self }
+    }
+
+    pub fn blending(&self) -> Blending {
+        self.blending
+    }
+
+    pub fn font(&self) -> &'a Font<'fnt> {
+        self.font
+    }
+
+    pub fn ppem(&self) -> f32 {
+        self.ppem
+    }
+
+    /// The number of pixels in a font unit.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// The advance width of `glyph` in pixels, widened by the synthetic
+    /// bold of the style.
+    pub fn advance(&self, glyph: GlyphId) -> Result<f32> {
+        let advance = self.font.hmtx()?.advance(glyph).ok_or(Error::InvalidGlyphId)?;
+        Ok(f32::from(advance) * self.scale + self.style.embolden * self.ppem)
+    }
+
+    /// Send the outline of `glyph` in the style to `sink`, in font units.
+    fn outline<S: OutlineSink>(&self, glyph: GlyphId, sink: &mut S) -> Result<()> {
+        if self.style.embolden == 0.0 {
+            return self.font.outline_glyph(glyph, sink)
+        }
+
+        let mut outline = Outline::new();
+        self.font.outline_glyph(glyph, &mut outline)?;
+        outline.embolden(self.style.embolden * self.ppem / self.scale).emit(sink);
+        Ok(())
+    }
+
+    /// Render `glyph` with the pen `x_offset` pixels right of a pixel
+    /// boundary, with coverage encoded in the gamma of the blending.  A
+    /// glyph without an outline is an empty bitmap.
+    pub fn rasterize(&self, glyph: GlyphId, x_offset: f32) -> Result<Bitmap> {
+        let mut bitmap = self.coverage(glyph, x_offset, 1, 0)?;
+        if self.blending.gamma != Gamma::Linear {
+            let table = self.blending.gamma.table();
+            for coverage in &mut bitmap.data {
+                *coverage = table[*coverage as usize];
+            }
+        }
+        Ok(bitmap)
+    }
+
+    /// Render `glyph` for an LCD, at three times the horizontal resolution,
+    /// with its subpixels spread by the filter of the blending and then
+    /// encoded in its gamma.  The bitmap has a pixel more on each side
+    /// than `rasterize` gives, for the coverage the filter spreads there.
+    pub fn rasterize_lcd(&self, glyph: GlyphId, x_offset: f32) -> Result<LcdBitmap> {
+        let samples = self.coverage(glyph, x_offset, 3, 1)?;
+        let width = samples.width / 3;
+        let (filter, table) = (self.blending.lcd_filter, self.blending.gamma.table());
+
+        let mut data = Vec::with_capacity(samples.data.len());
+        for row in samples.data.chunks(samples.width.max(1)) {
+            for pixel in filter.apply(row).chunks(3) {
+                let (red, blue) = match filter.order {
+                    SubpixelOrder::Rgb => (pixel[0], pixel[2]),
+                    SubpixelOrder::Bgr => (pixel[2], pixel[0]),
+                };
+                data.extend_from_slice(&[table[red as usize], table[pixel[1] as usize], table[blue as usize]]);
+            }
+        }
+
+        Ok(LcdBitmap { width, height: samples.height, left: samples.left, top: samples.top, data })
+    }
+
+    /// The linear coverage of `glyph`, sampled `oversample` times across
+    /// each pixel, over its bounds widened by `margin` pixels each side.
+    fn coverage(&self, glyph: GlyphId, x_offset: f32, oversample: usize, margin: i32) -> Result<Bitmap> {
+        let mut bounds = BoundsSink::new();
+        self.outline(glyph, &mut bounds)?;
+        let bounds = match bounds.bounds() {
+            Some(bounds) => bounds,
+            None => return Ok(Bitmap::default()),
+        };
+
+        let left = ((bounds.x_min * self.scale + x_offset).floor() as i32).saturating_sub(margin);
+        let right = ((bounds.x_max * self.scale + x_offset).ceil() as i32).saturating_add(margin);
+        let top = (bounds.y_max * self.scale).ceil() as i32;
+        let bottom = (bounds.y_min * self.scale).floor() as i32;
+        let (width, height) = bitmap_size(left, right, bottom, top)?;
+        let width = width * oversample;
+
+        let mut sink = RasterSink {
+            raster: Rasterizer::new(width, height),
+            scale: (self.scale * oversample as f32, self.scale),
+            x_origin: (x_offset - left as f32) * oversample as f32,
+            y_origin: top as f32,
+            start: (0.0, 0.0),
+            current: (0.0, 0.0),
+            open: false,
+        };
+        self.outline(glyph, &mut sink)?;
+        sink.close();
+
+        Ok(Bitmap { width, height, left, top, data: sink.raster.coverage() })
+    }
+
+    /// Render `glyph` once for each of `buckets` equal fractions of a
+    /// pixel, for subpixel positioning.  The bitmap of a pen position is
+    /// the one at the bucket `glyph_key::subpixel_bucket` gives it.
+    pub fn rasterize_buckets(&self, glyph: GlyphId, buckets: u8) -> Result<Vec<Bitmap>> {
+        (0..buckets.max(1))
+            .map(|bucket| self.rasterize(glyph, glyph_key::subpixel_offset(bucket, buckets)))
+            .collect()
+    }
+
+    /// The cache key of `glyph` rendered in `bucket`.
+    pub fn glyph_key(&self, glyph: GlyphId, bucket: u8) -> GlyphKey {
+        GlyphKey::new(glyph, self.ppem, self.ppem, bucket, &[])
+            .with_embolden(self.style.embolden * self.ppem)
+            .with_blending_hash(self.blending.hash())
+    }
+}
+
+/// The most pixels a bitmap may have.  Glyphs are far smaller at any
+/// practical size, so larger bounds come from a font with huge coordinates
+/// or a tiny em, and are rejected rather than allocated.
+pub const MAX_BITMAP_PIXELS: usize = 1 << 22;
+
+/// The width and height of a bitmap between pixel edges, which is at least
+/// a pixel each way, or `Error::InvalidData` if it has more than
+/// `MAX_BITMAP_PIXELS` pixels.
+pub(crate) fn bitmap_size(left: i32, right: i32, bottom: i32, top: i32) -> Result<(usize, usize)> {
+    let width = (i64::from(right) - i64::from(left)).max(1) as u64;
+    let height = (i64::from(top) - i64::from(bottom)).max(1) as u64;
+    if width * height > MAX_BITMAP_PIXELS as u64 {
+        return Err(Error::InvalidData)
+    }
+    Ok((width as usize, height as usize))
+}
+
+/// The coverage of the outline of `glyph` mapped by `transform` from font
+/// units to a `width` by `height` grid of pixels, with y pointing down.
+#[cfg(feature = "color")]
+pub(crate) fn rasterize_transformed(font: &Font, glyph: GlyphId, transform: Transform, width: usize, height: usize) -> Result<Vec<u8>> {
+    let mut sink = RasterSink {
+        raster: Rasterizer::new(width, height),
+        scale: (1.0, 1.0),
+        x_origin: 0.0,
+        y_origin: 0.0,
+        start: (0.0, 0.0),
+        current: (0.0, 0.0),
+        open: false,
+    };
+    // The sink flips y itself, so it is flipped back first.
+    font.outline_glyph_transformed(glyph, transform.then(&Transform::scale(1.0, -1.0)), &mut sink)?;
+    sink.close();
+    Ok(sink.raster.coverage())
+}
+
+/// Maps outline coordinates to the pixel grid of a bitmap, with y
+/// pointing down, and draws the edges.  The grid may be finer across than
+/// down, for subpixel rendering.
+struct RasterSink {
+    raster: Rasterizer,
+    scale: (f32, f32),
+    x_origin: f32,
+    y_origin: f32,
+    start: (f32, f32),
+    current: (f32, f32),
+    open: bool,
+}
+
+impl RasterSink {
+    fn point(&self, x: f32, y: f32) -> (f32, f32) {
+        (x * self.scale.0 + self.x_origin, self.y_origin - y * self.scale.1)
+    }
+
+    fn line(&mut self, to: (f32, f32)) {
+        self.raster.draw_line(self.current, to);
+        self.current = to;
+    }
+}
+
+impl OutlineSink for RasterSink {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.close();
+        self.start = self.point(x, y);
+        self.current = self.start;
+        self.open = true;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let to = self.point(x, y);
+        self.line(to);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (p0, p1, p2) = (self.current, self.point(x1, y1), self.point(x, y));
+        let deviation = length((p0.0 - 2.0 * p1.0 + p2.0, p0.1 - 2.0 * p1.1 + p2.1));
+        for t in flatten_steps(deviation) {
+            let mt = 1.0 - t;
+            let point = (
+                mt * mt * p0.0 + 2.0 * mt * t * p1.0 + t * t * p2.0,
+                mt * mt * p0.1 + 2.0 * mt * t * p1.1 + t * t * p2.1,
+            );
+            self.line(point);
+        }
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (p0, p1, p2, p3) = (self.current, self.point(x1, y1), self.point(x2, y2), self.point(x, y));
+        let deviation = length((p0.0 - 2.0 * p1.0 + p2.0, p0.1 - 2.0 * p1.1 + p2.1))
+            .max(length((p1.0 - 2.0 * p2.0 + p3.0, p1.1 - 2.0 * p2.1 + p3.1)));
+        for t in flatten_steps(deviation * 1.5) {
+            let mt = 1.0 - t;
+            let (a, b, c, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+            let point = (
+                a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+                a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1,
+            );
+            self.line(point);
+        }
+    }
+
+    /// Contours are closed with a line back to their start, whether or not
+    /// the outline closes them.
+    fn close(&mut self) {
+        if self.open {
+            let start = self.start;
+            self.line(start);
+            self.open = false;
+        }
+    }
+}
+
+fn length(vector: (f32, f32)) -> f32 {
+    (vector.0 * vector.0 + vector.1 * vector.1).sqrt()
+}
+
+/// The parameters of the points a curve is flattened to, ending at 1.  A
+/// curve's distance from its chord is at most a quarter of its second
+/// difference `deviation`, and shrinks with the square of the number of
+/// segments, which keeps the error below a tenth of a pixel.
+fn flatten_steps(deviation: f32) -> impl Iterator<Item = f32> {
+    let count = ((deviation * 2.5).sqrt().ceil() as usize).clamp(1, 100);
+    (1..=count).map(move |step| step as f32 / count as f32)
+}
+
+/// Accumulates the signed area covered by edges in each pixel, from which
+/// the coverage follows by summing along each row.
+struct Rasterizer {
+    width: usize,
+    height: usize,
+    accumulation: Vec<f32>,
+}
+
+impl Rasterizer {
+    fn new(width: usize, height: usize) -> Rasterizer {
+        // An edge on the right boundary adds to the cell past the end of
+        // its row, which is the start of the next.
+        Rasterizer { width, height, accumulation: vec![0.0; width * height + 2] }
+    }
+
+    fn add(&mut self, row: usize, column: i32, value: f32) {
+        let column = column.clamp(0, self.width as i32) as usize;
+        if let Some(cell) = self.accumulation.get_mut(row * self.width + column) {
+            *cell += value;
+        }
+    }
+
+    fn draw_line(&mut self, from: (f32, f32), to: (f32, f32)) {
+        if from.1 == to.1 {
+            return
+        }
+
+        let (direction, top, bottom) = if from.1 < to.1 { (1.0, from, to) } else { (-1.0, to, from) };
+        let dxdy = (bottom.0 - top.0) / (bottom.1 - top.1);
+        let first_row = top.1.max(0.0);
+        let mut x = top.0 + (first_row - top.1) * dxdy;
+
+        let end = (bottom.1.ceil().max(0.0) as usize).min(self.height);
+        for row in first_row as usize..end {
+            let dy = (row as f32 + 1.0).min(bottom.1) - (row as f32).max(top.1);
+            let x_next = x + dxdy * dy;
+            let d = dy * direction;
+            let (x0, x1) = if x < x_next { (x, x_next) } else { (x_next, x) };
+            let (x0_floor, x1_ceil) = (x0.floor(), x1.ceil());
+            let (x0i, x1i) = (x0_floor as i32, x1_ceil as i32);
+
+            if x1i <= x0i + 1 {
+                // The edge stays within one pixel of this row.
+                let fraction = 0.5 * (x + x_next) - x0_floor;
+                self.add(row, x0i, d - d * fraction);
+                self.add(row, x0i + 1, d * fraction);
+            } else {
+                let s = 1.0 / (x1 - x0);
+                let x0_fraction = x0 - x0_floor;
+                let a0 = 0.5 * s * (1.0 - x0_fraction) * (1.0 - x0_fraction);
+                let x1_fraction = x1 - x1_ceil + 1.0;
+                let am = 0.5 * s * x1_fraction * x1_fraction;
+                self.add(row, x0i, d * a0);
+                if x1i == x0i + 2 {
+                    self.add(row, x0i + 1, d * (1.0 - a0 - am));
+                } else {
+                    let a1 = s * (1.5 - x0_fraction);
+                    self.add(row, x0i + 1, d * (a1 - a0));
+                    for column in x0i + 2..x1i - 1 {
+                        self.add(row, column, d * s);
+                    }
+                    let a2 = a1 + (x1i - x0i - 3) as f32 * s;
+                    self.add(row, x1i - 1, d * (1.0 - a2 - am));
+                }
+                self.add(row, x1i, d * am);
+            }
+            x = x_next;
+        }
+    }
+
+    /// The coverage of each pixel under the nonzero winding rule, where
+    /// overlapping contours saturate.
+    fn coverage(&self) -> Vec<u8> {
+        let mut sum = 0.0;
+        self.accumulation[..self.width * self.height]
+            .iter()
+            .map(|&cell| {
+                sum += cell;
+                (sum.abs().min(1.0) * 255.0).round() as u8
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+    use test_fonts;
+
+    #[test]
+    fn rasterize_square() {
+        let data = test_fonts::minimal_font(false).build();
+        let font = Font::decode(&data).unwrap();
+        let scaled = ScaledFont::new(&font, 10.0).unwrap();
+        assert_eq!(scaled.advance(GlyphId(1)), Ok(7.0));
+
+        // The square covers whole pixels from (1, 0) to (6, 5).
+        let bitmap = scaled.rasterize(GlyphId(1), 0.0).unwrap();
+        assert_eq!((bitmap.width, bitmap.height, bitmap.left, bitmap.top), (5, 5, 1, 5));
+        assert!(bitmap.data.iter().all(|&coverage| coverage == 255));
+
+        // Half a pixel right, the edge columns are half covered.
+        let bitmap = scaled.rasterize(GlyphId(1), 0.5).unwrap();
+        assert_eq!((bitmap.width, bitmap.left), (6, 1));
+        assert_eq!((bitmap.get(0, 2), bitmap.get(3, 2), bitmap.get(5, 2)), (Some(128), Some(255), Some(128)));
+        assert_eq!(bitmap.get(6, 0), None);
+
+        assert_eq!(scaled.rasterize(GlyphId(0), 0.0), Ok(Bitmap::default()));
+        assert!(scaled.rasterize(GlyphId(3), 0.0).is_err());
+
+        // Bitmaps too large to allocate are rejected.
+        let huge = ScaledFont::new(&font, 1.0e6).unwrap();
+        assert_eq!(huge.rasterize(GlyphId(1), 0.0), Err(Error::InvalidData));
+        assert_eq!(huge.rasterize_lcd(GlyphId(1), 0.0), Err(Error::InvalidData));
+        assert_eq!(bitmap_size(i32::MIN, i32::MAX, 0, 1), Err(Error::InvalidData));
+        assert_eq!(bitmap_size(0, 2048, -2048, 0), Ok((2048, 2048)));
+
+        // The counter of a curved `O` is empty and its stroke solid.
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let scaled = ScaledFont::new(&font, 40.0).unwrap();
+        let bitmap = scaled.rasterize(font.glyph_index('O').unwrap().unwrap(), 0.0).unwrap();
+        let middle = bitmap.height / 2;
+        assert_eq!(bitmap.get(bitmap.width / 2, middle), Some(0));
+        assert_eq!(bitmap.get(2, middle), Some(255));
+    }
+
+    #[test]
+    fn synthetic_bold() {
+        let data = test_fonts::minimal_font(false).build();
+        let font = Font::decode(&data).unwrap();
+        let scaled = ScaledFont::new(&font, 10.0).unwrap().with_style(SyntheticStyle::bold(0.1));
+        assert_eq!(scaled.advance(GlyphId(1)), Ok(8.0));
+        assert_ne!(scaled.glyph_key(GlyphId(1), 0), ScaledFont::new(&font, 10.0).unwrap().glyph_key(GlyphId(1), 0));
+
+        // The square grows a pixel up and right, keeping its left edge and
+        // the baseline.
+        let bitmap = scaled.rasterize(GlyphId(1), 0.0).unwrap();
+        assert_eq!((bitmap.width, bitmap.height, bitmap.left, bitmap.top), (6, 6, 1, 6));
+        assert!(bitmap.data.iter().all(|&coverage| coverage == 255));
+        assert_eq!(scaled.rasterize(GlyphId(0), 0.0), Ok(Bitmap::default()));
+    }
+
+    #[test]
+    fn blending() {
+        let data = test_fonts::minimal_font(false).build();
+        let font = Font::decode(&data).unwrap();
+        let scaled = ScaledFont::new(&font, 10.0).unwrap();
+        assert_eq!(scaled.blending().hash(), 0);
+
+        // Gamma lifts partial coverage and keeps solid and empty pixels.
+        let edge = |gamma: Gamma| {
+            let bitmap = scaled.with_blending(Blending { gamma, ..Blending::default() }).rasterize(GlyphId(1), 0.5).unwrap();
+            (bitmap.get(0, 2), bitmap.get(3, 2))
+        };
+        assert_eq!(edge(Gamma::Linear), (Some(128), Some(255)));
+        assert_eq!(edge(Gamma::Srgb), (Some(188), Some(255)));
+        assert_eq!(edge(Gamma::Perceptual(2.2)), (Some(186), Some(255)));
+        assert_eq!(Gamma::Perceptual(2.2).table()[0], 0);
+
+        // The square fills whole pixels, and the filter spreads its edges
+        // a pixel out on either side.
+        let bitmap = scaled.rasterize_lcd(GlyphId(1), 0.0).unwrap();
+        assert_eq!((bitmap.width, bitmap.height, bitmap.left, bitmap.top), (7, 5, 0, 5));
+        assert_eq!(bitmap.get(0, 2), Some([0, 8, 85]));
+        assert_eq!(bitmap.get(1, 2), Some([170, 247, 255]));
+        assert_eq!(bitmap.get(3, 2), Some([255, 255, 255]));
+        assert_eq!(bitmap.get(6, 2), Some([85, 8, 0]));
+        assert_eq!(bitmap.get(7, 2), None);
+
+        let filter = LcdFilter::LIGHT.with_order(SubpixelOrder::Bgr);
+        let blending = Blending { gamma: Gamma::Linear, lcd_filter: filter };
+        let light = scaled.with_blending(blending);
+        assert_eq!(light.rasterize_lcd(GlyphId(1), 0.0).unwrap().get(0, 2), Some([85, 0, 0]));
+        assert_eq!(light.rasterize(GlyphId(1), 0.0), scaled.rasterize(GlyphId(1), 0.0));
+        assert_eq!(scaled.rasterize_lcd(GlyphId(0), 0.0), Ok(LcdBitmap::default()));
+
+        // Each blending renders differently, so it keys the cache.
+        assert_ne!(light.glyph_key(GlyphId(1), 0), scaled.glyph_key(GlyphId(1), 0));
+        assert_ne!(blending.hash(), Blending { lcd_filter: LcdFilter::LIGHT, ..blending }.hash());
+    }
+
+    #[test]
+    fn rasterize_subpixel_buckets() {
+        let data = test_fonts::minimal_font(false).build();
+        let font = Font::decode(&data).unwrap();
+        let scaled = ScaledFont::new(&font, 10.0).unwrap();
+
+        let bitmaps = scaled.rasterize_buckets(GlyphId(2), 4).unwrap();
+        assert_eq!(bitmaps.len(), 4);
+        assert_eq!(bitmaps[0], scaled.rasterize(GlyphId(2), 0.0).unwrap());
+        assert_eq!(bitmaps[2], scaled.rasterize(GlyphId(2), 0.5).unwrap());
+        assert_ne!(bitmaps[1], bitmaps[0]);
+
+        // Each shifted triangle still covers its area of 17.5 pixels.
+        for bitmap in &bitmaps {
+            let area = bitmap.data.iter().map(|&coverage| f32::from(coverage) / 255.0).sum::<f32>();
+            assert!((area - 17.5).abs() < 0.1, "area {}", area);
+        }
+
+        let (pixel, bucket) = glyph_key::subpixel_bucket(12.74, 4);
+        assert_eq!((pixel, bucket), (12, 3));
+        assert_eq!(scaled.glyph_key(GlyphId(2), bucket).ppem_x, 640);
+    }
+}
@@ -0,0 +1,813 @@
+//! This module contains a minimal shaper for scripts which need no
+//! reordering or contextual forms, such as Latin, Greek and Cyrillic.
+//!
+//! It is not a replacement for HarfBuzz.  Only single, multiple and
+//! ligature substitutions, single and pair adjustments, and contextual
+//! lookups nesting these are applied; chaining lookups are skipped.
+//! Lookup flags are honored with the glyph classes of `GDEF`, so a lookup
+//! passes over the bases, ligatures or marks it ignores, and marks outside
+//! its mark filtering set or attachment class.  That is enough for
+//! ligatures and kerning in most Western text, which is often all a UI
+//! toolkit needs.
+
+use std::borrow::Cow;
+
+use decode::{Decode, Decode1};
+use font::Font;
+use primitives::{GlyphId, Tag};
+use tags::features;
+use table::gdef::{Gdef, GlyphClass};
+use table::layout::{AccelLayoutTable, AccelSubtable, ClassDef, Coverage, Gpos, Gsub, Lookup, SequenceContext, ValueRecord};
+use unicode::{extends_grapheme, is_default_ignorable, is_variation_selector, script_runs, Script};
+use error::{Error, Result};
+
+/// Features which most text should be shaped with: glyph composition,
+/// standard ligatures and kerning.
+pub const DEFAULT_FEATURES: [Tag; 3] = [features::CCMP, features::LIGA, features::KERN];
+
+/// A glyph and its position, in font units.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PositionedGlyph {
+    pub glyph: GlyphId,
+    /// The byte offset in the text of the first grapheme the glyph was
+    /// shaped from.
+    pub cluster: usize,
+    pub x_advance: i32,
+    pub y_advance: i32,
+    pub x_offset: i32,
+    pub y_offset: i32,
+}
+
+/// The `GSUB` and `GPOS` tables of a font, expanded for shaping many runs
+/// of text with `shape_cached`, and its `GDEF` table.
+#[derive(Debug, Clone)]
+pub struct ShapeCache<'fnt> {
+    gsub: Option<AccelLayoutTable<'fnt>>,
+    gpos: Option<AccelLayoutTable<'fnt>>,
+    gdef: Option<Gdef<'fnt>>,
+}
+
+impl<'fnt> ShapeCache<'fnt> {
+    pub fn new(font: &Font<'fnt>) -> Result<ShapeCache<'fnt>> {
+        Ok(ShapeCache {
+            gsub: font.optional_table::<Gsub>()?.map(|gsub| AccelLayoutTable::gsub(&gsub)).transpose()?,
+            gpos: font.optional_table::<Gpos>()?.map(|gpos| AccelLayoutTable::gpos(&gpos)).transpose()?,
+            gdef: font.optional_table::<Gdef>()?,
+        })
+    }
+
+    /// The bytes the expanded lookups take on the heap.
+    pub fn heap_size(&self) -> usize {
+        self.gsub.iter().chain(&self.gpos).map(AccelLayoutTable::heap_size).sum()
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Item {
+    pub(crate) glyph: GlyphId,
+    pub(crate) cluster: usize,
+}
+
+/// Shape `text` with the `GSUB` and `GPOS` lookups of `features`, which
+/// is usually `DEFAULT_FEATURES`.  The text is split into runs of one
+/// script, and each run is shaped with the lookups the font lists for its
+/// script, or for `DFLT`.  Lookups do not apply across runs.
+///
+/// Combining marks and joiners are kept in the cluster of the character
+/// before them.  Characters the font does not map become `.notdef`,
+/// except default ignorable joiners and variation selectors, which are
+/// dropped.  A variation sequence the font supports in `cmap` becomes the
+/// glyph it maps to.
+pub fn shape_simple(font: &Font, text: &str, features: &[Tag]) -> Result<Vec<PositionedGlyph>> {
+    shape(font, None, text, features)
+}
+
+/// Shape `text` as `shape_simple` does, with the lookups of `cache`, which
+/// must have been built from `font`.  Coverage and class lookups take
+/// constant time, rather than a search of the table for every glyph.
+pub fn shape_cached(font: &Font, cache: &ShapeCache, text: &str, features: &[Tag]) -> Result<Vec<PositionedGlyph>> {
+    shape(font, Some(cache), text, features)
+}
+
+fn shape(font: &Font, cache: Option<&ShapeCache>, text: &str, features: &[Tag]) -> Result<Vec<PositionedGlyph>> {
+    let mut items = map_text(font, text)?;
+    let mut glyphs = Vec::with_capacity(items.len());
+    for run in script_runs(text) {
+        let count = items.iter().take_while(|item| item.cluster < run.range.end).count();
+        let run_items = items.drain(..count).collect();
+        glyphs.extend(shape_run(font, cache, run_items, run.script, features)?);
+    }
+
+    Ok(glyphs)
+}
+
+/// Shape the glyphs of a run of text in `script`.
+fn shape_run(font: &Font, cache: Option<&ShapeCache>, mut items: Vec<Item>, script: Script, features: &[Tag]) -> Result<Vec<PositionedGlyph>> {
+    let read_gdef;
+    let gdef = match cache {
+        Some(cache) => cache.gdef.as_ref(),
+        None => {
+            read_gdef = font.optional_table::<Gdef>()?;
+            read_gdef.as_ref()
+        },
+    };
+
+    match cache {
+        Some(&ShapeCache { gsub: Some(ref gsub), .. }) => {
+            let tables = Tables { lookups: LookupList::Accel(gsub), gdef };
+            for index in gsub.lookup_indices(gsub.select_script(script.tags())?, features)? {
+                let lookup = gsub.lookup(index)?;
+                substitute(&lookup.lookup, lookup.subtables(), tables, &mut items)?;
+            }
+        },
+        Some(_) => {},
+        None => if let Some(gsub) = font.optional_table::<Gsub>()? {
+            let tables = Tables { lookups: LookupList::Gsub(&gsub), gdef };
+            for index in gsub.lookup_indices(gsub.select_script(script.tags())?, features)? {
+                substitute(&gsub.lookup(index)?, &[], tables, &mut items)?;
+            }
+        },
+    }
+
+    let hmtx = font.hmtx()?;
+    let mut glyphs = items.iter()
+        .map(|item| Ok(PositionedGlyph {
+            glyph: item.glyph,
+            cluster: item.cluster,
+            x_advance: i32::from(hmtx.advance(item.glyph).ok_or(Error::InvalidGlyphId)?),
+            y_advance: 0,
+            x_offset: 0,
+            y_offset: 0,
+        }))
+        .collect::<Result<Vec<_>>>()?;
+
+    match cache {
+        Some(&ShapeCache { gpos: Some(ref gpos), .. }) => {
+            let tables = Tables { lookups: LookupList::Accel(gpos), gdef };
+            for index in gpos.lookup_indices(gpos.select_script(script.tags())?, features)? {
+                let lookup = gpos.lookup(index)?;
+                position(&lookup.lookup, lookup.subtables(), tables, &mut glyphs)?;
+            }
+        },
+        Some(_) => {},
+        None => if let Some(gpos) = font.optional_table::<Gpos>()? {
+            let tables = Tables { lookups: LookupList::Gpos(&gpos), gdef };
+            for index in gpos.lookup_indices(gpos.select_script(script.tags())?, features)? {
+                position(&gpos.lookup(index)?, &[], tables, &mut glyphs)?;
+            }
+        },
+    }
+
+    Ok(glyphs)
+}
+
+/// Map the characters of `text` to glyphs, as `shape_simple` describes.
+pub(crate) fn map_text(font: &Font, text: &str) -> Result<Vec<Item>> {
+    let mut items: Vec<Item> = Vec::new();
+    let mut cluster = 0;
+    let mut previous = None;
+    for (index, character) in text.char_indices() {
+        if index == 0 || !extends_grapheme(character) {
+            cluster = index;
+        }
+
+        // A variation selector the font supports replaces the glyph of the
+        // character before it.
+        if let Some(base) = previous.filter(|_| is_variation_selector(character)) {
+            if let Some(glyph) = font.glyph_variant(base, character)? {
+                if let Some(item) = items.last_mut() {
+                    item.glyph = glyph;
+                }
+                previous = None;
+                continue
+            }
+        }
+
+        previous = None;
+        match font.glyph_index(character)? {
+            Some(glyph) => {
+                items.push(Item { glyph, cluster });
+                previous = Some(character);
+            },
+            None if is_default_ignorable(character) => {},
+            None => {
+                items.push(Item { glyph: GlyphId(0), cluster });
+                previous = Some(character);
+            },
+        }
+    }
+
+    Ok(items)
+}
+
+/// Apply the `GSUB` lookups of `features` for `script` to `items`.
+pub(crate) fn substitute_features(font: &Font, items: &mut Vec<Item>, script: Tag, features: &[Tag]) -> Result<()> {
+    if let Some(gsub) = font.optional_table::<Gsub>()? {
+        let gdef = font.optional_table::<Gdef>()?;
+        let tables = Tables { lookups: LookupList::Gsub(&gsub), gdef: gdef.as_ref() };
+        for index in gsub.lookup_indices(script, features)? {
+            substitute(&gsub.lookup(index)?, &[], tables, items)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Result<u16> {
+    u16::decode(data.get(pos..).ok_or(Error::UnexpectedEof)?)
+}
+
+/// The coverage index of `glyph` in `subtable`, from the expanded
+/// coverage of `accel` when there is one.
+fn coverage_index(subtable: &[u8], accel: Option<&AccelSubtable>, glyph: GlyphId) -> Result<Option<u16>> {
+    if let Some(coverage) = accel.and_then(|accel| accel.coverage.as_ref()) {
+        return Ok(coverage.index(glyph))
+    }
+
+    let offset = read_u16(subtable, 2)? as usize;
+    let coverage = Coverage::decode(subtable.get(offset..).ok_or(Error::UnexpectedEof)?)?;
+    Ok(coverage.index(glyph))
+}
+
+/// The glyphs a lookup passes over, from its flag and the glyph classes
+/// of `GDEF`.  Without `GDEF` no glyph is skipped.
+#[derive(Copy, Clone)]
+struct GlyphFilter<'a, 'fnt: 'a> {
+    gdef: Option<&'a Gdef<'fnt>>,
+    flag: u16,
+    mark_filtering_set: Option<u16>,
+    mark_attachment_type: u16,
+}
+
+impl<'a, 'fnt> GlyphFilter<'a, 'fnt> {
+    fn new(lookup: &Lookup, gdef: Option<&'a Gdef<'fnt>>) -> GlyphFilter<'a, 'fnt> {
+        GlyphFilter {
+            gdef,
+            flag: lookup.flag,
+            mark_filtering_set: lookup.mark_filtering_set,
+            mark_attachment_type: lookup.mark_attachment_type(),
+        }
+    }
+
+    fn skips(&self, glyph: GlyphId) -> bool {
+        let gdef = match self.gdef {
+            Some(gdef) => gdef,
+            None => return false,
+        };
+        match gdef.glyph_class(glyph) {
+            Some(GlyphClass::Base) => self.flag & Lookup::IGNORE_BASE_GLYPHS != 0,
+            Some(GlyphClass::Ligature) => self.flag & Lookup::IGNORE_LIGATURES != 0,
+            // A mark filtering set supersedes the mark attachment type.
+            Some(GlyphClass::Mark) => self.flag & Lookup::IGNORE_MARKS != 0 || match self.mark_filtering_set {
+                Some(set) => !gdef.is_in_mark_glyph_set(set, glyph),
+                None => self.mark_attachment_type != 0 && gdef.mark_attachment_class(glyph) != self.mark_attachment_type,
+            },
+            _ => false,
+        }
+    }
+
+    /// The position of the first glyph after `pos` which is not skipped,
+    /// where `glyph_at` gives the glyph at a position, or `None` past the
+    /// end.
+    fn next<F: Fn(usize) -> Option<GlyphId>>(&self, pos: usize, glyph_at: F) -> Option<usize> {
+        let mut next = pos + 1;
+        loop {
+            if !self.skips(glyph_at(next)?) {
+                return Some(next)
+            }
+            next += 1;
+        }
+    }
+}
+
+/// The lookup list of a `GSUB` or `GPOS` table, from which contextual
+/// subtables take their nested lookups.
+#[derive(Copy, Clone)]
+enum LookupList<'a, 'fnt: 'a> {
+    Gsub(&'a Gsub<'fnt>),
+    Gpos(&'a Gpos<'fnt>),
+    Accel(&'a AccelLayoutTable<'fnt>),
+}
+
+impl<'a, 'fnt> LookupList<'a, 'fnt> {
+    /// The lookup at `index`, with the expanded tables of its subtables or
+    /// none.
+    fn lookup(&self, index: u16) -> Result<(Cow<'a, Lookup<'fnt>>, &'a [AccelSubtable])> {
+        match *self {
+            LookupList::Gsub(gsub) => Ok((Cow::Owned(gsub.lookup(index)?), &[])),
+            LookupList::Gpos(gpos) => Ok((Cow::Owned(gpos.lookup(index)?), &[])),
+            LookupList::Accel(table) => {
+                let lookup = table.lookup(index)?;
+                Ok((Cow::Borrowed(&lookup.lookup), lookup.subtables()))
+            },
+        }
+    }
+}
+
+/// The tables lookups are applied with, besides their own subtables.
+#[derive(Copy, Clone)]
+struct Tables<'a, 'fnt: 'a> {
+    lookups: LookupList<'a, 'fnt>,
+    gdef: Option<&'a Gdef<'fnt>>,
+}
+
+/// Contextual subtables may nest lookups which are contextual in turn;
+/// deeper nesting than this is not applied, so a font cannot recurse
+/// without end.
+const MAX_NESTING: usize = 16;
+
+/// The glyphs `GSUB` or `GPOS` lookups are applied to, so that both apply
+/// contextual subtables, and the lookups they nest, in the same way.
+trait LookupBuffer {
+    /// The lookup type of contextual subtables.
+    const CONTEXT_TYPE: u16;
+
+    fn glyph_at(&self, pos: usize) -> Option<GlyphId>;
+
+    fn len(&self) -> usize;
+
+    /// Apply one subtable of a lookup which is not contextual at `pos`,
+    /// returning the number of glyphs to move past, or `None` if it does
+    /// not apply.
+    fn apply_subtable(&mut self,
+                      lookup_type: u16,
+                      subtable: &[u8],
+                      accel: Option<&AccelSubtable>,
+                      filter: &GlyphFilter,
+                      pos: usize) -> Result<Option<usize>>;
+}
+
+impl LookupBuffer for Vec<Item> {
+    const CONTEXT_TYPE: u16 = 5;
+
+    fn glyph_at(&self, pos: usize) -> Option<GlyphId> {
+        self.get(pos).map(|item| item.glyph)
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn apply_subtable(&mut self,
+                      lookup_type: u16,
+                      subtable: &[u8],
+                      accel: Option<&AccelSubtable>,
+                      filter: &GlyphFilter,
+                      pos: usize) -> Result<Option<usize>> {
+        substitute_at(lookup_type, subtable, accel, filter, self, pos)
+    }
+}
+
+impl LookupBuffer for [PositionedGlyph] {
+    const CONTEXT_TYPE: u16 = 7;
+
+    fn glyph_at(&self, pos: usize) -> Option<GlyphId> {
+        self.get(pos).map(|glyph| glyph.glyph)
+    }
+
+    fn len(&self) -> usize {
+        <[PositionedGlyph]>::len(self)
+    }
+
+    fn apply_subtable(&mut self,
+                      lookup_type: u16,
+                      subtable: &[u8],
+                      accel: Option<&AccelSubtable>,
+                      filter: &GlyphFilter,
+                      pos: usize) -> Result<Option<usize>> {
+        position_at(lookup_type, subtable, accel, filter, self, pos)
+    }
+}
+
+/// Apply a lookup to each position of `buffer` in turn.  `accel` holds
+/// the expanded tables of the subtables, or is empty.
+fn apply_lookup<B>(lookup: &Lookup, accel: &[AccelSubtable], tables: Tables, buffer: &mut B) -> Result<()>
+    where B: LookupBuffer + ?Sized
+{
+    let mut pos = 0;
+    while pos < buffer.len() {
+        pos += apply_at(lookup, accel, tables, buffer, pos, 0)?.unwrap_or(1);
+    }
+
+    Ok(())
+}
+
+/// Apply the first subtable of a lookup which applies at `pos`, returning
+/// the number of glyphs to move past, or `None` if none applies or the
+/// lookup skips the glyph.
+fn apply_at<B>(lookup: &Lookup, accel: &[AccelSubtable], tables: Tables, buffer: &mut B, pos: usize, depth: usize) -> Result<Option<usize>>
+    where B: LookupBuffer + ?Sized
+{
+    let filter = GlyphFilter::new(lookup, tables.gdef);
+    match buffer.glyph_at(pos) {
+        Some(glyph) if !filter.skips(glyph) => {},
+        _ => return Ok(None),
+    }
+
+    for (index, subtable) in lookup.subtables().iter().enumerate() {
+        let applied = if lookup.lookup_type == B::CONTEXT_TYPE {
+            apply_context(subtable, tables, &filter, buffer, pos, depth)?
+        } else {
+            buffer.apply_subtable(lookup.lookup_type, subtable, accel.get(index), &filter, pos)?
+        };
+        if applied.is_some() {
+            return Ok(applied)
+        }
+    }
+
+    Ok(None)
+}
+
+/// Apply a contextual subtable at `pos`: if a rule matches the glyphs
+/// there, apply its nested lookups, and return the number of glyphs the
+/// sequence spans afterwards.
+fn apply_context<B>(subtable: &[u8], tables: Tables, filter: &GlyphFilter, buffer: &mut B, pos: usize, depth: usize) -> Result<Option<usize>>
+    where B: LookupBuffer + ?Sized
+{
+    if depth >= MAX_NESTING {
+        return Ok(None)
+    }
+
+    let context = SequenceContext::decode(subtable)?;
+    let first = match buffer.glyph_at(pos) {
+        Some(glyph) => glyph,
+        None => return Ok(None),
+    };
+    for rule in context.rules(first)? {
+        let mut positions = vec![pos];
+        while positions.len() < rule.glyph_count {
+            let next = filter.next(positions[positions.len() - 1], |next| buffer.glyph_at(next));
+            match next {
+                Some(next) if buffer.glyph_at(next).is_some_and(|glyph| context.matches(&rule, positions.len(), glyph)) => positions.push(next),
+                _ => break,
+            }
+        }
+        if positions.len() < rule.glyph_count || rule.glyph_count == 0 {
+            continue
+        }
+
+        // A nested substitution may change the number of glyphs, which
+        // moves the glyphs of the sequence after it.
+        let mut end = positions[positions.len() - 1] + 1;
+        for record in rule.lookups {
+            let index = record.sequence_index as usize;
+            let at = match positions.get(index) {
+                Some(&at) if at < buffer.len() => at,
+                _ => continue,
+            };
+            let (lookup, accel) = tables.lookups.lookup(record.lookup_list_index)?;
+            let len = buffer.len();
+            apply_at(&lookup, accel, tables, buffer, at, depth + 1)?;
+
+            let delta = buffer.len() as isize - len as isize;
+            for position in &mut positions[index + 1..] {
+                *position = (*position as isize + delta).max(at as isize + 1) as usize;
+            }
+            end = (end as isize + delta).max(at as isize + 1) as usize;
+        }
+        return Ok(Some(end.saturating_sub(pos).max(1)))
+    }
+
+    Ok(None)
+}
+
+/// Apply a `GSUB` lookup to each position of `items` in turn.
+fn substitute(lookup: &Lookup, accel: &[AccelSubtable], tables: Tables, items: &mut Vec<Item>) -> Result<()> {
+    apply_lookup(lookup, accel, tables, items)
+}
+
+/// Apply one substitution subtable at `pos`, returning the number of
+/// glyphs it produced, or `None` if it does not apply.
+fn substitute_at(lookup_type: u16,
+                 subtable: &[u8],
+                 accel: Option<&AccelSubtable>,
+                 filter: &GlyphFilter,
+                 items: &mut Vec<Item>,
+                 pos: usize) -> Result<Option<usize>> {
+    let index = match coverage_index(subtable, accel, items[pos].glyph)? {
+        Some(index) => index as usize,
+        None => return Ok(None),
+    };
+
+    match (lookup_type, read_u16(subtable, 0)?) {
+        // Single substitution by delta.
+        (1, 1) => {
+            let delta = read_u16(subtable, 4)?;
+            items[pos].glyph = GlyphId(items[pos].glyph.0.wrapping_add(delta));
+            Ok(Some(1))
+        },
+        // Single substitution by glyph list.
+        (1, 2) => {
+            items[pos].glyph = GlyphId(read_u16(subtable, 6 + index * 2)?);
+            Ok(Some(1))
+        },
+        // Multiple substitution.
+        (2, 1) => {
+            let offset = read_u16(subtable, 6 + index * 2)? as usize;
+            let count = read_u16(subtable, offset)? as usize;
+            let cluster = items[pos].cluster;
+            let glyphs = (0..count)
+                .map(|glyph| Ok(Item { glyph: GlyphId(read_u16(subtable, offset + 2 + glyph * 2)?), cluster }))
+                .collect::<Result<Vec<_>>>()?;
+            items.splice(pos..pos + 1, glyphs);
+            Ok(Some(count))
+        },
+        // Ligature substitution, where the first ligature which matches wins.
+        // Glyphs the lookup skips between the components are kept, after
+        // the ligature.
+        (4, 1) => {
+            let set = read_u16(subtable, 6 + index * 2)? as usize;
+            for ligature in 0..read_u16(subtable, set)? as usize {
+                let ligature = set + read_u16(subtable, set + 2 + ligature * 2)? as usize;
+                let glyph = GlyphId(read_u16(subtable, ligature)?);
+                let count = read_u16(subtable, ligature + 2)? as usize;
+                if count == 0 {
+                    continue
+                }
+
+                let mut components = vec![pos];
+                for component in 1..count {
+                    let next = filter.next(components[component - 1], |next| items.get(next).map(|item| item.glyph));
+                    match next {
+                        Some(next) if items[next].glyph.0 == read_u16(subtable, ligature + 2 + component * 2)? => components.push(next),
+                        _ => break,
+                    }
+                }
+
+                if components.len() == count {
+                    let cluster = components.iter().map(|&component| items[component].cluster).min().unwrap_or(0);
+                    for &component in components[1..].iter().rev() {
+                        items.remove(component);
+                    }
+                    items[pos] = Item { glyph, cluster };
+                    return Ok(Some(1))
+                }
+            }
+            Ok(None)
+        },
+        _ => Ok(None),
+    }
+}
+
+/// Apply a `GPOS` lookup to each position of `glyphs` in turn.
+fn position(lookup: &Lookup, accel: &[AccelSubtable], tables: Tables, glyphs: &mut [PositionedGlyph]) -> Result<()> {
+    apply_lookup(lookup, accel, tables, glyphs)
+}
+
+fn adjust(glyph: &mut PositionedGlyph, value: ValueRecord) {
+    glyph.x_offset += i32::from(value.x_placement);
+    glyph.y_offset += i32::from(value.y_placement);
+    glyph.x_advance += i32::from(value.x_advance);
+    glyph.y_advance += i32::from(value.y_advance);
+}
+
+fn value_record(subtable: &[u8], pos: usize, format: u16) -> Result<ValueRecord> {
+    ValueRecord::decode(subtable.get(pos..).ok_or(Error::UnexpectedEof)?, format)
+}
+
+/// Apply one positioning subtable at `pos`, returning the number of
+/// glyphs to move past, or `None` if it does not apply.
+fn position_at(lookup_type: u16,
+               subtable: &[u8],
+               accel: Option<&AccelSubtable>,
+               filter: &GlyphFilter,
+               glyphs: &mut [PositionedGlyph],
+               pos: usize) -> Result<Option<usize>> {
+    let index = match coverage_index(subtable, accel, glyphs[pos].glyph)? {
+        Some(index) => index as usize,
+        None => return Ok(None),
+    };
+
+    match (lookup_type, read_u16(subtable, 0)?) {
+        // Single adjustment, shared by every covered glyph.
+        (1, 1) => {
+            let value = value_record(subtable, 6, read_u16(subtable, 4)?)?;
+            adjust(&mut glyphs[pos], value);
+            Ok(Some(1))
+        },
+        // Single adjustment for each covered glyph.
+        (1, 2) => {
+            let format = read_u16(subtable, 4)?;
+            let value = value_record(subtable, 8 + index * ValueRecord::encoded_size(format), format)?;
+            adjust(&mut glyphs[pos], value);
+            Ok(Some(1))
+        },
+        // Pair adjustment, with the next glyph the lookup does not skip.
+        (2, format @ 1..=2) => {
+            let next = match filter.next(pos, |next| glyphs.get(next).map(|glyph| glyph.glyph)) {
+                Some(next) => next,
+                None => return Ok(None),
+            };
+            let second = glyphs[next].glyph;
+            let (format1, format2) = (read_u16(subtable, 4)?, read_u16(subtable, 6)?);
+            let (size1, size2) = (ValueRecord::encoded_size(format1), ValueRecord::encoded_size(format2));
+
+            // The position of the pair's value records.
+            let record = if format == 1 {
+                let set = read_u16(subtable, 10 + index * 2)? as usize;
+                let record_size = 2 + size1 + size2;
+                let found = (0..read_u16(subtable, set)? as usize)
+                    .map(|pair| set + 2 + pair * record_size)
+                    .find(|&record| read_u16(subtable, record).ok() == Some(second.0));
+                match found {
+                    Some(record) => record + 2,
+                    None => return Ok(None),
+                }
+            } else {
+                let (class1, class2) = match accel.and_then(|accel| accel.pair_classes.as_ref()) {
+                    Some((first, second_classes)) => (first.class(glyphs[pos].glyph), second_classes.class(second)),
+                    None => {
+                        let class_def = |pos: usize| -> Result<ClassDef> {
+                            let offset = read_u16(subtable, pos)? as usize;
+                            ClassDef::decode(subtable.get(offset..).ok_or(Error::UnexpectedEof)?)
+                        };
+                        (class_def(8)?.class(glyphs[pos].glyph), class_def(10)?.class(second))
+                    },
+                };
+                let (class1, class2) = (class1 as usize, class2 as usize);
+                let (class1_count, class2_count) = (read_u16(subtable, 12)? as usize, read_u16(subtable, 14)? as usize);
+                if class1 >= class1_count || class2 >= class2_count {
+                    return Ok(None)
+                }
+                16 + (class1 * class2_count + class2) * (size1 + size2)
+            };
+
+            let value1 = value_record(subtable, record, format1)?;
+            let value2 = value_record(subtable, record + size1, format2)?;
+            adjust(&mut glyphs[pos], value1);
+            adjust(&mut glyphs[next], value2);
+            Ok(Some(if format2 == 0 { next - pos } else { next - pos + 1 }))
+        },
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_fonts;
+
+    #[test]
+    fn shape_ligatures_and_kerning() {
+        // Glyph 3 is the ligature of `f` and `i`, and `A` kerns against it.
+        let gsub = test_fonts::layout_table(b"latn", &[(b"liga", &[1]), (b"smcp", &[0])], &[
+            (1, test_fonts::single_substitution(&[(1, 2)])),
+            (4, test_fonts::ligature_substitution(&[1, 2], 3)),
+        ]);
+        let gpos = test_fonts::layout_table(b"latn", &[(b"kern", &[0])], &[
+            (2, test_fonts::class_pair_adjustment(&[4], &[1, 3], -50)),
+        ]);
+        let data = test_fonts::minimal_font(false)
+            .table(b"hhea", test_fonts::hhea(800, -200, 5))
+            .table(b"maxp", test_fonts::maxp(5))
+            .table(b"hmtx", test_fonts::hmtx(&[(500, 0), (400, 0), (300, 0), (650, 0), (700, 0)], &[]))
+            .table(b"cmap", test_fonts::cmap(&[('f', 1), ('i', 2), ('A', 4)]))
+            .table(b"GSUB", gsub)
+            .table(b"GPOS", gpos)
+            .build();
+        let font = Font::decode(&data).unwrap();
+        let shaped = |text: &str, features: &[Tag]| {
+            shape_simple(&font, text, features).unwrap().into_iter()
+                .map(|glyph| (glyph.glyph.0, glyph.cluster, glyph.x_advance))
+                .collect::<Vec<_>>()
+        };
+
+        // The combining acute joins the cluster of `i`, and the joiner is
+        // dropped since the font does not map it.
+        let text = "Afi\u{301}\u{200D}";
+        assert_eq!(shaped(text, &DEFAULT_FEATURES), vec![(4, 0, 650), (3, 1, 650), (0, 2, 500)]);
+        assert_eq!(shaped(text, &[]), vec![(4, 0, 700), (1, 1, 400), (2, 2, 300), (0, 2, 500)]);
+        assert_eq!(shaped("fi", &[Tag::new(*b"smcp")]), vec![(2, 0, 300), (2, 1, 300)]);
+        assert_eq!(shaped("", &DEFAULT_FEATURES), Vec::new());
+
+        let cache = ShapeCache::new(&font).unwrap();
+        for features in &[&DEFAULT_FEATURES[..], &[Tag::new(*b"smcp")], &[]] {
+            assert_eq!(shape_cached(&font, &cache, text, features), shape_simple(&font, text, features));
+        }
+    }
+
+    #[test]
+    fn shape_lookup_flags() {
+        // Glyphs 4 and 5 are marks of attachment classes 1 and 2, and only
+        // glyph 5 is in mark glyph set 0.
+        let gdef = test_fonts::gdef(&[(1, 1), (2, 1), (3, 2), (4, 3), (5, 3), (6, 1)], &[(4, 1), (5, 2)], &[&[5]]);
+        let gsub = test_fonts::layout_table_with_flags(b"latn", &[(b"liga", &[0]), (b"ccmp", &[1])], &[
+            (4, test_fonts::ligature_substitution(&[1, 2], 3)),
+            (1, test_fonts::single_substitution(&[(4, 7), (5, 7)])),
+        ], &[(Lookup::IGNORE_MARKS, None), (0x0200, None)]);
+        let gpos = test_fonts::layout_table_with_flags(b"latn", &[(b"kern", &[0])], &[
+            (2, test_fonts::class_pair_adjustment(&[6], &[2], -50)),
+        ], &[(Lookup::USE_MARK_FILTERING_SET, Some(0))]);
+        let data = test_fonts::minimal_font(false)
+            .table(b"hhea", test_fonts::hhea(800, -200, 8))
+            .table(b"maxp", test_fonts::maxp(8))
+            .table(b"hmtx", test_fonts::hmtx(&[(500, 0), (400, 0), (300, 0), (650, 0), (0, 0), (0, 0), (700, 0), (0, 0)], &[]))
+            .table(b"cmap", test_fonts::cmap(&[('f', 1), ('i', 2), ('A', 6), ('\u{301}', 4), ('\u{323}', 5)]))
+            .table(b"GDEF", gdef)
+            .table(b"GSUB", gsub)
+            .table(b"GPOS", gpos)
+            .build();
+        let font = Font::decode(&data).unwrap();
+        let cache = ShapeCache::new(&font).unwrap();
+        let shaped = |text: &str, feature: &[u8; 4]| {
+            let features = [Tag::new(*feature)];
+            let glyphs = shape_simple(&font, text, &features).unwrap();
+            assert_eq!(shape_cached(&font, &cache, text, &features), Ok(glyphs.clone()));
+            glyphs.into_iter().map(|glyph| (glyph.glyph.0, glyph.cluster, glyph.x_advance)).collect::<Vec<_>>()
+        };
+
+        // The ligature ignores the mark between its components, which
+        // follows it.
+        assert_eq!(shaped("f\u{301}i", b"liga"), vec![(3, 0, 650), (4, 0, 0)]);
+        // Only marks of attachment class 2 are substituted.
+        assert_eq!(shaped("f\u{301}\u{323}", b"ccmp"), vec![(1, 0, 400), (4, 0, 0), (7, 0, 0)]);
+        // Kerning passes over marks outside the filtering set.
+        assert_eq!(shaped("A\u{301}i", b"kern"), vec![(6, 0, 650), (4, 0, 0), (2, 3, 300)]);
+        assert_eq!(shaped("A\u{323}i", b"kern"), vec![(6, 0, 700), (5, 0, 0), (2, 3, 300)]);
+    }
+
+    #[test]
+    fn shape_contextual_lookups() {
+        // Glyphs 1, 2, 4 and 5 are `f`, `i`, `A` and `V`.  Each feature
+        // is a contextual lookup of one format, nesting the lookups after.
+        let gsub = test_fonts::layout_table(b"latn", &[(b"calt", &[0]), (b"ss01", &[1]), (b"ss02", &[4])], &[
+            (5, test_fonts::glyph_context(&[1, 2], &[(1, 2)])),
+            (5, test_fonts::class_context(&[1, 4], &[(1, 1), (2, 2), (4, 1)], &[1, 2], &[(0, 3)])),
+            (1, test_fonts::single_substitution(&[(2, 3)])),
+            (1, test_fonts::single_substitution(&[(1, 6), (4, 6)])),
+            (5, test_fonts::coverage_context(&[&[1], &[2], &[5]], &[(0, 5), (2, 6)])),
+            (4, test_fonts::ligature_substitution(&[1, 2], 7)),
+            (1, test_fonts::single_substitution(&[(5, 6)])),
+        ]);
+        let gpos = test_fonts::layout_table(b"latn", &[(b"kern", &[0])], &[
+            (7, test_fonts::coverage_context(&[&[4], &[5], &[4]], &[(0, 1)])),
+            (2, test_fonts::class_pair_adjustment(&[4], &[5], -80)),
+        ]);
+        let data = test_fonts::minimal_font(false)
+            .table(b"hhea", test_fonts::hhea(800, -200, 8))
+            .table(b"maxp", test_fonts::maxp(8))
+            .table(b"hmtx", test_fonts::hmtx(&[(500, 0), (300, 0), (250, 0), (260, 0), (700, 0), (680, 0), (310, 0), (520, 0)], &[]))
+            .table(b"cmap", test_fonts::cmap(&[('f', 1), ('i', 2), ('A', 4), ('V', 5)]))
+            .table(b"GSUB", gsub)
+            .table(b"GPOS", gpos)
+            .build();
+        let font = Font::decode(&data).unwrap();
+        let cache = ShapeCache::new(&font).unwrap();
+        let shaped = |text: &str, feature: &[u8; 4]| {
+            let features = [Tag::new(*feature)];
+            let glyphs = shape_simple(&font, text, &features).unwrap();
+            assert_eq!(shape_cached(&font, &cache, text, &features), Ok(glyphs.clone()));
+            glyphs.into_iter().map(|glyph| (glyph.glyph.0, glyph.x_advance)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(shaped("fiif", b"calt"), vec![(1, 300), (3, 260), (2, 250), (1, 300)]);
+        assert_eq!(shaped("fAiAf", b"ss01"), vec![(1, 300), (6, 310), (2, 250), (4, 700), (1, 300)]);
+        // The ligature shortens the sequence, and the second nested lookup
+        // still applies to `V`.
+        assert_eq!(shaped("fiVfi", b"ss02"), vec![(7, 520), (6, 310), (1, 300), (2, 250)]);
+        assert_eq!(shaped("AVAV", b"kern"), vec![(4, 620), (5, 680), (4, 700), (5, 680)]);
+    }
+
+    #[test]
+    fn shape_script_runs() {
+        // The font substitutes glyph 2 for `β` only under `grek`, so the
+        // Latin run takes no lookups and the Greek run takes its own.
+        let gsub = test_fonts::layout_table(b"grek", &[(b"liga", &[0])], &[
+            (1, test_fonts::single_substitution(&[(1, 3), (2, 4)])),
+        ]);
+        let data = test_fonts::minimal_font(false)
+            .table(b"hhea", test_fonts::hhea(800, -200, 5))
+            .table(b"maxp", test_fonts::maxp(5))
+            .table(b"hmtx", test_fonts::hmtx(&[(500, 0), (400, 0), (300, 0), (650, 0), (700, 0)], &[]))
+            .table(b"cmap", test_fonts::cmap(&[('b', 1), ('\u{3B2}', 2)]))
+            .table(b"GSUB", gsub)
+            .build();
+        let font = Font::decode(&data).unwrap();
+        let glyphs = shape_simple(&font, "b \u{3B2}b", &DEFAULT_FEATURES).unwrap();
+        assert_eq!(glyphs.iter().map(|glyph| (glyph.glyph.0, glyph.cluster)).collect::<Vec<_>>(), vec![
+            (1, 0), (0, 1), (4, 2), (1, 4),
+        ]);
+
+        let cache = ShapeCache::new(&font).unwrap();
+        assert_eq!(shape_cached(&font, &cache, "b \u{3B2}b", &DEFAULT_FEATURES), Ok(glyphs));
+    }
+
+    #[test]
+    fn shape_kerning_pairs() {
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let a = font.glyph_index('A').unwrap().unwrap();
+        let advance = i32::from(font.hmtx().unwrap().advance(a).unwrap());
+
+        let glyphs = shape_simple(&font, "AV", &DEFAULT_FEATURES).unwrap();
+        assert_eq!(glyphs[0].x_advance, advance - 164);
+        let glyphs = shape_simple(&font, "AV", &[]).unwrap();
+        assert_eq!(glyphs[0].x_advance, advance);
+
+        // The cached tables shape the same.
+        let cache = ShapeCache::new(&font).unwrap();
+        for text in &["AV", "Waffle office", "Typography, AVATAR", ""] {
+            assert_eq!(shape_cached(&font, &cache, text, &DEFAULT_FEATURES), shape_simple(&font, text, &DEFAULT_FEATURES));
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! This module reports what a parsed font costs: the size of each table
+//! and the time it takes to decode, and the memory of the acceleration
+//! structures built for it, so that services which hold thousands of
+//! parsed fonts can plan their capacity.
+
+use std::time::{Duration, Instant};
+
+use decode::Decode;
+#[cfg(feature = "color")]
+use decode::Decode1;
+use font::Font;
+use primitives::Tag;
+use table::TaggedTable;
+use table::cmap::Cmap;
+use table::glyf::Glyf;
+use table::head::Head;
+use table::hhea::Hhea;
+use table::kern::Kern;
+use table::maxp::Maxp;
+use table::name::Name;
+use table::os2::Os2;
+use table::post::Post;
+#[cfg(feature = "cff")]
+use table::cff::Cff;
+#[cfg(feature = "variations")]
+use table::{avar::Avar, cvar::Cvar, fvar::Fvar, gvar::Gvar, hvar::Hvar, mvar::Mvar, stat::Stat};
+#[cfg(feature = "ift")]
+use table::ift::{Ift, Iftx};
+#[cfg(feature = "color")]
+use table::{cblc::{Cblc, Eblc}, colr::Colr, cpal::Cpal, ebsc::Ebsc, sbix::Sbix, svg::Svg};
+#[cfg(feature = "layout")]
+use table::{base::Base, gdef::Gdef, layout::{Gpos, Gsub}, math::Math};
+#[cfg(feature = "aat")]
+use table::feat::Feat;
+#[cfg(feature = "layout")]
+use shape::ShapeCache;
+use error::{Error, Result};
+
+/// The cost of one table of a font.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TableStats {
+    pub tag: Tag,
+    /// The length of the table in the file, in bytes.
+    pub length: u32,
+    /// The time the table took to decode, or `None` if this build of the
+    /// crate cannot decode it.
+    pub parse_time: Option<Duration>,
+    /// The error decoding the table, if it failed.
+    pub error: Option<Error>,
+}
+
+/// The cost of a structure built from the tables of a font to speed up
+/// later work, such as `ShapeCache`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AccelStats {
+    /// The name of the type of the structure.
+    pub name: &'static str,
+    /// The bytes the structure takes on the heap, besides the font data.
+    pub heap_size: usize,
+    pub build_time: Duration,
+}
+
+/// The cost of a parsed font, from `Font::parse_stats`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseStats {
+    /// The size of the whole font file, in bytes.
+    pub file_size: usize,
+    /// Each table, in the order of the table directory.
+    pub tables: Vec<TableStats>,
+    /// Each acceleration structure which could be built for the font.
+    pub accelerators: Vec<AccelStats>,
+}
+
+impl ParseStats {
+    /// The time every table took to decode.
+    pub fn total_parse_time(&self) -> Duration {
+        self.tables.iter().filter_map(|table| table.parse_time).sum()
+    }
+
+    /// The bytes every acceleration structure takes on the heap.
+    pub fn accelerator_heap_size(&self) -> usize {
+        self.accelerators.iter().map(|accelerator| accelerator.heap_size).sum()
+    }
+}
+
+impl<'fnt> Font<'fnt> {
+    /// Decode every table of the font which this build of the crate can
+    /// decode, and build each acceleration structure, timing each one.
+    ///
+    /// Tables are decoded lazily, so this is the cost of reading every
+    /// table once; glyphs are not outlined.  Times are from the system
+    /// clock, and vary from run to run.
+    pub fn parse_stats(&self) -> ParseStats {
+        let tables = self.table_directory().tables().into_iter()
+            .map(|record| {
+                let start = Instant::now();
+                let result = decode_table(self, record.tag);
+                TableStats {
+                    tag: record.tag,
+                    length: record.length,
+                    parse_time: result.map(|_| start.elapsed()),
+                    error: result.and_then(Result::err),
+                }
+            })
+            .collect();
+
+        ParseStats { file_size: self.data().len(), tables, accelerators: build_accelerators(self) }
+    }
+}
+
+/// Decode the table `tag` of `font`, or return `None` if this build of the
+/// crate cannot decode it.
+fn decode_table(font: &Font, tag: Tag) -> Option<Result<()>> {
+    fn decode<'fnt, T: TaggedTable<'fnt> + Decode<'fnt>>(font: &Font<'fnt>) -> Result<()> {
+        font.table::<T>().map(|_| ())
+    }
+
+    Some(match &tag.0 {
+        b"maxp" => decode::<Maxp>(font),
+        b"hhea" => decode::<Hhea>(font),
+        b"hmtx" => font.hmtx().map(|_| ()),
+        b"head" => decode::<Head>(font),
+        b"post" => decode::<Post>(font),
+        b"OS/2" => decode::<Os2>(font),
+        b"loca" => font.loca().map(|_| ()),
+        b"glyf" => decode::<Glyf>(font),
+        b"cmap" => decode::<Cmap>(font),
+        b"name" => decode::<Name>(font),
+        b"kern" => decode::<Kern>(font),
+        #[cfg(feature = "cff")]
+        b"CFF " => decode::<Cff>(font),
+        #[cfg(feature = "variations")]
+        b"fvar" => decode::<Fvar>(font),
+        #[cfg(feature = "variations")]
+        b"avar" => decode::<Avar>(font),
+        #[cfg(feature = "variations")]
+        b"gvar" => decode::<Gvar>(font),
+        #[cfg(feature = "variations")]
+        b"cvar" => decode::<Cvar>(font),
+        #[cfg(feature = "variations")]
+        b"HVAR" => decode::<Hvar>(font),
+        #[cfg(feature = "variations")]
+        b"MVAR" => decode::<Mvar>(font),
+        #[cfg(feature = "variations")]
+        b"STAT" => decode::<Stat>(font),
+        #[cfg(feature = "ift")]
+        b"IFT " => decode::<Ift>(font),
+        #[cfg(feature = "ift")]
+        b"IFTX" => decode::<Iftx>(font),
+        #[cfg(feature = "color")]
+        b"EBSC" => decode::<Ebsc>(font),
+        #[cfg(feature = "layout")]
+        b"GSUB" => decode::<Gsub>(font),
+        #[cfg(feature = "layout")]
+        b"GPOS" => decode::<Gpos>(font),
+        #[cfg(feature = "layout")]
+        b"GDEF" => decode::<Gdef>(font),
+        #[cfg(feature = "layout")]
+        b"BASE" => decode::<Base>(font),
+        #[cfg(feature = "layout")]
+        b"MATH" => decode::<Math>(font),
+        #[cfg(feature = "aat")]
+        b"feat" => decode::<Feat>(font),
+        #[cfg(feature = "color")]
+        b"COLR" => decode::<Colr>(font),
+        #[cfg(feature = "color")]
+        b"CPAL" => decode::<Cpal>(font),
+        #[cfg(feature = "color")]
+        b"SVG " => decode::<Svg>(font),
+        #[cfg(feature = "color")]
+        b"sbix" => font.num_glyphs()
+            .and_then(|num_glyphs| Sbix::decode(font.table_data(Sbix::tag())?, num_glyphs as usize))
+            .map(|_| ()),
+        #[cfg(feature = "color")]
+        b"CBLC" => decode::<Cblc>(font),
+        #[cfg(feature = "color")]
+        b"EBLC" => decode::<Eblc>(font),
+        _ => return None,
+    })
+}
+
+#[cfg(feature = "layout")]
+fn build_accelerators(font: &Font) -> Vec<AccelStats> {
+    let start = Instant::now();
+    match ShapeCache::new(font) {
+        Ok(cache) => vec![AccelStats { name: "ShapeCache", heap_size: cache.heap_size(), build_time: start.elapsed() }],
+        Err(_) => Vec::new(),
+    }
+}
+
+#[cfg(not(feature = "layout"))]
+fn build_accelerators(_font: &Font) -> Vec<AccelStats> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use table::SUPPORTED_TABLES;
+    use test_fonts;
+
+    #[test]
+    fn parse_stats() {
+        let data = open_file!("data/SourceSansPro/SourceSansPro-Regular.otf");
+        let font = Font::decode(&data).unwrap();
+        let stats = font.parse_stats();
+        assert_eq!(stats.file_size, data.len());
+        assert_eq!(stats.tables.len(), font.table_directory().tables().len());
+        for table in &stats.tables {
+            assert_eq!(table.parse_time.is_some(), SUPPORTED_TABLES.contains(&table.tag), "{:?}", table.tag);
+            assert_eq!(table.error, None);
+        }
+        let glyphs = stats.tables.iter().find(|table| table.tag == Tag::new(*b"CFF ")).unwrap();
+        assert_eq!(Some(glyphs.length), font.table_record(Tag::new(*b"CFF ")).map(|record| record.length));
+        assert!(stats.total_parse_time() >= glyphs.parse_time.unwrap_or_default());
+
+        assert_eq!(stats.accelerators.len(), cfg!(feature = "layout") as usize);
+        assert_eq!(stats.accelerator_heap_size() > 0, cfg!(feature = "layout"));
+
+        // A table which fails to decode is reported rather than stopping
+        // the others.
+        let data = test_fonts::minimal_font(false).table(b"cmap", vec![0, 0]).build();
+        let stats = Font::decode(&data).unwrap().parse_stats();
+        let cmap = stats.tables.iter().find(|table| table.tag == Tag::new(*b"cmap")).unwrap();
+        assert_eq!(cmap.error, Some(Error::UnexpectedEof));
+        assert!(stats.tables.iter().filter(|table| table.tag != cmap.tag).all(|table| table.error.is_none()));
+    }
+}
@@ -0,0 +1,183 @@
+//! This module writes glyph outlines as SVG path data, the string used in
+//! the `d` attribute of a `<path>` element.
+
+use std::fmt::{self, Write};
+
+use font::Font;
+use outline::OutlineSink;
+use primitives::GlyphId;
+use table::head::Head;
+use error::Result;
+
+/// How outline coordinates are mapped to path coordinates.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SvgPathOptions {
+    /// Negate y coordinates, since the y-axis of SVG points down.
+    pub flip_y: bool,
+    /// Scale from the font's `unitsPerEm` to an em of this size, rather
+    /// than writing font units.
+    pub em_size: Option<f32>,
+}
+
+impl Default for SvgPathOptions {
+    fn default() -> SvgPathOptions {
+        SvgPathOptions { flip_y: true, em_size: None }
+    }
+}
+
+/// An `OutlineSink` which writes absolute path commands to `out`.  Numbers
+/// are rounded to two decimal places and written without redundant
+/// separators or zeros, so the square `(0, 0)–(10, -5)` becomes
+/// `M0 0L10 0L10-5L0-5Z`.
+///
+/// A line back to the start of a contour is left out when the contour is
+/// closed straight after, since `Z` draws it.
+///
+/// The sink cannot fail, so the first error from `out` is kept and
+/// returned by `finish`.
+pub struct SvgPathSink<'a, W: 'a> {
+    out: &'a mut W,
+    scale: f32,
+    y_scale: f32,
+    start: (f32, f32),
+    closing_line: bool,
+    result: fmt::Result,
+}
+
+impl<'a, W: Write> SvgPathSink<'a, W> {
+    /// Write outline coordinates multiplied by `scale`, negating y when
+    /// `flip_y` is set.
+    pub fn new(out: &'a mut W, scale: f32, flip_y: bool) -> SvgPathSink<'a, W> {
+        let y_scale = if flip_y { -scale } else { scale };
+        SvgPathSink { out, scale, y_scale, start: (0.0, 0.0), closing_line: false, result: Ok(()) }
+    }
+
+    pub fn finish(self) -> fmt::Result {
+        self.result
+    }
+
+    fn command(&mut self, command: char, points: &[(f32, f32)]) {
+        if self.closing_line {
+            self.closing_line = false;
+            let start = self.start;
+            self.command('L', &[start]);
+        }
+        if self.result.is_err() {
+            return
+        }
+
+        let mut text = String::new();
+        text.push(command);
+        for &(x, y) in points {
+            for &value in &[x * self.scale, y * self.y_scale] {
+                let number = format_number(value);
+                if !number.starts_with('-') && text.ends_with(|c: char| c.is_ascii_digit() || c == '.') {
+                    text.push(' ');
+                }
+                text.push_str(&number);
+            }
+        }
+        self.result = self.out.write_str(&text);
+    }
+}
+
+impl<'a, W: Write> OutlineSink for SvgPathSink<'a, W> {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.command('M', &[(x, y)]);
+        self.start = (x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        if (x, y) == self.start {
+            self.closing_line = true;
+        } else {
+            self.command('L', &[(x, y)]);
+        }
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.command('Q', &[(x1, y1), (x, y)]);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.command('C', &[(x1, y1), (x2, y2), (x, y)]);
+    }
+
+    fn close(&mut self) {
+        self.closing_line = false;
+        self.command('Z', &[]);
+    }
+}
+
+/// `value` rounded to two decimal places, without a leading zero before
+/// the decimal point.
+fn format_number(value: f32) -> String {
+    let rounded = (value * 100.0).round() / 100.0;
+    // Avoid writing `-0`.
+    let rounded = if rounded == 0.0 { 0.0 } else { rounded };
+    let text = rounded.to_string();
+    if let Some(fraction) = text.strip_prefix("0.") {
+        format!(".{}", fraction)
+    } else if let Some(fraction) = text.strip_prefix("-0.") {
+        format!("-.{}", fraction)
+    } else {
+        text
+    }
+}
+
+impl<'fnt> Font<'fnt> {
+    /// The outline of `glyph` as SVG path data.
+    pub fn glyph_svg_path(&self, glyph: GlyphId, options: &SvgPathOptions) -> Result<String> {
+        let mut path = String::new();
+        self.write_glyph_svg_path(glyph, options, &mut path)?
+            .expect("writing to a String cannot fail");
+        Ok(path)
+    }
+
+    /// Write the outline of `glyph` as SVG path data to `out`.  Errors
+    /// reading the font are returned first, and errors from `out` inside.
+    pub fn write_glyph_svg_path<W: Write>(&self, glyph: GlyphId, options: &SvgPathOptions, out: &mut W)
+        -> Result<fmt::Result>
+    {
+        let scale = match options.em_size {
+            Some(em_size) => em_size / f32::from(self.table::<Head>()?.units_per_em),
+            None => 1.0,
+        };
+
+        let mut sink = SvgPathSink::new(out, scale, options.flip_y);
+        self.outline_glyph(glyph, &mut sink)?;
+        Ok(sink.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+    use test_fonts;
+
+    #[test]
+    fn svg_path() {
+        let data = test_fonts::minimal_font(false).build();
+        let font = Font::decode(&data).unwrap();
+
+        let path = font.glyph_svg_path(GlyphId(1), &SvgPathOptions::default()).unwrap();
+        assert_eq!(path, "M100 0L100-500L600-500L600 0Z");
+
+        let options = SvgPathOptions { flip_y: false, em_size: Some(1.0) };
+        let path = font.glyph_svg_path(GlyphId(2), &options).unwrap();
+        assert_eq!(path, "M.05 0L.3 .7L.55 0Z");
+
+        assert_eq!(font.glyph_svg_path(GlyphId(0), &options), Ok(String::new()));
+        assert!(font.glyph_svg_path(GlyphId(3), &options).is_err());
+
+        let mut path = String::new();
+        let mut sink = SvgPathSink::new(&mut path, 0.5, true);
+        sink.move_to(0.0, 0.0);
+        sink.curve_to(1.0, 1.0, -3.0, 0.25, 0.001, 0.0);
+        sink.line_to(0.0, 0.0);
+        sink.line_to(4.0, 0.0);
+        assert_eq!(sink.finish(), Ok(()));
+        assert_eq!(path, "M0 0C.5-.5-1.5-.13 0 0L0 0L2 0");
+    }
+}
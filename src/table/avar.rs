@@ -0,0 +1,179 @@
+use decode::{Decode, Decode1, StaticEncodeSize, EncodeSize};
+use primitives::{Array, F2Dot14};
+use table::variation::{DeltaSetIndexMap, ItemVariationStore, NormalizedCoord};
+use error::{Error, Result};
+
+/// The axis variations table, which modifies the normalization of axis
+/// coordinates with a piecewise linear mapping per axis.  Version 2 then
+/// adjusts each coordinate by a delta from an item variation store, so
+/// that the position on one axis can depend on the others.
+#[derive(Debug, Clone)]
+pub struct Avar<'fnt> {
+    pub version: u16,
+    segment_maps: Vec<Array<'fnt, AxisValueMap>>,
+    /// The delta set of each axis, which is the axis index itself when a
+    /// version 2 table has no map.
+    axis_index_map: Option<DeltaSetIndexMap<'fnt>>,
+    var_store: Option<ItemVariationStore<'fnt>>,
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq)]
+pub struct AxisValueMap {
+    pub from_coordinate: F2Dot14,
+    pub to_coordinate: F2Dot14,
+}
+
+impl<'fnt> Decode<'fnt> for Avar<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Avar<'fnt>> {
+        required_len!(buffer, 8);
+        let version = u16::decode(buffer)?;
+        if version != 1 && version != 2 {
+            return Err(Error::UnsupportedVersion(version.into()))
+        }
+
+        let axis_count = u16::decode(&buffer[6..])? as usize;
+        let mut buf = &buffer[8..];
+        let mut segment_maps = Vec::with_capacity(axis_count);
+        for _ in 0..axis_count {
+            let count = u16::decode(buf)? as usize;
+            let map = Array::<AxisValueMap>::decode(&buf[2..], count)?;
+            buf = &buf[2 + map.encode_size()..];
+            segment_maps.push(map);
+        }
+
+        let (mut axis_index_map, mut var_store) = (None, None);
+        if version == 2 {
+            required_len!(buf, 8);
+            let offset = |pos: usize| -> Result<Option<&'fnt [u8]>> {
+                match u32::decode(&buf[pos..])? as usize {
+                    0 => Ok(None),
+                    offset => buffer.get(offset..).ok_or(Error::UnexpectedEof).map(Some),
+                }
+            };
+            axis_index_map = offset(0)?.map(DeltaSetIndexMap::decode).transpose()?;
+            var_store = offset(4)?.map(ItemVariationStore::decode).transpose()?;
+        }
+
+        Ok(Avar { version, segment_maps, axis_index_map, var_store })
+    }
+}
+
+impl<'fnt> Avar<'fnt> {
+    pub fn segment_maps(&self) -> &[Array<'fnt, AxisValueMap>] {
+        &self.segment_maps
+    }
+
+    /// Apply the segment map of `axis` to a normalized coordinate.  The
+    /// deltas of version 2 depend on every axis, so they are applied by
+    /// `map_coords` alone.
+    pub fn map(&self, axis: usize, coord: NormalizedCoord) -> NormalizedCoord {
+        let map = match self.segment_maps.get(axis) {
+            Some(map) if !map.is_empty() => map,
+            _ => return coord,
+        };
+
+        NormalizedCoord::new(map_segments(map, coord.to_f32()))
+    }
+
+    /// Apply the table to normalized `coords`, in `fvar` axis order.  Each
+    /// coordinate is mapped through the segment map of its axis, and then
+    /// for version 2 moved by the delta its delta set has at the mapped
+    /// coordinates, rounded and clamped to the normalized range.  A delta
+    /// which cannot be read is zero.
+    pub fn map_coords(&self, coords: &[NormalizedCoord]) -> Vec<NormalizedCoord> {
+        let mapped = coords.iter()
+            .enumerate()
+            .map(|(axis, &coord)| self.map(axis, coord))
+            .collect::<Vec<_>>();
+        let store = match self.var_store {
+            Some(ref store) => store,
+            None => return mapped,
+        };
+
+        mapped.iter()
+            .enumerate()
+            .map(|(axis, &coord)| {
+                let (outer, inner) = match self.axis_index_map {
+                    Some(ref map) => map.get(axis).unwrap_or((0xFFFF, 0xFFFF)),
+                    None => (0, axis as u16),
+                };
+                let delta = store.delta(outer, inner, &mapped).unwrap_or_default();
+                let bits = (i32::from(coord.to_bits()) + delta.round() as i32).clamp(-0x4000, 0x4000);
+                NormalizedCoord::from_bits(bits as i16)
+            })
+            .collect()
+    }
+}
+
+/// Map `value` through the `(from, to)` points of a non-empty segment map.
+fn map_segments(map: &Array<AxisValueMap>, value: f32) -> f32 {
+    let points = map.into_iter()
+        .map(|m| (f32::from(m.from_coordinate), f32::from(m.to_coordinate)))
+        .collect::<Vec<_>>();
+
+    let (first, last) = (points[0], points[points.len() - 1]);
+    if value <= first.0 {
+        return value + first.1 - first.0
+    }
+    if value >= last.0 {
+        return value + last.1 - last.0
+    }
+
+    for pair in points.windows(2) {
+        let ((from0, to0), (from1, to1)) = (pair[0], pair[1]);
+        if value <= from1 {
+            if from1 == from0 {
+                return to1
+            }
+            return to0 + (to1 - to0) * (value - from0) / (from1 - from0)
+        }
+    }
+
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A version 2 table for two axes with empty segment maps, whose
+    /// store moves one axis by 0.25 at the peak 1.0 of the first axis.
+    /// Without `index_map`, that is the second axis.
+    fn avar2(index_map: Option<&[u8]>) -> Vec<u8> {
+        let mut data = vec![0, 2, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0];
+        let store_offset = 20 + index_map.map_or(0, <[u8]>::len) as u32;
+        data.extend_from_slice(&(if index_map.is_some() { 20u32 } else { 0 }).to_be_bytes());
+        data.extend_from_slice(&store_offset.to_be_bytes());
+        data.extend_from_slice(index_map.unwrap_or_default());
+        data.extend_from_slice(&[
+            0, 1, 0, 0, 0, 12, 0, 1, 0, 0, 0, 28,
+            0, 2, 0, 1, 0, 0, 0x40, 0, 0x40, 0, 0, 0, 0, 0, 0, 0,
+            0, 2, 0, 1, 0, 1, 0, 0, 0, 0, 0x10, 0,
+        ]);
+        data
+    }
+
+    #[test]
+    fn axis_deltas() {
+        let coords = |values: &[f32]| values.iter().map(|&value| NormalizedCoord::new(value)).collect::<Vec<_>>();
+
+        let data = avar2(None);
+        let avar = Avar::decode(&data).unwrap();
+        assert_eq!(avar.version, 2);
+        assert_eq!(avar.map_coords(&coords(&[1.0, 0.0])), coords(&[1.0, 0.25]));
+        assert_eq!(avar.map_coords(&coords(&[0.5, 1.0])), coords(&[0.5, 1.0]));
+        assert_eq!(avar.map_coords(&coords(&[0.5, -0.5])), coords(&[0.5, -0.375]));
+        assert_eq!(avar.map_coords(&coords(&[-1.0, 0.0])), coords(&[-1.0, 0.0]));
+        assert_eq!(avar.map(1, NormalizedCoord::new(0.5)), NormalizedCoord::new(0.5));
+
+        // Map the first axis to the delta set which moves, and the second
+        // to the one which does not.
+        let data = avar2(Some(&[0, 0x00, 0, 2, 0x01, 0x00]));
+        let avar = Avar::decode(&data).unwrap();
+        assert_eq!(avar.map_coords(&coords(&[0.5, 0.0])), coords(&[0.625, 0.0]));
+
+        let mut data = avar2(None);
+        data[1] = 3;
+        assert_eq!(Avar::decode(&data).err(), Some(Error::UnsupportedVersion(3)));
+    }
+}
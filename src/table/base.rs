@@ -0,0 +1,139 @@
+use decode::{Decode, Decode1};
+use primitives::{Array, Tag};
+use tags::scripts;
+use error::{Error, Result};
+
+/// The baseline table, which gives the positions of the baselines each
+/// script aligns to, such as the hanging baseline of Devanagari, for
+/// horizontal and vertical text.
+#[derive(Debug, Copy, Clone)]
+pub struct Base<'fnt> {
+    pub horizontal: Option<BaseAxis<'fnt>>,
+    pub vertical: Option<BaseAxis<'fnt>>,
+}
+
+/// The baselines of one text direction.
+#[derive(Debug, Copy, Clone)]
+pub struct BaseAxis<'fnt> {
+    tags: Array<'fnt, Tag>,
+    scripts: &'fnt [u8],
+}
+
+impl<'fnt> Decode<'fnt> for Base<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Base<'fnt>> {
+        required_len!(buffer, 8);
+        let major = u16::decode(buffer)?;
+        if major != 1 {
+            return Err(Error::UnsupportedVersion(u32::from(major) << 16 | u32::from(u16::decode(&buffer[2..])?)))
+        }
+
+        let axis = |pos: usize| -> Result<Option<BaseAxis<'fnt>>> {
+            match u16::decode(&buffer[pos..])? as usize {
+                0 => Ok(None),
+                offset => BaseAxis::decode(buffer.get(offset..).ok_or(Error::UnexpectedEof)?).map(Some),
+            }
+        };
+        Ok(Base { horizontal: axis(4)?, vertical: axis(6)? })
+    }
+}
+
+impl<'fnt> Decode<'fnt> for BaseAxis<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<BaseAxis<'fnt>> {
+        required_len!(buffer, 4);
+        let tags = match u16::decode(buffer)? as usize {
+            0 => Array::decode(&[], 0)?,
+            offset => {
+                let list = buffer.get(offset..).ok_or(Error::UnexpectedEof)?;
+                Array::decode(list.get(2..).ok_or(Error::UnexpectedEof)?, u16::decode(list)? as usize)?
+            },
+        };
+        let scripts = buffer.get(u16::decode(&buffer[2..])? as usize..).ok_or(Error::UnexpectedEof)?;
+        required_len!(scripts, 2 + u16::decode(scripts)? as usize * 6);
+        Ok(BaseAxis { tags, scripts })
+    }
+}
+
+impl<'fnt> BaseAxis<'fnt> {
+    /// The baselines the scripts of this axis give positions for, in the
+    /// order of their coordinates.
+    pub fn baseline_tags(&self) -> Array<'fnt, Tag> {
+        self.tags
+    }
+
+    /// The baseline values of `script`, or of the `DFLT` script if the
+    /// table does not list it.
+    fn values(&self, script: Tag) -> Result<Option<&'fnt [u8]>> {
+        let count = u16::decode(self.scripts)? as usize;
+        let records = (0..count).map(|index| &self.scripts[2 + index * 6..]);
+        let record = match records.clone().find(|record| Tag::decode(record).ok() == Some(script)) {
+            Some(record) => record,
+            None => match records.clone().find(|record| Tag::decode(record).ok() == Some(scripts::DEFAULT)) {
+                Some(record) => record,
+                None => return Ok(None),
+            },
+        };
+
+        let script = self.scripts.get(u16::decode(&record[4..])? as usize..).ok_or(Error::UnexpectedEof)?;
+        match u16::decode(script)? as usize {
+            0 => Ok(None),
+            offset => script.get(offset..).ok_or(Error::UnexpectedEof).map(Some),
+        }
+    }
+
+    /// The position of `baseline` for `script` in font units, or `None`
+    /// if the table does not give one.
+    pub fn coordinate(&self, script: Tag, baseline: Tag) -> Result<Option<i16>> {
+        let index = match self.tags.into_iter().position(|tag| tag == baseline) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        let values = match self.values(script)? {
+            Some(values) => values,
+            None => return Ok(None),
+        };
+
+        required_len!(values, 4);
+        if index >= u16::decode(&values[2..])? as usize {
+            return Ok(None)
+        }
+        let offset = u16::decode(values.get(4 + index * 2..).ok_or(Error::UnexpectedEof)?)? as usize;
+        // Each format of a `BaseCoord` starts with its format and coordinate.
+        let coord = values.get(offset..).ok_or(Error::UnexpectedEof)?;
+        required_len!(coord, 4);
+        Ok(Some(i16::decode(&coord[2..])?))
+    }
+
+    /// The baseline `script` aligns to by default.
+    pub fn default_baseline(&self, script: Tag) -> Result<Option<Tag>> {
+        match self.values(script)? {
+            Some(values) => Ok(self.tags.get(u16::decode(values)? as usize)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_fonts;
+
+    #[test]
+    fn script_baselines() {
+        let data = test_fonts::base(&[b"hang", b"ideo", b"romn"], &[
+            (b"DFLT", 2, &[600, -120, 0]),
+            (b"deva", 0, &[640, -120, 0]),
+        ]);
+        let base = Base::decode(&data).unwrap();
+        assert!(base.vertical.is_none());
+        let axis = base.horizontal.unwrap();
+        assert_eq!(axis.baseline_tags().len(), 3);
+
+        let (hang, ideo, math) = (Tag::new(*b"hang"), Tag::new(*b"ideo"), Tag::new(*b"math"));
+        assert_eq!(axis.coordinate(Tag::new(*b"deva"), hang), Ok(Some(640)));
+        assert_eq!(axis.coordinate(Tag::new(*b"latn"), hang), Ok(Some(600)));
+        assert_eq!(axis.coordinate(Tag::new(*b"hani"), ideo), Ok(Some(-120)));
+        assert_eq!(axis.coordinate(Tag::new(*b"latn"), math), Ok(None));
+        assert_eq!(axis.default_baseline(Tag::new(*b"deva")), Ok(Some(hang)));
+        assert_eq!(axis.default_baseline(Tag::new(*b"latn")), Ok(Some(Tag::new(*b"romn"))));
+    }
+}
@@ -0,0 +1,182 @@
+use std::ops::Deref;
+
+use decode::{Decode, Decode1, StaticEncodeSize, EncodeSize};
+use primitives::{Array, GlyphId};
+use table::ebsc::SbitLineMetrics;
+use error::{Error, Result};
+
+/// The color bitmap location table, which locates the bitmaps of each
+/// strike within `CBDT`.
+#[derive(Debug, Copy, Clone)]
+pub struct Cblc<'fnt> {
+    buffer: &'fnt [u8],
+    pub major_version: u16,
+    pub minor_version: u16,
+    sizes: Array<'fnt, BitmapSize>,
+}
+
+/// The header of a single strike.
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BitmapSize {
+    pub index_sub_table_array_offset: u32,
+    pub index_tables_size: u32,
+    pub number_of_index_sub_tables: u32,
+    pub color_ref: u32,
+    pub hori: SbitLineMetrics,
+    pub vert: SbitLineMetrics,
+    pub start_glyph_index: GlyphId,
+    pub end_glyph_index: GlyphId,
+    pub ppem_x: u8,
+    pub ppem_y: u8,
+    pub bit_depth: u8,
+    pub flags: u8,
+}
+
+/// The embedded bitmap location table, which locates the monochrome and
+/// grayscale bitmaps of each strike within `EBDT`.  Its layout is that of
+/// `CBLC`, at version 2.0.
+#[derive(Debug, Copy, Clone)]
+pub struct Eblc<'fnt>(Cblc<'fnt>);
+
+impl<'fnt> Decode<'fnt> for Eblc<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Eblc<'fnt>> {
+        Cblc::decode(buffer).map(Eblc)
+    }
+}
+
+impl<'fnt> Deref for Eblc<'fnt> {
+    type Target = Cblc<'fnt>;
+
+    fn deref(&self) -> &Cblc<'fnt> {
+        &self.0
+    }
+}
+
+/// The flag of `BitmapSize::flags` for strikes with horizontal metrics.
+pub const HORIZONTAL_METRICS: u8 = 0x01;
+/// The flag of `BitmapSize::flags` for strikes with vertical metrics.
+pub const VERTICAL_METRICS: u8 = 0x02;
+
+/// The metadata of a strike, read without touching its bitmaps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrikeInfo {
+    pub ppem_x: u8,
+    pub ppem_y: u8,
+    /// 1, 2, 4 or 8 bits per pixel for grayscale strikes, or 32 for the
+    /// BGRA strikes of `CBLC`.
+    pub bit_depth: u8,
+    pub flags: u8,
+    /// The ranges of glyphs with bitmaps, as inclusive `(first, last)`
+    /// pairs in the order of the index subtables.  A range may include
+    /// glyphs whose bitmaps are empty.
+    pub glyph_ranges: Vec<(GlyphId, GlyphId)>,
+}
+
+impl StrikeInfo {
+    pub fn contains(&self, glyph: GlyphId) -> bool {
+        self.glyph_ranges.iter().any(|&(first, last)| first <= glyph && glyph <= last)
+    }
+
+    /// The number of glyphs in the strike's ranges.
+    pub fn glyph_count(&self) -> usize {
+        self.glyph_ranges.iter().map(|&(first, last)| (last.0 as usize + 1).saturating_sub(first.0 as usize)).sum()
+    }
+}
+
+/// A range of glyphs which share an index subtable.
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct IndexSubTableRecord {
+    pub first_glyph_index: GlyphId,
+    pub last_glyph_index: GlyphId,
+    pub additional_offset_to_index_subtable: u32,
+}
+
+impl<'fnt> Decode<'fnt> for Cblc<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Cblc<'fnt>> {
+        required_len!(buffer, 8);
+        let num_sizes = u32::decode(&buffer[4..])? as usize;
+
+        Ok(Cblc {
+            buffer,
+            major_version: u16::decode(buffer)?,
+            minor_version: u16::decode(&buffer[2..])?,
+            sizes: Array::decode(&buffer[8..], num_sizes)?,
+        })
+    }
+}
+
+impl<'fnt> Cblc<'fnt> {
+    pub fn sizes(&self) -> Array<'fnt, BitmapSize> {
+        self.sizes
+    }
+
+    /// The index subtable records of `size`.
+    pub fn index_sub_tables(&self, size: &BitmapSize) -> Result<Array<'fnt, IndexSubTableRecord>> {
+        let data = self.buffer.get(size.index_sub_table_array_offset as usize..)
+            .ok_or(Error::UnexpectedEof)?;
+        Array::decode(data, size.number_of_index_sub_tables as usize)
+    }
+
+    /// The metadata of each strike, in table order.  Only the strike
+    /// headers and index subtable records are read.
+    pub fn strikes(&self) -> Result<Vec<StrikeInfo>> {
+        self.sizes.into_iter()
+            .map(|size| Ok(StrikeInfo {
+                ppem_x: size.ppem_x,
+                ppem_y: size.ppem_y,
+                bit_depth: size.bit_depth,
+                flags: size.flags,
+                glyph_ranges: self.index_sub_tables(&size)?.into_iter()
+                    .map(|record| (record.first_glyph_index, record.last_glyph_index))
+                    .collect(),
+            }))
+            .collect()
+    }
+
+    /// Whether any strike has an index subtable which covers `glyph`.
+    pub fn has_glyph(&self, glyph: GlyphId) -> Result<bool> {
+        for size in self.sizes {
+            if glyph < size.start_glyph_index || glyph > size.end_glyph_index {
+                continue
+            }
+
+            for record in self.index_sub_tables(&size)? {
+                if record.first_glyph_index <= glyph && glyph <= record.last_glyph_index {
+                    return Ok(true)
+                }
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_fonts;
+
+    #[test]
+    fn strikes() {
+        let data = test_fonts::bitmap_location(3, &[(16, 32, &[(1, 3), (5, 5)]), (64, 32, &[(1, 5)])]);
+        let cblc = Cblc::decode(&data).unwrap();
+        let strikes = cblc.strikes().unwrap();
+        assert_eq!(strikes.len(), 2);
+        assert_eq!(strikes[0], StrikeInfo {
+            ppem_x: 16,
+            ppem_y: 16,
+            bit_depth: 32,
+            flags: HORIZONTAL_METRICS,
+            glyph_ranges: vec![(GlyphId(1), GlyphId(3)), (GlyphId(5), GlyphId(5))],
+        });
+        assert_eq!(strikes[0].glyph_count(), 4);
+        assert!(strikes[0].contains(GlyphId(5)) && !strikes[0].contains(GlyphId(4)));
+        assert_eq!(cblc.has_glyph(GlyphId(4)), Ok(true));
+        assert_eq!(cblc.has_glyph(GlyphId(6)), Ok(false));
+
+        let data = test_fonts::bitmap_location(2, &[(12, 1, &[(2, 2)])]);
+        let eblc = Eblc::decode(&data).unwrap();
+        assert_eq!(eblc.major_version, 2);
+        assert_eq!(eblc.strikes().unwrap()[0].glyph_ranges, vec![(GlyphId(2), GlyphId(2))]);
+    }
+}
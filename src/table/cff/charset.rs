@@ -0,0 +1,132 @@
+use decode::Decode;
+use primitives::GlyphId;
+use error::{Error, Result};
+
+/// A charset maps glyph ids to string ids (SIDs), or to CIDs in
+/// CID-keyed fonts.  Glyph 0 is always `.notdef` and is not encoded.
+#[derive(Debug, Copy, Clone)]
+pub enum Charset<'fnt> {
+    IsoAdobe,
+    Expert,
+    ExpertSubset,
+    Format0(&'fnt [u8]),
+    Format1(&'fnt [u8]),
+    Format2(&'fnt [u8]),
+}
+
+impl<'fnt> Charset<'fnt> {
+    /// Parse the charset at `offset` within the CFF table.  The offsets 0-2
+    /// refer to the predefined charsets.
+    pub fn parse(cff: &'fnt [u8], offset: usize) -> Result<Charset<'fnt>> {
+        match offset {
+            0 => return Ok(Charset::IsoAdobe),
+            1 => return Ok(Charset::Expert),
+            2 => return Ok(Charset::ExpertSubset),
+            _ => (),
+        }
+
+        let buffer = cff.get(offset..).ok_or(Error::UnexpectedEof)?;
+        let format = u8::decode(buffer)?;
+        let data = &buffer[1..];
+        match format {
+            0 => Ok(Charset::Format0(data)),
+            1 => Ok(Charset::Format1(data)),
+            2 => Ok(Charset::Format2(data)),
+            _ => Err(Error::InvalidData),
+        }
+    }
+
+    /// The SID (or CID) of `glyph`.  The predefined expert charsets are
+    /// not supported and always return `None` for glyphs other than 0.
+    pub fn sid(&self, glyph: GlyphId) -> Option<u16> {
+        let gid = glyph.0;
+        if gid == 0 {
+            return Some(0)
+        }
+
+        match *self {
+            // The ISOAdobe charset maps each glyph to the SID of the same value.
+            Charset::IsoAdobe if gid <= 228 => Some(gid),
+            Charset::IsoAdobe | Charset::Expert | Charset::ExpertSubset => None,
+            Charset::Format0(data) => {
+                let index = (gid as usize - 1) * 2;
+                u16::decode(data.get(index..)?).ok()
+            },
+            Charset::Format1(data) => ranges(data, 3, gid),
+            Charset::Format2(data) => ranges(data, 4, gid),
+        }
+    }
+
+    /// The glyph id of the glyph with the given SID (or CID).
+    pub fn glyph(&self, sid: u16, num_glyphs: usize) -> Option<GlyphId> {
+        (0..num_glyphs)
+            .map(|gid| GlyphId(gid as u16))
+            .find(|&glyph| self.sid(glyph) == Some(sid))
+    }
+}
+
+/// Search the `{ first, nLeft }` ranges of a format 1 or 2 charset,
+/// whose records are `record_size` bytes long.
+fn ranges(data: &[u8], record_size: usize, gid: u16) -> Option<u16> {
+    let mut remaining = gid as u32 - 1;
+    for record in data.chunks(record_size) {
+        if record.len() < record_size {
+            return None
+        }
+
+        let first = u16::decode(record).ok()? as u32;
+        let n_left = if record_size == 3 {
+            record[2] as u32
+        } else {
+            u16::decode(&record[2..]).ok()? as u32
+        };
+
+        if remaining <= n_left {
+            return Some((first + remaining) as u16)
+        }
+        remaining -= n_left + 1;
+    }
+
+    None
+}
+
+/// FDSelect maps glyph ids to an index into the FDArray of a
+/// CID-keyed font.
+#[derive(Debug, Copy, Clone)]
+pub enum FdSelect<'fnt> {
+    Format0(&'fnt [u8]),
+    Format3(&'fnt [u8]),
+}
+
+impl<'fnt> FdSelect<'fnt> {
+    pub fn parse(cff: &'fnt [u8], offset: usize) -> Result<FdSelect<'fnt>> {
+        let buffer = cff.get(offset..).ok_or(Error::UnexpectedEof)?;
+        let format = u8::decode(buffer)?;
+        match format {
+            0 => Ok(FdSelect::Format0(&buffer[1..])),
+            3 => Ok(FdSelect::Format3(&buffer[1..])),
+            _ => Err(Error::InvalidData),
+        }
+    }
+
+    /// The index of the Font DICT used by `glyph`.
+    pub fn font_dict_index(&self, glyph: GlyphId) -> Option<u8> {
+        match *self {
+            FdSelect::Format0(data) => data.get(glyph.0 as usize).cloned(),
+            FdSelect::Format3(data) => {
+                let n_ranges = u16::decode(data).ok()? as usize;
+                let ranges = data.get(2..2 + n_ranges * 3 + 2)?;
+                let mut result = None;
+                for i in 0..n_ranges {
+                    let first = u16::decode(&ranges[i * 3..]).ok()?;
+                    let next = u16::decode(&ranges[i * 3 + 3..]).ok()?;
+                    if glyph.0 >= first && glyph.0 < next {
+                        result = Some(ranges[i * 3 + 2]);
+                        break
+                    }
+                }
+                result
+            },
+        }
+    }
+}
@@ -0,0 +1,510 @@
+//! A Type 2 charstring interpreter.
+
+use std::collections::BTreeSet;
+
+use super::Cff;
+use super::index::Index;
+use super::strings::STANDARD_ENCODING;
+use outline::OutlineSink;
+use primitives::GlyphId;
+use error::{Error, Result};
+
+/// The maximum subroutine nesting depth allowed by the Type 2 specification.
+const MAX_CALL_DEPTH: usize = 10;
+
+/// The maximum number of operators a glyph may execute, counting those of
+/// its subroutines each time they are called, so that subroutines which
+/// call each other many times cannot make a glyph take forever.
+const MAX_OPERATORS: usize = 1 << 16;
+
+/// The maximum number of operands on the argument stack.
+const MAX_STACK: usize = 48;
+
+/// The charstring operators.  Two-byte operators are encoded as `0x0C00 | b1`.
+pub mod op {
+    pub const HSTEM: u16 = 1;
+    pub const VSTEM: u16 = 3;
+    pub const VMOVETO: u16 = 4;
+    pub const RLINETO: u16 = 5;
+    pub const HLINETO: u16 = 6;
+    pub const VLINETO: u16 = 7;
+    pub const RRCURVETO: u16 = 8;
+    pub const CALLSUBR: u16 = 10;
+    pub const RETURN: u16 = 11;
+    pub const ENDCHAR: u16 = 14;
+    pub const HSTEMHM: u16 = 18;
+    pub const HINTMASK: u16 = 19;
+    pub const CNTRMASK: u16 = 20;
+    pub const RMOVETO: u16 = 21;
+    pub const HMOVETO: u16 = 22;
+    pub const VSTEMHM: u16 = 23;
+    pub const RCURVELINE: u16 = 24;
+    pub const RLINECURVE: u16 = 25;
+    pub const VVCURVETO: u16 = 26;
+    pub const HHCURVETO: u16 = 27;
+    pub const CALLGSUBR: u16 = 29;
+    pub const VHCURVETO: u16 = 30;
+    pub const HVCURVETO: u16 = 31;
+
+    pub const HFLEX: u16 = 0x0C22;
+    pub const FLEX: u16 = 0x0C23;
+    pub const HFLEX1: u16 = 0x0C24;
+    pub const FLEX1: u16 = 0x0C25;
+}
+
+/// The bias added to subroutine numbers, which depends on the number
+/// of subroutines in the INDEX.
+pub fn subr_bias(count: usize) -> i32 {
+    if count < 1240 {
+        107
+    } else if count < 33900 {
+        1131
+    } else {
+        32768
+    }
+}
+
+/// The subroutines referenced by a set of charstrings.  The indices stored
+/// are the unbiased positions within the subroutine INDEX.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SubrUsage {
+    pub global: BTreeSet<usize>,
+    pub local: BTreeSet<usize>,
+    /// Glyphs referenced by the `endchar` accent composition operator.
+    pub components: BTreeSet<GlyphId>,
+}
+
+/// An `OutlineSink` which ignores the outline, for when only the side
+/// effects of interpretation are needed.
+pub(crate) struct NullSink;
+
+impl OutlineSink for NullSink {
+    fn move_to(&mut self, _: f32, _: f32) {}
+    fn line_to(&mut self, _: f32, _: f32) {}
+    fn quad_to(&mut self, _: f32, _: f32, _: f32, _: f32) {}
+    fn curve_to(&mut self, _: f32, _: f32, _: f32, _: f32, _: f32, _: f32) {}
+    fn close(&mut self) {}
+}
+
+/// Interprets charstrings, tracking the state needed to decode them
+/// correctly: the argument stack, the number of declared stems (which
+/// determines the length of `hintmask` data), the advance width, which is
+/// an optional first operand of the first stack-clearing operator, and
+/// subroutine calls.
+pub(crate) struct Interpreter<'a, 'fnt: 'a, S: OutlineSink + 'a> {
+    cff: &'a Cff<'fnt>,
+    local_subrs: Option<Index<'fnt>>,
+    sink: &'a mut S,
+    usage: &'a mut SubrUsage,
+    stack: Vec<f64>,
+    num_stems: usize,
+    width: Option<f64>,
+    seen_width: bool,
+    open: bool,
+    x: f64,
+    y: f64,
+    /// The offset applied to every emitted point, used to position the
+    /// accent of an `endchar` composition.
+    origin: (f64, f64),
+    /// The number of operators left to execute before giving up.
+    budget: usize,
+    /// Whether this draws a component of an `endchar` composition, which
+    /// may not itself be one.
+    component: bool,
+}
+
+impl<'a, 'fnt, S: OutlineSink> Interpreter<'a, 'fnt, S> {
+    pub fn new(cff: &'a Cff<'fnt>,
+               glyph: GlyphId,
+               sink: &'a mut S,
+               usage: &'a mut SubrUsage) -> Interpreter<'a, 'fnt, S> {
+        Interpreter {
+            cff,
+            local_subrs: cff.local_subrs(glyph),
+            sink,
+            usage,
+            stack: Vec::with_capacity(MAX_STACK),
+            num_stems: 0,
+            width: None,
+            seen_width: false,
+            open: false,
+            x: 0.0,
+            y: 0.0,
+            origin: (0.0, 0.0),
+            budget: MAX_OPERATORS,
+            component: false,
+        }
+    }
+
+    /// Run a glyph's charstring to completion, returning the width operand
+    /// if the charstring contains one.
+    pub fn run(&mut self, charstring: &[u8]) -> Result<Option<f64>> {
+        self.execute(charstring, 0)?;
+        self.close_path();
+        Ok(self.width)
+    }
+
+    /// Execute a charstring or subroutine.  Returns `true` once `endchar`
+    /// has been reached, which terminates every enclosing subroutine.
+    fn execute(&mut self, charstring: &[u8], depth: usize) -> Result<bool> {
+        if depth > MAX_CALL_DEPTH {
+            return Err(Error::InvalidData)
+        }
+
+        let mut pos = 0;
+        while pos < charstring.len() {
+            let b0 = charstring[pos];
+            match b0 {
+                28 | 32..=255 => {
+                    let (value, len) = parse_operand(&charstring[pos..])?;
+                    if self.stack.len() >= MAX_STACK {
+                        return Err(Error::InvalidData)
+                    }
+                    self.stack.push(value);
+                    pos += len;
+                    continue
+                },
+                _ => (),
+            }
+
+            let operator = if b0 == 12 {
+                pos += 1;
+                0x0C00 | *charstring.get(pos).ok_or(Error::UnexpectedEof)? as u16
+            } else {
+                b0 as u16
+            };
+            pos += 1;
+            self.budget = self.budget.checked_sub(1).ok_or(Error::InvalidData)?;
+
+            match operator {
+                op::HSTEM | op::VSTEM | op::HSTEMHM | op::VSTEMHM => {
+                    self.take_width(self.stack.len() % 2 == 1);
+                    self.num_stems += self.stack.len() / 2;
+                    self.stack.clear();
+                },
+                op::HINTMASK | op::CNTRMASK => {
+                    // Any arguments on the stack are an implicit `vstem`.
+                    self.take_width(self.stack.len() % 2 == 1);
+                    self.num_stems += self.stack.len() / 2;
+                    self.stack.clear();
+                    pos += self.num_stems.div_ceil(8);
+                },
+                op::RMOVETO => {
+                    self.take_width(self.stack.len() > 2);
+                    let (dx, dy) = (self.arg(0)?, self.arg(1)?);
+                    self.move_to(dx, dy);
+                },
+                op::HMOVETO => {
+                    self.take_width(self.stack.len() > 1);
+                    let dx = self.arg(0)?;
+                    self.move_to(dx, 0.0);
+                },
+                op::VMOVETO => {
+                    self.take_width(self.stack.len() > 1);
+                    let dy = self.arg(0)?;
+                    self.move_to(0.0, dy);
+                },
+                op::RLINETO => {
+                    for pair in self.stack.clone().chunks(2) {
+                        if let [dx, dy] = *pair {
+                            self.line_to(dx, dy);
+                        }
+                    }
+                },
+                op::HLINETO | op::VLINETO => {
+                    let mut horizontal = operator == op::HLINETO;
+                    for &d in &self.stack.clone() {
+                        if horizontal { self.line_to(d, 0.0) } else { self.line_to(0.0, d) }
+                        horizontal = !horizontal;
+                    }
+                },
+                op::RRCURVETO => {
+                    for args in self.stack.clone().chunks(6) {
+                        if let [dxa, dya, dxb, dyb, dxc, dyc] = *args {
+                            self.curve_to(dxa, dya, dxb, dyb, dxc, dyc);
+                        }
+                    }
+                },
+                op::HHCURVETO => {
+                    let stack = self.stack.clone();
+                    let (mut dy1, args) = split_odd(&stack);
+                    for args in args.chunks(4) {
+                        if let [dxa, dxb, dyb, dxc] = *args {
+                            self.curve_to(dxa, dy1, dxb, dyb, dxc, 0.0);
+                            dy1 = 0.0;
+                        }
+                    }
+                },
+                op::VVCURVETO => {
+                    let stack = self.stack.clone();
+                    let (mut dx1, args) = split_odd(&stack);
+                    for args in args.chunks(4) {
+                        if let [dya, dxb, dyb, dyc] = *args {
+                            self.curve_to(dx1, dya, dxb, dyb, 0.0, dyc);
+                            dx1 = 0.0;
+                        }
+                    }
+                },
+                op::HVCURVETO | op::VHCURVETO => {
+                    let stack = self.stack.clone();
+                    let mut horizontal = operator == op::HVCURVETO;
+                    let mut chunks = stack.chunks(4).peekable();
+                    while let Some(args) = chunks.next() {
+                        if args.len() < 4 {
+                            break
+                        }
+
+                        // The final curve may have an extra operand for the
+                        // otherwise-zero end point delta.
+                        let last = match chunks.peek() {
+                            Some(next) if next.len() == 1 => next[0],
+                            _ => 0.0,
+                        };
+
+                        if horizontal {
+                            self.curve_to(args[0], 0.0, args[1], args[2], last, args[3]);
+                        } else {
+                            self.curve_to(0.0, args[0], args[1], args[2], args[3], last);
+                        }
+                        horizontal = !horizontal;
+                    }
+                },
+                op::RCURVELINE => {
+                    let stack = self.stack.clone();
+                    if stack.len() >= 2 {
+                        let (curves, line) = stack.split_at(stack.len() - 2);
+                        for args in curves.chunks(6) {
+                            if let [dxa, dya, dxb, dyb, dxc, dyc] = *args {
+                                self.curve_to(dxa, dya, dxb, dyb, dxc, dyc);
+                            }
+                        }
+                        self.line_to(line[0], line[1]);
+                    }
+                },
+                op::RLINECURVE => {
+                    let stack = self.stack.clone();
+                    if stack.len() >= 6 {
+                        let (lines, curve) = stack.split_at(stack.len() - 6);
+                        for pair in lines.chunks(2) {
+                            if let [dx, dy] = *pair {
+                                self.line_to(dx, dy);
+                            }
+                        }
+                        self.curve_to(curve[0], curve[1], curve[2], curve[3], curve[4], curve[5]);
+                    }
+                },
+                op::HFLEX => {
+                    if let [dx1, dx2, dy2, dx3, dx4, dx5, dx6] = self.stack[..] {
+                        self.curve_to(dx1, 0.0, dx2, dy2, dx3, 0.0);
+                        self.curve_to(dx4, 0.0, dx5, -dy2, dx6, 0.0);
+                    }
+                },
+                op::FLEX => {
+                    if let [dx1, dy1, dx2, dy2, dx3, dy3, dx4, dy4, dx5, dy5, dx6, dy6, _] = self.stack[..] {
+                        self.curve_to(dx1, dy1, dx2, dy2, dx3, dy3);
+                        self.curve_to(dx4, dy4, dx5, dy5, dx6, dy6);
+                    }
+                },
+                op::HFLEX1 => {
+                    if let [dx1, dy1, dx2, dy2, dx3, dx4, dx5, dy5, dx6] = self.stack[..] {
+                        self.curve_to(dx1, dy1, dx2, dy2, dx3, 0.0);
+                        self.curve_to(dx4, 0.0, dx5, dy5, dx6, -(dy1 + dy2 + dy5));
+                    }
+                },
+                op::FLEX1 => {
+                    if let [dx1, dy1, dx2, dy2, dx3, dy3, dx4, dy4, dx5, dy5, d6] = self.stack[..] {
+                        let dx = dx1 + dx2 + dx3 + dx4 + dx5;
+                        let dy = dy1 + dy2 + dy3 + dy4 + dy5;
+                        let (dx6, dy6) = if dx.abs() > dy.abs() { (d6, -dy) } else { (-dx, d6) };
+                        self.curve_to(dx1, dy1, dx2, dy2, dx3, dy3);
+                        self.curve_to(dx4, dy4, dx5, dy5, dx6, dy6);
+                    }
+                },
+                op::CALLSUBR => {
+                    let subrs = self.local_subrs.ok_or(Error::InvalidData)?;
+                    let index = self.pop_subr_index(subrs)?;
+                    self.usage.local.insert(index);
+                    let subr = subrs.get(index).ok_or(Error::InvalidData)?;
+                    if self.execute(subr, depth + 1)? {
+                        return Ok(true)
+                    }
+                    continue
+                },
+                op::CALLGSUBR => {
+                    let subrs = self.cff.global_subrs();
+                    let index = self.pop_subr_index(subrs)?;
+                    self.usage.global.insert(index);
+                    let subr = subrs.get(index).ok_or(Error::InvalidData)?;
+                    if self.execute(subr, depth + 1)? {
+                        return Ok(true)
+                    }
+                    continue
+                },
+                op::RETURN => return Ok(false),
+                op::ENDCHAR => {
+                    self.take_width(self.stack.len() == 1 || self.stack.len() == 5);
+                    self.close_path();
+                    if let [adx, ady, bchar, achar] = self.stack[..] {
+                        if self.component {
+                            return Err(Error::InvalidData)
+                        }
+                        self.seac(adx, ady, bchar, achar, depth)?;
+                    }
+                    return Ok(true)
+                },
+                _ => (),
+            }
+
+            self.stack.clear();
+        }
+
+        Ok(false)
+    }
+
+    /// The deprecated accent composition form of `endchar`, which draws the
+    /// glyphs for the StandardEncoding codes `bchar` and `achar`, with the
+    /// accent offset by `(adx, ady)`.
+    fn seac(&mut self, adx: f64, ady: f64, bchar: f64, achar: f64, depth: usize) -> Result<()> {
+        let base = self.standard_glyph(bchar)?;
+        let accent = self.standard_glyph(achar)?;
+        self.usage.components.insert(base);
+        self.usage.components.insert(accent);
+
+        let origin = self.origin;
+        for &(glyph, offset) in &[(base, (0.0, 0.0)), (accent, (adx, ady))] {
+            let charstring = self.cff.char_strings().get(glyph.0 as usize).ok_or(Error::InvalidData)?;
+            let mut component = Interpreter::new(self.cff, glyph, &mut *self.sink, &mut *self.usage);
+            component.origin = (origin.0 + offset.0, origin.1 + offset.1);
+            // The components' own widths are irrelevant.
+            component.seen_width = true;
+            // The components share the glyph's budget.
+            component.budget = self.budget;
+            component.component = true;
+            let result = component.execute(charstring, depth + 1);
+            self.budget = component.budget;
+            result?;
+            component.close_path();
+        }
+
+        Ok(())
+    }
+
+    fn standard_glyph(&self, code: f64) -> Result<GlyphId> {
+        if !(0.0..=255.0).contains(&code) {
+            return Err(Error::InvalidData)
+        }
+
+        let sid = STANDARD_ENCODING[code as usize];
+        if sid == 0 {
+            return Err(Error::InvalidData)
+        }
+
+        self.cff.charset()
+            .glyph(sid, self.cff.num_glyphs())
+            .ok_or(Error::InvalidData)
+    }
+
+    /// If this is the first stack-clearing operator and `has_width` is set,
+    /// the first operand is the advance width and is removed from the stack.
+    fn take_width(&mut self, has_width: bool) {
+        if !self.seen_width {
+            self.seen_width = true;
+            if has_width && !self.stack.is_empty() {
+                self.width = Some(self.stack.remove(0));
+            }
+        }
+    }
+
+    fn arg(&self, index: usize) -> Result<f64> {
+        self.stack.get(index).cloned().ok_or(Error::InvalidData)
+    }
+
+    fn move_to(&mut self, dx: f64, dy: f64) {
+        self.close_path();
+        self.x += dx;
+        self.y += dy;
+        let (x, y) = self.point(self.x, self.y);
+        self.sink.move_to(x, y);
+        self.open = true;
+    }
+
+    fn line_to(&mut self, dx: f64, dy: f64) {
+        self.x += dx;
+        self.y += dy;
+        let (x, y) = self.point(self.x, self.y);
+        self.sink.line_to(x, y);
+    }
+
+    fn curve_to(&mut self, dxa: f64, dya: f64, dxb: f64, dyb: f64, dxc: f64, dyc: f64) {
+        let (xa, ya) = (self.x + dxa, self.y + dya);
+        let (xb, yb) = (xa + dxb, ya + dyb);
+        self.x = xb + dxc;
+        self.y = yb + dyc;
+
+        let (x1, y1) = self.point(xa, ya);
+        let (x2, y2) = self.point(xb, yb);
+        let (x, y) = self.point(self.x, self.y);
+        self.sink.curve_to(x1, y1, x2, y2, x, y);
+    }
+
+    fn close_path(&mut self) {
+        if self.open {
+            self.sink.close();
+            self.open = false;
+        }
+    }
+
+    fn point(&self, x: f64, y: f64) -> (f32, f32) {
+        ((x + self.origin.0) as f32, (y + self.origin.1) as f32)
+    }
+
+    fn pop_subr_index(&mut self, subrs: Index) -> Result<usize> {
+        let value = self.stack.pop().ok_or(Error::InvalidData)? as i32;
+        let index = value + subr_bias(subrs.len());
+        if index < 0 {
+            return Err(Error::InvalidData)
+        }
+
+        Ok(index as usize)
+    }
+}
+
+/// Split off the optional leading operand of an operator whose operands
+/// otherwise come in groups of four.
+fn split_odd(stack: &[f64]) -> (f64, &[f64]) {
+    if stack.len() % 2 == 1 {
+        (stack[0], &stack[1..])
+    } else {
+        (0.0, stack)
+    }
+}
+
+/// Parse a charstring operand, returning the value and its encoded length.
+fn parse_operand(buffer: &[u8]) -> Result<(f64, usize)> {
+    let b0 = buffer[0] as i32;
+    match b0 {
+        28 => {
+            required_len!(buffer, 3);
+            let value = ((buffer[1] as u16) << 8 | buffer[2] as u16) as i16;
+            Ok((value as f64, 3))
+        },
+        32..=246 => Ok(((b0 - 139) as f64, 1)),
+        247..=250 => {
+            required_len!(buffer, 2);
+            Ok((((b0 - 247) * 256 + buffer[1] as i32 + 108) as f64, 2))
+        },
+        251..=254 => {
+            required_len!(buffer, 2);
+            Ok(((-(b0 - 251) * 256 - buffer[1] as i32 - 108) as f64, 2))
+        },
+        255 => {
+            required_len!(buffer, 5);
+            let value = (buffer[1] as i32) << 24
+                | (buffer[2] as i32) << 16
+                | (buffer[3] as i32) << 8
+                | buffer[4] as i32;
+            Ok((value as f64 / 65536.0, 5))
+        },
+        _ => Err(Error::InvalidData),
+    }
+}
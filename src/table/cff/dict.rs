@@ -0,0 +1,272 @@
+use error::{Error, Result};
+
+/// DICT operators.  Two-byte operators are encoded as `0x0C00 | b1`.
+pub mod op {
+    pub const VERSION: u16 = 0;
+    pub const NOTICE: u16 = 1;
+    pub const FULL_NAME: u16 = 2;
+    pub const FAMILY_NAME: u16 = 3;
+    pub const WEIGHT: u16 = 4;
+    pub const FONT_BBOX: u16 = 5;
+    pub const BLUE_VALUES: u16 = 6;
+    pub const OTHER_BLUES: u16 = 7;
+    pub const FAMILY_BLUES: u16 = 8;
+    pub const FAMILY_OTHER_BLUES: u16 = 9;
+    pub const STD_HW: u16 = 10;
+    pub const STD_VW: u16 = 11;
+    pub const UNIQUE_ID: u16 = 13;
+    pub const XUID: u16 = 14;
+    pub const CHARSET: u16 = 15;
+    pub const ENCODING: u16 = 16;
+    pub const CHAR_STRINGS: u16 = 17;
+    pub const PRIVATE: u16 = 18;
+    pub const SUBRS: u16 = 19;
+    pub const DEFAULT_WIDTH_X: u16 = 20;
+    pub const NOMINAL_WIDTH_X: u16 = 21;
+
+    pub const COPYRIGHT: u16 = 0x0C00;
+    pub const IS_FIXED_PITCH: u16 = 0x0C01;
+    pub const ITALIC_ANGLE: u16 = 0x0C02;
+    pub const UNDERLINE_POSITION: u16 = 0x0C03;
+    pub const UNDERLINE_THICKNESS: u16 = 0x0C04;
+    pub const PAINT_TYPE: u16 = 0x0C05;
+    pub const CHARSTRING_TYPE: u16 = 0x0C06;
+    pub const FONT_MATRIX: u16 = 0x0C07;
+    pub const STROKE_WIDTH: u16 = 0x0C08;
+    pub const BLUE_SCALE: u16 = 0x0C09;
+    pub const BLUE_SHIFT: u16 = 0x0C0A;
+    pub const BLUE_FUZZ: u16 = 0x0C0B;
+    pub const STEM_SNAP_H: u16 = 0x0C0C;
+    pub const STEM_SNAP_V: u16 = 0x0C0D;
+    pub const FORCE_BOLD: u16 = 0x0C0E;
+    pub const LANGUAGE_GROUP: u16 = 0x0C11;
+    pub const EXPANSION_FACTOR: u16 = 0x0C12;
+    pub const INITIAL_RANDOM_SEED: u16 = 0x0C13;
+    pub const SYNTHETIC_BASE: u16 = 0x0C14;
+    pub const POST_SCRIPT: u16 = 0x0C15;
+    pub const BASE_FONT_NAME: u16 = 0x0C16;
+    pub const BASE_FONT_BLEND: u16 = 0x0C17;
+    pub const ROS: u16 = 0x0C1E;
+    pub const CID_FONT_VERSION: u16 = 0x0C1F;
+    pub const CID_FONT_REVISION: u16 = 0x0C20;
+    pub const CID_FONT_TYPE: u16 = 0x0C21;
+    pub const CID_COUNT: u16 = 0x0C22;
+    pub const UID_BASE: u16 = 0x0C23;
+    pub const FD_ARRAY: u16 = 0x0C24;
+    pub const FD_SELECT: u16 = 0x0C25;
+    pub const FONT_NAME: u16 = 0x0C26;
+}
+
+/// A DICT operand, which is either an integer or a real number.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Operand {
+    Integer(i32),
+    Real(f64),
+}
+
+impl Operand {
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            Operand::Integer(value) => value as f64,
+            Operand::Real(value) => value,
+        }
+    }
+
+    pub fn as_i32(&self) -> i32 {
+        match *self {
+            Operand::Integer(value) => value,
+            Operand::Real(value) => value as i32,
+        }
+    }
+
+    /// The operand as an offset or size, which must be a non-negative integer.
+    pub fn as_usize(&self) -> Result<usize> {
+        match *self {
+            Operand::Integer(value) if value >= 0 => Ok(value as usize),
+            _ => Err(Error::InvalidData),
+        }
+    }
+}
+
+/// A single `operands operator` pair of a DICT.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry<'fnt> {
+    pub operator: u16,
+    pub operands: Vec<Operand>,
+    /// The encoded bytes of the entry, including the operator.
+    pub(crate) raw: &'fnt [u8],
+}
+
+/// A CFF DICT, which is a list of key-value pairs in a compact encoding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dict<'fnt> {
+    entries: Vec<Entry<'fnt>>,
+}
+
+impl<'fnt> Dict<'fnt> {
+    pub fn parse(buffer: &'fnt [u8]) -> Result<Dict<'fnt>> {
+        let mut entries = Vec::new();
+        let mut operands = Vec::new();
+        let mut start = 0;
+        let mut pos = 0;
+
+        while pos < buffer.len() {
+            let b0 = buffer[pos];
+            match b0 {
+                0..=21 => {
+                    let operator = if b0 == 12 {
+                        pos += 1;
+                        0x0C00 | *buffer.get(pos).ok_or(Error::UnexpectedEof)? as u16
+                    } else {
+                        b0 as u16
+                    };
+
+                    pos += 1;
+                    entries.push(Entry {
+                        operator,
+                        operands: operands.split_off(0),
+                        raw: &buffer[start..pos],
+                    });
+                    start = pos;
+                },
+                30 => {
+                    let (value, len) = parse_real(&buffer[pos + 1..])?;
+                    operands.push(Operand::Real(value));
+                    pos += 1 + len;
+                },
+                _ => {
+                    let (value, len) = parse_integer(&buffer[pos..])?;
+                    operands.push(Operand::Integer(value));
+                    pos += len;
+                },
+            }
+        }
+
+        Ok(Dict { entries })
+    }
+
+    pub fn entries(&self) -> &[Entry<'fnt>] {
+        &self.entries
+    }
+
+    /// The operands of `operator`, if the DICT contains it.
+    pub fn get(&self, operator: u16) -> Option<&[Operand]> {
+        self.entries.iter()
+            .find(|entry| entry.operator == operator)
+            .map(|entry| &entry.operands[..])
+    }
+
+    /// The first operand of `operator` as a number.
+    pub fn get_f64(&self, operator: u16) -> Option<f64> {
+        self.get(operator).and_then(|operands| operands.first()).map(Operand::as_f64)
+    }
+
+    /// The first operand of `operator` as an offset.
+    pub(crate) fn get_offset(&self, operator: u16) -> Result<Option<usize>> {
+        match self.get(operator).and_then(|operands| operands.first()) {
+            Some(operand) => operand.as_usize().map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The `(size, offset)` operands of a `Private` entry.
+    pub(crate) fn get_private(&self) -> Result<Option<(usize, usize)>> {
+        match self.get(op::PRIVATE) {
+            Some(&[size, offset]) => Ok(Some((size.as_usize()?, offset.as_usize()?))),
+            Some(_) => Err(Error::InvalidData),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Parse an integer operand, returning the value and its encoded length.
+pub(crate) fn parse_integer(buffer: &[u8]) -> Result<(i32, usize)> {
+    required_len!(buffer, 1);
+    let b0 = buffer[0] as i32;
+    match b0 {
+        32..=246 => Ok((b0 - 139, 1)),
+        247..=250 => {
+            required_len!(buffer, 2);
+            Ok(((b0 - 247) * 256 + buffer[1] as i32 + 108, 2))
+        },
+        251..=254 => {
+            required_len!(buffer, 2);
+            Ok((-(b0 - 251) * 256 - buffer[1] as i32 - 108, 2))
+        },
+        28 => {
+            required_len!(buffer, 3);
+            let value = ((buffer[1] as u16) << 8 | buffer[2] as u16) as i16;
+            Ok((value as i32, 3))
+        },
+        29 => {
+            required_len!(buffer, 5);
+            let value = (buffer[1] as i32) << 24
+                | (buffer[2] as i32) << 16
+                | (buffer[3] as i32) << 8
+                | buffer[4] as i32;
+            Ok((value, 5))
+        },
+        _ => Err(Error::InvalidData),
+    }
+}
+
+/// Parse the nibbles of a real operand, returning the value and the
+/// number of bytes consumed.
+fn parse_real(buffer: &[u8]) -> Result<(f64, usize)> {
+    let mut text = String::new();
+    for (i, &byte) in buffer.iter().enumerate() {
+        for &nibble in &[byte >> 4, byte & 0x0F] {
+            match nibble {
+                0..=9 => text.push((b'0' + nibble) as char),
+                0xA => text.push('.'),
+                0xB => text.push('E'),
+                0xC => text.push_str("E-"),
+                0xE => text.push('-'),
+                0xF => {
+                    let value = text.parse().map_err(|_| Error::InvalidData)?;
+                    return Ok((value, i + 1))
+                },
+                _ => return Err(Error::InvalidData),
+            }
+        }
+    }
+
+    Err(Error::UnexpectedEof)
+}
+
+/// Encode a DICT entry whose operands are all integers.  Integers are
+/// always written in the 5-byte form so that the size of a DICT does not
+/// depend on the offsets it contains.
+pub(crate) fn write_entry(out: &mut Vec<u8>, operator: u16, operands: &[i32]) {
+    for &operand in operands {
+        out.push(29);
+        out.extend_from_slice(&[
+            (operand >> 24) as u8,
+            (operand >> 16) as u8,
+            (operand >> 8) as u8,
+            operand as u8,
+        ]);
+    }
+
+    if operator & 0x0C00 == 0x0C00 {
+        out.push(12);
+    }
+    out.push(operator as u8);
+}
+
+/// Re-encode `dict`, dropping the entries for `replaced` operators and
+/// appending `entries` in their place.
+pub(crate) fn rebuild(dict: &Dict, replaced: &[u16], entries: &[(u16, Vec<i32>)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for entry in dict.entries() {
+        if !replaced.contains(&entry.operator) {
+            out.extend_from_slice(entry.raw);
+        }
+    }
+
+    for &(operator, ref operands) in entries {
+        write_entry(&mut out, operator, operands);
+    }
+
+    out
+}
@@ -0,0 +1,113 @@
+use decode::{Decode, EncodeSize};
+use error::{Error, Result};
+
+/// A CFF INDEX, which is an array of variable-sized objects.  The objects
+/// are located through an array of `offSize`-byte offsets which are relative
+/// to the byte preceding the object data.
+#[derive(Copy, Clone, Debug)]
+pub struct Index<'fnt> {
+    count: usize,
+    off_size: usize,
+    offsets: &'fnt [u8],
+    objects: &'fnt [u8],
+}
+
+impl<'fnt> Decode<'fnt> for Index<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Index<'fnt>> {
+        let count = u16::decode(buffer)? as usize;
+        if count == 0 {
+            return Ok(Index { count: 0, off_size: 1, offsets: &[], objects: &[] })
+        }
+
+        required_len!(buffer, 3);
+        let off_size = buffer[2] as usize;
+        if off_size == 0 || off_size > 4 {
+            return Err(Error::InvalidData)
+        }
+
+        let offsets_len = (count + 1) * off_size;
+        required_len!(buffer, 3 + offsets_len);
+        let offsets = &buffer[3..3 + offsets_len];
+
+        let mut index = Index { count, off_size, offsets, objects: &[] };
+        let data_len = index.offset(count).checked_sub(1).ok_or(Error::InvalidData)?;
+        let start = 3 + offsets_len;
+        required_len!(buffer, start + data_len);
+        index.objects = &buffer[start..start + data_len];
+        Ok(index)
+    }
+}
+
+impl<'fnt> EncodeSize for Index<'fnt> {
+    fn encode_size(&self) -> usize {
+        if self.count == 0 {
+            2
+        } else {
+            3 + self.offsets.len() + self.objects.len()
+        }
+    }
+}
+
+impl<'fnt> Index<'fnt> {
+    /// The number of objects in the index.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// The bytes of the object at `index`.
+    pub fn get(&self, index: usize) -> Option<&'fnt [u8]> {
+        if index >= self.count {
+            return None
+        }
+
+        let start = self.offset(index).checked_sub(1)?;
+        let end = self.offset(index + 1).checked_sub(1)?;
+        self.objects.get(start..end)
+    }
+
+    /// An iterator over every object in the index.
+    pub fn iter(&self) -> impl Iterator<Item = &'fnt [u8]> + 'fnt {
+        let index = *self;
+        (0..index.count).map(move |i| index.get(i).unwrap_or(&[]))
+    }
+
+    fn offset(&self, index: usize) -> usize {
+        let bytes = &self.offsets[index * self.off_size..(index + 1) * self.off_size];
+        bytes.iter().fold(0, |acc, &b| (acc << 8) | b as usize)
+    }
+}
+
+/// Serialize `objects` as a CFF INDEX, using the smallest possible `offSize`.
+pub(crate) fn write_index<T: AsRef<[u8]>>(out: &mut Vec<u8>, objects: &[T]) {
+    let count = objects.len();
+    out.push((count >> 8) as u8);
+    out.push(count as u8);
+    if count == 0 {
+        return
+    }
+
+    let data_len: usize = objects.iter().map(|object| object.as_ref().len()).sum();
+    let off_size = match data_len + 1 {
+        0..=0xFF => 1,
+        0x100..=0xFFFF => 2,
+        0x10000..=0xFF_FFFF => 3,
+        _ => 4,
+    };
+
+    out.push(off_size as u8);
+    let mut offset = 1;
+    for object in objects.iter().map(|object| object.as_ref().len()).chain(Some(0)) {
+        for shift in (0..off_size).rev() {
+            out.push((offset >> (shift * 8)) as u8);
+        }
+        offset += object;
+    }
+
+    for object in objects {
+        out.extend_from_slice(object.as_ref());
+    }
+}
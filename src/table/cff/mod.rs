@@ -0,0 +1,469 @@
+//! The Compact Font Format table, which contains PostScript outlines.
+
+pub mod index;
+pub mod dict;
+pub mod charset;
+pub mod charstring;
+pub mod strings;
+mod subset;
+
+use self::index::Index;
+use self::dict::{Dict, op};
+use self::charset::{Charset, FdSelect};
+use self::charstring::{Interpreter, NullSink, SubrUsage};
+use self::strings::STANDARD_STRINGS;
+use outline::{OutlineSink, BoundsSink, Rect};
+use decode::{Decode, EncodeSize};
+use primitives::GlyphId;
+use error::{Error, Result};
+
+/// The `CFF ` table.  Only the first font of the FontSet is decoded, since
+/// a CFF table embedded in an OpenType font contains exactly one font.
+#[derive(Debug, Clone)]
+pub struct Cff<'fnt> {
+    buffer: &'fnt [u8],
+    names: Index<'fnt>,
+    top_dict: Dict<'fnt>,
+    strings: Index<'fnt>,
+    global_subrs: Index<'fnt>,
+    char_strings: Index<'fnt>,
+    charset: Charset<'fnt>,
+    kind: FontKind<'fnt>,
+}
+
+/// Name-keyed fonts have a single Private DICT, whereas CID-keyed fonts
+/// select a Font DICT (and its Private DICT) for each glyph.
+#[derive(Debug, Clone)]
+pub enum FontKind<'fnt> {
+    Simple(PrivateDict<'fnt>),
+    Cid {
+        font_dicts: Vec<FontDict<'fnt>>,
+        fd_select: FdSelect<'fnt>,
+    },
+}
+
+/// A Font DICT from the FDArray of a CID-keyed font.
+#[derive(Debug, Clone)]
+pub struct FontDict<'fnt> {
+    pub dict: Dict<'fnt>,
+    pub private: PrivateDict<'fnt>,
+}
+
+/// A Private DICT, which contains hinting parameters and the local
+/// subroutines used by charstrings.
+#[derive(Debug, Clone)]
+pub struct PrivateDict<'fnt> {
+    dict: Dict<'fnt>,
+    subrs: Option<Index<'fnt>>,
+}
+
+impl<'fnt> PrivateDict<'fnt> {
+    fn parse(cff: &'fnt [u8], size: usize, offset: usize) -> Result<PrivateDict<'fnt>> {
+        let end = offset.checked_add(size).ok_or(Error::InvalidData)?;
+        let data = cff.get(offset..end).ok_or(Error::UnexpectedEof)?;
+        let dict = Dict::parse(data)?;
+
+        // The `Subrs` offset is relative to the start of the Private DICT.
+        let subrs = match dict.get_offset(op::SUBRS)? {
+            Some(subrs) => {
+                let start = offset.checked_add(subrs).ok_or(Error::InvalidData)?;
+                Some(Index::decode(cff.get(start..).ok_or(Error::UnexpectedEof)?)?)
+            },
+            None => None,
+        };
+
+        Ok(PrivateDict { dict, subrs })
+    }
+
+    pub fn dict(&self) -> &Dict<'fnt> {
+        &self.dict
+    }
+
+    /// The local subroutines, if the font has any.
+    pub fn subrs(&self) -> Option<Index<'fnt>> {
+        self.subrs
+    }
+
+    /// The `BlueValues` alignment zones, with the delta encoding removed.
+    pub fn blue_values(&self) -> Vec<f64> {
+        self.delta(op::BLUE_VALUES)
+    }
+
+    pub fn other_blues(&self) -> Vec<f64> {
+        self.delta(op::OTHER_BLUES)
+    }
+
+    pub fn std_hw(&self) -> Option<f64> {
+        self.dict.get_f64(op::STD_HW)
+    }
+
+    pub fn std_vw(&self) -> Option<f64> {
+        self.dict.get_f64(op::STD_VW)
+    }
+
+    pub fn default_width_x(&self) -> f64 {
+        self.dict.get_f64(op::DEFAULT_WIDTH_X).unwrap_or(0.0)
+    }
+
+    pub fn nominal_width_x(&self) -> f64 {
+        self.dict.get_f64(op::NOMINAL_WIDTH_X).unwrap_or(0.0)
+    }
+
+    fn delta(&self, operator: u16) -> Vec<f64> {
+        let mut value = 0.0;
+        self.dict.get(operator)
+            .unwrap_or(&[])
+            .iter()
+            .map(|operand| {
+                value += operand.as_f64();
+                value
+            })
+            .collect()
+    }
+}
+
+impl<'fnt> Decode<'fnt> for Cff<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Cff<'fnt>> {
+        required_len!(buffer, 4);
+        if buffer[0] != 1 {
+            return Err(Error::UnsupportedVersion(buffer[0].into()))
+        }
+
+        let header_size = buffer[2] as usize;
+        let mut buf = buffer.get(header_size..).ok_or(Error::UnexpectedEof)?;
+        let names = Index::decode(buf)?;
+        buf = &buf[names.encode_size()..];
+        let top_dicts = Index::decode(buf)?;
+        buf = &buf[top_dicts.encode_size()..];
+        let strings = Index::decode(buf)?;
+        buf = &buf[strings.encode_size()..];
+        let global_subrs = Index::decode(buf)?;
+
+        let top_dict = Dict::parse(top_dicts.get(0).ok_or(Error::InvalidData)?)?;
+        if top_dict.get_f64(op::CHARSTRING_TYPE).unwrap_or(2.0) != 2.0 {
+            return Err(Error::InvalidData)
+        }
+
+        let char_strings = match top_dict.get_offset(op::CHAR_STRINGS)? {
+            Some(offset) => Index::decode(buffer.get(offset..).ok_or(Error::UnexpectedEof)?)?,
+            None => return Err(Error::InvalidData),
+        };
+
+        let charset = Charset::parse(buffer, top_dict.get_offset(op::CHARSET)?.unwrap_or(0))?;
+
+        let kind = if top_dict.get(op::ROS).is_some() {
+            let fd_array = match top_dict.get_offset(op::FD_ARRAY)? {
+                Some(offset) => Index::decode(buffer.get(offset..).ok_or(Error::UnexpectedEof)?)?,
+                None => return Err(Error::InvalidData),
+            };
+
+            let fd_select = match top_dict.get_offset(op::FD_SELECT)? {
+                Some(offset) => FdSelect::parse(buffer, offset)?,
+                None => return Err(Error::InvalidData),
+            };
+
+            let font_dicts = fd_array.iter()
+                .map(|data| {
+                    let dict = Dict::parse(data)?;
+                    let (size, offset) = dict.get_private()?.ok_or(Error::InvalidData)?;
+                    let private = PrivateDict::parse(buffer, size, offset)?;
+                    Ok(FontDict { dict, private })
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            FontKind::Cid { font_dicts, fd_select }
+        } else {
+            let (size, offset) = top_dict.get_private()?.ok_or(Error::InvalidData)?;
+            FontKind::Simple(PrivateDict::parse(buffer, size, offset)?)
+        };
+
+        Ok(Cff {
+            buffer,
+            names,
+            top_dict,
+            strings,
+            global_subrs,
+            char_strings,
+            charset,
+            kind,
+        })
+    }
+}
+
+impl<'fnt> Cff<'fnt> {
+    /// The raw bytes of the CFF table.
+    pub fn data(&self) -> &'fnt [u8] {
+        self.buffer
+    }
+
+    pub fn names(&self) -> Index<'fnt> {
+        self.names
+    }
+
+    pub fn top_dict(&self) -> &Dict<'fnt> {
+        &self.top_dict
+    }
+
+    /// The String INDEX, which contains the strings whose SIDs follow the
+    /// predefined standard strings.
+    pub fn strings(&self) -> Index<'fnt> {
+        self.strings
+    }
+
+    pub fn global_subrs(&self) -> Index<'fnt> {
+        self.global_subrs
+    }
+
+    pub fn char_strings(&self) -> Index<'fnt> {
+        self.char_strings
+    }
+
+    pub fn charset(&self) -> Charset<'fnt> {
+        self.charset
+    }
+
+    /// The string of `sid`, which is either a standard string or one from
+    /// the String INDEX.
+    pub fn string(&self, sid: u16) -> Option<&'fnt str> {
+        match (sid as usize).checked_sub(STANDARD_STRINGS.len()) {
+            None => Some(STANDARD_STRINGS[sid as usize]),
+            Some(index) => ::std::str::from_utf8(self.strings.get(index)?).ok(),
+        }
+    }
+
+    /// The SID of `string`, preferring a standard string to an equal one in
+    /// the String INDEX.
+    pub fn sid(&self, string: &str) -> Option<u16> {
+        if let Some(sid) = STANDARD_STRINGS.iter().position(|&standard| standard == string) {
+            return Some(sid as u16)
+        }
+
+        let index = self.strings.iter().position(|data| data == string.as_bytes())?;
+        Some((STANDARD_STRINGS.len() + index) as u16)
+    }
+
+    /// The name of `glyph` from the charset.  CID-keyed fonts map glyphs to
+    /// CIDs rather than names, so they have none.
+    pub fn glyph_name(&self, glyph: GlyphId) -> Option<&'fnt str> {
+        if self.is_cid_keyed() || glyph.0 as usize >= self.num_glyphs() {
+            return None
+        }
+
+        self.string(self.charset.sid(glyph)?)
+    }
+
+    /// The glyph named `name` in the charset of a name-keyed font.
+    pub fn gid_by_name(&self, name: &str) -> Option<GlyphId> {
+        if self.is_cid_keyed() {
+            return None
+        }
+
+        self.charset.glyph(self.sid(name)?, self.num_glyphs())
+    }
+
+    pub fn kind(&self) -> &FontKind<'fnt> {
+        &self.kind
+    }
+
+    pub fn num_glyphs(&self) -> usize {
+        self.char_strings.len()
+    }
+
+    pub fn is_cid_keyed(&self) -> bool {
+        match self.kind {
+            FontKind::Cid { .. } => true,
+            FontKind::Simple(_) => false,
+        }
+    }
+
+    /// The Private DICT of a name-keyed font.
+    pub fn private_dict(&self) -> Option<&PrivateDict<'fnt>> {
+        match self.kind {
+            FontKind::Simple(ref private) => Some(private),
+            FontKind::Cid { .. } => None,
+        }
+    }
+
+    /// The Private DICT which applies to `glyph`.
+    pub fn private_dict_for(&self, glyph: GlyphId) -> Option<&PrivateDict<'fnt>> {
+        match self.kind {
+            FontKind::Simple(ref private) => Some(private),
+            FontKind::Cid { ref font_dicts, ref fd_select } => {
+                let index = fd_select.font_dict_index(glyph)?;
+                font_dicts.get(index as usize).map(|font_dict| &font_dict.private)
+            },
+        }
+    }
+
+    /// The local subroutines available to `glyph`'s charstring.
+    pub fn local_subrs(&self, glyph: GlyphId) -> Option<Index<'fnt>> {
+        self.private_dict_for(glyph).and_then(PrivateDict::subrs)
+    }
+
+    /// Interpret the charstring of `glyph`, sending its outline to `sink`,
+    /// and return the glyph's advance width.
+    pub fn outline<S: OutlineSink>(&self, glyph: GlyphId, sink: &mut S) -> Result<f64> {
+        let charstring = self.char_strings.get(glyph.0 as usize).ok_or(Error::InvalidGlyphId)?;
+        let private = self.private_dict_for(glyph).ok_or(Error::InvalidData)?;
+        let mut usage = SubrUsage::default();
+        let width = Interpreter::new(self, glyph, sink, &mut usage).run(charstring)?;
+        Ok(match width {
+            Some(width) => private.nominal_width_x() + width,
+            None => private.default_width_x(),
+        })
+    }
+
+    /// The advance width of `glyph`, as encoded in its charstring.
+    pub fn glyph_width(&self, glyph: GlyphId) -> Result<f64> {
+        self.outline(glyph, &mut NullSink)
+    }
+
+    /// The exact bounding box of `glyph`, or `None` if it has no outline.
+    pub fn glyph_bounds(&self, glyph: GlyphId) -> Result<Option<Rect>> {
+        let mut sink = BoundsSink::new();
+        self.outline(glyph, &mut sink)?;
+        Ok(sink.bounds())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use font::Font;
+    use decode::Decode;
+
+    #[test]
+    fn subset_round_trip() {
+        let data = open_file!("data/SourceSansPro/SourceSansPro-Regular.otf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let cff = font.table::<Cff>().expect("failed to read CFF");
+        assert!(!cff.is_cid_keyed());
+        assert!(cff.private_dict().and_then(PrivateDict::subrs).is_some());
+
+        let glyphs = [GlyphId(0), GlyphId(36), GlyphId(68)];
+        let subset = cff.subset(&glyphs).expect("failed to subset");
+        let result = Cff::decode(&subset).expect("failed to read subset");
+
+        assert_eq!(result.num_glyphs(), 3);
+        for (new, old) in glyphs.iter().enumerate() {
+            assert_eq!(result.char_strings().get(new), cff.char_strings().get(old.0 as usize));
+            assert_eq!(result.charset().sid(GlyphId(new as u16)), cff.charset().sid(*old));
+        }
+
+        let subrs = result.private_dict().and_then(PrivateDict::subrs).unwrap();
+        assert_eq!(subrs.len(), cff.private_dict().and_then(PrivateDict::subrs).unwrap().len());
+        assert!(subset.len() < cff.data().len());
+    }
+
+    #[test]
+    fn glyph_names() {
+        let data = open_file!("data/SourceSansPro/SourceSansPro-Regular.otf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let cff = font.table::<Cff>().expect("failed to read CFF");
+
+        assert_eq!(cff.string(0), Some(".notdef"));
+        assert_eq!(cff.string(390), Some("Semibold"));
+        assert_eq!(cff.sid("zcaron"), Some(228));
+        assert_eq!(cff.glyph_name(GlyphId(0)), Some(".notdef"));
+        assert_eq!(cff.glyph_name(GlyphId(cff.num_glyphs() as u16)), None);
+
+        let a = font.glyph_index('a').unwrap().unwrap();
+        assert_eq!(cff.glyph_name(a), Some("a"));
+        assert_eq!(cff.gid_by_name("a"), Some(a));
+        assert_eq!(cff.gid_by_name("no such glyph"), None);
+        assert_eq!(font.glyph_name(a), Ok(Some("a".to_string())));
+        for &(character, name) in &[('ß', "germandbls"), ('Ž', "Zcaron"), ('‰', "perthousand"), ('¾', "threequarters")] {
+            assert_eq!(cff.glyph_name(font.glyph_index(character).unwrap().unwrap()), Some(name));
+        }
+
+        // Names from the String INDEX map back to their glyphs too.
+        let custom = (0..cff.num_glyphs() as u16)
+            .map(GlyphId)
+            .filter(|&glyph| cff.charset().sid(glyph).unwrap() as usize >= STANDARD_STRINGS.len())
+            .take(20)
+            .collect::<Vec<_>>();
+        assert!(!custom.is_empty());
+        for glyph in custom {
+            assert_eq!(cff.gid_by_name(cff.glyph_name(glyph).unwrap()), Some(glyph));
+        }
+    }
+
+    #[test]
+    fn widths_and_bounds() {
+        let data = open_file!("data/SourceSansPro/SourceSansPro-Regular.otf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let cff = font.table::<Cff>().expect("failed to read CFF");
+        let hmtx = font.hmtx().expect("failed to read hmtx");
+
+        for gid in 0..cff.num_glyphs() as u16 {
+            let glyph = GlyphId(gid);
+            let width = cff.glyph_width(glyph).expect("failed to interpret charstring");
+            assert_eq!(width, hmtx.advance(glyph).unwrap() as f64);
+
+            if let Some(bounds) = cff.glyph_bounds(glyph).unwrap() {
+                let lsb = hmtx.left_side_bearing(glyph).unwrap() as f32;
+                assert!((bounds.x_min - lsb).abs() <= 1.0, "glyph {}", gid);
+            }
+        }
+    }
+
+    #[test]
+    fn operator_budget() {
+        let data = open_file!("data/SourceSansPro/SourceSansPro-Regular.otf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let cff = font.table::<Cff>().expect("failed to read CFF");
+
+        // Each global subroutine calls the next `fan_out` times, so the
+        // work grows exponentially with the nesting depth.  Operands from
+        // 32 to 41 are the biased indices of subroutines 0 to 9.
+        let chain = |fan_out: usize| {
+            let mut subrs = (1..10u8).map(|next| [next + 32, 29].repeat(fan_out)).collect::<Vec<_>>();
+            subrs.push(Vec::new());
+            for subr in &mut subrs {
+                subr.push(11);
+            }
+            let mut buffer = Vec::new();
+            index::write_index(&mut buffer, &subrs);
+            buffer
+        };
+        let mut char_strings = Vec::new();
+        index::write_index(&mut char_strings, &[[32, 29, 14]]);
+
+        let small = chain(2);
+        let cff_small = Cff {
+            global_subrs: Index::decode(&small).unwrap(),
+            char_strings: Index::decode(&char_strings).unwrap(),
+            ..cff.clone()
+        };
+        assert_eq!(cff_small.glyph_width(GlyphId(0)), Ok(cff.private_dict().unwrap().default_width_x()));
+
+        let large = chain(8);
+        let cff_large = Cff {
+            global_subrs: Index::decode(&large).unwrap(),
+            char_strings: Index::decode(&char_strings).unwrap(),
+            ..cff.clone()
+        };
+        assert_eq!(cff_large.glyph_width(GlyphId(0)), Err(Error::InvalidData));
+    }
+
+    #[test]
+    fn nested_seac() {
+        let data = open_file!("data/SourceSansPro/SourceSansPro-Regular.otf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let cff = font.table::<Cff>().expect("failed to read CFF");
+
+        // `B` becomes `A` with a grave accent, and `C` becomes `B` with
+        // one, which composes a composition.
+        let b = cff.gid_by_name("B").unwrap();
+        let c = cff.gid_by_name("C").unwrap();
+        let mut glyphs = cff.char_strings().iter().map(<[u8]>::to_vec).collect::<Vec<_>>();
+        glyphs[b.0 as usize] = vec![139, 139, 204, 247, 85, 14];
+        glyphs[c.0 as usize] = vec![139, 139, 205, 247, 85, 14];
+        let mut char_strings = Vec::new();
+        index::write_index(&mut char_strings, &glyphs);
+        let cff = Cff { char_strings: Index::decode(&char_strings).unwrap(), ..cff.clone() };
+
+        assert!(cff.glyph_bounds(b).unwrap().is_some());
+        assert_eq!(cff.glyph_bounds(c), Err(Error::InvalidData));
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::BTreeSet;
+
+use super::{Cff, FontKind, PrivateDict};
+use super::index::{Index, write_index};
+use super::dict::{self, op};
+use super::charstring::{Interpreter, NullSink, SubrUsage};
+use glyph_set::GlyphSet;
+use primitives::GlyphId;
+use error::{Error, Result};
+
+/// The Top DICT entries whose offsets are rewritten in a subset.  The
+/// `Encoding` is dropped, since a subset font is referenced by glyph id.
+const TOP_DICT_REPLACED: &[u16] = &[
+    op::CHARSET,
+    op::ENCODING,
+    op::CHAR_STRINGS,
+    op::PRIVATE,
+    op::FD_ARRAY,
+    op::FD_SELECT,
+];
+
+impl<'fnt> Cff<'fnt> {
+    /// Serialize a bare CFF font program containing only `glyphs`, for
+    /// example to embed as a PDF `FontFile3`.  Glyph 0 (`.notdef`) is always
+    /// kept as the first glyph; the remaining glyphs are renumbered in the
+    /// order given, followed by any glyphs needed for accent compositions.
+    ///
+    /// Subroutines which are not reachable from the retained charstrings
+    /// are emptied rather than removed, so that subroutine numbers, and
+    /// therefore the charstrings which reference them, are unchanged.
+    pub fn subset(&self, glyphs: &[GlyphId]) -> Result<Vec<u8>> {
+        let mut order = vec![GlyphId(0)];
+        order.extend(glyphs.iter().filter(|glyph| glyph.0 != 0));
+        let mut retained = order.iter().cloned().collect::<GlyphSet>();
+
+        // Find the subroutines reachable from each retained glyph.  Local
+        // subroutines are tracked per Private DICT in CID-keyed fonts.
+        // Glyphs used by an accent composition are retained as well, since
+        // `endchar` refers to them through the charset.
+        let num_privates = match self.kind {
+            FontKind::Simple(_) => 1,
+            FontKind::Cid { ref font_dicts, .. } => font_dicts.len(),
+        };
+        let mut global = BTreeSet::new();
+        let mut local = vec![BTreeSet::new(); num_privates];
+        let mut char_strings = Vec::new();
+        let mut next = 0;
+        while next < order.len() {
+            let glyph = order[next];
+            let charstring = self.char_strings.get(glyph.0 as usize).ok_or(Error::InvalidGlyphId)?;
+            let fd = self.font_dict_index(glyph)?;
+            let mut usage = SubrUsage::default();
+            Interpreter::new(self, glyph, &mut NullSink, &mut usage).run(charstring)?;
+            global.extend(usage.global);
+            local[fd].extend(usage.local);
+            for component in usage.components {
+                if retained.insert(component) {
+                    order.push(component);
+                }
+            }
+
+            char_strings.push(charstring);
+            next += 1;
+        }
+
+        let mut charset = vec![0];
+        for &glyph in &order[1..] {
+            let sid = self.charset.sid(glyph).ok_or(Error::InvalidData)?;
+            charset.push((sid >> 8) as u8);
+            charset.push(sid as u8);
+        }
+
+        // Everything before the charset has a size which is independent of
+        // the offsets in the Top DICT, since they are encoded in 5 bytes.
+        let mut header = vec![1, 0, 4, 4];
+        write_index(&mut header, &[self.names.get(0).ok_or(Error::InvalidData)?]);
+
+        let mut strings_and_subrs = Vec::new();
+        write_index(&mut strings_and_subrs, &self.strings.iter().collect::<Vec<_>>());
+        write_index(&mut strings_and_subrs, &prune(self.global_subrs, &global));
+
+        let top_dict_len = self.build_top_dict(&[0; 5]).len();
+        let mut top_dict_index_len = Vec::new();
+        write_index(&mut top_dict_index_len, &[vec![0; top_dict_len]]);
+
+        let charset_offset = header.len() + top_dict_index_len.len() + strings_and_subrs.len();
+        let mut body = charset;
+
+        let (offsets, privates) = match self.kind {
+            FontKind::Simple(ref private) => {
+                let char_strings_offset = charset_offset + body.len();
+                write_index(&mut body, &char_strings);
+
+                let private_offset = charset_offset + body.len();
+                let private = build_private(private, &local[0]);
+                let top = [charset_offset, 0, char_strings_offset, private_offset, private.0];
+                (top, vec![private.1])
+            },
+            FontKind::Cid { ref font_dicts, .. } => {
+                let fd_select_offset = charset_offset + body.len();
+                body.push(0);
+                for &glyph in &order {
+                    body.push(self.font_dict_index(glyph)? as u8);
+                }
+
+                let char_strings_offset = charset_offset + body.len();
+                write_index(&mut body, &char_strings);
+
+                let privates = font_dicts.iter()
+                    .zip(&local)
+                    .map(|(font_dict, usage)| build_private(&font_dict.private, usage))
+                    .collect::<Vec<_>>();
+
+                // Build the FDArray once to measure it, then again with the
+                // final Private DICT offsets.
+                let fd_array_offset = charset_offset + body.len();
+                let build_fd_array = |private_offset: usize| {
+                    let mut offset = private_offset;
+                    let dicts = font_dicts.iter()
+                        .zip(&privates)
+                        .map(|(font_dict, private)| {
+                            let entry = vec![private.0 as i32, offset as i32];
+                            offset += private.1.len();
+                            dict::rebuild(&font_dict.dict, &[op::PRIVATE], &[(op::PRIVATE, entry)])
+                        })
+                        .collect::<Vec<_>>();
+                    let mut fd_array = Vec::new();
+                    write_index(&mut fd_array, &dicts);
+                    fd_array
+                };
+
+                let fd_array_len = build_fd_array(0).len();
+                body.extend(build_fd_array(fd_array_offset + fd_array_len));
+
+                let top = [charset_offset, fd_select_offset, char_strings_offset, fd_array_offset, 0];
+                (top, privates.into_iter().map(|private| private.1).collect())
+            },
+        };
+
+        let mut out = header;
+        write_index(&mut out, &[self.build_top_dict(&offsets)]);
+        out.extend(strings_and_subrs);
+        out.extend(body);
+        for private in privates {
+            out.extend(private);
+        }
+
+        Ok(out)
+    }
+
+    fn font_dict_index(&self, glyph: GlyphId) -> Result<usize> {
+        match self.kind {
+            FontKind::Simple(_) => Ok(0),
+            FontKind::Cid { ref fd_select, .. } => fd_select.font_dict_index(glyph)
+                .map(|index| index as usize)
+                .ok_or(Error::InvalidData),
+        }
+    }
+
+    /// Build the Top DICT from `[charset, fd_select, char_strings, private
+    /// or fd_array, private size]` offsets.
+    fn build_top_dict(&self, offsets: &[usize; 5]) -> Vec<u8> {
+        let [charset, fd_select, char_strings, private, private_size] = offsets.map(|o| o as i32);
+        let mut entries = vec![
+            (op::CHARSET, vec![charset]),
+            (op::CHAR_STRINGS, vec![char_strings]),
+        ];
+
+        match self.kind {
+            FontKind::Simple(_) => entries.push((op::PRIVATE, vec![private_size, private])),
+            FontKind::Cid { .. } => {
+                entries.push((op::FD_SELECT, vec![fd_select]));
+                entries.push((op::FD_ARRAY, vec![private]));
+            },
+        }
+
+        // The ROS must remain the first entry of a CID-keyed Top DICT, which
+        // is preserved since untouched entries are copied in order.
+        dict::rebuild(&self.top_dict, TOP_DICT_REPLACED, &entries)
+    }
+}
+
+/// Serialize a Private DICT followed by its pruned local subroutines,
+/// returning the size of the DICT and the serialized bytes.
+fn build_private(private: &PrivateDict, used: &BTreeSet<usize>) -> (usize, Vec<u8>) {
+    let subrs = match private.subrs() {
+        Some(subrs) => subrs,
+        None => {
+            let dict = dict::rebuild(private.dict(), &[op::SUBRS], &[]);
+            return (dict.len(), dict)
+        },
+    };
+
+    // `Subrs` is relative to the Private DICT, and the subroutines are
+    // placed immediately after it.
+    let len = dict::rebuild(private.dict(), &[op::SUBRS], &[(op::SUBRS, vec![0])]).len();
+    let mut out = dict::rebuild(private.dict(), &[op::SUBRS], &[(op::SUBRS, vec![len as i32])]);
+    write_index(&mut out, &prune(subrs, used));
+    (len, out)
+}
+
+/// Replace every subroutine not in `used` with an empty subroutine.
+fn prune<'fnt>(subrs: Index<'fnt>, used: &BTreeSet<usize>) -> Vec<&'fnt [u8]> {
+    subrs.iter()
+        .enumerate()
+        .map(|(index, subr)| if used.contains(&index) { subr } else { &[] })
+        .collect()
+}
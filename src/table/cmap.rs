@@ -0,0 +1,903 @@
+use byteorder::{BigEndian, ByteOrder};
+
+use decode::{Decode, Decode1, StaticEncodeSize, EncodeSize};
+use encode::EncodeBuf;
+use primitives::{Array, ArrayIter, GlyphId, Uint24};
+use error::{Error, Result};
+
+/// The character to glyph index mapping table.
+#[derive(Debug, Copy, Clone)]
+pub struct Cmap<'fnt> {
+    buffer: &'fnt [u8],
+    pub version: u16,
+    records: Array<'fnt, EncodingRecord>,
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EncodingRecord {
+    pub platform_id: u16,
+    pub encoding_id: u16,
+    /// The offset of the subtable from the start of the `cmap` table.
+    pub offset: u32,
+}
+
+impl<'fnt> Decode<'fnt> for Cmap<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Cmap<'fnt>> {
+        required_len!(buffer, 4);
+        let version = u16::decode(buffer)?;
+        let num_tables = u16::decode(&buffer[2..])? as usize;
+        let records = Array::decode(&buffer[4..], num_tables)?;
+
+        Ok(Cmap { buffer, version, records })
+    }
+}
+
+/// The encodings which map Unicode code points, from most to least
+/// preferred.  Full repertoire subtables come before BMP-only ones.
+const UNICODE_ENCODINGS: [(u16, u16); 7] = [
+    (3, 10),
+    (0, 6),
+    (0, 4),
+    (3, 1),
+    (0, 3),
+    (0, 2),
+    (0, 1),
+];
+
+impl<'fnt> Cmap<'fnt> {
+    pub fn records(&self) -> Array<'fnt, EncodingRecord> {
+        self.records
+    }
+
+    /// Decode the subtable of `record`.
+    pub fn subtable(&self, record: &EncodingRecord) -> Result<CmapSubtable<'fnt>> {
+        let data = self.buffer.get(record.offset as usize..).ok_or(Error::UnexpectedEof)?;
+        CmapSubtable::decode(data)
+    }
+
+    /// The format of the subtable of `record`, which may be one that
+    /// `subtable` cannot decode.
+    pub fn subtable_format(&self, record: &EncodingRecord) -> Result<u16> {
+        let data = self.buffer.get(record.offset as usize..).ok_or(Error::UnexpectedEof)?;
+        u16::decode(data)
+    }
+
+    /// The preferred subtable for mapping Unicode code points, or `None`
+    /// if the font has no Unicode encoding.
+    pub fn unicode_subtable(&self) -> Result<Option<CmapSubtable<'fnt>>> {
+        for &(platform_id, encoding_id) in &UNICODE_ENCODINGS {
+            let record = self.records.into_iter()
+                .find(|record| record.platform_id == platform_id && record.encoding_id == encoding_id);
+            if let Some(record) = record {
+                return self.subtable(&record).map(Some)
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Map each of `chars` to its glyph in `glyphs`, as `Font::glyph_index`
+    /// would, with `.notdef` for unmapped characters.  The subtable is
+    /// chosen once for the whole run.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chars` and `glyphs` differ in length.
+    pub fn map_chars(&self, chars: &[char], glyphs: &mut [GlyphId]) -> Result<()> {
+        assert_eq!(chars.len(), glyphs.len(), "map_chars needs a glyph for every char");
+        if let Some(subtable) = self.unicode_subtable()? {
+            subtable.map_chars(chars, glyphs);
+            return Ok(())
+        }
+
+        let symbol = self.symbol_subtable()?;
+        for (&character, glyph) in chars.iter().zip(glyphs.iter_mut()) {
+            *glyph = symbol.and_then(|symbol| match character as u32 {
+                code @ 0..=0xFF => symbol.byte_glyph_id(code as u8),
+                code => symbol.glyph_id(code),
+            }).unwrap_or(GlyphId(0));
+        }
+        Ok(())
+    }
+
+    /// The Windows Symbol subtable (platform 3, encoding 0), or `None` if
+    /// the font has no symbol encoding.
+    pub fn symbol_subtable(&self) -> Result<Option<SymbolSubtable<'fnt>>> {
+        let record = self.records.into_iter()
+            .find(|record| record.platform_id == 3 && record.encoding_id == 0);
+        match record {
+            Some(record) => self.subtable(&record).map(|subtable| Some(SymbolSubtable { subtable })),
+            None => Ok(None),
+        }
+    }
+
+    /// The Unicode Variation Sequences subtable (platform 0, encoding 5),
+    /// or `None` if the font has none.
+    pub fn variation_sequences(&self) -> Result<Option<VariationSequences<'fnt>>> {
+        let record = self.records.into_iter()
+            .find(|record| record.platform_id == 0 && record.encoding_id == 5);
+        match record {
+            Some(record) => {
+                let data = self.buffer.get(record.offset as usize..).ok_or(Error::UnexpectedEof)?;
+                VariationSequences::decode(data).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+/// The format 14 subtable, which maps a character followed by a variation
+/// selector either to the glyph of the character alone or to a variant.
+#[derive(Debug, Copy, Clone)]
+pub struct VariationSequences<'fnt> {
+    buffer: &'fnt [u8],
+    records: Array<'fnt, VariationSelectorRecord>,
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct VariationSelectorRecord {
+    pub var_selector: Uint24,
+    /// The offset of the default UVS table, or zero.
+    pub default_uvs_offset: u32,
+    /// The offset of the non-default UVS table, or zero.
+    pub non_default_uvs_offset: u32,
+}
+
+/// A range of the default UVS table, covering `start_unicode_value` and
+/// the `additional_count` characters after it.
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct UnicodeRange {
+    pub start_unicode_value: Uint24,
+    pub additional_count: u8,
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct UvsMapping {
+    pub unicode_value: Uint24,
+    pub glyph_id: GlyphId,
+}
+
+/// The glyph of a variation sequence.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VariantGlyph {
+    /// The sequence uses the glyph the character maps to by itself.
+    Default,
+    Variant(GlyphId),
+}
+
+impl<'fnt> Decode<'fnt> for VariationSequences<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<VariationSequences<'fnt>> {
+        required_len!(buffer, 10);
+        if u16::decode(buffer)? != 14 {
+            return Err(Error::UnsupportedCmapFormat)
+        }
+
+        let count = u32::decode(&buffer[6..])? as usize;
+        let records = Array::decode(&buffer[10..], count)?;
+        Ok(VariationSequences { buffer, records })
+    }
+}
+
+impl<'fnt> VariationSequences<'fnt> {
+    pub fn records(&self) -> Array<'fnt, VariationSelectorRecord> {
+        self.records
+    }
+
+    /// The glyph of `code` followed by the variation selector `selector`,
+    /// or `None` if the font does not support the sequence.
+    pub fn variant(&self, code: u32, selector: u32) -> Result<Option<VariantGlyph>> {
+        let record = match self.records.into_iter().find(|record| u32::from(record.var_selector) == selector) {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+
+        if record.default_uvs_offset != 0 {
+            let data = self.buffer.get(record.default_uvs_offset as usize..).ok_or(Error::UnexpectedEof)?;
+            let count = u32::decode(data)? as usize;
+            let ranges = Array::<UnicodeRange>::decode(&data[4..], count)?;
+            let covered = ranges.into_iter().any(|range| {
+                let start = u32::from(range.start_unicode_value);
+                start <= code && code <= start + u32::from(range.additional_count)
+            });
+            if covered {
+                return Ok(Some(VariantGlyph::Default))
+            }
+        }
+
+        if record.non_default_uvs_offset != 0 {
+            let data = self.buffer.get(record.non_default_uvs_offset as usize..).ok_or(Error::UnexpectedEof)?;
+            let count = u32::decode(data)? as usize;
+            let mappings = Array::<UvsMapping>::decode(&data[4..], count)?;
+            if let Some(mapping) = mappings.into_iter().find(|mapping| u32::from(mapping.unicode_value) == code) {
+                return Ok(Some(VariantGlyph::Variant(mapping.glyph_id)))
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// The first code of the private use range where symbol fonts map their
+/// glyphs.  A symbol font's single byte codes are offset by this amount.
+pub const SYMBOL_BASE: u32 = 0xF000;
+
+/// A Windows Symbol subtable, such as that of Symbol or Wingdings.  These
+/// map codes in the private use range `0xF020..=0xF0FF`, which legacy
+/// consumers address by the single byte code without the `0xF000` offset.
+#[derive(Debug, Copy, Clone)]
+pub struct SymbolSubtable<'fnt> {
+    subtable: CmapSubtable<'fnt>,
+}
+
+impl<'fnt> SymbolSubtable<'fnt> {
+    pub fn subtable(&self) -> CmapSubtable<'fnt> {
+        self.subtable
+    }
+
+    /// The glyph mapped to the raw `code`, as it appears in the subtable.
+    pub fn glyph_id(&self, code: u32) -> Option<GlyphId> {
+        self.subtable.glyph_id(code)
+    }
+
+    /// The glyph mapped to the single byte `code`.  The code is looked up
+    /// in the private use range first, and as is when that is unmapped,
+    /// since some fonts map the byte range directly.
+    pub fn byte_glyph_id(&self, code: u8) -> Option<GlyphId> {
+        self.subtable.glyph_id(SYMBOL_BASE + u32::from(code))
+            .or_else(|| self.subtable.glyph_id(u32::from(code)))
+    }
+
+    /// Every raw `(code, glyph)` mapping of the subtable.
+    pub fn mappings(&self) -> Vec<(u32, GlyphId)> {
+        self.subtable.mappings()
+    }
+
+    /// The mappings of the single byte codes, with the private use range
+    /// moved down to `0x00..=0xFF`.  A code mapped both ways keeps the
+    /// glyph of the private use range.
+    pub fn byte_mappings(&self) -> Vec<(u8, GlyphId)> {
+        (0..=0xFF).filter_map(|code| self.byte_glyph_id(code).map(|glyph| (code, glyph))).collect()
+    }
+}
+
+/// The subtable characters are mapped through: the preferred Unicode
+/// subtable, or else the symbol subtable.
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum CharacterMap<'fnt> {
+    Unicode(CmapSubtable<'fnt>),
+    Symbol(SymbolSubtable<'fnt>),
+}
+
+impl<'fnt> CharacterMap<'fnt> {
+    /// The glyph of `character`.  A symbol subtable maps characters up to
+    /// `U+00FF` as single byte codes, and others by their code point.
+    pub fn glyph_id(&self, character: char) -> Option<GlyphId> {
+        match *self {
+            CharacterMap::Unicode(ref subtable) => subtable.glyph_id(character as u32),
+            CharacterMap::Symbol(ref symbol) => match character as u32 {
+                code @ 0..=0xFF => symbol.byte_glyph_id(code as u8),
+                code => symbol.glyph_id(code),
+            },
+        }
+    }
+}
+
+/// A subtable of `cmap`, which maps character codes of a single encoding.
+#[derive(Debug, Copy, Clone)]
+pub enum CmapSubtable<'fnt> {
+    Format0(Format0<'fnt>),
+    Format4(Format4<'fnt>),
+    Format6(Format6<'fnt>),
+    Format12(SegmentedCoverage<'fnt>),
+    Format13(SegmentedCoverage<'fnt>),
+}
+
+impl<'fnt> Decode<'fnt> for CmapSubtable<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<CmapSubtable<'fnt>> {
+        match u16::decode(buffer)? {
+            0 => Format0::decode(buffer).map(CmapSubtable::Format0),
+            4 => Format4::decode(buffer).map(CmapSubtable::Format4),
+            6 => Format6::decode(buffer).map(CmapSubtable::Format6),
+            12 => SegmentedCoverage::decode(buffer).map(CmapSubtable::Format12),
+            13 => SegmentedCoverage::decode(buffer).map(CmapSubtable::Format13),
+            _ => Err(Error::UnsupportedCmapFormat),
+        }
+    }
+}
+
+impl<'fnt> CmapSubtable<'fnt> {
+    /// The glyph mapped to `code`, or `None` if the code is unmapped or
+    /// maps to `.notdef`.
+    pub fn glyph_id(&self, code: u32) -> Option<GlyphId> {
+        let glyph = match *self {
+            CmapSubtable::Format0(ref table) => table.glyph_id(code),
+            CmapSubtable::Format4(ref table) => table.glyph_id(code),
+            CmapSubtable::Format6(ref table) => table.glyph_id(code),
+            CmapSubtable::Format12(ref table) => table.glyph_id(code, false),
+            CmapSubtable::Format13(ref table) => table.glyph_id(code, true),
+        };
+
+        glyph.filter(|glyph| glyph.0 != 0)
+    }
+
+    /// Map each of `chars` to its glyph in `glyphs`, with `.notdef` for
+    /// unmapped characters.  The format is matched once for the run, and
+    /// since neighbouring characters mostly share a segment or group, the
+    /// previous character's is tried before searching a sorted subtable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chars` and `glyphs` differ in length.
+    pub fn map_chars(&self, chars: &[char], glyphs: &mut [GlyphId]) {
+        assert_eq!(chars.len(), glyphs.len(), "map_chars needs a glyph for every char");
+        match *self {
+            CmapSubtable::Format4(ref table) => table.map_chars(chars, glyphs),
+            CmapSubtable::Format12(ref table) => table.map_chars(chars, glyphs, false),
+            CmapSubtable::Format13(ref table) => table.map_chars(chars, glyphs, true),
+            CmapSubtable::Format0(ref table) => for (&character, glyph) in chars.iter().zip(glyphs.iter_mut()) {
+                *glyph = table.glyph_id(character as u32).unwrap_or(GlyphId(0));
+            },
+            CmapSubtable::Format6(ref table) => for (&character, glyph) in chars.iter().zip(glyphs.iter_mut()) {
+                *glyph = table.glyph_id(character as u32).unwrap_or(GlyphId(0));
+            },
+        }
+    }
+
+    /// Whether the segments or groups are sorted by code without overlap,
+    /// as the specification requires.  Lookups in an unsorted subtable
+    /// fall back to a linear search, as rasterizers tolerate them.
+    pub fn is_sorted(&self) -> bool {
+        match *self {
+            CmapSubtable::Format4(ref table) => table.sorted,
+            CmapSubtable::Format12(ref table) | CmapSubtable::Format13(ref table) => table.sorted,
+            CmapSubtable::Format0(_) | CmapSubtable::Format6(_) => true,
+        }
+    }
+
+    /// Every `(code, glyph)` mapping of the subtable, in code order.
+    /// Codes which map to `.notdef` are left out.
+    pub fn mappings(&self) -> Vec<(u32, GlyphId)> {
+        let codes: Box<dyn Iterator<Item = u32>> = match *self {
+            CmapSubtable::Format0(_) => Box::new(0..256),
+            CmapSubtable::Format4(ref table) => Box::new((0..table.seg_count).flat_map(move |segment| {
+                table.start_code(segment) as u32..=table.end_code(segment) as u32
+            })),
+            CmapSubtable::Format6(ref table) => {
+                let first = table.first_code as u32;
+                Box::new(first..first + table.glyph_ids.len() as u32)
+            },
+            CmapSubtable::Format12(ref table) | CmapSubtable::Format13(ref table) => {
+                Box::new(table.groups.into_iter().flat_map(|group| {
+                    group.start_char_code..=group.end_char_code.min(0x10FFFF)
+                }))
+            },
+        };
+
+        let mut mappings = codes
+            .filter_map(|code| self.glyph_id(code).map(|glyph| (code, glyph)))
+            .collect::<Vec<_>>();
+        mappings.sort();
+        mappings.dedup_by_key(|mapping| mapping.0);
+        mappings
+    }
+}
+
+/// Serialize a `cmap` table for Unicode `mappings`, with a format 4
+/// subtable for the Basic Multilingual Plane and a format 12 subtable
+/// when any code point cannot be stored in format 4.
+pub fn encode_unicode(mappings: &[(u32, GlyphId)]) -> Result<Vec<u8>> {
+    let mut mappings = mappings.to_vec();
+    mappings.sort();
+    mappings.dedup_by_key(|mapping| mapping.0);
+
+    // Runs of consecutive codes mapped to consecutive glyphs.
+    let mut groups: Vec<(u32, u32, u16)> = Vec::new();
+    for &(code, glyph) in &mappings {
+        match groups.last_mut() {
+            Some(group) if group.1 + 1 == code && u32::from(group.2) + code - group.0 == u32::from(glyph.0) => {
+                group.1 = code;
+            },
+            _ => groups.push((code, code, glyph.0)),
+        }
+    }
+
+    let bmp = groups.iter()
+        .filter(|group| group.0 < 0xFFFF)
+        .map(|&(start, end, glyph)| (start as u16, end.min(0xFFFE) as u16, glyph))
+        .collect::<Vec<_>>();
+    let format4 = encode_format4(&bmp)?;
+    let format12 = if groups.last().is_some_and(|group| group.1 >= 0xFFFF) {
+        Some(encode_format12(&groups))
+    } else {
+        None
+    };
+
+    let mut out = EncodeBuf::new();
+    out.put_u16(0);
+    out.put_u16(if format12.is_some() { 2 } else { 1 });
+    out.put_u16(3);
+    out.put_u16(1);
+    let format4_offset = out.reserve_offset32(0);
+    let format12_offset = format12.as_ref().map(|_| {
+        out.put_u16(3);
+        out.put_u16(10);
+        out.reserve_offset32(0)
+    });
+
+    out.patch_offset(format4_offset)?;
+    out.put_bytes(&format4);
+    if let (Some(offset), Some(format12)) = (format12_offset, format12) {
+        out.patch_offset(offset)?;
+        out.put_bytes(&format12);
+    }
+
+    Ok(out.into_vec())
+}
+
+fn encode_format4(groups: &[(u16, u16, u16)]) -> Result<Vec<u8>> {
+    // The final segment must map 0xFFFF to `.notdef`.
+    let mut segments = groups.to_vec();
+    segments.push((0xFFFF, 0xFFFF, 0));
+
+    let seg_count = segments.len();
+    let length = 16 + seg_count * 8;
+    if length > 0xFFFF {
+        return Err(Error::InvalidData)
+    }
+
+    let entry_selector = (usize::BITS - seg_count.leading_zeros() - 1) as u16;
+    let search_range = 2 << entry_selector;
+
+    let mut out = EncodeBuf::new();
+    out.put_u16(4);
+    out.put_u16(length as u16);
+    out.put_u16(0);
+    out.put_u16(seg_count as u16 * 2);
+    out.put_u16(search_range);
+    out.put_u16(entry_selector);
+    out.put_u16(seg_count as u16 * 2 - search_range);
+    for segment in &segments {
+        out.put_u16(segment.1);
+    }
+    out.put_u16(0);
+    for segment in &segments {
+        out.put_u16(segment.0);
+    }
+    for segment in &segments {
+        out.put_u16(match *segment {
+            (0xFFFF, _, 0) => 1,
+            (start, _, glyph) => glyph.wrapping_sub(start),
+        });
+    }
+    for _ in &segments {
+        out.put_u16(0);
+    }
+
+    Ok(out.into_vec())
+}
+
+fn encode_format12(groups: &[(u32, u32, u16)]) -> Vec<u8> {
+    let mut out = EncodeBuf::new();
+    out.put_u16(12);
+    out.put_u16(0);
+    out.put_u32(16 + groups.len() as u32 * 12);
+    out.put_u32(0);
+    out.put_u32(groups.len() as u32);
+    for &(start, end, glyph) in groups {
+        out.put_u32(start);
+        out.put_u32(end);
+        out.put_u32(u32::from(glyph));
+    }
+    out.into_vec()
+}
+
+/// Byte encoding table, which maps single byte codes.
+#[derive(Debug, Copy, Clone)]
+pub struct Format0<'fnt> {
+    glyph_ids: &'fnt [u8],
+}
+
+impl<'fnt> Decode<'fnt> for Format0<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Format0<'fnt>> {
+        required_len!(buffer, 6 + 256);
+        Ok(Format0 { glyph_ids: &buffer[6..6 + 256] })
+    }
+}
+
+impl<'fnt> Format0<'fnt> {
+    pub fn glyph_id(&self, code: u32) -> Option<GlyphId> {
+        self.glyph_ids.get(code as usize).map(|&glyph| GlyphId(glyph as u16))
+    }
+}
+
+/// Segment mapping to delta values, which maps the Basic Multilingual Plane.
+#[derive(Debug, Copy, Clone)]
+pub struct Format4<'fnt> {
+    buffer: &'fnt [u8],
+    seg_count: usize,
+    sorted: bool,
+}
+
+impl<'fnt> Decode<'fnt> for Format4<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Format4<'fnt>> {
+        required_len!(buffer, 14);
+        let seg_count = u16::decode(&buffer[6..])? as usize / 2;
+        required_len!(buffer, 16 + seg_count * 8);
+
+        let mut table = Format4 { buffer, seg_count, sorted: true };
+        table.sorted = (0..seg_count).all(|segment| table.start_code(segment) <= table.end_code(segment))
+            && (1..seg_count).all(|segment| table.end_code(segment - 1) < table.start_code(segment));
+        Ok(table)
+    }
+}
+
+impl<'fnt> Format4<'fnt> {
+    fn end_code(&self, segment: usize) -> u16 {
+        self.read(14, segment)
+    }
+
+    fn start_code(&self, segment: usize) -> u16 {
+        self.read(16 + self.seg_count * 2, segment)
+    }
+
+    fn id_delta(&self, segment: usize) -> u16 {
+        self.read(16 + self.seg_count * 4, segment)
+    }
+
+    fn id_range_offset_pos(&self, segment: usize) -> usize {
+        16 + self.seg_count * 6 + segment * 2
+    }
+
+    /// Read the `index`th element of the array at `pos`, which decoding
+    /// has already checked is in bounds.
+    fn read(&self, pos: usize, index: usize) -> u16 {
+        BigEndian::read_u16(&self.buffer[pos + index * 2..])
+    }
+
+    pub fn glyph_id(&self, code: u32) -> Option<GlyphId> {
+        if code > 0xFFFF {
+            return None
+        }
+        let code = code as u16;
+        self.segment(code).and_then(|segment| self.segment_glyph(segment, code))
+    }
+
+    fn map_chars(&self, chars: &[char], glyphs: &mut [GlyphId]) {
+        let mut previous = None;
+        for (&character, glyph) in chars.iter().zip(glyphs.iter_mut()) {
+            *glyph = GlyphId(0);
+            let code = character as u32;
+            if code > 0xFFFF {
+                continue
+            }
+            let code = code as u16;
+
+            let segment = match previous {
+                Some(segment) if self.sorted && self.start_code(segment) <= code && code <= self.end_code(segment) => segment,
+                _ => match self.segment(code) {
+                    Some(segment) => segment,
+                    None => continue,
+                },
+            };
+            previous = Some(segment);
+            *glyph = self.segment_glyph(segment, code).unwrap_or(GlyphId(0));
+        }
+    }
+
+    /// The glyph of `code` in `segment`, which contains it.
+    fn segment_glyph(&self, segment: usize, code: u16) -> Option<GlyphId> {
+        let delta = self.id_delta(segment);
+        let range_pos = self.id_range_offset_pos(segment);
+        let id_range_offset = self.read(range_pos, 0) as usize;
+        if id_range_offset == 0 {
+            return Some(GlyphId(code.wrapping_add(delta)))
+        }
+
+        // The offset is relative to its own position in the table.
+        let pos = range_pos + id_range_offset + (code - self.start_code(segment)) as usize * 2;
+        let glyph = u16::decode(self.buffer.get(pos..)?).ok()?;
+        match glyph {
+            0 => None,
+            glyph => Some(GlyphId(glyph.wrapping_add(delta))),
+        }
+    }
+
+    /// The segment which contains `code`.
+    fn segment(&self, code: u16) -> Option<usize> {
+        if !self.sorted {
+            return (0..self.seg_count)
+                .find(|&segment| self.start_code(segment) <= code && code <= self.end_code(segment))
+        }
+
+        // Segments are sorted by end code, so find the first which ends
+        // at or after `code`.
+        let (mut low, mut high) = (0, self.seg_count);
+        while low < high {
+            let mid = (low + high) / 2;
+            if self.end_code(mid) < code {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        if low == self.seg_count || self.start_code(low) > code {
+            return None
+        }
+
+        Some(low)
+    }
+}
+
+/// Trimmed table mapping, which maps a single dense range of codes.
+#[derive(Debug, Copy, Clone)]
+pub struct Format6<'fnt> {
+    pub first_code: u16,
+    glyph_ids: Array<'fnt, u16>,
+}
+
+impl<'fnt> Decode<'fnt> for Format6<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Format6<'fnt>> {
+        required_len!(buffer, 10);
+        let first_code = u16::decode(&buffer[6..])?;
+        let entry_count = u16::decode(&buffer[8..])? as usize;
+        let glyph_ids = Array::decode(&buffer[10..], entry_count)?;
+
+        Ok(Format6 { first_code, glyph_ids })
+    }
+}
+
+impl<'fnt> Format6<'fnt> {
+    pub fn glyph_id(&self, code: u32) -> Option<GlyphId> {
+        let index = code.checked_sub(self.first_code as u32)?;
+        self.glyph_ids.get(index as usize).map(GlyphId)
+    }
+}
+
+/// The groups of formats 12 and 13, which cover all of Unicode.  In format
+/// 12 each code maps to consecutive glyphs, while in format 13 every code
+/// of a group maps to the same glyph.
+///
+/// CJK fonts have tens of thousands of groups, so the groups are never
+/// copied and each lookup binary searches the records in the font data.
+/// Decoding is not free, though: it reads every group once, without
+/// allocating, to check that they are sorted, so that a lookup which
+/// misses need not fall back to a linear search.
+#[derive(Debug, Copy, Clone)]
+pub struct SegmentedCoverage<'fnt> {
+    groups: Array<'fnt, MapGroup>,
+    sorted: bool,
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MapGroup {
+    pub start_char_code: u32,
+    pub end_char_code: u32,
+    pub glyph_id: u32,
+}
+
+impl<'fnt> Decode<'fnt> for SegmentedCoverage<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<SegmentedCoverage<'fnt>> {
+        required_len!(buffer, 16);
+        let num_groups = u32::decode(&buffer[12..])? as usize;
+        let groups = Array::<MapGroup>::decode(&buffer[16..], num_groups)?;
+        Ok(SegmentedCoverage { groups, sorted: is_sorted(groups) })
+    }
+}
+
+impl<'fnt> SegmentedCoverage<'fnt> {
+    pub fn num_groups(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// The groups in the order the table stores them, each decoded as the
+    /// iterator reaches it.
+    pub fn groups(&self) -> ArrayIter<'fnt, MapGroup> {
+        self.groups.into_iter()
+    }
+
+    fn glyph_id(&self, code: u32, many_to_one: bool) -> Option<GlyphId> {
+        self.group(code).and_then(|group| SegmentedCoverage::group_glyph(group, code, many_to_one))
+    }
+
+    fn map_chars(&self, chars: &[char], glyphs: &mut [GlyphId], many_to_one: bool) {
+        let mut previous: Option<MapGroup> = None;
+        for (&character, glyph) in chars.iter().zip(glyphs.iter_mut()) {
+            let code = character as u32;
+            let group = match previous {
+                Some(group) if group.start_char_code <= code && code <= group.end_char_code => Some(group),
+                _ => self.group(code),
+            };
+            if group.is_some() {
+                previous = group;
+            }
+            *glyph = group
+                .and_then(|group| SegmentedCoverage::group_glyph(group, code, many_to_one))
+                .unwrap_or(GlyphId(0));
+        }
+    }
+
+    /// The glyph of `code` in `group`, which contains it.
+    fn group_glyph(group: MapGroup, code: u32, many_to_one: bool) -> Option<GlyphId> {
+        let glyph = if many_to_one {
+            group.glyph_id
+        } else {
+            group.glyph_id.checked_add(code - group.start_char_code)?
+        };
+
+        if glyph > 0xFFFF { None } else { Some(GlyphId(glyph as u16)) }
+    }
+
+    /// The group which contains `code`.  The groups are binary searched,
+    /// and when that misses in groups which decoding found out of order,
+    /// searched linearly.
+    fn group(&self, code: u32) -> Option<MapGroup> {
+        let (mut low, mut high) = (0, self.groups.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            let group = self.groups.get(mid)?;
+            if code < group.start_char_code {
+                high = mid;
+            } else if code > group.end_char_code {
+                low = mid + 1;
+            } else {
+                return Some(group)
+            }
+        }
+
+        if self.sorted {
+            return None
+        }
+        self.groups.into_iter().find(|group| group.start_char_code <= code && code <= group.end_char_code)
+    }
+}
+
+/// Whether `groups` are sorted by code without overlap.
+fn is_sorted(groups: Array<MapGroup>) -> bool {
+    let mut previous: Option<MapGroup> = None;
+    for group in groups {
+        if group.start_char_code > group.end_char_code || previous.is_some_and(|previous| previous.end_char_code >= group.start_char_code) {
+            return false
+        }
+        previous = Some(group);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use font::Font;
+    use test_fonts;
+
+    #[test]
+    fn unicode_mapping() {
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let cmap = font.table::<Cmap>().expect("failed to read cmap");
+        assert_eq!(cmap.records().len(), 3);
+
+        let subtable = cmap.unicode_subtable().unwrap().expect("no unicode subtable");
+        assert_eq!(subtable.glyph_id('H' as u32), Some(GlyphId(43)));
+        assert_eq!(subtable.glyph_id('x' as u32), Some(GlyphId(91)));
+        assert_eq!(subtable.glyph_id(0x10FFFF), None);
+
+        // The Macintosh Roman subtable uses format 0.
+        let record = cmap.records().into_iter().find(|record| record.platform_id == 1).unwrap();
+        let mac = cmap.subtable(&record).expect("failed to read format 0");
+        assert_eq!(mac.glyph_id('x' as u32), Some(GlyphId(91)));
+
+        // Encoding every mapping reproduces the same lookups.
+        let mappings = subtable.mappings();
+        assert_eq!(mappings.iter().find(|m| m.0 == 'x' as u32), Some(&('x' as u32, GlyphId(91))));
+        let encoded = encode_unicode(&mappings).expect("failed to encode cmap");
+        let encoded = Cmap::decode(&encoded).expect("failed to read encoded cmap");
+        assert_eq!(encoded.records().len(), 1);
+        assert_eq!(encoded.unicode_subtable().unwrap().unwrap().mappings(), mappings);
+
+        let wide = [(0x41, GlyphId(3)), (0x42, GlyphId(4)), (0xFFFF, GlyphId(6)), (0x1F600, GlyphId(5))];
+        let encoded = encode_unicode(&wide).unwrap();
+        let encoded = Cmap::decode(&encoded).unwrap();
+        assert_eq!(encoded.unicode_subtable().unwrap().unwrap().mappings(), wide.to_vec());
+        let record = encoded.records().get(0).unwrap();
+        assert_eq!(encoded.subtable(&record).unwrap().mappings(), vec![(0x41, GlyphId(3)), (0x42, GlyphId(4))]);
+
+        let data = test_fonts::minimal_font(false)
+            .table(b"cmap", test_fonts::cmap(&[('A', 1), ('B', 2), ('\u{1F600}', 2)]))
+            .build();
+        let font = Font::decode(&data).expect("failed to read font");
+        assert_eq!(font.glyph_index('A'), Ok(Some(GlyphId(1))));
+        assert_eq!(font.glyph_index('B'), Ok(Some(GlyphId(2))));
+        assert_eq!(font.glyph_index('\u{1F600}'), Ok(Some(GlyphId(2))));
+        assert_eq!(font.glyph_index('C'), Ok(None));
+    }
+
+    #[test]
+    fn map_chars() {
+        let text = "Hamburgefonstiv \u{1F600}\u{300}xH\u{FFFF}".chars().collect::<Vec<_>>();
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let cmap = font.table::<Cmap>().unwrap();
+        let mut glyphs = vec![GlyphId(0xFFFF); text.len()];
+        cmap.map_chars(&text, &mut glyphs).unwrap();
+        let expected = text.iter().map(|&c| font.glyph_index(c).unwrap().unwrap_or(GlyphId(0))).collect::<Vec<_>>();
+        assert_eq!(glyphs, expected);
+        assert_eq!(glyphs[0], GlyphId(43));
+
+        let cmap = test_fonts::cmap(&[('a', 1), ('b', 2), ('\u{1F600}', 3)]);
+        let subtable = Cmap::decode(&cmap).unwrap().unicode_subtable().unwrap().unwrap();
+        let mut glyphs = [GlyphId(0); 5];
+        subtable.map_chars(&['b', 'a', 'c', '\u{1F600}', 'b'], &mut glyphs);
+        assert_eq!(glyphs, [GlyphId(2), GlyphId(1), GlyphId(0), GlyphId(3), GlyphId(2)]);
+    }
+
+    #[test]
+    fn large_segmented_coverage() {
+        // A group for each of 40000 ideographs, mapped in reverse so that no
+        // two share a group.
+        let mappings = (0..40000u32)
+            .map(|index| (::std::char::from_u32(0x20000 + index).unwrap(), (40000 - index) as u16))
+            .collect::<Vec<_>>();
+        let cmap = test_fonts::cmap(&mappings);
+        let table = match Cmap::decode(&cmap).unwrap().unicode_subtable().unwrap() {
+            Some(CmapSubtable::Format12(table)) => table,
+            subtable => panic!("unexpected subtable {:?}", subtable),
+        };
+        assert_eq!(table.num_groups(), 40000);
+        assert_eq!(table.groups().nth(1).map(|group| group.start_char_code), Some(0x20001));
+        assert_eq!(table.glyph_id(0x24000, false), Some(GlyphId(40000 - 0x4000)));
+        assert_eq!(table.glyph_id(0x20000 + 40000, false), None);
+        assert!(table.sorted);
+    }
+
+    #[test]
+    fn symbol_mapping() {
+        // Rewrite the (3, 1) record of a format 4 subtable as (3, 0).
+        let mut cmap = encode_unicode(&[(0xF041, GlyphId(1)), (0xF042, GlyphId(2)), (0x20, GlyphId(3))]).unwrap();
+        cmap[6..8].copy_from_slice(&[0, 0]);
+        let table = Cmap::decode(&cmap).expect("failed to read cmap");
+        assert!(table.unicode_subtable().unwrap().is_none());
+
+        let symbol = table.symbol_subtable().unwrap().expect("no symbol subtable");
+        assert_eq!(symbol.glyph_id(0xF041), Some(GlyphId(1)));
+        assert_eq!(symbol.glyph_id(0x41), None);
+        assert_eq!(symbol.byte_glyph_id(b'A'), Some(GlyphId(1)));
+        assert_eq!(symbol.byte_glyph_id(b' '), Some(GlyphId(3)));
+        assert_eq!(symbol.byte_mappings(), vec![(b' ', GlyphId(3)), (b'A', GlyphId(1)), (b'B', GlyphId(2))]);
+        let mut glyphs = [GlyphId(0); 3];
+        table.map_chars(&['A', '\u{F042}', 'z'], &mut glyphs).unwrap();
+        assert_eq!(glyphs, [GlyphId(1), GlyphId(2), GlyphId(0)]);
+
+        // Without a Unicode subtable, characters are looked up as bytes.
+        let data = test_fonts::minimal_font(false).table(b"cmap", cmap).build();
+        let font = Font::decode(&data).expect("failed to read font");
+        assert_eq!(font.glyph_index('B'), Ok(Some(GlyphId(2))));
+        assert_eq!(font.glyph_index('\u{F042}'), Ok(Some(GlyphId(2))));
+        assert_eq!(font.glyph_index('\u{100}'), Ok(None));
+    }
+
+    #[test]
+    fn variation_sequences() {
+        let cmap = test_fonts::cmap_with_sequences(&[('\u{263A}', 1), ('\u{845B}', 2)], &[
+            ('\u{263A}', '\u{FE0E}', None),
+            ('\u{263A}', '\u{FE0F}', Some(3)),
+            ('\u{845B}', '\u{E0100}', None),
+            ('\u{845B}', '\u{E0101}', Some(4)),
+        ]);
+        let table = Cmap::decode(&cmap).expect("failed to read cmap");
+        let sequences = table.variation_sequences().unwrap().expect("no format 14 subtable");
+        assert_eq!(sequences.records().len(), 4);
+        assert_eq!(sequences.variant(0x263A, 0xFE0E), Ok(Some(VariantGlyph::Default)));
+        assert_eq!(sequences.variant(0x263A, 0xFE0F), Ok(Some(VariantGlyph::Variant(GlyphId(3)))));
+        assert_eq!(sequences.variant(0x845B, 0xFE0F), Ok(None));
+        assert_eq!(sequences.variant(0x845B, 0xE0102), Ok(None));
+
+        let data = test_fonts::minimal_font(false).table(b"cmap", cmap).build();
+        let font = Font::decode(&data).expect("failed to read font");
+        assert_eq!(font.glyph_index('\u{263A}'), Ok(Some(GlyphId(1))));
+        assert_eq!(font.glyph_variant('\u{263A}', '\u{FE0E}'), Ok(Some(GlyphId(1))));
+        assert_eq!(font.glyph_variant('\u{845B}', '\u{E0101}'), Ok(Some(GlyphId(4))));
+        assert_eq!(font.glyph_variant('A', '\u{FE0F}'), Ok(None));
+        assert!(Cmap::decode(&test_fonts::cmap(&[])).unwrap().variation_sequences().unwrap().is_none());
+    }
+}
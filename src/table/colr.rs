@@ -0,0 +1,422 @@
+use decode::{Decode, Decode1, StaticEncodeSize, EncodeSize};
+use outline::Transform;
+use primitives::{Array, F2Dot14, Fixed, GlyphId, Uint24};
+use error::{Error, Result};
+
+/// The color table, which defines color glyphs as layers of other glyphs
+/// (version 0) or as graphs of paint operations (version 1).
+#[derive(Debug, Copy, Clone)]
+pub struct Colr<'fnt> {
+    pub version: u16,
+    base_glyphs: Array<'fnt, BaseGlyphRecord>,
+    layers: Array<'fnt, LayerRecord>,
+    base_glyph_paints: Array<'fnt, BaseGlyphPaintRecord>,
+    base_glyph_list: &'fnt [u8],
+    layer_list: &'fnt [u8],
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BaseGlyphRecord {
+    pub glyph_id: GlyphId,
+    pub first_layer_index: u16,
+    pub num_layers: u16,
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LayerRecord {
+    pub glyph_id: GlyphId,
+    pub palette_index: u16,
+}
+
+/// A version 1 base glyph, with the offset of its root paint relative to
+/// the start of the base glyph list.
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BaseGlyphPaintRecord {
+    pub glyph_id: GlyphId,
+    pub paint_offset: u32,
+}
+
+impl<'fnt> Decode<'fnt> for Colr<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Colr<'fnt>> {
+        required_len!(buffer, 14);
+        let version = u16::decode(buffer)?;
+        let base_glyphs = array_at(buffer, u32::decode(&buffer[4..])?, u16::decode(&buffer[2..])?)?;
+        let layers = array_at(buffer, u32::decode(&buffer[8..])?, u16::decode(&buffer[12..])?)?;
+
+        let (mut base_glyph_paints, mut base_glyph_list, mut layer_list) = (Array::decode(&[], 0)?, &[][..], &[][..]);
+        if version >= 1 {
+            required_len!(buffer, 22);
+            let list_offset = u32::decode(&buffer[14..])?;
+            if list_offset != 0 {
+                base_glyph_list = buffer.get(list_offset as usize..).ok_or(Error::UnexpectedEof)?;
+                let count = u32::decode(base_glyph_list)? as usize;
+                base_glyph_paints = Array::decode(base_glyph_list.get(4..).unwrap_or(&[]), count)?;
+            }
+            let layer_offset = u32::decode(&buffer[18..])?;
+            if layer_offset != 0 {
+                layer_list = buffer.get(layer_offset as usize..).ok_or(Error::UnexpectedEof)?;
+            }
+        }
+
+        Ok(Colr { version, base_glyphs, layers, base_glyph_paints, base_glyph_list, layer_list })
+    }
+}
+
+impl<'fnt> Colr<'fnt> {
+    pub fn base_glyphs(&self) -> Array<'fnt, BaseGlyphRecord> {
+        self.base_glyphs
+    }
+
+    pub fn layers(&self) -> Array<'fnt, LayerRecord> {
+        self.layers
+    }
+
+    pub fn base_glyph_paints(&self) -> Array<'fnt, BaseGlyphPaintRecord> {
+        self.base_glyph_paints
+    }
+
+    /// The version 0 layers of `glyph`, if it is a layered color glyph.
+    pub fn base_glyph(&self, glyph: GlyphId) -> Option<BaseGlyphRecord> {
+        search(self.base_glyphs, glyph, |record| record.glyph_id)
+    }
+
+    /// The version 1 paint record of `glyph`, if it has one.
+    pub fn base_glyph_paint(&self, glyph: GlyphId) -> Option<BaseGlyphPaintRecord> {
+        search(self.base_glyph_paints, glyph, |record| record.glyph_id)
+    }
+
+    /// The root of the version 1 paint graph of `glyph`, if it has one.
+    pub fn paint(&self, glyph: GlyphId) -> Result<Option<Paint<'fnt>>> {
+        match self.base_glyph_paint(glyph) {
+            Some(record) => PaintRef::at(self.base_glyph_list, record.paint_offset as usize)?.get().map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The paint at `index` of the layer list, which `Paint::Layers`
+    /// refers to.
+    pub fn layer_paint(&self, index: u32) -> Result<Paint<'fnt>> {
+        if index >= u32::decode(self.layer_list)? {
+            return Err(Error::InvalidData)
+        }
+        let offset = u32::decode(self.layer_list.get(4 + index as usize * 4..).ok_or(Error::UnexpectedEof)?)?;
+        PaintRef::at(self.layer_list, offset as usize)?.get()
+    }
+}
+
+/// A node of a version 1 paint graph.  Translations, scales, rotations
+/// and skews are decoded to the transform they apply, and the variable
+/// formats to their default values, since variations are not applied.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Paint<'fnt> {
+    /// The layer list paints from `first_layer`, composited in order.
+    Layers { first_layer: u32, num_layers: u8 },
+    /// A color from the palette, with 0xFFFF for the text color.
+    Solid { palette_index: u16, alpha: f32 },
+    /// A gradient along `p0` to `p1`, with color lines perpendicular to
+    /// the line from `p0` to `p2`.
+    LinearGradient { color_line: ColorLine, p0: (f32, f32), p1: (f32, f32), p2: (f32, f32) },
+    /// A gradient between two circles.
+    RadialGradient { color_line: ColorLine, c0: (f32, f32), r0: f32, c1: (f32, f32), r1: f32 },
+    /// A gradient around `center`, between angles in degrees
+    /// counterclockwise from the x axis.
+    SweepGradient { color_line: ColorLine, center: (f32, f32), start_angle: f32, end_angle: f32 },
+    /// `paint` clipped to the outline of `glyph`.
+    Glyph { glyph: GlyphId, paint: PaintRef<'fnt> },
+    /// The paint graph of another base glyph.
+    ColrGlyph { glyph: GlyphId },
+    /// `paint` with its coordinates mapped by `transform`.
+    Transform { transform: Transform, paint: PaintRef<'fnt> },
+    Composite { source: PaintRef<'fnt>, mode: CompositeMode, backdrop: PaintRef<'fnt> },
+}
+
+/// A child of a paint, which is decoded on request, so that a graph is
+/// only read as far as it is drawn.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PaintRef<'fnt>(&'fnt [u8]);
+
+impl<'fnt> PaintRef<'fnt> {
+    fn at(buffer: &'fnt [u8], offset: usize) -> Result<PaintRef<'fnt>> {
+        buffer.get(offset..).map(PaintRef).ok_or(Error::UnexpectedEof)
+    }
+
+    pub fn get(&self) -> Result<Paint<'fnt>> {
+        Paint::decode(self.0)
+    }
+}
+
+/// The stops of a gradient and how it continues past them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorLine {
+    pub extend: Extend,
+    pub stops: Vec<ColorStop>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ColorStop {
+    pub offset: f32,
+    pub palette_index: u16,
+    pub alpha: f32,
+}
+
+/// How a gradient continues before its first stop and after its last.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Extend {
+    Pad,
+    Repeat,
+    Reflect,
+}
+
+/// How `Paint::Composite` combines its source with its backdrop.  The
+/// modes are those of the W3C compositing and blending specification.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CompositeMode {
+    Clear,
+    Source,
+    Destination,
+    SourceOver,
+    DestinationOver,
+    SourceIn,
+    DestinationIn,
+    SourceOut,
+    DestinationOut,
+    SourceAtop,
+    DestinationAtop,
+    Xor,
+    Plus,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Multiply,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
+
+impl<'fnt> Decode<'fnt> for Paint<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Paint<'fnt>> {
+        required_len!(buffer, 1);
+        let format = buffer[0];
+        // Each variable format follows the one before it, with an index
+        // into the variation store appended.
+        let variable = format % 2 == 1 && format > 1 && format < 32;
+        let base = if variable { format - 1 } else { format };
+
+        let u16_at = |pos: usize| -> Result<u16> { u16::decode(buffer.get(pos..).ok_or(Error::UnexpectedEof)?) };
+        let fword = |pos: usize| -> Result<f32> { u16_at(pos).map(|value| f32::from(value as i16)) };
+        let f2dot14 = |pos: usize| -> Result<f32> {
+            F2Dot14::decode(buffer.get(pos..).ok_or(Error::UnexpectedEof)?).map(f32::from)
+        };
+        let point = |pos: usize| -> Result<(f32, f32)> { Ok((fword(pos)?, fword(pos + 2)?)) };
+        let offset = |pos: usize| -> Result<PaintRef<'fnt>> {
+            let offset = u32::from(Uint24::decode(buffer.get(pos..).ok_or(Error::UnexpectedEof)?)?);
+            PaintRef::at(buffer, offset as usize)
+        };
+        let color_line = || -> Result<ColorLine> { ColorLine::decode(offset(1)?.0, variable) };
+        let child = || offset(1);
+        // The transform about `center` which applies `transform` about
+        // the origin.
+        let around = |transform: Transform, center: (f32, f32)| {
+            Transform::translate(-center.0, -center.1).then(&transform).then(&Transform::translate(center.0, center.1))
+        };
+
+        Ok(match base {
+            1 => {
+                required_len!(buffer, 6);
+                Paint::Layers { num_layers: buffer[1], first_layer: u32::decode(&buffer[2..])? }
+            },
+            2 => Paint::Solid { palette_index: u16_at(1)?, alpha: f2dot14(3)? },
+            4 => Paint::LinearGradient { color_line: color_line()?, p0: point(4)?, p1: point(8)?, p2: point(12)? },
+            6 => Paint::RadialGradient {
+                color_line: color_line()?,
+                c0: point(4)?,
+                r0: f32::from(u16_at(8)?),
+                c1: point(10)?,
+                r1: f32::from(u16_at(14)?),
+            },
+            8 => Paint::SweepGradient {
+                color_line: color_line()?,
+                center: point(4)?,
+                start_angle: f2dot14(8)? * 180.0,
+                end_angle: f2dot14(10)? * 180.0,
+            },
+            10 => Paint::Glyph { paint: child()?, glyph: GlyphId(u16_at(4)?) },
+            11 => Paint::ColrGlyph { glyph: GlyphId(u16_at(1)?) },
+            12 => {
+                let affine = offset(4)?.0;
+                let fixed = |index: usize| -> Result<f32> {
+                    Fixed::decode(affine.get(index * 4..).ok_or(Error::UnexpectedEof)?).map(f32::from)
+                };
+                let transform = Transform {
+                    xx: fixed(0)?, yx: fixed(1)?, xy: fixed(2)?, yy: fixed(3)?, dx: fixed(4)?, dy: fixed(5)?,
+                };
+                Paint::Transform { transform, paint: child()? }
+            },
+            14 => Paint::Transform { transform: Transform::translate(fword(4)?, fword(6)?), paint: child()? },
+            16 => Paint::Transform { transform: Transform::scale(f2dot14(4)?, f2dot14(6)?), paint: child()? },
+            18 => Paint::Transform {
+                transform: around(Transform::scale(f2dot14(4)?, f2dot14(6)?), point(8)?),
+                paint: child()?,
+            },
+            20 => Paint::Transform { transform: Transform::scale(f2dot14(4)?, f2dot14(4)?), paint: child()? },
+            22 => Paint::Transform {
+                transform: around(Transform::scale(f2dot14(4)?, f2dot14(4)?), point(6)?),
+                paint: child()?,
+            },
+            24 => Paint::Transform { transform: rotation(f2dot14(4)?), paint: child()? },
+            26 => Paint::Transform { transform: around(rotation(f2dot14(4)?), point(6)?), paint: child()? },
+            28 => Paint::Transform { transform: skew(f2dot14(4)?, f2dot14(6)?), paint: child()? },
+            30 => Paint::Transform { transform: around(skew(f2dot14(4)?, f2dot14(6)?), point(8)?), paint: child()? },
+            32 => {
+                required_len!(buffer, 8);
+                Paint::Composite { source: offset(1)?, mode: CompositeMode::from_u8(buffer[4])?, backdrop: offset(5)? }
+            },
+            _ => return Err(Error::InvalidData),
+        })
+    }
+}
+
+/// A rotation counterclockwise by `angle` half turns.
+fn rotation(angle: f32) -> Transform {
+    Transform::rotate(angle * ::std::f32::consts::PI)
+}
+
+/// A skew by angles in half turns, where a positive `x_angle` leans
+/// vertical lines to the left and a positive `y_angle` tilts horizontal
+/// lines up.
+fn skew(x_angle: f32, y_angle: f32) -> Transform {
+    let pi = ::std::f32::consts::PI;
+    Transform::shear(-(x_angle * pi).tan(), (y_angle * pi).tan())
+}
+
+impl ColorLine {
+    fn decode(buffer: &[u8], variable: bool) -> Result<ColorLine> {
+        required_len!(buffer, 3);
+        let extend = match buffer[0] {
+            1 => Extend::Repeat,
+            2 => Extend::Reflect,
+            // Unknown modes are read as the default.
+            _ => Extend::Pad,
+        };
+        let count = u16::decode(&buffer[1..])? as usize;
+        let size = if variable { 10 } else { 6 };
+        let stops = (0..count)
+            .map(|index| {
+                let stop = buffer.get(3 + index * size..).ok_or(Error::UnexpectedEof)?;
+                required_len!(stop, 6);
+                Ok(ColorStop {
+                    offset: f32::from(F2Dot14::decode(stop)?),
+                    palette_index: u16::decode(&stop[2..])?,
+                    alpha: f32::from(F2Dot14::decode(&stop[4..])?),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(ColorLine { extend, stops })
+    }
+}
+
+impl CompositeMode {
+    fn from_u8(mode: u8) -> Result<CompositeMode> {
+        use self::CompositeMode::*;
+        const MODES: [CompositeMode; 28] = [
+            Clear, Source, Destination, SourceOver, DestinationOver, SourceIn, DestinationIn, SourceOut,
+            DestinationOut, SourceAtop, DestinationAtop, Xor, Plus, Screen, Overlay, Darken, Lighten,
+            ColorDodge, ColorBurn, HardLight, SoftLight, Difference, Exclusion, Multiply, Hue, Saturation,
+            Color, Luminosity,
+        ];
+        MODES.get(mode as usize).cloned().ok_or(Error::InvalidData)
+    }
+}
+
+fn array_at<'fnt, T: StaticEncodeSize>(buffer: &'fnt [u8], offset: u32, count: u16) -> Result<Array<'fnt, T>> {
+    let data = buffer.get(offset as usize..).ok_or(Error::UnexpectedEof)?;
+    Array::decode(data, count as usize)
+}
+
+/// Binary search an array of records sorted by glyph id.
+fn search<'fnt, T, F>(records: Array<'fnt, T>, glyph: GlyphId, key: F) -> Option<T>
+where
+    T: Decode<'fnt> + StaticEncodeSize,
+    F: Fn(&T) -> GlyphId,
+{
+    let (mut low, mut high) = (0, records.len());
+    while low < high {
+        let mid = (low + high) / 2;
+        let record = records.get(mid)?;
+        match key(&record).cmp(&glyph) {
+            ::std::cmp::Ordering::Less => low = mid + 1,
+            ::std::cmp::Ordering::Greater => high = mid,
+            ::std::cmp::Ordering::Equal => return Some(record),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_fonts;
+
+    #[test]
+    fn layers() {
+        let data = test_fonts::colr_v0(&[(2, &[(1, 0), (2, 1)]), (4, &[(3, 2)])]);
+        let colr = Colr::decode(&data).unwrap();
+        assert_eq!(colr.base_glyph(GlyphId(4)), Some(BaseGlyphRecord { glyph_id: GlyphId(4), first_layer_index: 2, num_layers: 1 }));
+        assert_eq!(colr.layers().get(2), Some(LayerRecord { glyph_id: GlyphId(3), palette_index: 2 }));
+        assert_eq!((colr.base_glyph(GlyphId(3)), colr.paint(GlyphId(2))), (None, Ok(None)));
+    }
+
+    #[test]
+    fn paint_graph() {
+        let gradient = test_fonts::paint_linear_gradient([(100, 0), (600, 0), (100, 100)], &[(0.0, 0), (1.0, 1)]);
+        let data = test_fonts::colr_v1(
+            &[(3, test_fonts::paint_layers(0, 2)), (4, test_fonts::paint_solid(0, 0.5))],
+            &[test_fonts::paint_glyph(1, gradient), test_fonts::paint_glyph(2, test_fonts::paint_solid(1, 1.0))],
+        );
+        let colr = Colr::decode(&data).unwrap();
+        assert_eq!(colr.base_glyph_paints().len(), 2);
+        assert_eq!(colr.paint(GlyphId(3)), Ok(Some(Paint::Layers { first_layer: 0, num_layers: 2 })));
+        assert_eq!(colr.paint(GlyphId(4)), Ok(Some(Paint::Solid { palette_index: 0, alpha: 0.5 })));
+        assert_eq!(colr.paint(GlyphId(1)), Ok(None));
+
+        let child = match colr.layer_paint(0).unwrap() {
+            Paint::Glyph { glyph: GlyphId(1), paint } => paint.get().unwrap(),
+            other => panic!("unexpected paint {:?}", other),
+        };
+        let stops = vec![
+            ColorStop { offset: 0.0, palette_index: 0, alpha: 1.0 },
+            ColorStop { offset: 1.0, palette_index: 1, alpha: 1.0 },
+        ];
+        assert_eq!(child, Paint::LinearGradient {
+            color_line: ColorLine { extend: Extend::Pad, stops },
+            p0: (100.0, 0.0),
+            p1: (600.0, 0.0),
+            p2: (100.0, 100.0),
+        });
+        assert!(colr.layer_paint(1).is_ok());
+        assert_eq!(colr.layer_paint(2), Err(Error::InvalidData));
+
+        // A rotation about a point keeps the point in place.
+        let paint = Paint::decode(&[26, 0, 0, 10, 0x20, 0, 0, 100, 0, 50, 2, 0, 1, 0, 0]).unwrap();
+        match paint {
+            Paint::Transform { transform, paint } => {
+                let (x, y) = transform.apply(100.0, 50.0);
+                assert!((x - 100.0).abs() < 1e-3 && (y - 50.0).abs() < 1e-3);
+                let (x, y) = transform.apply(200.0, 50.0);
+                assert!((x - 100.0).abs() < 1e-3 && (y - 150.0).abs() < 1e-3);
+                assert_eq!(paint.get(), Ok(Paint::Solid { palette_index: 1, alpha: 0.0 }));
+            },
+            other => panic!("unexpected paint {:?}", other),
+        }
+        assert_eq!(Paint::decode(&[33, 0, 0, 0, 0, 0, 0, 0]), Err(Error::InvalidData));
+    }
+}
@@ -0,0 +1,86 @@
+use decode::Decode;
+use error::{Error, Result};
+
+/// The color palette table, which holds the palettes `COLR` paints with.
+/// Each palette has the same number of entries, and palettes share color
+/// records where their entries coincide.
+#[derive(Debug, Copy, Clone)]
+pub struct Cpal<'fnt> {
+    pub version: u16,
+    num_palette_entries: u16,
+    num_palettes: u16,
+    color_records: &'fnt [u8],
+    first_color_indices: &'fnt [u8],
+}
+
+/// A color in sRGB, with straight alpha.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+impl Color {
+    pub const BLACK: Color = Color { red: 0, green: 0, blue: 0, alpha: 255 };
+}
+
+impl<'fnt> Decode<'fnt> for Cpal<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Cpal<'fnt>> {
+        required_len!(buffer, 12);
+        let version = u16::decode(buffer)?;
+        let num_palette_entries = u16::decode(&buffer[2..])?;
+        let num_palettes = u16::decode(&buffer[4..])?;
+        let num_color_records = u16::decode(&buffer[6..])? as usize;
+        let records_offset = u32::decode(&buffer[8..])? as usize;
+
+        let color_records = buffer.get(records_offset..records_offset + num_color_records * 4)
+            .ok_or(Error::UnexpectedEof)?;
+        let first_color_indices = buffer.get(12..12 + num_palettes as usize * 2).ok_or(Error::UnexpectedEof)?;
+
+        Ok(Cpal { version, num_palette_entries, num_palettes, color_records, first_color_indices })
+    }
+}
+
+impl<'fnt> Cpal<'fnt> {
+    pub fn num_palettes(&self) -> u16 {
+        self.num_palettes
+    }
+
+    pub fn num_palette_entries(&self) -> u16 {
+        self.num_palette_entries
+    }
+
+    /// The color of `entry` in `palette`, or `None` if the table has no
+    /// such palette or entry.
+    pub fn color(&self, palette: u16, entry: u16) -> Option<Color> {
+        if palette >= self.num_palettes || entry >= self.num_palette_entries {
+            return None
+        }
+
+        let first = u16::decode(&self.first_color_indices[palette as usize * 2..]).ok()? as usize;
+        let record = self.color_records.get((first + entry as usize) * 4..)?.get(..4)?;
+        // Records are stored in BGRA order.
+        Some(Color { blue: record[0], green: record[1], red: record[2], alpha: record[3] })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_fonts;
+
+    #[test]
+    fn palettes() {
+        let data = test_fonts::cpal(&[&[[255, 0, 0, 255], [0, 0, 255, 128]], &[[0, 255, 0, 255], [0, 0, 255, 128]]]);
+        let cpal = Cpal::decode(&data).unwrap();
+        assert_eq!((cpal.num_palettes(), cpal.num_palette_entries()), (2, 2));
+        assert_eq!(cpal.color(0, 0), Some(Color { red: 255, green: 0, blue: 0, alpha: 255 }));
+        assert_eq!(cpal.color(1, 0), Some(Color { red: 0, green: 255, blue: 0, alpha: 255 }));
+        assert_eq!(cpal.color(1, 1), Some(Color { red: 0, green: 0, blue: 255, alpha: 128 }));
+        assert_eq!((cpal.color(2, 0), cpal.color(0, 2)), (None, None));
+
+        assert_eq!(Cpal::decode(&data[..10]).err(), Some(Error::UnexpectedEof));
+    }
+}
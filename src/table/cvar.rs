@@ -0,0 +1,59 @@
+use decode::Decode;
+use table::gvar::{self, TupleStore, TupleVariation};
+use table::variation::NormalizedCoord;
+use error::{Error, Result};
+
+/// The CVT variations table, which varies the values of the control value
+/// table of a variable TrueType font.
+#[derive(Debug, Copy, Clone)]
+pub struct Cvar<'fnt> {
+    buffer: &'fnt [u8],
+}
+
+impl<'fnt> Decode<'fnt> for Cvar<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Cvar<'fnt>> {
+        required_len!(buffer, 8);
+        let version = u16::decode(buffer)?;
+        if version != 1 {
+            return Err(Error::UnsupportedVersion(version.into()))
+        }
+
+        Ok(Cvar { buffer })
+    }
+}
+
+impl<'fnt> Cvar<'fnt> {
+    /// Decode every tuple variation, for a font with `axis_count` axes
+    /// and `cvt_count` control values.  Each variation has only
+    /// `x_deltas`, one for each control value it varies.
+    pub fn tuple_variations(&self, axis_count: usize, cvt_count: usize) -> Result<Vec<TupleVariation>> {
+        gvar::decode_tuple_variations(self.buffer, 4, TupleStore {
+            axis_count,
+            // Every peak tuple is embedded in its header.
+            shared_tuples: &[],
+            shared_tuple_count: 0,
+            point_count: cvt_count,
+            dimensions: 1,
+        })
+    }
+
+    /// The control values `cvt` at the normalized `coords`.  Unlike
+    /// `gvar`, values without an explicit delta are not inferred.
+    pub fn apply(&self, cvt: &[i16], coords: &[NormalizedCoord]) -> Result<Vec<f32>> {
+        let mut values = cvt.iter().map(|&value| value as f32).collect::<Vec<_>>();
+        for variation in self.tuple_variations(coords.len(), cvt.len())? {
+            let scalar = variation.scalar(coords);
+            if scalar == 0.0 {
+                continue
+            }
+
+            for (index, value) in values.iter_mut().enumerate() {
+                if let Some((delta, _)) = variation.delta(index) {
+                    *value += delta as f32 * scalar;
+                }
+            }
+        }
+
+        Ok(values)
+    }
+}
@@ -0,0 +1,107 @@
+use decode::{Decode, Decode1, StaticEncodeSize, EncodeSize};
+use primitives::{Array, Discarded, FixedVersion};
+use error::Result;
+use std::marker::PhantomData;
+
+/// The embedded bitmap scaling table, which names the strikes that should
+/// be scaled to produce sizes the font has no bitmaps for.
+#[derive(Decode, Debug, Copy, Clone)]
+#[allow(dead_code)]
+pub struct Ebsc<'fnt> {
+    pub version: FixedVersion,
+    num_sizes: Discarded<u32>,
+    #[WithParam = "num_sizes as usize"]
+    scales: Array<'fnt, BitmapScale>,
+}
+
+/// The line metrics of a bitmap strike in one direction.
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SbitLineMetrics {
+    pub ascender: i8,
+    pub descender: i8,
+    pub width_max: u8,
+    pub caret_slope_numerator: i8,
+    pub caret_slope_denominator: i8,
+    pub caret_offset: i8,
+    pub min_origin_sb: i8,
+    pub min_advance_sb: i8,
+    pub max_before_bl: i8,
+    pub min_after_bl: i8,
+    pub pad1: i8,
+    pub pad2: i8,
+}
+
+/// A size which is drawn by scaling the strike at the substitute size.
+/// The line metrics are those of the scaled size.
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BitmapScale {
+    pub hori: SbitLineMetrics,
+    pub vert: SbitLineMetrics,
+    pub ppem_x: u8,
+    pub ppem_y: u8,
+    pub substitute_ppem_x: u8,
+    pub substitute_ppem_y: u8,
+}
+
+/// The strike chosen to draw a particular size.  Indices refer to the
+/// slice of available strike sizes given to `Ebsc::select_strike`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StrikeSelection {
+    Exact(usize),
+    Scaled { strike: usize, scale: BitmapScale },
+}
+
+impl<'fnt> Ebsc<'fnt> {
+    pub fn scales(&self) -> Array<'fnt, BitmapScale> {
+        self.scales
+    }
+
+    /// The scaling record for the size `ppem_x` by `ppem_y`, if any.
+    pub fn scale(&self, ppem_x: u8, ppem_y: u8) -> Option<BitmapScale> {
+        self.scales.into_iter().find(|scale| scale.ppem_x == ppem_x && scale.ppem_y == ppem_y)
+    }
+
+    /// Choose a strike from the `(ppem_x, ppem_y)` sizes present in the
+    /// bitmap location table.  An exact strike is always preferred, and
+    /// otherwise the substitute strike named by this table is used.
+    pub fn select_strike(&self, ppem_x: u8, ppem_y: u8, strikes: &[(u8, u8)]) -> Option<StrikeSelection> {
+        if let Some(index) = strikes.iter().position(|&size| size == (ppem_x, ppem_y)) {
+            return Some(StrikeSelection::Exact(index))
+        }
+
+        let scale = self.scale(ppem_x, ppem_y)?;
+        let substitute = (scale.substitute_ppem_x, scale.substitute_ppem_y);
+        strikes.iter()
+            .position(|&size| size == substitute)
+            .map(|strike| StrikeSelection::Scaled { strike, scale })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select_strike() {
+        let mut data = vec![0, 2, 0, 0, 0, 0, 0, 1];
+        data.extend(&[10, 0xfe, 12, 1, 0, 0, 0, 12, 10, 0xfe, 0, 0]);
+        data.extend(&[0; 12]);
+        data.extend(&[24, 24, 12, 12]);
+
+        let ebsc = Ebsc::decode(&data).expect("failed to read EBSC");
+        assert_eq!(ebsc.version, FixedVersion { major: 2, minor: 0 });
+        assert_eq!(ebsc.scales().len(), 1);
+        assert_eq!(ebsc.scale(24, 24).map(|scale| scale.hori.descender), Some(-2));
+
+        let strikes = [(16, 16), (12, 12)];
+        assert_eq!(ebsc.select_strike(16, 16, &strikes), Some(StrikeSelection::Exact(0)));
+        assert_eq!(ebsc.select_strike(20, 20, &strikes), None);
+        match ebsc.select_strike(24, 24, &strikes) {
+            Some(StrikeSelection::Scaled { strike, scale }) => {
+                assert_eq!(strike, 1);
+                assert_eq!(scale.hori.ascender, 10);
+            },
+            other => panic!("unexpected selection {:?}", other),
+        }
+    }
+}
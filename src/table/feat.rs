@@ -0,0 +1,129 @@
+use decode::{Decode, Decode1, StaticEncodeSize, EncodeSize};
+use primitives::{Array, Fixed, Tag};
+use error::{Error, Result};
+
+/// The AAT feature name table, which lists the layout features a font
+/// supports as numbered feature types and settings.
+#[derive(Debug, Copy, Clone)]
+pub struct Feat<'fnt> {
+    buffer: &'fnt [u8],
+    pub version: Fixed,
+    features: Array<'fnt, FeatureName>,
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FeatureName {
+    pub feature: u16,
+    pub n_settings: u16,
+    pub setting_table: u32,
+    pub feature_flags: u16,
+    pub name_index: i16,
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FeatureSetting {
+    pub setting: u16,
+    pub name_index: i16,
+}
+
+impl<'fnt> Decode<'fnt> for Feat<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Feat<'fnt>> {
+        required_len!(buffer, 12);
+        let version = Fixed::decode(buffer)?;
+        let feature_name_count = u16::decode(&buffer[4..])? as usize;
+        let features = Array::decode(&buffer[12..], feature_name_count)?;
+
+        Ok(Feat { buffer, version, features })
+    }
+}
+
+impl<'fnt> Feat<'fnt> {
+    pub fn features(&self) -> Array<'fnt, FeatureName> {
+        self.features
+    }
+
+    /// The settings of `feature`, which are stored after the feature names.
+    pub fn settings(&self, feature: &FeatureName) -> Result<Array<'fnt, FeatureSetting>> {
+        let data = self.buffer.get(feature.setting_table as usize..).ok_or(Error::UnexpectedEof)?;
+        Array::decode(data, feature.n_settings as usize)
+    }
+
+    /// The OpenType feature tags equivalent to the settings of this table.
+    /// Settings without an OpenType equivalent are skipped.
+    pub fn opentype_features(&self) -> Result<Vec<Tag>> {
+        let mut tags = Vec::new();
+        for feature in self.features {
+            for setting in self.settings(&feature)? {
+                if let Some(tag) = opentype_feature(feature.feature, setting.setting) {
+                    tags.push(tag);
+                }
+            }
+        }
+
+        Ok(tags)
+    }
+}
+
+/// The OpenType feature which corresponds to an AAT feature type and
+/// selector, for the selectors which enable a feature.
+pub fn opentype_feature(feature: u16, selector: u16) -> Option<Tag> {
+    let tag = match (feature, selector) {
+        // Ligatures
+        (1, 0) => b"rlig",
+        (1, 2) => b"liga",
+        (1, 4) => b"dlig",
+        (1, 8) => b"hlig",
+        (1, 18) => b"clig",
+        // Vertical substitution
+        (4, 0) => b"vert",
+        // Number spacing
+        (6, 0) => b"tnum",
+        (6, 1) => b"pnum",
+        // Vertical position
+        (10, 1) => b"sups",
+        (10, 2) => b"subs",
+        (10, 3) => b"ordn",
+        (10, 4) => b"sinf",
+        // Fractions
+        (11, 1) => b"afrc",
+        (11, 2) => b"frac",
+        // Typographic extras: slashed zero
+        (14, 4) => b"zero",
+        // Number case
+        (21, 0) => b"onum",
+        (21, 1) => b"lnum",
+        // Text spacing
+        (22, 0) => b"pwid",
+        (22, 1) => b"fwid",
+        (22, 2) => b"hwid",
+        (22, 3) => b"twid",
+        (22, 4) => b"qwid",
+        // Ruby kana
+        (28, 2) => b"ruby",
+        // CJK italic roman
+        (32, 2) => b"ital",
+        // Case sensitive layout
+        (33, 0) => b"case",
+        (33, 2) => b"cpsp",
+        // Alternate kana
+        (34, 0) => b"hkna",
+        (34, 2) => b"vkna",
+        // Stylistic alternatives, where each set has an on and off selector.
+        (35, selector) if (2..=40).contains(&selector) && selector % 2 == 0 => {
+            let set = selector / 2;
+            return Some(Tag([b's', b's', b'0' + (set / 10) as u8, b'0' + (set % 10) as u8]))
+        },
+        // Contextual alternates
+        (36, 0) => b"calt",
+        (36, 2) => b"swsh",
+        (36, 4) => b"cswh",
+        // Lower and upper case
+        (37, 1) => b"smcp",
+        (37, 2) => b"pcap",
+        (38, 1) => b"c2sc",
+        (38, 2) => b"c2pc",
+        _ => return None,
+    };
+
+    Some(Tag(*tag))
+}
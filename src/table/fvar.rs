@@ -0,0 +1,101 @@
+use decode::{Decode, Decode1, StaticEncodeSize, EncodeSize};
+use primitives::{Array, Fixed, Tag};
+use table::variation::{NormalizedCoord, UserCoord};
+use error::{Error, Result};
+
+/// The font variations table, which describes the variation axes and
+/// named instances of a variable font.
+#[derive(Debug, Copy, Clone)]
+pub struct Fvar<'fnt> {
+    axes: Array<'fnt, VariationAxisRecord>,
+    instances: &'fnt [u8],
+    instance_count: usize,
+    instance_size: usize,
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq)]
+pub struct VariationAxisRecord {
+    pub axis_tag: Tag,
+    pub min_value: Fixed,
+    pub default_value: Fixed,
+    pub max_value: Fixed,
+    pub flags: u16,
+    pub axis_name_id: u16,
+}
+
+impl VariationAxisRecord {
+    /// Map a user-space coordinate on this axis into the normalized
+    /// `[-1, 1]` range, before any `avar` adjustment.
+    pub fn normalize(&self, value: UserCoord) -> NormalizedCoord {
+        let min = f32::from(self.min_value);
+        let default = f32::from(self.default_value);
+        let max = f32::from(self.max_value);
+        let value = value.to_f32().max(min).min(max);
+
+        NormalizedCoord::new(if value < default {
+            -(default - value) / (default - min)
+        } else if value > default {
+            (value - default) / (max - default)
+        } else {
+            0.0
+        })
+    }
+}
+
+/// A named instance, which is a predefined position in the design space.
+#[derive(Debug, Copy, Clone)]
+pub struct InstanceRecord<'fnt> {
+    pub subfamily_name_id: u16,
+    pub flags: u16,
+    pub coordinates: Array<'fnt, Fixed>,
+    /// Only present if the `instanceSize` leaves room for it.
+    pub post_script_name_id: Option<u16>,
+}
+
+impl<'fnt> Decode<'fnt> for Fvar<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Fvar<'fnt>> {
+        required_len!(buffer, 16);
+        let axes_offset = u16::decode(&buffer[4..])? as usize;
+        let axis_count = u16::decode(&buffer[8..])? as usize;
+        let axis_size = u16::decode(&buffer[10..])? as usize;
+        let instance_count = u16::decode(&buffer[12..])? as usize;
+        let instance_size = u16::decode(&buffer[14..])? as usize;
+
+        if axis_size != VariationAxisRecord::size() || instance_size < axis_count * 4 + 4 {
+            return Err(Error::InvalidData)
+        }
+
+        let axes_data = buffer.get(axes_offset..).ok_or(Error::UnexpectedEof)?;
+        let axes = Array::decode(axes_data, axis_count)?;
+        let instances = &axes_data[axes.encode_size()..];
+        required_len!(instances, instance_count * instance_size);
+
+        Ok(Fvar { axes, instances, instance_count, instance_size })
+    }
+}
+
+impl<'fnt> Fvar<'fnt> {
+    pub fn axes(&self) -> Array<'fnt, VariationAxisRecord> {
+        self.axes
+    }
+
+    pub fn instances(&self) -> impl Iterator<Item = InstanceRecord<'fnt>> + 'fnt {
+        let (data, size, axis_count) = (self.instances, self.instance_size, self.axes.len());
+        (0..self.instance_count).filter_map(move |i| {
+            let record = &data[i * size..(i + 1) * size];
+            let coordinates = Array::decode(&record[4..], axis_count).ok()?;
+            let post_script_name_id = if size >= axis_count * 4 + 6 {
+                u16::decode(&record[4 + axis_count * 4..]).ok()
+            } else {
+                None
+            };
+
+            Some(InstanceRecord {
+                subfamily_name_id: u16::decode(record).ok()?,
+                flags: u16::decode(&record[2..]).ok()?,
+                coordinates,
+                post_script_name_id,
+            })
+        })
+    }
+}
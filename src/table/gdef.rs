@@ -0,0 +1,113 @@
+use decode::Decode;
+use primitives::GlyphId;
+use table::layout::{ClassDef, Coverage};
+use error::{Error, Result};
+
+/// The glyph definition table, which classifies glyphs as bases,
+/// ligatures, marks and components, so that lookups can skip the glyphs
+/// their flags ignore.
+#[derive(Debug, Clone)]
+pub struct Gdef<'fnt> {
+    glyph_classes: Option<ClassDef<'fnt>>,
+    mark_attachment_classes: Option<ClassDef<'fnt>>,
+    mark_glyph_sets: Vec<Coverage<'fnt>>,
+}
+
+/// The class `GDEF` gives a glyph.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GlyphClass {
+    Base,
+    Ligature,
+    Mark,
+    /// A part of a ligature which is drawn as separate glyphs.
+    Component,
+}
+
+impl<'fnt> Decode<'fnt> for Gdef<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Gdef<'fnt>> {
+        required_len!(buffer, 12);
+        let (major, minor) = (u16::decode(buffer)?, u16::decode(&buffer[2..])?);
+        if major != 1 {
+            return Err(Error::UnsupportedVersion(u32::from(major) << 16 | u32::from(minor)))
+        }
+
+        let subtable = |pos: usize| -> Result<Option<&'fnt [u8]>> {
+            match u16::decode(buffer.get(pos..).ok_or(Error::UnexpectedEof)?)? as usize {
+                0 => Ok(None),
+                offset => buffer.get(offset..).ok_or(Error::UnexpectedEof).map(Some),
+            }
+        };
+        let class_def = |pos: usize| subtable(pos)?.map(ClassDef::decode).transpose();
+
+        // Mark glyph sets were added in version 1.2.
+        let mut mark_glyph_sets = Vec::new();
+        if let Some(sets) = if minor >= 2 { subtable(12)? } else { None } {
+            required_len!(sets, 4);
+            let count = u16::decode(&sets[2..])? as usize;
+            for index in 0..count {
+                let offset = u32::decode(sets.get(4 + index * 4..).ok_or(Error::UnexpectedEof)?)? as usize;
+                mark_glyph_sets.push(Coverage::decode(sets.get(offset..).ok_or(Error::UnexpectedEof)?)?);
+            }
+        }
+
+        Ok(Gdef {
+            glyph_classes: class_def(4)?,
+            mark_attachment_classes: class_def(10)?,
+            mark_glyph_sets,
+        })
+    }
+}
+
+impl<'fnt> Gdef<'fnt> {
+    /// The class of `glyph`, or `None` if the table does not classify it.
+    pub fn glyph_class(&self, glyph: GlyphId) -> Option<GlyphClass> {
+        match self.glyph_classes?.class(glyph) {
+            1 => Some(GlyphClass::Base),
+            2 => Some(GlyphClass::Ligature),
+            3 => Some(GlyphClass::Mark),
+            4 => Some(GlyphClass::Component),
+            _ => None,
+        }
+    }
+
+    /// The mark attachment class of `glyph`, or 0 if it has none.
+    pub fn mark_attachment_class(&self, glyph: GlyphId) -> u16 {
+        self.mark_attachment_classes.map_or(0, |classes| classes.class(glyph))
+    }
+
+    /// The number of mark glyph sets, which lookups refer to by index.
+    pub fn mark_glyph_set_count(&self) -> usize {
+        self.mark_glyph_sets.len()
+    }
+
+    /// Whether `glyph` is in the mark glyph set at `set`.  A set the table
+    /// does not have holds no glyphs.
+    pub fn is_in_mark_glyph_set(&self, set: u16, glyph: GlyphId) -> bool {
+        self.mark_glyph_sets.get(set as usize).is_some_and(|coverage| coverage.index(glyph).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_fonts;
+
+    #[test]
+    fn glyph_classes() {
+        let data = test_fonts::gdef(&[(1, 1), (2, 3), (3, 3), (4, 2)], &[(2, 1), (3, 2)], &[&[3]]);
+        let gdef = Gdef::decode(&data).unwrap();
+        assert_eq!(gdef.glyph_class(GlyphId(1)), Some(GlyphClass::Base));
+        assert_eq!(gdef.glyph_class(GlyphId(3)), Some(GlyphClass::Mark));
+        assert_eq!(gdef.glyph_class(GlyphId(4)), Some(GlyphClass::Ligature));
+        assert_eq!(gdef.glyph_class(GlyphId(5)), None);
+        assert_eq!((gdef.mark_attachment_class(GlyphId(2)), gdef.mark_attachment_class(GlyphId(3))), (1, 2));
+        assert_eq!(gdef.mark_attachment_class(GlyphId(1)), 0);
+        assert_eq!(gdef.mark_glyph_set_count(), 1);
+        assert!(gdef.is_in_mark_glyph_set(0, GlyphId(3)));
+        assert!(!gdef.is_in_mark_glyph_set(0, GlyphId(2)));
+        assert!(!gdef.is_in_mark_glyph_set(1, GlyphId(3)));
+
+        assert_eq!(Gdef::decode(&[0, 2, 0, 0]).err(), Some(Error::UnexpectedEof));
+        assert_eq!(Gdef::decode(&[0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]).err(), Some(Error::UnsupportedVersion(0x0002_0000)));
+    }
+}
@@ -0,0 +1,695 @@
+use decode::{Decode, DecodeRead, StaticEncodeSize, EncodeSize};
+use primitives::{F2Dot14, GlyphId};
+use table::loca::Loca;
+use error::{Error, Result};
+use outline::{FillRule, OutlineSink, WindingSink};
+use font::Font;
+
+/// The glyph data table, which contains TrueType outlines.  Glyphs are
+/// located through the `loca` table.
+#[derive(Debug, Copy, Clone)]
+pub struct Glyf<'fnt>(&'fnt [u8]);
+
+impl<'fnt> Decode<'fnt> for Glyf<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Glyf<'fnt>> {
+        Ok(Glyf(buffer))
+    }
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq)]
+pub struct GlyphHeader {
+    /// Negative for composite glyphs.
+    pub number_of_contours: i16,
+    pub x_min: i16,
+    pub y_min: i16,
+    pub x_max: i16,
+    pub y_max: i16,
+}
+
+pub mod component_flags {
+    pub const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+    pub const ARGS_ARE_XY_VALUES: u16 = 0x0002;
+    pub const ROUND_XY_TO_GRID: u16 = 0x0004;
+    pub const WE_HAVE_A_SCALE: u16 = 0x0008;
+    pub const MORE_COMPONENTS: u16 = 0x0020;
+    pub const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+    pub const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+    pub const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+    pub const USE_MY_METRICS: u16 = 0x0200;
+    pub const OVERLAP_COMPOUND: u16 = 0x0400;
+    pub const SCALED_COMPONENT_OFFSET: u16 = 0x0800;
+    pub const UNSCALED_COMPONENT_OFFSET: u16 = 0x1000;
+}
+
+impl<'fnt> Glyf<'fnt> {
+    /// The raw data of `glyph`, which is empty for glyphs without an outline.
+    pub fn glyph_data(&self, loca: &Loca, glyph: GlyphId) -> Result<&'fnt [u8]> {
+        let index = glyph.0 as usize;
+        let start = loca.offset(index).ok_or(Error::InvalidGlyphId)?;
+        let end = loca.offset(index + 1).ok_or(Error::InvalidGlyphId)?;
+        if start > end {
+            return Err(Error::InvalidData)
+        }
+
+        self.0.get(start..end).ok_or(Error::UnexpectedEof)
+    }
+}
+
+/// The header of a glyph, or `None` for an empty glyph.
+pub fn glyph_header(data: &[u8]) -> Result<Option<GlyphHeader>> {
+    if data.is_empty() {
+        return Ok(None)
+    }
+
+    GlyphHeader::decode(data).map(Some)
+}
+
+/// The number of points in a glyph, as counted by `gvar`: the number of
+/// outline points of a simple glyph, or the number of components of a
+/// composite glyph.  Phantom points are not included.
+pub fn num_points(data: &[u8]) -> Result<usize> {
+    let header = match glyph_header(data)? {
+        Some(header) => header,
+        None => return Ok(0),
+    };
+
+    let body = &data[GlyphHeader::size()..];
+    if header.number_of_contours >= 0 {
+        let contours = header.number_of_contours as usize;
+        if contours == 0 {
+            return Ok(0)
+        }
+
+        let last = body.get((contours - 1) * 2..).ok_or(Error::UnexpectedEof)?;
+        return Ok(u16::decode(last)? as usize + 1)
+    }
+
+    component_glyph_offsets(data).map(|offsets| offsets.len())
+}
+
+/// The offsets within the glyph data of the `glyphIndex` field of each
+/// component of a composite glyph, for rewriting glyph ids in place.
+pub fn component_glyph_offsets(data: &[u8]) -> Result<Vec<usize>> {
+    let (offsets, _) = component_records(data)?;
+    Ok(offsets.into_iter().map(|pos| pos + 2).collect())
+}
+
+/// The offsets of the `flags` field of each component of a composite
+/// glyph, and the offset of the end of the last component.
+fn component_records(data: &[u8]) -> Result<(Vec<usize>, usize)> {
+    use self::component_flags::*;
+
+    match glyph_header(data)? {
+        Some(ref header) if header.number_of_contours < 0 => (),
+        _ => return Err(Error::InvalidData),
+    }
+
+    let mut offsets = Vec::new();
+    let mut pos = GlyphHeader::size();
+    loop {
+        let flags = u16::decode(data.get(pos..).ok_or(Error::UnexpectedEof)?)?;
+        let mut len = 4 + if flags & ARG_1_AND_2_ARE_WORDS != 0 { 4 } else { 2 };
+        if flags & WE_HAVE_A_SCALE != 0 {
+            len += 2;
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            len += 4;
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            len += 8;
+        }
+
+        required_len!(data, pos + len);
+        offsets.push(pos);
+        pos += len;
+        if flags & MORE_COMPONENTS == 0 {
+            return Ok((offsets, pos))
+        }
+    }
+}
+
+/// The offset of the instruction length field of a glyph, or `None` if
+/// it has none, as an empty glyph or a composite glyph may not.
+fn instructions_offset(data: &[u8]) -> Result<Option<usize>> {
+    use self::component_flags::*;
+
+    let header = match glyph_header(data)? {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+    if header.number_of_contours >= 0 {
+        return Ok(Some(GlyphHeader::size() + header.number_of_contours as usize * 2))
+    }
+
+    let (components, end) = component_records(data)?;
+    for pos in components {
+        if u16::decode(&data[pos..])? & WE_HAVE_INSTRUCTIONS != 0 {
+            return Ok(Some(end))
+        }
+    }
+    Ok(None)
+}
+
+/// The TrueType instructions of a glyph.
+pub fn instructions(data: &[u8]) -> Result<&[u8]> {
+    match instructions_offset(data)? {
+        Some(pos) => {
+            let len = u16::decode(data.get(pos..).ok_or(Error::UnexpectedEof)?)? as usize;
+            data.get(pos + 2..pos + 2 + len).ok_or(Error::UnexpectedEof)
+        }
+        None => Ok(&[]),
+    }
+}
+
+/// The glyph data `data` without its TrueType instructions, for a glyph
+/// moved into a font whose `fpgm`, `prep` and `cvt ` they were not
+/// written for.
+pub fn strip_instructions(data: &[u8]) -> Result<Vec<u8>> {
+    use self::component_flags::*;
+
+    let pos = match instructions_offset(data)? {
+        Some(pos) => pos,
+        None => return Ok(data.to_vec()),
+    };
+    let end = pos + 2 + instructions(data)?.len();
+
+    let mut out = data[..pos].to_vec();
+    if glyph_header(data)?.is_some_and(|header| header.number_of_contours >= 0) {
+        out.extend_from_slice(&[0, 0]);
+        out.extend_from_slice(&data[end..]);
+    } else {
+        // A composite glyph ends with its instructions, which a component
+        // flag announces.
+        for component in component_records(data)?.0 {
+            let flags = u16::decode(&out[component..])? & !WE_HAVE_INSTRUCTIONS;
+            out[component..component + 2].copy_from_slice(&flags.to_be_bytes());
+        }
+    }
+
+    Ok(out)
+}
+
+pub mod point_flags {
+    pub const ON_CURVE_POINT: u8 = 0x01;
+    pub const X_SHORT_VECTOR: u8 = 0x02;
+    pub const Y_SHORT_VECTOR: u8 = 0x04;
+    pub const REPEAT_FLAG: u8 = 0x08;
+    pub const X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR: u8 = 0x10;
+    pub const Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR: u8 = 0x20;
+}
+
+/// Composite glyphs may nest, but deeper nesting than this is treated as
+/// a cycle.
+const MAX_COMPONENT_DEPTH: usize = 16;
+
+/// A point of a glyph outline.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+    pub on_curve: bool,
+}
+
+/// The contours of a glyph, as quadratic B-spline points.  Composite
+/// glyphs are resolved into the points of their transformed components.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimpleGlyph {
+    /// The index of the last point of each contour.
+    pub end_points: Vec<usize>,
+    pub points: Vec<Point>,
+}
+
+impl SimpleGlyph {
+    /// Decode the outline of a simple glyph.
+    pub fn decode(data: &[u8]) -> Result<SimpleGlyph> {
+        use self::point_flags::*;
+
+        let header = match glyph_header(data)? {
+            Some(header) => header,
+            None => return Ok(SimpleGlyph::default()),
+        };
+        if header.number_of_contours < 0 {
+            return Err(Error::InvalidData)
+        }
+
+        let mut buf = &data[GlyphHeader::size()..];
+        let mut end_points = Vec::with_capacity(header.number_of_contours as usize);
+        for _ in 0..header.number_of_contours {
+            let end = buf.decode_read::<u16>()? as usize;
+            if end_points.last().is_some_and(|&last| end <= last) {
+                return Err(Error::InvalidData)
+            }
+            end_points.push(end);
+        }
+
+        let num_points = end_points.last().map_or(0, |&end| end + 1);
+        let instruction_length = buf.decode_read::<u16>()? as usize;
+        buf = buf.get(instruction_length..).ok_or(Error::UnexpectedEof)?;
+
+        let mut flags = Vec::with_capacity(num_points);
+        while flags.len() < num_points {
+            let flag = buf.decode_read::<u8>()?;
+            let repeat = if flag & REPEAT_FLAG != 0 { buf.decode_read::<u8>()? } else { 0 };
+            for _ in 0..=repeat {
+                flags.push(flag);
+            }
+        }
+        flags.truncate(num_points);
+
+        let xs = read_coordinates(&mut buf, &flags, X_SHORT_VECTOR, X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR)?;
+        let ys = read_coordinates(&mut buf, &flags, Y_SHORT_VECTOR, Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR)?;
+        let points = flags.iter().zip(xs).zip(ys)
+            .map(|((&flag, x), y)| Point { x: x as f32, y: y as f32, on_curve: flag & ON_CURVE_POINT != 0 })
+            .collect();
+
+        Ok(SimpleGlyph { end_points, points })
+    }
+
+    /// The points of each contour.
+    pub fn contours(&self) -> impl Iterator<Item = &[Point]> + '_ {
+        let starts = ::std::iter::once(0).chain(self.end_points.iter().map(|&end| end + 1));
+        starts.zip(&self.end_points).map(move |(start, &end)| &self.points[start..=end])
+    }
+
+    /// Send the contours to `sink`.  Consecutive off-curve points have an
+    /// implied on-curve point halfway between them.
+    pub fn outline<S: OutlineSink>(&self, sink: &mut S) {
+        for contour in self.contours() {
+            outline_contour(contour, sink);
+        }
+    }
+}
+
+fn read_coordinates(buf: &mut &[u8], flags: &[u8], short: u8, same_or_positive: u8) -> Result<Vec<i16>> {
+    let mut value = 0i16;
+    let mut coordinates = Vec::with_capacity(flags.len());
+    for &flag in flags {
+        let delta = if flag & short != 0 {
+            let magnitude = buf.decode_read::<u8>()? as i16;
+            if flag & same_or_positive != 0 { magnitude } else { -magnitude }
+        } else if flag & same_or_positive != 0 {
+            0
+        } else {
+            buf.decode_read::<i16>()?
+        };
+
+        value = value.wrapping_add(delta);
+        coordinates.push(value);
+    }
+
+    Ok(coordinates)
+}
+
+fn outline_contour<S: OutlineSink>(contour: &[Point], sink: &mut S) {
+    let midpoint = |a: Point, b: Point| Point { x: (a.x + b.x) / 2.0, y: (a.y + b.y) / 2.0, on_curve: true };
+    let (first, last) = match (contour.first(), contour.last()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return,
+    };
+
+    // Start at an on-curve point, which may be implied when both ends of
+    // the contour are off-curve.
+    let (start, rest) = if first.on_curve {
+        (first, &contour[1..])
+    } else if last.on_curve {
+        (last, &contour[..contour.len() - 1])
+    } else {
+        (midpoint(first, last), contour)
+    };
+
+    sink.move_to(start.x, start.y);
+    let mut control: Option<Point> = None;
+    for &point in rest {
+        match (point.on_curve, control) {
+            (true, Some(c)) => sink.quad_to(c.x, c.y, point.x, point.y),
+            (true, None) => sink.line_to(point.x, point.y),
+            (false, Some(c)) => {
+                let mid = midpoint(c, point);
+                sink.quad_to(c.x, c.y, mid.x, mid.y);
+            },
+            (false, None) => (),
+        }
+        control = if point.on_curve { None } else { Some(point) };
+    }
+
+    match control {
+        Some(c) => sink.quad_to(c.x, c.y, start.x, start.y),
+        None => sink.line_to(start.x, start.y),
+    }
+    sink.close();
+}
+
+/// A component of a composite glyph.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Component {
+    pub flags: u16,
+    pub glyph: GlyphId,
+    pub arg1: i32,
+    pub arg2: i32,
+    /// The 2x2 transform `[xx, yx, xy, yy]` applied to the component.
+    pub transform: [f32; 4],
+}
+
+impl Component {
+    /// Whether the arguments are an offset, rather than the indices of a
+    /// parent point and a component point which should be aligned.
+    pub fn args_are_offset(&self) -> bool {
+        self.flags & component_flags::ARGS_ARE_XY_VALUES != 0
+    }
+
+    fn apply(&self, point: Point, dx: f32, dy: f32) -> Point {
+        let [xx, yx, xy, yy] = self.transform;
+        Point {
+            x: xx * point.x + xy * point.y + dx,
+            y: yx * point.x + yy * point.y + dy,
+            on_curve: point.on_curve,
+        }
+    }
+}
+
+/// The components of a composite glyph.
+pub fn components(data: &[u8]) -> Result<Vec<Component>> {
+    use self::component_flags::*;
+
+    let header = glyph_header(data)?.ok_or(Error::InvalidData)?;
+    if header.number_of_contours >= 0 {
+        return Err(Error::InvalidData)
+    }
+
+    let mut buf = &data[GlyphHeader::size()..];
+    let mut components = Vec::new();
+    loop {
+        let flags = buf.decode_read::<u16>()?;
+        let glyph = buf.decode_read::<GlyphId>()?;
+        let (arg1, arg2) = match (flags & ARG_1_AND_2_ARE_WORDS != 0, flags & ARGS_ARE_XY_VALUES != 0) {
+            (true, true) => (buf.decode_read::<i16>()? as i32, buf.decode_read::<i16>()? as i32),
+            (true, false) => (buf.decode_read::<u16>()? as i32, buf.decode_read::<u16>()? as i32),
+            (false, true) => (buf.decode_read::<i8>()? as i32, buf.decode_read::<i8>()? as i32),
+            (false, false) => (buf.decode_read::<u8>()? as i32, buf.decode_read::<u8>()? as i32),
+        };
+
+        let mut read_f2dot14 = || buf.decode_read::<F2Dot14>().map(f32::from);
+        let transform = if flags & WE_HAVE_A_SCALE != 0 {
+            let scale = read_f2dot14()?;
+            [scale, 0.0, 0.0, scale]
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            let x_scale = read_f2dot14()?;
+            [x_scale, 0.0, 0.0, read_f2dot14()?]
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            [read_f2dot14()?, read_f2dot14()?, read_f2dot14()?, read_f2dot14()?]
+        } else {
+            [1.0, 0.0, 0.0, 1.0]
+        };
+
+        components.push(Component { flags, glyph, arg1, arg2, transform });
+        if flags & MORE_COMPONENTS == 0 {
+            return Ok(components)
+        }
+    }
+}
+
+/// A single glyph of the `glyf` table, whose components, if it is a
+/// composite, are not yet resolved.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Glyph<'fnt> {
+    pub id: GlyphId,
+    data: &'fnt [u8],
+}
+
+impl<'fnt> Glyph<'fnt> {
+    pub fn data(&self) -> &'fnt [u8] {
+        self.data
+    }
+
+    /// The header of the glyph, or `None` if it is empty.
+    pub fn header(&self) -> Result<Option<GlyphHeader>> {
+        glyph_header(self.data)
+    }
+
+    pub fn is_composite(&self) -> Result<bool> {
+        Ok(self.header()?.is_some_and(|header| header.number_of_contours < 0))
+    }
+
+    /// The components of a composite glyph, or none for a simple glyph.
+    pub fn components(&self) -> Result<Vec<Component>> {
+        if self.is_composite()? { components(self.data) } else { Ok(Vec::new()) }
+    }
+
+    /// Resolve every component of the glyph into a single simple outline,
+    /// which can be exported where composites cannot be expressed.  The
+    /// transformed points are rounded to whole font units, and an on-curve
+    /// point which repeats the one before it in its contour is dropped.
+    /// Repeated off-curve points are kept, since each is a control point
+    /// of its own curve segment.
+    pub fn flatten(&self, font: &Font<'fnt>) -> Result<SimpleGlyph> {
+        let loca = font.loca()?;
+        let resolved = font.table::<Glyf>()?.glyph_points(&loca, self.id)?;
+
+        let mut flattened = SimpleGlyph::default();
+        for contour in resolved.contours() {
+            let start = flattened.points.len();
+            for point in contour {
+                let point = Point { x: point.x.round(), y: point.y.round(), on_curve: point.on_curve };
+                if !point.on_curve || flattened.points[start..].last() != Some(&point) {
+                    flattened.points.push(point);
+                }
+            }
+
+            // The contour closes itself, so a final copy of its first point
+            // is also a repeat.
+            let last = flattened.points.last().filter(|last| last.on_curve);
+            if flattened.points.len() - start > 1 && last.is_some() && last == flattened.points.get(start) {
+                flattened.points.pop();
+            }
+            flattened.end_points.push(flattened.points.len() - 1);
+        }
+
+        Ok(flattened)
+    }
+
+    /// Whether the outline of the glyph, with its components resolved,
+    /// covers the point `(x, y)` in font units under `rule`.
+    pub fn contains_point(&self, font: &Font<'fnt>, x: f32, y: f32, rule: FillRule) -> Result<bool> {
+        let loca = font.loca()?;
+        let mut sink = WindingSink::new(x, y);
+        font.table::<Glyf>()?.outline(&loca, self.id, &mut sink)?;
+        sink.close();
+        Ok(sink.contains(rule))
+    }
+}
+
+impl<'fnt> Glyf<'fnt> {
+    /// The glyph `id`, located through `loca`.
+    pub fn glyph(&self, loca: &Loca, id: GlyphId) -> Result<Glyph<'fnt>> {
+        Ok(Glyph { id, data: self.glyph_data(loca, id)? })
+    }
+
+    /// The points of `glyph`, with composite glyphs resolved.
+    pub fn glyph_points(&self, loca: &Loca, glyph: GlyphId) -> Result<SimpleGlyph> {
+        self.resolve(loca, glyph, 0)
+    }
+
+    /// Send the outline of `glyph` to `sink`.
+    pub fn outline<S: OutlineSink>(&self, loca: &Loca, glyph: GlyphId, sink: &mut S) -> Result<()> {
+        self.glyph_points(loca, glyph)?.outline(sink);
+        Ok(())
+    }
+
+    fn resolve(&self, loca: &Loca, glyph: GlyphId, depth: usize) -> Result<SimpleGlyph> {
+        if depth > MAX_COMPONENT_DEPTH {
+            return Err(Error::InvalidData)
+        }
+
+        let data = self.glyph_data(loca, glyph)?;
+        match glyph_header(data)? {
+            Some(ref header) if header.number_of_contours < 0 => (),
+            _ => return SimpleGlyph::decode(data),
+        }
+
+        let mut resolved = SimpleGlyph::default();
+        for component in components(data)? {
+            let child = self.resolve(loca, component.glyph, depth + 1)?;
+            let (dx, dy) = if component.args_are_offset() {
+                let offset = Point { x: component.arg1 as f32, y: component.arg2 as f32, on_curve: true };
+                if component.flags & component_flags::SCALED_COMPONENT_OFFSET != 0 {
+                    let scaled = component.apply(offset, 0.0, 0.0);
+                    (scaled.x, scaled.y)
+                } else {
+                    (offset.x, offset.y)
+                }
+            } else {
+                // Align a point of the component with a point already placed.
+                let parent = resolved.points.get(component.arg1 as usize).ok_or(Error::InvalidData)?;
+                let child = child.points.get(component.arg2 as usize).ok_or(Error::InvalidData)?;
+                let child = component.apply(*child, 0.0, 0.0);
+                (parent.x - child.x, parent.y - child.y)
+            };
+
+            let base = resolved.points.len();
+            resolved.points.extend(child.points.iter().map(|&point| component.apply(point, dx, dy)));
+            resolved.end_points.extend(child.end_points.iter().map(|&end| base + end));
+        }
+
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use font::Font;
+    use test_fonts;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    #[test]
+    fn points_and_bounds() {
+        for &long_loca in &[false, true] {
+            let data = test_fonts::minimal_font(long_loca).build();
+            let font = Font::decode(&data).expect("failed to read font");
+            let loca = font.loca().expect("failed to read loca");
+            let glyf = font.table::<Glyf>().expect("failed to read glyf");
+            assert_eq!(loca.is_long(), long_loca);
+
+            let points = (0..3)
+                .map(|gid| num_points(glyf.glyph_data(&loca, GlyphId(gid)).unwrap()).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(points, vec![0, 4, 3]);
+
+            let triangle = glyph_header(glyf.glyph_data(&loca, GlyphId(2)).unwrap()).unwrap();
+            assert_eq!(triangle, Some(GlyphHeader {
+                number_of_contours: 1,
+                x_min: 50,
+                y_min: 0,
+                x_max: 550,
+                y_max: 700,
+            }));
+            assert_eq!(glyf.glyph_data(&loca, GlyphId(3)), Err(Error::InvalidGlyphId));
+        }
+    }
+
+    #[test]
+    fn outlines() {
+        let data = test_fonts::minimal_font(false).build();
+        let font = Font::decode(&data).expect("failed to read font");
+        let loca = font.loca().expect("failed to read loca");
+        let glyf = font.table::<Glyf>().expect("failed to read glyf");
+
+        let square = glyf.glyph_points(&loca, GlyphId(1)).expect("failed to read points");
+        assert_eq!(square.end_points, vec![3]);
+        assert_eq!(square.points[2], Point { x: 600.0, y: 500.0, on_curve: true });
+        assert_eq!(glyf.glyph_points(&loca, GlyphId(0)), Ok(SimpleGlyph::default()));
+
+        // A composite glyph is resolved into its transformed components.
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let loca = font.loca().expect("failed to read loca");
+        let glyf = font.table::<Glyf>().expect("failed to read glyf");
+        let aacute = glyf.glyph_data(&loca, GlyphId(131)).unwrap();
+        assert_eq!(components(aacute).map(|components| components.len()), Ok(2));
+
+        let header = glyph_header(aacute).unwrap().unwrap();
+        let points = glyf.glyph_points(&loca, GlyphId(131)).expect("failed to resolve composite");
+        let x_max = points.points.iter().map(|p| p.x).fold(f32::MIN, f32::max);
+        let y_max = points.points.iter().map(|p| p.y).fold(f32::MIN, f32::max);
+        assert_eq!((x_max, y_max), (header.x_max as f32, header.y_max as f32));
+
+        let bounds = font.glyph_bounds(GlyphId(131)).unwrap().expect("empty outline");
+        assert!(bounds.y_max <= y_max && bounds.y_max > y_max - 2.0);
+    }
+
+    #[test]
+    fn strip_instructions() {
+        let square = test_fonts::hinted_glyph(&[&[(0, 0), (0, 100), (100, 100)]], &[0xB0, 0x01]);
+        assert_eq!(instructions(&square), Ok(&[0xB0, 0x01][..]));
+        let stripped = super::strip_instructions(&square).unwrap();
+        assert_eq!(stripped, test_fonts::simple_glyph(&[&[(0, 0), (0, 100), (100, 100)]]));
+        assert_eq!(instructions(&[]), Ok(&[][..]));
+
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let loca = font.loca().expect("failed to read loca");
+        let glyf = font.table::<Glyf>().expect("failed to read glyf");
+        let aacute = glyf.glyph_data(&loca, GlyphId(131)).unwrap();
+        assert!(!instructions(aacute).unwrap().is_empty());
+        let stripped = super::strip_instructions(aacute).unwrap();
+        assert_eq!(instructions(&stripped), Ok(&[][..]));
+        assert_eq!(stripped.len(), component_records(aacute).unwrap().1);
+        assert_eq!(component_glyph_offsets(&stripped), component_glyph_offsets(aacute));
+    }
+
+    #[test]
+    fn flatten() {
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let loca = font.loca().expect("failed to read loca");
+        let glyf = font.table::<Glyf>().expect("failed to read glyf");
+
+        let aacute = glyf.glyph(&loca, GlyphId(131)).unwrap();
+        assert_eq!(aacute.is_composite(), Ok(true));
+        let flat = aacute.flatten(&font).expect("failed to flatten");
+        let resolved = glyf.glyph_points(&loca, GlyphId(131)).unwrap();
+        assert_eq!(flat.end_points.len(), resolved.end_points.len());
+        assert!(flat.points.iter().all(|p| p.x.fract() == 0.0 && p.y.fract() == 0.0));
+
+        // The components of Aacute are 'A' and the acute accent, shifted.
+        let components = aacute.components().unwrap();
+        let parts = components.iter()
+            .map(|c| glyf.glyph(&loca, c.glyph).unwrap().flatten(&font).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(flat.points.len(), parts.iter().map(|part| part.points.len()).sum::<usize>());
+
+        let mut sink = ::outline::BoundsSink::new();
+        flat.outline(&mut sink);
+        assert_eq!(sink.bounds(), font.glyph_bounds(GlyphId(131)).unwrap());
+
+        // Flattening a simple glyph only drops repeated points.
+        let square = test_fonts::simple_glyph(&[&[(0, 0), (0, 0), (100, 0), (100, 100), (0, 0)]]);
+        let (glyf_data, loca_data) = test_fonts::glyf_and_loca(&[Vec::new(), square], false);
+        let data = test_fonts::minimal_font(false)
+            .table(b"glyf", glyf_data)
+            .table(b"loca", loca_data)
+            .table(b"maxp", test_fonts::maxp(2))
+            .build();
+        let font = Font::decode(&data).expect("failed to read font");
+        let glyph = font.glyph(GlyphId(1)).unwrap();
+        assert_eq!(glyph.components(), Ok(Vec::new()));
+        let flat = glyph.flatten(&font).unwrap();
+        assert_eq!(flat.end_points, vec![2]);
+        assert_eq!(flat.points.iter().map(|p| (p.x, p.y)).collect::<Vec<_>>(),
+                   vec![(0.0, 0.0), (100.0, 0.0), (100.0, 100.0)]);
+
+        // Two off-curve points at the same place are both kept.
+        let mut curve = vec![0, 1, 0, 0, 0, 0, 0, 100, 0, 100, 0, 3, 0, 0, 1, 0, 0, 1];
+        for delta in [0i16, 50, 0, 50, 0, 100, 0, -100].iter() {
+            curve.extend_from_slice(&delta.to_be_bytes());
+        }
+        let (glyf_data, loca_data) = test_fonts::glyf_and_loca(&[Vec::new(), curve], false);
+        let data = test_fonts::minimal_font(false)
+            .table(b"glyf", glyf_data)
+            .table(b"loca", loca_data)
+            .table(b"maxp", test_fonts::maxp(2))
+            .build();
+        let font = Font::decode(&data).expect("failed to read font");
+        let flat = font.glyph(GlyphId(1)).unwrap().flatten(&font).unwrap();
+        assert_eq!(flat.points.iter().map(|p| (p.x, p.y, p.on_curve)).collect::<Vec<_>>(), vec![
+            (0.0, 0.0, true), (50.0, 100.0, false), (50.0, 100.0, false), (100.0, 0.0, true),
+        ]);
+    }
+
+    proptest! {
+        #[test]
+        fn simple_glyph_round_trip(contours in vec(vec((-16384..16384i16, -16384..16384i16), 1..20), 1..5)) {
+            let contours = contours.iter().map(Vec::as_slice).collect::<Vec<_>>();
+            let data = test_fonts::simple_glyph(&contours);
+            let points = contours.iter().flat_map(|contour| contour.iter()).collect::<Vec<_>>();
+
+            prop_assert_eq!(num_points(&data), Ok(points.len()));
+            prop_assert_eq!(glyph_header(&data), Ok(Some(GlyphHeader {
+                number_of_contours: contours.len() as i16,
+                x_min: points.iter().map(|p| p.0).min().unwrap(),
+                y_min: points.iter().map(|p| p.1).min().unwrap(),
+                x_max: points.iter().map(|p| p.0).max().unwrap(),
+                y_max: points.iter().map(|p| p.1).max().unwrap(),
+            })));
+        }
+    }
+}
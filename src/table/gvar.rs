@@ -0,0 +1,513 @@
+use decode::Decode;
+use primitives::{F2Dot14, GlyphId};
+use table::glyf::SimpleGlyph;
+use table::variation::{region_scalar, NormalizedCoord};
+use error::{Error, Result};
+
+/// The glyph variations table, which contains the point deltas used to
+/// interpolate TrueType outlines.
+#[derive(Debug, Copy, Clone)]
+pub struct Gvar<'fnt> {
+    axis_count: usize,
+    shared_tuples: &'fnt [u8],
+    shared_tuple_count: usize,
+    glyph_count: usize,
+    long_offsets: bool,
+    offsets: &'fnt [u8],
+    data: &'fnt [u8],
+}
+
+/// A decoded tuple variation of a single glyph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TupleVariation {
+    pub peak: Vec<f32>,
+    /// The `(start, end)` tuples of an intermediate region.
+    pub intermediate: Option<(Vec<f32>, Vec<f32>)>,
+    /// The points which have deltas, or `None` if every point has a delta.
+    pub points: Option<Vec<u16>>,
+    pub x_deltas: Vec<i32>,
+    /// Empty for `cvar`, whose values vary in one dimension.
+    pub y_deltas: Vec<i32>,
+}
+
+impl TupleVariation {
+    /// The scalar of this variation at the normalized `coords`.
+    pub fn scalar(&self, coords: &[NormalizedCoord]) -> f32 {
+        match self.intermediate {
+            Some((ref start, ref end)) => region_scalar(coords, (0..self.peak.len())
+                .map(|i| (start[i], self.peak[i], end[i]))),
+            None => region_scalar(coords, self.peak.iter()
+                .map(|&peak| (peak.min(0.0), peak, peak.max(0.0)))),
+        }
+    }
+
+    /// The delta for `point`, without inferring deltas for points which
+    /// are not referenced.
+    pub fn delta(&self, point: usize) -> Option<(i32, i32)> {
+        let index = match self.points {
+            Some(ref points) => points.iter().position(|&p| p as usize == point)?,
+            None => point,
+        };
+
+        let y_delta = if self.y_deltas.is_empty() { 0 } else { *self.y_deltas.get(index)? };
+        Some((*self.x_deltas.get(index)?, y_delta))
+    }
+}
+
+const SHARED_POINT_NUMBERS: u16 = 0x8000;
+const COUNT_MASK: u16 = 0x0FFF;
+const EMBEDDED_PEAK_TUPLE: u16 = 0x8000;
+const INTERMEDIATE_REGION: u16 = 0x4000;
+const PRIVATE_POINT_NUMBERS: u16 = 0x2000;
+const TUPLE_INDEX_MASK: u16 = 0x0FFF;
+
+impl<'fnt> Decode<'fnt> for Gvar<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Gvar<'fnt>> {
+        required_len!(buffer, 20);
+        let version = u16::decode(buffer)?;
+        if version != 1 {
+            return Err(Error::UnsupportedVersion(version.into()))
+        }
+
+        let axis_count = u16::decode(&buffer[4..])? as usize;
+        let shared_tuple_count = u16::decode(&buffer[6..])? as usize;
+        let shared_tuples_offset = u32::decode(&buffer[8..])? as usize;
+        let glyph_count = u16::decode(&buffer[12..])? as usize;
+        let long_offsets = u16::decode(&buffer[14..])? & 1 != 0;
+        let data_offset = u32::decode(&buffer[16..])? as usize;
+
+        let offsets_len = (glyph_count + 1) * if long_offsets { 4 } else { 2 };
+        required_len!(buffer, 20 + offsets_len);
+
+        let shared_tuples = buffer.get(shared_tuples_offset..).ok_or(Error::UnexpectedEof)?;
+        required_len!(shared_tuples, shared_tuple_count * axis_count * 2);
+
+        Ok(Gvar {
+            axis_count,
+            shared_tuples,
+            shared_tuple_count,
+            glyph_count,
+            long_offsets,
+            offsets: &buffer[20..20 + offsets_len],
+            data: buffer.get(data_offset..).ok_or(Error::UnexpectedEof)?,
+        })
+    }
+}
+
+impl<'fnt> Gvar<'fnt> {
+    pub fn axis_count(&self) -> usize {
+        self.axis_count
+    }
+
+    /// The GlyphVariationData of `glyph`, or `None` if it has no variations.
+    pub fn glyph_variation_data(&self, glyph: GlyphId) -> Result<Option<&'fnt [u8]>> {
+        let index = glyph.0 as usize;
+        if index >= self.glyph_count {
+            return Ok(None)
+        }
+
+        let offset = |i: usize| -> Result<usize> {
+            if self.long_offsets {
+                Ok(u32::decode(&self.offsets[i * 4..])? as usize)
+            } else {
+                Ok(u16::decode(&self.offsets[i * 2..])? as usize * 2)
+            }
+        };
+
+        let (start, end) = (offset(index)?, offset(index + 1)?);
+        if start >= end {
+            return Ok(None)
+        }
+
+        self.data.get(start..end).ok_or(Error::UnexpectedEof).map(Some)
+    }
+
+    /// Decode every tuple variation of `glyph`, which has `num_points`
+    /// outline points (or components) in addition to the four phantom points.
+    pub fn tuple_variations(&self, glyph: GlyphId, num_points: usize) -> Result<Vec<TupleVariation>> {
+        let data = match self.glyph_variation_data(glyph)? {
+            Some(data) => data,
+            None => return Ok(Vec::new()),
+        };
+
+        decode_tuple_variations(data, 0, TupleStore {
+            axis_count: self.axis_count,
+            shared_tuples: self.shared_tuples,
+            shared_tuple_count: self.shared_tuple_count,
+            point_count: num_points + 4,
+            dimensions: 2,
+        })
+    }
+
+    /// The interpolated deltas of the four phantom points of `glyph`, which
+    /// follow its `num_points` outline points.  In order, these are the
+    /// left side bearing, advance, top and bottom phantom points.
+    pub fn phantom_deltas(&self,
+                          glyph: GlyphId,
+                          num_points: usize,
+                          coords: &[NormalizedCoord]) -> Result<[(f32, f32); 4]> {
+        let mut deltas = [(0.0, 0.0); 4];
+        for variation in self.tuple_variations(glyph, num_points)? {
+            let scalar = variation.scalar(coords);
+            if scalar == 0.0 {
+                continue
+            }
+
+            // Phantom points are never inferred, so points without an
+            // explicit delta do not move.
+            for (i, delta) in deltas.iter_mut().enumerate() {
+                if let Some((dx, dy)) = variation.delta(num_points + i) {
+                    delta.0 += dx as f32 * scalar;
+                    delta.1 += dy as f32 * scalar;
+                }
+            }
+        }
+
+        Ok(deltas)
+    }
+
+    /// The interpolated deltas of each point of the simple glyph `glyph`,
+    /// whose points are `outline`, followed by its four phantom points.
+    ///
+    /// Outline points which a variation gives no delta are inferred from
+    /// the nearest points of their contour which have one, as the
+    /// specification describes.  Phantom points are never inferred.
+    pub fn glyph_deltas(&self,
+                        glyph: GlyphId,
+                        outline: &SimpleGlyph,
+                        coords: &[NormalizedCoord]) -> Result<Vec<(f32, f32)>> {
+        let num_points = outline.points.len();
+        let mut deltas = vec![(0.0, 0.0); num_points + 4];
+        for variation in self.tuple_variations(glyph, num_points)? {
+            let scalar = variation.scalar(coords);
+            if scalar == 0.0 {
+                continue
+            }
+
+            let explicit = (0..num_points + 4).map(|point| variation.delta(point)).collect::<Vec<_>>();
+            let inferred = infer_deltas(outline, &explicit[..num_points]);
+            let phantoms = explicit[num_points..].iter().map(|delta| {
+                delta.map_or((0.0, 0.0), |(dx, dy)| (dx as f32, dy as f32))
+            });
+            for (delta, (dx, dy)) in deltas.iter_mut().zip(inferred.into_iter().chain(phantoms)) {
+                delta.0 += dx * scalar;
+                delta.1 += dy * scalar;
+            }
+        }
+
+        Ok(deltas)
+    }
+}
+
+/// The delta of each point of `outline`, from its `explicit` delta or
+/// inferred from the explicit deltas of the points before and after it
+/// in its contour.  Contours without an explicit delta do not move.
+fn infer_deltas(outline: &SimpleGlyph, explicit: &[Option<(i32, i32)>]) -> Vec<(f32, f32)> {
+    let mut deltas = vec![(0.0, 0.0); explicit.len()];
+    let mut start = 0;
+    for &end in &outline.end_points {
+        let contour = start..(end + 1).min(explicit.len());
+        start = end + 1;
+        let touched = contour.clone().filter(|&point| explicit[point].is_some()).collect::<Vec<_>>();
+        let (first, last) = match (touched.first(), touched.last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => continue,
+        };
+
+        for point in contour {
+            deltas[point] = match explicit[point] {
+                Some((dx, dy)) => (dx as f32, dy as f32),
+                None => {
+                    // The touched points on either side, wrapping around
+                    // the contour.
+                    let prev = touched.iter().rev().find(|&&touched| touched < point).map_or(last, |&prev| prev);
+                    let next = touched.iter().find(|&&touched| touched > point).map_or(first, |&next| next);
+                    let (prev_delta, next_delta) = (explicit[prev].unwrap_or_default(), explicit[next].unwrap_or_default());
+                    let (p, a, b) = (outline.points[point], outline.points[prev], outline.points[next]);
+                    (interpolate(p.x, (a.x, prev_delta.0 as f32), (b.x, next_delta.0 as f32)),
+                     interpolate(p.y, (a.y, prev_delta.1 as f32), (b.y, next_delta.1 as f32)))
+                },
+            };
+        }
+    }
+    deltas
+}
+
+/// The delta of a coordinate `value` between the `(coordinate, delta)` of
+/// two reference points: linear between them, and that of the nearer
+/// reference beyond them.
+fn interpolate(value: f32, a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (low, high) = if a.0 <= b.0 { (a, b) } else { (b, a) };
+    if low.0 == high.0 {
+        return if low.1 == high.1 { low.1 } else { 0.0 }
+    }
+
+    if value <= low.0 {
+        low.1
+    } else if value >= high.0 {
+        high.1
+    } else {
+        low.1 + (high.1 - low.1) * (value - low.0) / (high.0 - low.0)
+    }
+}
+
+/// The parameters of a tuple variation store, which `gvar` and `cvar`
+/// both use to vary a list of points or values.
+pub(crate) struct TupleStore<'a> {
+    pub axis_count: usize,
+    pub shared_tuples: &'a [u8],
+    pub shared_tuple_count: usize,
+    /// The number of points or values varied, which each variation
+    /// without explicit point numbers has a delta for.
+    pub point_count: usize,
+    /// Two for `gvar`, which has `x` and `y` deltas, and one for `cvar`.
+    pub dimensions: usize,
+}
+
+/// Decode the tuple variations of a store whose `tupleVariationCount`
+/// is at `pos` in `data`.  The offset to the serialized data which follows
+/// it is relative to the start of `data`.
+pub(crate) fn decode_tuple_variations(data: &[u8], pos: usize, store: TupleStore) -> Result<Vec<TupleVariation>> {
+    let data_start = data.get(pos..).ok_or(Error::UnexpectedEof)?;
+    required_len!(data_start, 4);
+    let tuple_count = u16::decode(data_start)?;
+    let serialized_offset = u16::decode(&data_start[2..])? as usize;
+    let mut serialized = data.get(serialized_offset..).ok_or(Error::UnexpectedEof)?;
+    let axis_count = store.axis_count;
+
+    let shared_points = if tuple_count & SHARED_POINT_NUMBERS != 0 {
+        let (points, len) = unpack_points(serialized)?;
+        serialized = &serialized[len..];
+        points
+    } else {
+        None
+    };
+
+    let mut headers = &data_start[4..];
+    let mut variations = Vec::new();
+    for _ in 0..(tuple_count & COUNT_MASK) {
+        required_len!(headers, 4);
+        let data_size = u16::decode(headers)? as usize;
+        let tuple_index = u16::decode(&headers[2..])?;
+        headers = &headers[4..];
+
+        let peak = if tuple_index & EMBEDDED_PEAK_TUPLE != 0 {
+            let peak = read_tuple(headers, axis_count)?;
+            headers = &headers[axis_count * 2..];
+            peak
+        } else {
+            let index = (tuple_index & TUPLE_INDEX_MASK) as usize;
+            if index >= store.shared_tuple_count {
+                return Err(Error::InvalidData)
+            }
+            read_tuple(&store.shared_tuples[index * axis_count * 2..], axis_count)?
+        };
+
+        let intermediate = if tuple_index & INTERMEDIATE_REGION != 0 {
+            let start = read_tuple(headers, axis_count)?;
+            let end = read_tuple(&headers[axis_count * 2..], axis_count)?;
+            headers = &headers[axis_count * 4..];
+            Some((start, end))
+        } else {
+            None
+        };
+
+        let mut tuple_data = serialized.get(..data_size).ok_or(Error::UnexpectedEof)?;
+        serialized = &serialized[data_size..];
+
+        let points = if tuple_index & PRIVATE_POINT_NUMBERS != 0 {
+            let (points, len) = unpack_points(tuple_data)?;
+            tuple_data = &tuple_data[len..];
+            points
+        } else {
+            shared_points.clone()
+        };
+
+        let count = points.as_ref().map(Vec::len).unwrap_or(store.point_count);
+        let (x_deltas, len) = unpack_deltas(tuple_data, count)?;
+        let y_deltas = if store.dimensions == 2 {
+            unpack_deltas(&tuple_data[len..], count)?.0
+        } else {
+            Vec::new()
+        };
+
+        variations.push(TupleVariation { peak, intermediate, points, x_deltas, y_deltas });
+    }
+
+    Ok(variations)
+}
+
+fn read_tuple(buffer: &[u8], axis_count: usize) -> Result<Vec<f32>> {
+    required_len!(buffer, axis_count * 2);
+    (0..axis_count)
+        .map(|axis| F2Dot14::decode(&buffer[axis * 2..]).map(f32::from))
+        .collect()
+}
+
+/// Decode packed point numbers, returning the points (or `None` for all
+/// points) and the number of bytes read.
+pub fn unpack_points(buffer: &[u8]) -> Result<(Option<Vec<u16>>, usize)> {
+    required_len!(buffer, 1);
+    let (count, mut pos) = if buffer[0] & 0x80 != 0 {
+        required_len!(buffer, 2);
+        ((((buffer[0] & 0x7F) as usize) << 8) | buffer[1] as usize, 2)
+    } else {
+        (buffer[0] as usize, 1)
+    };
+
+    if count == 0 {
+        return Ok((None, pos))
+    }
+
+    let mut points = Vec::with_capacity(count);
+    let mut point = 0u16;
+    while points.len() < count {
+        let control = *buffer.get(pos).ok_or(Error::UnexpectedEof)?;
+        pos += 1;
+        let run = (control & 0x7F) as usize + 1;
+        let words = control & 0x80 != 0;
+        for _ in 0..run {
+            let delta = if words {
+                let value = u16::decode(buffer.get(pos..).ok_or(Error::UnexpectedEof)?)?;
+                pos += 2;
+                value
+            } else {
+                let value = *buffer.get(pos).ok_or(Error::UnexpectedEof)? as u16;
+                pos += 1;
+                value
+            };
+
+            point = point.wrapping_add(delta);
+            points.push(point);
+        }
+    }
+
+    points.truncate(count);
+    Ok((Some(points), pos))
+}
+
+/// Decode `count` packed deltas, returning the deltas and the number of
+/// bytes read.
+pub fn unpack_deltas(buffer: &[u8], count: usize) -> Result<(Vec<i32>, usize)> {
+    const DELTAS_ARE_ZERO: u8 = 0x80;
+    const DELTAS_ARE_WORDS: u8 = 0x40;
+    const RUN_COUNT_MASK: u8 = 0x3F;
+
+    let mut deltas = Vec::with_capacity(count);
+    let mut pos = 0;
+    while deltas.len() < count {
+        let control = *buffer.get(pos).ok_or(Error::UnexpectedEof)?;
+        pos += 1;
+        let run = (control & RUN_COUNT_MASK) as usize + 1;
+        let size = match control & (DELTAS_ARE_ZERO | DELTAS_ARE_WORDS) {
+            DELTAS_ARE_ZERO => 0,
+            DELTAS_ARE_WORDS => 2,
+            0 => 1,
+            // Both flags together indicate 32-bit deltas.
+            _ => 4,
+        };
+
+        let run_data = buffer.get(pos..pos + run * size).ok_or(Error::UnexpectedEof)?;
+        for i in 0..run {
+            let value = match size {
+                0 => 0,
+                1 => run_data[i] as i8 as i32,
+                2 => i16::decode(&run_data[i * 2..])? as i32,
+                _ => i32::decode(&run_data[i * 4..])?,
+            };
+            deltas.push(value);
+        }
+        pos += run * size;
+    }
+
+    deltas.truncate(count);
+    Ok((deltas, pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_points_and_intermediate_regions() {
+        // One glyph of two points and one axis, with three variations: the
+        // shared peak 1.0 at the shared points 1 and 3, an embedded peak
+        // 0.5 with the intermediate region 0.25 to 1.0 at the shared
+        // points, and the shared peak with private points naming every
+        // point.
+        let glyph = [
+            0x80, 3, 0, 22,
+            0, 6, 0x00, 0x00,
+            0, 6, 0xC0, 0x00, 0x20, 0x00, 0x10, 0x00, 0x40, 0x00,
+            0, 9, 0x20, 0x00,
+            // The shared points, as a run of two point number deltas.
+            2, 0x01, 1, 2,
+            0x01, 10, 20, 0x01, 0xFB, 0,
+            0x41, 0, 100, 0, 200, 0x81,
+            0, 0x05, 1, 2, 3, 4, 5, 6, 0x85,
+        ];
+        let mut data = vec![
+            0, 1, 0, 0, 0, 1, 0, 1, 0, 0, 0, 28,
+            0, 1, 0, 1, 0, 0, 0, 30,
+            0, 0, 0, 0, 0, 0, 0, glyph.len() as u8,
+            0x40, 0x00,
+        ];
+        data.extend_from_slice(&glyph);
+        let gvar = Gvar::decode(&data).unwrap();
+
+        let variations = gvar.tuple_variations(GlyphId(0), 2).unwrap();
+        assert_eq!(variations, vec![
+            TupleVariation {
+                peak: vec![1.0],
+                intermediate: None,
+                points: Some(vec![1, 3]),
+                x_deltas: vec![10, 20],
+                y_deltas: vec![-5, 0],
+            },
+            TupleVariation {
+                peak: vec![0.5],
+                intermediate: Some((vec![0.25], vec![1.0])),
+                points: Some(vec![1, 3]),
+                x_deltas: vec![100, 200],
+                y_deltas: vec![0, 0],
+            },
+            TupleVariation {
+                peak: vec![1.0],
+                intermediate: None,
+                points: None,
+                x_deltas: vec![1, 2, 3, 4, 5, 6],
+                y_deltas: vec![0; 6],
+            },
+        ]);
+
+        let scalars = [-0.5, 0.25, 0.375, 0.5, 0.75, 1.0].iter()
+            .map(|&coord| variations[1].scalar(&[NormalizedCoord::new(coord)]))
+            .collect::<Vec<_>>();
+        assert_eq!(scalars, vec![0.0, 0.0, 0.5, 1.0, 0.5, 0.0]);
+        assert_eq!((variations[0].delta(3), variations[0].delta(0)), (Some((20, 0)), None));
+
+        // At 0.75 the variations scale by 0.75, 0.5 and 0.75.
+        let deltas = gvar.phantom_deltas(GlyphId(0), 2, &[NormalizedCoord::new(0.75)]).unwrap();
+        assert_eq!(deltas, [(2.25, 0.0), (118.0, 0.0), (3.75, 0.0), (4.5, 0.0)]);
+    }
+
+    #[test]
+    fn infer_untouched_points() {
+        use table::glyf::Point;
+
+        // A contour of four points along x, of which the first and third
+        // move, and a second contour where nothing does.
+        let point = |x, y| Point { x, y, on_curve: true };
+        let outline = SimpleGlyph {
+            end_points: vec![3, 4],
+            points: vec![point(0.0, 0.0), point(50.0, 0.0), point(100.0, 100.0), point(150.0, 100.0), point(10.0, 10.0)],
+        };
+        let explicit = [Some((10, 0)), None, Some((30, 40)), None, None];
+        let deltas = infer_deltas(&outline, &explicit);
+        // The second point lies halfway between its references in x, and
+        // the fourth beyond both, so it takes the delta of the nearer.
+        assert_eq!(deltas, vec![(10.0, 0.0), (20.0, 0.0), (30.0, 40.0), (30.0, 40.0), (0.0, 0.0)]);
+        assert_eq!(interpolate(5.0, (5.0, 3.0), (5.0, 4.0)), 0.0);
+        assert_eq!(interpolate(5.0, (5.0, 3.0), (5.0, 3.0)), 3.0);
+    }
+}
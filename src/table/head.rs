@@ -0,0 +1,37 @@
+use decode::{Decode, StaticEncodeSize, EncodeSize};
+use primitives::{FixedVersion, Fixed, LongDateTime};
+use error::Result;
+
+/// The font header table, which contains global information about the font.
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq)]
+pub struct Head {
+    pub version: FixedVersion,
+    pub font_revision: Fixed,
+    pub check_sum_adjustment: u32,
+    pub magic_number: u32,
+    pub flags: u16,
+    pub units_per_em: u16,
+    pub created: LongDateTime,
+    pub modified: LongDateTime,
+    pub x_min: i16,
+    pub y_min: i16,
+    pub x_max: i16,
+    pub y_max: i16,
+    pub mac_style: u16,
+    pub lowest_rec_ppem: u16,
+    pub font_direction_hint: i16,
+    pub index_to_loc_format: i16,
+    pub glyph_data_format: i16,
+}
+
+impl Head {
+    /// Whether the `macStyle` bold bit is set.
+    pub fn is_bold(&self) -> bool {
+        self.mac_style & 0x0001 != 0
+    }
+
+    /// Whether the `macStyle` italic bit is set.
+    pub fn is_italic(&self) -> bool {
+        self.mac_style & 0x0002 != 0
+    }
+}
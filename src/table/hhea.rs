@@ -0,0 +1,26 @@
+use decode::{Decode, StaticEncodeSize, EncodeSize};
+use primitives::{FixedVersion, FWord, UFWord, Ignored};
+use error::Result;
+
+/// The horizontal header table, which contains the information needed
+/// to lay out fonts horizontally.
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq)]
+pub struct Hhea {
+    pub version: FixedVersion,
+    pub ascender: FWord,
+    pub descender: FWord,
+    pub line_gap: FWord,
+    pub advance_width_max: UFWord,
+    pub min_left_side_bearing: FWord,
+    pub min_right_side_bearing: FWord,
+    pub x_max_extent: FWord,
+    pub caret_slope_rise: i16,
+    pub caret_slope_run: i16,
+    pub caret_offset: i16,
+    reserved0: Ignored<i16>,
+    reserved1: Ignored<i16>,
+    reserved2: Ignored<i16>,
+    reserved3: Ignored<i16>,
+    pub metric_data_format: i16,
+    pub number_of_h_metrics: u16,
+}
@@ -0,0 +1,175 @@
+use decode::{Decode, Decode1, Decode2, StaticEncodeSize, EncodeSize};
+use encode::EncodeBuf;
+use primitives::{Array, Discarded, GlyphId};
+use error::Result;
+use std::marker::PhantomData;
+
+/// The horizontal metrics table.  The table is decoded with the
+/// `numberOfHMetrics` from `hhea` and the `numGlyphs` from `maxp`.
+#[derive(Decode, Debug, Copy, Clone)]
+pub struct Hmtx<'fnt> {
+    __num_h_metrics: Discarded<usize>,
+    __num_glyphs: Discarded<usize>,
+    #[WithParam = "__num_h_metrics"]
+    h_metrics: Array<'fnt, LongHorMetric>,
+    #[WithParam = "__num_glyphs.saturating_sub(__num_h_metrics)"]
+    left_side_bearings: Array<'fnt, i16>,
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq)]
+pub struct LongHorMetric {
+    pub advance_width: u16,
+    pub lsb: i16,
+}
+
+/// The horizontal metrics of a single glyph.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct GlyphMetrics {
+    pub advance_width: u16,
+    pub left_side_bearing: i16,
+}
+
+impl<'fnt> Hmtx<'fnt> {
+    /// Encode the metrics of every glyph, in glyph order, as the table
+    /// and its `numberOfHMetrics`.  Glyphs at the end which share the
+    /// advance of the last glyph store only their side bearing, so a
+    /// monospaced font needs a single long metric.
+    pub fn encode(metrics: &[GlyphMetrics]) -> (Vec<u8>, u16) {
+        let mut long = metrics.len();
+        while long > 1 && metrics[long - 2].advance_width == metrics[long - 1].advance_width {
+            long -= 1;
+        }
+
+        let mut out = EncodeBuf::new();
+        for (index, metric) in metrics.iter().enumerate() {
+            if index < long {
+                out.put_u16(metric.advance_width);
+            }
+            out.put_i16(metric.left_side_bearing);
+        }
+        (out.into_vec(), long as u16)
+    }
+
+    /// The number of long metrics, which is `numberOfHMetrics` in `hhea`.
+    pub fn num_long_metrics(&self) -> usize {
+        self.h_metrics.len()
+    }
+
+    /// The number of glyphs covered by this table.
+    pub fn num_glyphs(&self) -> usize {
+        self.h_metrics.len() + self.left_side_bearings.len()
+    }
+
+    /// The advance width of a glyph.  Glyphs beyond the last long metric
+    /// share the advance width of the last entry in `hMetrics`.
+    pub fn advance(&self, glyph: GlyphId) -> Option<u16> {
+        let index = glyph.0 as usize;
+        if index >= self.num_glyphs() {
+            return None
+        }
+
+        match self.h_metrics.get(index) {
+            Some(metric) => Some(metric.advance_width),
+            None => self.h_metrics.last().map(|metric| metric.advance_width),
+        }
+    }
+
+    /// The left side bearing of a glyph.
+    pub fn left_side_bearing(&self, glyph: GlyphId) -> Option<i16> {
+        let index = glyph.0 as usize;
+        match self.h_metrics.get(index) {
+            Some(metric) => Some(metric.lsb),
+            None => self.left_side_bearings.get(index - self.h_metrics.len()),
+        }
+    }
+
+    /// The advance width and left side bearing of a glyph.
+    pub fn metrics(&self, glyph: GlyphId) -> Option<GlyphMetrics> {
+        Some(GlyphMetrics {
+            advance_width: self.advance(glyph)?,
+            left_side_bearing: self.left_side_bearing(glyph)?,
+        })
+    }
+
+    /// An iterator over the advance widths of every glyph in the font,
+    /// in glyph order.
+    pub fn advances(&self) -> impl Iterator<Item = u16> + 'fnt {
+        let last = self.h_metrics.last().map(|metric| metric.advance_width);
+        let repeated = match last {
+            Some(_) => self.left_side_bearings.len(),
+            None => 0,
+        };
+
+        self.h_metrics
+            .into_iter()
+            .map(|metric| metric.advance_width)
+            .chain(::std::iter::repeat_n(last.unwrap_or(0), repeated))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use font::Font;
+    use test_fonts::{self, FontBuilder};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn metrics_round_trip(metrics in vec(any::<(u16, i16)>(), 1..40),
+                              left_side_bearings in vec(any::<i16>(), 0..40)) {
+            let num_glyphs = metrics.len() + left_side_bearings.len();
+            let data = FontBuilder::new()
+                .table(b"hhea", test_fonts::hhea(0, 0, metrics.len() as u16))
+                .table(b"maxp", test_fonts::maxp(num_glyphs as u16))
+                .table(b"hmtx", test_fonts::hmtx(&metrics, &left_side_bearings))
+                .build();
+
+            let font = Font::decode(&data).unwrap();
+            let hmtx = font.hmtx().unwrap();
+            prop_assert_eq!(hmtx.num_glyphs(), num_glyphs);
+
+            let last_advance = metrics[metrics.len() - 1].0;
+            let expected = metrics.iter().cloned()
+                .chain(left_side_bearings.iter().map(|&lsb| (last_advance, lsb)));
+            for (gid, (advance, lsb)) in expected.enumerate() {
+                let glyph = GlyphId(gid as u16);
+                prop_assert_eq!(hmtx.metrics(glyph), Some(GlyphMetrics {
+                    advance_width: advance,
+                    left_side_bearing: lsb,
+                }));
+            }
+            prop_assert_eq!(hmtx.metrics(GlyphId(num_glyphs as u16)), None);
+        }
+    }
+
+    #[test]
+    fn monospace_compression() {
+        let metrics = (0..2000)
+            .map(|glyph| GlyphMetrics { advance_width: if glyph == 0 { 500 } else { 600 }, left_side_bearing: glyph % 7 })
+            .collect::<Vec<_>>();
+        let (data, long) = Hmtx::encode(&metrics);
+        assert_eq!((long, data.len()), (2, 2 * 4 + 1998 * 2));
+
+        let data = FontBuilder::new()
+            .table(b"hhea", test_fonts::hhea(0, 0, long))
+            .table(b"maxp", test_fonts::maxp(2000))
+            .table(b"hmtx", data)
+            .build();
+        let font = Font::decode(&data).unwrap();
+        let hmtx = font.hmtx().unwrap();
+        assert_eq!(hmtx.num_long_metrics(), 2);
+        assert_eq!(font.metrics_for_glyphs(&[GlyphId(0), GlyphId(1), GlyphId(1999)]).unwrap(), vec![
+            metrics[0],
+            metrics[1],
+            metrics[1999],
+        ]);
+        assert_eq!(hmtx.advances().filter(|&advance| advance == 600).count(), 1999);
+
+        // A single glyph, or every glyph with one advance, needs one.
+        assert_eq!(Hmtx::encode(&metrics[1..]).1, 1);
+        assert_eq!(Hmtx::encode(&metrics[..1]), (vec![1, 0xF4, 0, 0], 1));
+        assert_eq!(Hmtx::encode(&[]), (Vec::new(), 0));
+    }
+}
@@ -0,0 +1,81 @@
+use decode::Decode;
+use primitives::GlyphId;
+use table::variation::{ItemVariationStore, DeltaSetIndexMap, NormalizedCoord};
+use error::{Error, Result};
+
+/// The horizontal metrics variations table.
+#[derive(Debug, Copy, Clone)]
+pub struct Hvar<'fnt> {
+    store: ItemVariationStore<'fnt>,
+    advance_mapping: Option<DeltaSetIndexMap<'fnt>>,
+    lsb_mapping: Option<DeltaSetIndexMap<'fnt>>,
+    rsb_mapping: Option<DeltaSetIndexMap<'fnt>>,
+}
+
+impl<'fnt> Decode<'fnt> for Hvar<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Hvar<'fnt>> {
+        required_len!(buffer, 20);
+        let version = u16::decode(buffer)?;
+        if version != 1 {
+            return Err(Error::UnsupportedVersion(version.into()))
+        }
+
+        let store_offset = u32::decode(&buffer[4..])? as usize;
+        let store = ItemVariationStore::decode(buffer.get(store_offset..).ok_or(Error::UnexpectedEof)?)?;
+
+        let mapping = |pos: usize| -> Result<Option<DeltaSetIndexMap<'fnt>>> {
+            match u32::decode(&buffer[pos..])? as usize {
+                0 => Ok(None),
+                offset => {
+                    let data = buffer.get(offset..).ok_or(Error::UnexpectedEof)?;
+                    DeltaSetIndexMap::decode(data).map(Some)
+                },
+            }
+        };
+
+        Ok(Hvar {
+            store,
+            advance_mapping: mapping(8)?,
+            lsb_mapping: mapping(12)?,
+            rsb_mapping: mapping(16)?,
+        })
+    }
+}
+
+impl<'fnt> Hvar<'fnt> {
+    pub fn item_variation_store(&self) -> ItemVariationStore<'fnt> {
+        self.store
+    }
+
+    /// The advance width delta of `glyph` at the normalized `coords`.
+    /// Without an advance mapping, the glyph id is used as the inner index
+    /// of the first ItemVariationData.
+    pub fn advance_delta(&self, glyph: GlyphId, coords: &[NormalizedCoord]) -> Result<f32> {
+        let (outer, inner) = match self.advance_mapping {
+            Some(ref mapping) => mapping.get(glyph.0 as usize).ok_or(Error::InvalidData)?,
+            None => (0, glyph.0),
+        };
+
+        self.store.delta(outer, inner, coords)
+    }
+
+    /// The left side bearing delta of `glyph`, if the table has an lsb mapping.
+    pub fn lsb_delta(&self, glyph: GlyphId, coords: &[NormalizedCoord]) -> Result<Option<f32>> {
+        self.side_bearing_delta(self.lsb_mapping, glyph, coords)
+    }
+
+    /// The right side bearing delta of `glyph`, if the table has an rsb mapping.
+    pub fn rsb_delta(&self, glyph: GlyphId, coords: &[NormalizedCoord]) -> Result<Option<f32>> {
+        self.side_bearing_delta(self.rsb_mapping, glyph, coords)
+    }
+
+    fn side_bearing_delta(&self,
+                          mapping: Option<DeltaSetIndexMap>,
+                          glyph: GlyphId,
+                          coords: &[NormalizedCoord]) -> Result<Option<f32>> {
+        match mapping.and_then(|mapping| mapping.get(glyph.0 as usize)) {
+            Some((outer, inner)) => self.store.delta(outer, inner, coords).map(Some),
+            None => Ok(None),
+        }
+    }
+}
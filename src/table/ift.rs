@@ -0,0 +1,647 @@
+//! The `IFT ` and `IFTX` tables of the Incremental Font Transfer
+//! specification.  Both tables contain a patch map, which describes the
+//! patches that can be fetched to extend an incrementally loaded font, and
+//! which of those patches have already been applied.
+
+use byteorder::{BigEndian, ByteOrder};
+use decode::{Decode, DecodeRead, EncodeSize, StaticEncodeSize};
+use primitives::{Tag, Fixed, Uint24, GlyphId};
+use error::{Error, Result};
+
+/// The `IFT ` table.
+#[derive(Debug, Copy, Clone)]
+pub struct Ift<'fnt>(PatchMap<'fnt>);
+
+/// The `IFTX` table, which has the same format as `IFT ` and allows a font
+/// to carry a second, independent patch map.
+#[derive(Debug, Copy, Clone)]
+pub struct Iftx<'fnt>(PatchMap<'fnt>);
+
+impl<'fnt> Decode<'fnt> for Ift<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Ift<'fnt>> {
+        PatchMap::decode(buffer).map(Ift)
+    }
+}
+
+impl<'fnt> Decode<'fnt> for Iftx<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Iftx<'fnt>> {
+        PatchMap::decode(buffer).map(Iftx)
+    }
+}
+
+impl<'fnt> Ift<'fnt> {
+    pub fn patch_map(&self) -> &PatchMap<'fnt> {
+        &self.0
+    }
+}
+
+impl<'fnt> Iftx<'fnt> {
+    pub fn patch_map(&self) -> &PatchMap<'fnt> {
+        &self.0
+    }
+}
+
+/// A patch map in either of its two formats.
+#[derive(Debug, Copy, Clone)]
+pub enum PatchMap<'fnt> {
+    Format1(GlyphPatchMap<'fnt>),
+    Format2(EntryPatchMap<'fnt>),
+}
+
+versioned_table!(PatchMap<'fnt>, u8 =>
+    1 => Format1(GlyphPatchMap<'fnt>),
+    2 => Format2(EntryPatchMap<'fnt>),
+);
+
+impl<'fnt> PatchMap<'fnt> {
+    /// The id which patches must match to be applied to this font.
+    pub fn compatibility_id(&self) -> [u32; 4] {
+        match *self {
+            PatchMap::Format1(ref map) => map.compatibility_id,
+            PatchMap::Format2(ref map) => map.compatibility_id,
+        }
+    }
+
+    /// The URI template used to locate patches, in UTF-8.
+    pub fn uri_template(&self) -> &'fnt [u8] {
+        match *self {
+            PatchMap::Format1(ref map) => map.uri_template,
+            PatchMap::Format2(ref map) => map.uri_template,
+        }
+    }
+}
+
+fn take<'fnt>(buf: &mut &'fnt [u8], len: usize) -> Result<&'fnt [u8]> {
+    if buf.len() < len {
+        return Err(Error::UnexpectedEof)
+    }
+
+    let (head, tail) = buf.split_at(len);
+    *buf = tail;
+    Ok(head)
+}
+
+fn compatibility_id(buf: &mut &[u8]) -> Result<[u32; 4]> {
+    Ok([buf.decode_read()?, buf.decode_read()?, buf.decode_read()?, buf.decode_read()?])
+}
+
+fn offset_data(buffer: &[u8], offset: u32) -> Result<Option<&[u8]>> {
+    match offset as usize {
+        0 => Ok(None),
+        offset => buffer.get(offset..).map(Some).ok_or(Error::UnexpectedEof),
+    }
+}
+
+/// A format 1 patch map, which maps glyph ids and features to patch
+/// entries.  Whether each entry has been applied is recorded in a bitmap.
+#[derive(Debug, Copy, Clone)]
+pub struct GlyphPatchMap<'fnt> {
+    compatibility_id: [u32; 4],
+    max_entry_index: u16,
+    max_glyph_map_entry_index: u16,
+    glyph_count: u32,
+    glyph_map: &'fnt [u8],
+    feature_map: Option<&'fnt [u8]>,
+    applied_entries: &'fnt [u8],
+    uri_template: &'fnt [u8],
+    patch_format: u8,
+}
+
+/// A feature specific entry of a format 1 patch map.  The entry applies
+/// when `feature` is requested together with any glyph mapped to an entry
+/// within `first_entry..=last_entry`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FeatureEntry {
+    pub feature: Tag,
+    pub entry_index: u16,
+    pub first_entry: u16,
+    pub last_entry: u16,
+}
+
+impl<'fnt> Decode<'fnt> for GlyphPatchMap<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<GlyphPatchMap<'fnt>> {
+        let mut buf = buffer;
+        let _format = buf.decode_read::<u8>()?;
+        let _reserved = buf.decode_read::<u32>()?;
+        let compatibility_id = compatibility_id(&mut buf)?;
+        let max_entry_index = buf.decode_read::<u16>()?;
+        let max_glyph_map_entry_index = buf.decode_read::<u16>()?;
+        let glyph_count = u32::from(buf.decode_read::<Uint24>()?);
+        let glyph_map_offset = buf.decode_read::<u32>()?;
+        let feature_map_offset = buf.decode_read::<u32>()?;
+        let applied_entries = take(&mut buf, max_entry_index as usize / 8 + 1)?;
+        let uri_template_length = buf.decode_read::<u16>()?;
+        let uri_template = take(&mut buf, uri_template_length as usize)?;
+        let patch_format = buf.decode_read::<u8>()?;
+
+        if max_glyph_map_entry_index > max_entry_index {
+            return Err(Error::InvalidData)
+        }
+
+        let glyph_map = offset_data(buffer, glyph_map_offset)?.ok_or(Error::InvalidData)?;
+        let first_mapped_glyph = u16::decode(glyph_map)? as u32;
+        let entry_size = if max_entry_index < 256 { 1 } else { 2 };
+        let mapped = glyph_count.checked_sub(first_mapped_glyph).ok_or(Error::InvalidData)?;
+        required_len!(glyph_map, 2 + mapped as usize * entry_size);
+
+        Ok(GlyphPatchMap {
+            compatibility_id,
+            max_entry_index,
+            max_glyph_map_entry_index,
+            glyph_count,
+            glyph_map,
+            feature_map: offset_data(buffer, feature_map_offset)?,
+            applied_entries,
+            uri_template,
+            patch_format,
+        })
+    }
+}
+
+impl<'fnt> GlyphPatchMap<'fnt> {
+    pub fn max_entry_index(&self) -> u16 {
+        self.max_entry_index
+    }
+
+    pub fn glyph_count(&self) -> u32 {
+        self.glyph_count
+    }
+
+    /// The format of every patch referenced by this map.
+    pub fn patch_format(&self) -> u8 {
+        self.patch_format
+    }
+
+    fn read_index(&self, buf: &mut &[u8]) -> Result<u16> {
+        if self.max_entry_index < 256 {
+            buf.decode_read::<u8>().map(u16::from)
+        } else {
+            buf.decode_read::<u16>()
+        }
+    }
+
+    /// Whether the patch for `entry_index` has already been applied.
+    pub fn is_applied(&self, entry_index: u16) -> bool {
+        let byte = self.applied_entries.get(entry_index as usize / 8).cloned().unwrap_or(0);
+        byte & (1 << (entry_index % 8)) != 0
+    }
+
+    /// The entry which adds `glyph` to the font, or `None` if the glyph is
+    /// not mapped to any patch.
+    pub fn glyph_entry(&self, glyph: GlyphId) -> Result<Option<u16>> {
+        if glyph.0 as u32 >= self.glyph_count {
+            return Err(Error::InvalidGlyphId)
+        }
+
+        let first_mapped_glyph = u16::decode(self.glyph_map)?;
+        if glyph.0 < first_mapped_glyph {
+            return Ok(None)
+        }
+
+        let entry_size = if self.max_entry_index < 256 { 1 } else { 2 };
+        let mut buf = &self.glyph_map[2 + (glyph.0 - first_mapped_glyph) as usize * entry_size..];
+        match self.read_index(&mut buf)? {
+            0 => Ok(None),
+            index if index > self.max_glyph_map_entry_index => Ok(None),
+            index => Ok(Some(index)),
+        }
+    }
+
+    /// The feature specific entries of this map.  Entries which refer to
+    /// indices beyond `max_entry_index` are skipped.
+    pub fn feature_entries(&self) -> Result<Vec<FeatureEntry>> {
+        let mut buf = match self.feature_map {
+            Some(data) => data,
+            None => return Ok(Vec::new()),
+        };
+
+        let feature_count = buf.decode_read::<u16>()?;
+        let mut features = Vec::with_capacity(feature_count as usize);
+        for _ in 0..feature_count {
+            let tag = buf.decode_read::<Tag>()?;
+            let first_new_entry_index = self.read_index(&mut buf)?;
+            let entry_map_count = self.read_index(&mut buf)?;
+            features.push((tag, first_new_entry_index, entry_map_count));
+        }
+
+        let mut entries = Vec::new();
+        for (feature, first_new_entry_index, entry_map_count) in features {
+            for i in 0..entry_map_count {
+                let first_entry = self.read_index(&mut buf)?;
+                let last_entry = self.read_index(&mut buf)?;
+                let entry_index = match first_new_entry_index.checked_add(i) {
+                    Some(index) if index <= self.max_entry_index => index,
+                    _ => continue,
+                };
+
+                entries.push(FeatureEntry { feature, entry_index, first_entry, last_entry });
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Mark `entry_index` as applied in the raw bytes of a format 1 patch map,
+/// which should be done after the entry's patch has been applied to a font.
+pub fn mark_applied(table: &mut [u8], entry_index: u16) -> Result<()> {
+    // The applied entries bitmap immediately follows the fixed size header.
+    const APPLIED_ENTRIES_OFFSET: usize = 36;
+    required_len!(table, APPLIED_ENTRIES_OFFSET);
+    if table[0] != 1 {
+        return Err(Error::UnsupportedVersion(table[0].into()))
+    }
+
+    if entry_index > BigEndian::read_u16(&table[21..]) {
+        return Err(Error::InvalidData)
+    }
+
+    table[APPLIED_ENTRIES_OFFSET + entry_index as usize / 8] |= 1 << (entry_index % 8);
+    Ok(())
+}
+
+/// A format 2 patch map, which is a list of entries that each describe
+/// the codepoints, features and design space covered by a patch.
+#[derive(Debug, Copy, Clone)]
+pub struct EntryPatchMap<'fnt> {
+    compatibility_id: [u32; 4],
+    default_patch_format: u8,
+    entry_count: u32,
+    entries: &'fnt [u8],
+    entry_id_strings: Option<&'fnt [u8]>,
+    uri_template: &'fnt [u8],
+}
+
+impl<'fnt> Decode<'fnt> for EntryPatchMap<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<EntryPatchMap<'fnt>> {
+        let mut buf = buffer;
+        let _format = buf.decode_read::<u8>()?;
+        let _reserved = buf.decode_read::<u32>()?;
+        let compatibility_id = compatibility_id(&mut buf)?;
+        let default_patch_format = buf.decode_read::<u8>()?;
+        let entry_count = u32::from(buf.decode_read::<Uint24>()?);
+        let entries_offset = buf.decode_read::<u32>()?;
+        let entry_id_string_offset = buf.decode_read::<u32>()?;
+        let uri_template_length = buf.decode_read::<u16>()?;
+        let uri_template = take(&mut buf, uri_template_length as usize)?;
+
+        Ok(EntryPatchMap {
+            compatibility_id,
+            default_patch_format,
+            entry_count,
+            entries: offset_data(buffer, entries_offset)?.ok_or(Error::InvalidData)?,
+            entry_id_strings: offset_data(buffer, entry_id_string_offset)?,
+            uri_template,
+        })
+    }
+}
+
+/// The id of a format 2 entry, which is substituted into the URI template.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EntryId<'fnt> {
+    Numeric(u32),
+    String(&'fnt [u8]),
+}
+
+/// A segment of the design space along a single axis.
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq)]
+pub struct DesignSpaceSegment {
+    pub axis_tag: Tag,
+    pub start: Fixed,
+    pub end: Fixed,
+}
+
+/// A single entry of a format 2 patch map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MappingEntry<'fnt> {
+    pub id: EntryId<'fnt>,
+    pub features: Vec<Tag>,
+    pub design_space: Vec<DesignSpaceSegment>,
+    /// The indices of earlier entries whose subset definitions are
+    /// combined with this entry's.
+    pub copy_indices: Vec<u32>,
+    pub patch_format: u8,
+    /// Sorted and non-overlapping inclusive codepoint ranges.
+    pub codepoints: Vec<(u32, u32)>,
+    /// Set once the entry's patch has been applied, or when it should
+    /// otherwise not be considered.
+    pub ignored: bool,
+}
+
+impl<'fnt> MappingEntry<'fnt> {
+    pub fn contains_codepoint(&self, codepoint: u32) -> bool {
+        self.codepoints.binary_search_by(|&(start, end)| {
+            if end < codepoint {
+                ::std::cmp::Ordering::Less
+            } else if start > codepoint {
+                ::std::cmp::Ordering::Greater
+            } else {
+                ::std::cmp::Ordering::Equal
+            }
+        }).is_ok()
+    }
+}
+
+mod entry_flags {
+    pub const FEATURES_AND_DESIGN_SPACE: u8 = 1 << 0;
+    pub const COPY_INDICES: u8 = 1 << 1;
+    pub const ENTRY_ID_DELTA: u8 = 1 << 2;
+    pub const PATCH_FORMAT: u8 = 1 << 3;
+    pub const CODEPOINTS_MASK: u8 = 0b11 << 4;
+    pub const IGNORED: u8 = 1 << 6;
+}
+
+impl<'fnt> EntryPatchMap<'fnt> {
+    pub fn default_patch_format(&self) -> u8 {
+        self.default_patch_format
+    }
+
+    pub fn entry_count(&self) -> u32 {
+        self.entry_count
+    }
+
+    /// Decode every entry of the map.  Numeric ids start at one and
+    /// increment by one, adjusted by each entry's id delta.  String ids
+    /// repeat the previous entry's id unless a new length is given.
+    pub fn entries(&self) -> Result<Vec<MappingEntry<'fnt>>> {
+        use self::entry_flags::*;
+
+        let mut buf = self.entries;
+        let mut strings = self.entry_id_strings;
+        let mut last_id = EntryId::Numeric(0);
+        if strings.is_some() {
+            last_id = EntryId::String(&[]);
+        }
+
+        let mut entries = Vec::new();
+        for _ in 0..self.entry_count {
+            let flags = buf.decode_read::<u8>()?;
+
+            let mut features = Vec::new();
+            let mut design_space = Vec::new();
+            if flags & FEATURES_AND_DESIGN_SPACE != 0 {
+                let feature_count = buf.decode_read::<u8>()?;
+                for _ in 0..feature_count {
+                    features.push(buf.decode_read::<Tag>()?);
+                }
+
+                let segment_count = buf.decode_read::<u16>()?;
+                for _ in 0..segment_count {
+                    design_space.push(buf.decode_read::<DesignSpaceSegment>()?);
+                }
+            }
+
+            let mut copy_indices = Vec::new();
+            if flags & COPY_INDICES != 0 {
+                let copy_count = buf.decode_read::<u8>()?;
+                for _ in 0..copy_count {
+                    copy_indices.push(u32::from(buf.decode_read::<Uint24>()?));
+                }
+            }
+
+            let id = match (last_id, strings.as_mut()) {
+                (EntryId::String(previous), Some(strings)) => {
+                    if flags & ENTRY_ID_DELTA != 0 {
+                        let length = buf.decode_read::<u16>()?;
+                        EntryId::String(take(strings, length as usize)?)
+                    } else {
+                        EntryId::String(previous)
+                    }
+                },
+                (EntryId::Numeric(previous), _) => {
+                    let mut delta = 0;
+                    if flags & ENTRY_ID_DELTA != 0 {
+                        delta = BigEndian::read_i24(take(&mut buf, 3)?);
+                    }
+
+                    let id = i64::from(previous) + 1 + i64::from(delta);
+                    if id < 0 || id > i64::from(u32::MAX) {
+                        return Err(Error::InvalidData)
+                    }
+
+                    EntryId::Numeric(id as u32)
+                },
+                _ => unreachable!(),
+            };
+            last_id = id;
+
+            let mut patch_format = self.default_patch_format;
+            if flags & PATCH_FORMAT != 0 {
+                patch_format = buf.decode_read::<u8>()?;
+            }
+
+            let codepoints = match (flags & CODEPOINTS_MASK) >> 4 {
+                0 => Vec::new(),
+                encoding => {
+                    let bias = match encoding {
+                        1 => 0,
+                        2 => u32::from(buf.decode_read::<u16>()?),
+                        _ => u32::from(buf.decode_read::<Uint24>()?),
+                    };
+
+                    sparse_bit_set(&mut buf, bias)?
+                },
+            };
+
+            entries.push(MappingEntry {
+                id,
+                features,
+                design_space,
+                copy_indices,
+                patch_format,
+                codepoints,
+                ignored: flags & IGNORED != 0,
+            });
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Reads bits least significant first from each byte.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn read(&mut self, count: u32) -> Result<u32> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = *self.data.get(self.position / 8).ok_or(Error::UnexpectedEof)?;
+            if byte & (1 << (self.position % 8)) != 0 {
+                value |= 1 << i;
+            }
+            self.position += 1;
+        }
+
+        Ok(value)
+    }
+}
+
+/// Decode a sparse bit set into inclusive ranges, each offset by `bias`.
+/// The set is a tree stored in breadth first order, where a node whose
+/// bits are all zero represents a completely filled subtree.
+fn sparse_bit_set(buf: &mut &[u8], bias: u32) -> Result<Vec<(u32, u32)>> {
+    let header = buf.decode_read::<u8>()?;
+    let branch_factor: u64 = match header & 0b11 {
+        0 => 2,
+        1 => 4,
+        2 => 8,
+        _ => 32,
+    };
+    let height = u32::from((header >> 2) & 0b1_1111);
+    if height == 0 {
+        return Ok(Vec::new())
+    }
+
+    let max_value = u64::from(u32::MAX - bias);
+    let mut reader = BitReader { data: buf, position: 0 };
+    let mut ranges = Vec::new();
+    let mut queue = ::std::collections::VecDeque::new();
+    queue.push_back((0u64, 1u32));
+
+    while let Some((start, depth)) = queue.pop_front() {
+        let node = reader.read(branch_factor as u32)?;
+        let child_span = branch_factor.checked_pow(height - depth).ok_or(Error::InvalidData)?;
+
+        if node == 0 {
+            let end = start + child_span * branch_factor - 1;
+            if end > max_value {
+                return Err(Error::InvalidData)
+            }
+            ranges.push((start as u32 + bias, end as u32 + bias));
+            continue
+        }
+
+        for i in 0..branch_factor {
+            if node & (1 << i) == 0 {
+                continue
+            }
+
+            let child = start + i * child_span;
+            if depth == height {
+                if child > max_value {
+                    return Err(Error::InvalidData)
+                }
+                ranges.push((child as u32 + bias, child as u32 + bias));
+            } else {
+                queue.push_back((child, depth + 1));
+            }
+        }
+    }
+
+    let consumed = reader.position.div_ceil(8);
+    *buf = &buf[consumed..];
+
+    ranges.sort();
+    let mut merged: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        if let Some(last) = merged.last_mut() {
+            if start <= last.1.saturating_add(1) {
+                last.1 = last.1.max(end);
+                continue
+            }
+        }
+        merged.push((start, end));
+    }
+
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glyph_patch_map() {
+        let mut table = vec![
+            1, 0, 0, 0, 0,
+            0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4,
+            0, 3,       // max entry index
+            0, 2,       // max glyph map entry index
+            0, 0, 6,    // glyph count
+            0, 0, 0, 43, // glyph map
+            0, 0, 0, 50, // feature map
+            0b0000_0010,
+            0, 3, b'x', b'{', b'}',
+            3,
+        ];
+        // The glyph map, where glyph 1 is mapped to the entry beyond the
+        // glyph map maximum.
+        table.extend(&[0, 1, 3, 1, 2, 0, 1]);
+        // One feature with one entry map record covering entries 1 and 2.
+        table.extend(&[0, 1, b's', b'm', b'c', b'p', 3, 1, 1, 2]);
+
+        let map = match PatchMap::decode(&table).expect("failed to read patch map") {
+            PatchMap::Format1(map) => map,
+            _ => panic!("expected a format 1 patch map"),
+        };
+
+        // The format is read from the table into the error.
+        let mut unknown = table.clone();
+        unknown[0] = 7;
+        assert_eq!(PatchMap::decode(&unknown).err(), Some(Error::UnsupportedVersion(7)));
+
+        assert_eq!(map.compatibility_id, [1, 2, 3, 4]);
+        assert_eq!(map.uri_template, b"x{}");
+        assert_eq!(map.patch_format(), 3);
+        assert!(map.is_applied(1) && !map.is_applied(2));
+
+        let entries = (0..6).map(|gid| map.glyph_entry(GlyphId(gid)).unwrap()).collect::<Vec<_>>();
+        assert_eq!(entries, vec![None, None, Some(1), Some(2), None, Some(1)]);
+        assert_eq!(map.glyph_entry(GlyphId(6)), Err(Error::InvalidGlyphId));
+
+        let feature = map.feature_entries().unwrap();
+        assert_eq!(feature, vec![FeatureEntry {
+            feature: Tag(*b"smcp"),
+            entry_index: 3,
+            first_entry: 1,
+            last_entry: 2,
+        }]);
+
+        mark_applied(&mut table, 3).unwrap();
+        match PatchMap::decode(&table).unwrap() {
+            PatchMap::Format1(map) => assert!(map.is_applied(3)),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn entry_patch_map() {
+        let mut table = vec![
+            2, 0, 0, 0, 0,
+            0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4,
+            1,          // default patch format
+            0, 0, 2,    // entry count
+            0, 0, 0, 36, // entries
+            0, 0, 0, 0,  // entry id strings
+            0, 1, b'x',
+        ];
+        // Codepoints {2, 3, 5} with a branch factor of 4 and height 2,
+        // biased by 0x100.
+        table.extend(&[0b0010_0000, 0x01, 0x00, 0b0000_1001, 0b1100_0011, 0b0000_0010]);
+        // Features, an id delta of +4, a patch format override and a
+        // completely filled tree of height 1.
+        table.extend(&[0b0101_1101, 1, b'l', b'i', b'g', b'a', 0, 0]);
+        table.extend(&[0, 0, 4, 2, 0b0000_0101, 0]);
+
+        let map = match PatchMap::decode(&table).expect("failed to read patch map") {
+            PatchMap::Format2(map) => map,
+            _ => panic!("expected a format 2 patch map"),
+        };
+
+        let entries = map.entries().expect("failed to read entries");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].id, EntryId::Numeric(1));
+        assert_eq!(entries[0].patch_format, 1);
+        assert_eq!(entries[0].codepoints, vec![(0x102, 0x103), (0x105, 0x105)]);
+        assert!(entries[0].contains_codepoint(0x103) && !entries[0].contains_codepoint(0x104));
+
+        assert_eq!(entries[1].id, EntryId::Numeric(6));
+        assert_eq!(entries[1].features, vec![Tag(*b"liga")]);
+        assert_eq!(entries[1].patch_format, 2);
+        assert_eq!(entries[1].codepoints, vec![(0, 3)]);
+        assert!(entries[1].ignored);
+    }
+}
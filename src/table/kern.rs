@@ -0,0 +1,187 @@
+use std::cmp::Ordering;
+
+use decode::{Decode, Decode1, StaticEncodeSize, EncodeSize};
+use primitives::{Array, FWord, GlyphId};
+use error::{Error, Result};
+
+/// The legacy kerning table, which older fonts use instead of the `kern`
+/// feature of `GPOS`.  Both the Windows header (version 0) and the Apple
+/// header (version 1.0) are read, but only format 0 subtables, the
+/// ordered pair lists, are searched.
+#[derive(Debug, Clone)]
+pub struct Kern<'fnt> {
+    subtables: Vec<KernSubtable<'fnt>>,
+}
+
+/// A subtable of `kern` with its coverage flags.  Apple subtables with
+/// variation values are left out, since they need a tuple to apply.
+#[derive(Debug, Copy, Clone)]
+pub struct KernSubtable<'fnt> {
+    pub format: u8,
+    pub horizontal: bool,
+    pub cross_stream: bool,
+    /// The values are minimums rather than adjustments, which only the
+    /// Windows header can express.
+    pub minimum: bool,
+    /// The value replaces the kerning of the subtables before, rather
+    /// than adding to it.
+    pub replace: bool,
+    data: &'fnt [u8],
+}
+
+/// A kerning value for a pair of glyphs, from a format 0 subtable.
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KernPair {
+    pub left: GlyphId,
+    pub right: GlyphId,
+    pub value: FWord,
+}
+
+impl<'fnt> Decode<'fnt> for Kern<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Kern<'fnt>> {
+        let apple = match u16::decode(buffer)? {
+            0 => false,
+            1 if u32::decode(buffer)? == 0x0001_0000 => true,
+            version => return Err(Error::UnsupportedVersion(version.into())),
+        };
+
+        required_len!(buffer, if apple { 8 } else { 4 });
+        let (count, mut pos, header_len) = if apple {
+            (u32::decode(&buffer[4..])? as usize, 8, 8)
+        } else {
+            (u16::decode(&buffer[2..])? as usize, 4, 6)
+        };
+
+        let mut subtables = Vec::new();
+        for index in 0..count {
+            let header = buffer.get(pos..).ok_or(Error::UnexpectedEof)?;
+            required_len!(header, header_len);
+            let (length, coverage) = if apple {
+                (u32::decode(header)? as usize, u16::decode(&header[4..])?)
+            } else {
+                (u16::decode(&header[2..])? as usize, u16::decode(&header[4..])?)
+            };
+
+            // The 16-bit length of a Windows subtable overflows for large
+            // pair lists, so the last subtable extends to the end.
+            let end = if index + 1 == count { buffer.len() } else { pos + length };
+            let data = buffer.get(pos + header_len..end).ok_or(Error::UnexpectedEof)?;
+            pos += length.max(header_len);
+
+            let subtable = if apple {
+                if coverage & 0x2000 != 0 {
+                    continue
+                }
+                KernSubtable {
+                    format: coverage as u8,
+                    horizontal: coverage & 0x8000 == 0,
+                    cross_stream: coverage & 0x4000 != 0,
+                    minimum: false,
+                    replace: false,
+                    data,
+                }
+            } else {
+                KernSubtable {
+                    format: (coverage >> 8) as u8,
+                    horizontal: coverage & 0x01 != 0,
+                    minimum: coverage & 0x02 != 0,
+                    cross_stream: coverage & 0x04 != 0,
+                    replace: coverage & 0x08 != 0,
+                    data,
+                }
+            };
+            subtables.push(subtable);
+        }
+
+        Ok(Kern { subtables })
+    }
+}
+
+impl<'fnt> Kern<'fnt> {
+    pub fn subtables(&self) -> &[KernSubtable<'fnt>] {
+        &self.subtables
+    }
+
+    /// The horizontal kerning between `left` and `right` in font units,
+    /// summed over the horizontal format 0 subtables which adjust the
+    /// advance.
+    pub fn kerning(&self, left: GlyphId, right: GlyphId) -> Result<i32> {
+        let mut kerning = 0;
+        for subtable in &self.subtables {
+            if !subtable.horizontal || subtable.cross_stream || subtable.minimum {
+                continue
+            }
+
+            if let Some(value) = subtable.pair_value(left, right)? {
+                if subtable.replace {
+                    kerning = 0;
+                }
+                kerning += i32::from(i16::from(value));
+            }
+        }
+
+        Ok(kerning)
+    }
+}
+
+impl<'fnt> KernSubtable<'fnt> {
+    /// The pairs of a format 0 subtable, sorted by left and right glyph.
+    pub fn pairs(&self) -> Result<Option<Array<'fnt, KernPair>>> {
+        if self.format != 0 {
+            return Ok(None)
+        }
+
+        required_len!(self.data, 8);
+        let count = u16::decode(self.data)? as usize;
+        Array::decode(&self.data[8..], count).map(Some)
+    }
+
+    /// The value of a format 0 subtable for the pair, found by binary
+    /// search.
+    pub fn pair_value(&self, left: GlyphId, right: GlyphId) -> Result<Option<FWord>> {
+        let pairs = match self.pairs()? {
+            Some(pairs) => pairs,
+            None => return Ok(None),
+        };
+
+        let key = (left.0, right.0);
+        let (mut low, mut high) = (0, pairs.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            let pair = pairs.get(mid).ok_or(Error::UnexpectedEof)?;
+            match (pair.left.0, pair.right.0).cmp(&key) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Ok(Some(pair.value)),
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_fonts;
+
+    #[test]
+    fn kerning_pairs() {
+        let data = test_fonts::kern(&[(1, 2, -80), (2, 1, 40), (2, 2, 5)]);
+        let kern = Kern::decode(&data).unwrap();
+        assert_eq!(kern.subtables().len(), 1);
+        assert_eq!(kern.kerning(GlyphId(1), GlyphId(2)), Ok(-80));
+        assert_eq!(kern.kerning(GlyphId(2), GlyphId(1)), Ok(40));
+        assert_eq!(kern.kerning(GlyphId(2), GlyphId(2)), Ok(5));
+        assert_eq!(kern.kerning(GlyphId(1), GlyphId(1)), Ok(0));
+
+        // The same subtable under the Apple header.
+        let windows = test_fonts::kern(&[(1, 2, -80)]);
+        let mut apple = vec![0, 1, 0, 0, 0, 0, 0, 1];
+        apple.extend_from_slice(&[0, 0, 0, windows.len() as u8 - 2, 0, 0, 0, 0]);
+        apple.extend_from_slice(&windows[10..]);
+        let kern = Kern::decode(&apple).unwrap();
+        assert_eq!(kern.kerning(GlyphId(1), GlyphId(2)), Ok(-80));
+        assert!(Kern::decode(&[0, 2, 0, 0]).is_err());
+    }
+}
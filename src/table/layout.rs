@@ -0,0 +1,1281 @@
+//! The common table formats shared by the OpenType layout tables.
+
+use std::fmt;
+use std::mem;
+use std::ops::Deref;
+
+use decode::{Decode, Decode1, StaticEncodeSize, EncodeSize};
+use encode::EncodeBuf;
+use glyph_set::GlyphSet;
+use primitives::{Array, Discarded, FixedVersion, GlyphId, Tag, Uint24};
+use tags::scripts;
+use error::{Error, Result};
+use std::marker::PhantomData;
+
+/// The header shared by `GSUB` and `GPOS`, which locates the script,
+/// feature and lookup lists.
+#[derive(Debug, Copy, Clone)]
+pub struct LayoutTable<'fnt> {
+    pub version: FixedVersion,
+    script_list: &'fnt [u8],
+    feature_list: &'fnt [u8],
+    lookup_list: &'fnt [u8],
+}
+
+impl<'fnt> Decode<'fnt> for LayoutTable<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<LayoutTable<'fnt>> {
+        required_len!(buffer, 10);
+        let version = FixedVersion::decode(buffer)?;
+        if version.major != 1 {
+            return Err(Error::UnsupportedVersion((version.major as u32) << 16 | version.minor as u32))
+        }
+
+        let list = |pos: usize| -> Result<&'fnt [u8]> {
+            let offset = u16::decode(&buffer[pos..])? as usize;
+            buffer.get(offset..).ok_or(Error::UnexpectedEof)
+        };
+
+        Ok(LayoutTable {
+            version,
+            script_list: list(4)?,
+            feature_list: list(6)?,
+            lookup_list: list(8)?,
+        })
+    }
+}
+
+impl<'fnt> LayoutTable<'fnt> {
+    /// The scripts of the script list, in the order they are stored.
+    pub fn scripts(&self) -> Result<TagList<'fnt>> {
+        TagList::decode(self.script_list)
+    }
+
+    /// The features of the feature list.  The same tag may appear more
+    /// than once, for example with different lookups per language system.
+    pub fn features(&self) -> Result<TagList<'fnt>> {
+        TagList::decode(self.feature_list)
+    }
+
+    pub fn lookup_list(&self) -> &'fnt [u8] {
+        self.lookup_list
+    }
+
+    /// The parameters of the feature at `index` of the feature list, or
+    /// `None` if it has none or its tag is not one of the features which
+    /// take parameters.
+    ///
+    /// Early fonts stored the offset of the `size` parameters relative to
+    /// the feature list rather than the feature table, so that offset is
+    /// tried when the parameters are not valid at the other.
+    pub fn feature_params(&self, index: usize) -> Result<Option<FeatureParams>> {
+        let record = self.features()?.records().get(index).ok_or(Error::InvalidData)?;
+        let feature = self.feature_list.get(record.offset as usize..).ok_or(Error::UnexpectedEof)?;
+        let offset = u16::decode(feature)? as usize;
+        if offset == 0 {
+            return Ok(None)
+        }
+
+        let params = feature.get(offset..).ok_or(Error::UnexpectedEof);
+        match &record.tag.0 {
+            b"size" => {
+                let size = params.and_then(FeatureParams::decode_size).ok().filter(FeatureParams::is_valid_size);
+                match size {
+                    Some(size) => Ok(Some(size)),
+                    None => self.feature_list.get(offset..)
+                        .ok_or(Error::UnexpectedEof)
+                        .and_then(FeatureParams::decode_size)
+                        .map(|size| Some(size).filter(FeatureParams::is_valid_size)),
+                }
+            },
+            [b's', b's', ..] if is_numbered(record.tag) => {
+                let params = params?;
+                required_len!(params, 4);
+                Ok(Some(FeatureParams::StylisticSet { ui_name_id: u16::decode(&params[2..])? }))
+            },
+            [b'c', b'v', ..] if is_numbered(record.tag) => {
+                let params = params?;
+                required_len!(params, 14);
+                let count = u16::decode(&params[12..])? as usize;
+                Ok(Some(FeatureParams::CharacterVariant {
+                    label_name_id: u16::decode(&params[2..])?,
+                    tooltip_name_id: u16::decode(&params[4..])?,
+                    sample_text_name_id: u16::decode(&params[6..])?,
+                    num_named_parameters: u16::decode(&params[8..])?,
+                    first_param_label_name_id: u16::decode(&params[10..])?,
+                    characters: Array::<Uint24>::decode(&params[14..], count)?.into_iter().map(u32::from).collect(),
+                }))
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// The first of `candidates` in the script list, such as the tags of
+    /// `Script::tags`, or `DFLT` if the font lists none of them.
+    pub fn select_script(&self, candidates: &[Tag]) -> Result<Tag> {
+        let scripts = self.scripts()?;
+        Ok(candidates.iter().cloned()
+            .find(|&candidate| scripts.tags().any(|tag| tag == candidate))
+            .unwrap_or(scripts::DEFAULT))
+    }
+
+    /// The indices of the lookups used by `features` under the default
+    /// language system of `script`, or of `DFLT` if the font does not
+    /// list `script`.  The required feature is always included.  Lookups
+    /// are returned in lookup list order, which is the order they apply.
+    pub fn lookup_indices(&self, script: Tag, features: &[Tag]) -> Result<Vec<u16>> {
+        let scripts = self.scripts()?;
+        let find = |tag: Tag| scripts.records().into_iter().find(|record| record.tag == tag);
+        let record = match find(script).or_else(|| find(scripts::DEFAULT)) {
+            Some(record) => record,
+            None => return Ok(Vec::new()),
+        };
+
+        let script_table = self.script_list.get(record.offset as usize..).ok_or(Error::UnexpectedEof)?;
+        let default_lang_sys = u16::decode(script_table)? as usize;
+        if default_lang_sys == 0 {
+            return Ok(Vec::new())
+        }
+
+        let lang_sys = script_table.get(default_lang_sys..).ok_or(Error::UnexpectedEof)?;
+        required_len!(lang_sys, 6);
+        let required_feature = u16::decode(&lang_sys[2..])?;
+        let count = u16::decode(&lang_sys[4..])? as usize;
+        let mut feature_indices = Array::<u16>::decode(&lang_sys[6..], count)?.into_iter().collect::<Vec<_>>();
+        if required_feature != 0xFFFF {
+            feature_indices.push(required_feature);
+        }
+
+        let feature_records = self.features()?.records();
+        let mut lookups = Vec::new();
+        for index in feature_indices {
+            let record = feature_records.get(index as usize).ok_or(Error::InvalidData)?;
+            if index != required_feature && !features.contains(&record.tag) {
+                continue
+            }
+
+            let feature = self.feature_list.get(record.offset as usize..).ok_or(Error::UnexpectedEof)?;
+            required_len!(feature, 4);
+            let count = u16::decode(&feature[2..])? as usize;
+            lookups.extend(Array::<u16>::decode(&feature[4..], count)?);
+        }
+
+        lookups.sort();
+        lookups.dedup();
+        Ok(lookups)
+    }
+
+    /// Every language system of the script list: the default of each
+    /// script, as the language `dflt`, followed by its languages.
+    pub fn language_systems(&self) -> Result<Vec<LanguageSystem>> {
+        let mut systems = Vec::new();
+        for record in self.scripts()?.records() {
+            let script = self.script_list.get(record.offset as usize..).ok_or(Error::UnexpectedEof)?;
+            required_len!(script, 4);
+            let count = u16::decode(&script[2..])? as usize;
+            let languages = Array::<TagRecord>::decode(&script[4..], count)?.into_iter()
+                .map(|language| (language.tag, language.offset));
+            let default = Some((Tag::new(*b"dflt"), u16::decode(script)?)).filter(|&(_, offset)| offset != 0);
+            for (language, offset) in default.into_iter().chain(languages) {
+                let lang_sys = script.get(offset as usize..).ok_or(Error::UnexpectedEof)?;
+                required_len!(lang_sys, 6);
+                let required_feature = u16::decode(&lang_sys[2..])?;
+                let count = u16::decode(&lang_sys[4..])? as usize;
+                systems.push(LanguageSystem {
+                    script: record.tag,
+                    language,
+                    required_feature: Some(required_feature).filter(|&index| index != 0xFFFF),
+                    feature_indices: Array::<u16>::decode(&lang_sys[6..], count)?.into_iter().collect(),
+                });
+            }
+        }
+        Ok(systems)
+    }
+
+    /// The lookup indices of the feature at `index` of the feature list.
+    pub fn feature_lookups(&self, index: usize) -> Result<Vec<u16>> {
+        let record = self.features()?.records().get(index).ok_or(Error::InvalidData)?;
+        let feature = self.feature_list.get(record.offset as usize..).ok_or(Error::UnexpectedEof)?;
+        required_len!(feature, 4);
+        let count = u16::decode(&feature[2..])? as usize;
+        Ok(Array::<u16>::decode(&feature[4..], count)?.into_iter().collect())
+    }
+
+    /// The number of lookups in the lookup list.
+    pub fn lookup_count(&self) -> Result<u16> {
+        u16::decode(self.lookup_list)
+    }
+
+    /// The lookup at `index`.  Subtables of `extension_type` are replaced
+    /// by the subtables they point to, which all share one type.
+    fn lookup(&self, index: u16, extension_type: u16) -> Result<Lookup<'fnt>> {
+        if index >= self.lookup_count()? {
+            return Err(Error::InvalidData)
+        }
+
+        let offset = self.lookup_list.get(2 + index as usize * 2..).ok_or(Error::UnexpectedEof)?;
+        let offset = u16::decode(offset)? as usize;
+        let lookup = self.lookup_list.get(offset..).ok_or(Error::UnexpectedEof)?;
+        required_len!(lookup, 6);
+        let mut lookup_type = u16::decode(lookup)?;
+        let flag = u16::decode(&lookup[2..])?;
+        let count = u16::decode(&lookup[4..])? as usize;
+
+        let mark_filtering_set = if flag & Lookup::USE_MARK_FILTERING_SET != 0 {
+            Some(u16::decode(lookup.get(6 + count * 2..).ok_or(Error::UnexpectedEof)?)?)
+        } else {
+            None
+        };
+
+        let mut subtables = Vec::with_capacity(count);
+        for offset in Array::<u16>::decode(&lookup[6..], count)? {
+            let mut subtable = lookup.get(offset as usize..).ok_or(Error::UnexpectedEof)?;
+            if lookup_type == extension_type {
+                required_len!(subtable, 8);
+                let extension_offset = u32::decode(&subtable[4..])? as usize;
+                let extended_type = u16::decode(&subtable[2..])?;
+                subtable = subtable.get(extension_offset..).ok_or(Error::UnexpectedEof)?;
+                if subtables.is_empty() {
+                    lookup_type = extended_type;
+                } else if extended_type != lookup_type {
+                    return Err(Error::InvalidData)
+                }
+            }
+            subtables.push(subtable);
+        }
+
+        Ok(Lookup { lookup_type, flag, mark_filtering_set, subtables })
+    }
+}
+
+/// The features a script uses for one language, as indices into the
+/// feature list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageSystem {
+    pub script: Tag,
+    /// The language, or `dflt` for the default language system.
+    pub language: Tag,
+    /// The feature which is always applied, if any.
+    pub required_feature: Option<u16>,
+    pub feature_indices: Vec<u16>,
+}
+
+/// Whether the last two characters of `tag` are digits, as in `ss01`.
+fn is_numbered(tag: Tag) -> bool {
+    tag.0[2].is_ascii_digit() && tag.0[3].is_ascii_digit()
+}
+
+/// The parameters of a feature, which depend on its tag.  Name IDs refer
+/// to the `name` table, and are zero when the font gives no name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeatureParams {
+    /// The `size` feature, with sizes in decipoints.  A font which is one
+    /// of a family of optical sizes gives the range of sizes it is meant
+    /// for; otherwise the range and subfamily are zero.
+    Size {
+        design_size: u16,
+        subfamily_id: u16,
+        subfamily_name_id: u16,
+        range_start: u16,
+        range_end: u16,
+    },
+    /// A stylistic set, `ss01` to `ss20`.
+    StylisticSet { ui_name_id: u16 },
+    /// A character variant, `cv01` to `cv99`.
+    CharacterVariant {
+        label_name_id: u16,
+        tooltip_name_id: u16,
+        sample_text_name_id: u16,
+        /// The number of alternates with a label, which have consecutive
+        /// name IDs from `first_param_label_name_id`.
+        num_named_parameters: u16,
+        first_param_label_name_id: u16,
+        /// The characters the variant applies to.
+        characters: Vec<u32>,
+    },
+}
+
+impl FeatureParams {
+    fn decode_size(params: &[u8]) -> Result<FeatureParams> {
+        required_len!(params, 10);
+        Ok(FeatureParams::Size {
+            design_size: u16::decode(params)?,
+            subfamily_id: u16::decode(&params[2..])?,
+            subfamily_name_id: u16::decode(&params[4..])?,
+            range_start: u16::decode(&params[6..])?,
+            range_end: u16::decode(&params[8..])?,
+        })
+    }
+
+    /// Whether `size` parameters are consistent: a design size, and either
+    /// no subfamily or a range containing it with a font-specific name.
+    fn is_valid_size(&self) -> bool {
+        match *self {
+            FeatureParams::Size { design_size, subfamily_id, subfamily_name_id, range_start, range_end } => {
+                design_size != 0 && (
+                    (subfamily_id, subfamily_name_id, range_start, range_end) == (0, 0, 0, 0) ||
+                    (range_start <= design_size && design_size <= range_end && (256..=32767).contains(&subfamily_name_id))
+                )
+            },
+            _ => false,
+        }
+    }
+
+    /// The name IDs of the labels of the named alternates of a character
+    /// variant, in order.
+    pub fn param_label_name_ids(&self) -> ::std::ops::Range<u16> {
+        match *self {
+            FeatureParams::CharacterVariant { num_named_parameters, first_param_label_name_id, .. } => {
+                first_param_label_name_id..first_param_label_name_id.saturating_add(num_named_parameters)
+            },
+            _ => 0..0,
+        }
+    }
+}
+
+/// A lookup of the lookup list, whose subtables are tried in order until
+/// one applies.
+#[derive(Debug, Clone)]
+pub struct Lookup<'fnt> {
+    pub lookup_type: u16,
+    pub flag: u16,
+    /// The index of the `GDEF` mark glyph set the lookup is limited to,
+    /// when its flag has `USE_MARK_FILTERING_SET`.
+    pub mark_filtering_set: Option<u16>,
+    subtables: Vec<&'fnt [u8]>,
+}
+
+impl<'fnt> Lookup<'fnt> {
+    pub const RIGHT_TO_LEFT: u16 = 0x0001;
+    pub const IGNORE_BASE_GLYPHS: u16 = 0x0002;
+    pub const IGNORE_LIGATURES: u16 = 0x0004;
+    pub const IGNORE_MARKS: u16 = 0x0008;
+    pub const USE_MARK_FILTERING_SET: u16 = 0x0010;
+    pub const MARK_ATTACHMENT_TYPE: u16 = 0xFF00;
+
+    /// The `GDEF` mark attachment class the lookup is limited to, or 0 if
+    /// it applies to marks of every class.
+    pub fn mark_attachment_type(&self) -> u16 {
+        (self.flag & Lookup::MARK_ATTACHMENT_TYPE) >> 8
+    }
+
+    /// The data of each subtable, starting at its format field.
+    pub fn subtables(&self) -> &[&'fnt [u8]] {
+        &self.subtables
+    }
+}
+
+/// A `ScriptList` or `FeatureList`: an array of tagged offsets, relative
+/// to the start of the list.
+#[derive(Decode, Debug, Copy, Clone)]
+#[allow(dead_code)]
+pub struct TagList<'fnt> {
+    count: Discarded<u16>,
+    #[WithParam = "count as usize"]
+    records: Array<'fnt, TagRecord>,
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TagRecord {
+    pub tag: Tag,
+    pub offset: u16,
+}
+
+impl<'fnt> TagList<'fnt> {
+    pub fn records(&self) -> Array<'fnt, TagRecord> {
+        self.records
+    }
+
+    pub fn tags(&self) -> impl Iterator<Item = Tag> + 'fnt {
+        self.records.into_iter().map(|record| record.tag)
+    }
+}
+
+/// The glyph substitution table.
+#[derive(Debug, Copy, Clone)]
+pub struct Gsub<'fnt>(LayoutTable<'fnt>);
+
+/// The glyph positioning table.
+#[derive(Debug, Copy, Clone)]
+pub struct Gpos<'fnt>(LayoutTable<'fnt>);
+
+impl<'fnt> Decode<'fnt> for Gsub<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Gsub<'fnt>> {
+        LayoutTable::decode(buffer).map(Gsub)
+    }
+}
+
+impl<'fnt> Decode<'fnt> for Gpos<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Gpos<'fnt>> {
+        LayoutTable::decode(buffer).map(Gpos)
+    }
+}
+
+impl<'fnt> Deref for Gsub<'fnt> {
+    type Target = LayoutTable<'fnt>;
+
+    fn deref(&self) -> &LayoutTable<'fnt> {
+        &self.0
+    }
+}
+
+impl<'fnt> Deref for Gpos<'fnt> {
+    type Target = LayoutTable<'fnt>;
+
+    fn deref(&self) -> &LayoutTable<'fnt> {
+        &self.0
+    }
+}
+
+impl<'fnt> Gsub<'fnt> {
+    /// The lookup at `index`, with extension subtables resolved.
+    pub fn lookup(&self, index: u16) -> Result<Lookup<'fnt>> {
+        self.0.lookup(index, 7)
+    }
+
+    /// A summary of the lookup at `index`, for debugging.
+    pub fn describe(&self, index: u16) -> Result<LookupDescription> {
+        let lookup = self.lookup(index)?;
+        let (kind, counts) = match lookup.lookup_type {
+            1 => ("Single sub", Vec::new()),
+            2 => ("Multiple sub", vec![("sequences", lookup.sum_u16(4)?)]),
+            3 => ("Alternate sub", vec![("alternate sets", lookup.sum_u16(4)?)]),
+            4 => ("Ligature sub", vec![("ligatures", lookup.sum_sets(None, 4, 6, 0)?)]),
+            5 => ("Context sub", Vec::new()),
+            6 => ("Chained context sub", Vec::new()),
+            8 => ("Reverse chained single sub", Vec::new()),
+            _ => ("Unknown sub", Vec::new()),
+        };
+        LookupDescription::new(index, kind, &lookup, &GSUB_CONTEXT_TYPES, counts)
+    }
+}
+
+impl<'fnt> Gpos<'fnt> {
+    /// The lookup at `index`, with extension subtables resolved.
+    pub fn lookup(&self, index: u16) -> Result<Lookup<'fnt>> {
+        self.0.lookup(index, 9)
+    }
+
+    /// A summary of the lookup at `index`, for debugging.
+    pub fn describe(&self, index: u16) -> Result<LookupDescription> {
+        let lookup = self.lookup(index)?;
+        let (kind, counts) = match lookup.lookup_type {
+            1 => ("Single pos", Vec::new()),
+            2 => ("Pair pos", vec![
+                ("pairs", lookup.sum_sets(Some(1), 8, 10, 0)?),
+                ("class pairs", lookup.sum_class_pairs()?),
+            ]),
+            3 => ("Cursive pos", Vec::new()),
+            4 => ("Mark-to-base pos", Vec::new()),
+            5 => ("Mark-to-ligature pos", Vec::new()),
+            6 => ("Mark-to-mark pos", Vec::new()),
+            7 => ("Context pos", Vec::new()),
+            8 => ("Chained context pos", Vec::new()),
+            _ => ("Unknown pos", Vec::new()),
+        };
+        let counts = counts.into_iter().filter(|&(_, count)| count > 0).collect();
+        LookupDescription::new(index, kind, &lookup, &GPOS_CONTEXT_TYPES, counts)
+    }
+}
+
+/// A summary of a lookup, which displays as a line such as
+/// `Lookup 12: Ligature sub, 214 ligatures, coverage 180 glyphs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupDescription {
+    pub index: u16,
+    /// The name of the lookup type, such as `Ligature sub`.
+    pub kind: &'static str,
+    pub flag: u16,
+    pub subtable_count: usize,
+    /// The number of glyphs covered by any subtable.  Contextual subtables
+    /// of format 3 cover sequences rather than glyphs, and are not counted.
+    pub coverage_glyphs: usize,
+    /// The number of entries of the subtables, by what they hold, such as
+    /// `("ligatures", 214)`.
+    pub counts: Vec<(&'static str, usize)>,
+}
+
+impl LookupDescription {
+    fn new(index: u16,
+           kind: &'static str,
+           lookup: &Lookup,
+           context_types: &[u16],
+           counts: Vec<(&'static str, usize)>) -> Result<LookupDescription> {
+        let mut coverage = GlyphSet::new();
+        for &subtable in lookup.subtables() {
+            if context_types.contains(&lookup.lookup_type) && u16::decode(subtable)? == 3 {
+                continue
+            }
+            let offset = u16::decode(subtable.get(2..).ok_or(Error::UnexpectedEof)?)? as usize;
+            let data = subtable.get(offset..).ok_or(Error::UnexpectedEof)?;
+            coverage.union_with(&Coverage::decode(data)?.glyph_set());
+        }
+
+        Ok(LookupDescription {
+            index,
+            kind,
+            flag: lookup.flag,
+            subtable_count: lookup.subtables().len(),
+            coverage_glyphs: coverage.len(),
+            counts,
+        })
+    }
+}
+
+impl fmt::Display for LookupDescription {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Lookup {}: {}", self.index, self.kind)?;
+        for &(what, count) in &self.counts {
+            write!(f, ", {} {}", count, what)?;
+        }
+        write!(f, ", coverage {} glyphs", self.coverage_glyphs)?;
+        if self.subtable_count != 1 {
+            write!(f, ", {} subtables", self.subtable_count)?;
+        }
+        if self.flag != 0 {
+            write!(f, ", flag 0x{:04X}", self.flag)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'fnt> Lookup<'fnt> {
+    /// The sum over the subtables of the `u16` at `pos`.
+    fn sum_u16(&self, pos: usize) -> Result<usize> {
+        self.subtables.iter()
+            .map(|subtable| Ok(u16::decode(subtable.get(pos..).ok_or(Error::UnexpectedEof)?)? as usize))
+            .sum()
+    }
+
+    /// The total size of the sets of the subtables of `format`, or of
+    /// every subtable, where a subtable has a count of sets at
+    /// `count_pos` followed by their offsets at `offsets_pos`, and each set
+    /// has its size at `size_pos`.
+    fn sum_sets(&self, format: Option<u16>, count_pos: usize, offsets_pos: usize, size_pos: usize) -> Result<usize> {
+        let mut total = 0;
+        for &subtable in &self.subtables {
+            if format.is_some_and(|format| u16::decode(subtable) != Ok(format)) {
+                continue
+            }
+
+            let count = u16::decode(subtable.get(count_pos..).ok_or(Error::UnexpectedEof)?)? as usize;
+            let offsets = subtable.get(offsets_pos..).ok_or(Error::UnexpectedEof)?;
+            for offset in Array::<u16>::decode(offsets, count)? {
+                let set = subtable.get(offset as usize + size_pos..).ok_or(Error::UnexpectedEof)?;
+                total += u16::decode(set)? as usize;
+            }
+        }
+        Ok(total)
+    }
+
+    /// The number of class pairs of the format 2 subtables of a pair
+    /// adjustment lookup.
+    fn sum_class_pairs(&self) -> Result<usize> {
+        let mut total = 0;
+        for &subtable in &self.subtables {
+            if u16::decode(subtable)? == 2 {
+                required_len!(subtable, 16);
+                total += u16::decode(&subtable[12..])? as usize * u16::decode(&subtable[14..])? as usize;
+            }
+        }
+        Ok(total)
+    }
+}
+
+/// Lookup types whose format 3 subtables have no coverage table at the
+/// usual place: contextual and chained contextual lookups.
+const GSUB_CONTEXT_TYPES: [u16; 2] = [5, 6];
+const GPOS_CONTEXT_TYPES: [u16; 2] = [7, 8];
+
+/// A `GSUB` or `GPOS` table with its lookups resolved, and the coverage
+/// and class definition tables of their subtables expanded into arrays
+/// indexed by glyph.  Building one reads every lookup, which pays off
+/// when the table is applied to many runs of text.  Each array spans only
+/// the glyphs its table lists, not the whole font.
+#[derive(Debug, Clone)]
+pub struct AccelLayoutTable<'fnt> {
+    table: LayoutTable<'fnt>,
+    /// Each lookup, or the error resolving it, which is reported when the
+    /// lookup is applied rather than when the table is built.
+    lookups: Vec<Result<AccelLookup<'fnt>>>,
+}
+
+/// A lookup with an expanded form of each of its subtables.
+#[derive(Debug, Clone)]
+pub struct AccelLookup<'fnt> {
+    pub lookup: Lookup<'fnt>,
+    subtables: Vec<AccelSubtable>,
+}
+
+/// The expanded tables of one subtable.  A table which could not be
+/// decoded is `None`, so that it is read, and fails, where it is used.
+#[derive(Debug, Clone, Default)]
+pub struct AccelSubtable {
+    pub coverage: Option<CoverageArray>,
+    /// The class definitions of the first and second glyphs of a pair
+    /// adjustment by class.
+    pub pair_classes: Option<(ClassArray, ClassArray)>,
+}
+
+impl<'fnt> AccelLayoutTable<'fnt> {
+    pub fn gsub(gsub: &Gsub<'fnt>) -> Result<AccelLayoutTable<'fnt>> {
+        AccelLayoutTable::new(gsub.0, |index| gsub.lookup(index), &GSUB_CONTEXT_TYPES, None)
+    }
+
+    pub fn gpos(gpos: &Gpos<'fnt>) -> Result<AccelLayoutTable<'fnt>> {
+        AccelLayoutTable::new(gpos.0, |index| gpos.lookup(index), &GPOS_CONTEXT_TYPES, Some(2))
+    }
+
+    fn new<F>(table: LayoutTable<'fnt>, lookup: F, context_types: &[u16], pair_type: Option<u16>) -> Result<AccelLayoutTable<'fnt>>
+        where F: Fn(u16) -> Result<Lookup<'fnt>>
+    {
+        let read = |data: &'fnt [u8], pos: usize| -> Option<&'fnt [u8]> {
+            let offset = u16::decode(data.get(pos..)?).ok()? as usize;
+            data.get(offset..)
+        };
+
+        let lookups = (0..table.lookup_count()?)
+            .map(|index| lookup(index).map(|lookup| {
+                let subtables = lookup.subtables().iter()
+                    .map(|&subtable| {
+                        let format = u16::decode(subtable).ok();
+                        if context_types.contains(&lookup.lookup_type) && format == Some(3) {
+                            return AccelSubtable::default()
+                        }
+
+                        let class_def = |pos| read(subtable, pos).and_then(|data| ClassDef::decode(data).ok());
+                        let pair_classes = if Some(lookup.lookup_type) == pair_type && format == Some(2) {
+                            class_def(8).and_then(|first| class_def(10).map(|second| (first, second)))
+                                .map(|(first, second)| (ClassArray::new(&first), ClassArray::new(&second)))
+                        } else {
+                            None
+                        };
+
+                        AccelSubtable {
+                            coverage: read(subtable, 2)
+                                .and_then(|data| Coverage::decode(data).ok())
+                                .map(|coverage| CoverageArray::new(&coverage)),
+                            pair_classes,
+                        }
+                    })
+                    .collect();
+                AccelLookup { lookup, subtables }
+            }))
+            .collect();
+
+        Ok(AccelLayoutTable { table, lookups })
+    }
+
+    /// The lookup at `index`, or the error resolving it.
+    pub fn lookup(&self, index: u16) -> Result<&AccelLookup<'fnt>> {
+        match self.lookups.get(index as usize) {
+            Some(Ok(lookup)) => Ok(lookup),
+            Some(Err(error)) => Err(*error),
+            None => Err(Error::InvalidData),
+        }
+    }
+
+    /// The bytes the expanded lookups take on the heap.
+    pub fn heap_size(&self) -> usize {
+        let lookups = self.lookups.capacity() * mem::size_of::<Result<AccelLookup>>();
+        lookups + self.lookups.iter().flatten().map(AccelLookup::heap_size).sum::<usize>()
+    }
+}
+
+impl<'fnt> Deref for AccelLayoutTable<'fnt> {
+    type Target = LayoutTable<'fnt>;
+
+    fn deref(&self) -> &LayoutTable<'fnt> {
+        &self.table
+    }
+}
+
+impl<'fnt> AccelLookup<'fnt> {
+    /// The expanded tables of each subtable, in subtable order.
+    pub fn subtables(&self) -> &[AccelSubtable] {
+        &self.subtables
+    }
+
+    fn heap_size(&self) -> usize {
+        let subtables = self.subtables.capacity() * mem::size_of::<AccelSubtable>();
+        subtables + self.subtables.iter().map(AccelSubtable::heap_size).sum::<usize>()
+    }
+}
+
+impl AccelSubtable {
+    fn heap_size(&self) -> usize {
+        let coverage = self.coverage.as_ref().map_or(0, |coverage| coverage.indices.capacity() * 2);
+        let classes = self.pair_classes.as_ref().map_or(0, |(first, second)| (first.classes.capacity() + second.classes.capacity()) * 2);
+        coverage + classes
+    }
+}
+
+/// A coverage table, which lists the glyphs a lookup subtable applies to.
+/// The coverage index of a glyph is its position in the sorted list.
+#[derive(Debug, Copy, Clone)]
+pub enum Coverage<'fnt> {
+    Format1(Array<'fnt, GlyphId>),
+    Format2(Array<'fnt, RangeRecord>),
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RangeRecord {
+    pub start_glyph_id: GlyphId,
+    pub end_glyph_id: GlyphId,
+    pub start_coverage_index: u16,
+}
+
+impl<'fnt> Decode<'fnt> for Coverage<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Coverage<'fnt>> {
+        required_len!(buffer, 4);
+        let count = u16::decode(&buffer[2..])? as usize;
+        match u16::decode(buffer)? {
+            1 => Array::decode(&buffer[4..], count).map(Coverage::Format1),
+            2 => Array::decode(&buffer[4..], count).map(Coverage::Format2),
+            format => Err(Error::UnsupportedVersion(format.into())),
+        }
+    }
+}
+
+impl<'fnt> Coverage<'fnt> {
+    /// The coverage index of `glyph`, or `None` if it is not covered.
+    pub fn index(&self, glyph: GlyphId) -> Option<u16> {
+        match *self {
+            Coverage::Format1(glyphs) => {
+                let (mut low, mut high) = (0, glyphs.len());
+                while low < high {
+                    let mid = (low + high) / 2;
+                    let covered = glyphs.get(mid)?;
+                    if covered < glyph {
+                        low = mid + 1;
+                    } else if covered > glyph {
+                        high = mid;
+                    } else {
+                        return Some(mid as u16)
+                    }
+                }
+                None
+            },
+            Coverage::Format2(ranges) => {
+                let (mut low, mut high) = (0, ranges.len());
+                while low < high {
+                    let mid = (low + high) / 2;
+                    let range = ranges.get(mid)?;
+                    if range.end_glyph_id < glyph {
+                        low = mid + 1;
+                    } else if range.start_glyph_id > glyph {
+                        high = mid;
+                    } else {
+                        return range.start_coverage_index.checked_add(glyph.0 - range.start_glyph_id.0)
+                    }
+                }
+                None
+            },
+        }
+    }
+
+    /// The covered glyphs, in coverage index order.
+    pub fn glyphs(&self) -> Vec<GlyphId> {
+        match *self {
+            Coverage::Format1(glyphs) => glyphs.into_iter().collect(),
+            Coverage::Format2(ranges) => ranges.into_iter()
+                .flat_map(|range| (range.start_glyph_id.0..=range.end_glyph_id.0).map(GlyphId))
+                .collect(),
+        }
+    }
+
+    /// The covered glyphs as a set.
+    pub fn glyph_set(&self) -> GlyphSet {
+        let mut set = GlyphSet::new();
+        match *self {
+            Coverage::Format1(glyphs) => set.extend(glyphs),
+            Coverage::Format2(ranges) => for range in ranges {
+                set.insert_range(range.start_glyph_id, range.end_glyph_id);
+            },
+        }
+        set
+    }
+
+    /// Serialize a coverage table for `glyphs`, in whichever format is
+    /// smaller.  The glyphs are sorted and deduplicated first.
+    pub fn encode(glyphs: &[GlyphId]) -> Vec<u8> {
+        let mut glyphs = glyphs.to_vec();
+        glyphs.sort();
+        glyphs.dedup();
+
+        let mut ranges: Vec<(u16, u16)> = Vec::new();
+        for glyph in &glyphs {
+            match ranges.last_mut() {
+                Some(range) if range.1.checked_add(1) == Some(glyph.0) => range.1 = glyph.0,
+                _ => ranges.push((glyph.0, glyph.0)),
+            }
+        }
+
+        let mut out = EncodeBuf::new();
+        if ranges.len() * 6 < glyphs.len() * 2 {
+            out.put_u16(2);
+            out.put_u16(ranges.len() as u16);
+            // Indices are counted wider, since a range may end at the last
+            // glyph id.
+            let mut index = 0u32;
+            for (start, end) in ranges {
+                out.put_u16(start);
+                out.put_u16(end);
+                out.put_u16(index as u16);
+                index += u32::from(end - start) + 1;
+            }
+        } else {
+            out.put_u16(1);
+            out.put_u16(glyphs.len() as u16);
+            for glyph in glyphs {
+                out.put_glyph_id(glyph);
+            }
+        }
+
+        out.into_vec()
+    }
+}
+
+/// A class definition table, which assigns glyphs to classes.  Glyphs
+/// which are not listed are in class 0.
+#[derive(Debug, Copy, Clone)]
+pub enum ClassDef<'fnt> {
+    Format1 { start_glyph_id: GlyphId, classes: Array<'fnt, u16> },
+    Format2(Array<'fnt, ClassRangeRecord>),
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ClassRangeRecord {
+    pub start_glyph_id: GlyphId,
+    pub end_glyph_id: GlyphId,
+    pub class: u16,
+}
+
+impl<'fnt> Decode<'fnt> for ClassDef<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<ClassDef<'fnt>> {
+        required_len!(buffer, 4);
+        match u16::decode(buffer)? {
+            1 => {
+                required_len!(buffer, 6);
+                let count = u16::decode(&buffer[4..])? as usize;
+                Ok(ClassDef::Format1 {
+                    start_glyph_id: GlyphId::decode(&buffer[2..])?,
+                    classes: Array::decode(&buffer[6..], count)?,
+                })
+            },
+            2 => {
+                let count = u16::decode(&buffer[2..])? as usize;
+                Array::decode(&buffer[4..], count).map(ClassDef::Format2)
+            },
+            format => Err(Error::UnsupportedVersion(format.into())),
+        }
+    }
+}
+
+impl<'fnt> ClassDef<'fnt> {
+    pub fn class(&self, glyph: GlyphId) -> u16 {
+        match *self {
+            ClassDef::Format1 { start_glyph_id, classes } => glyph.0.checked_sub(start_glyph_id.0)
+                .and_then(|index| classes.get(index as usize))
+                .unwrap_or(0),
+            ClassDef::Format2(ranges) => ranges.into_iter()
+                .find(|range| range.start_glyph_id <= glyph && glyph <= range.end_glyph_id)
+                .map_or(0, |range| range.class),
+        }
+    }
+
+    /// The glyphs listed in `class`, in glyph order.  Class 0 holds every
+    /// glyph which is not listed, so only those listed in it are returned.
+    pub fn glyphs(&self, class: u16) -> Vec<GlyphId> {
+        let mut glyphs = match *self {
+            ClassDef::Format1 { start_glyph_id, classes } => classes.into_iter()
+                .enumerate()
+                .filter(|&(_, glyph_class)| glyph_class == class)
+                .map(|(index, _)| GlyphId(start_glyph_id.0.wrapping_add(index as u16)))
+                .collect::<Vec<_>>(),
+            ClassDef::Format2(ranges) => ranges.into_iter()
+                .filter(|range| range.class == class)
+                .flat_map(|range| (range.start_glyph_id.0..=range.end_glyph_id.0).map(GlyphId))
+                .collect(),
+        };
+        glyphs.sort();
+        glyphs
+    }
+}
+
+/// A contextual subtable, of `GSUB` lookup type 5 or `GPOS` lookup type
+/// 7, which apply nested lookups to sequences of glyphs.  The formats are
+/// the same in both tables.
+#[derive(Debug, Clone)]
+pub enum SequenceContext<'fnt> {
+    /// Rules matching glyphs, in sets by the coverage index of the first.
+    Glyphs { coverage: Coverage<'fnt>, rule_sets: RuleSets<'fnt> },
+    /// Rules matching classes, in sets by the class of the first glyph.
+    Classes { coverage: Coverage<'fnt>, class_def: ClassDef<'fnt>, rule_sets: RuleSets<'fnt> },
+    /// A single rule matching a coverage table at each glyph.
+    Coverages { coverages: Vec<Coverage<'fnt>>, lookups: Array<'fnt, SequenceLookupRecord> },
+}
+
+/// The rule sets of a format 1 or 2 `SequenceContext`.
+#[derive(Debug, Copy, Clone)]
+pub struct RuleSets<'fnt> {
+    subtable: &'fnt [u8],
+    offsets: Array<'fnt, u16>,
+}
+
+/// A rule of a `SequenceContext`: the length of the sequence it matches,
+/// and the lookups to apply to the sequence.
+#[derive(Debug, Copy, Clone)]
+pub struct SequenceRule<'fnt> {
+    pub glyph_count: usize,
+    /// The glyphs or classes of the sequence after the first, or nothing
+    /// for a rule of coverage tables.
+    pub input: Array<'fnt, u16>,
+    pub lookups: Array<'fnt, SequenceLookupRecord>,
+}
+
+/// A lookup to apply at a glyph of a matched sequence.
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SequenceLookupRecord {
+    pub sequence_index: u16,
+    pub lookup_list_index: u16,
+}
+
+impl<'fnt> Decode<'fnt> for SequenceContext<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<SequenceContext<'fnt>> {
+        required_len!(buffer, 6);
+        let read = |pos: usize| -> Result<&'fnt [u8]> {
+            let offset = u16::decode(buffer.get(pos..).ok_or(Error::UnexpectedEof)?)? as usize;
+            buffer.get(offset..).ok_or(Error::UnexpectedEof)
+        };
+        let rule_sets = |pos: usize| -> Result<RuleSets<'fnt>> {
+            let count = u16::decode(buffer.get(pos..).ok_or(Error::UnexpectedEof)?)? as usize;
+            let offsets = Array::decode(buffer.get(pos + 2..).ok_or(Error::UnexpectedEof)?, count)?;
+            Ok(RuleSets { subtable: buffer, offsets })
+        };
+
+        match u16::decode(buffer)? {
+            1 => Ok(SequenceContext::Glyphs { coverage: Coverage::decode(read(2)?)?, rule_sets: rule_sets(4)? }),
+            2 => Ok(SequenceContext::Classes {
+                coverage: Coverage::decode(read(2)?)?,
+                class_def: ClassDef::decode(read(4)?)?,
+                rule_sets: rule_sets(6)?,
+            }),
+            3 => {
+                let glyph_count = u16::decode(&buffer[2..])? as usize;
+                let lookup_count = u16::decode(&buffer[4..])? as usize;
+                let coverages = (0..glyph_count)
+                    .map(|index| Coverage::decode(read(6 + index * 2)?))
+                    .collect::<Result<Vec<_>>>()?;
+                let lookups = Array::decode(buffer.get(6 + glyph_count * 2..).ok_or(Error::UnexpectedEof)?, lookup_count)?;
+                Ok(SequenceContext::Coverages { coverages, lookups })
+            },
+            format => Err(Error::UnsupportedVersion(format.into())),
+        }
+    }
+}
+
+impl<'fnt> SequenceContext<'fnt> {
+    /// The rules to try in order at a sequence starting with `glyph`.
+    pub fn rules(&self, glyph: GlyphId) -> Result<Vec<SequenceRule<'fnt>>> {
+        match *self {
+            SequenceContext::Glyphs { coverage, rule_sets } => match coverage.index(glyph) {
+                Some(index) => rule_sets.rules(index),
+                None => Ok(Vec::new()),
+            },
+            SequenceContext::Classes { coverage, class_def, rule_sets } => match coverage.index(glyph) {
+                Some(_) => rule_sets.rules(class_def.class(glyph)),
+                None => Ok(Vec::new()),
+            },
+            SequenceContext::Coverages { ref coverages, lookups } => match coverages.first() {
+                Some(first) if first.index(glyph).is_some() => {
+                    Ok(vec![SequenceRule { glyph_count: coverages.len(), input: Array::decode(&[], 0)?, lookups }])
+                },
+                _ => Ok(Vec::new()),
+            },
+        }
+    }
+
+    /// Whether `glyph` matches the glyph at `index` of the sequence of
+    /// `rule`, for an index after the first.
+    pub fn matches(&self, rule: &SequenceRule, index: usize, glyph: GlyphId) -> bool {
+        match *self {
+            SequenceContext::Glyphs { .. } => rule.input.get(index - 1) == Some(glyph.0),
+            SequenceContext::Classes { class_def, .. } => rule.input.get(index - 1) == Some(class_def.class(glyph)),
+            SequenceContext::Coverages { ref coverages, .. } => {
+                coverages.get(index).is_some_and(|coverage| coverage.index(glyph).is_some())
+            },
+        }
+    }
+}
+
+impl<'fnt> RuleSets<'fnt> {
+    /// The rules of the set at `index`, which has none if its offset is
+    /// null.
+    fn rules(&self, index: u16) -> Result<Vec<SequenceRule<'fnt>>> {
+        let set = match self.offsets.get(index as usize) {
+            None | Some(0) => return Ok(Vec::new()),
+            Some(offset) => self.subtable.get(offset as usize..).ok_or(Error::UnexpectedEof)?,
+        };
+
+        let count = u16::decode(set)? as usize;
+        Array::<u16>::decode(set.get(2..).ok_or(Error::UnexpectedEof)?, count)?.into_iter()
+            .map(|offset| {
+                let rule = set.get(offset as usize..).ok_or(Error::UnexpectedEof)?;
+                required_len!(rule, 4);
+                let glyph_count = u16::decode(rule)? as usize;
+                let lookup_count = u16::decode(&rule[2..])? as usize;
+                let input_count = glyph_count.saturating_sub(1);
+                Ok(SequenceRule {
+                    glyph_count,
+                    input: Array::decode(&rule[4..], input_count)?,
+                    lookups: Array::decode(rule.get(4 + input_count * 2..).ok_or(Error::UnexpectedEof)?, lookup_count)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A coverage table as an array of coverage indices, from the first to
+/// the last covered glyph.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoverageArray {
+    first: u16,
+    indices: Vec<u16>,
+}
+
+/// Marks glyphs in a `CoverageArray` which are not covered.  No coverage
+/// index reaches it, as a font has at most 65535 glyphs besides `.notdef`.
+const NOT_COVERED: u16 = 0xFFFF;
+
+impl CoverageArray {
+    pub fn new(coverage: &Coverage) -> CoverageArray {
+        let mut entries = Vec::new();
+        match *coverage {
+            Coverage::Format1(glyphs) => entries.extend(glyphs.into_iter().enumerate().map(|(index, glyph)| (glyph.0, index as u16))),
+            Coverage::Format2(ranges) => for range in ranges {
+                let first = range.start_glyph_id.0;
+                for glyph in first..=range.end_glyph_id.0 {
+                    entries.push((glyph, range.start_coverage_index.wrapping_add(glyph - first)));
+                }
+            },
+        }
+
+        let first = entries.iter().map(|entry| entry.0).min().unwrap_or(0);
+        let last = entries.iter().map(|entry| entry.0).max().unwrap_or(0);
+        let mut indices = vec![NOT_COVERED; if entries.is_empty() { 0 } else { (last - first) as usize + 1 }];
+        // The first entry of a glyph listed twice wins, as in a search of
+        // a sorted table.
+        for &(glyph, index) in entries.iter().rev() {
+            indices[(glyph - first) as usize] = index;
+        }
+        CoverageArray { first, indices }
+    }
+
+    pub fn index(&self, glyph: GlyphId) -> Option<u16> {
+        let index = *self.indices.get(glyph.0.checked_sub(self.first)? as usize)?;
+        if index == NOT_COVERED { None } else { Some(index) }
+    }
+}
+
+/// A class definition table as an array of classes, from the first to the
+/// last glyph it lists.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassArray {
+    first: u16,
+    classes: Vec<u16>,
+}
+
+impl ClassArray {
+    pub fn new(class_def: &ClassDef) -> ClassArray {
+        match *class_def {
+            ClassDef::Format1 { start_glyph_id, classes } => ClassArray {
+                first: start_glyph_id.0,
+                classes: classes.into_iter().collect(),
+            },
+            ClassDef::Format2(ranges) => {
+                let first = ranges.into_iter().map(|range| range.start_glyph_id.0).min().unwrap_or(0);
+                let last = ranges.into_iter().map(|range| range.end_glyph_id.0).max().unwrap_or(0);
+                let mut classes = vec![0; if ranges.is_empty() || last < first { 0 } else { (last - first) as usize + 1 }];
+                // `ClassDef::class` takes the first range which contains a
+                // glyph, so earlier ranges are written last.
+                let ranges = ranges.into_iter().collect::<Vec<_>>();
+                for range in ranges.iter().rev() {
+                    for glyph in range.start_glyph_id.0..=range.end_glyph_id.0 {
+                        classes[(glyph - first) as usize] = range.class;
+                    }
+                }
+                ClassArray { first, classes }
+            },
+        }
+    }
+
+    pub fn class(&self, glyph: GlyphId) -> u16 {
+        glyph.0.checked_sub(self.first)
+            .and_then(|index| self.classes.get(index as usize).cloned())
+            .unwrap_or(0)
+    }
+}
+
+/// The adjustments of a `GPOS` value record, in font units.  Device and
+/// variation tables are skipped.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ValueRecord {
+    pub x_placement: i16,
+    pub y_placement: i16,
+    pub x_advance: i16,
+    pub y_advance: i16,
+}
+
+impl ValueRecord {
+    /// The encoded size of a value record with `format`, which has a
+    /// 16-bit field for each bit set.
+    pub fn encoded_size(format: u16) -> usize {
+        2 * (format & 0xFF).count_ones() as usize
+    }
+}
+
+impl<'fnt> Decode1<'fnt, u16> for ValueRecord {
+    fn decode(buffer: &'fnt [u8], format: u16) -> Result<ValueRecord> {
+        required_len!(buffer, ValueRecord::encoded_size(format));
+        let mut fields = [0i16; 4];
+        let mut pos = 0;
+        for (bit, field) in fields.iter_mut().enumerate() {
+            if format & 1 << bit != 0 {
+                *field = i16::decode(&buffer[pos..])?;
+                pos += 2;
+            }
+        }
+
+        Ok(ValueRecord {
+            x_placement: fields[0],
+            y_placement: fields[1],
+            x_advance: fields[2],
+            y_advance: fields[3],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use font::Font;
+    use test_fonts;
+
+    #[test]
+    fn coverage() {
+        let glyphs = [GlyphId(4), GlyphId(9), GlyphId(2)];
+        let data = Coverage::encode(&glyphs);
+        assert_eq!(data.len(), 10);
+        let coverage = Coverage::decode(&data).unwrap();
+        assert_eq!(coverage.glyphs(), vec![GlyphId(2), GlyphId(4), GlyphId(9)]);
+        assert_eq!(coverage.index(GlyphId(9)), Some(2));
+        assert_eq!(coverage.index(GlyphId(5)), None);
+
+        // Long runs are stored as ranges.
+        let glyphs = (10..40).chain(50..60).map(GlyphId).collect::<Vec<_>>();
+        let data = Coverage::encode(&glyphs);
+        assert_eq!(data.len(), 16);
+        let coverage = Coverage::decode(&data).unwrap();
+        assert_eq!(coverage.glyphs(), glyphs);
+        assert_eq!(coverage.index(GlyphId(52)), Some(32));
+        assert_eq!(coverage.glyph_set(), glyphs.iter().cloned().collect());
+
+        let array = CoverageArray::new(&coverage);
+        for glyph in (0..70).map(GlyphId) {
+            assert_eq!(array.index(glyph), coverage.index(glyph));
+        }
+        assert_eq!(coverage.index(GlyphId(45)), None);
+
+        // A range may end at the last glyph id, and a crafted range whose
+        // indices pass 0xFFFF covers nothing past it.
+        let glyphs = [GlyphId(0xFFFC), GlyphId(0xFFFD), GlyphId(0xFFFE), GlyphId(0xFFFF)];
+        let data = Coverage::encode(&glyphs);
+        assert_eq!(Coverage::decode(&data).unwrap().index(GlyphId(0xFFFF)), Some(3));
+        let coverage = Coverage::decode(&[0, 2, 0, 1, 0, 5, 0, 10, 0xFF, 0xFE]).unwrap();
+        assert_eq!((coverage.index(GlyphId(6)), coverage.index(GlyphId(7))), (Some(0xFFFF), None));
+    }
+
+    #[test]
+    fn feature_params() {
+        use table::name::{NameBuilder, NameId};
+
+        let mut gsub = test_fonts::layout_table(b"latn", &[(b"ss01", &[]), (b"cv01", &[]), (b"size", &[]), (b"ss02", &[])], &[]);
+        let feature_list = u16::decode(&gsub[6..]).unwrap() as usize;
+        let feature = |gsub: &[u8], index: usize| feature_list + u16::decode(&gsub[feature_list + 6 + index * 6..]).unwrap() as usize;
+        let params: [(usize, &[u8]); 3] = [
+            (0, &[0, 0, 1, 0]),
+            (1, &[0, 0, 1, 1, 0, 0, 0, 0, 0, 2, 1, 2, 0, 2, 0, 0, 0x61, 0x01, 0xF6, 0x00]),
+            // The offset of the `size` parameters is relative to the
+            // feature list, as in early fonts.
+            (2, &[0, 100, 0, 0, 0, 0, 0, 0, 0, 0]),
+        ];
+        for &(index, data) in &params {
+            let base = if index == 2 { feature_list } else { feature(&gsub, index) };
+            let (pos, offset) = (feature(&gsub, index), (gsub.len() - base) as u16);
+            gsub[pos..pos + 2].copy_from_slice(&offset.to_be_bytes());
+            gsub.extend_from_slice(data);
+        }
+
+        let table = Gsub::decode(&gsub).unwrap();
+        assert_eq!(table.feature_params(0), Ok(Some(FeatureParams::StylisticSet { ui_name_id: 256 })));
+        let variant = table.feature_params(1).unwrap().unwrap();
+        assert_eq!(variant, FeatureParams::CharacterVariant {
+            label_name_id: 257,
+            tooltip_name_id: 0,
+            sample_text_name_id: 0,
+            num_named_parameters: 2,
+            first_param_label_name_id: 258,
+            characters: vec![0x61, 0x1F600],
+        });
+        assert_eq!(variant.param_label_name_ids(), 258..260);
+        assert_eq!(table.feature_params(2), Ok(Some(FeatureParams::Size {
+            design_size: 100,
+            subfamily_id: 0,
+            subfamily_name_id: 0,
+            range_start: 0,
+            range_end: 0,
+        })));
+        assert_eq!(table.feature_params(3), Ok(None));
+        assert_eq!(table.feature_params(4), Err(Error::InvalidData));
+
+        let mut names = NameBuilder::new();
+        names.set(NameId::Custom(256), "Open digits").set(NameId::Custom(257), "Single-storey a");
+        let data = test_fonts::minimal_font(false)
+            .table(b"GSUB", gsub)
+            .table(b"name", names.build().unwrap())
+            .build();
+        let font = Font::decode(&data).unwrap();
+        assert_eq!(font.feature_ui_name(Tag::new(*b"ss01")), Ok(Some("Open digits".to_string())));
+        assert_eq!(font.feature_ui_name(Tag::new(*b"cv01")), Ok(Some("Single-storey a".to_string())));
+        assert_eq!(font.feature_ui_name(Tag::new(*b"ss02")), Ok(None));
+        assert_eq!(font.feature_ui_name(Tag::new(*b"size")), Ok(None));
+        assert!(font.feature_params(Tag::new(*b"size")).unwrap().is_some());
+    }
+
+    #[test]
+    fn describe_lookups() {
+        let gsub = test_fonts::layout_table(b"latn", &[(b"liga", &[0])], &[
+            (4, test_fonts::ligature_substitution(&[1, 2], 3)),
+            (1, test_fonts::single_substitution(&[(4, 5), (6, 7)])),
+        ]);
+        let gsub = Gsub::decode(&gsub).unwrap();
+        assert_eq!(gsub.describe(0).unwrap().to_string(), "Lookup 0: Ligature sub, 1 ligatures, coverage 1 glyphs");
+        let single = gsub.describe(1).unwrap();
+        assert_eq!((single.kind, single.coverage_glyphs, single.counts.len()), ("Single sub", 2, 0));
+        assert!(gsub.describe(2).is_err());
+
+        let gpos = test_fonts::layout_table(b"latn", &[(b"kern", &[0])], &[
+            (2, test_fonts::class_pair_adjustment(&[4, 5], &[3], -50)),
+        ]);
+        let gpos = Gpos::decode(&gpos).unwrap();
+        let pair = gpos.describe(0).unwrap();
+        assert_eq!(pair.to_string(), "Lookup 0: Pair pos, 4 class pairs, coverage 2 glyphs");
+
+        let data = open_file!("data/OpenSans-Regular.ttf");
+        let font = Font::decode(&data).unwrap();
+        let gpos = font.table::<Gpos>().unwrap();
+        for index in 0..gpos.lookup_count().unwrap() {
+            assert!(gpos.describe(index).unwrap().to_string().starts_with(&format!("Lookup {}: ", index)));
+        }
+    }
+}
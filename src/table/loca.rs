@@ -0,0 +1,85 @@
+use std::ops::Range;
+
+use decode::{Decode, Decode2};
+use primitives::GlyphId;
+use error::{Error, Result};
+
+/// The index to location table, which stores the offsets of each glyph
+/// within the `glyf` table.  The short format stores offsets divided by two.
+#[derive(Debug, Copy, Clone)]
+pub struct Loca<'fnt> {
+    data: &'fnt [u8],
+    long: bool,
+    num_glyphs: usize,
+}
+
+impl<'fnt> Decode2<'fnt, bool, usize> for Loca<'fnt> {
+    fn decode(buffer: &'fnt [u8], long: bool, num_glyphs: usize) -> Result<Loca<'fnt>> {
+        let entry_size = if long { 4 } else { 2 };
+        required_len!(buffer, (num_glyphs + 1) * entry_size);
+        Ok(Loca { data: buffer, long, num_glyphs })
+    }
+}
+
+impl<'fnt> Loca<'fnt> {
+    pub fn num_glyphs(&self) -> usize {
+        self.num_glyphs
+    }
+
+    pub fn is_long(&self) -> bool {
+        self.long
+    }
+
+    /// The offset of entry `index` into `glyf`.  There is one more entry
+    /// than glyphs, marking the end of the last glyph.
+    pub fn offset(&self, index: usize) -> Option<usize> {
+        if index > self.num_glyphs {
+            return None
+        }
+
+        if self.long {
+            u32::decode(&self.data[index * 4..]).ok().map(|offset| offset as usize)
+        } else {
+            u16::decode(&self.data[index * 2..]).ok().map(|offset| offset as usize * 2)
+        }
+    }
+
+    /// The range of `glyf` holding `glyph`, read from its two entries
+    /// alone, or `None` if the glyph is out of range or its entries are
+    /// out of order.
+    pub fn range(&self, glyph: GlyphId) -> Option<Range<usize>> {
+        let index = glyph.0 as usize;
+        let (start, end) = (self.offset(index)?, self.offset(index + 1)?);
+        if start > end {
+            return None
+        }
+
+        Some(start..end)
+    }
+
+    /// Whether `glyph` has no outline, as its two entries are equal.
+    pub fn is_empty_glyph(&self, glyph: GlyphId) -> bool {
+        self.range(glyph).is_some_and(|range| range.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glyph_ranges() {
+        // Short offsets are stored halved.
+        let loca = Loca::decode(&[0, 0, 0, 0, 0, 10, 0, 8], false, 3).unwrap();
+        assert_eq!(loca.range(GlyphId(0)), Some(0..0));
+        assert_eq!(loca.range(GlyphId(1)), Some(0..20));
+        assert_eq!(loca.range(GlyphId(2)), None);
+        assert_eq!(loca.range(GlyphId(3)), None);
+        assert!(loca.is_empty_glyph(GlyphId(0)));
+        assert!(!loca.is_empty_glyph(GlyphId(1)));
+        assert!(!loca.is_empty_glyph(GlyphId(3)));
+
+        let loca = Loca::decode(&[0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0], true, 2).unwrap();
+        assert_eq!((loca.range(GlyphId(0)), loca.range(GlyphId(1))), (Some(0..65536), Some(65536..65536)));
+    }
+}
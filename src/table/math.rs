@@ -0,0 +1,280 @@
+use decode::Decode;
+use primitives::GlyphId;
+use table::layout::Coverage;
+use error::{Error, Result};
+
+/// Each glyph part of an assembly with this flag may be repeated any
+/// number of times, including none, to make the assembly longer.
+pub const EXTENDER_FLAG: u16 = 0x0001;
+
+/// The mathematical typesetting table.  Only the variants subtable, which
+/// gives the larger forms of stretchy glyphs such as brackets and radicals,
+/// is read.
+#[derive(Debug, Copy, Clone)]
+pub struct Math<'fnt> {
+    variants: Option<&'fnt [u8]>,
+}
+
+/// The direction a glyph is stretched in: vertically for brackets and
+/// radicals, horizontally for accents and arrows.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StretchAxis {
+    Vertical,
+    Horizontal,
+}
+
+/// A part of a glyph assembly.  The connectors are the lengths at either
+/// end which may overlap the neighbouring parts.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GlyphPart {
+    pub glyph: GlyphId,
+    pub start_connector_length: u16,
+    pub end_connector_length: u16,
+    pub full_advance: u16,
+    pub flags: u16,
+}
+
+impl GlyphPart {
+    pub fn is_extender(&self) -> bool {
+        self.flags & EXTENDER_FLAG != 0
+    }
+}
+
+/// The ways a glyph can be made larger along one axis.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlyphConstruction {
+    /// The `(glyph, advance)` of each pre-built size, smallest first.  The
+    /// first is usually the glyph itself.
+    pub variants: Vec<(GlyphId, u16)>,
+    /// The parts of the assembly, from bottom to top or left to right.
+    pub parts: Vec<GlyphPart>,
+    pub italics_correction: i16,
+}
+
+/// A glyph part placed within an assembly.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PlacedPart {
+    pub glyph: GlyphId,
+    /// The distance from the bottom or left edge of the assembly to the
+    /// start of the part.
+    pub offset: u32,
+}
+
+/// How to draw a glyph stretched to a size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stretch {
+    /// A pre-built size of the glyph.
+    Variant { glyph: GlyphId, advance: u16 },
+    /// The parts to draw, overlapping, in place of the glyph.
+    Assembly { parts: Vec<PlacedPart>, size: u32, italics_correction: i16 },
+}
+
+impl<'fnt> Decode<'fnt> for Math<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Math<'fnt>> {
+        required_len!(buffer, 10);
+        let major = u16::decode(buffer)?;
+        if major != 1 {
+            return Err(Error::UnsupportedVersion(u32::from(major) << 16 | u32::from(u16::decode(&buffer[2..])?)))
+        }
+
+        let variants = match u16::decode(&buffer[8..])? as usize {
+            0 => None,
+            offset => {
+                let variants = buffer.get(offset..).ok_or(Error::UnexpectedEof)?;
+                required_len!(variants, 10);
+                let count = u16::decode(&variants[6..])? as usize + u16::decode(&variants[8..])? as usize;
+                required_len!(variants, 10 + count * 2);
+                Some(variants)
+            },
+        };
+        Ok(Math { variants })
+    }
+}
+
+impl<'fnt> Math<'fnt> {
+    /// The least overlap of the connectors of neighbouring parts of an
+    /// assembly.
+    pub fn min_connector_overlap(&self) -> Result<u16> {
+        match self.variants {
+            Some(variants) => u16::decode(variants),
+            None => Ok(0),
+        }
+    }
+
+    /// The larger forms of `glyph` along `axis`, or `None` if the table
+    /// has none.
+    pub fn construction(&self, glyph: GlyphId, axis: StretchAxis) -> Result<Option<GlyphConstruction>> {
+        let variants = match self.variants {
+            Some(variants) => variants,
+            None => return Ok(None),
+        };
+        let (coverage_pos, first) = match axis {
+            StretchAxis::Vertical => (2, 0),
+            StretchAxis::Horizontal => (4, u16::decode(&variants[6..])? as usize),
+        };
+        let coverage = match u16::decode(&variants[coverage_pos..])? as usize {
+            0 => return Ok(None),
+            offset => Coverage::decode(variants.get(offset..).ok_or(Error::UnexpectedEof)?)?,
+        };
+        let index = match coverage.index(glyph) {
+            Some(index) => first + index as usize,
+            None => return Ok(None),
+        };
+
+        let offset = u16::decode(variants.get(10 + index * 2..).ok_or(Error::UnexpectedEof)?)? as usize;
+        let construction = variants.get(offset..).ok_or(Error::UnexpectedEof)?;
+        required_len!(construction, 4);
+        let variant_count = u16::decode(&construction[2..])? as usize;
+        required_len!(construction, 4 + variant_count * 4);
+        let variants = (0..variant_count)
+            .map(|index| {
+                let record = &construction[4 + index * 4..];
+                Ok((GlyphId(u16::decode(record)?), u16::decode(&record[2..])?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let (parts, italics_correction) = match u16::decode(construction)? as usize {
+            0 => (Vec::new(), 0),
+            offset => {
+                let assembly = construction.get(offset..).ok_or(Error::UnexpectedEof)?;
+                required_len!(assembly, 6);
+                let part_count = u16::decode(&assembly[4..])? as usize;
+                required_len!(assembly, 6 + part_count * 10);
+                let parts = (0..part_count)
+                    .map(|index| {
+                        let record = &assembly[6 + index * 10..];
+                        Ok(GlyphPart {
+                            glyph: GlyphId(u16::decode(record)?),
+                            start_connector_length: u16::decode(&record[2..])?,
+                            end_connector_length: u16::decode(&record[4..])?,
+                            full_advance: u16::decode(&record[6..])?,
+                            flags: u16::decode(&record[8..])?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                (parts, i16::decode(assembly)?)
+            },
+        };
+
+        Ok(Some(GlyphConstruction { variants, parts, italics_correction }))
+    }
+
+    /// How to draw `glyph` at least `target` font units long along `axis`,
+    /// or `None` if the table has no larger forms of it.
+    ///
+    /// The first pre-built size which is long enough is used.  Otherwise
+    /// the glyph is assembled from its parts, repeating the extenders as
+    /// few times as reaches `target` with the least overlap, and then
+    /// spreading the excess length evenly over the joins as extra overlap,
+    /// as far as the connectors allow.  Without an assembly, the largest
+    /// pre-built size is used.
+    pub fn stretch(&self, glyph: GlyphId, axis: StretchAxis, target: u16) -> Result<Option<Stretch>> {
+        let construction = match self.construction(glyph, axis)? {
+            Some(construction) => construction,
+            None => return Ok(None),
+        };
+        if let Some(&(glyph, advance)) = construction.variants.iter().find(|&&(_, advance)| advance >= target) {
+            return Ok(Some(Stretch::Variant { glyph, advance }))
+        }
+        if construction.parts.is_empty() {
+            let largest = construction.variants.iter().max_by_key(|&&(_, advance)| advance);
+            return Ok(largest.map(|&(glyph, advance)| Stretch::Variant { glyph, advance }))
+        }
+
+        let min_overlap = u32::from(self.min_connector_overlap()?);
+        let parts = repeat_extenders(&construction.parts, min_overlap, u32::from(target));
+        Ok(Some(place_parts(&parts, min_overlap, u32::from(target), construction.italics_correction)))
+    }
+}
+
+/// The parts of an assembly with its extenders repeated as few times as
+/// makes it at least `target` long with the least overlap.
+fn repeat_extenders(parts: &[GlyphPart], min_overlap: u32, target: u32) -> Vec<GlyphPart> {
+    // Each part after the first adds its advance less the overlap.
+    let added = |part: &GlyphPart| u32::from(part.full_advance).saturating_sub(min_overlap);
+    let fixed = parts.iter().filter(|part| !part.is_extender()).map(&added).sum::<u32>();
+    let repeated = parts.iter().filter(|part| part.is_extender()).map(&added).sum::<u32>();
+    let first = parts.iter().find(|part| !part.is_extender()).or_else(|| parts.first());
+    // The first part overlaps nothing, so it adds back the overlap.
+    let base = fixed + first.map_or(0, |_| min_overlap);
+
+    let repeats = if repeated == 0 || base >= target {
+        0
+    } else {
+        (target - base).div_ceil(repeated) as usize
+    };
+    let mut out = Vec::new();
+    for part in parts {
+        let count = if part.is_extender() { repeats } else { 1 };
+        out.extend((0..count).map(|_| *part));
+    }
+    if out.is_empty() {
+        // An assembly of extenders alone uses them at least once.
+        out.extend_from_slice(parts);
+    }
+    out
+}
+
+/// Place `parts` end to end, overlapping each join by at least
+/// `min_overlap` and by more to bring the assembly towards `target`.
+fn place_parts(parts: &[GlyphPart], min_overlap: u32, target: u32, italics_correction: i16) -> Stretch {
+    let full = parts.iter().map(|part| u32::from(part.full_advance)).sum::<u32>();
+    let joins = parts.len().saturating_sub(1) as u32;
+    let longest = full.saturating_sub(joins * min_overlap);
+    let extra = longest.saturating_sub(target).checked_div(joins).unwrap_or(0);
+
+    let mut placed = Vec::with_capacity(parts.len());
+    let mut offset = 0;
+    for (index, part) in parts.iter().enumerate() {
+        if index > 0 {
+            let previous = &parts[index - 1];
+            let connectors = u32::from(previous.end_connector_length.min(part.start_connector_length));
+            let overlap = (min_overlap + extra).min(connectors.max(min_overlap));
+            offset = (offset + u32::from(previous.full_advance)).saturating_sub(overlap);
+        }
+        placed.push(PlacedPart { glyph: part.glyph, offset });
+    }
+    let size = parts.last().map_or(0, |part| offset + u32::from(part.full_advance));
+    Stretch::Assembly { parts: placed, size, italics_correction }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_fonts;
+
+    #[test]
+    fn stretch_parenthesis() {
+        // A parenthesis with two larger sizes, and an assembly of a bottom
+        // hook, an extender and a top hook.
+        let data = test_fonts::math(20, &[
+            (10, &[(10, 800), (11, 1200), (12, 1800)], &[
+                (13, 0, 150, 600, 0),
+                (14, 150, 150, 300, EXTENDER_FLAG),
+                (15, 150, 0, 600, 0),
+            ]),
+            (20, &[(20, 700)], &[]),
+        ]);
+        let math = Math::decode(&data).unwrap();
+        assert_eq!(math.min_connector_overlap(), Ok(20));
+        assert_eq!(math.stretch(GlyphId(10), StretchAxis::Horizontal, 900), Ok(None));
+        assert_eq!(math.stretch(GlyphId(11), StretchAxis::Vertical, 900), Ok(None));
+
+        let variant = |glyph, advance| Ok(Some(Stretch::Variant { glyph: GlyphId(glyph), advance }));
+        assert_eq!(math.stretch(GlyphId(10), StretchAxis::Vertical, 500), variant(10, 800));
+        assert_eq!(math.stretch(GlyphId(10), StretchAxis::Vertical, 1000), variant(11, 1200));
+        assert_eq!(math.stretch(GlyphId(20), StretchAxis::Vertical, 1000), variant(20, 700));
+
+        // The hooks alone reach 1180; one extender reaches 1460, so 2400
+        // takes five, reaching 2580, and the 180 over spreads across the
+        // six joins as 30 more overlap each.
+        let offsets = match math.stretch(GlyphId(10), StretchAxis::Vertical, 2400).unwrap() {
+            Some(Stretch::Assembly { parts, size, .. }) => {
+                assert_eq!(size, 2400);
+                parts.iter().map(|part| (part.glyph.0, part.offset)).collect::<Vec<_>>()
+            },
+            stretch => panic!("unexpected stretch {:?}", stretch),
+        };
+        assert_eq!(offsets, vec![(13, 0), (14, 550), (14, 800), (14, 1050), (14, 1300), (14, 1550), (15, 1800)]);
+    }
+}
@@ -1,22 +1,17 @@
-use decode::{Decode, StaticEncodeSize, EncodeSize, DecodeRead};
+use decode::{Decode, StaticEncodeSize, EncodeSize};
 use primitives::Fixed;
 use error::{Error, Result};
 
+#[derive(EncodeSize, Debug, Copy, Clone, PartialEq)]
 pub enum Maxp {
     Version05(Version05),
     Version1(Version1),
 }
 
-impl<'fnt> Decode<'fnt> for Maxp {
-    fn decode(buffer: &'fnt [u8]) -> Result<Maxp> {
-        let version = u32::decode(buffer)?;
-        match version {
-            0x00005000 => Ok(Maxp::Version05(Version05::decode(buffer)?)),
-            0x00010000 => Ok(Maxp::Version1(Version1::decode(buffer)?)),
-            _ => Err(Error::UnsupportedVersion),
-        }
-    }
-}
+versioned_table!(Maxp, u32 =>
+    0x00005000 => Version05(Version05),
+    0x00010000 => Version1(Version1),
+);
 
 impl Maxp {
     pub fn get_num_glyphs(&self) -> u16 {
@@ -25,15 +20,60 @@ impl Maxp {
             Maxp::Version1(ref t) => t.num_glyphs,
         }
     }
+
+    /// The capacity hints of a version 1 table, or `None` for version 0.5,
+    /// which fonts with CFF outlines use.
+    pub fn profile(&self) -> Option<Profile> {
+        match *self {
+            Maxp::Version05(_) => None,
+            Maxp::Version1(ref t) => Some(Profile {
+                max_points: t.max_points.max(t.max_composite_points),
+                max_contours: t.max_contours.max(t.max_composite_contours),
+                max_component_elements: t.max_component_elements,
+                max_component_depth: t.max_component_depth,
+                max_twilight_points: t.max_twilight_points,
+                max_storage: t.max_storage,
+                max_function_defs: t.max_function_defs,
+                max_instruction_defs: t.max_instruction_defs,
+                max_stack_elements: t.max_stack_elements,
+                max_size_of_instructions: t.max_size_of_instructions,
+            }),
+        }
+    }
+}
+
+/// The largest sizes a font declares for its glyphs and hinting program,
+/// which a rasterizer can use to allocate its buffers once per font.
+/// These are claims of the font, not checked against its glyphs, so a
+/// consumer must still grow its buffers when a glyph exceeds them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Profile {
+    /// The most points of any glyph, simple or composite.
+    pub max_points: u16,
+    /// The most contours of any glyph, simple or composite.
+    pub max_contours: u16,
+    pub max_component_elements: u16,
+    /// The deepest nesting of composite glyphs, where a composite of
+    /// simple glyphs has depth 1.
+    pub max_component_depth: u16,
+    pub max_twilight_points: u16,
+    /// The number of storage area locations the hinting program uses.
+    pub max_storage: u16,
+    pub max_function_defs: u16,
+    pub max_instruction_defs: u16,
+    /// The depth of the interpreter stack.
+    pub max_stack_elements: u16,
+    /// The length of the longest glyph program, in bytes.
+    pub max_size_of_instructions: u16,
 }
 
-#[derive(Decode, StaticEncodeSize, Debug, PartialEq)]
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq)]
 pub struct Version05 {
     pub version: Fixed,
     pub num_glyphs: u16,
 }
 
-#[derive(Decode, StaticEncodeSize, Debug, PartialEq)]
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq)]
 pub struct Version1 {
     pub version: Fixed,
     pub num_glyphs: u16,
@@ -50,4 +90,30 @@ pub struct Version1 {
     pub max_size_of_instructions: u16,
     pub max_component_elements: u16,
     pub max_component_depth: u16,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::DecodeRead;
+    use test_fonts;
+
+    #[test]
+    fn versions() {
+        let maxp = Maxp::decode(&test_fonts::maxp(3)).expect("failed to read maxp");
+        assert_eq!(maxp.get_num_glyphs(), 3);
+        assert_eq!(maxp.profile(), None);
+
+        let data = [0x00, 0x02, 0x00, 0x00, 0x00, 0x03];
+        assert_eq!(Maxp::decode(&data), Err(Error::UnsupportedVersion(0x00020000)));
+
+        // Each version reads as much of a stream as its size.
+        let mut data = test_fonts::maxp(3);
+        data.extend_from_slice(&[0xAB, 0xCD]);
+        let mut buf = &data[..];
+        assert_eq!(buf.decode_read::<Maxp>().map(|maxp| maxp.encode_size()), Ok(Version05::size()));
+        assert_eq!(buf.decode_read::<u16>(), Ok(0xABCD));
+        let version1 = [&[0x00, 0x01, 0x00, 0x00][..], &[0; 28]].concat();
+        assert_eq!(Maxp::decode(&version1).map(|maxp| maxp.encode_size()), Ok(Version1::size()));
+    }
+}
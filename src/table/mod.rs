@@ -1,4 +1,4 @@
-// pub mod maxp;
+#![forbid(unsafe_code)]
 
 use primitives::Tag;
 
@@ -7,14 +7,134 @@ pub trait TaggedTable<'tbl> {
     fn tag() -> Tag;
 }
 
-macro_rules! impl_tagged_table {
-    ($($name:ty => $tag:expr),* $(,)*) => (
+/// Register the tables which can be decoded from a `Font`.  Each entry
+/// names the module and type of a table and its tag, and optionally the
+/// cargo feature which compiles it, so that a build without the feature
+/// has neither the table nor its entry in `SUPPORTED_TABLES`.
+macro_rules! tables {
+    ($($(#[feature = $feature:tt])* $module:ident :: $name:ident $(<$lt:lifetime>)* => $tag:expr),* $(,)*) => (
         $(
-        impl<'tbl> TaggedTable<'tbl> for $name {
+        $(#[cfg(feature = $feature)])*
+        impl<'tbl> TaggedTable<'tbl> for $module::$name$(<$lt>)* {
             fn tag() -> Tag {
                 Tag($tag)
             }
         }
         )*
+
+        /// The tags of the tables this build of the crate can decode.
+        pub const SUPPORTED_TABLES: &[Tag] = &[
+            $(
+            $(#[cfg(feature = $feature)])*
+            Tag($tag),
+            )*
+        ];
     )
-}
\ No newline at end of file
+}
+
+pub mod maxp;
+pub mod hhea;
+pub mod hmtx;
+pub mod head;
+pub mod post;
+pub mod os2;
+pub mod loca;
+pub mod glyf;
+pub mod cmap;
+pub mod name;
+pub mod kern;
+#[cfg(feature = "cff")]
+pub mod cff;
+#[cfg(feature = "variations")]
+pub mod variation;
+#[cfg(feature = "variations")]
+pub mod fvar;
+#[cfg(feature = "variations")]
+pub mod avar;
+#[cfg(feature = "variations")]
+pub mod gvar;
+#[cfg(feature = "variations")]
+pub mod cvar;
+#[cfg(feature = "variations")]
+pub mod hvar;
+#[cfg(feature = "variations")]
+pub mod mvar;
+#[cfg(feature = "variations")]
+pub mod stat;
+#[cfg(feature = "ift")]
+pub mod ift;
+#[cfg(feature = "color")]
+pub mod ebsc;
+#[cfg(feature = "layout")]
+pub mod layout;
+#[cfg(feature = "layout")]
+pub mod gdef;
+#[cfg(feature = "layout")]
+pub mod base;
+#[cfg(feature = "layout")]
+pub mod math;
+#[cfg(feature = "aat")]
+pub mod feat;
+#[cfg(feature = "color")]
+pub mod colr;
+#[cfg(feature = "color")]
+pub mod cpal;
+#[cfg(feature = "color")]
+pub mod svg;
+#[cfg(feature = "color")]
+pub mod sbix;
+#[cfg(feature = "color")]
+pub mod cblc;
+
+tables!(
+    maxp::Maxp => *b"maxp",
+    hhea::Hhea => *b"hhea",
+    hmtx::Hmtx<'tbl> => *b"hmtx",
+    head::Head => *b"head",
+    post::Post<'tbl> => *b"post",
+    os2::Os2 => *b"OS/2",
+    loca::Loca<'tbl> => *b"loca",
+    glyf::Glyf<'tbl> => *b"glyf",
+    cmap::Cmap<'tbl> => *b"cmap",
+    name::Name<'tbl> => *b"name",
+    kern::Kern<'tbl> => *b"kern",
+    #[feature = "cff"] cff::Cff<'tbl> => *b"CFF ",
+    #[feature = "variations"] fvar::Fvar<'tbl> => *b"fvar",
+    #[feature = "variations"] avar::Avar<'tbl> => *b"avar",
+    #[feature = "variations"] gvar::Gvar<'tbl> => *b"gvar",
+    #[feature = "variations"] cvar::Cvar<'tbl> => *b"cvar",
+    #[feature = "variations"] hvar::Hvar<'tbl> => *b"HVAR",
+    #[feature = "variations"] mvar::Mvar<'tbl> => *b"MVAR",
+    #[feature = "variations"] stat::Stat<'tbl> => *b"STAT",
+    #[feature = "ift"] ift::Ift<'tbl> => *b"IFT ",
+    #[feature = "ift"] ift::Iftx<'tbl> => *b"IFTX",
+    #[feature = "color"] ebsc::Ebsc<'tbl> => *b"EBSC",
+    #[feature = "layout"] layout::Gsub<'tbl> => *b"GSUB",
+    #[feature = "layout"] layout::Gpos<'tbl> => *b"GPOS",
+    #[feature = "layout"] gdef::Gdef<'tbl> => *b"GDEF",
+    #[feature = "layout"] base::Base<'tbl> => *b"BASE",
+    #[feature = "layout"] math::Math<'tbl> => *b"MATH",
+    #[feature = "aat"] feat::Feat<'tbl> => *b"feat",
+    #[feature = "color"] colr::Colr<'tbl> => *b"COLR",
+    #[feature = "color"] cpal::Cpal<'tbl> => *b"CPAL",
+    #[feature = "color"] svg::Svg<'tbl> => *b"SVG ",
+    #[feature = "color"] sbix::Sbix<'tbl> => *b"sbix",
+    #[feature = "color"] cblc::Cblc<'tbl> => *b"CBLC",
+    #[feature = "color"] cblc::Eblc<'tbl> => *b"EBLC",
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn supported_tables() {
+        assert!(SUPPORTED_TABLES.contains(&glyf::Glyf::tag()));
+        assert!(SUPPORTED_TABLES.contains(&cmap::Cmap::tag()));
+        assert_eq!(SUPPORTED_TABLES.contains(&Tag(*b"GSUB")), cfg!(feature = "layout"));
+        assert_eq!(SUPPORTED_TABLES.contains(&Tag(*b"gvar")), cfg!(feature = "variations"));
+        // `EBDT` is only read through the locations in `EBLC`.
+        assert_eq!(SUPPORTED_TABLES.contains(&Tag(*b"EBLC")), cfg!(feature = "color"));
+        assert!(!SUPPORTED_TABLES.contains(&Tag(*b"EBDT")));
+    }
+}
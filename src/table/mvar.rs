@@ -0,0 +1,100 @@
+use byteorder::{BigEndian, ByteOrder};
+
+use decode::Decode;
+use primitives::Tag;
+use table::variation::{ItemVariationStore, NormalizedCoord};
+use error::{Error, Result};
+
+/// The metrics variations table, which varies font-wide metrics such as
+/// the underline position by tag.
+#[derive(Debug, Copy, Clone)]
+pub struct Mvar<'fnt> {
+    records: &'fnt [u8],
+    record_size: usize,
+    record_count: usize,
+    store: Option<ItemVariationStore<'fnt>>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ValueRecord {
+    pub value_tag: Tag,
+    pub delta_set_outer_index: u16,
+    pub delta_set_inner_index: u16,
+}
+
+/// Tags of the metrics which `MVAR` can vary.
+pub mod tags {
+    use primitives::Tag;
+
+    pub const UNDERLINE_OFFSET: Tag = Tag::new(*b"undo");
+    pub const UNDERLINE_SIZE: Tag = Tag::new(*b"unds");
+    pub const STRIKEOUT_OFFSET: Tag = Tag::new(*b"stro");
+    pub const STRIKEOUT_SIZE: Tag = Tag::new(*b"strs");
+}
+
+impl<'fnt> Decode<'fnt> for Mvar<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Mvar<'fnt>> {
+        required_len!(buffer, 12);
+        let major_version = u16::decode(buffer)?;
+        if major_version != 1 {
+            return Err(Error::UnsupportedVersion(major_version.into()))
+        }
+
+        let record_size = u16::decode(&buffer[6..])? as usize;
+        let record_count = u16::decode(&buffer[8..])? as usize;
+        if record_count > 0 && record_size < 8 {
+            return Err(Error::InvalidData)
+        }
+
+        let records = &buffer[12..];
+        required_len!(records, record_size * record_count);
+
+        let store = match u16::decode(&buffer[10..])? as usize {
+            0 => None,
+            offset => {
+                let data = buffer.get(offset..).ok_or(Error::UnexpectedEof)?;
+                Some(ItemVariationStore::decode(data)?)
+            },
+        };
+
+        Ok(Mvar { records, record_size, record_count, store })
+    }
+}
+
+impl<'fnt> Mvar<'fnt> {
+    /// The value records, which may be larger than the fields known here.
+    pub fn records(&self) -> impl Iterator<Item = ValueRecord> + 'fnt {
+        let records = self.records;
+        let record_size = self.record_size;
+        (0..self.record_count).map(move |index| read_record(&records[index * record_size..]))
+    }
+
+    /// The delta of the metric `tag` at the normalized `coords`, which is
+    /// zero for metrics the table does not vary.
+    pub fn delta(&self, tag: Tag, coords: &[NormalizedCoord]) -> Result<f32> {
+        let (mut low, mut high) = (0, self.record_count);
+        while low < high {
+            let mid = (low + high) / 2;
+            let record = read_record(&self.records[mid * self.record_size..]);
+            if record.value_tag < tag {
+                low = mid + 1;
+            } else if record.value_tag > tag {
+                high = mid;
+            } else {
+                let store = self.store.as_ref().ok_or(Error::InvalidData)?;
+                return store.delta(record.delta_set_outer_index, record.delta_set_inner_index, coords)
+            }
+        }
+
+        Ok(0.0)
+    }
+}
+
+/// Read a record which decoding has already checked is in bounds.
+fn read_record(data: &[u8]) -> ValueRecord {
+    ValueRecord {
+        value_tag: Tag::new([data[0], data[1], data[2], data[3]]),
+        delta_set_outer_index: BigEndian::read_u16(&data[4..]),
+        delta_set_inner_index: BigEndian::read_u16(&data[6..]),
+    }
+}
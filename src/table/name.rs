@@ -0,0 +1,443 @@
+use std::collections::BTreeMap;
+
+use decode::{Decode, Decode1, StaticEncodeSize, EncodeSize};
+use encode::EncodeBuf;
+use primitives::Array;
+use error::{Error, Result};
+
+/// The naming table, which holds the human readable strings of a font,
+/// such as its family name and copyright notice, in any number of
+/// platforms, encodings and languages.
+#[derive(Debug, Copy, Clone)]
+pub struct Name<'fnt> {
+    pub format: u16,
+    records: Array<'fnt, NameRecord>,
+    /// The language tag records of format 1.
+    lang_tags: Array<'fnt, LangTagRecord>,
+    storage: &'fnt [u8],
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NameRecord {
+    pub platform_id: u16,
+    pub encoding_id: u16,
+    pub language_id: u16,
+    pub name_id: u16,
+    pub length: u16,
+    /// The offset of the string from the start of the storage area.
+    pub offset: u16,
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LangTagRecord {
+    pub length: u16,
+    pub offset: u16,
+}
+
+impl<'fnt> Decode<'fnt> for Name<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Name<'fnt>> {
+        required_len!(buffer, 6);
+        let format = u16::decode(buffer)?;
+        if format > 1 {
+            return Err(Error::UnsupportedVersion(format.into()))
+        }
+
+        let count = u16::decode(&buffer[2..])? as usize;
+        let storage = buffer.get(u16::decode(&buffer[4..])? as usize..).ok_or(Error::UnexpectedEof)?;
+        let records = Array::decode(&buffer[6..], count)?;
+
+        let mut lang_tags = Array::decode(&[], 0)?;
+        if format == 1 {
+            let pos = 6 + count * 12;
+            required_len!(buffer, pos + 2);
+            let lang_tag_count = u16::decode(&buffer[pos..])? as usize;
+            lang_tags = Array::decode(&buffer[pos + 2..], lang_tag_count)?;
+        }
+
+        Ok(Name { format, records, lang_tags, storage })
+    }
+}
+
+/// The meaning of a name record, from its name ID.  IDs which the
+/// specification reserves, and the font-specific IDs from 256, are
+/// `Custom`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum NameId {
+    Copyright,
+    FamilyName,
+    SubfamilyName,
+    UniqueId,
+    FullName,
+    Version,
+    PostScriptName,
+    Trademark,
+    Manufacturer,
+    Designer,
+    Description,
+    VendorUrl,
+    DesignerUrl,
+    License,
+    LicenseUrl,
+    TypographicFamilyName,
+    TypographicSubfamilyName,
+    CompatibleFullName,
+    SampleText,
+    PostScriptCidFindfontName,
+    WwsFamilyName,
+    WwsSubfamilyName,
+    LightBackgroundPalette,
+    DarkBackgroundPalette,
+    VariationsPostScriptNamePrefix,
+    Custom(u16),
+}
+
+/// The predefined name IDs, indexed by ID.  ID 15 is reserved.
+const PREDEFINED_NAMES: [Option<NameId>; 26] = [
+    Some(NameId::Copyright),
+    Some(NameId::FamilyName),
+    Some(NameId::SubfamilyName),
+    Some(NameId::UniqueId),
+    Some(NameId::FullName),
+    Some(NameId::Version),
+    Some(NameId::PostScriptName),
+    Some(NameId::Trademark),
+    Some(NameId::Manufacturer),
+    Some(NameId::Designer),
+    Some(NameId::Description),
+    Some(NameId::VendorUrl),
+    Some(NameId::DesignerUrl),
+    Some(NameId::License),
+    Some(NameId::LicenseUrl),
+    None,
+    Some(NameId::TypographicFamilyName),
+    Some(NameId::TypographicSubfamilyName),
+    Some(NameId::CompatibleFullName),
+    Some(NameId::SampleText),
+    Some(NameId::PostScriptCidFindfontName),
+    Some(NameId::WwsFamilyName),
+    Some(NameId::WwsSubfamilyName),
+    Some(NameId::LightBackgroundPalette),
+    Some(NameId::DarkBackgroundPalette),
+    Some(NameId::VariationsPostScriptNamePrefix),
+];
+
+impl From<u16> for NameId {
+    fn from(id: u16) -> NameId {
+        PREDEFINED_NAMES.get(id as usize).cloned().flatten().unwrap_or(NameId::Custom(id))
+    }
+}
+
+impl From<NameId> for u16 {
+    fn from(name: NameId) -> u16 {
+        match name {
+            NameId::Custom(id) => id,
+            name => PREDEFINED_NAMES.iter().position(|&predefined| predefined == Some(name)).unwrap_or(0) as u16,
+        }
+    }
+}
+
+/// A name record with its string decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameEntry {
+    pub platform_id: u16,
+    pub encoding_id: u16,
+    pub language_id: u16,
+    pub name_id: NameId,
+    /// The string, or `None` when its encoding is not supported or the
+    /// record points outside the table.
+    pub text: Option<String>,
+}
+
+impl<'fnt> Name<'fnt> {
+    pub fn records(&self) -> Array<'fnt, NameRecord> {
+        self.records
+    }
+
+    /// The raw bytes of the string of `record`.
+    pub fn data(&self, record: &NameRecord) -> Option<&'fnt [u8]> {
+        let start = record.offset as usize;
+        self.storage.get(start..start + record.length as usize)
+    }
+
+    /// The string of `record`.  Unicode and Windows strings are UTF-16,
+    /// and Macintosh strings in the Roman encoding are supported.
+    pub fn text(&self, record: &NameRecord) -> Option<String> {
+        decode_text(record.platform_id, record.encoding_id, self.data(record)?)
+    }
+
+    /// Every record in the table, in the order they are stored, with its
+    /// string decoded.
+    pub fn iter_all(&self) -> impl Iterator<Item = NameEntry> + 'fnt {
+        let name = *self;
+        self.records.into_iter().map(move |record| NameEntry {
+            platform_id: record.platform_id,
+            encoding_id: record.encoding_id,
+            language_id: record.language_id,
+            name_id: record.name_id.into(),
+            text: name.text(&record),
+        })
+    }
+
+    /// The string for `name_id` in the language best suited to a user
+    /// interface without a locale: Windows US English, then any Windows
+    /// language, then Macintosh English, then any record which decodes.
+    pub fn find(&self, name_id: NameId) -> Option<String> {
+        let id = u16::from(name_id);
+        let rank = |record: &NameRecord| match (record.platform_id, record.language_id) {
+            (3, 0x409) => 0,
+            (3, _) => 1,
+            (1, 0) => 2,
+            _ => 3,
+        };
+
+        let mut records = self.records.into_iter().filter(|record| record.name_id == id).collect::<Vec<_>>();
+        records.sort_by_key(rank);
+        records.iter().filter_map(|record| self.text(record)).next()
+    }
+
+    /// The BCP 47 language tag of a `language_id` of `0x8000` or more,
+    /// which format 1 tables store alongside the names.
+    pub fn language_tag(&self, language_id: u16) -> Option<String> {
+        let record = self.lang_tags.get(language_id.checked_sub(0x8000)? as usize)?;
+        let start = record.offset as usize;
+        decode_utf16(self.storage.get(start..start + record.length as usize)?)
+    }
+}
+
+/// Builds a `name` table from strings, writing each as a Windows US
+/// English record in UTF-16BE and, when every character has a Macintosh
+/// Roman byte, as a Macintosh English record as well.
+#[derive(Debug, Clone, Default)]
+pub struct NameBuilder {
+    names: BTreeMap<u16, String>,
+}
+
+impl NameBuilder {
+    pub fn new() -> NameBuilder {
+        NameBuilder::default()
+    }
+
+    /// A builder holding the string `Name::find` gives for each name ID of
+    /// `name`, to edit and build again.
+    pub fn from_name(name: &Name) -> NameBuilder {
+        let mut ids = name.records().into_iter().map(|record| record.name_id).collect::<Vec<_>>();
+        ids.sort();
+        ids.dedup();
+        let names = ids.into_iter().filter_map(|id| Some((id, name.find(id.into())?))).collect();
+        NameBuilder { names }
+    }
+
+    /// Set the string of `name_id`, replacing any existing string.
+    pub fn set(&mut self, name_id: NameId, text: &str) -> &mut NameBuilder {
+        self.names.insert(name_id.into(), text.to_string());
+        self
+    }
+
+    /// Remove the string of `name_id`, returning it if it was present.
+    pub fn remove(&mut self, name_id: NameId) -> Option<String> {
+        self.names.remove(&name_id.into())
+    }
+
+    pub fn get(&self, name_id: NameId) -> Option<&str> {
+        self.names.get(&name_id.into()).map(|text| text.as_str())
+    }
+
+    pub fn copyright(&mut self, text: &str) -> &mut NameBuilder {
+        self.set(NameId::Copyright, text)
+    }
+
+    pub fn family(&mut self, text: &str) -> &mut NameBuilder {
+        self.set(NameId::FamilyName, text)
+    }
+
+    /// Set the style within the family, such as `Bold Italic`.
+    pub fn style(&mut self, text: &str) -> &mut NameBuilder {
+        self.set(NameId::SubfamilyName, text)
+    }
+
+    pub fn full_name(&mut self, text: &str) -> &mut NameBuilder {
+        self.set(NameId::FullName, text)
+    }
+
+    pub fn version(&mut self, text: &str) -> &mut NameBuilder {
+        self.set(NameId::Version, text)
+    }
+
+    pub fn postscript_name(&mut self, text: &str) -> &mut NameBuilder {
+        self.set(NameId::PostScriptName, text)
+    }
+
+    pub fn license(&mut self, text: &str) -> &mut NameBuilder {
+        self.set(NameId::License, text)
+    }
+
+    pub fn license_url(&mut self, text: &str) -> &mut NameBuilder {
+        self.set(NameId::LicenseUrl, text)
+    }
+
+    pub fn typographic_family(&mut self, text: &str) -> &mut NameBuilder {
+        self.set(NameId::TypographicFamilyName, text)
+    }
+
+    pub fn typographic_style(&mut self, text: &str) -> &mut NameBuilder {
+        self.set(NameId::TypographicSubfamilyName, text)
+    }
+
+    /// Serialize a format 0 `name` table, with its records sorted by
+    /// platform, encoding, language and name ID.  Fails with `InvalidData`
+    /// if the strings do not fit the 16-bit offsets of the table.
+    pub fn build(&self) -> Result<Vec<u8>> {
+        // Records sort by platform first, so the Macintosh ones come first.
+        let mac = self.names.iter().filter_map(|(&id, text)| Some(((1, 0, 0, id), encode_mac_roman(text)?)));
+        let windows = self.names.iter().map(|(&id, text)| {
+            ((3, 1, 0x409, id), text.encode_utf16().flat_map(|unit| unit.to_be_bytes().to_vec()).collect())
+        });
+        let strings = mac.chain(windows).collect::<Vec<((u16, u16, u16, u16), Vec<u8>)>>();
+
+        let mut storage = Vec::new();
+        let mut out = EncodeBuf::new();
+        out.put_u16(0);
+        out.put_u16(strings.len() as u16);
+        out.put_u16((6 + strings.len() * 12) as u16);
+        for &((platform_id, encoding_id, language_id, name_id), ref data) in &strings {
+            if data.len() > 0xFFFF || storage.len() > 0xFFFF || 6 + strings.len() * 12 > 0xFFFF {
+                return Err(Error::InvalidData)
+            }
+            out.put_u16(platform_id);
+            out.put_u16(encoding_id);
+            out.put_u16(language_id);
+            out.put_u16(name_id);
+            out.put_u16(data.len() as u16);
+            out.put_u16(storage.len() as u16);
+            storage.extend_from_slice(data);
+        }
+        out.put_bytes(&storage);
+        Ok(out.into_vec())
+    }
+}
+
+/// `text` in the Macintosh Roman encoding, or `None` if a character has
+/// no byte in it.
+fn encode_mac_roman(text: &str) -> Option<Vec<u8>> {
+    text.chars()
+        .map(|c| match c as u32 {
+            code @ 0..=0x7F => Some(code as u8),
+            _ => MAC_ROMAN_HIGH.iter().position(|&high| high == c).map(|index| 0x80 + index as u8),
+        })
+        .collect()
+}
+
+fn decode_text(platform_id: u16, encoding_id: u16, data: &[u8]) -> Option<String> {
+    match (platform_id, encoding_id) {
+        (0, _) | (3, 0) | (3, 1) | (3, 10) => decode_utf16(data),
+        (1, 0) => Some(data.iter().map(|&byte| mac_roman(byte)).collect()),
+        _ => None,
+    }
+}
+
+fn decode_utf16(data: &[u8]) -> Option<String> {
+    if data.len() & 1 != 0 {
+        return None
+    }
+
+    let units = data.chunks(2).map(|pair| u16::from(pair[0]) << 8 | u16::from(pair[1]));
+    ::std::char::decode_utf16(units).collect::<::std::result::Result<String, _>>().ok()
+}
+
+fn mac_roman(byte: u8) -> char {
+    if byte < 0x80 {
+        return byte as char
+    }
+    MAC_ROMAN_HIGH[byte as usize - 0x80]
+}
+
+/// The Macintosh Roman characters from `0x80` to `0xFF`.
+const MAC_ROMAN_HIGH: [char; 128] = [
+    'Ä', 'Å', 'Ç', 'É', 'Ñ', 'Ö', 'Ü', 'á', 'à', 'â', 'ä', 'ã', 'å', 'ç', 'é', 'è',
+    'ê', 'ë', 'í', 'ì', 'î', 'ï', 'ñ', 'ó', 'ò', 'ô', 'ö', 'õ', 'ú', 'ù', 'û', 'ü',
+    '†', '°', '¢', '£', '§', '•', '¶', 'ß', '®', '©', '™', '´', '¨', '≠', 'Æ', 'Ø',
+    '∞', '±', '≤', '≥', '¥', 'µ', '∂', '∑', '∏', 'π', '∫', 'ª', 'º', 'Ω', 'æ', 'ø',
+    '¿', '¡', '¬', '√', 'ƒ', '≈', '∆', '«', '»', '…', '\u{A0}', 'À', 'Ã', 'Õ', 'Œ', 'œ',
+    '–', '—', '“', '”', '‘', '’', '÷', '◊', 'ÿ', 'Ÿ', '⁄', '€', '‹', '›', 'ﬁ', 'ﬂ',
+    '‡', '·', '‚', '„', '‰', 'Â', 'Ê', 'Á', 'Ë', 'È', 'Í', 'Î', 'Ï', 'Ì', 'Ó', 'Ô',
+    '\u{F8FF}', 'Ò', 'Ú', 'Û', 'Ù', 'ı', 'ˆ', '˜', '¯', '˘', '˙', '˚', '¸', '˝', '˛', 'ˇ',
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use font::Font;
+
+    #[test]
+    fn names() {
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let name = font.table::<Name>().expect("failed to read name table");
+
+        let entries = name.iter_all().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 28);
+        assert!(entries.iter().all(|entry| entry.text.is_some()));
+        let family = entries.iter()
+            .filter(|entry| entry.name_id == NameId::FamilyName)
+            .map(|entry| (entry.platform_id, entry.text.clone().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(family, vec![(1, "Droid Serif".to_string()), (3, "Droid Serif".to_string())]);
+
+        assert_eq!(name.find(NameId::PostScriptName), Some("DroidSerif".to_string()));
+        assert_eq!(name.find(NameId::TypographicFamilyName), None);
+
+        assert_eq!(NameId::from(16), NameId::TypographicFamilyName);
+        assert_eq!(NameId::from(15), NameId::Custom(15));
+        assert_eq!(NameId::from(300), NameId::Custom(300));
+        assert_eq!(u16::from(NameId::WwsFamilyName), 21);
+        assert_eq!(u16::from(NameId::Custom(256)), 256);
+
+        let data = [
+            0, 1, 0, 1, 0, 24,
+            0, 3, 0, 1, 0x80, 0, 0, 1, 0, 2, 0, 0,
+            0, 1, 0, 4, 0, 2,
+            0, b'A', 0, b'e', 0, b'n',
+        ];
+        let name = Name::decode(&data).unwrap();
+        let entry = name.iter_all().next().unwrap();
+        assert_eq!(entry.text, Some("A".to_string()));
+        assert_eq!(name.language_tag(entry.language_id), Some("en".to_string()));
+        assert_eq!(name.language_tag(0x409), None);
+
+        assert_eq!(decode_text(1, 0, &[b'a', 0x8A, 0xD5]), Some("aä’".to_string()));
+        assert_eq!(decode_text(3, 1, &[0, b'a', 0xD8]), None);
+        assert_eq!(decode_text(2, 0, b"a"), None);
+    }
+
+    #[test]
+    fn build_names() {
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let mut builder = NameBuilder::from_name(&font.table::<Name>().unwrap());
+        assert_eq!(builder.get(NameId::FamilyName), Some("Droid Serif"));
+        builder.family("Droid Serif Extra").style("Bold").license("Ünïcödé → licence");
+        assert!(builder.remove(NameId::Trademark).is_some());
+
+        let data = builder.build().unwrap();
+        let name = Name::decode(&data).unwrap();
+        let keys = name.records().into_iter()
+            .map(|record| (record.platform_id, record.encoding_id, record.language_id, record.name_id))
+            .collect::<Vec<_>>();
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(name.iter_all().all(|entry| entry.name_id != NameId::Trademark));
+
+        let texts = |id| name.iter_all()
+            .filter(|entry| entry.name_id == id)
+            .map(|entry| (entry.platform_id, entry.text.unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(texts(NameId::FamilyName), vec![(1, "Droid Serif Extra".to_string()), (3, "Droid Serif Extra".to_string())]);
+        assert_eq!(texts(NameId::SubfamilyName), vec![(1, "Bold".to_string()), (3, "Bold".to_string())]);
+        // The arrow has no Macintosh Roman byte.
+        assert_eq!(texts(NameId::License), vec![(3, "Ünïcödé → licence".to_string())]);
+        assert_eq!(encode_mac_roman("aä’"), Some(vec![b'a', 0x8A, 0xD5]));
+
+        let mut builder = NameBuilder::new();
+        builder.copyright(&"x".repeat(0x8000)).license(&"y".repeat(0x8000));
+        assert_eq!(builder.build().err(), Some(Error::InvalidData));
+    }
+}
@@ -0,0 +1,560 @@
+use decode::{Decode, DecodeRead, StaticEncodeSize};
+use primitives::Tag;
+use error::Result;
+
+/// The OS/2 and Windows metrics table.  Fields which were added in later
+/// versions of the table are `None` when the table is too old to have them.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Os2 {
+    pub version: u16,
+    pub x_avg_char_width: i16,
+    pub us_weight_class: u16,
+    pub us_width_class: u16,
+    pub fs_type: u16,
+    pub y_subscript_x_size: i16,
+    pub y_subscript_y_size: i16,
+    pub y_subscript_x_offset: i16,
+    pub y_subscript_y_offset: i16,
+    pub y_superscript_x_size: i16,
+    pub y_superscript_y_size: i16,
+    pub y_superscript_x_offset: i16,
+    pub y_superscript_y_offset: i16,
+    pub y_strikeout_size: i16,
+    pub y_strikeout_position: i16,
+    pub s_family_class: i16,
+    pub panose: Panose,
+    pub ul_unicode_range: [u32; 4],
+    pub ach_vend_id: Tag,
+    pub fs_selection: u16,
+    pub us_first_char_index: u16,
+    pub us_last_char_index: u16,
+    pub s_typo_ascender: i16,
+    pub s_typo_descender: i16,
+    pub s_typo_line_gap: i16,
+    pub us_win_ascent: u16,
+    pub us_win_descent: u16,
+    /// Available from version 1.
+    pub ul_code_page_range: Option<[u32; 2]>,
+    /// Available from version 2.
+    pub sx_height: Option<i16>,
+    /// Available from version 2.
+    pub s_cap_height: Option<i16>,
+    /// Available from version 2.
+    pub us_default_char: Option<u16>,
+    /// Available from version 2.
+    pub us_break_char: Option<u16>,
+    /// Available from version 2.
+    pub us_max_context: Option<u16>,
+    /// Available from version 5.
+    pub us_lower_optical_point_size: Option<u16>,
+    /// Available from version 5.
+    pub us_upper_optical_point_size: Option<u16>,
+}
+
+impl<'fnt> Decode<'fnt> for Os2 {
+    fn decode(buffer: &'fnt [u8]) -> Result<Os2> {
+        let mut buf = buffer;
+        let version = buf.decode_read::<u16>()?;
+        let x_avg_char_width = buf.decode_read()?;
+        let us_weight_class = buf.decode_read()?;
+        let us_width_class = buf.decode_read()?;
+        let fs_type = buf.decode_read()?;
+        let y_subscript_x_size = buf.decode_read()?;
+        let y_subscript_y_size = buf.decode_read()?;
+        let y_subscript_x_offset = buf.decode_read()?;
+        let y_subscript_y_offset = buf.decode_read()?;
+        let y_superscript_x_size = buf.decode_read()?;
+        let y_superscript_y_size = buf.decode_read()?;
+        let y_superscript_x_offset = buf.decode_read()?;
+        let y_superscript_y_offset = buf.decode_read()?;
+        let y_strikeout_size = buf.decode_read()?;
+        let y_strikeout_position = buf.decode_read()?;
+        let s_family_class = buf.decode_read()?;
+
+        let panose = buf.decode_read()?;
+
+        let ul_unicode_range = buf.decode_read()?;
+
+        let ach_vend_id = buf.decode_read()?;
+        let fs_selection = buf.decode_read()?;
+        let us_first_char_index = buf.decode_read()?;
+        let us_last_char_index = buf.decode_read()?;
+        let s_typo_ascender = buf.decode_read()?;
+        let s_typo_descender = buf.decode_read()?;
+        let s_typo_line_gap = buf.decode_read()?;
+        let us_win_ascent = buf.decode_read()?;
+        let us_win_descent = buf.decode_read()?;
+
+        let ul_code_page_range = if version >= 1 {
+            Some([buf.decode_read()?, buf.decode_read()?])
+        } else {
+            None
+        };
+
+        let (sx_height, s_cap_height, us_default_char, us_break_char, us_max_context) =
+            if version >= 2 {
+                (Some(buf.decode_read()?),
+                 Some(buf.decode_read()?),
+                 Some(buf.decode_read()?),
+                 Some(buf.decode_read()?),
+                 Some(buf.decode_read()?))
+            } else {
+                (None, None, None, None, None)
+            };
+
+        let (us_lower_optical_point_size, us_upper_optical_point_size) = if version >= 5 {
+            (Some(buf.decode_read()?), Some(buf.decode_read()?))
+        } else {
+            (None, None)
+        };
+
+        Ok(Os2 {
+            version,
+            x_avg_char_width,
+            us_weight_class,
+            us_width_class,
+            fs_type,
+            y_subscript_x_size,
+            y_subscript_y_size,
+            y_subscript_x_offset,
+            y_subscript_y_offset,
+            y_superscript_x_size,
+            y_superscript_y_size,
+            y_superscript_x_offset,
+            y_superscript_y_offset,
+            y_strikeout_size,
+            y_strikeout_position,
+            s_family_class,
+            panose,
+            ul_unicode_range,
+            ach_vend_id,
+            fs_selection,
+            us_first_char_index,
+            us_last_char_index,
+            s_typo_ascender,
+            s_typo_descender,
+            s_typo_line_gap,
+            us_win_ascent,
+            us_win_descent,
+            ul_code_page_range,
+            sx_height,
+            s_cap_height,
+            us_default_char,
+            us_break_char,
+            us_max_context,
+            us_lower_optical_point_size,
+            us_upper_optical_point_size,
+        })
+    }
+}
+
+impl Os2 {
+    /// Whether PANOSE classifies the font as Latin text with monospaced
+    /// proportions.
+    pub fn is_panose_monospaced(&self) -> bool {
+        self.panose.proportion() == Some(Proportion::Monospaced)
+    }
+
+    /// Whether the `fsSelection` italic bit is set.
+    pub fn is_italic(&self) -> bool {
+        self.fs_selection & 0x0001 != 0
+    }
+
+    /// Whether the `fsSelection` bold bit is set.
+    pub fn is_bold(&self) -> bool {
+        self.fs_selection & 0x0020 != 0
+    }
+
+    /// Whether the `fsSelection` bit `USE_TYPO_METRICS` is set, which asks
+    /// for lines to be spaced by the typographic metrics rather than the
+    /// Windows ones.
+    pub fn use_typo_metrics(&self) -> bool {
+        self.fs_selection & 0x0080 != 0
+    }
+
+    /// The IBM font class, which is the high byte of `sFamilyClass`.
+    pub fn family_class(&self) -> u8 {
+        (self.s_family_class >> 8) as u8
+    }
+
+    pub fn unicode_ranges(&self) -> UnicodeRanges {
+        UnicodeRanges(self.ul_unicode_range)
+    }
+
+    /// The code page ranges, which versions 1 and later declare.
+    pub fn code_page_ranges(&self) -> Option<CodePageRanges> {
+        self.ul_code_page_range.map(CodePageRanges)
+    }
+}
+
+/// A bit of `ulUnicodeRange`, which stands for one or more Unicode blocks.
+/// Bits 0 to 122 are assigned.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct UnicodeBlockBit(pub u8);
+
+impl UnicodeBlockBit {
+    pub const BASIC_LATIN: UnicodeBlockBit = UnicodeBlockBit(0);
+    pub const LATIN_1_SUPPLEMENT: UnicodeBlockBit = UnicodeBlockBit(1);
+    pub const LATIN_EXTENDED_A: UnicodeBlockBit = UnicodeBlockBit(2);
+    pub const LATIN_EXTENDED_B: UnicodeBlockBit = UnicodeBlockBit(3);
+    pub const GREEK_AND_COPTIC: UnicodeBlockBit = UnicodeBlockBit(7);
+    pub const CYRILLIC: UnicodeBlockBit = UnicodeBlockBit(9);
+    pub const HEBREW: UnicodeBlockBit = UnicodeBlockBit(11);
+    pub const ARABIC: UnicodeBlockBit = UnicodeBlockBit(13);
+    pub const DEVANAGARI: UnicodeBlockBit = UnicodeBlockBit(15);
+    pub const THAI: UnicodeBlockBit = UnicodeBlockBit(24);
+    pub const LATIN_EXTENDED_ADDITIONAL: UnicodeBlockBit = UnicodeBlockBit(29);
+    pub const GENERAL_PUNCTUATION: UnicodeBlockBit = UnicodeBlockBit(31);
+    pub const CURRENCY_SYMBOLS: UnicodeBlockBit = UnicodeBlockBit(33);
+    pub const HIRAGANA: UnicodeBlockBit = UnicodeBlockBit(49);
+    pub const KATAKANA: UnicodeBlockBit = UnicodeBlockBit(50);
+    pub const HANGUL_SYLLABLES: UnicodeBlockBit = UnicodeBlockBit(56);
+    /// Set when the font maps any character outside the Basic
+    /// Multilingual Plane.
+    pub const NON_PLANE_0: UnicodeBlockBit = UnicodeBlockBit(57);
+    pub const CJK_UNIFIED_IDEOGRAPHS: UnicodeBlockBit = UnicodeBlockBit(59);
+    pub const PRIVATE_USE_AREA: UnicodeBlockBit = UnicodeBlockBit(60);
+
+    /// The code point ranges of the blocks of this bit.  `NON_PLANE_0`
+    /// has none, as it stands for the surrogates used to encode them.
+    pub fn ranges(self) -> impl Iterator<Item = (u32, u32)> {
+        UNICODE_BLOCKS.iter()
+            .filter(move |&&(bit, _, _)| bit == self.0)
+            .map(|&(_, start, end)| (start, end))
+    }
+
+    /// The bit of the block which contains `character`, if any.
+    pub fn of(character: char) -> Option<UnicodeBlockBit> {
+        let code = character as u32;
+        UNICODE_BLOCKS.iter()
+            .find(|&&(_, start, end)| start <= code && code <= end)
+            .map(|&(bit, _, _)| UnicodeBlockBit(bit))
+    }
+}
+
+/// The `ulUnicodeRange1` to `ulUnicodeRange4` bits, which declare the
+/// Unicode blocks a font is functional for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct UnicodeRanges(pub [u32; 4]);
+
+impl UnicodeRanges {
+    pub fn supports_block(&self, bit: UnicodeBlockBit) -> bool {
+        bit.0 < 128 && self.0[bit.0 as usize / 32] & 1 << (bit.0 % 32) != 0
+    }
+
+    /// The bits which are set, in order.
+    pub fn blocks(&self) -> Vec<UnicodeBlockBit> {
+        (0..128).map(UnicodeBlockBit).filter(|&bit| self.supports_block(bit)).collect()
+    }
+}
+
+/// A bit of `ulCodePageRange`, which stands for a code page.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CodePageBit(pub u8);
+
+impl CodePageBit {
+    pub const LATIN_1: CodePageBit = CodePageBit(0);
+    pub const LATIN_2: CodePageBit = CodePageBit(1);
+    pub const CYRILLIC: CodePageBit = CodePageBit(2);
+    pub const GREEK: CodePageBit = CodePageBit(3);
+    pub const TURKISH: CodePageBit = CodePageBit(4);
+    pub const HEBREW: CodePageBit = CodePageBit(5);
+    pub const ARABIC: CodePageBit = CodePageBit(6);
+    pub const BALTIC: CodePageBit = CodePageBit(7);
+    pub const VIETNAMESE: CodePageBit = CodePageBit(8);
+    pub const THAI: CodePageBit = CodePageBit(16);
+    pub const JAPANESE: CodePageBit = CodePageBit(17);
+    pub const CHINESE_SIMPLIFIED: CodePageBit = CodePageBit(18);
+    pub const KOREAN_WANSUNG: CodePageBit = CodePageBit(19);
+    pub const CHINESE_TRADITIONAL: CodePageBit = CodePageBit(20);
+    pub const KOREAN_JOHAB: CodePageBit = CodePageBit(21);
+    pub const MACINTOSH: CodePageBit = CodePageBit(29);
+    pub const OEM: CodePageBit = CodePageBit(30);
+    pub const SYMBOL: CodePageBit = CodePageBit(31);
+
+    /// The Windows or OEM code page number, such as 1252 for `LATIN_1`,
+    /// for bits which stand for one.
+    pub fn code_page(self) -> Option<u16> {
+        let code_page = match self.0 {
+            0..=8 => WINDOWS_CODE_PAGES[self.0 as usize],
+            16 => 874,
+            17 => 932,
+            18 => 936,
+            19 => 949,
+            20 => 950,
+            21 => 1361,
+            48..=63 => OEM_CODE_PAGES[self.0 as usize - 48],
+            _ => return None,
+        };
+        Some(code_page)
+    }
+}
+
+/// The Windows code pages of bits 0 to 8.
+const WINDOWS_CODE_PAGES: [u16; 9] = [1252, 1250, 1251, 1253, 1254, 1255, 1256, 1257, 1258];
+
+/// The OEM code pages of bits 48 to 63.
+const OEM_CODE_PAGES: [u16; 16] = [869, 866, 865, 864, 863, 862, 861, 860, 857, 855, 852, 775, 737, 708, 850, 437];
+
+/// The `ulCodePageRange1` and `ulCodePageRange2` bits, which declare the
+/// code pages a font is functional for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CodePageRanges(pub [u32; 2]);
+
+impl CodePageRanges {
+    pub fn supports(&self, bit: CodePageBit) -> bool {
+        bit.0 < 64 && self.0[bit.0 as usize / 32] & 1 << (bit.0 % 32) != 0
+    }
+
+    /// The bits which are set, in order.
+    pub fn code_pages(&self) -> Vec<CodePageBit> {
+        (0..64).map(CodePageBit).filter(|&bit| self.supports(bit)).collect()
+    }
+}
+
+/// The Unicode blocks of each `ulUnicodeRange` bit, as `(bit, first, last)`.
+const UNICODE_BLOCKS: &[(u8, u32, u32)] = &[
+    (0, 0x0000, 0x007F), (1, 0x0080, 0x00FF), (2, 0x0100, 0x017F), (3, 0x0180, 0x024F),
+    (4, 0x0250, 0x02AF), (4, 0x1D00, 0x1D7F), (4, 0x1D80, 0x1DBF),
+    (5, 0x02B0, 0x02FF), (5, 0xA700, 0xA71F),
+    (6, 0x0300, 0x036F), (6, 0x1DC0, 0x1DFF),
+    (7, 0x0370, 0x03FF), (8, 0x2C80, 0x2CFF),
+    (9, 0x0400, 0x04FF), (9, 0x0500, 0x052F), (9, 0x2DE0, 0x2DFF), (9, 0xA640, 0xA69F),
+    (10, 0x0530, 0x058F), (11, 0x0590, 0x05FF), (12, 0xA500, 0xA63F),
+    (13, 0x0600, 0x06FF), (13, 0x0750, 0x077F),
+    (14, 0x07C0, 0x07FF), (15, 0x0900, 0x097F), (16, 0x0980, 0x09FF), (17, 0x0A00, 0x0A7F),
+    (18, 0x0A80, 0x0AFF), (19, 0x0B00, 0x0B7F), (20, 0x0B80, 0x0BFF), (21, 0x0C00, 0x0C7F),
+    (22, 0x0C80, 0x0CFF), (23, 0x0D00, 0x0D7F), (24, 0x0E00, 0x0E7F), (25, 0x0E80, 0x0EFF),
+    (26, 0x10A0, 0x10FF), (26, 0x2D00, 0x2D2F),
+    (27, 0x1B00, 0x1B7F), (28, 0x1100, 0x11FF),
+    (29, 0x1E00, 0x1EFF), (29, 0x2C60, 0x2C7F), (29, 0xA720, 0xA7FF),
+    (30, 0x1F00, 0x1FFF),
+    (31, 0x2000, 0x206F), (31, 0x2E00, 0x2E7F),
+    (32, 0x2070, 0x209F), (33, 0x20A0, 0x20CF), (34, 0x20D0, 0x20FF), (35, 0x2100, 0x214F),
+    (36, 0x2150, 0x218F),
+    (37, 0x2190, 0x21FF), (37, 0x27F0, 0x27FF), (37, 0x2900, 0x297F), (37, 0x2B00, 0x2BFF),
+    (38, 0x2200, 0x22FF), (38, 0x2A00, 0x2AFF), (38, 0x27C0, 0x27EF), (38, 0x2980, 0x29FF),
+    (39, 0x2300, 0x23FF), (40, 0x2400, 0x243F), (41, 0x2440, 0x245F), (42, 0x2460, 0x24FF),
+    (43, 0x2500, 0x257F), (44, 0x2580, 0x259F), (45, 0x25A0, 0x25FF), (46, 0x2600, 0x26FF),
+    (47, 0x2700, 0x27BF), (48, 0x3000, 0x303F), (49, 0x3040, 0x309F),
+    (50, 0x30A0, 0x30FF), (50, 0x31F0, 0x31FF),
+    (51, 0x3100, 0x312F), (51, 0x31A0, 0x31BF),
+    (52, 0x3130, 0x318F), (53, 0xA840, 0xA87F), (54, 0x3200, 0x32FF), (55, 0x3300, 0x33FF),
+    (56, 0xAC00, 0xD7AF), (58, 0x10900, 0x1091F),
+    (59, 0x4E00, 0x9FFF), (59, 0x2E80, 0x2EFF), (59, 0x2F00, 0x2FDF), (59, 0x2FF0, 0x2FFF),
+    (59, 0x3400, 0x4DBF), (59, 0x20000, 0x2A6DF), (59, 0x3190, 0x319F),
+    (60, 0xE000, 0xF8FF),
+    (61, 0x31C0, 0x31EF), (61, 0xF900, 0xFAFF), (61, 0x2F800, 0x2FA1F),
+    (62, 0xFB00, 0xFB4F), (63, 0xFB50, 0xFDFF), (64, 0xFE20, 0xFE2F),
+    (65, 0xFE10, 0xFE1F), (65, 0xFE30, 0xFE4F),
+    (66, 0xFE50, 0xFE6F), (67, 0xFE70, 0xFEFF), (68, 0xFF00, 0xFFEF), (69, 0xFFF0, 0xFFFF),
+    (70, 0x0F00, 0x0FFF), (71, 0x0700, 0x074F), (72, 0x0780, 0x07BF), (73, 0x0D80, 0x0DFF),
+    (74, 0x1000, 0x109F),
+    (75, 0x1200, 0x137F), (75, 0x1380, 0x139F), (75, 0x2D80, 0x2DDF),
+    (76, 0x13A0, 0x13FF), (77, 0x1400, 0x167F), (78, 0x1680, 0x169F), (79, 0x16A0, 0x16FF),
+    (80, 0x1780, 0x17FF), (80, 0x19E0, 0x19FF),
+    (81, 0x1800, 0x18AF), (82, 0x2800, 0x28FF),
+    (83, 0xA000, 0xA48F), (83, 0xA490, 0xA4CF),
+    (84, 0x1700, 0x171F), (84, 0x1720, 0x173F), (84, 0x1740, 0x175F), (84, 0x1760, 0x177F),
+    (85, 0x10300, 0x1032F), (86, 0x10330, 0x1034F), (87, 0x10400, 0x1044F),
+    (88, 0x1D000, 0x1D0FF), (88, 0x1D100, 0x1D1FF), (88, 0x1D200, 0x1D24F),
+    (89, 0x1D400, 0x1D7FF),
+    (90, 0xF0000, 0xFFFFD), (90, 0x100000, 0x10FFFD),
+    (91, 0xFE00, 0xFE0F), (91, 0xE0100, 0xE01EF),
+    (92, 0xE0000, 0xE007F), (93, 0x1900, 0x194F), (94, 0x1950, 0x197F), (95, 0x1980, 0x19DF),
+    (96, 0x1A00, 0x1A1F), (97, 0x2C00, 0x2C5F), (98, 0x2D30, 0x2D7F), (99, 0x4DC0, 0x4DFF),
+    (100, 0xA800, 0xA82F),
+    (101, 0x10000, 0x1007F), (101, 0x10080, 0x100FF), (101, 0x10100, 0x1013F),
+    (102, 0x10140, 0x1018F), (103, 0x10380, 0x1039F), (104, 0x103A0, 0x103DF),
+    (105, 0x10450, 0x1047F), (106, 0x10480, 0x104AF), (107, 0x10800, 0x1083F),
+    (108, 0x10A00, 0x10A5F), (109, 0x1D300, 0x1D35F),
+    (110, 0x12000, 0x123FF), (110, 0x12400, 0x1247F),
+    (111, 0x1D360, 0x1D37F), (112, 0x1B80, 0x1BBF), (113, 0x1C00, 0x1C4F), (114, 0x1C50, 0x1C7F),
+    (115, 0xA880, 0xA8DF), (116, 0xA900, 0xA92F), (117, 0xA930, 0xA95F), (118, 0xAA00, 0xAA5F),
+    (119, 0x10190, 0x101CF), (120, 0x101D0, 0x101FF),
+    (121, 0x102A0, 0x102DF), (121, 0x10280, 0x1029F), (121, 0x10920, 0x1093F),
+    (122, 0x1F030, 0x1F09F), (122, 0x1F000, 0x1F02F),
+];
+
+/// The 10 digit PANOSE classification of a font's visual appearance.
+/// The meaning of every digit after the first depends on the family kind,
+/// so the typed accessors return `None` for kinds they do not apply to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Panose(pub [u8; 10]);
+
+static_size!(Panose = 10);
+
+impl<'fnt> Decode<'fnt> for Panose {
+    fn decode(buffer: &'fnt [u8]) -> Result<Panose> {
+        Ok(Panose(<[u8; 10]>::decode(buffer)?))
+    }
+}
+
+macro_rules! panose_digit {
+    ($(#[$attr:meta])* $name:ident { $($variant:ident = $value:expr,)* }) => (
+        $(#[$attr])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant,)*
+            /// A value which is not defined by the specification.
+            Other(u8),
+        }
+
+        impl From<u8> for $name {
+            fn from(value: u8) -> $name {
+                match value {
+                    $($value => $name::$variant,)*
+                    other => $name::Other(other),
+                }
+            }
+        }
+    )
+}
+
+panose_digit!(
+    /// The first PANOSE digit, which determines how the others are read.
+    FamilyKind {
+        Any = 0,
+        NoFit = 1,
+        LatinText = 2,
+        LatinHandWritten = 3,
+        LatinDecorative = 4,
+        LatinSymbol = 5,
+    }
+);
+
+panose_digit!(
+    /// The serif style of Latin text.
+    SerifStyle {
+        Any = 0,
+        NoFit = 1,
+        Cove = 2,
+        ObtuseCove = 3,
+        SquareCove = 4,
+        ObtuseSquareCove = 5,
+        Square = 6,
+        Thin = 7,
+        Oval = 8,
+        Exaggerated = 9,
+        Triangle = 10,
+        NormalSans = 11,
+        ObtuseSans = 12,
+        PerpendicularSans = 13,
+        Flared = 14,
+        Rounded = 15,
+    }
+);
+
+panose_digit!(
+    /// The weight of any Latin family kind.
+    Weight {
+        Any = 0,
+        NoFit = 1,
+        VeryLight = 2,
+        Light = 3,
+        Thin = 4,
+        Book = 5,
+        Medium = 6,
+        Demi = 7,
+        Bold = 8,
+        Heavy = 9,
+        Black = 10,
+        ExtraBlack = 11,
+    }
+);
+
+panose_digit!(
+    /// The proportion of Latin text.
+    Proportion {
+        Any = 0,
+        NoFit = 1,
+        OldStyle = 2,
+        Modern = 3,
+        EvenWidth = 4,
+        Extended = 5,
+        Condensed = 6,
+        VeryExtended = 7,
+        VeryCondensed = 8,
+        Monospaced = 9,
+    }
+);
+
+impl Panose {
+    pub fn family_kind(&self) -> FamilyKind {
+        FamilyKind::from(self.0[0])
+    }
+
+    pub fn serif_style(&self) -> Option<SerifStyle> {
+        match self.family_kind() {
+            FamilyKind::LatinText => Some(SerifStyle::from(self.0[1])),
+            _ => None,
+        }
+    }
+
+    pub fn weight(&self) -> Option<Weight> {
+        match self.family_kind() {
+            FamilyKind::LatinText
+            | FamilyKind::LatinHandWritten
+            | FamilyKind::LatinDecorative
+            | FamilyKind::LatinSymbol => Some(Weight::from(self.0[2])),
+            _ => None,
+        }
+    }
+
+    pub fn proportion(&self) -> Option<Proportion> {
+        match self.family_kind() {
+            FamilyKind::LatinText => Some(Proportion::from(self.0[3])),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use font::Font;
+
+    #[test]
+    fn panose() {
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let panose = font.table::<Os2>().expect("failed to read OS/2").panose;
+
+        assert_eq!(panose.family_kind(), FamilyKind::LatinText);
+        assert!(panose.serif_style().is_some());
+        assert_eq!(panose.proportion().map(|p| p == Proportion::Monospaced), Some(false));
+
+        let symbol = Panose([5, 2, 1, 2, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(symbol.family_kind(), FamilyKind::LatinSymbol);
+        assert_eq!(symbol.weight(), Some(Weight::NoFit));
+        assert_eq!(symbol.serif_style(), None);
+        assert_eq!(Panose([17, 0, 0, 0, 0, 0, 0, 0, 0, 0]).family_kind(), FamilyKind::Other(17));
+    }
+
+    #[test]
+    fn ranges() {
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let os2 = font.table::<Os2>().expect("failed to read OS/2");
+
+        let unicode = os2.unicode_ranges();
+        assert!(unicode.supports_block(UnicodeBlockBit::BASIC_LATIN));
+        assert!(unicode.supports_block(UnicodeBlockBit::CYRILLIC));
+        assert!(!unicode.supports_block(UnicodeBlockBit::HEBREW));
+        assert!(!unicode.supports_block(UnicodeBlockBit(200)));
+        assert_eq!(unicode.blocks().len(), 20);
+
+        let code_pages = os2.code_page_ranges().unwrap();
+        assert_eq!(code_pages.code_pages(), vec![
+            CodePageBit::LATIN_1, CodePageBit::LATIN_2, CodePageBit::CYRILLIC, CodePageBit::GREEK,
+            CodePageBit::TURKISH, CodePageBit::BALTIC, CodePageBit::VIETNAMESE, CodePageBit::MACINTOSH,
+        ]);
+        assert_eq!(CodePageBit::GREEK.code_page(), Some(1253));
+        assert_eq!(CodePageBit(63).code_page(), Some(437));
+        assert_eq!(CodePageBit::MACINTOSH.code_page(), None);
+
+        assert_eq!(UnicodeBlockBit::of('\u{416}'), Some(UnicodeBlockBit::CYRILLIC));
+        assert_eq!(UnicodeBlockBit::of('\u{1F600}'), None);
+        assert_eq!(UnicodeBlockBit::CYRILLIC.ranges().count(), 4);
+    }
+}
@@ -0,0 +1,317 @@
+use decode::{Decode, DecodeRead};
+use primitives::{Fixed, FWord, GlyphId};
+use error::{Error, Result};
+
+/// The PostScript table, which contains additional information needed to
+/// use TrueType or OpenType fonts on PostScript printers.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Post<'fnt> {
+    pub version: Fixed,
+    pub italic_angle: Fixed,
+    pub underline_position: FWord,
+    pub underline_thickness: FWord,
+    pub is_fixed_pitch: u32,
+    pub min_mem_type42: u32,
+    pub max_mem_type42: u32,
+    pub min_mem_type1: u32,
+    pub max_mem_type1: u32,
+    /// The glyph name data which follows the header in versions 2 and 2.5.
+    names: &'fnt [u8],
+}
+
+impl<'fnt> Decode<'fnt> for Post<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Post<'fnt>> {
+        let mut buf = buffer;
+        Ok(Post {
+            version: buf.decode_read()?,
+            italic_angle: buf.decode_read()?,
+            underline_position: buf.decode_read()?,
+            underline_thickness: buf.decode_read()?,
+            is_fixed_pitch: buf.decode_read()?,
+            min_mem_type42: buf.decode_read()?,
+            max_mem_type42: buf.decode_read()?,
+            min_mem_type1: buf.decode_read()?,
+            max_mem_type1: buf.decode_read()?,
+            names: buf,
+        })
+    }
+}
+
+const VERSION_1: i32 = 0x00010000;
+const VERSION_2: i32 = 0x00020000;
+const VERSION_2_5: i32 = 0x00025000;
+
+impl<'fnt> Post<'fnt> {
+    /// Whether the font is monospaced according to `isFixedPitch`.
+    pub fn is_fixed_pitch(&self) -> bool {
+        self.is_fixed_pitch != 0
+    }
+
+    /// The name of every glyph, in glyph order.  Version 1 names the 258
+    /// glyphs of the standard Macintosh order, and fonts with versions
+    /// which store no names have an empty glyph order.
+    pub fn glyph_order(&self) -> Result<Vec<String>> {
+        match i32::from(self.version) {
+            VERSION_1 => Ok(MAC_GLYPH_NAMES.iter().map(|name| name.to_string()).collect()),
+            VERSION_2 => {
+                let names = self.custom_names()?;
+                (0..self.num_glyphs()?)
+                    .map(|glyph| self.version_2_name(glyph, &names).map(str::to_string))
+                    .collect()
+            },
+            VERSION_2_5 => {
+                (0..self.num_glyphs()?)
+                    .map(|glyph| self.version_2_5_name(glyph).map(str::to_string))
+                    .collect()
+            },
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// The name of `glyph`, or `None` if the table does not name it.
+    pub fn glyph_name(&self, glyph: GlyphId) -> Result<Option<String>> {
+        let index = glyph.0 as usize;
+        let name = match i32::from(self.version) {
+            VERSION_1 => MAC_GLYPH_NAMES.get(index).cloned(),
+            VERSION_2 if index < self.num_glyphs()? => {
+                Some(self.version_2_name(index, &self.custom_names()?)?)
+            },
+            VERSION_2_5 if index < self.num_glyphs()? => Some(self.version_2_5_name(index)?),
+            _ => None,
+        };
+
+        Ok(name.map(str::to_string))
+    }
+
+    fn num_glyphs(&self) -> Result<usize> {
+        u16::decode(self.names).map(usize::from)
+    }
+
+    /// The Pascal strings which follow the name indices of version 2.
+    fn custom_names(&self) -> Result<Vec<&'fnt str>> {
+        let mut data = self.names.get(2 + self.num_glyphs()? * 2..).ok_or(Error::UnexpectedEof)?;
+        let mut names = Vec::new();
+        while let Some((&len, rest)) = data.split_first() {
+            let name = rest.get(..len as usize).ok_or(Error::UnexpectedEof)?;
+            names.push(::std::str::from_utf8(name).map_err(|_| Error::InvalidData)?);
+            data = &rest[len as usize..];
+        }
+
+        Ok(names)
+    }
+
+    fn version_2_name(&self, glyph: usize, names: &[&'fnt str]) -> Result<&'fnt str> {
+        let index = u16::decode(self.names.get(2 + glyph * 2..).ok_or(Error::UnexpectedEof)?)? as usize;
+        match index.checked_sub(MAC_GLYPH_NAMES.len()) {
+            None => Ok(MAC_GLYPH_NAMES[index]),
+            Some(custom) => names.get(custom).cloned().ok_or(Error::InvalidData),
+        }
+    }
+
+    /// Version 2.5 stores each glyph as an offset from its own index into
+    /// the standard Macintosh order.
+    fn version_2_5_name(&self, glyph: usize) -> Result<&'static str> {
+        let offset = i8::decode(self.names.get(2 + glyph..).ok_or(Error::UnexpectedEof)?)?;
+        let index = glyph as isize + offset as isize;
+        if index < 0 {
+            return Err(Error::InvalidData)
+        }
+
+        MAC_GLYPH_NAMES.get(index as usize).cloned().ok_or(Error::InvalidData)
+    }
+}
+
+/// The character an Adobe Glyph List name stands for, such as `é` for
+/// `eacute`, `uni00E9` or `u00E9`, or `None` if the name is unknown or
+/// stands for a sequence of characters.  A suffix after a period, as in
+/// `a.sc`, is ignored, and ligatures such as `f_i` are sequences.
+pub fn agl_character(name: &str) -> Option<char> {
+    let name = name.split('.').next().unwrap_or_default();
+    if name.contains('_') {
+        return None
+    }
+    if let Ok(index) = AGL_NAMES.binary_search_by_key(&name, |&(agl, _)| agl) {
+        return Some(AGL_NAMES[index].1)
+    }
+
+    // Only uppercase hex digits are allowed, so that names such as `uniform`
+    // are not read as code points.
+    let hex = |digits: &str| if digits.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'A'..=b'F')) {
+        u32::from_str_radix(digits, 16).ok().and_then(::std::char::from_u32)
+    } else {
+        None
+    };
+    match (name.strip_prefix("uni"), name.strip_prefix('u')) {
+        (Some(digits), _) if digits.len() == 4 => hex(digits),
+        (_, Some(digits)) if (4..=6).contains(&digits.len()) => hex(digits),
+        _ => None,
+    }
+}
+
+/// The names of the standard Macintosh glyph order.
+pub const MAC_GLYPH_NAMES: [&str; 258] = [
+    ".notdef", ".null", "nonmarkingreturn", "space", "exclam", "quotedbl", "numbersign",
+    "dollar", "percent", "ampersand", "quotesingle", "parenleft", "parenright", "asterisk",
+    "plus", "comma", "hyphen", "period", "slash", "zero", "one", "two", "three", "four", "five",
+    "six", "seven", "eight", "nine", "colon", "semicolon", "less", "equal", "greater",
+    "question", "at", "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O",
+    "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y", "Z", "bracketleft", "backslash",
+    "bracketright", "asciicircum", "underscore", "grave", "a", "b", "c", "d", "e", "f", "g",
+    "h", "i", "j", "k", "l", "m", "n", "o", "p", "q", "r", "s", "t", "u", "v", "w", "x", "y",
+    "z", "braceleft", "bar", "braceright", "asciitilde", "Adieresis", "Aring", "Ccedilla",
+    "Eacute", "Ntilde", "Odieresis", "Udieresis", "aacute", "agrave", "acircumflex",
+    "adieresis", "atilde", "aring", "ccedilla", "eacute", "egrave", "ecircumflex", "edieresis",
+    "iacute", "igrave", "icircumflex", "idieresis", "ntilde", "oacute", "ograve",
+    "ocircumflex", "odieresis", "otilde", "uacute", "ugrave", "ucircumflex", "udieresis",
+    "dagger", "degree", "cent", "sterling", "section", "bullet", "paragraph", "germandbls",
+    "registered", "copyright", "trademark", "acute", "dieresis", "notequal", "AE", "Oslash",
+    "infinity", "plusminus", "lessequal", "greaterequal", "yen", "mu", "partialdiff",
+    "summation", "product", "pi", "integral", "ordfeminine", "ordmasculine", "Omega", "ae",
+    "oslash", "questiondown", "exclamdown", "logicalnot", "radical", "florin",
+    "approxequal", "Delta", "guillemotleft", "guillemotright", "ellipsis", "nonbreakingspace",
+    "Agrave", "Atilde", "Otilde", "OE", "oe", "endash", "emdash", "quotedblleft",
+    "quotedblright", "quoteleft", "quoteright", "divide", "lozenge", "ydieresis",
+    "Ydieresis", "fraction", "currency", "guilsinglleft", "guilsinglright", "fi", "fl",
+    "daggerdbl", "periodcentered", "quotesinglbase", "quotedblbase", "perthousand",
+    "Acircumflex", "Ecircumflex", "Aacute", "Edieresis", "Egrave", "Iacute", "Icircumflex",
+    "Idieresis", "Igrave", "Oacute", "Ocircumflex", "apple", "Ograve", "Uacute",
+    "Ucircumflex", "Ugrave", "dotlessi", "circumflex", "tilde", "macron", "breve",
+    "dotaccent", "ring", "cedilla", "hungarumlaut", "ogonek", "caron", "Lslash", "lslash",
+    "Scaron", "scaron", "Zcaron", "zcaron", "brokenbar", "Eth", "eth", "Yacute", "yacute",
+    "Thorn", "thorn", "minus", "multiply", "onesuperior", "twosuperior", "threesuperior",
+    "onehalf", "onequarter", "threequarters", "franc", "Gbreve", "gbreve", "Idotaccent",
+    "Scedilla", "scedilla", "Cacute", "cacute", "Ccaron", "ccaron", "dcroat",
+];
+
+/// The Adobe Glyph List names of the standard Macintosh order, Latin-1
+/// and Latin Extended-A, sorted by name.
+const AGL_NAMES: [(&str, char); 367] = [
+    ("A", 'A'), ("AE", '\u{C6}'), ("Aacute", '\u{C1}'), ("Abreve", '\u{102}'),
+    ("Acircumflex", '\u{C2}'), ("Adieresis", '\u{C4}'), ("Agrave", '\u{C0}'),
+    ("Amacron", '\u{100}'), ("Aogonek", '\u{104}'), ("Aring", '\u{C5}'), ("Atilde", '\u{C3}'),
+    ("B", 'B'), ("C", 'C'), ("Cacute", '\u{106}'), ("Ccaron", '\u{10C}'), ("Ccedilla", '\u{C7}'),
+    ("Ccircumflex", '\u{108}'), ("Cdotaccent", '\u{10A}'), ("D", 'D'), ("Dcaron", '\u{10E}'),
+    ("Dcroat", '\u{110}'), ("Delta", '\u{2206}'), ("E", 'E'), ("Eacute", '\u{C9}'),
+    ("Ebreve", '\u{114}'), ("Ecaron", '\u{11A}'), ("Ecircumflex", '\u{CA}'),
+    ("Edieresis", '\u{CB}'), ("Edotaccent", '\u{116}'), ("Egrave", '\u{C8}'),
+    ("Emacron", '\u{112}'), ("Eng", '\u{14A}'), ("Eogonek", '\u{118}'), ("Eth", '\u{D0}'),
+    ("Euro", '\u{20AC}'), ("F", 'F'), ("G", 'G'), ("Gbreve", '\u{11E}'), ("Gcircumflex", '\u{11C}'),
+    ("Gcommaaccent", '\u{122}'), ("Gdotaccent", '\u{120}'), ("H", 'H'), ("Hbar", '\u{126}'),
+    ("Hcircumflex", '\u{124}'), ("I", 'I'), ("IJ", '\u{132}'), ("Iacute", '\u{CD}'),
+    ("Ibreve", '\u{12C}'), ("Icircumflex", '\u{CE}'), ("Idieresis", '\u{CF}'),
+    ("Idotaccent", '\u{130}'), ("Igrave", '\u{CC}'), ("Imacron", '\u{12A}'), ("Iogonek", '\u{12E}'),
+    ("Itilde", '\u{128}'), ("J", 'J'), ("Jcircumflex", '\u{134}'), ("K", 'K'),
+    ("Kcommaaccent", '\u{136}'), ("L", 'L'), ("Lacute", '\u{139}'), ("Lcaron", '\u{13D}'),
+    ("Lcommaaccent", '\u{13B}'), ("Ldot", '\u{13F}'), ("Lslash", '\u{141}'), ("M", 'M'), ("N", 'N'),
+    ("Nacute", '\u{143}'), ("Ncaron", '\u{147}'), ("Ncommaaccent", '\u{145}'), ("Ntilde", '\u{D1}'),
+    ("O", 'O'), ("OE", '\u{152}'), ("Oacute", '\u{D3}'), ("Obreve", '\u{14E}'),
+    ("Ocircumflex", '\u{D4}'), ("Odieresis", '\u{D6}'), ("Ograve", '\u{D2}'),
+    ("Ohungarumlaut", '\u{150}'), ("Omacron", '\u{14C}'), ("Omega", '\u{2126}'),
+    ("Oslash", '\u{D8}'), ("Otilde", '\u{D5}'), ("P", 'P'), ("Q", 'Q'), ("R", 'R'),
+    ("Racute", '\u{154}'), ("Rcaron", '\u{158}'), ("Rcommaaccent", '\u{156}'), ("S", 'S'),
+    ("Sacute", '\u{15A}'), ("Scaron", '\u{160}'), ("Scedilla", '\u{15E}'),
+    ("Scircumflex", '\u{15C}'), ("T", 'T'), ("Tbar", '\u{166}'), ("Tcaron", '\u{164}'),
+    ("Tcommaaccent", '\u{162}'), ("Thorn", '\u{DE}'), ("U", 'U'), ("Uacute", '\u{DA}'),
+    ("Ubreve", '\u{16C}'), ("Ucircumflex", '\u{DB}'), ("Udieresis", '\u{DC}'), ("Ugrave", '\u{D9}'),
+    ("Uhungarumlaut", '\u{170}'), ("Umacron", '\u{16A}'), ("Uogonek", '\u{172}'),
+    ("Uring", '\u{16E}'), ("Utilde", '\u{168}'), ("V", 'V'), ("W", 'W'), ("Wcircumflex", '\u{174}'),
+    ("X", 'X'), ("Y", 'Y'), ("Yacute", '\u{DD}'), ("Ycircumflex", '\u{176}'),
+    ("Ydieresis", '\u{178}'), ("Z", 'Z'), ("Zacute", '\u{179}'), ("Zcaron", '\u{17D}'),
+    ("Zdotaccent", '\u{17B}'), ("a", 'a'), ("aacute", '\u{E1}'), ("abreve", '\u{103}'),
+    ("acircumflex", '\u{E2}'), ("acute", '\u{B4}'), ("adieresis", '\u{E4}'), ("ae", '\u{E6}'),
+    ("agrave", '\u{E0}'), ("amacron", '\u{101}'), ("ampersand", '&'), ("aogonek", '\u{105}'),
+    ("apple", '\u{F8FF}'), ("approxequal", '\u{2248}'), ("aring", '\u{E5}'), ("asciicircum", '^'),
+    ("asciitilde", '~'), ("asterisk", '*'), ("at", '@'), ("atilde", '\u{E3}'), ("b", 'b'),
+    ("backslash", '\\'), ("bar", '|'), ("braceleft", '{'), ("braceright", '}'),
+    ("bracketleft", '['), ("bracketright", ']'), ("breve", '\u{2D8}'), ("brokenbar", '\u{A6}'),
+    ("bullet", '\u{2022}'), ("c", 'c'), ("cacute", '\u{107}'), ("caron", '\u{2C7}'),
+    ("ccaron", '\u{10D}'), ("ccedilla", '\u{E7}'), ("ccircumflex", '\u{109}'),
+    ("cdotaccent", '\u{10B}'), ("cedilla", '\u{B8}'), ("cent", '\u{A2}'), ("circumflex", '\u{2C6}'),
+    ("colon", ':'), ("comma", ','), ("copyright", '\u{A9}'), ("currency", '\u{A4}'), ("d", 'd'),
+    ("dagger", '\u{2020}'), ("daggerdbl", '\u{2021}'), ("dcaron", '\u{10F}'), ("dcroat", '\u{111}'),
+    ("degree", '\u{B0}'), ("dieresis", '\u{A8}'), ("divide", '\u{F7}'), ("dollar", '$'),
+    ("dotaccent", '\u{2D9}'), ("dotlessi", '\u{131}'), ("e", 'e'), ("eacute", '\u{E9}'),
+    ("ebreve", '\u{115}'), ("ecaron", '\u{11B}'), ("ecircumflex", '\u{EA}'),
+    ("edieresis", '\u{EB}'), ("edotaccent", '\u{117}'), ("egrave", '\u{E8}'), ("eight", '8'),
+    ("ellipsis", '\u{2026}'), ("emacron", '\u{113}'), ("emdash", '\u{2014}'),
+    ("endash", '\u{2013}'), ("eng", '\u{14B}'), ("eogonek", '\u{119}'), ("equal", '='),
+    ("eth", '\u{F0}'), ("exclam", '!'), ("exclamdown", '\u{A1}'), ("f", 'f'), ("fi", '\u{FB01}'),
+    ("five", '5'), ("fl", '\u{FB02}'), ("florin", '\u{192}'), ("four", '4'),
+    ("fraction", '\u{2044}'), ("franc", '\u{20A3}'), ("g", 'g'), ("gbreve", '\u{11F}'),
+    ("gcircumflex", '\u{11D}'), ("gcommaaccent", '\u{123}'), ("gdotaccent", '\u{121}'),
+    ("germandbls", '\u{DF}'), ("grave", '`'), ("greater", '>'), ("greaterequal", '\u{2265}'),
+    ("guillemotleft", '\u{AB}'), ("guillemotright", '\u{BB}'), ("guilsinglleft", '\u{2039}'),
+    ("guilsinglright", '\u{203A}'), ("h", 'h'), ("hbar", '\u{127}'), ("hcircumflex", '\u{125}'),
+    ("hungarumlaut", '\u{2DD}'), ("hyphen", '-'), ("i", 'i'), ("iacute", '\u{ED}'),
+    ("ibreve", '\u{12D}'), ("icircumflex", '\u{EE}'), ("idieresis", '\u{EF}'), ("igrave", '\u{EC}'),
+    ("ij", '\u{133}'), ("imacron", '\u{12B}'), ("infinity", '\u{221E}'), ("integral", '\u{222B}'),
+    ("iogonek", '\u{12F}'), ("itilde", '\u{129}'), ("j", 'j'), ("jcircumflex", '\u{135}'),
+    ("k", 'k'), ("kcommaaccent", '\u{137}'), ("kgreenlandic", '\u{138}'), ("l", 'l'),
+    ("lacute", '\u{13A}'), ("lcaron", '\u{13E}'), ("lcommaaccent", '\u{13C}'), ("ldot", '\u{140}'),
+    ("less", '<'), ("lessequal", '\u{2264}'), ("logicalnot", '\u{AC}'), ("longs", '\u{17F}'),
+    ("lozenge", '\u{25CA}'), ("lslash", '\u{142}'), ("m", 'm'), ("macron", '\u{AF}'),
+    ("minus", '\u{2212}'), ("mu", '\u{B5}'), ("multiply", '\u{D7}'), ("n", 'n'),
+    ("nacute", '\u{144}'), ("napostrophe", '\u{149}'), ("nbspace", '\u{A0}'), ("ncaron", '\u{148}'),
+    ("ncommaaccent", '\u{146}'), ("nine", '9'), ("nonbreakingspace", '\u{A0}'),
+    ("nonmarkingreturn", '\u{D}'), ("notequal", '\u{2260}'), ("ntilde", '\u{F1}'),
+    ("numbersign", '#'), ("o", 'o'), ("oacute", '\u{F3}'), ("obreve", '\u{14F}'),
+    ("ocircumflex", '\u{F4}'), ("odieresis", '\u{F6}'), ("oe", '\u{153}'), ("ogonek", '\u{2DB}'),
+    ("ograve", '\u{F2}'), ("ohungarumlaut", '\u{151}'), ("omacron", '\u{14D}'), ("one", '1'),
+    ("onehalf", '\u{BD}'), ("onequarter", '\u{BC}'), ("onesuperior", '\u{B9}'),
+    ("ordfeminine", '\u{AA}'), ("ordmasculine", '\u{BA}'), ("oslash", '\u{F8}'),
+    ("otilde", '\u{F5}'), ("p", 'p'), ("paragraph", '\u{B6}'), ("parenleft", '('),
+    ("parenright", ')'), ("partialdiff", '\u{2202}'), ("percent", '%'), ("period", '.'),
+    ("periodcentered", '\u{B7}'), ("perthousand", '\u{2030}'), ("pi", '\u{3C0}'), ("plus", '+'),
+    ("plusminus", '\u{B1}'), ("product", '\u{220F}'), ("q", 'q'), ("question", '?'),
+    ("questiondown", '\u{BF}'), ("quotedbl", '"'), ("quotedblbase", '\u{201E}'),
+    ("quotedblleft", '\u{201C}'), ("quotedblright", '\u{201D}'), ("quoteleft", '\u{2018}'),
+    ("quoteright", '\u{2019}'), ("quotesinglbase", '\u{201A}'), ("quotesingle", '\''), ("r", 'r'),
+    ("racute", '\u{155}'), ("radical", '\u{221A}'), ("rcaron", '\u{159}'),
+    ("rcommaaccent", '\u{157}'), ("registered", '\u{AE}'), ("ring", '\u{2DA}'), ("s", 's'),
+    ("sacute", '\u{15B}'), ("scaron", '\u{161}'), ("scedilla", '\u{15F}'),
+    ("scircumflex", '\u{15D}'), ("section", '\u{A7}'), ("semicolon", ';'), ("seven", '7'),
+    ("sfthyphen", '\u{AD}'), ("six", '6'), ("slash", '/'), ("space", ' '), ("sterling", '\u{A3}'),
+    ("summation", '\u{2211}'), ("t", 't'), ("tbar", '\u{167}'), ("tcaron", '\u{165}'),
+    ("tcommaaccent", '\u{163}'), ("thorn", '\u{FE}'), ("three", '3'), ("threequarters", '\u{BE}'),
+    ("threesuperior", '\u{B3}'), ("tilde", '\u{2DC}'), ("trademark", '\u{2122}'), ("two", '2'),
+    ("twosuperior", '\u{B2}'), ("u", 'u'), ("uacute", '\u{FA}'), ("ubreve", '\u{16D}'),
+    ("ucircumflex", '\u{FB}'), ("udieresis", '\u{FC}'), ("ugrave", '\u{F9}'),
+    ("uhungarumlaut", '\u{171}'), ("umacron", '\u{16B}'), ("underscore", '_'),
+    ("uogonek", '\u{173}'), ("uring", '\u{16F}'), ("utilde", '\u{169}'), ("v", 'v'), ("w", 'w'),
+    ("wcircumflex", '\u{175}'), ("x", 'x'), ("y", 'y'), ("yacute", '\u{FD}'),
+    ("ycircumflex", '\u{177}'), ("ydieresis", '\u{FF}'), ("yen", '\u{A5}'), ("z", 'z'),
+    ("zacute", '\u{17A}'), ("zcaron", '\u{17E}'), ("zdotaccent", '\u{17C}'), ("zero", '0'),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use font::Font;
+
+    #[test]
+    fn glyph_order() {
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let post = font.table::<Post>().expect("failed to read post");
+
+        let order = post.glyph_order().expect("failed to read glyph names");
+        assert_eq!(order.len(), font.hmtx().unwrap().num_glyphs());
+        assert_eq!(order[43], "H");
+        assert_eq!(order[91], "x");
+        assert_eq!(order[131], "Aacute");
+        assert_eq!(post.glyph_name(GlyphId(91)), Ok(Some("x".to_string())));
+        assert_eq!(post.glyph_name(GlyphId(order.len() as u16)), Ok(None));
+
+        // Version 2.5 maps three glyphs to "A", "B" and "space".
+        let mut data = vec![0, 2, 0x50, 0, 0, 0, 0, 0];
+        data.extend(&[0; 24]);
+        data.extend(&[0, 3, 36, 36, 1]);
+        let post = Post::decode(&data).expect("failed to read post 2.5");
+        assert_eq!(post.glyph_order(), Ok(vec!["A".to_string(), "B".to_string(), "space".to_string()]));
+
+        // Version 3 stores no names.
+        let mut data = vec![0, 3, 0, 0];
+        data.extend(&[0; 28]);
+        assert_eq!(Post::decode(&data).unwrap().glyph_order(), Ok(Vec::new()));
+    }
+}
@@ -0,0 +1,97 @@
+use decode::{Decode, Decode1};
+use primitives::{Array, GlyphId, Tag};
+use error::{Error, Result};
+
+/// The standard bitmap graphics table, which contains strikes of images
+/// such as PNGs for each glyph.  Decoding requires the number of glyphs.
+#[derive(Debug, Copy, Clone)]
+pub struct Sbix<'fnt> {
+    buffer: &'fnt [u8],
+    pub version: u16,
+    pub flags: u16,
+    strike_offsets: Array<'fnt, u32>,
+    num_glyphs: usize,
+}
+
+/// A single size of bitmaps.
+#[derive(Debug, Copy, Clone)]
+pub struct Strike<'fnt> {
+    buffer: &'fnt [u8],
+    pub ppem: u16,
+    pub ppi: u16,
+    num_glyphs: usize,
+}
+
+/// The bitmap data of a glyph in a strike.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GlyphImage<'fnt> {
+    pub origin_offset_x: i16,
+    pub origin_offset_y: i16,
+    pub graphic_type: Tag,
+    pub data: &'fnt [u8],
+}
+
+impl<'fnt> Decode1<'fnt, usize> for Sbix<'fnt> {
+    fn decode(buffer: &'fnt [u8], num_glyphs: usize) -> Result<Sbix<'fnt>> {
+        required_len!(buffer, 8);
+        let num_strikes = u32::decode(&buffer[4..])? as usize;
+
+        Ok(Sbix {
+            buffer,
+            version: u16::decode(buffer)?,
+            flags: u16::decode(&buffer[2..])?,
+            strike_offsets: Array::decode(&buffer[8..], num_strikes)?,
+            num_glyphs,
+        })
+    }
+}
+
+impl<'fnt> Sbix<'fnt> {
+    pub fn strikes(&self) -> impl Iterator<Item = Result<Strike<'fnt>>> + 'fnt {
+        let (buffer, num_glyphs) = (self.buffer, self.num_glyphs);
+        self.strike_offsets.into_iter().map(move |offset| {
+            let data = buffer.get(offset as usize..).ok_or(Error::UnexpectedEof)?;
+            required_len!(data, 4 + (num_glyphs + 1) * 4);
+            Ok(Strike {
+                buffer: data,
+                ppem: u16::decode(data)?,
+                ppi: u16::decode(&data[2..])?,
+                num_glyphs,
+            })
+        })
+    }
+}
+
+impl<'fnt> Strike<'fnt> {
+    fn range(&self, glyph: GlyphId) -> Result<(usize, usize)> {
+        let index = glyph.0 as usize;
+        if index >= self.num_glyphs {
+            return Err(Error::InvalidGlyphId)
+        }
+
+        let start = u32::decode(&self.buffer[4 + index * 4..])? as usize;
+        let end = u32::decode(&self.buffer[8 + index * 4..])? as usize;
+        Ok((start, end))
+    }
+
+    pub fn has_glyph(&self, glyph: GlyphId) -> Result<bool> {
+        self.range(glyph).map(|(start, end)| end > start)
+    }
+
+    /// The image of `glyph`, or `None` if this strike has no data for it.
+    pub fn glyph(&self, glyph: GlyphId) -> Result<Option<GlyphImage<'fnt>>> {
+        let (start, end) = self.range(glyph)?;
+        if end <= start {
+            return Ok(None)
+        }
+
+        let data = self.buffer.get(start..end).ok_or(Error::UnexpectedEof)?;
+        required_len!(data, 8);
+        Ok(Some(GlyphImage {
+            origin_offset_x: i16::decode(data)?,
+            origin_offset_y: i16::decode(&data[2..])?,
+            graphic_type: Tag::decode(&data[4..])?,
+            data: &data[8..],
+        }))
+    }
+}
@@ -0,0 +1,260 @@
+use decode::Decode;
+use primitives::{Fixed, Tag};
+use error::{Error, Result};
+
+/// The axis value is also the attribute of an older font of the family,
+/// such as the weight of a legacy bold face.
+pub const OLDER_SIBLING_FONT_ATTRIBUTE: u16 = 0x0001;
+/// The name of the axis value may be left out of a style name, as
+/// `Regular` is left out of `Condensed`.
+pub const ELIDABLE_AXIS_VALUE_NAME: u16 = 0x0002;
+
+/// The style attributes table, which describes the design axes of a font
+/// family, including axes a face does not vary, and names values on
+/// them.  Style names of variable instances are built from these names.
+#[derive(Debug, Clone)]
+pub struct Stat<'fnt> {
+    design_axes: &'fnt [u8],
+    design_axis_size: usize,
+    design_axis_count: usize,
+    axis_values: Vec<AxisValue>,
+    /// The name of the style when every axis value name is elided, such
+    /// as `Regular`.  Version 1.0 tables leave it out.
+    pub elided_fallback_name_id: Option<u16>,
+}
+
+/// A design axis, whose `axis_ordering` places the names of its values
+/// within a style name.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AxisRecord {
+    pub axis_tag: Tag,
+    pub axis_name_id: u16,
+    pub axis_ordering: u16,
+}
+
+/// A named value on one or more design axes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AxisValue {
+    pub format: u16,
+    pub flags: u16,
+    pub value_name_id: u16,
+    /// The `(design axis index, value)` the table names, one pair for
+    /// formats 1 to 3.  For format 2 this is the nominal value.
+    pub values: Vec<(u16, f32)>,
+    /// The `(min, max)` range of values of format 2.
+    pub range: Option<(f32, f32)>,
+    /// The value format 3 links to, such as bold for regular.
+    pub linked_value: Option<f32>,
+}
+
+impl AxisValue {
+    pub fn is_elidable(&self) -> bool {
+        self.flags & ELIDABLE_AXIS_VALUE_NAME != 0
+    }
+
+    /// Whether the table names `value` on the design axis `axis`.
+    pub(crate) fn matches(&self, axis: u16, value: f32) -> bool {
+        match self.range {
+            Some((min, max)) => self.values[0].0 == axis && min <= value && value <= max,
+            None => self.values.iter().any(|&(index, nominal)| index == axis && nominal == value),
+        }
+    }
+}
+
+impl<'fnt> Decode<'fnt> for Stat<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Stat<'fnt>> {
+        required_len!(buffer, 18);
+        let major = u16::decode(buffer)?;
+        let minor = u16::decode(&buffer[2..])?;
+        if major != 1 {
+            return Err(Error::UnsupportedVersion((u32::from(major) << 16) | u32::from(minor)))
+        }
+
+        let design_axis_size = u16::decode(&buffer[4..])? as usize;
+        let design_axis_count = u16::decode(&buffer[6..])? as usize;
+        if design_axis_size < 8 && design_axis_count > 0 {
+            return Err(Error::InvalidData)
+        }
+        let design_axes = buffer.get(u32::decode(&buffer[8..])? as usize..).ok_or(Error::UnexpectedEof)?;
+        required_len!(design_axes, design_axis_size * design_axis_count);
+
+        let axis_value_count = u16::decode(&buffer[12..])? as usize;
+        let offsets = buffer.get(u32::decode(&buffer[14..])? as usize..).ok_or(Error::UnexpectedEof)?;
+        required_len!(offsets, axis_value_count * 2);
+        let axis_values = (0..axis_value_count)
+            .map(|index| {
+                let offset = u16::decode(&offsets[index * 2..])? as usize;
+                read_axis_value(offsets.get(offset..).ok_or(Error::UnexpectedEof)?)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let elided_fallback_name_id = if minor >= 1 {
+            required_len!(buffer, 20);
+            Some(u16::decode(&buffer[18..])?)
+        } else {
+            None
+        };
+
+        Ok(Stat { design_axes, design_axis_size, design_axis_count, axis_values, elided_fallback_name_id })
+    }
+}
+
+/// Read an axis value table of formats 1 to 4.
+fn read_axis_value(data: &[u8]) -> Result<AxisValue> {
+    let fixed = |pos: usize| Fixed::decode(data.get(pos..).ok_or(Error::UnexpectedEof)?).map(f32::from);
+    let format = u16::decode(data)?;
+    required_len!(data, 8);
+    let mut axis_value = AxisValue {
+        format,
+        flags: u16::decode(&data[4..])?,
+        value_name_id: u16::decode(&data[6..])?,
+        values: Vec::new(),
+        range: None,
+        linked_value: None,
+    };
+
+    match format {
+        1..=3 => {
+            axis_value.values.push((u16::decode(&data[2..])?, fixed(8)?));
+            if format == 2 {
+                axis_value.range = Some((fixed(12)?, fixed(16)?));
+            } else if format == 3 {
+                axis_value.linked_value = Some(fixed(12)?);
+            }
+        },
+        4 => {
+            // The axis count takes the place of the axis index, and the
+            // flags and name follow it.
+            let count = u16::decode(&data[2..])? as usize;
+            required_len!(data, 8 + count * 6);
+            for index in 0..count {
+                let record = &data[8 + index * 6..];
+                axis_value.values.push((u16::decode(record)?, f32::from(Fixed::decode(&record[2..])?)));
+            }
+        },
+        _ => return Err(Error::InvalidData),
+    }
+
+    Ok(axis_value)
+}
+
+impl<'fnt> Stat<'fnt> {
+    pub fn design_axes(&self) -> impl Iterator<Item = AxisRecord> + 'fnt {
+        let (data, size) = (self.design_axes, self.design_axis_size);
+        (0..self.design_axis_count).map(move |index| {
+            let record = &data[index * size..];
+            AxisRecord {
+                axis_tag: Tag::new([record[0], record[1], record[2], record[3]]),
+                axis_name_id: u16::decode(&record[4..]).unwrap_or_default(),
+                axis_ordering: u16::decode(&record[6..]).unwrap_or_default(),
+            }
+        })
+    }
+
+    pub fn axis_values(&self) -> &[AxisValue] {
+        &self.axis_values
+    }
+
+    /// The name IDs of the style at `location`, in the order the words of
+    /// the style name appear.
+    ///
+    /// As the specification describes, a format 4 table which matches
+    /// every axis it lists is used first, preferring the one which lists
+    /// the most axes.  Each other design axis takes the table which names
+    /// its value exactly, or else the format 2 range which contains it.
+    /// An axis which `location` has no value for, such as `ital` in an
+    /// upright font, takes its first table of formats 1 or 3, and is left
+    /// out of the name if it has none.  Elidable names are left out, and
+    /// if every name is, the style is the elided fallback name alone, or
+    /// `Regular` (name ID 2) for version 1.0.
+    ///
+    /// `None` is returned if no table names the value of some axis in
+    /// `location`, since its part of the name is unknown.
+    pub fn style_name_ids(&self, location: &[(Tag, f32)]) -> Option<Vec<u16>> {
+        let axes = self.design_axes().collect::<Vec<_>>();
+        let value = |axis: usize| location.iter().find(|&&(tag, _)| tag == axes[axis].axis_tag).map(|&(_, value)| value);
+
+        let mut covered = vec![false; axes.len()];
+        // The names with the ordering of their first axis.
+        let mut names = Vec::new();
+
+        // `max_by_key` returns the last of equals, so the list is reversed
+        // to prefer the first.
+        let multi_axis = self.axis_values.iter().rev()
+            .filter(|axis_value| axis_value.format == 4 && !axis_value.values.is_empty())
+            .filter(|axis_value| axis_value.values.iter().all(|&(axis, nominal)| {
+                (axis as usize) < axes.len() && value(axis as usize) == Some(nominal)
+            }))
+            .max_by_key(|axis_value| axis_value.values.len());
+        if let Some(axis_value) = multi_axis {
+            for &(axis, _) in &axis_value.values {
+                covered[axis as usize] = true;
+            }
+            let ordering = axis_value.values.iter().map(|&(axis, _)| axes[axis as usize].axis_ordering).min();
+            names.push((ordering.unwrap_or_default(), axis_value));
+        }
+
+        for (index, axis) in axes.iter().enumerate().filter(|&(index, _)| !covered[index]) {
+            let single_axis = self.axis_values.iter().filter(|axis_value| axis_value.format != 4);
+            let axis_value = match value(index) {
+                Some(value) => {
+                    let mut candidates = single_axis.filter(|axis_value| axis_value.matches(index as u16, value));
+                    let first = candidates.next()?;
+                    match first.range {
+                        // An exact match is preferred to a range.
+                        Some(_) => candidates.find(|axis_value| axis_value.range.is_none()).unwrap_or(first),
+                        None => first,
+                    }
+                },
+                None => match single_axis.filter(|axis_value| axis_value.range.is_none())
+                    .find(|axis_value| axis_value.values[0].0 == index as u16)
+                {
+                    Some(axis_value) => axis_value,
+                    None => continue,
+                },
+            };
+            names.push((axis.axis_ordering, axis_value));
+        }
+
+        names.sort_by_key(|&(ordering, _)| ordering);
+        let ids = names.into_iter()
+            .filter(|&(_, axis_value)| !axis_value.is_elidable())
+            .map(|(_, axis_value)| axis_value.value_name_id)
+            .collect::<Vec<_>>();
+        if ids.is_empty() {
+            return Some(vec![self.elided_fallback_name_id.unwrap_or(2)])
+        }
+        Some(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_fonts;
+
+    #[test]
+    fn style_names() {
+        let data = test_fonts::stat(&[(b"wght", 1), (b"wdth", 0), (b"ital", 2)], &[
+            test_fonts::AxisValueRecord::Single(0, 400.0, 0x0002, 260),
+            test_fonts::AxisValueRecord::Single(0, 700.0, 0, 261),
+            test_fonts::AxisValueRecord::Range(0, 800.0, 750.0, 900.0, 262),
+            test_fonts::AxisValueRecord::Single(1, 100.0, 0x0002, 260),
+            test_fonts::AxisValueRecord::Single(1, 75.0, 0, 263),
+            test_fonts::AxisValueRecord::Single(2, 0.0, 0x0002, 264),
+            test_fonts::AxisValueRecord::Multiple(&[(0, 700.0), (1, 75.0)], 0, 265),
+        ], 260);
+        let stat = Stat::decode(&data).unwrap();
+        assert_eq!(stat.design_axes().map(|axis| axis.axis_ordering).collect::<Vec<_>>(), vec![1, 0, 2]);
+        assert_eq!(stat.axis_values()[2].range, Some((750.0, 900.0)));
+        assert_eq!(stat.elided_fallback_name_id, Some(260));
+
+        let (weight, width) = (Tag::new(*b"wght"), Tag::new(*b"wdth"));
+        assert_eq!(stat.style_name_ids(&[(weight, 400.0), (width, 100.0)]), Some(vec![260]));
+        // Width is ordered before weight, and regular weight is elided.
+        assert_eq!(stat.style_name_ids(&[(weight, 400.0), (width, 75.0)]), Some(vec![263]));
+        assert_eq!(stat.style_name_ids(&[(weight, 850.0), (width, 100.0)]), Some(vec![262]));
+        assert_eq!(stat.style_name_ids(&[(weight, 700.0), (width, 75.0)]), Some(vec![265]));
+        assert_eq!(stat.style_name_ids(&[(weight, 500.0), (width, 100.0)]), None);
+    }
+}
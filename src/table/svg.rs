@@ -0,0 +1,52 @@
+use decode::{Decode, Decode1, StaticEncodeSize, EncodeSize};
+use primitives::{Array, GlyphId};
+use error::{Error, Result};
+
+/// The SVG table, which contains SVG documents for ranges of glyphs.
+#[derive(Debug, Copy, Clone)]
+pub struct Svg<'fnt> {
+    document_list: &'fnt [u8],
+    records: Array<'fnt, SvgDocumentRecord>,
+}
+
+#[derive(Decode, StaticEncodeSize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SvgDocumentRecord {
+    pub start_glyph_id: GlyphId,
+    pub end_glyph_id: GlyphId,
+    /// The offset of the document from the start of the document list.
+    pub svg_doc_offset: u32,
+    pub svg_doc_length: u32,
+}
+
+impl<'fnt> Decode<'fnt> for Svg<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<Svg<'fnt>> {
+        required_len!(buffer, 6);
+        let offset = u32::decode(&buffer[2..])? as usize;
+        let document_list = buffer.get(offset..).ok_or(Error::UnexpectedEof)?;
+        let count = u16::decode(document_list)? as usize;
+        let records = Array::decode(&document_list[2..], count)?;
+
+        Ok(Svg { document_list, records })
+    }
+}
+
+impl<'fnt> Svg<'fnt> {
+    pub fn records(&self) -> Array<'fnt, SvgDocumentRecord> {
+        self.records
+    }
+
+    /// The SVG document which contains `glyph`, which may be gzip encoded.
+    pub fn document(&self, glyph: GlyphId) -> Result<Option<&'fnt [u8]>> {
+        let record = self.records.into_iter()
+            .find(|record| record.start_glyph_id <= glyph && glyph <= record.end_glyph_id);
+
+        match record {
+            Some(record) => {
+                let start = record.svg_doc_offset as usize;
+                let end = start + record.svg_doc_length as usize;
+                self.document_list.get(start..end).ok_or(Error::UnexpectedEof).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+}
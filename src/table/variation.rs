@@ -0,0 +1,386 @@
+//! Data structures shared by the tables of variable fonts.
+
+use std::iter::FromIterator;
+
+use decode::Decode;
+use primitives::{F2Dot14, Fixed, Tag};
+use table::avar::Avar;
+use table::fvar::Fvar;
+use error::{Error, Result};
+
+/// A coordinate on a variation axis in the normalized `[-1, 1]` range,
+/// with the precision of an `F2Dot14`.  The tables of a variable font are
+/// addressed by normalized coordinates, in `fvar` axis order.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NormalizedCoord(F2Dot14);
+
+impl NormalizedCoord {
+    /// The coordinate nearest to `value`, which is clamped to `[-1, 1]`.
+    pub fn new(value: f32) -> NormalizedCoord {
+        let value = if value.is_nan() { 0.0 } else { value.clamp(-1.0, 1.0) };
+        NormalizedCoord(F2Dot14::from((value * 16384.0).round() as i16))
+    }
+
+    pub fn from_bits(bits: i16) -> NormalizedCoord {
+        NormalizedCoord(F2Dot14::from(bits))
+    }
+
+    pub fn to_bits(self) -> i16 {
+        i16::from(self.0)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        f32::from(self.0)
+    }
+}
+
+impl Default for NormalizedCoord {
+    /// The default position of an axis.
+    fn default() -> NormalizedCoord {
+        NormalizedCoord::from_bits(0)
+    }
+}
+
+impl From<F2Dot14> for NormalizedCoord {
+    fn from(value: F2Dot14) -> NormalizedCoord {
+        NormalizedCoord(value)
+    }
+}
+
+impl From<NormalizedCoord> for f32 {
+    fn from(coord: NormalizedCoord) -> f32 {
+        coord.to_f32()
+    }
+}
+
+/// A coordinate on a variation axis in user space, the units `fvar`
+/// gives axis ranges in, such as 400 for a regular weight.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct UserCoord(Fixed);
+
+impl UserCoord {
+    /// The coordinate nearest to `value`.
+    pub fn new(value: f32) -> UserCoord {
+        UserCoord(Fixed::from((f64::from(value) * 65536.0).round() as i32))
+    }
+
+    pub fn to_f32(self) -> f32 {
+        f32::from(self.0)
+    }
+}
+
+impl From<Fixed> for UserCoord {
+    fn from(value: Fixed) -> UserCoord {
+        UserCoord(value)
+    }
+}
+
+impl From<UserCoord> for f32 {
+    fn from(coord: UserCoord) -> f32 {
+        coord.to_f32()
+    }
+}
+
+/// A position in the design space of a variable font, as user-space
+/// values for some of its axes.  Axes which are not set are at their
+/// default.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Location {
+    values: Vec<(Tag, UserCoord)>,
+}
+
+impl Location {
+    pub fn new() -> Location {
+        Location::default()
+    }
+
+    /// Set the value of the axis `tag`, replacing any earlier value.
+    pub fn set(&mut self, tag: Tag, value: UserCoord) {
+        match self.values.iter_mut().find(|&&mut (axis, _)| axis == tag) {
+            Some(entry) => entry.1 = value,
+            None => self.values.push((tag, value)),
+        }
+    }
+
+    pub fn get(&self, tag: Tag) -> Option<UserCoord> {
+        self.values.iter().find(|&&(axis, _)| axis == tag).map(|&(_, value)| value)
+    }
+
+    pub fn values(&self) -> &[(Tag, UserCoord)] {
+        &self.values
+    }
+
+    /// The normalized coordinates of this location, in `fvar` axis order.
+    /// Values are clamped to the range of their axis, normalized and then
+    /// mapped through `avar` if the font has one, including the deltas of
+    /// version 2.  Tags which are not axes of the font are ignored.
+    pub fn normalize(&self, fvar: &Fvar, avar: Option<&Avar>) -> Vec<NormalizedCoord> {
+        let coords = fvar.axes()
+            .into_iter()
+            .map(|axis| self.get(axis.axis_tag).map(|value| axis.normalize(value)).unwrap_or_default())
+            .collect::<Vec<_>>();
+        match avar {
+            Some(avar) => avar.map_coords(&coords),
+            None => coords,
+        }
+    }
+}
+
+impl FromIterator<(Tag, UserCoord)> for Location {
+    fn from_iter<I: IntoIterator<Item = (Tag, UserCoord)>>(values: I) -> Location {
+        let mut location = Location::new();
+        for (tag, value) in values {
+            location.set(tag, value);
+        }
+        location
+    }
+}
+
+impl<'a> From<&'a [(Tag, f32)]> for Location {
+    fn from(values: &'a [(Tag, f32)]) -> Location {
+        values.iter().map(|&(tag, value)| (tag, UserCoord::new(value))).collect()
+    }
+}
+
+/// The scalar of a variation region for the normalized `coords`.  Each
+/// axis of the region is given as `(start, peak, end)`.
+pub fn region_scalar<I>(coords: &[NormalizedCoord], region: I) -> f32
+    where I: IntoIterator<Item = (f32, f32, f32)>
+{
+    let mut scalar = 1.0;
+    for (axis, (start, peak, end)) in region.into_iter().enumerate() {
+        let coord = coords.get(axis).map_or(0.0, |coord| coord.to_f32());
+        if start > peak || peak > end || (start < 0.0 && end > 0.0) || peak == 0.0 {
+            continue
+        }
+
+        if coord < start || coord > end {
+            return 0.0
+        }
+
+        if coord == peak {
+            continue
+        } else if coord < peak {
+            scalar *= (coord - start) / (peak - start);
+        } else {
+            scalar *= (end - coord) / (end - peak);
+        }
+    }
+
+    scalar
+}
+
+/// An ItemVariationStore, which contains delta sets addressed by an outer
+/// (ItemVariationData) index and an inner (delta set) index.
+#[derive(Debug, Copy, Clone)]
+pub struct ItemVariationStore<'fnt> {
+    buffer: &'fnt [u8],
+    regions: &'fnt [u8],
+    axis_count: usize,
+    region_count: usize,
+    data_count: usize,
+}
+
+impl<'fnt> Decode<'fnt> for ItemVariationStore<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<ItemVariationStore<'fnt>> {
+        required_len!(buffer, 8);
+        let version = u16::decode(buffer)?;
+        if version != 1 {
+            return Err(Error::UnsupportedVersion(version.into()))
+        }
+
+        let regions_offset = u32::decode(&buffer[2..])? as usize;
+        let data_count = u16::decode(&buffer[6..])? as usize;
+        required_len!(buffer, 8 + data_count * 4);
+
+        let regions = buffer.get(regions_offset..).ok_or(Error::UnexpectedEof)?;
+        required_len!(regions, 4);
+        let axis_count = u16::decode(regions)? as usize;
+        let region_count = u16::decode(&regions[2..])? as usize;
+        required_len!(regions, 4 + axis_count * region_count * 6);
+
+        Ok(ItemVariationStore { buffer, regions, axis_count, region_count, data_count })
+    }
+}
+
+impl<'fnt> ItemVariationStore<'fnt> {
+    /// The `(start, peak, end)` coordinates of each axis of a region.
+    pub fn region(&self, index: usize) -> Option<Vec<(f32, f32, f32)>> {
+        if index >= self.region_count {
+            return None
+        }
+
+        let record = &self.regions[4 + index * self.axis_count * 6..];
+        (0..self.axis_count)
+            .map(|axis| {
+                let coords = &record[axis * 6..];
+                Some((f32::from(F2Dot14::decode(coords).ok()?),
+                      f32::from(F2Dot14::decode(&coords[2..]).ok()?),
+                      f32::from(F2Dot14::decode(&coords[4..]).ok()?)))
+            })
+            .collect()
+    }
+
+    /// The interpolated delta of the delta set at `(outer, inner)` for the
+    /// normalized `coords`.
+    pub fn delta(&self, outer: u16, inner: u16, coords: &[NormalizedCoord]) -> Result<f32> {
+        let outer = outer as usize;
+        if outer >= self.data_count {
+            return Err(Error::InvalidData)
+        }
+
+        let offset = u32::decode(&self.buffer[8 + outer * 4..])? as usize;
+        let data = self.buffer.get(offset..).ok_or(Error::UnexpectedEof)?;
+        required_len!(data, 6);
+        let item_count = u16::decode(data)? as usize;
+        let word_delta_count = u16::decode(&data[2..])?;
+        let region_index_count = u16::decode(&data[4..])? as usize;
+
+        let long_words = word_delta_count & 0x8000 != 0;
+        let word_count = (word_delta_count & 0x7FFF) as usize;
+        let (word_size, short_size) = if long_words { (4, 2) } else { (2, 1) };
+        if word_count > region_index_count {
+            return Err(Error::InvalidData)
+        }
+
+        let inner = inner as usize;
+        if inner >= item_count {
+            return Err(Error::InvalidData)
+        }
+
+        let row_size = word_count * word_size + (region_index_count - word_count) * short_size;
+        let region_indices = &data[6..];
+        let rows = region_indices.get(region_index_count * 2..).ok_or(Error::UnexpectedEof)?;
+        let row = rows.get(inner * row_size..(inner + 1) * row_size).ok_or(Error::UnexpectedEof)?;
+
+        let mut delta = 0.0;
+        for i in 0..region_index_count {
+            let value = if i < word_count {
+                let pos = i * word_size;
+                if long_words { i32::decode(&row[pos..])? } else { i16::decode(&row[pos..])? as i32 }
+            } else {
+                let pos = word_count * word_size + (i - word_count) * short_size;
+                if long_words { i16::decode(&row[pos..])? as i32 } else { i8::decode(&row[pos..])? as i32 }
+            };
+
+            if value == 0 {
+                continue
+            }
+
+            let region_index = u16::decode(&region_indices[i * 2..])? as usize;
+            let region = self.region(region_index).ok_or(Error::InvalidData)?;
+            delta += value as f32 * region_scalar(coords, region);
+        }
+
+        Ok(delta)
+    }
+}
+
+/// A DeltaSetIndexMap, which maps glyph ids (or other indices) to
+/// `(outer, inner)` indices into an `ItemVariationStore`.  It is shared by
+/// the tables which address a store by index, such as `HVAR`.
+///
+/// Format 0 has a 16-bit count of entries and format 1 a 32-bit count.
+/// Entries of either format are 1 to 4 bytes.
+#[derive(Debug, Copy, Clone)]
+pub struct DeltaSetIndexMap<'fnt> {
+    entry_format: u8,
+    map_count: usize,
+    data: &'fnt [u8],
+}
+
+impl<'fnt> Decode<'fnt> for DeltaSetIndexMap<'fnt> {
+    fn decode(buffer: &'fnt [u8]) -> Result<DeltaSetIndexMap<'fnt>> {
+        required_len!(buffer, 4);
+        let entry_format = buffer[1];
+        let (map_count, data) = match buffer[0] {
+            0 => (u16::decode(&buffer[2..])? as usize, &buffer[4..]),
+            1 => {
+                required_len!(buffer, 6);
+                (u32::decode(&buffer[2..])? as usize, &buffer[6..])
+            },
+            format => return Err(Error::UnsupportedVersion(format.into())),
+        };
+        let entry_size = ((entry_format & 0x30) >> 4) as usize + 1;
+        required_len!(data, map_count * entry_size);
+        Ok(DeltaSetIndexMap { entry_format, map_count, data })
+    }
+}
+
+impl<'fnt> DeltaSetIndexMap<'fnt> {
+    /// The number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.map_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map_count == 0
+    }
+
+    /// The `(outer, inner)` indices for `index`.  Indices past the end of
+    /// the map use the last entry.
+    pub fn get(&self, index: usize) -> Option<(u16, u16)> {
+        if self.map_count == 0 {
+            return None
+        }
+
+        let index = index.min(self.map_count - 1);
+        let entry_size = ((self.entry_format & 0x30) >> 4) as usize + 1;
+        let inner_bits = (self.entry_format & 0x0F) as u32 + 1;
+        let bytes = &self.data[index * entry_size..(index + 1) * entry_size];
+        let entry = bytes.iter().fold(0u32, |acc, &b| (acc << 8) | b as u32);
+
+        Some(((entry >> inner_bits) as u16, (entry & ((1 << inner_bits) - 1)) as u16))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_fonts;
+
+    #[test]
+    fn normalize_location() {
+        let fvar = test_fonts::fvar(&[(b"wght", 100.0, 400.0, 900.0), (b"wdth", 75.0, 100.0, 100.0)]);
+        let fvar = Fvar::decode(&fvar).unwrap();
+        let weight = Tag::new(*b"wght");
+        let width = Tag::new(*b"wdth");
+
+        let mut location = Location::from(&[(weight, 650.0), (Tag::new(*b"slnt"), -10.0)][..]);
+        location.set(width, UserCoord::new(50.0));
+        let coords = location.normalize(&fvar, None);
+        assert_eq!(coords, vec![NormalizedCoord::new(0.5), NormalizedCoord::new(-1.0)]);
+        assert_eq!(Location::new().normalize(&fvar, None), vec![NormalizedCoord::default(); 2]);
+
+        // An `avar` which maps 0.5 to 0.8 on the weight axis only.
+        let avar = [
+            0, 1, 0, 0, 0, 0, 0, 2,
+            0, 4, 0xC0, 0, 0xC0, 0, 0, 0, 0, 0, 0x20, 0, 0x33, 0x33, 0x40, 0, 0x40, 0,
+            0, 0,
+        ];
+        let avar = Avar::decode(&avar).unwrap();
+        let coords = location.normalize(&fvar, Some(&avar));
+        assert_eq!(coords[0].to_bits(), 0x3333);
+        assert_eq!(coords[1], NormalizedCoord::from_bits(-0x4000));
+
+        assert_eq!(NormalizedCoord::new(2.0).to_f32(), 1.0);
+        assert_eq!(UserCoord::new(-0.5).to_f32(), -0.5);
+    }
+
+    #[test]
+    fn delta_set_index_maps() {
+        // Format 0 with 2-byte entries of 4 inner bits.
+        let map = DeltaSetIndexMap::decode(&[0, 0x13, 0, 2, 0x00, 0x21, 0x01, 0x05]).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!((map.get(0), map.get(1), map.get(7)), (Some((2, 1)), Some((16, 5)), Some((16, 5))));
+
+        // Format 1 with 4-byte entries of 16 inner bits.
+        let map = DeltaSetIndexMap::decode(&[1, 0x3F, 0, 0, 0, 1, 0x00, 0x03, 0xFF, 0xFE]).unwrap();
+        assert_eq!((map.len(), map.get(0)), (1, Some((3, 0xFFFE))));
+
+        let empty = DeltaSetIndexMap::decode(&[1, 0x00, 0, 0, 0, 0]).unwrap();
+        assert_eq!((empty.is_empty(), empty.get(0)), (true, None));
+        assert_eq!(DeltaSetIndexMap::decode(&[1, 0x30, 0, 0, 0, 2, 0, 0, 0, 0]).err(), Some(Error::UnexpectedEof));
+        assert_eq!(DeltaSetIndexMap::decode(&[2, 0, 0, 0]).err(), Some(Error::UnsupportedVersion(2)));
+    }
+}
@@ -0,0 +1,340 @@
+//! This module contains constants for the tags of the OpenType layout
+//! registries: features, scripts and language systems, so that callers
+//! write `tags::features::LIGA` rather than spelling out
+//! `Tag::new(*b"liga")`.
+//!
+//! The lists are checked in rather than generated at build time.  They
+//! hold every registered feature and the scripts and languages most text
+//! is written in; any other tag can still be made with `Tag::new`.
+
+/// Define a constant for each `NAME => tag, "name";` entry, `ALL`, and
+/// `name`, which finds the registered name of a tag.
+macro_rules! registry {
+    ($($constant:ident => $tag:expr, $name:expr;)*) => (
+        use primitives::Tag;
+
+        $(
+        #[doc = $name]
+        pub const $constant: Tag = Tag::new(*$tag);
+        )*
+
+        /// Every tag of the list with its registered name.
+        pub const ALL: &[(Tag, &str)] = &[$(($constant, $name)),*];
+
+        /// The registered name of `tag`, or `None` if it is not in the list.
+        pub fn name(tag: Tag) -> Option<&'static str> {
+            ALL.iter().find(|&&(other, _)| other == tag).map(|&(_, name)| name)
+        }
+    )
+}
+
+/// Layout features, by which `GSUB` and `GPOS` group their lookups.
+pub mod features {
+    registry! {
+        AALT => b"aalt", "Access All Alternates";
+        ABVF => b"abvf", "Above-base Forms";
+        ABVM => b"abvm", "Above-base Mark Positioning";
+        ABVS => b"abvs", "Above-base Substitutions";
+        AFRC => b"afrc", "Alternative Fractions";
+        AKHN => b"akhn", "Akhand";
+        APKN => b"apkn", "Kerning for Alternate Proportional Widths";
+        BLWF => b"blwf", "Below-base Forms";
+        BLWM => b"blwm", "Below-base Mark Positioning";
+        BLWS => b"blws", "Below-base Substitutions";
+        C2PC => b"c2pc", "Petite Capitals From Capitals";
+        C2SC => b"c2sc", "Small Capitals From Capitals";
+        CALT => b"calt", "Contextual Alternates";
+        CASE => b"case", "Case-Sensitive Forms";
+        CCMP => b"ccmp", "Glyph Composition / Decomposition";
+        CFAR => b"cfar", "Conjunct Form After Ro";
+        CHWS => b"chws", "Contextual Half-width Spacing";
+        CJCT => b"cjct", "Conjunct Forms";
+        CLIG => b"clig", "Contextual Ligatures";
+        CPCT => b"cpct", "Centered CJK Punctuation";
+        CPSP => b"cpsp", "Capital Spacing";
+        CSWH => b"cswh", "Contextual Swash";
+        CURS => b"curs", "Cursive Positioning";
+        DIST => b"dist", "Distances";
+        DLIG => b"dlig", "Discretionary Ligatures";
+        DNOM => b"dnom", "Denominators";
+        DTLS => b"dtls", "Dotless Forms";
+        EXPT => b"expt", "Expert Forms";
+        FALT => b"falt", "Final Glyph on Line Alternates";
+        FIN2 => b"fin2", "Terminal Forms #2";
+        FIN3 => b"fin3", "Terminal Forms #3";
+        FINA => b"fina", "Terminal Forms";
+        FLAC => b"flac", "Flattened Accent Forms";
+        FRAC => b"frac", "Fractions";
+        FWID => b"fwid", "Full Widths";
+        HALF => b"half", "Half Forms";
+        HALN => b"haln", "Halant Forms";
+        HALT => b"halt", "Alternate Half Widths";
+        HIST => b"hist", "Historical Forms";
+        HKNA => b"hkna", "Horizontal Kana Alternates";
+        HLIG => b"hlig", "Historical Ligatures";
+        HNGL => b"hngl", "Hangul";
+        HOJO => b"hojo", "Hojo Kanji Forms";
+        HWID => b"hwid", "Half Widths";
+        INIT => b"init", "Initial Forms";
+        ISOL => b"isol", "Isolated Forms";
+        ITAL => b"ital", "Italics";
+        JALT => b"jalt", "Justification Alternates";
+        JP04 => b"jp04", "JIS2004 Forms";
+        JP78 => b"jp78", "JIS78 Forms";
+        JP83 => b"jp83", "JIS83 Forms";
+        JP90 => b"jp90", "JIS90 Forms";
+        KERN => b"kern", "Kerning";
+        LFBD => b"lfbd", "Left Bounds";
+        LIGA => b"liga", "Standard Ligatures";
+        LJMO => b"ljmo", "Leading Jamo Forms";
+        LNUM => b"lnum", "Lining Figures";
+        LOCL => b"locl", "Localized Forms";
+        LTRA => b"ltra", "Left-to-right Alternates";
+        LTRM => b"ltrm", "Left-to-right Mirrored Forms";
+        MARK => b"mark", "Mark Positioning";
+        MED2 => b"med2", "Medial Forms #2";
+        MEDI => b"medi", "Medial Forms";
+        MGRK => b"mgrk", "Mathematical Greek";
+        MKMK => b"mkmk", "Mark to Mark Positioning";
+        MSET => b"mset", "Mark Positioning via Substitution";
+        NALT => b"nalt", "Alternate Annotation Forms";
+        NLCK => b"nlck", "NLC Kanji Forms";
+        NUKT => b"nukt", "Nukta Forms";
+        NUMR => b"numr", "Numerators";
+        ONUM => b"onum", "Oldstyle Figures";
+        OPBD => b"opbd", "Optical Bounds";
+        ORDN => b"ordn", "Ordinals";
+        ORNM => b"ornm", "Ornaments";
+        PALT => b"palt", "Proportional Alternate Widths";
+        PCAP => b"pcap", "Petite Capitals";
+        PKNA => b"pkna", "Proportional Kana";
+        PNUM => b"pnum", "Proportional Figures";
+        PREF => b"pref", "Pre-base Forms";
+        PRES => b"pres", "Pre-base Substitutions";
+        PSTF => b"pstf", "Post-base Forms";
+        PSTS => b"psts", "Post-base Substitutions";
+        PWID => b"pwid", "Proportional Widths";
+        QWID => b"qwid", "Quarter Widths";
+        RAND => b"rand", "Randomize";
+        RCLT => b"rclt", "Required Contextual Alternates";
+        RKRF => b"rkrf", "Rakar Forms";
+        RLIG => b"rlig", "Required Ligatures";
+        RPHF => b"rphf", "Reph Form";
+        RTBD => b"rtbd", "Right Bounds";
+        RTLA => b"rtla", "Right-to-left Alternates";
+        RTLM => b"rtlm", "Right-to-left Mirrored Forms";
+        RUBY => b"ruby", "Ruby Notation Forms";
+        RVRN => b"rvrn", "Required Variation Alternates";
+        SALT => b"salt", "Stylistic Alternates";
+        SINF => b"sinf", "Scientific Inferiors";
+        SIZE => b"size", "Optical Size";
+        SMCP => b"smcp", "Small Capitals";
+        SMPL => b"smpl", "Simplified Forms";
+        SSTY => b"ssty", "Math Script-style Alternates";
+        STCH => b"stch", "Stretching Glyph Decomposition";
+        SUBS => b"subs", "Subscript";
+        SUPS => b"sups", "Superscript";
+        SWSH => b"swsh", "Swash";
+        TITL => b"titl", "Titling";
+        TJMO => b"tjmo", "Trailing Jamo Forms";
+        TNAM => b"tnam", "Traditional Name Forms";
+        TNUM => b"tnum", "Tabular Figures";
+        TRAD => b"trad", "Traditional Forms";
+        TWID => b"twid", "Third Widths";
+        UNIC => b"unic", "Unicase";
+        VALT => b"valt", "Alternate Vertical Metrics";
+        VATU => b"vatu", "Vattu Variants";
+        VCHW => b"vchw", "Vertical Contextual Half-width Spacing";
+        VERT => b"vert", "Vertical Alternates";
+        VHAL => b"vhal", "Alternate Vertical Half Metrics";
+        VJMO => b"vjmo", "Vowel Jamo Forms";
+        VKNA => b"vkna", "Vertical Kana Alternates";
+        VKRN => b"vkrn", "Vertical Kerning";
+        VPAL => b"vpal", "Proportional Alternate Vertical Metrics";
+        VRT2 => b"vrt2", "Vertical Alternates and Rotation";
+        VRTR => b"vrtr", "Vertical Alternates for Rotation";
+        ZERO => b"zero", "Slashed Zero";
+    }
+
+    /// The stylistic set feature `ss01` to `ss20` for `set` from 1 to 20.
+    pub fn stylistic_set(set: u8) -> Option<Tag> {
+        numbered(*b"ss", set, 20)
+    }
+
+    /// The character variant feature `cv01` to `cv99` for `variant` from 1
+    /// to 99.
+    pub fn character_variant(variant: u8) -> Option<Tag> {
+        numbered(*b"cv", variant, 99)
+    }
+
+    fn numbered(prefix: [u8; 2], number: u8, max: u8) -> Option<Tag> {
+        if number == 0 || number > max {
+            return None
+        }
+        Some(Tag::new([prefix[0], prefix[1], b'0' + number / 10, b'0' + number % 10]))
+    }
+}
+
+/// Script tags, which select a script table of `GSUB`, `GPOS` or `BASE`.
+/// The Indic scripts and Myanmar have a second tag for their newer
+/// shaping model, marked `_V2`.
+pub mod scripts {
+    registry! {
+        DEFAULT => b"DFLT", "Default";
+        ADLAM => b"adlm", "Adlam";
+        ARABIC => b"arab", "Arabic";
+        ARMENIAN => b"armn", "Armenian";
+        BALINESE => b"bali", "Balinese";
+        BENGALI => b"beng", "Bengali";
+        BENGALI_V2 => b"bng2", "Bengali v.2";
+        BOPOMOFO => b"bopo", "Bopomofo";
+        BRAILLE => b"brai", "Braille";
+        BUGINESE => b"bugi", "Buginese";
+        BYZANTINE_MUSIC => b"byzm", "Byzantine Music";
+        CANADIAN_SYLLABICS => b"cans", "Canadian Syllabics";
+        CHEROKEE => b"cher", "Cherokee";
+        COPTIC => b"copt", "Coptic";
+        CYRILLIC => b"cyrl", "Cyrillic";
+        DEVANAGARI => b"deva", "Devanagari";
+        DEVANAGARI_V2 => b"dev2", "Devanagari v.2";
+        ETHIOPIC => b"ethi", "Ethiopic";
+        GEORGIAN => b"geor", "Georgian";
+        GOTHIC => b"goth", "Gothic";
+        GREEK => b"grek", "Greek";
+        GUJARATI => b"gujr", "Gujarati";
+        GUJARATI_V2 => b"gjr2", "Gujarati v.2";
+        GURMUKHI => b"guru", "Gurmukhi";
+        GURMUKHI_V2 => b"gur2", "Gurmukhi v.2";
+        HAN => b"hani", "CJK Ideographic";
+        HANGUL => b"hang", "Hangul";
+        HANGUL_JAMO => b"jamo", "Hangul Jamo";
+        HEBREW => b"hebr", "Hebrew";
+        JAVANESE => b"java", "Javanese";
+        KANA => b"kana", "Hiragana and Katakana";
+        KANNADA => b"knda", "Kannada";
+        KANNADA_V2 => b"knd2", "Kannada v.2";
+        KHMER => b"khmr", "Khmer";
+        LAO => b"lao ", "Lao";
+        LATIN => b"latn", "Latin";
+        LIMBU => b"limb", "Limbu";
+        MALAYALAM => b"mlym", "Malayalam";
+        MALAYALAM_V2 => b"mlm2", "Malayalam v.2";
+        MATH => b"math", "Mathematical Alphanumeric Symbols";
+        MONGOLIAN => b"mong", "Mongolian";
+        MUSICAL_SYMBOLS => b"musc", "Musical Symbols";
+        MYANMAR => b"mymr", "Myanmar";
+        MYANMAR_V2 => b"mym2", "Myanmar v.2";
+        NKO => b"nko ", "N'Ko";
+        OGHAM => b"ogam", "Ogham";
+        OL_CHIKI => b"olck", "Ol Chiki";
+        ORIYA => b"orya", "Odia";
+        ORIYA_V2 => b"ory2", "Odia v.2";
+        RUNIC => b"runr", "Runic";
+        SINHALA => b"sinh", "Sinhala";
+        SUNDANESE => b"sund", "Sundanese";
+        SYRIAC => b"syrc", "Syriac";
+        TAGALOG => b"tglg", "Tagalog";
+        TAI_LE => b"tale", "Tai Le";
+        TAI_THAM => b"lana", "Tai Tham";
+        TAMIL => b"taml", "Tamil";
+        TAMIL_V2 => b"tml2", "Tamil v.2";
+        TELUGU => b"telu", "Telugu";
+        TELUGU_V2 => b"tel2", "Telugu v.2";
+        THAANA => b"thaa", "Thaana";
+        THAI => b"thai", "Thai";
+        TIBETAN => b"tibt", "Tibetan";
+        TIFINAGH => b"tfng", "Tifinagh";
+        VAI => b"vai ", "Vai";
+        YI => b"yi  ", "Yi";
+    }
+}
+
+/// Language system tags, which select a language system within a script
+/// table.  They are uppercase and padded with spaces.
+pub mod languages {
+    registry! {
+        DEFAULT => b"dflt", "Default";
+        ALBANIAN => b"SQI ", "Albanian";
+        ARABIC => b"ARA ", "Arabic";
+        ARMENIAN => b"HYE ", "Armenian";
+        AZERBAIJANI => b"AZE ", "Azerbaijani";
+        BASHKIR => b"BSH ", "Bashkir";
+        BULGARIAN => b"BGR ", "Bulgarian";
+        CATALAN => b"CAT ", "Catalan";
+        CHINESE_HONG_KONG => b"ZHH ", "Chinese, Traditional, Hong Kong SAR";
+        CHINESE_SIMPLIFIED => b"ZHS ", "Chinese, Simplified";
+        CHINESE_TRADITIONAL => b"ZHT ", "Chinese, Traditional";
+        CHUVASH => b"CHU ", "Chuvash";
+        CRIMEAN_TATAR => b"CRT ", "Crimean Tatar";
+        CROATIAN => b"HRV ", "Croatian";
+        CZECH => b"CSY ", "Czech";
+        DANISH => b"DAN ", "Danish";
+        DUTCH => b"NLD ", "Dutch";
+        ENGLISH => b"ENG ", "English";
+        FINNISH => b"FIN ", "Finnish";
+        FRENCH => b"FRA ", "French";
+        GAGAUZ => b"GAG ", "Gagauz";
+        GERMAN => b"DEU ", "German";
+        GREEK => b"ELL ", "Greek";
+        HEBREW => b"IWR ", "Hebrew";
+        HINDI => b"HIN ", "Hindi";
+        HUNGARIAN => b"HUN ", "Hungarian";
+        IPA_PHONETIC => b"IPPH", "International Phonetic Alphabet";
+        IRISH_TRADITIONAL => b"IRT ", "Irish Traditional";
+        ITALIAN => b"ITA ", "Italian";
+        JAPANESE => b"JAN ", "Japanese";
+        KAZAKH => b"KAZ ", "Kazakh";
+        KOREAN => b"KOR ", "Korean";
+        KURDISH => b"KUR ", "Kurdish";
+        MACEDONIAN => b"MKD ", "Macedonian";
+        MARATHI => b"MAR ", "Marathi";
+        MOLDAVIAN => b"MOL ", "Moldavian";
+        NAVAJO => b"NAV ", "Navajo";
+        NEPALI => b"NEP ", "Nepali";
+        NORWEGIAN => b"NOR ", "Norwegian";
+        PERSIAN => b"FAR ", "Persian";
+        POLISH => b"PLK ", "Polish";
+        PORTUGUESE => b"PTG ", "Portuguese";
+        ROMANIAN => b"ROM ", "Romanian";
+        RUSSIAN => b"RUS ", "Russian";
+        SERBIAN => b"SRB ", "Serbian";
+        SINDHI => b"SND ", "Sindhi";
+        SLOVAK => b"SKY ", "Slovak";
+        SLOVENIAN => b"SLV ", "Slovenian";
+        SPANISH => b"ESP ", "Spanish";
+        SWEDISH => b"SVE ", "Swedish";
+        TATAR => b"TAT ", "Tatar";
+        THAI => b"THA ", "Thai";
+        TURKISH => b"TRK ", "Turkish";
+        UKRAINIAN => b"UKR ", "Ukrainian";
+        URDU => b"URD ", "Urdu";
+        VIETNAMESE => b"VIT ", "Vietnamese";
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use primitives::Tag;
+
+    #[test]
+    fn registered_tags() {
+        assert_eq!(features::LIGA, Tag::new(*b"liga"));
+        assert_eq!(features::name(features::KERN), Some("Kerning"));
+        assert_eq!(features::name(Tag::new(*b"zzzz")), None);
+        assert_eq!(features::stylistic_set(7), Some(Tag::new(*b"ss07")));
+        assert_eq!(features::stylistic_set(21), None);
+        assert_eq!(features::character_variant(42), Some(Tag::new(*b"cv42")));
+        assert_eq!(scripts::LAO, Tag::new(*b"lao "));
+        assert_eq!(scripts::name(scripts::DEVANAGARI_V2), Some("Devanagari v.2"));
+        assert_eq!(languages::TURKISH, Tag::new(*b"TRK "));
+
+        // No tag is listed twice.
+        for all in &[features::ALL, scripts::ALL, languages::ALL] {
+            let mut tags = all.iter().map(|&(tag, _)| tag).collect::<Vec<_>>();
+            tags.sort();
+            tags.dedup();
+            assert_eq!(tags.len(), all.len());
+        }
+    }
+}
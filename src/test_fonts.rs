@@ -0,0 +1,1158 @@
+//! Builders for small fonts constructed in memory, so that tables can be
+//! tested deterministically without shipping binary fixtures.  The helpers
+//! only produce what the decoders read, and are not complete fonts.
+
+use primitives::Tag;
+use writer::FontWriter;
+
+fn push_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn push_i16(out: &mut Vec<u8>, value: i16) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn push_u24(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_be_bytes()[1..]);
+}
+
+fn push_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+#[cfg(feature = "variations")]
+fn push_fixed(out: &mut Vec<u8>, value: f32) {
+    push_u32(out, (value * 65536.0).round() as i32 as u32);
+}
+
+#[cfg(any(feature = "variations", feature = "color"))]
+fn push_f2dot14(out: &mut Vec<u8>, value: f32) {
+    push_i16(out, (value * 16384.0).round() as i16);
+}
+
+/// Collects the tables of a test font, which are assembled by `FontWriter`.
+#[derive(Debug, Clone, Default)]
+pub struct FontBuilder {
+    tables: Vec<([u8; 4], Vec<u8>)>,
+}
+
+impl FontBuilder {
+    pub fn new() -> FontBuilder {
+        FontBuilder::default()
+    }
+
+    /// Add `data` as the table `tag`, replacing any existing table.
+    pub fn table(mut self, tag: &[u8; 4], data: Vec<u8>) -> FontBuilder {
+        self.tables.retain(|&(existing, _)| existing != *tag);
+        self.tables.push((*tag, data));
+        self
+    }
+
+    pub fn without(mut self, tag: &[u8; 4]) -> FontBuilder {
+        self.tables.retain(|&(existing, _)| existing != *tag);
+        self
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let mut writer = FontWriter::new();
+        for &(tag, ref data) in &self.tables {
+            writer.table(Tag::new(tag), data.clone());
+        }
+        writer.build()
+    }
+}
+
+pub fn head(units_per_em: u16, long_loca: bool) -> Vec<u8> {
+    let mut out = Vec::new();
+    push_u32(&mut out, 0x00010000);
+    push_u32(&mut out, 0x00010000);
+    push_u32(&mut out, 0);
+    push_u32(&mut out, 0x5F0F3CF5);
+    push_u16(&mut out, 0);
+    push_u16(&mut out, units_per_em);
+    out.extend_from_slice(&[0; 16]);
+    for &bound in &[0, 0, units_per_em as i16, units_per_em as i16] {
+        push_i16(&mut out, bound);
+    }
+    push_u16(&mut out, 0);
+    push_u16(&mut out, 8);
+    push_i16(&mut out, 2);
+    push_i16(&mut out, long_loca as i16);
+    push_i16(&mut out, 0);
+    out
+}
+
+pub fn hhea(ascender: i16, descender: i16, number_of_h_metrics: u16) -> Vec<u8> {
+    let mut out = Vec::new();
+    push_u32(&mut out, 0x00010000);
+    push_i16(&mut out, ascender);
+    push_i16(&mut out, descender);
+    out.extend_from_slice(&[0; 24]);
+    push_i16(&mut out, 0);
+    push_u16(&mut out, number_of_h_metrics);
+    out
+}
+
+pub fn maxp(num_glyphs: u16) -> Vec<u8> {
+    let mut out = Vec::new();
+    push_u32(&mut out, 0x00005000);
+    push_u16(&mut out, num_glyphs);
+    out
+}
+
+/// An `hmtx` table with a long metric for each of `metrics`, followed by
+/// the left side bearings of glyphs which share the last advance.
+pub fn hmtx(metrics: &[(u16, i16)], left_side_bearings: &[i16]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for &(advance, lsb) in metrics {
+        push_u16(&mut out, advance);
+        push_i16(&mut out, lsb);
+    }
+    for &lsb in left_side_bearings {
+        push_i16(&mut out, lsb);
+    }
+    out
+}
+
+/// A simple glyph whose contours contain only on-curve points.
+pub fn simple_glyph(contours: &[&[(i16, i16)]]) -> Vec<u8> {
+    hinted_glyph(contours, &[])
+}
+
+/// A simple glyph as `simple_glyph` builds it, with `instructions`.
+pub fn hinted_glyph(contours: &[&[(i16, i16)]], instructions: &[u8]) -> Vec<u8> {
+    let points = contours.iter().flat_map(|contour| contour.iter()).collect::<Vec<_>>();
+    if points.is_empty() {
+        return Vec::new()
+    }
+
+    let mut out = Vec::new();
+    push_i16(&mut out, contours.len() as i16);
+    push_i16(&mut out, points.iter().map(|p| p.0).min().unwrap());
+    push_i16(&mut out, points.iter().map(|p| p.1).min().unwrap());
+    push_i16(&mut out, points.iter().map(|p| p.0).max().unwrap());
+    push_i16(&mut out, points.iter().map(|p| p.1).max().unwrap());
+
+    let mut end = 0;
+    for contour in contours {
+        end += contour.len() as u16;
+        push_u16(&mut out, end - 1);
+    }
+
+    // Every point is on-curve with 16-bit coordinates.
+    push_u16(&mut out, instructions.len() as u16);
+    out.extend_from_slice(instructions);
+    out.extend(points.iter().map(|_| 0x01));
+    let (mut x, mut y) = (0, 0);
+    for point in &points {
+        push_i16(&mut out, point.0 - x);
+        x = point.0;
+    }
+    for point in &points {
+        push_i16(&mut out, point.1 - y);
+        y = point.1;
+    }
+
+    out
+}
+
+/// Build the `glyf` and `loca` tables for `glyphs`.
+pub fn glyf_and_loca(glyphs: &[Vec<u8>], long_loca: bool) -> (Vec<u8>, Vec<u8>) {
+    let mut glyf = Vec::new();
+    let mut loca = Vec::new();
+    let push_offset = |loca: &mut Vec<u8>, offset: usize| {
+        if long_loca {
+            push_u32(loca, offset as u32);
+        } else {
+            push_u16(loca, (offset / 2) as u16);
+        }
+    };
+
+    for glyph in glyphs {
+        push_offset(&mut loca, glyf.len());
+        glyf.extend_from_slice(glyph);
+        if glyf.len() % 2 != 0 {
+            glyf.push(0);
+        }
+    }
+    push_offset(&mut loca, glyf.len());
+
+    (glyf, loca)
+}
+
+/// An `fvar` table with `(tag, min, default, max)` axes and no instances.
+#[cfg(feature = "variations")]
+pub fn fvar(axes: &[(&[u8; 4], f32, f32, f32)]) -> Vec<u8> {
+    fvar_with_instances(axes, &[])
+}
+
+/// An `fvar` table with `(tag, min, default, max)` axes and a named
+/// instance for each `(subfamily name ID, coordinates)`.
+#[cfg(feature = "variations")]
+pub fn fvar_with_instances(axes: &[(&[u8; 4], f32, f32, f32)], instances: &[(u16, &[f32])]) -> Vec<u8> {
+    let mut out = Vec::new();
+    push_u32(&mut out, 0x00010000);
+    push_u16(&mut out, 16);
+    push_u16(&mut out, 2);
+    push_u16(&mut out, axes.len() as u16);
+    push_u16(&mut out, 20);
+    push_u16(&mut out, instances.len() as u16);
+    push_u16(&mut out, axes.len() as u16 * 4 + 4);
+    for &(tag, min, default, max) in axes {
+        out.extend_from_slice(tag);
+        push_fixed(&mut out, min);
+        push_fixed(&mut out, default);
+        push_fixed(&mut out, max);
+        push_u16(&mut out, 0);
+        push_u16(&mut out, 256);
+    }
+    for &(name_id, coordinates) in instances {
+        push_u16(&mut out, name_id);
+        push_u16(&mut out, 0);
+        for &coordinate in coordinates {
+            push_fixed(&mut out, coordinate);
+        }
+    }
+    out
+}
+
+/// An axis value table of `STAT`, whose fields follow the order of the
+/// table: axis index, values, flags and then the value name ID.
+#[cfg(feature = "variations")]
+pub enum AxisValueRecord<'a> {
+    /// Format 1: an axis value.
+    Single(u16, f32, u16, u16),
+    /// Format 2: a nominal value with a `(min, max)` range.
+    Range(u16, f32, f32, f32, u16),
+    /// Format 4: a value on each of several axes.
+    Multiple(&'a [(u16, f32)], u16, u16),
+}
+
+/// A version 1.1 `STAT` table with `(tag, ordering)` design axes, named
+/// by IDs from 256, and the given axis values.
+#[cfg(feature = "variations")]
+pub fn stat(axes: &[(&[u8; 4], u16)], values: &[AxisValueRecord], elided_fallback_name_id: u16) -> Vec<u8> {
+    let mut out = Vec::new();
+    push_u16(&mut out, 1);
+    push_u16(&mut out, 1);
+    push_u16(&mut out, 8);
+    push_u16(&mut out, axes.len() as u16);
+    push_u32(&mut out, 20);
+    push_u16(&mut out, values.len() as u16);
+    push_u32(&mut out, 20 + axes.len() as u32 * 8);
+    push_u16(&mut out, elided_fallback_name_id);
+    for (index, &(tag, ordering)) in axes.iter().enumerate() {
+        out.extend_from_slice(tag);
+        push_u16(&mut out, 256 + index as u16);
+        push_u16(&mut out, ordering);
+    }
+
+    let mut tables = Vec::new();
+    for value in values {
+        push_u16(&mut out, (values.len() * 2 + tables.len()) as u16);
+        match *value {
+            AxisValueRecord::Single(axis, value, flags, name_id) => {
+                push_u16(&mut tables, 1);
+                push_u16(&mut tables, axis);
+                push_u16(&mut tables, flags);
+                push_u16(&mut tables, name_id);
+                push_fixed(&mut tables, value);
+            },
+            AxisValueRecord::Range(axis, value, min, max, name_id) => {
+                push_u16(&mut tables, 2);
+                push_u16(&mut tables, axis);
+                push_u16(&mut tables, 0);
+                push_u16(&mut tables, name_id);
+                push_fixed(&mut tables, value);
+                push_fixed(&mut tables, min);
+                push_fixed(&mut tables, max);
+            },
+            AxisValueRecord::Multiple(axis_values, flags, name_id) => {
+                push_u16(&mut tables, 4);
+                push_u16(&mut tables, axis_values.len() as u16);
+                push_u16(&mut tables, flags);
+                push_u16(&mut tables, name_id);
+                for &(axis, value) in axis_values {
+                    push_u16(&mut tables, axis);
+                    push_fixed(&mut tables, value);
+                }
+            },
+        }
+    }
+    out.extend(tables);
+    out
+}
+
+/// A tuple variation with an embedded peak and a delta for every point,
+/// including the four phantom points.
+#[cfg(feature = "variations")]
+pub type TupleDeltas = (Vec<f32>, Vec<(i16, i16)>);
+
+/// A `gvar` table with a list of tuple variations for each glyph.
+#[cfg(feature = "variations")]
+pub fn gvar(axis_count: usize, glyphs: &[Vec<TupleDeltas>]) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut offsets = Vec::new();
+    for variations in glyphs {
+        offsets.push(data.len() as u32);
+        if variations.is_empty() {
+            continue
+        }
+
+        let mut serialized = vec![0];
+        let mut headers = Vec::new();
+        for (peak, deltas) in variations {
+            let start = serialized.len();
+            pack_deltas(&mut serialized, deltas.iter().map(|delta| delta.0));
+            pack_deltas(&mut serialized, deltas.iter().map(|delta| delta.1));
+
+            push_u16(&mut headers, (serialized.len() - start) as u16);
+            push_u16(&mut headers, 0x8000);
+            for &coord in peak {
+                push_f2dot14(&mut headers, coord);
+            }
+        }
+
+        push_u16(&mut data, 0x8000 | variations.len() as u16);
+        push_u16(&mut data, 4 + headers.len() as u16);
+        data.extend(headers);
+        data.extend(serialized);
+    }
+    offsets.push(data.len() as u32);
+
+    let mut out = Vec::new();
+    let offsets_end = 20 + offsets.len() as u32 * 4;
+    push_u32(&mut out, 0x00010000);
+    push_u16(&mut out, axis_count as u16);
+    push_u16(&mut out, 0);
+    push_u32(&mut out, offsets_end);
+    push_u16(&mut out, glyphs.len() as u16);
+    push_u16(&mut out, 1);
+    push_u32(&mut out, offsets_end);
+    for offset in offsets {
+        push_u32(&mut out, offset);
+    }
+    out.extend(data);
+    out
+}
+
+#[cfg(feature = "variations")]
+fn pack_deltas<I: Iterator<Item = i16>>(out: &mut Vec<u8>, deltas: I) {
+    let deltas = deltas.collect::<Vec<_>>();
+    for run in deltas.chunks(64) {
+        out.push(0x40 | (run.len() - 1) as u8);
+        for &delta in run {
+            push_i16(out, delta);
+        }
+    }
+}
+
+/// A `cmap` table with a single Unicode format 12 subtable, with a group
+/// for each of `mappings`.
+pub fn cmap(mappings: &[(char, u16)]) -> Vec<u8> {
+    cmap_with_sequences(mappings, &[])
+}
+
+/// A `cmap` table as `cmap` builds it, and a format 14 subtable for each
+/// `(character, selector, glyph)` sequence when there are any.  Sequences
+/// without a glyph use the default glyph of the character.
+pub fn cmap_with_sequences(mappings: &[(char, u16)], sequences: &[(char, char, Option<u16>)]) -> Vec<u8> {
+    let mut mappings = mappings.to_vec();
+    mappings.sort();
+
+    let mut subtable = Vec::new();
+    push_u16(&mut subtable, 12);
+    push_u16(&mut subtable, 0);
+    push_u32(&mut subtable, 16 + mappings.len() as u32 * 12);
+    push_u32(&mut subtable, 0);
+    push_u32(&mut subtable, mappings.len() as u32);
+    for &(character, glyph) in &mappings {
+        push_u32(&mut subtable, character as u32);
+        push_u32(&mut subtable, character as u32);
+        push_u32(&mut subtable, glyph as u32);
+    }
+
+    let mut out = Vec::new();
+    push_u16(&mut out, 0);
+    if sequences.is_empty() {
+        push_u16(&mut out, 1);
+        push_u16(&mut out, 3);
+        push_u16(&mut out, 10);
+        push_u32(&mut out, 12);
+        out.extend(subtable);
+        return out
+    }
+
+    let variations = variation_sequences(sequences);
+    push_u16(&mut out, 2);
+    push_u16(&mut out, 0);
+    push_u16(&mut out, 5);
+    push_u32(&mut out, 20);
+    push_u16(&mut out, 3);
+    push_u16(&mut out, 10);
+    push_u32(&mut out, 20 + variations.len() as u32);
+    out.extend(variations);
+    out.extend(subtable);
+    out
+}
+
+/// A format 14 subtable with a default and a non-default UVS table for
+/// each selector of `sequences`.
+fn variation_sequences(sequences: &[(char, char, Option<u16>)]) -> Vec<u8> {
+    let mut selectors = sequences.iter().map(|&(_, selector, _)| selector).collect::<Vec<_>>();
+    selectors.sort();
+    selectors.dedup();
+
+    let mut tables = Vec::new();
+    let mut records = Vec::new();
+    let tables_start = 10 + selectors.len() as u32 * 11;
+    for &selector in &selectors {
+        let mut defaults = sequences.iter()
+            .filter(|&&(_, sequence_selector, glyph)| sequence_selector == selector && glyph.is_none())
+            .map(|&(character, _, _)| character as u32)
+            .collect::<Vec<_>>();
+        defaults.sort();
+        let mut variants = sequences.iter()
+            .filter(|&&(_, sequence_selector, _)| sequence_selector == selector)
+            .filter_map(|&(character, _, glyph)| glyph.map(|glyph| (character as u32, glyph)))
+            .collect::<Vec<_>>();
+        variants.sort();
+
+        push_u24(&mut records, selector as u32);
+        push_u32(&mut records, tables_start + tables.len() as u32);
+        push_u32(&mut tables, defaults.len() as u32);
+        for code in defaults {
+            push_u24(&mut tables, code);
+            tables.push(0);
+        }
+        push_u32(&mut records, tables_start + tables.len() as u32);
+        push_u32(&mut tables, variants.len() as u32);
+        for (code, glyph) in variants {
+            push_u24(&mut tables, code);
+            push_u16(&mut tables, glyph);
+        }
+    }
+
+    let mut out = Vec::new();
+    push_u16(&mut out, 14);
+    push_u32(&mut out, tables_start + tables.len() as u32);
+    push_u32(&mut out, selectors.len() as u32);
+    out.extend(records);
+    out.extend(tables);
+    out
+}
+
+/// A `name` table with a Windows US English record for each
+/// `(name ID, text)`.
+#[cfg(feature = "variations")]
+pub fn name(names: &[(u16, &str)]) -> Vec<u8> {
+    let mut storage = Vec::new();
+    let mut out = Vec::new();
+    push_u16(&mut out, 0);
+    push_u16(&mut out, names.len() as u16);
+    push_u16(&mut out, 6 + names.len() as u16 * 12);
+    for &(name_id, text) in names {
+        let start = storage.len();
+        for unit in text.encode_utf16() {
+            push_u16(&mut storage, unit);
+        }
+        push_u16(&mut out, 3);
+        push_u16(&mut out, 1);
+        push_u16(&mut out, 0x409);
+        push_u16(&mut out, name_id);
+        push_u16(&mut out, (storage.len() - start) as u16);
+        push_u16(&mut out, start as u16);
+    }
+    out.extend(storage);
+    out
+}
+
+/// A version 2 `post` table naming each glyph in turn, with every name
+/// stored as a custom name.
+pub fn post(names: &[&str]) -> Vec<u8> {
+    let mut out = Vec::new();
+    push_u32(&mut out, 0x0002_0000);
+    out.extend_from_slice(&[0; 28]);
+    push_u16(&mut out, names.len() as u16);
+    for index in 0..names.len() {
+        push_u16(&mut out, 258 + index as u16);
+    }
+    for name in names {
+        out.push(name.len() as u8);
+        out.extend_from_slice(name.as_bytes());
+    }
+    out
+}
+
+/// An `MVAR` table varying each `(tag, delta)` metric by `delta` at the
+/// maximum of the first of `axis_count` axes.
+#[cfg(feature = "variations")]
+pub fn mvar(axis_count: usize, metrics: &[(&[u8; 4], i16)]) -> Vec<u8> {
+    let mut metrics = metrics.to_vec();
+    metrics.sort();
+
+    let mut out = Vec::new();
+    push_u16(&mut out, 1);
+    push_u16(&mut out, 0);
+    push_u16(&mut out, 0);
+    push_u16(&mut out, 8);
+    push_u16(&mut out, metrics.len() as u16);
+    push_u16(&mut out, 12 + metrics.len() as u16 * 8);
+    for (index, &(tag, _)) in metrics.iter().enumerate() {
+        out.extend_from_slice(tag);
+        push_u16(&mut out, 0);
+        push_u16(&mut out, index as u16);
+    }
+
+    // An ItemVariationStore with a single region and ItemVariationData.
+    push_u16(&mut out, 1);
+    push_u32(&mut out, 12);
+    push_u16(&mut out, 1);
+    push_u32(&mut out, 16 + axis_count as u32 * 6);
+
+    push_u16(&mut out, axis_count as u16);
+    push_u16(&mut out, 1);
+    for axis in 0..axis_count {
+        let peak = if axis == 0 { 1.0 } else { 0.0 };
+        push_f2dot14(&mut out, 0.0);
+        push_f2dot14(&mut out, peak);
+        push_f2dot14(&mut out, peak);
+    }
+
+    push_u16(&mut out, metrics.len() as u16);
+    push_u16(&mut out, 1);
+    push_u16(&mut out, 1);
+    push_u16(&mut out, 0);
+    for &(_, delta) in &metrics {
+        push_i16(&mut out, delta);
+    }
+    out
+}
+
+/// A version 4 OS/2 table of a regular weight and width face, with the
+/// typographic ascender, descender and line gap, the Windows ascent and
+/// descent, and `fsSelection`.  Other fields are zero.
+pub fn os2(typo: (i16, i16, i16), win: (u16, u16), fs_selection: u16) -> Vec<u8> {
+    let mut out = vec![0; 96];
+    out[0..2].copy_from_slice(&4u16.to_be_bytes());
+    out[4..6].copy_from_slice(&400u16.to_be_bytes());
+    out[6..8].copy_from_slice(&5u16.to_be_bytes());
+    out[62..64].copy_from_slice(&fs_selection.to_be_bytes());
+    out[68..70].copy_from_slice(&typo.0.to_be_bytes());
+    out[70..72].copy_from_slice(&typo.1.to_be_bytes());
+    out[72..74].copy_from_slice(&typo.2.to_be_bytes());
+    out[74..76].copy_from_slice(&win.0.to_be_bytes());
+    out[76..78].copy_from_slice(&win.1.to_be_bytes());
+    out
+}
+
+/// A `kern` table with the Windows header and one horizontal format 0
+/// subtable of `(left, right, value)` pairs.
+pub fn kern(pairs: &[(u16, u16, i16)]) -> Vec<u8> {
+    let mut pairs = pairs.to_vec();
+    pairs.sort();
+    let entry_selector = (pairs.len().max(1) as f32).log2() as u16;
+    let search_range = 6 << entry_selector;
+
+    let mut out = Vec::new();
+    push_u16(&mut out, 0);
+    push_u16(&mut out, 1);
+    push_u16(&mut out, 0);
+    push_u16(&mut out, 14 + pairs.len() as u16 * 6);
+    push_u16(&mut out, 0x0001);
+    push_u16(&mut out, pairs.len() as u16);
+    push_u16(&mut out, search_range);
+    push_u16(&mut out, entry_selector);
+    push_u16(&mut out, (pairs.len() as u16 * 6).saturating_sub(search_range));
+    for &(left, right, value) in &pairs {
+        push_u16(&mut out, left);
+        push_u16(&mut out, right);
+        push_i16(&mut out, value);
+    }
+    out
+}
+
+/// A bitmap strike with its ppem, bit depth and inclusive glyph ranges.
+#[cfg(feature = "color")]
+pub type StrikeRanges<'a> = (u8, u8, &'a [(u16, u16)]);
+
+/// A `CBLC` or `EBLC` table at `major_version` with a strike for each of
+/// `strikes`.  The index subtables themselves are not written, since only
+/// their records are read.
+#[cfg(feature = "color")]
+pub fn bitmap_location(major_version: u16, strikes: &[StrikeRanges]) -> Vec<u8> {
+    let mut out = Vec::new();
+    push_u16(&mut out, major_version);
+    push_u16(&mut out, 0);
+    push_u32(&mut out, strikes.len() as u32);
+
+    let mut array_offset = 8 + strikes.len() * 48;
+    for &(ppem, bit_depth, ranges) in strikes {
+        push_u32(&mut out, array_offset as u32);
+        push_u32(&mut out, ranges.len() as u32 * 8);
+        push_u32(&mut out, ranges.len() as u32);
+        push_u32(&mut out, 0);
+        out.extend_from_slice(&[0; 24]);
+        push_u16(&mut out, ranges.first().map_or(0, |range| range.0));
+        push_u16(&mut out, ranges.iter().map(|range| range.1).max().unwrap_or(0));
+        out.extend_from_slice(&[ppem, ppem, bit_depth, 0x01]);
+        array_offset += ranges.len() * 8;
+    }
+
+    for &(_, _, ranges) in strikes {
+        for &(first, last) in ranges {
+            push_u16(&mut out, first);
+            push_u16(&mut out, last);
+            push_u32(&mut out, 0);
+        }
+    }
+    out
+}
+
+/// A `CPAL` table with each of `palettes`, whose colors are in RGBA
+/// order.  Palettes do not share color records.
+#[cfg(feature = "color")]
+pub fn cpal(palettes: &[&[[u8; 4]]]) -> Vec<u8> {
+    let entries = palettes.first().map_or(0, |palette| palette.len());
+    let mut out = Vec::new();
+    push_u16(&mut out, 0);
+    push_u16(&mut out, entries as u16);
+    push_u16(&mut out, palettes.len() as u16);
+    push_u16(&mut out, (palettes.len() * entries) as u16);
+    push_u32(&mut out, 12 + palettes.len() as u32 * 2);
+    for index in 0..palettes.len() {
+        push_u16(&mut out, (index * entries) as u16);
+    }
+    for &[red, green, blue, alpha] in palettes.iter().flat_map(|palette| palette.iter()) {
+        out.extend_from_slice(&[blue, green, red, alpha]);
+    }
+    out
+}
+
+/// A version 0 `COLR` table in which each base glyph is drawn as its
+/// layers of glyphs and palette indices.
+#[cfg(feature = "color")]
+pub fn colr_v0(glyphs: &[(u16, &[(u16, u16)])]) -> Vec<u8> {
+    let mut out = Vec::new();
+    push_u16(&mut out, 0);
+    push_u16(&mut out, glyphs.len() as u16);
+    push_u32(&mut out, 14);
+    push_u32(&mut out, 14 + glyphs.len() as u32 * 6);
+    push_u16(&mut out, glyphs.iter().map(|glyph| glyph.1.len() as u16).sum());
+    let mut first_layer = 0;
+    for &(glyph, layers) in glyphs {
+        push_u16(&mut out, glyph);
+        push_u16(&mut out, first_layer);
+        push_u16(&mut out, layers.len() as u16);
+        first_layer += layers.len() as u16;
+    }
+    for &(glyph, palette_index) in glyphs.iter().flat_map(|glyph| glyph.1.iter()) {
+        push_u16(&mut out, glyph);
+        push_u16(&mut out, palette_index);
+    }
+    out
+}
+
+/// A version 1 `COLR` table with the root paint of each base glyph, and
+/// a layer list of `layers`.
+#[cfg(feature = "color")]
+pub fn colr_v1(glyphs: &[(u16, Vec<u8>)], layers: &[Vec<u8>]) -> Vec<u8> {
+    let glyph_ids = glyphs.iter().map(|glyph| glyph.0.to_be_bytes()).collect::<Vec<_>>();
+    let base_glyph_list = paint_list(glyphs.iter().zip(&glyph_ids).map(|(glyph, id)| (&id[..], &glyph.1[..])));
+    let layer_list = paint_list(layers.iter().map(|layer| (&[][..], &layer[..])));
+
+    let mut out = Vec::new();
+    push_u16(&mut out, 1);
+    push_u16(&mut out, 0);
+    push_u32(&mut out, 0);
+    push_u32(&mut out, 0);
+    push_u16(&mut out, 0);
+    push_u32(&mut out, 34);
+    push_u32(&mut out, 34 + base_glyph_list.len() as u32);
+    out.extend_from_slice(&[0; 12]);
+    out.extend(base_glyph_list);
+    out.extend(layer_list);
+    out
+}
+
+/// A list of records, each a key and the offset of its paint, followed by
+/// the paints.
+#[cfg(feature = "color")]
+fn paint_list<'a, I: ExactSizeIterator<Item = (&'a [u8], &'a [u8])> + Clone>(entries: I) -> Vec<u8> {
+    let mut out = Vec::new();
+    push_u32(&mut out, entries.len() as u32);
+    let mut offset = 4 + entries.clone().map(|(key, _)| key.len() + 4).sum::<usize>();
+    for (key, paint) in entries.clone() {
+        out.extend_from_slice(key);
+        push_u32(&mut out, offset as u32);
+        offset += paint.len();
+    }
+    for (_, paint) in entries {
+        out.extend_from_slice(paint);
+    }
+    out
+}
+
+/// A `PaintSolid` of the palette entry `palette_index`.
+#[cfg(feature = "color")]
+pub fn paint_solid(palette_index: u16, alpha: f32) -> Vec<u8> {
+    let mut out = vec![2];
+    push_u16(&mut out, palette_index);
+    push_f2dot14(&mut out, alpha);
+    out
+}
+
+/// A `PaintGlyph` clipping `paint` to the outline of `glyph`.
+#[cfg(feature = "color")]
+pub fn paint_glyph(glyph: u16, paint: Vec<u8>) -> Vec<u8> {
+    let mut out = vec![10];
+    push_u24(&mut out, 6);
+    push_u16(&mut out, glyph);
+    out.extend(paint);
+    out
+}
+
+/// A `PaintColrLayers` of `count` layers from `first`.
+#[cfg(feature = "color")]
+pub fn paint_layers(first: u32, count: u8) -> Vec<u8> {
+    let mut out = vec![1, count];
+    push_u32(&mut out, first);
+    out
+}
+
+/// A `PaintLinearGradient` through `points` with stops of an offset and a
+/// palette index, which pads past its ends.
+#[cfg(feature = "color")]
+pub fn paint_linear_gradient(points: [(i16, i16); 3], stops: &[(f32, u16)]) -> Vec<u8> {
+    let mut out = vec![4];
+    push_u24(&mut out, 16);
+    for &(x, y) in &points {
+        push_i16(&mut out, x);
+        push_i16(&mut out, y);
+    }
+    out.push(0);
+    push_u16(&mut out, stops.len() as u16);
+    for &(offset, palette_index) in stops {
+        push_f2dot14(&mut out, offset);
+        push_u16(&mut out, palette_index);
+        push_f2dot14(&mut out, 1.0);
+    }
+    out
+}
+
+/// A TrueType font with three glyphs: an empty `.notdef`, a square and a
+/// triangle, at 1000 units per em.
+pub fn minimal_font(long_loca: bool) -> FontBuilder {
+    let glyphs = [
+        Vec::new(),
+        simple_glyph(&[&[(100, 0), (100, 500), (600, 500), (600, 0)]]),
+        simple_glyph(&[&[(50, 0), (300, 700), (550, 0)]]),
+    ];
+    let (glyf, loca) = glyf_and_loca(&glyphs, long_loca);
+
+    FontBuilder::new()
+        .table(b"head", head(1000, long_loca))
+        .table(b"hhea", hhea(800, -200, 2))
+        .table(b"maxp", maxp(3))
+        .table(b"hmtx", hmtx(&[(500, 0), (700, 100)], &[50]))
+        .table(b"loca", loca)
+        .table(b"glyf", glyf)
+}
+
+/// `minimal_font` with a `wght` axis from 100 to 900, where the square
+/// widens by 200 units and its advance by 100 at the heaviest weight.
+#[cfg(feature = "variations")]
+pub fn variable_font() -> FontBuilder {
+    let square = vec![
+        (0, 0), (0, 0), (200, 0), (200, 0),
+        (0, 0), (100, 0), (0, 0), (0, 0),
+    ];
+
+    minimal_font(false)
+        .table(b"fvar", fvar(&[(b"wght", 100.0, 400.0, 900.0)]))
+        .table(b"gvar", gvar(1, &[Vec::new(), vec![(vec![1.0], square)], Vec::new()]))
+}
+
+/// The baselines of a script in a `BASE` table: its tag, the index of
+/// its default baseline and a coordinate for each baseline.
+#[cfg(feature = "layout")]
+pub type ScriptBaselines<'a> = (&'a [u8; 4], u16, &'a [i16]);
+
+/// A `BASE` table with a horizontal axis listing the baselines `tags`.
+#[cfg(feature = "layout")]
+pub fn base(tags: &[&[u8; 4]], scripts: &[ScriptBaselines]) -> Vec<u8> {
+    let mut script_tables = Vec::new();
+    let mut records = Vec::new();
+    for &(tag, default, coords) in scripts {
+        records.extend_from_slice(tag);
+        push_u16(&mut records, (2 + scripts.len() * 6 + script_tables.len()) as u16);
+        push_u16(&mut script_tables, 6);
+        push_u16(&mut script_tables, 0);
+        push_u16(&mut script_tables, 0);
+        push_u16(&mut script_tables, default);
+        push_u16(&mut script_tables, coords.len() as u16);
+        for index in 0..coords.len() {
+            push_u16(&mut script_tables, (4 + coords.len() * 2 + index * 4) as u16);
+        }
+        for &coord in coords {
+            push_u16(&mut script_tables, 1);
+            push_i16(&mut script_tables, coord);
+        }
+    }
+
+    let mut out = Vec::new();
+    push_u16(&mut out, 1);
+    push_u16(&mut out, 0);
+    push_u16(&mut out, 8);
+    push_u16(&mut out, 0);
+    push_u16(&mut out, 4);
+    push_u16(&mut out, 4 + 2 + tags.len() as u16 * 4);
+    push_u16(&mut out, tags.len() as u16);
+    for tag in tags {
+        out.extend_from_slice(*tag);
+    }
+    push_u16(&mut out, scripts.len() as u16);
+    out.extend(records);
+    out.extend(script_tables);
+    out
+}
+
+/// The `(glyph, variants, parts)` of a stretchy glyph: its pre-built sizes
+/// as `(glyph, advance)` and its assembly parts as `(glyph, start
+/// connector, end connector, advance, flags)`, with no assembly if empty.
+#[cfg(feature = "layout")]
+pub type MathConstruction<'a> = (u16, &'a [(u16, u16)], &'a [(u16, u16, u16, u16, u16)]);
+
+/// A `MATH` table with only a variants subtable, whose vertical
+/// constructions are `constructions`, sorted by glyph.
+#[cfg(feature = "layout")]
+pub fn math(min_connector_overlap: u16, constructions: &[MathConstruction]) -> Vec<u8> {
+    let glyphs = constructions.iter().map(|&(glyph, _, _)| glyph).collect::<Vec<_>>();
+    let coverage = coverage(&glyphs);
+    let header_len = 10 + constructions.len() * 2;
+
+    let mut tables = Vec::new();
+    let mut offsets = Vec::new();
+    for &(_, variants, parts) in constructions {
+        push_u16(&mut offsets, (header_len + coverage.len() + tables.len()) as u16);
+        let assembly_offset = if parts.is_empty() { 0 } else { 4 + variants.len() * 4 };
+        push_u16(&mut tables, assembly_offset as u16);
+        push_u16(&mut tables, variants.len() as u16);
+        for &(glyph, advance) in variants {
+            push_u16(&mut tables, glyph);
+            push_u16(&mut tables, advance);
+        }
+        if !parts.is_empty() {
+            push_i16(&mut tables, 0);
+            push_u16(&mut tables, 0);
+            push_u16(&mut tables, parts.len() as u16);
+            for &(glyph, start, end, advance, flags) in parts {
+                for value in &[glyph, start, end, advance, flags] {
+                    push_u16(&mut tables, *value);
+                }
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    push_u16(&mut out, 1);
+    push_u16(&mut out, 0);
+    push_u16(&mut out, 0);
+    push_u16(&mut out, 0);
+    push_u16(&mut out, 10);
+    push_u16(&mut out, min_connector_overlap);
+    push_u16(&mut out, header_len as u16);
+    push_u16(&mut out, 0);
+    push_u16(&mut out, constructions.len() as u16);
+    push_u16(&mut out, 0);
+    out.extend(offsets);
+    out.extend(coverage);
+    out.extend(tables);
+    out
+}
+
+/// A `GSUB` or `GPOS` table with the single script `script`, whose default
+/// language system enables every feature.  Each feature uses the lookups
+/// at the given indices, and each lookup is a `(type, subtable)` pair.
+#[cfg(feature = "layout")]
+pub fn layout_table(script: &[u8; 4], features: &[(&[u8; 4], &[u16])], lookups: &[(u16, Vec<u8>)]) -> Vec<u8> {
+    layout_table_with_flags(script, features, lookups, &[])
+}
+
+/// A `layout_table` whose lookups have the `(flag, mark filtering set)` at
+/// their index in `flags`, or no flag if `flags` is shorter.
+#[cfg(feature = "layout")]
+pub fn layout_table_with_flags(script: &[u8; 4],
+                               features: &[(&[u8; 4], &[u16])],
+                               lookups: &[(u16, Vec<u8>)],
+                               flags: &[(u16, Option<u16>)]) -> Vec<u8> {
+    let mut script_list = Vec::new();
+    push_u16(&mut script_list, 1);
+    script_list.extend_from_slice(script);
+    push_u16(&mut script_list, 8);
+    push_u16(&mut script_list, 4);
+    push_u16(&mut script_list, 0);
+    push_u16(&mut script_list, 0);
+    push_u16(&mut script_list, 0xFFFF);
+    push_u16(&mut script_list, features.len() as u16);
+    for index in 0..features.len() {
+        push_u16(&mut script_list, index as u16);
+    }
+
+    let mut feature_list = Vec::new();
+    let mut feature_tables = Vec::new();
+    push_u16(&mut feature_list, features.len() as u16);
+    for &(tag, indices) in features {
+        feature_list.extend_from_slice(tag);
+        push_u16(&mut feature_list, (2 + features.len() * 6 + feature_tables.len()) as u16);
+        push_u16(&mut feature_tables, 0);
+        push_u16(&mut feature_tables, indices.len() as u16);
+        for &index in indices {
+            push_u16(&mut feature_tables, index);
+        }
+    }
+    feature_list.extend(feature_tables);
+
+    let mut lookup_list = Vec::new();
+    let mut lookup_tables = Vec::new();
+    push_u16(&mut lookup_list, lookups.len() as u16);
+    for (index, &(lookup_type, ref subtable)) in lookups.iter().enumerate() {
+        let (flag, mark_filtering_set) = flags.get(index).cloned().unwrap_or((0, None));
+        push_u16(&mut lookup_list, (2 + lookups.len() * 2 + lookup_tables.len()) as u16);
+        push_u16(&mut lookup_tables, lookup_type);
+        push_u16(&mut lookup_tables, flag);
+        push_u16(&mut lookup_tables, 1);
+        push_u16(&mut lookup_tables, if mark_filtering_set.is_some() { 10 } else { 8 });
+        if let Some(set) = mark_filtering_set {
+            push_u16(&mut lookup_tables, set);
+        }
+        lookup_tables.extend_from_slice(subtable);
+    }
+    lookup_list.extend(lookup_tables);
+
+    let mut out = Vec::new();
+    push_u16(&mut out, 1);
+    push_u16(&mut out, 0);
+    push_u16(&mut out, 10);
+    push_u16(&mut out, (10 + script_list.len()) as u16);
+    push_u16(&mut out, (10 + script_list.len() + feature_list.len()) as u16);
+    out.extend(script_list);
+    out.extend(feature_list);
+    out.extend(lookup_list);
+    out
+}
+
+/// A version 1.2 `GDEF` table with the `(glyph, class)` glyph classes and
+/// mark attachment classes, each sorted by glyph, and a mark glyph set of
+/// each of `mark_sets`.
+#[cfg(feature = "layout")]
+pub fn gdef(glyph_classes: &[(u16, u16)], mark_attachment_classes: &[(u16, u16)], mark_sets: &[&[u16]]) -> Vec<u8> {
+    let class_def = |classes: &[(u16, u16)]| {
+        let mut out = Vec::new();
+        push_u16(&mut out, 2);
+        push_u16(&mut out, classes.len() as u16);
+        for &(glyph, class) in classes {
+            push_u16(&mut out, glyph);
+            push_u16(&mut out, glyph);
+            push_u16(&mut out, class);
+        }
+        out
+    };
+    let (glyph_classes, mark_attachment_classes) = (class_def(glyph_classes), class_def(mark_attachment_classes));
+
+    let mut sets = Vec::new();
+    let mut coverages = Vec::new();
+    push_u16(&mut sets, 1);
+    push_u16(&mut sets, mark_sets.len() as u16);
+    for glyphs in mark_sets {
+        push_u32(&mut sets, (4 + mark_sets.len() * 4 + coverages.len()) as u32);
+        coverages.extend(coverage(glyphs));
+    }
+    sets.extend(coverages);
+
+    let mut out = Vec::new();
+    push_u16(&mut out, 1);
+    push_u16(&mut out, 2);
+    push_u16(&mut out, 14);
+    push_u16(&mut out, 0);
+    push_u16(&mut out, 0);
+    push_u16(&mut out, (14 + glyph_classes.len()) as u16);
+    push_u16(&mut out, (14 + glyph_classes.len() + mark_attachment_classes.len()) as u16);
+    out.extend(glyph_classes);
+    out.extend(mark_attachment_classes);
+    out.extend(sets);
+    out
+}
+
+/// A format 1 coverage table for `glyphs`, which must be sorted.
+#[cfg(feature = "layout")]
+fn coverage(glyphs: &[u16]) -> Vec<u8> {
+    let mut out = Vec::new();
+    push_u16(&mut out, 1);
+    push_u16(&mut out, glyphs.len() as u16);
+    for &glyph in glyphs {
+        push_u16(&mut out, glyph);
+    }
+    out
+}
+
+/// A format 2 single substitution subtable replacing each `(from, to)`,
+/// sorted by `from`.
+#[cfg(feature = "layout")]
+pub fn single_substitution(substitutions: &[(u16, u16)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    push_u16(&mut out, 2);
+    push_u16(&mut out, 6 + substitutions.len() as u16 * 2);
+    push_u16(&mut out, substitutions.len() as u16);
+    for &(_, to) in substitutions {
+        push_u16(&mut out, to);
+    }
+    out.extend(coverage(&substitutions.iter().map(|&(from, _)| from).collect::<Vec<_>>()));
+    out
+}
+
+/// A ligature substitution subtable replacing `components` by `ligature`.
+#[cfg(feature = "layout")]
+pub fn ligature_substitution(components: &[u16], ligature: u16) -> Vec<u8> {
+    let mut out = Vec::new();
+    push_u16(&mut out, 1);
+    push_u16(&mut out, 8);
+    push_u16(&mut out, 1);
+    push_u16(&mut out, 8 + 6);
+    out.extend(coverage(&components[..1]));
+    push_u16(&mut out, 1);
+    push_u16(&mut out, 4);
+    push_u16(&mut out, ligature);
+    push_u16(&mut out, components.len() as u16);
+    for &component in &components[1..] {
+        push_u16(&mut out, component);
+    }
+    out
+}
+
+/// The glyphs or classes after the first of a contextual rule, then its
+/// `(sequence index, lookup index)` records.
+#[cfg(feature = "layout")]
+fn sequence_rule(out: &mut Vec<u8>, sequence: &[u16], lookups: &[(u16, u16)]) {
+    push_u16(out, sequence.len() as u16);
+    push_u16(out, lookups.len() as u16);
+    for &value in &sequence[1..] {
+        push_u16(out, value);
+    }
+    for &(sequence_index, lookup_index) in lookups {
+        push_u16(out, sequence_index);
+        push_u16(out, lookup_index);
+    }
+}
+
+/// A format 1 contextual subtable whose one rule matches `glyphs` and
+/// applies each `(sequence index, lookup index)` of `lookups`.
+#[cfg(feature = "layout")]
+pub fn glyph_context(glyphs: &[u16], lookups: &[(u16, u16)]) -> Vec<u8> {
+    let mut rule = Vec::new();
+    sequence_rule(&mut rule, glyphs, lookups);
+
+    let mut out = Vec::new();
+    push_u16(&mut out, 1);
+    push_u16(&mut out, (12 + rule.len()) as u16);
+    push_u16(&mut out, 1);
+    push_u16(&mut out, 8);
+    push_u16(&mut out, 1);
+    push_u16(&mut out, 4);
+    out.extend(rule);
+    out.extend(coverage(&glyphs[..1]));
+    out
+}
+
+/// A format 2 contextual subtable with the `(glyph, class)` classes, sorted
+/// by glyph, whose one rule matches the sequence of `classes` and applies
+/// `lookups` as `glyph_context` does.  The rule applies at the glyphs of
+/// `first`, which must be sorted.
+#[cfg(feature = "layout")]
+pub fn class_context(first: &[u16], glyph_classes: &[(u16, u16)], classes: &[u16], lookups: &[(u16, u16)]) -> Vec<u8> {
+    let set_count = classes[0] as usize + 1;
+    let mut rule = Vec::new();
+    sequence_rule(&mut rule, classes, lookups);
+    let coverage = coverage(first);
+
+    let header = 8 + set_count * 2;
+    let mut out = Vec::new();
+    push_u16(&mut out, 2);
+    push_u16(&mut out, (header + 4 + rule.len()) as u16);
+    push_u16(&mut out, (header + 4 + rule.len() + coverage.len()) as u16);
+    push_u16(&mut out, set_count as u16);
+    for class in 0..set_count {
+        push_u16(&mut out, if class == set_count - 1 { header as u16 } else { 0 });
+    }
+    push_u16(&mut out, 1);
+    push_u16(&mut out, 4);
+    out.extend(rule);
+    out.extend(coverage);
+    push_u16(&mut out, 2);
+    push_u16(&mut out, glyph_classes.len() as u16);
+    for &(glyph, class) in glyph_classes {
+        push_u16(&mut out, glyph);
+        push_u16(&mut out, glyph);
+        push_u16(&mut out, class);
+    }
+    out
+}
+
+/// A format 3 contextual subtable matching a glyph of each of `coverages`
+/// and applying `lookups` as `glyph_context` does.
+#[cfg(feature = "layout")]
+pub fn coverage_context(coverages: &[&[u16]], lookups: &[(u16, u16)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut tables = Vec::new();
+    let header = 6 + coverages.len() * 2 + lookups.len() * 4;
+    push_u16(&mut out, 3);
+    push_u16(&mut out, coverages.len() as u16);
+    push_u16(&mut out, lookups.len() as u16);
+    for glyphs in coverages {
+        push_u16(&mut out, (header + tables.len()) as u16);
+        tables.extend(coverage(glyphs));
+    }
+    for &(sequence_index, lookup_index) in lookups {
+        push_u16(&mut out, sequence_index);
+        push_u16(&mut out, lookup_index);
+    }
+    out.extend(tables);
+    out
+}
+
+/// A format 2 pair adjustment subtable changing the advance of each of
+/// `first` by `x_advance` when followed by any of `second`.  Both lists
+/// must be sorted.
+#[cfg(feature = "layout")]
+pub fn class_pair_adjustment(first: &[u16], second: &[u16], x_advance: i16) -> Vec<u8> {
+    let class_def = |glyphs: &[u16]| {
+        let mut out = Vec::new();
+        push_u16(&mut out, 2);
+        push_u16(&mut out, glyphs.len() as u16);
+        for &glyph in glyphs {
+            push_u16(&mut out, glyph);
+            push_u16(&mut out, glyph);
+            push_u16(&mut out, 1);
+        }
+        out
+    };
+    let (coverage, class_def1, class_def2) = (coverage(first), class_def(first), class_def(second));
+
+    let mut out = Vec::new();
+    push_u16(&mut out, 2);
+    push_u16(&mut out, 24);
+    push_u16(&mut out, 4);
+    push_u16(&mut out, 0);
+    push_u16(&mut out, (24 + coverage.len()) as u16);
+    push_u16(&mut out, (24 + coverage.len() + class_def1.len()) as u16);
+    push_u16(&mut out, 2);
+    push_u16(&mut out, 2);
+    for &value in &[0, 0, 0, x_advance] {
+        push_i16(&mut out, value);
+    }
+    out.extend(coverage);
+    out.extend(class_def1);
+    out.extend(class_def2);
+    out
+}
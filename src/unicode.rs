@@ -0,0 +1,304 @@
+//! Character properties which shaping and font fallback need, for the
+//! characters they treat specially, and the scripts of characters, which
+//! split text into runs that are each shaped with the lookups of their
+//! script.
+
+use std::cmp::Ordering;
+use std::ops::Range;
+
+use primitives::Tag;
+use tags::scripts;
+
+/// Whether `character` continues the grapheme before it: combining marks,
+/// the zero width joiner, variation selectors, emoji skin tone modifiers
+/// and tags.
+pub(crate) fn extends_grapheme(character: char) -> bool {
+    matches!(character as u32,
+             0x0300..=0x036F | 0x0483..=0x0489 | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF |
+             0x20D0..=0x20FF | 0xFE20..=0xFE2F | 0x200D | 0xFE00..=0xFE0F | 0xE0100..=0xE01EF |
+             0x1F3FB..=0x1F3FF | 0xE0020..=0xE007F)
+}
+
+pub(crate) fn is_variation_selector(character: char) -> bool {
+    matches!(character as u32, 0xFE00..=0xFE0F | 0xE0100..=0xE01EF)
+}
+
+pub(crate) fn is_default_ignorable(character: char) -> bool {
+    matches!(character as u32, 0x200B..=0x200F | 0x2060 | 0xFEFF) || is_variation_selector(character)
+}
+
+/// A writing system, as far as shaping needs to tell them apart.
+/// Characters shared between scripts, such as digits and punctuation, are
+/// `Common`, and combining marks which take the script of the character
+/// before them are `Inherited`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Script {
+    Common,
+    Inherited,
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+    Hebrew,
+    Arabic,
+    Syriac,
+    Thaana,
+    Devanagari,
+    Bengali,
+    Gurmukhi,
+    Gujarati,
+    Oriya,
+    Tamil,
+    Telugu,
+    Kannada,
+    Malayalam,
+    Sinhala,
+    Thai,
+    Lao,
+    Tibetan,
+    Myanmar,
+    Georgian,
+    Hangul,
+    Ethiopic,
+    Khmer,
+    Mongolian,
+    Hiragana,
+    Katakana,
+    Han,
+}
+
+impl Script {
+    /// The OpenType script tags of the script, in the order a font's script
+    /// list should be searched.  The Indic scripts and Myanmar list their
+    /// second version tag first, whose shaping model fonts made since 2005
+    /// expect.  `Common` and `Inherited` have no tags.
+    pub fn tags(self) -> &'static [Tag] {
+        SCRIPT_TAGS.iter()
+            .find(|&&(script, _)| script == self)
+            .map_or(&[], |&(_, tags)| tags)
+    }
+
+    /// Whether the script takes the script of the text around it.
+    pub fn is_shared(self) -> bool {
+        self == Script::Common || self == Script::Inherited
+    }
+}
+
+/// The OpenType tags of each script, as `Script::tags` gives them.  Both
+/// kana share a tag.
+const SCRIPT_TAGS: &[(Script, &[Tag])] = &[
+    (Script::Latin, &[scripts::LATIN]),
+    (Script::Greek, &[scripts::GREEK]),
+    (Script::Cyrillic, &[scripts::CYRILLIC]),
+    (Script::Armenian, &[scripts::ARMENIAN]),
+    (Script::Hebrew, &[scripts::HEBREW]),
+    (Script::Arabic, &[scripts::ARABIC]),
+    (Script::Syriac, &[scripts::SYRIAC]),
+    (Script::Thaana, &[scripts::THAANA]),
+    (Script::Devanagari, &[scripts::DEVANAGARI_V2, scripts::DEVANAGARI]),
+    (Script::Bengali, &[scripts::BENGALI_V2, scripts::BENGALI]),
+    (Script::Gurmukhi, &[scripts::GURMUKHI_V2, scripts::GURMUKHI]),
+    (Script::Gujarati, &[scripts::GUJARATI_V2, scripts::GUJARATI]),
+    (Script::Oriya, &[scripts::ORIYA_V2, scripts::ORIYA]),
+    (Script::Tamil, &[scripts::TAMIL_V2, scripts::TAMIL]),
+    (Script::Telugu, &[scripts::TELUGU_V2, scripts::TELUGU]),
+    (Script::Kannada, &[scripts::KANNADA_V2, scripts::KANNADA]),
+    (Script::Malayalam, &[scripts::MALAYALAM_V2, scripts::MALAYALAM]),
+    (Script::Sinhala, &[scripts::SINHALA]),
+    (Script::Thai, &[scripts::THAI]),
+    (Script::Lao, &[scripts::LAO]),
+    (Script::Tibetan, &[scripts::TIBETAN]),
+    (Script::Myanmar, &[scripts::MYANMAR_V2, scripts::MYANMAR]),
+    (Script::Georgian, &[scripts::GEORGIAN]),
+    (Script::Hangul, &[scripts::HANGUL]),
+    (Script::Ethiopic, &[scripts::ETHIOPIC]),
+    (Script::Khmer, &[scripts::KHMER]),
+    (Script::Mongolian, &[scripts::MONGOLIAN]),
+    (Script::Hiragana, &[scripts::KANA]),
+    (Script::Katakana, &[scripts::KANA]),
+    (Script::Han, &[scripts::HAN]),
+];
+
+/// The first and last character of each range of characters of one script,
+/// sorted and without overlaps.  Characters outside every range are
+/// `Common`.  The ranges follow the Unicode blocks of each script, as the
+/// `OS/2` Unicode ranges do, rather than the exact `Scripts.txt` property,
+/// so a few shared signs inside a block, such as the Devanagari danda,
+/// take the script of the block.
+const SCRIPT_RANGES: &[(u32, u32, Script)] = &[
+    (0x0041, 0x005A, Script::Latin),
+    (0x0061, 0x007A, Script::Latin),
+    (0x00AA, 0x00AA, Script::Latin),
+    (0x00BA, 0x00BA, Script::Latin),
+    (0x00C0, 0x00D6, Script::Latin),
+    (0x00D8, 0x00F6, Script::Latin),
+    (0x00F8, 0x02AF, Script::Latin),
+    (0x0300, 0x036F, Script::Inherited),
+    (0x0370, 0x03FF, Script::Greek),
+    (0x0400, 0x052F, Script::Cyrillic),
+    (0x0531, 0x058F, Script::Armenian),
+    (0x0591, 0x05FF, Script::Hebrew),
+    (0x0600, 0x06FF, Script::Arabic),
+    (0x0700, 0x074F, Script::Syriac),
+    (0x0750, 0x077F, Script::Arabic),
+    (0x0780, 0x07BF, Script::Thaana),
+    (0x08A0, 0x08FF, Script::Arabic),
+    (0x0900, 0x097F, Script::Devanagari),
+    (0x0980, 0x09FF, Script::Bengali),
+    (0x0A00, 0x0A7F, Script::Gurmukhi),
+    (0x0A80, 0x0AFF, Script::Gujarati),
+    (0x0B00, 0x0B7F, Script::Oriya),
+    (0x0B80, 0x0BFF, Script::Tamil),
+    (0x0C00, 0x0C7F, Script::Telugu),
+    (0x0C80, 0x0CFF, Script::Kannada),
+    (0x0D00, 0x0D7F, Script::Malayalam),
+    (0x0D80, 0x0DFF, Script::Sinhala),
+    (0x0E00, 0x0E7F, Script::Thai),
+    (0x0E80, 0x0EFF, Script::Lao),
+    (0x0F00, 0x0FFF, Script::Tibetan),
+    (0x1000, 0x109F, Script::Myanmar),
+    (0x10A0, 0x10FF, Script::Georgian),
+    (0x1100, 0x11FF, Script::Hangul),
+    (0x1200, 0x139F, Script::Ethiopic),
+    (0x1780, 0x17FF, Script::Khmer),
+    (0x1800, 0x18AF, Script::Mongolian),
+    (0x1AB0, 0x1AFF, Script::Inherited),
+    (0x1C80, 0x1C8F, Script::Cyrillic),
+    (0x1D00, 0x1D7F, Script::Latin),
+    (0x1DC0, 0x1DFF, Script::Inherited),
+    (0x1E00, 0x1EFF, Script::Latin),
+    (0x1F00, 0x1FFF, Script::Greek),
+    (0x200C, 0x200D, Script::Inherited),
+    (0x20D0, 0x20FF, Script::Inherited),
+    (0x2C60, 0x2C7F, Script::Latin),
+    (0x2D00, 0x2D2F, Script::Georgian),
+    (0x2DE0, 0x2DFF, Script::Cyrillic),
+    (0x2E80, 0x2FDF, Script::Han),
+    (0x3005, 0x3005, Script::Han),
+    (0x3007, 0x3007, Script::Han),
+    (0x3021, 0x3029, Script::Han),
+    (0x3038, 0x303B, Script::Han),
+    (0x3041, 0x3096, Script::Hiragana),
+    (0x3099, 0x309A, Script::Inherited),
+    (0x309D, 0x309F, Script::Hiragana),
+    (0x30A1, 0x30FA, Script::Katakana),
+    (0x30FD, 0x30FF, Script::Katakana),
+    (0x3131, 0x318F, Script::Hangul),
+    (0x31F0, 0x31FF, Script::Katakana),
+    (0x3400, 0x4DBF, Script::Han),
+    (0x4E00, 0x9FFF, Script::Han),
+    (0xA640, 0xA69F, Script::Cyrillic),
+    (0xA720, 0xA7FF, Script::Latin),
+    (0xA8E0, 0xA8FF, Script::Devanagari),
+    (0xA960, 0xA97F, Script::Hangul),
+    (0xAB30, 0xAB6F, Script::Latin),
+    (0xAC00, 0xD7FF, Script::Hangul),
+    (0xF900, 0xFAFF, Script::Han),
+    (0xFB00, 0xFB06, Script::Latin),
+    (0xFB13, 0xFB17, Script::Armenian),
+    (0xFB1D, 0xFB4F, Script::Hebrew),
+    (0xFB50, 0xFDFF, Script::Arabic),
+    (0xFE00, 0xFE0F, Script::Inherited),
+    (0xFE20, 0xFE2F, Script::Inherited),
+    (0xFE70, 0xFEFC, Script::Arabic),
+    (0xFF21, 0xFF3A, Script::Latin),
+    (0xFF41, 0xFF5A, Script::Latin),
+    (0xFF66, 0xFF9D, Script::Katakana),
+    (0xFFA0, 0xFFDC, Script::Hangul),
+    (0x20000, 0x3134F, Script::Han),
+    (0xE0100, 0xE01EF, Script::Inherited),
+];
+
+/// The script of `character`.
+pub fn script_of(character: char) -> Script {
+    let code = character as u32;
+    match SCRIPT_RANGES.binary_search_by(|&(first, last, _)| {
+        if last < code {
+            Ordering::Less
+        } else if first > code {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }) {
+        Ok(index) => SCRIPT_RANGES[index].2,
+        Err(_) => Script::Common,
+    }
+}
+
+/// A run of text in one script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptRun {
+    /// The byte range of the run in the text.
+    pub range: Range<usize>,
+    /// The script of the run, which is `Common` only if every character of
+    /// the run is shared.
+    pub script: Script,
+}
+
+/// Split `text` into runs of one script each.  Shared characters join the
+/// run before them, or the run after them at the start of the text, so
+/// spaces and punctuation never start a run of their own.
+pub fn script_runs(text: &str) -> ScriptRuns<'_> {
+    ScriptRuns { text, pos: 0 }
+}
+
+/// The script runs of a text, in order.
+#[derive(Debug, Clone)]
+pub struct ScriptRuns<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Iterator for ScriptRuns<'a> {
+    type Item = ScriptRun;
+
+    fn next(&mut self) -> Option<ScriptRun> {
+        if self.pos >= self.text.len() {
+            return None
+        }
+
+        let start = self.pos;
+        let mut script = Script::Common;
+        self.pos = self.text.len();
+        for (index, character) in self.text[start..].char_indices() {
+            let character_script = script_of(character);
+            if character_script.is_shared() || character_script == script {
+                continue
+            }
+            if script != Script::Common {
+                self.pos = start + index;
+                break
+            }
+            script = character_script;
+        }
+        Some(ScriptRun { range: start..self.pos, script })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_itemization() {
+        assert_eq!(script_of('a'), Script::Latin);
+        assert_eq!(script_of('\u{3B1}'), Script::Greek);
+        assert_eq!(script_of('\u{915}'), Script::Devanagari);
+        assert_eq!(script_of('\u{4E2D}'), Script::Han);
+        assert_eq!(script_of('\u{301}'), Script::Inherited);
+        assert_eq!(script_of('1'), Script::Common);
+        assert_eq!(Script::Devanagari.tags(), &[Tag::new(*b"dev2"), Tag::new(*b"deva")]);
+
+        let runs = |text: &str| script_runs(text).map(|run| (run.range, run.script)).collect::<Vec<_>>();
+        // The leading quote joins the Latin run, and the space and the
+        // combining mark join the run before them.
+        assert_eq!(runs("\"ab \u{3B1}\u{301}\u{3B2}, \u{43F}"), vec![
+            (0..4, Script::Latin),
+            (4..12, Script::Greek),
+            (12..14, Script::Cyrillic),
+        ]);
+        assert_eq!(runs("12 + 3"), vec![(0..6, Script::Common)]);
+        assert_eq!(runs(""), vec![]);
+    }
+}
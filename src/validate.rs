@@ -0,0 +1,369 @@
+//! This module lints the layout of a font file: how its tables are placed
+//! within the file, rather than what they contain.  Fonts with these
+//! problems are usually still readable, so each is reported as a warning
+//! rather than an error.
+//!
+//! It also compares the Unicode ranges OS/2 declares with the characters
+//! `cmap` maps, and the style OS/2 and `STAT` declare for a variable font
+//! with the default location of its axes, since these often disagree.
+
+use decode::{EncodeSize, StaticEncodeSize};
+use font::Font;
+use primitives::Tag;
+use table::head::Head;
+use table::hhea::Hhea;
+use table::maxp::Maxp;
+use table::os2::{Os2, UnicodeBlockBit};
+use table::cmap::Cmap;
+#[cfg(feature = "variations")]
+use family::width_class;
+#[cfg(feature = "variations")]
+use table::{fvar::Fvar, stat::Stat};
+use error::Result;
+
+/// A problem with the placement of tables in a font file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StructureWarning {
+    /// The byte ranges of two tables overlap.
+    Overlap { first: Tag, second: Tag },
+    /// The table does not start on a 4-byte boundary.
+    Misaligned { tag: Tag },
+    /// The table extends past the end of the file.
+    OutOfBounds { tag: Tag },
+    /// The bytes between the end of the table and the next 4-byte
+    /// boundary are not zero.
+    NonZeroPadding { tag: Tag },
+    /// The directory length of the table differs from the length its
+    /// contents require.
+    LengthMismatch { tag: Tag, declared: u32, expected: u32 },
+}
+
+/// A disagreement between a `ulUnicodeRange` bit of OS/2 and `cmap`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RangeMismatch {
+    /// The bit is set, but no character of its blocks is mapped.
+    DeclaredNotCovered { bit: UnicodeBlockBit },
+    /// The bit is not set, but `mapped` characters of its blocks are,
+    /// which is at least a quarter of the blocks.
+    CoveredNotDeclared { bit: UnicodeBlockBit, mapped: usize },
+}
+
+/// A disagreement between the default location of the axes of a variable
+/// font and the style OS/2 or `STAT` declares for it.
+#[cfg(feature = "variations")]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AxisMismatch {
+    /// `usWeightClass` differs from the default of the `wght` axis.
+    WeightClass { os2: u16, default: f32 },
+    /// `usWidthClass` is not the class nearest the default of the `wdth`
+    /// axis.
+    WidthClass { os2: u16, default: f32 },
+    /// The italic bit of `fsSelection` disagrees with the default of the
+    /// `ital` axis.
+    Italic { os2: bool, default: f32 },
+    /// `STAT` has no design axis for an axis of `fvar`.
+    MissingStatAxis { tag: Tag },
+    /// No axis value of `STAT` names the default of an axis of `fvar`.
+    UnnamedDefault { tag: Tag, default: f32 },
+}
+
+/// The number of assigned `ulUnicodeRange` bits.
+const UNICODE_RANGE_BITS: u8 = 123;
+
+impl<'fnt> Font<'fnt> {
+    /// Compare the Unicode ranges of OS/2 with the preferred Unicode
+    /// subtable of `cmap`, in bit order.  This is approximate: a font
+    /// counts as covering a block when it maps a quarter of its code
+    /// points, or any code point outside the BMP for `NON_PLANE_0`.
+    /// Fonts without OS/2 or a Unicode subtable have no mismatches.
+    pub fn unicode_range_mismatches(&self) -> Result<Vec<RangeMismatch>> {
+        let os2 = match self.optional_table::<Os2>()? {
+            Some(os2) => os2,
+            None => return Ok(Vec::new()),
+        };
+        let subtable = match self.table::<Cmap>()?.unicode_subtable()? {
+            Some(subtable) => subtable,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut mapped = [0usize; UNICODE_RANGE_BITS as usize];
+        for (code, glyph) in subtable.mappings() {
+            if glyph.0 == 0 {
+                continue
+            }
+            if code > 0xFFFF {
+                mapped[UnicodeBlockBit::NON_PLANE_0.0 as usize] += 1;
+            }
+            if let Some(bit) = ::std::char::from_u32(code).and_then(UnicodeBlockBit::of) {
+                mapped[bit.0 as usize] += 1;
+            }
+        }
+
+        let declared = os2.unicode_ranges();
+        let mut mismatches = Vec::new();
+        for bit in (0..UNICODE_RANGE_BITS).map(UnicodeBlockBit) {
+            let mapped = mapped[bit.0 as usize];
+            let size = match bit {
+                UnicodeBlockBit::NON_PLANE_0 => 1,
+                _ => bit.ranges().map(|(start, end)| (end - start + 1) as usize).sum(),
+            };
+
+            if declared.supports_block(bit) && mapped == 0 {
+                mismatches.push(RangeMismatch::DeclaredNotCovered { bit });
+            } else if !declared.supports_block(bit) && mapped > 0 && mapped * 4 >= size {
+                mismatches.push(RangeMismatch::CoveredNotDeclared { bit, mapped });
+            }
+        }
+
+        Ok(mismatches)
+    }
+
+    /// Compare the defaults of the `wght`, `wdth` and `ital` axes of `fvar`
+    /// with the weight, width and italic bit of OS/2, and check that
+    /// `STAT` has a design axis for each axis of `fvar` and names its
+    /// default.  Mismatches are returned in `fvar` axis order.  Fonts
+    /// without `fvar` have none, and the checks of a table the font
+    /// lacks are skipped.
+    #[cfg(feature = "variations")]
+    pub fn axis_mismatches(&self) -> Result<Vec<AxisMismatch>> {
+        let fvar = match self.optional_table::<Fvar>()? {
+            Some(fvar) => fvar,
+            None => return Ok(Vec::new()),
+        };
+        let os2 = self.optional_table::<Os2>()?;
+        let stat = self.optional_table::<Stat>()?;
+        let stat_axes = stat.as_ref().map(|stat| stat.design_axes().map(|axis| axis.axis_tag).collect::<Vec<_>>());
+
+        let mut mismatches = Vec::new();
+        for axis in fvar.axes() {
+            let (tag, default) = (axis.axis_tag, f32::from(axis.default_value));
+            if let Some(os2) = os2 {
+                match &tag.0 {
+                    b"wght" if os2.us_weight_class != default.round().clamp(1.0, 1000.0) as u16 => {
+                        mismatches.push(AxisMismatch::WeightClass { os2: os2.us_weight_class, default });
+                    },
+                    b"wdth" if os2.us_width_class != width_class(default) => {
+                        mismatches.push(AxisMismatch::WidthClass { os2: os2.us_width_class, default });
+                    },
+                    b"ital" if os2.is_italic() != (default >= 0.5) => {
+                        mismatches.push(AxisMismatch::Italic { os2: os2.is_italic(), default });
+                    },
+                    _ => {},
+                }
+            }
+
+            if let (Some(stat), Some(stat_axes)) = (&stat, &stat_axes) {
+                match stat_axes.iter().position(|&stat_axis| stat_axis == tag) {
+                    None => mismatches.push(AxisMismatch::MissingStatAxis { tag }),
+                    Some(index) => {
+                        if !stat.axis_values().iter().any(|value| value.matches(index as u16, default)) {
+                            mismatches.push(AxisMismatch::UnnamedDefault { tag, default });
+                        }
+                    },
+                }
+            }
+        }
+
+        Ok(mismatches)
+    }
+
+    /// Check the table directory against the tables it describes.
+    /// Warnings are returned in directory order, with overlaps last.
+    pub fn structure_warnings(&self) -> Vec<StructureWarning> {
+        let data = self.data();
+        let mut warnings = Vec::new();
+        let mut ranges = Vec::new();
+
+        for record in self.table_directory().tables() {
+            let tag = record.tag;
+            let start = record.offset as usize;
+            let end = start + record.length as usize;
+            if start & 3 != 0 {
+                warnings.push(StructureWarning::Misaligned { tag });
+            }
+
+            if end > data.len() {
+                warnings.push(StructureWarning::OutOfBounds { tag });
+                continue
+            }
+            ranges.push((start, end, tag));
+
+            let padding_end = ((end + 3) & !3).min(data.len());
+            if data[end..padding_end].iter().any(|&byte| byte != 0) {
+                warnings.push(StructureWarning::NonZeroPadding { tag });
+            }
+
+            if let Some(expected) = self.expected_length(tag) {
+                if expected != record.length {
+                    warnings.push(StructureWarning::LengthMismatch {
+                        tag,
+                        declared: record.length,
+                        expected,
+                    });
+                }
+            }
+        }
+
+        ranges.sort();
+        for (index, &(_, end, first)) in ranges.iter().enumerate() {
+            for &(_, _, second) in ranges[index + 1..].iter().take_while(|range| range.0 < end) {
+                warnings.push(StructureWarning::Overlap { first, second });
+            }
+        }
+
+        warnings
+    }
+
+    /// The length the contents of `tag` require, for tables whose length
+    /// is determined by their header or by other tables.  `None` when the
+    /// length is not known or the tables it depends on cannot be read.
+    fn expected_length(&self, tag: Tag) -> Option<u32> {
+        let length = match &tag.0 {
+            b"head" => Head::size(),
+            b"hhea" => Hhea::size(),
+            b"maxp" => self.table::<Maxp>().ok()?.encode_size(),
+            b"OS/2" => match self.table::<Os2>().ok()?.version {
+                0 => 78,
+                1 => 86,
+                2..=4 => 96,
+                _ => 100,
+            },
+            b"hmtx" => {
+                let long = self.table::<Hhea>().ok()?.number_of_h_metrics as usize;
+                let glyphs = self.table::<Maxp>().ok()?.get_num_glyphs() as usize;
+                long * 4 + glyphs.saturating_sub(long) * 2
+            },
+            b"loca" => {
+                let long = self.table::<Head>().ok()?.index_to_loc_format != 0;
+                let glyphs = self.table::<Maxp>().ok()?.get_num_glyphs() as usize;
+                (glyphs + 1) * if long { 4 } else { 2 }
+            },
+            b"glyf" => {
+                let loca = self.loca().ok()?;
+                loca.offset(loca.num_glyphs())?
+            },
+            _ => return None,
+        };
+
+        Some(length as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+    use table::TaggedTable;
+    use test_fonts;
+
+    #[test]
+    fn structure_warnings() {
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        assert_eq!(font.structure_warnings(), Vec::new());
+
+        let data = test_fonts::minimal_font(false).build();
+        let font = Font::decode(&data).unwrap();
+        assert_eq!(font.structure_warnings(), Vec::new());
+        let maxp = font.table_record(Maxp::tag()).unwrap();
+        let record_of = |tag: Tag| {
+            12 + 16 * font.table_directory().tables().into_iter().position(|record| record.tag == tag).unwrap()
+        };
+        let (hhea_record, maxp_record) = (record_of(Hhea::tag()), record_of(Maxp::tag()));
+
+        // `maxp` version 0.5 is 6 bytes, followed by two bytes of padding.
+        let mut broken = data.clone();
+        broken[maxp.offset as usize + 7] = 1;
+        let font = Font::decode(&broken).unwrap();
+        assert_eq!(font.structure_warnings(), vec![StructureWarning::NonZeroPadding { tag: Maxp::tag() }]);
+
+        // Claim `hhea` is longer, so it runs into the following table.
+        let mut broken = data.clone();
+        broken[hhea_record + 12..hhea_record + 16].copy_from_slice(&40u32.to_be_bytes());
+        let font = Font::decode(&broken).unwrap();
+        let warnings = font.structure_warnings();
+        assert!(warnings.contains(&StructureWarning::LengthMismatch {
+            tag: Hhea::tag(),
+            declared: 40,
+            expected: 36,
+        }));
+        assert!(warnings.iter().any(|warning| match *warning {
+            StructureWarning::Overlap { first, .. } => first == Hhea::tag(),
+            _ => false,
+        }));
+
+        // Move `maxp` off its boundary and past the end of the file.
+        let mut broken = data.clone();
+        broken[maxp_record + 8..maxp_record + 12].copy_from_slice(&(maxp.offset + 1).to_be_bytes());
+        let font = Font::decode(&broken).unwrap();
+        assert!(font.structure_warnings().contains(&StructureWarning::Misaligned { tag: Maxp::tag() }));
+
+        let offset = data.len() as u32 - 4;
+        broken[maxp_record + 8..maxp_record + 12].copy_from_slice(&offset.to_be_bytes());
+        let font = Font::decode(&broken).unwrap();
+        assert!(font.structure_warnings().contains(&StructureWarning::OutOfBounds { tag: Maxp::tag() }));
+    }
+
+    #[test]
+    fn unicode_range_mismatches() {
+        let data = open_file!("data/DroidSerif.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        // Greek and Cyrillic are declared, but this version of the font
+        // only maps Latin.
+        let mismatches = font.unicode_range_mismatches().unwrap();
+        assert!(mismatches.contains(&RangeMismatch::DeclaredNotCovered { bit: UnicodeBlockBit::GREEK_AND_COPTIC }));
+        assert!(mismatches.contains(&RangeMismatch::DeclaredNotCovered { bit: UnicodeBlockBit::CYRILLIC }));
+        assert!(mismatches.iter().all(|mismatch| match *mismatch {
+            RangeMismatch::DeclaredNotCovered { bit } => bit != UnicodeBlockBit::BASIC_LATIN,
+            RangeMismatch::CoveredNotDeclared { .. } => false,
+        }));
+
+        // Clear the Basic Latin bit of `ulUnicodeRange1`.
+        let os2 = font.table_record(Os2::tag()).unwrap().offset as usize;
+        let mut data = data.clone();
+        data[os2 + 45] &= !1;
+        let font = Font::decode(&data).unwrap();
+        let mismatches = font.unicode_range_mismatches().unwrap();
+        assert!(mismatches.iter().any(|mismatch| match *mismatch {
+            RangeMismatch::CoveredNotDeclared { bit, mapped } => bit == UnicodeBlockBit::BASIC_LATIN && mapped > 90,
+            _ => false,
+        }));
+
+        let data = test_fonts::minimal_font(false).build();
+        assert_eq!(Font::decode(&data).unwrap().unicode_range_mismatches(), Ok(Vec::new()));
+    }
+
+    #[cfg(feature = "variations")]
+    #[test]
+    fn axis_mismatches() {
+        use test_fonts::AxisValueRecord;
+
+        let stat = test_fonts::stat(&[(b"wght", 0), (b"wdth", 1)], &[
+            AxisValueRecord::Single(0, 400.0, 0, 300),
+            AxisValueRecord::Range(1, 100.0, 90.0, 110.0, 301),
+        ], 2);
+        let font = |axes: &[(&[u8; 4], f32, f32, f32)], fs_selection| test_fonts::minimal_font(false)
+            .table(b"OS/2", test_fonts::os2((800, -200, 0), (1000, 200), fs_selection))
+            .table(b"fvar", test_fonts::fvar(axes))
+            .table(b"STAT", stat.clone())
+            .build();
+
+        // The defaults match the regular weight and normal width of OS/2.
+        let data = font(&[(b"wght", 100.0, 400.0, 900.0), (b"wdth", 75.0, 100.0, 100.0)], 0);
+        assert_eq!(Font::decode(&data).unwrap().axis_mismatches(), Ok(Vec::new()));
+
+        let data = font(&[(b"wght", 100.0, 700.0, 900.0), (b"wdth", 75.0, 75.0, 100.0), (b"ital", 0.0, 1.0, 1.0)], 0);
+        let font = Font::decode(&data).unwrap();
+        assert_eq!(font.axis_mismatches(), Ok(vec![
+            AxisMismatch::WeightClass { os2: 400, default: 700.0 },
+            AxisMismatch::UnnamedDefault { tag: Tag::new(*b"wght"), default: 700.0 },
+            AxisMismatch::WidthClass { os2: 5, default: 75.0 },
+            AxisMismatch::UnnamedDefault { tag: Tag::new(*b"wdth"), default: 75.0 },
+            AxisMismatch::Italic { os2: false, default: 1.0 },
+            AxisMismatch::MissingStatAxis { tag: Tag::new(*b"ital") },
+        ]));
+
+        let data = test_fonts::minimal_font(false).build();
+        assert_eq!(Font::decode(&data).unwrap().axis_mismatches(), Ok(Vec::new()));
+    }
+}
@@ -0,0 +1,313 @@
+//! This module collects diagnostics for constructs which a font may
+//! contain but which fontkit skips or tolerates, such as `cmap` formats it
+//! cannot read.  A font with these problems is still usable, so a
+//! permissive consumer can render it and report the warnings alongside.
+//!
+//! The decoders do not report what they tolerate.  Instead, the font is
+//! checked again after it is parsed, for the problems `DecodeWarning`
+//! lists; others, such as a malformed glyph which is only read when it is
+//! drawn, are not found.
+
+use font::{corrected_num_glyphs, Font, ParseMode, HEAD_RESERVED_FLAGS, FS_SELECTION_RESERVED};
+use primitives::Tag;
+use table::TaggedTable;
+use table::head::Head;
+use table::hhea::Hhea;
+use table::hmtx::Hmtx;
+use table::loca::Loca;
+use table::maxp::Maxp;
+use table::os2::Os2;
+use table::cmap::Cmap;
+#[cfg(feature = "layout")]
+use table::layout::{Gsub, Gpos, Lookup};
+use error::{Error, Result};
+
+/// A construct which was skipped or tolerated while decoding a font.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DecodeWarning {
+    /// A `cmap` subtable in a format which cannot be read, and which is
+    /// never chosen for mapping characters.
+    UnsupportedCmapFormat { platform_id: u16, encoding_id: u16, format: u16 },
+    /// The Unicode `cmap` subtable is not sorted, so lookups in it are
+    /// linear.
+    UnsortedCmap,
+    /// A lookup of a type the table does not define, which shaping
+    /// ignores.
+    UnknownLookupType { tag: Tag, lookup_index: u16, lookup_type: u16 },
+    /// Reserved bits of a flags field are set.
+    ReservedBits { tag: Tag, field: &'static str, bits: u16 },
+    /// The glyph count `maxp` declares does not fit `tag`, which is `loca`
+    /// or `hmtx`, and which has room for `actual` glyphs.  A count of zero
+    /// does not fit `loca` either.  `hmtx` is checked against the count
+    /// as `loca` corrects it.
+    GlyphCountMismatch { tag: Tag, declared: u16, actual: u16 },
+    /// `head.indexToLocFormat` declares the format `declared`, but `loca`
+    /// can only be read in the format `actual`, which a permissive font
+    /// reads it in instead.
+    LocaFormatMismatch { declared: i16, actual: i16 },
+    /// A table which could not be decoded at all, and was not checked
+    /// further.
+    InvalidTable { tag: Tag, error: Error },
+}
+
+/// The warnings collected by `Font::parse_with_warnings`, in the order
+/// they were found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecodeWarnings {
+    warnings: Vec<DecodeWarning>,
+}
+
+impl DecodeWarnings {
+    pub fn new() -> DecodeWarnings {
+        DecodeWarnings::default()
+    }
+
+    pub fn push(&mut self, warning: DecodeWarning) {
+        self.warnings.push(warning);
+    }
+
+    pub fn warnings(&self) -> &[DecodeWarning] {
+        &self.warnings
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.warnings.len()
+    }
+
+    /// Record the error of decoding table `tag` as a warning, and return
+    /// the table if it was decoded.
+    fn table<T>(&mut self, tag: Tag, table: Result<Option<T>>) -> Option<T> {
+        table.unwrap_or_else(|error| {
+            self.push(DecodeWarning::InvalidTable { tag, error });
+            None
+        })
+    }
+}
+
+impl IntoIterator for DecodeWarnings {
+    type Item = DecodeWarning;
+    type IntoIter = ::std::vec::IntoIter<DecodeWarning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.warnings.into_iter()
+    }
+}
+
+impl<'fnt> Font<'fnt> {
+    /// Decode the font in `buffer` as `Font::parse` does, then check the
+    /// font for the problems `DecodeWarning` lists and add a warning to
+    /// `warnings` for each one found.  Only `head`, `maxp`, `loca`,
+    /// `hhea`, `hmtx`, `OS/2`, `cmap`, and the lookup types of `GSUB` and
+    /// `GPOS` are checked, so a font without warnings may still have
+    /// constructs which are tolerated elsewhere.  Tables which cannot be
+    /// decoded are warnings rather than errors, so this fails only where
+    /// `Font::parse` does.
+    pub fn parse_with_warnings(buffer: &'fnt [u8], mode: ParseMode, warnings: &mut DecodeWarnings) -> Result<Font<'fnt>> {
+        let font = Font::parse(buffer, mode)?;
+        font.collect_warnings(warnings);
+        Ok(font)
+    }
+
+    fn collect_warnings(&self, warnings: &mut DecodeWarnings) {
+        if let Some(head) = warnings.table(Head::tag(), self.table::<Head>().map(Some)) {
+            reserved_bits(warnings, Head::tag(), "flags", head.flags & HEAD_RESERVED_FLAGS);
+            let declared = head.index_to_loc_format;
+            if let Ok(long) = self.loca_is_long() {
+                if long != (declared != 0) {
+                    warnings.push(DecodeWarning::LocaFormatMismatch { declared, actual: long as i16 });
+                }
+            }
+        }
+
+        self.glyph_count_warnings(warnings);
+
+        if let Some(os2) = warnings.table(Os2::tag(), self.optional_table::<Os2>()) {
+            reserved_bits(warnings, Os2::tag(), "fsSelection", os2.fs_selection & FS_SELECTION_RESERVED);
+        }
+
+        if let Some(cmap) = warnings.table(Cmap::tag(), self.optional_table::<Cmap>()) {
+            cmap_warnings(warnings, &cmap);
+        }
+
+        #[cfg(feature = "layout")]
+        {
+            if let Some(gsub) = warnings.table(Gsub::tag(), self.optional_table::<Gsub>()) {
+                let lookups = gsub.lookup_count().and_then(|count| (0..count).map(|index| gsub.lookup(index)).collect());
+                lookup_warnings(warnings, Gsub::tag(), lookups, 8);
+            }
+
+            if let Some(gpos) = warnings.table(Gpos::tag(), self.optional_table::<Gpos>()) {
+                let lookups = gpos.lookup_count().and_then(|count| (0..count).map(|index| gpos.lookup(index)).collect());
+                lookup_warnings(warnings, Gpos::tag(), lookups, 9);
+            }
+        }
+    }
+
+    fn glyph_count_warnings(&self, warnings: &mut DecodeWarnings) {
+        let maxp = match warnings.table(Maxp::tag(), self.table::<Maxp>().map(Some)) {
+            Some(maxp) => maxp.get_num_glyphs(),
+            None => return,
+        };
+
+        let loca = self.loca_num_glyphs();
+        if let Some(actual) = loca.filter(|&loca| maxp == 0 || maxp > loca) {
+            warnings.push(DecodeWarning::GlyphCountMismatch { tag: Loca::tag(), declared: maxp, actual });
+        }
+
+        let declared = corrected_num_glyphs(Ok(maxp), loca).unwrap_or(maxp);
+        if let (Ok(hhea), Ok(hmtx)) = (self.table::<Hhea>(), self.table_data(Hmtx::tag())) {
+            let long = hhea.number_of_h_metrics as usize;
+            let actual = (long + hmtx.len().saturating_sub(long * 4) / 2).min(0xFFFF) as u16;
+            if actual < declared {
+                warnings.push(DecodeWarning::GlyphCountMismatch { tag: Hmtx::tag(), declared, actual });
+            }
+        }
+    }
+}
+
+fn reserved_bits(warnings: &mut DecodeWarnings, tag: Tag, field: &'static str, bits: u16) {
+    if bits != 0 {
+        warnings.push(DecodeWarning::ReservedBits { tag, field, bits });
+    }
+}
+
+fn cmap_warnings(warnings: &mut DecodeWarnings, cmap: &Cmap) {
+    for record in cmap.records() {
+        // Format 14 holds variation sequences rather than a mapping, and
+        // is read by `Cmap::variation_sequences`.
+        match cmap.subtable(&record) {
+            Ok(_) => {},
+            Err(Error::UnsupportedCmapFormat) => {
+                let format = cmap.subtable_format(&record).unwrap_or(0);
+                if format != 14 {
+                    warnings.push(DecodeWarning::UnsupportedCmapFormat {
+                        platform_id: record.platform_id,
+                        encoding_id: record.encoding_id,
+                        format,
+                    });
+                }
+            },
+            Err(error) => warnings.push(DecodeWarning::InvalidTable { tag: Cmap::tag(), error }),
+        }
+    }
+
+    if let Some(subtable) = warnings.table(Cmap::tag(), cmap.unicode_subtable()) {
+        if !subtable.is_sorted() {
+            warnings.push(DecodeWarning::UnsortedCmap);
+        }
+    }
+}
+
+/// Warn about each lookup of `lookups` whose type is not between one and
+/// `max_type`, after extension subtables are resolved.
+#[cfg(feature = "layout")]
+fn lookup_warnings(warnings: &mut DecodeWarnings, tag: Tag, lookups: Result<Vec<Lookup>>, max_type: u16) {
+    let lookups = match lookups {
+        Ok(lookups) => lookups,
+        Err(error) => return warnings.push(DecodeWarning::InvalidTable { tag, error }),
+    };
+
+    for (index, lookup) in lookups.iter().enumerate() {
+        if lookup.lookup_type == 0 || lookup.lookup_type > max_type {
+            warnings.push(DecodeWarning::UnknownLookupType {
+                tag,
+                lookup_index: index as u16,
+                lookup_type: lookup.lookup_type,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_fonts;
+
+    #[test]
+    fn collect_warnings() {
+        let mut warnings = DecodeWarnings::new();
+        let data = open_file!("data/DroidSerif.ttf");
+        Font::parse_with_warnings(&data, ParseMode::Strict, &mut warnings).expect("failed to read font");
+        assert_eq!(warnings.warnings(), &[]);
+
+        // A format 2 subtable for Shift-JIS ahead of a format 12 subtable
+        // for Unicode.
+        let mut cmap = vec![0, 0, 0, 2, 0, 3, 0, 2, 0, 0, 0, 20, 0, 3, 0, 10, 0, 0, 0, 26, 0, 2, 0, 6, 0, 0];
+        cmap.extend_from_slice(&test_fonts::cmap(&[('a', 1)])[12..]);
+        let mut head = test_fonts::head(1000, false);
+        head[16] |= 0x80;
+        let builder = test_fonts::minimal_font(false).table(b"cmap", cmap).table(b"head", head);
+        #[cfg(feature = "layout")]
+        let builder = builder.table(b"GSUB", test_fonts::layout_table(b"DFLT", &[(b"liga", &[0, 1])], &[
+            (4, test_fonts::ligature_substitution(&[1, 2], 3)),
+            (12, test_fonts::single_substitution(&[(1, 2)])),
+        ]));
+        let data = builder.build();
+
+        let mut warnings = DecodeWarnings::new();
+        let font = Font::parse_with_warnings(&data, ParseMode::Permissive, &mut warnings).unwrap();
+        assert_eq!(font.glyph_index('a'), Ok(Some(::primitives::GlyphId(1))));
+        assert_eq!(warnings.warnings()[..2], [
+            DecodeWarning::ReservedBits { tag: Head::tag(), field: "flags", bits: 0x8000 },
+            DecodeWarning::UnsupportedCmapFormat { platform_id: 3, encoding_id: 2, format: 2 },
+        ]);
+        #[cfg(feature = "layout")]
+        assert_eq!(warnings.warnings()[2..], [
+            DecodeWarning::UnknownLookupType { tag: Gsub::tag(), lookup_index: 1, lookup_type: 12 },
+        ]);
+
+        assert!(Font::parse_with_warnings(&data, ParseMode::Strict, &mut DecodeWarnings::new()).is_err());
+    }
+
+    #[test]
+    fn glyph_count_warnings() {
+        let data = test_fonts::minimal_font(false).table(b"maxp", test_fonts::maxp(0)).build();
+        let mut warnings = DecodeWarnings::new();
+        Font::parse_with_warnings(&data, ParseMode::Permissive, &mut warnings).unwrap();
+        assert_eq!(warnings.warnings(), &[DecodeWarning::GlyphCountMismatch { tag: Loca::tag(), declared: 0, actual: 3 }]);
+
+        // `loca` has room for the glyphs, but `hmtx` does not.
+        let (glyf, loca) = test_fonts::glyf_and_loca(&[Vec::new(), Vec::new(), Vec::new(), Vec::new()], false);
+        let data = test_fonts::minimal_font(false)
+            .table(b"maxp", test_fonts::maxp(4))
+            .table(b"glyf", glyf)
+            .table(b"loca", loca)
+            .build();
+        let mut warnings = DecodeWarnings::new();
+        let font = Font::parse_with_warnings(&data, ParseMode::Permissive, &mut warnings).unwrap();
+        assert_eq!(warnings.warnings(), &[DecodeWarning::GlyphCountMismatch { tag: Hmtx::tag(), declared: 4, actual: 3 }]);
+        assert_eq!(font.hmtx().map(|hmtx| hmtx.num_glyphs()), Ok(3));
+
+        let data = test_fonts::minimal_font(false).table(b"maxp", Vec::new()).build();
+        let mut warnings = DecodeWarnings::new();
+        Font::parse_with_warnings(&data, ParseMode::Permissive, &mut warnings).unwrap();
+        assert_eq!(warnings.warnings(), &[DecodeWarning::InvalidTable { tag: Maxp::tag(), error: Error::UnexpectedEof }]);
+    }
+
+    #[test]
+    fn wrong_loca_format() {
+        // Long offsets under a `head` which declares short ones, and the
+        // reverse.
+        for &long in &[true, false] {
+            let data = test_fonts::minimal_font(long).table(b"head", test_fonts::head(1000, !long)).build();
+            let mut warnings = DecodeWarnings::new();
+            let font = Font::parse_with_warnings(&data, ParseMode::Permissive, &mut warnings).unwrap();
+            assert_eq!(warnings.warnings(), &[DecodeWarning::LocaFormatMismatch { declared: !long as i16, actual: long as i16 }]);
+            let loca = font.loca().unwrap();
+            assert_eq!((loca.is_long(), loca.num_glyphs()), (long, 3));
+            assert_eq!(font.glyph_outline(::primitives::GlyphId(2)).map(|outline| outline.contours.len()), Ok(1));
+
+            let font = Font::parse(&data, ParseMode::Strict).unwrap();
+            assert_eq!(font.loca().map(|loca| loca.is_long()).ok(), if long { Some(false) } else { None });
+        }
+
+        let data = test_fonts::minimal_font(true).build();
+        let mut warnings = DecodeWarnings::new();
+        Font::parse_with_warnings(&data, ParseMode::Permissive, &mut warnings).unwrap();
+        assert!(warnings.is_empty());
+    }
+}
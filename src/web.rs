@@ -0,0 +1,135 @@
+//! This module describes a font for serving on the web: the descriptors of
+//! a CSS `@font-face` rule for it and the size of the file, so that tools
+//! which subset fonts and write style sheets need not read the tables
+//! themselves.
+
+use std::fmt::Write;
+
+use font::{Font, OutlineFormat};
+use ingest::CharCoverage;
+use primitives::Tag;
+use table::cmap::Cmap;
+use error::Result;
+
+/// What a browser needs to know to choose a font before downloading it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebFontReport {
+    /// The size of the font file, in bytes.
+    pub file_size: usize,
+    pub num_glyphs: u16,
+    /// The characters `cmap` maps to a glyph other than `.notdef`.
+    pub coverage: CharCoverage,
+    /// The `format()` hint of the `src` descriptor: `truetype` for fonts
+    /// with `glyf` outlines and `opentype` otherwise.
+    pub format: &'static str,
+    /// The `tech()` keywords of the font, such as `variations` and
+    /// `color-COLRv1`, in the order CSS lists them.
+    pub tech: Vec<&'static str>,
+}
+
+impl WebFontReport {
+    pub fn read(font: &Font) -> Result<WebFontReport> {
+        let coverage = match font.optional_table::<Cmap>()? {
+            Some(cmap) => CharCoverage::read(&cmap)?,
+            None => CharCoverage::new(),
+        };
+        let format = match font.outline_format() {
+            OutlineFormat::TrueType => "truetype",
+            _ => "opentype",
+        };
+
+        let has = |tag: &[u8; 4]| font.table_record(Tag(*tag)).is_some();
+        let mut tech = Vec::new();
+        if has(b"GSUB") || has(b"GPOS") {
+            tech.push("features-opentype");
+        }
+        if has(b"morx") {
+            tech.push("features-aat");
+        }
+        if has(b"Silf") {
+            tech.push("features-graphite");
+        }
+        if has(b"COLR") {
+            let version = font.table_data(Tag(*b"COLR"))?.get(..2).map(|version| version != [0, 0]);
+            tech.push(if version == Some(true) { "color-COLRv1" } else { "color-COLRv0" });
+        }
+        if has(b"SVG ") {
+            tech.push("color-SVG");
+        }
+        if has(b"sbix") {
+            tech.push("color-sbix");
+        }
+        if has(b"CBDT") {
+            tech.push("color-CBDT");
+        }
+        if has(b"fvar") {
+            tech.push("variations");
+        }
+        if has(b"CPAL") {
+            tech.push("palettes");
+        }
+        if has(b"IFT ") || has(b"IFTX") {
+            tech.push("incremental");
+        }
+
+        Ok(WebFontReport { file_size: font.data().len(), num_glyphs: font.num_glyphs()?, coverage, format, tech })
+    }
+
+    /// The value of the `unicode-range` descriptor for the characters the
+    /// font covers.
+    pub fn unicode_range(&self) -> String {
+        unicode_range(&self.coverage.ranges())
+    }
+
+    /// The value of the `src` descriptor for the font served at `url`,
+    /// such as `url("a.otf") format("opentype") tech(variations)`.
+    pub fn src(&self, url: &str) -> String {
+        let mut src = format!("url(\"{}\") format(\"{}\")", url.replace('\\', "\\\\").replace('"', "\\\""), self.format);
+        if !self.tech.is_empty() {
+            let _ = write!(src, " tech({})", self.tech.join(", "));
+        }
+        src
+    }
+}
+
+/// The `unicode-range` descriptor value for `ranges` of character codes,
+/// such as `U+20-7E, U+A0`.
+pub fn unicode_range(ranges: &[(u32, u32)]) -> String {
+    let mut out = String::new();
+    for &(first, last) in ranges {
+        if !out.is_empty() {
+            out.push_str(", ");
+        }
+        let _ = if first == last {
+            write!(out, "U+{:X}", first)
+        } else {
+            write!(out, "U+{:X}-{:X}", first, last)
+        };
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+    use test_fonts;
+
+    #[test]
+    fn web_font_report() {
+        let cmap = test_fonts::cmap(&[(' ', 1), ('!', 1), ('"', 2), ('\u{E9}', 2), ('\u{20AC}', 1), ('\u{1F600}', 2), ('x', 0)]);
+        let data = test_fonts::minimal_font(false).table(b"cmap", cmap).build();
+        let font = Font::decode(&data).unwrap();
+        let report = WebFontReport::read(&font).unwrap();
+        assert_eq!(report.unicode_range(), "U+20-22, U+E9, U+20AC, U+1F600");
+        assert_eq!((report.file_size, report.num_glyphs), (data.len(), 3));
+        assert_eq!(report.src("fonts/a \"b\".ttf"), r#"url("fonts/a \"b\".ttf") format("truetype")"#);
+
+        // Only the presence of `fvar` and the version of `COLR` are read.
+        let data = test_fonts::minimal_font(false).table(b"fvar", vec![0, 1]).table(b"COLR", vec![0, 1, 0, 0]).build();
+        let report = WebFontReport::read(&Font::decode(&data).unwrap()).unwrap();
+        assert_eq!(report.tech, vec!["color-COLRv1", "variations"]);
+        assert_eq!(report.src("a.ttf"), r#"url("a.ttf") format("truetype") tech(color-COLRv1, variations)"#);
+        assert_eq!(unicode_range(&[]), "");
+    }
+}
@@ -0,0 +1,193 @@
+//! This module serializes tables into an sfnt file.  Tables are written
+//! as given; only the table directory, padding and checksums are computed.
+//! A `DSIG` table is never written, since any change to the file, even
+//! moving a table, invalidates the signature.
+
+use std::collections::BTreeMap;
+
+use encode::EncodeBuf;
+use font::{checksum_at, Font, SearchParams, Version};
+use primitives::Tag;
+use error::{Error, Result};
+
+const DSIG: Tag = Tag::new(*b"DSIG");
+
+/// Assembles tables into an sfnt font file, with a sorted table
+/// directory, 4-byte aligned tables and valid checksums.
+#[derive(Debug, Clone, Default)]
+pub struct FontWriter {
+    tables: BTreeMap<Tag, Vec<u8>>,
+}
+
+impl FontWriter {
+    pub fn new() -> FontWriter {
+        FontWriter::default()
+    }
+
+    /// A writer holding every table of `font`, to edit and write again.
+    pub fn from_font(font: &Font) -> Result<FontWriter> {
+        let mut writer = FontWriter::new();
+        for record in font.table_directory().tables() {
+            writer.table(record.tag, font.table_data(record.tag)?.to_vec());
+        }
+        Ok(writer)
+    }
+
+    /// Add `data` as the table `tag`, replacing any existing table.
+    pub fn table(&mut self, tag: Tag, data: Vec<u8>) -> &mut FontWriter {
+        self.tables.insert(tag, data);
+        self
+    }
+
+    /// Remove the table `tag`, returning its data if it was present.
+    pub fn remove(&mut self, tag: Tag) -> Option<Vec<u8>> {
+        self.tables.remove(&tag)
+    }
+
+    pub fn contains(&self, tag: Tag) -> bool {
+        self.tables.contains_key(&tag)
+    }
+
+    /// Serialize the font, leaving out any `DSIG` table.  When a `head`
+    /// table is present, its `checkSumAdjustment` is set for the finished
+    /// file.
+    pub fn build(&self) -> Vec<u8> {
+        let head_tag = Tag::new(*b"head");
+        let mut tables = self.tables.clone();
+        tables.remove(&DSIG);
+        let num_tables = tables.len() as u16;
+        let search = SearchParams::for_tables(num_tables);
+
+        // Fonts with only CFF outlines are marked as OpenType.
+        let cff = [*b"CFF ", *b"CFF2"].iter().any(|&tag| self.contains(Tag::new(tag)));
+        let version = if cff && !self.contains(Tag::new(*b"glyf")) { Version::OpenType } else { Version::TrueType };
+
+        let mut out = EncodeBuf::new();
+        out.put_tag(version.tag());
+        out.put_u16(num_tables);
+        out.put_u16(search.search_range);
+        out.put_u16(search.entry_selector);
+        out.put_u16(search.range_shift);
+
+        // The adjustment is zero while checksums are computed.
+        if let Some(head) = tables.get_mut(&head_tag).filter(|head| head.len() >= 12) {
+            head[8..12].copy_from_slice(&[0; 4]);
+        }
+
+        let mut offset = 12 + tables.len() * 16;
+        for (tag, data) in &tables {
+            out.put_tag(*tag);
+            out.put_u32(checksum_at(data, 0));
+            out.put_u32(offset as u32);
+            out.put_u32(data.len() as u32);
+            offset += (data.len() + 3) & !3;
+        }
+
+        let mut head = None;
+        for (tag, data) in &tables {
+            if *tag == head_tag && data.len() >= 12 {
+                head = Some(out.len());
+            }
+
+            out.put_bytes(data);
+            out.align(4);
+        }
+
+        let mut out = out.into_vec();
+        if let Some(head) = head {
+            let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum_at(&out, 0));
+            out[head + 8..head + 12].copy_from_slice(&adjustment.to_be_bytes());
+        }
+
+        out
+    }
+}
+
+/// Edits the tables of an existing font by patching their bytes, for
+/// tools which change a few fields of `name` or `OS/2` without decoding
+/// and encoding the whole table.  The tables which are not patched are
+/// written unchanged.
+#[derive(Debug, Clone)]
+pub struct FontEditor {
+    writer: FontWriter,
+}
+
+impl FontEditor {
+    pub fn new(font: &Font) -> Result<FontEditor> {
+        Ok(FontEditor { writer: FontWriter::from_font(font)? })
+    }
+
+    /// Let `patch` change the bytes of the table `tag`, which may grow or
+    /// shrink.  The offsets, lengths and checksums of the directory are
+    /// computed again when the font is built.
+    pub fn patch_table<F: FnOnce(&mut Vec<u8>)>(&mut self, tag: Tag, patch: F) -> Result<&mut FontEditor> {
+        let data = self.writer.tables.get_mut(&tag).ok_or(Error::MissingTable)?;
+        patch(data);
+        Ok(self)
+    }
+
+    /// Serialize the edited font, as `FontWriter::build` does.
+    pub fn build(&self) -> Vec<u8> {
+        self.writer.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decode::Decode;
+    use table::os2::Os2;
+    use validate::StructureWarning;
+
+    #[test]
+    fn rewrite_font() {
+        let data = open_file!("data/OpenSans-Regular.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        assert!(font.table_record(DSIG).is_some());
+
+        let mut writer = FontWriter::from_font(&font).unwrap();
+        assert!(writer.remove(Tag::new(*b"GPOS")).is_some());
+        assert_eq!(writer.remove(Tag::new(*b"GPOS")), None);
+        writer.table(Tag::new(*b"zzzz"), vec![1, 2, 3]);
+        let out = writer.build();
+
+        let rewritten = Font::decode(&out).expect("failed to read rewritten font");
+        let tags = rewritten.table_directory().tables().into_iter().map(|record| record.tag).collect::<Vec<_>>();
+        assert!(!tags.contains(&DSIG) && !tags.contains(&Tag::new(*b"GPOS")));
+        assert!(tags.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(tags.len(), font.table_directory().tables().len() - 1);
+        assert_eq!(rewritten.table_data(Tag::new(*b"glyf")), font.table_data(Tag::new(*b"glyf")));
+
+        for record in rewritten.table_directory().tables() {
+            if record.tag != Tag::new(*b"head") {
+                assert_eq!(checksum_at(rewritten.table_data(record.tag).unwrap(), 0), record.check_sum);
+            }
+        }
+        assert_eq!(checksum_at(&out, 0), 0xB1B0_AFBA);
+        assert!(rewritten.structure_warnings().iter().all(|warning| !matches!(warning, StructureWarning::Overlap { .. })));
+    }
+
+    #[test]
+    fn patch_tables() {
+        let data = open_file!("data/OpenSans-Regular.ttf");
+        let font = Font::decode(&data).expect("failed to read font");
+        let mut editor = FontEditor::new(&font).unwrap();
+        editor
+            .patch_table(Tag::new(*b"OS/2"), |os2| os2[4..6].copy_from_slice(&700u16.to_be_bytes())).unwrap()
+            .patch_table(Tag::new(*b"name"), |name| name.extend_from_slice(&[0; 5])).unwrap();
+        assert_eq!(editor.patch_table(Tag::new(*b"zzzz"), |_| ()).err(), Some(Error::MissingTable));
+        let out = editor.build();
+
+        let patched = Font::decode(&out).expect("failed to read patched font");
+        assert_eq!(patched.table::<Os2>().unwrap().us_weight_class, 700);
+        let name = patched.table_record(Tag::new(*b"name")).unwrap();
+        assert_eq!(name.length, font.table_record(Tag::new(*b"name")).unwrap().length + 5);
+        for record in patched.table_directory().tables() {
+            if record.tag != Tag::new(*b"head") {
+                assert_eq!(checksum_at(patched.table_data(record.tag).unwrap(), 0), record.check_sum);
+            }
+        }
+        assert_eq!(checksum_at(&out, 0), 0xB1B0_AFBA);
+        assert_eq!(patched.table_data(Tag::new(*b"glyf")), font.table_data(Tag::new(*b"glyf")));
+    }
+}